@@ -0,0 +1,40 @@
+#!/bin/sh
+# SPDX-FileCopyrightText: © 2025 David Bliss
+#
+# SPDX-License-Identifier: GPL-3.0-or-later
+#
+# Regenerates core/resources/geocode/cities.tsv from the GeoNames "cities5000"
+# dump, which has every city with a population of at least 5000.
+# GeoNames data is licensed under CC BY 4.0, so the attribution in the table header,
+# REUSE.toml, and the about dialog must be kept.
+#
+# Usage: build-aux/geocode-cities.sh [SOURCE_ROOT]
+set -eu
+SOURCE_ROOT="${1:-.}"
+GEONAMES="https://download.geonames.org/export/dump"
+OUTPUT="$SOURCE_ROOT/core/resources/geocode/cities.tsv"
+
+WORK=$(mktemp -d)
+trap 'rm -rf "$WORK"' EXIT
+
+curl --fail --silent --show-error --location --output "$WORK/cities5000.zip" "$GEONAMES/cities5000.zip"
+curl --fail --silent --show-error --location --output "$WORK/countryInfo.txt" "$GEONAMES/countryInfo.txt"
+unzip -q -d "$WORK" "$WORK/cities5000.zip"
+
+# Write to a temporary file first so that a failed download never leaves a truncated table.
+{
+    echo "# Cities for offline reverse geocoding."
+    echo "# Columns: name, country, latitude, longitude (decimal degrees, WGS 84)."
+    echo "# Data from GeoNames (https://www.geonames.org/), licensed under CC BY 4.0"
+    echo "# (https://creativecommons.org/licenses/by/4.0/). Generated by build-aux/geocode-cities.sh."
+
+    # countryInfo.txt maps ISO country codes (column 1) to country names (column 5).
+    # cities5000.txt has the name in column 2, coordinates in columns 5 and 6, and
+    # the country code in column 9.
+    awk -F '\t' '
+        FNR == NR { if ($0 !~ /^#/) country[$1] = $5; next }
+        ($9 in country) { printf "%s\t%s\t%s\t%s\n", $2, country[$9], $5, $6 }
+    ' "$WORK/countryInfo.txt" "$WORK/cities5000.txt" | LC_ALL=C sort
+} > "$WORK/cities.tsv"
+
+mv "$WORK/cities.tsv" "$OUTPUT"
@@ -0,0 +1,3 @@
+-- Ignored people are hidden from the people album, but their faces stay assigned
+-- to them so that face recognition doesn't offer them up again as unknown faces.
+ALTER TABLE people ADD COLUMN is_ignored BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,3 @@
+-- Face recognition features for clustering unknown faces.
+-- Little-endian f32 values. Null until computed by face recognition.
+ALTER TABLE pictures_faces ADD COLUMN embedding BLOB;
@@ -0,0 +1,8 @@
+-- Hash of file contents for finding duplicate pictures.
+-- File size and modification timestamp are those of the file when it was hashed,
+-- so that the hash is only recomputed when the file changes.
+ALTER TABLE pictures ADD COLUMN content_hash TEXT;
+ALTER TABLE pictures ADD COLUMN content_hash_file_size INTEGER;
+ALTER TABLE pictures ADD COLUMN content_hash_fs_modified_ts DATETIME;
+
+CREATE INDEX pictures_content_hash_idx ON pictures (content_hash);
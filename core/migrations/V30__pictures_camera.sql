@@ -0,0 +1,8 @@
+-- Camera and exposure settings extracted from EXIF tags.
+-- Capture time and GPS coordinates are already stored in exif_created_ts and pictures_geo.
+ALTER TABLE pictures ADD COLUMN camera_make TEXT;
+ALTER TABLE pictures ADD COLUMN camera_model TEXT;
+ALTER TABLE pictures ADD COLUMN iso INTEGER;
+ALTER TABLE pictures ADD COLUMN f_number REAL;
+ALTER TABLE pictures ADD COLUMN exposure_time REAL; -- seconds
+ALTER TABLE pictures ADD COLUMN focal_length REAL; -- millimetres
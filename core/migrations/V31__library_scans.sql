@@ -0,0 +1,6 @@
+-- Time the last library scan started, so that later scans only need to
+-- look at files changed since then. Only ever has one row.
+CREATE TABLE library_scans (
+  scan_id INTEGER PRIMARY KEY CHECK (scan_id = 1),
+  scanned_at DATETIME NOT NULL
+);
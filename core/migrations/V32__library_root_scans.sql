@@ -0,0 +1,8 @@
+-- Time the last scan of each library root started, so that later scans only need to
+-- look at files changed since then, and a newly added root is scanned in full.
+DROP TABLE library_scans;
+
+CREATE TABLE library_scans (
+  root_path_b64 TEXT PRIMARY KEY NOT NULL, -- sandbox path of root (base64 encoded)
+  scanned_at DATETIME NOT NULL
+);
@@ -0,0 +1,9 @@
+-- Path of a picture or video with symbolic links resolved (base64 encoded).
+-- A file reachable by more than one path, such as through a symbolic link or a bind mount,
+-- is only added to the library once.
+-- Null for files added before canonical paths were recorded, until they are next scanned.
+ALTER TABLE pictures ADD COLUMN canonical_path_b64 TEXT;
+CREATE UNIQUE INDEX pictures_canonical_path_idx ON pictures (canonical_path_b64);
+
+ALTER TABLE videos ADD COLUMN canonical_path_b64 TEXT;
+CREATE UNIQUE INDEX videos_canonical_path_idx ON videos (canonical_path_b64);
@@ -0,0 +1,11 @@
+-- Picture chosen by the user as the cover of a folder.
+-- Cover is user data, so must survive rescans of the library.
+-- If the picture is deleted, the folder falls back to its first item as the cover.
+CREATE TABLE IF NOT EXISTS folder_covers (
+        -- Host path of folder (base64 encoded), as shown in the folders album.
+        folder_path_b64 TEXT PRIMARY KEY NOT NULL,
+
+        picture_id INTEGER NOT NULL,
+
+        FOREIGN KEY (picture_id) REFERENCES pictures (picture_id) ON DELETE CASCADE
+);
@@ -0,0 +1,4 @@
+-- Pictures on a network share that couldn't be reached when last checked.
+-- Separate from is_broken because the share might come back, and checked
+-- again on every library scan.
+ALTER TABLE pictures ADD COLUMN is_unavailable BOOLEAN NOT NULL DEFAULT FALSE;
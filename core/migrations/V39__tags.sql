@@ -0,0 +1,22 @@
+-- Freeform tags that users give pictures, such as "Holiday" or "Receipts".
+-- Tags are user data, so must survive rescans of the library.
+CREATE TABLE IF NOT EXISTS tags (
+        tag_id INTEGER PRIMARY KEY UNIQUE NOT NULL,
+
+        -- Name as first typed by the user, without surrounding white space.
+        name TEXT NOT NULL,
+
+        -- Name in lower case, for matching regardless of case.
+        folded_name TEXT UNIQUE NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS pictures_tags (
+        picture_id INTEGER NOT NULL,
+        tag_id INTEGER NOT NULL,
+
+        PRIMARY KEY (picture_id, tag_id),
+        FOREIGN KEY (picture_id) REFERENCES pictures (picture_id) ON DELETE CASCADE,
+        FOREIGN KEY (tag_id) REFERENCES tags (tag_id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS pictures_tags_tag_id ON pictures_tags (tag_id);
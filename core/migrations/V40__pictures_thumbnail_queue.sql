@@ -0,0 +1,9 @@
+-- Pictures found to need a thumbnail that haven't been thumbnailed yet.
+-- Lets thumbnailing resume after a restart without first walking the whole
+-- library to work out what is left, which is slow on slow disks.
+-- A picture is removed from the queue once it is thumbnailed or found to be broken.
+CREATE TABLE IF NOT EXISTS pictures_thumbnail_queue (
+        picture_id INTEGER PRIMARY KEY UNIQUE NOT NULL,
+
+        FOREIGN KEY (picture_id) REFERENCES pictures (picture_id) ON DELETE CASCADE
+);
@@ -0,0 +1,25 @@
+-- Bursts of pictures taken in quick succession, shown as one stacked item in albums.
+-- Bursts are kept so that grouping doesn't change from one library refresh to the next.
+CREATE TABLE IF NOT EXISTS bursts (
+        burst_id INTEGER PRIMARY KEY UNIQUE NOT NULL,
+
+        -- Frame shown for the whole burst. The sharpest frame when the burst was found.
+        cover_picture_id INTEGER NOT NULL,
+
+        -- Has the user chosen to show the frames as separate pictures?
+        -- An ungrouped burst is kept so that it isn't found and grouped again.
+        is_ungrouped BOOLEAN NOT NULL DEFAULT FALSE,
+
+        FOREIGN KEY (cover_picture_id) REFERENCES pictures (picture_id) ON DELETE CASCADE
+);
+
+-- A picture is a frame of at most one burst.
+CREATE TABLE IF NOT EXISTS pictures_bursts (
+        picture_id INTEGER PRIMARY KEY UNIQUE NOT NULL,
+        burst_id INTEGER NOT NULL,
+
+        FOREIGN KEY (picture_id) REFERENCES pictures (picture_id) ON DELETE CASCADE,
+        FOREIGN KEY (burst_id) REFERENCES bursts (burst_id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS pictures_bursts_burst_id ON pictures_bursts (burst_id);
@@ -0,0 +1,4 @@
+-- Why a picture was marked broken, and when, so the user can see why it is
+-- missing from albums. NULL for pictures marked broken before this was kept.
+ALTER TABLE pictures ADD COLUMN broken_reason TEXT;
+ALTER TABLE pictures ADD COLUMN broken_at DATETIME;
@@ -0,0 +1,7 @@
+-- Folders the user has hidden from the folders album, along with their subfolders.
+-- Hiding is user data, so must survive rescans of the library.
+-- Items in hidden folders are still shown by other albums, search, and people.
+CREATE TABLE IF NOT EXISTS hidden_folders (
+        -- Path of folder (base64 encoded), as shown in the folders album.
+        folder_path_b64 TEXT PRIMARY KEY NOT NULL
+);
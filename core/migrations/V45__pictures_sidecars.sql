@@ -0,0 +1,11 @@
+-- XMP sidecar files that Fotema has written or imported favorites and tags from.
+-- A sidecar is only imported again once another tool has changed it, so that
+-- changes made in Fotema since aren't reverted by the next scan.
+CREATE TABLE IF NOT EXISTS pictures_sidecars (
+        picture_id INTEGER PRIMARY KEY UNIQUE NOT NULL,
+
+        -- Modification time of the sidecar when last written or imported.
+        sidecar_modified_ts DATETIME NOT NULL,
+
+        FOREIGN KEY (picture_id) REFERENCES pictures (picture_id) ON DELETE CASCADE
+);
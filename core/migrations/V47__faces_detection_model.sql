@@ -0,0 +1,6 @@
+-- Detection model setting that faces were found with, so that embeddings from
+-- different models are never compared. Existing faces were found by both
+-- BlazeFace and MTCNN, which is the 'Accurate' model.
+ALTER TABLE pictures_faces ADD COLUMN detection_model TEXT NOT NULL DEFAULT 'Accurate';
+
+ALTER TABLE pictures_face_scans ADD COLUMN detection_model TEXT NOT NULL DEFAULT 'Accurate';
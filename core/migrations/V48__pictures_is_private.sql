@@ -0,0 +1,3 @@
+-- Pictures the user has marked as private. Private pictures are hidden, or blurred,
+-- while private pictures are locked.
+ALTER TABLE pictures ADD COLUMN is_private BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,4 @@
+-- Perceptual hash of a picture, for finding pictures that look alike but aren't
+-- identical, such as a resized or re-compressed copy. Computed from the picture's
+-- largest thumbnail, so null until the picture has been thumbnailed.
+ALTER TABLE pictures ADD COLUMN perceptual_hash INTEGER;
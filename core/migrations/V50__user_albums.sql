@@ -0,0 +1,31 @@
+-- Albums that users make and fill with pictures themselves, beyond folders and people.
+-- Albums are user data, so must survive rescans of the library.
+CREATE TABLE IF NOT EXISTS albums (
+        album_id INTEGER PRIMARY KEY UNIQUE NOT NULL,
+
+        -- Name as first typed by the user, without surrounding white space.
+        name TEXT NOT NULL,
+
+        -- Name in lower case, for matching regardless of case.
+        folded_name TEXT UNIQUE NOT NULL,
+
+        -- Picture the user has chosen to show for the album.
+        -- Null to show the first picture in the album's order.
+        cover_picture_id INTEGER,
+
+        FOREIGN KEY (cover_picture_id) REFERENCES pictures (picture_id) ON DELETE SET NULL
+);
+
+CREATE TABLE IF NOT EXISTS albums_pictures (
+        album_id INTEGER NOT NULL,
+        picture_id INTEGER NOT NULL,
+
+        -- Position of the picture in the order the user has arranged the album in.
+        sort_index INTEGER NOT NULL,
+
+        PRIMARY KEY (album_id, picture_id),
+        FOREIGN KEY (album_id) REFERENCES albums (album_id) ON DELETE CASCADE,
+        FOREIGN KEY (picture_id) REFERENCES pictures (picture_id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS albums_pictures_picture_id ON albums_pictures (picture_id);
@@ -0,0 +1,4 @@
+-- Pictures trashed by Fotema rather than by the user, such as pictures in a folder removed
+-- from the library, or matching an ignore pattern. Emptying the trash never deletes
+-- their files.
+ALTER TABLE pictures ADD COLUMN is_auto_trashed BOOLEAN NOT NULL DEFAULT FALSE;
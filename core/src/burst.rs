@@ -0,0 +1,475 @@
+// SPDX-FileCopyrightText: © 2025 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Bursts of pictures taken in quick succession, such as when holding down the shutter
+//! button. A burst is shown as one stacked item, with the sharpest frame as its cover.
+//!
+//! Frames of a burst are pictures in the same folder with sequentially numbered file
+//! names, such as `IMG_1234.jpg` and `IMG_1235.jpg`, taken within a couple of seconds
+//! of each other.
+
+use crate::database;
+use crate::photo::model::{Picture, PictureId};
+use crate::thumbnailify::{ThumbnailSize, Thumbnailer};
+
+use anyhow::*;
+use image::DynamicImage;
+use rusqlite::params;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::result::Result::Ok;
+use std::sync::{Arc, Mutex};
+use tracing::warn;
+
+/// Fewest frames in a burst. Two similar pictures are more often a retake than a burst.
+const MIN_FRAMES: usize = 3;
+
+/// Most time between one frame and the next.
+const MAX_FRAME_GAP_MILLIS: i64 = 2000;
+
+/// Most that the number in a file name can go up from one frame to the next,
+/// allowing for frames that were deleted.
+const MAX_NUMBER_STEP: u64 = 5;
+
+/// Longest edge of image when measuring sharpness. Larger images are scaled down,
+/// which is faster and evens out differences in noise.
+const SHARPNESS_EDGE_LENGTH: u32 = 512;
+
+/// Database ID of burst
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BurstId(i64);
+
+impl BurstId {
+    pub fn new(id: i64) -> Self {
+        Self(id)
+    }
+
+    pub fn id(&self) -> i64 {
+        self.0
+    }
+}
+
+impl std::fmt::Display for BurstId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// How a picture belongs to a burst.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame {
+    pub burst_id: BurstId,
+
+    /// Is the picture shown for the whole burst?
+    pub is_cover: bool,
+}
+
+/// A picture that might be a frame of a burst.
+#[derive(Debug)]
+struct Shot<'a> {
+    picture: &'a Picture,
+
+    folder: PathBuf,
+
+    /// File name before the number, such as `IMG_` for `IMG_1234.jpg`.
+    prefix: String,
+
+    /// Number at end of file name, such as `1234` for `IMG_1234.jpg`.
+    number: u64,
+
+    /// Lower case file extension.
+    extension: String,
+}
+
+impl<'a> Shot<'a> {
+    fn build(picture: &'a Picture) -> Option<Self> {
+        let path = picture.host_path();
+        let folder = path.parent()?.to_path_buf();
+        let stem = path.file_stem()?.to_string_lossy();
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        let prefix = stem.trim_end_matches(|c: char| c.is_ascii_digit());
+        let number = stem[prefix.len()..].parse().ok()?;
+
+        Some(Self {
+            picture,
+            folder,
+            prefix: prefix.to_string(),
+            number,
+            extension,
+        })
+    }
+
+    /// Could the other shot be the next frame of the same burst as this one?
+    fn is_followed_by(&self, other: &Shot) -> bool {
+        let gap = other.picture.ordering_ts - self.picture.ordering_ts;
+
+        self.folder == other.folder
+            && self.prefix == other.prefix
+            && self.extension == other.extension
+            && other.number > self.number
+            && other.number - self.number <= MAX_NUMBER_STEP
+            && gap.num_milliseconds().abs() <= MAX_FRAME_GAP_MILLIS
+    }
+}
+
+/// Finds bursts of pictures. Each burst is a list of frames in the order they were taken.
+pub fn find_bursts(pictures: &[Picture]) -> Vec<Vec<PictureId>> {
+    let mut shots: Vec<Shot> = pictures.iter().filter_map(Shot::build).collect();
+
+    shots.sort_by(|a, b| {
+        (&a.folder, &a.prefix, &a.extension, a.number).cmp(&(
+            &b.folder,
+            &b.prefix,
+            &b.extension,
+            b.number,
+        ))
+    });
+
+    let mut bursts = Vec::new();
+    let mut frames: Vec<&Shot> = Vec::new();
+
+    for shot in &shots {
+        if !frames.last().is_some_and(|last| last.is_followed_by(shot)) {
+            if frames.len() >= MIN_FRAMES {
+                bursts.push(frames.iter().map(|s| s.picture.picture_id).collect());
+            }
+            frames.clear();
+        }
+        frames.push(shot);
+    }
+
+    if frames.len() >= MIN_FRAMES {
+        bursts.push(frames.iter().map(|s| s.picture.picture_id).collect());
+    }
+
+    bursts
+}
+
+/// How sharp an image is, as the variance of the Laplacian of its brightness.
+/// Blurry images have few edges so have a low variance. Only useful for comparing
+/// images of the same scene, such as the frames of a burst.
+pub fn sharpness(image: &DynamicImage) -> f64 {
+    let image = if image.width().max(image.height()) > SHARPNESS_EDGE_LENGTH {
+        image.thumbnail(SHARPNESS_EDGE_LENGTH, SHARPNESS_EDGE_LENGTH)
+    } else {
+        image.clone()
+    };
+
+    let luma = image.to_luma8();
+    let (width, height) = luma.dimensions();
+    if width < 3 || height < 3 {
+        return 0.0;
+    }
+
+    let at = |x: u32, y: u32| f64::from(luma.get_pixel(x, y).0[0]);
+
+    let mut laplacians = Vec::with_capacity(((width - 2) * (height - 2)) as usize);
+    for y in 1..height - 1 {
+        for x in 1..width - 1 {
+            let laplacian =
+                at(x - 1, y) + at(x + 1, y) + at(x, y - 1) + at(x, y + 1) - 4.0 * at(x, y);
+            laplacians.push(laplacian);
+        }
+    }
+
+    let count = laplacians.len() as f64;
+    let mean = laplacians.iter().sum::<f64>() / count;
+    laplacians.iter().map(|l| (l - mean).powi(2)).sum::<f64>() / count
+}
+
+/// Frame of a burst with the sharpest thumbnail, to show for the whole burst.
+/// None if none of the frames has a thumbnail that can be read.
+pub fn sharpest(thumbnailer: &Thumbnailer, frames: &[&Picture]) -> Option<PictureId> {
+    frames
+        .iter()
+        .filter_map(|picture| {
+            let path =
+                thumbnailer.nearest_thumbnail(&picture.thumbnail_hash(), ThumbnailSize::Large)?;
+            let image = image::open(&path)
+                .map_err(|e| warn!("Failed reading thumbnail {:?}: {}", path, e))
+                .ok()?;
+            Some((picture.picture_id, sharpness(&image)))
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(picture_id, _)| picture_id)
+}
+
+/// Bursts of pictures.
+/// Repository is backed by a Sqlite database.
+#[derive(Debug, Clone)]
+pub struct Repository {
+    /// Connection to backing Sqlite database.
+    con: Arc<Mutex<rusqlite::Connection>>,
+}
+
+impl Repository {
+    pub fn open(con: Arc<Mutex<rusqlite::Connection>>) -> Result<Repository> {
+        Ok(Repository { con })
+    }
+
+    /// Adds a burst with a frame to show for the whole burst.
+    /// Returns None, and adds nothing, if any of the frames is already in a burst.
+    pub fn add_burst(
+        &mut self,
+        picture_ids: &[PictureId],
+        cover_picture_id: &PictureId,
+    ) -> Result<Option<BurstId>> {
+        let mut con = database::lock(&self.con);
+        let tx = con.transaction()?;
+
+        {
+            let mut stmt = tx.prepare_cached(
+                "SELECT EXISTS (SELECT 1 FROM pictures_bursts WHERE picture_id = ?1)",
+            )?;
+            for picture_id in picture_ids {
+                let is_in_burst: bool = stmt.query_row(params![picture_id], |row| row.get(0))?;
+                if is_in_burst {
+                    return Ok(None);
+                }
+            }
+        }
+
+        tx.execute(
+            "INSERT INTO bursts (cover_picture_id) VALUES (?1)",
+            params![cover_picture_id],
+        )?;
+        let burst_id = BurstId::new(tx.last_insert_rowid());
+
+        {
+            let mut stmt = tx.prepare_cached(
+                "INSERT INTO pictures_bursts (picture_id, burst_id) VALUES (?1, ?2)",
+            )?;
+            for picture_id in picture_ids {
+                stmt.execute(params![picture_id, burst_id.id()])?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(Some(burst_id))
+    }
+
+    /// Bursts that are shown grouped, excluding trashed frames.
+    /// Each burst starts with its cover, followed by the other frames in the order scanned.
+    pub fn bursts(&self) -> Result<Vec<Vec<PictureId>>> {
+        let con = database::lock(&self.con);
+        let mut stmt = con.prepare(
+            "SELECT pictures_bursts.burst_id, pictures_bursts.picture_id
+            FROM pictures_bursts
+            INNER JOIN bursts USING (burst_id)
+            INNER JOIN pictures USING (picture_id)
+            WHERE bursts.is_ungrouped = FALSE
+            AND pictures.trashed_at IS NULL
+            ORDER BY
+                pictures_bursts.burst_id,
+                pictures_bursts.picture_id = bursts.cover_picture_id DESC,
+                pictures_bursts.picture_id",
+        )?;
+
+        let mut bursts: Vec<Vec<PictureId>> = Vec::new();
+        let mut last_burst_id = None;
+
+        for row in stmt.query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, PictureId::new(row.get(1)?)))
+        })? {
+            let (burst_id, picture_id) = row?;
+            if last_burst_id != Some(burst_id) {
+                bursts.push(Vec::new());
+                last_burst_id = Some(burst_id);
+            }
+            if let Some(frames) = bursts.last_mut() {
+                frames.push(picture_id);
+            }
+        }
+
+        Ok(bursts)
+    }
+
+    /// Pictures that are frames of any burst, including ungrouped bursts.
+    /// For skipping pictures that have already been grouped, or ungrouped by the user.
+    pub fn pictures_in_bursts(&self) -> Result<HashSet<PictureId>> {
+        let con = database::lock(&self.con);
+        let mut stmt = con.prepare("SELECT picture_id FROM pictures_bursts")?;
+
+        let picture_ids = stmt
+            .query_map([], |row| row.get(0).map(PictureId::new))?
+            .flatten()
+            .collect();
+
+        Ok(picture_ids)
+    }
+
+    /// Shows the frames of a burst as separate pictures.
+    /// The burst is kept so that the frames aren't grouped again.
+    pub fn ungroup(&mut self, burst_id: &BurstId) -> Result<()> {
+        let con = database::lock(&self.con);
+        con.execute(
+            "UPDATE bursts SET is_ungrouped = TRUE WHERE burst_id = ?1",
+            params![burst_id.id()],
+        )?;
+        Ok(())
+    }
+
+    /// Burst of every picture that is a frame of a grouped burst.
+    /// For loading the bursts of the whole library at once.
+    pub fn all_frames(&self) -> Result<HashMap<PictureId, Frame>> {
+        all_frames(&database::lock(&self.con))
+    }
+}
+
+/// Burst of every picture that is a frame of a grouped burst.
+pub(crate) fn all_frames(con: &rusqlite::Connection) -> Result<HashMap<PictureId, Frame>> {
+    let mut stmt = con.prepare(
+        "SELECT pictures_bursts.picture_id, pictures_bursts.burst_id, bursts.cover_picture_id
+        FROM pictures_bursts
+        INNER JOIN bursts USING (burst_id)
+        WHERE bursts.is_ungrouped = FALSE",
+    )?;
+
+    let mut frames = HashMap::new();
+    for row in stmt.query_map([], |row| {
+        let picture_id = PictureId::new(row.get(0)?);
+        let burst_id = BurstId::new(row.get(1)?);
+        let cover_picture_id = PictureId::new(row.get(2)?);
+        Ok((picture_id, burst_id, cover_picture_id))
+    })? {
+        let (picture_id, burst_id, cover_picture_id) = row?;
+        let frame = Frame {
+            burst_id,
+            is_cover: picture_id == cover_picture_id,
+        };
+        frames.insert(picture_id, frame);
+    }
+
+    Ok(frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::photo;
+    use crate::photo::model::Orientation;
+    use crate::{FlatpakPathBuf, LibraryRoots, ScannedFile};
+    use chrono::{DateTime, TimeDelta};
+    use image::{GrayImage, Luma};
+    use std::path::Path;
+
+    fn picture(path: &str, id: i64, millis: i64) -> Picture {
+        Picture {
+            path: FlatpakPathBuf::build(path, path),
+            picture_id: PictureId::new(id),
+            ordering_ts: DateTime::UNIX_EPOCH + TimeDelta::milliseconds(millis),
+            is_selfie: None,
+            is_favorite: false,
+            blurhash: None,
+            orientation: Orientation::North,
+            width: None,
+            height: None,
+            byte_size: None,
+            is_broken: false,
+        }
+    }
+
+    fn number(path: &str) -> Option<u64> {
+        Shot::build(&picture(path, 0, 0)).map(|shot| shot.number)
+    }
+
+    fn ids(ids: &[i64]) -> Vec<PictureId> {
+        ids.iter().map(|id| PictureId::new(*id)).collect()
+    }
+
+    #[test]
+    fn number_is_end_of_file_name() {
+        assert_eq!(Some(1234), number("/a/IMG_1234.jpg"));
+        assert_eq!(Some(3), number("/a/20240101_101010_003.JPG"));
+        assert_eq!(None, number("/a/holiday.jpg"));
+    }
+
+    #[test]
+    fn finds_sequential_frames_taken_together() {
+        let pictures = vec![
+            picture("/a/IMG_0003.jpg", 3, 900),
+            picture("/a/IMG_0001.jpg", 1, 0),
+            picture("/a/IMG_0002.jpg", 2, 400),
+            // Too long after the previous frame
+            picture("/a/IMG_0004.jpg", 4, 60_000),
+            // Different folder
+            picture("/b/IMG_0005.jpg", 5, 60_100),
+            // Skips a deleted frame
+            picture("/a/IMG_0006.jpg", 6, 60_200),
+            picture("/a/IMG_0007.jpg", 7, 60_300),
+        ];
+
+        assert_eq!(
+            vec![ids(&[1, 2, 3]), ids(&[4, 6, 7])],
+            find_bursts(&pictures)
+        );
+    }
+
+    #[test]
+    fn two_frames_are_not_a_burst() {
+        let pictures = vec![
+            picture("/a/IMG_0001.jpg", 1, 0),
+            picture("/a/IMG_0002.jpg", 2, 100),
+            picture("/a/DSC_0003.jpg", 3, 200),
+        ];
+
+        assert!(find_bursts(&pictures).is_empty());
+    }
+
+    #[test]
+    fn sharp_image_is_sharper_than_flat_image() {
+        let flat = DynamicImage::ImageLuma8(GrayImage::from_pixel(16, 16, Luma([128])));
+        let checks = DynamicImage::ImageLuma8(GrayImage::from_fn(16, 16, |x, y| {
+            Luma([if (x + y) % 2 == 0 { 0 } else { 255 }])
+        }));
+
+        assert_eq!(0.0, sharpness(&flat));
+        assert!(sharpness(&checks) > sharpness(&flat));
+    }
+
+    #[test]
+    fn ungrouped_burst_is_not_grouped_again() {
+        let con = Arc::new(Mutex::new(database::setup_in_memory().unwrap()));
+        let library_roots = LibraryRoots::build(&FlatpakPathBuf::build("/", "/"), &[]);
+        let mut photo_repo =
+            photo::Repository::open(&library_roots, Path::new("/"), Path::new("/"), con.clone())
+                .unwrap();
+        photo_repo
+            .add_all(&vec![
+                ScannedFile::Photo(PathBuf::from("/IMG_0001.jpg")),
+                ScannedFile::Photo(PathBuf::from("/IMG_0002.jpg")),
+                ScannedFile::Photo(PathBuf::from("/IMG_0003.jpg")),
+            ])
+            .unwrap();
+        let mut picture_ids: Vec<PictureId> = photo_repo
+            .all()
+            .unwrap()
+            .iter()
+            .map(|p| p.picture_id)
+            .collect();
+        picture_ids.sort_by_key(|id| id.id());
+
+        let mut repo = Repository::open(con).unwrap();
+        let cover = picture_ids[1];
+        let burst_id = repo.add_burst(&picture_ids, &cover).unwrap().unwrap();
+
+        // Cover first
+        let expected = vec![vec![picture_ids[1], picture_ids[0], picture_ids[2]]];
+        assert_eq!(expected, repo.bursts().unwrap());
+        assert!(repo.all_frames().unwrap()[&cover].is_cover);
+
+        // Frames can only be in one burst
+        assert_eq!(None, repo.add_burst(&picture_ids[1..], &cover).unwrap());
+
+        repo.ungroup(&burst_id).unwrap();
+        assert!(repo.bursts().unwrap().is_empty());
+        assert!(repo.all_frames().unwrap().is_empty());
+        assert_eq!(3, repo.pictures_in_bursts().unwrap().len());
+        assert_eq!(None, repo.add_burst(&picture_ids, &cover).unwrap());
+    }
+}
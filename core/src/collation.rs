@@ -0,0 +1,108 @@
+// SPDX-FileCopyrightText: © 2025 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Orders names the way people expect, rather than by their bytes.
+//!
+//! Numbers are compared by value, so "Folder 2" comes before "Folder 10", and letters
+//! follow the rules of the user's locale, so accented letters sort beside unaccented ones.
+
+use std::cmp::Ordering;
+use std::sync::LazyLock;
+
+use icu_collator::options::CollatorOptions;
+use icu_collator::preferences::CollationNumericOrdering;
+use icu_collator::{Collator, CollatorBorrowed, CollatorPreferences};
+use icu_locale_core::Locale;
+use tracing::{info, warn};
+
+static COLLATOR: LazyLock<Option<CollatorBorrowed<'static>>> = LazyLock::new(|| {
+    let locale = system_locale("LC_COLLATE");
+    info!("Collating names for locale {:?}", locale);
+    collator(locale.as_ref())
+});
+
+/// Compares two names for the user's locale.
+/// Falls back to comparing bytes if no collator could be built.
+pub fn compare(a: &str, b: &str) -> Ordering {
+    match COLLATOR.as_ref() {
+        Some(collator) => collator.compare(a, b),
+        None => a.cmp(b),
+    }
+}
+
+/// Numeric-aware collator for a locale, or for the root locale if there isn't one.
+fn collator(locale: Option<&Locale>) -> Option<CollatorBorrowed<'static>> {
+    let mut prefs = locale.map(CollatorPreferences::from).unwrap_or_default();
+    prefs.numeric_ordering = Some(CollationNumericOrdering::True);
+
+    Collator::try_new(prefs, CollatorOptions::default())
+        .inspect_err(|e| warn!("Failed building collator: {:?}", e))
+        .ok()
+}
+
+/// Locale of the user for a category, such as "LC_COLLATE", with the same precedence
+/// of environment variables as the C library.
+pub(crate) fn system_locale(category: &str) -> Option<Locale> {
+    ["LC_ALL", category, "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .and_then(|value| parse_posix_locale(&value))
+}
+
+/// Converts a POSIX locale, such as "de_DE.UTF-8@euro", to a Unicode locale, such as "de-DE".
+/// The "C" and "POSIX" locales have no language, so have no Unicode locale.
+fn parse_posix_locale(value: &str) -> Option<Locale> {
+    let name = value.split(['.', '@']).next()?;
+    if name == "C" || name == "POSIX" {
+        return None;
+    }
+    Locale::try_from_str(&name.replace('_', "-")).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(locale: &str, names: &[&'static str]) -> Vec<&'static str> {
+        let locale = Locale::try_from_str(locale).unwrap();
+        let collator = collator(Some(&locale)).unwrap();
+        let mut names = names.to_vec();
+        names.sort_by(|a, b| collator.compare(a, b));
+        names
+    }
+
+    #[test]
+    fn numbers_sort_by_value() {
+        assert_eq!(
+            vec!["Folder 1", "Folder 2", "Folder 10", "Folder 100"],
+            sorted("en", &["Folder 10", "Folder 2", "Folder 100", "Folder 1"])
+        );
+    }
+
+    #[test]
+    fn accented_letters_sort_beside_unaccented() {
+        assert_eq!(
+            vec!["Eagle", "Été", "Ewe", "Zoo"],
+            sorted("en", &["Zoo", "Été", "Ewe", "Eagle"])
+        );
+    }
+
+    #[test]
+    fn locale_changes_order() {
+        // Swedish sorts "Ö" as a separate letter after "Z".
+        assert_eq!(vec!["Öland", "Zebra"], sorted("en", &["Zebra", "Öland"]));
+        assert_eq!(vec!["Zebra", "Öland"], sorted("sv", &["Zebra", "Öland"]));
+    }
+
+    #[test]
+    fn posix_locale_is_parsed() {
+        assert_eq!(
+            Some(Locale::try_from_str("de-DE").unwrap()),
+            parse_posix_locale("de_DE.UTF-8@euro")
+        );
+        assert_eq!(None, parse_posix_locale("C.UTF-8"));
+        assert_eq!(None, parse_posix_locale("POSIX"));
+    }
+}
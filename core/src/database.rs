@@ -5,6 +5,7 @@
 use anyhow::*;
 pub use rusqlite::Connection;
 use std::path;
+use std::sync::{Mutex, MutexGuard, PoisonError};
 
 // Embed migration SQL in executable.
 refinery::embed_migrations!("migrations");
@@ -21,3 +22,37 @@ pub fn setup_in_memory() -> Result<Connection> {
     migrations::runner().run(&mut con)?;
     Ok(con)
 }
+
+/// Lock a shared connection.
+/// A thread that panics while holding the lock poisons the mutex, but the connection
+/// is still usable because any open transaction is rolled back when dropped.
+/// So recover the lock rather than panicking every later user of the connection.
+pub fn lock(con: &Mutex<Connection>) -> MutexGuard<'_, Connection> {
+    con.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn lock_recovers_from_poisoning() {
+        let con = Arc::new(Mutex::new(setup_in_memory().unwrap()));
+
+        let poisoner = con.clone();
+        let result = std::thread::spawn(move || {
+            let _con = poisoner.lock().unwrap();
+            panic!("Poison the connection mutex");
+        })
+        .join();
+
+        assert!(result.is_err());
+        assert!(con.is_poisoned());
+
+        let count: i64 = lock(&con)
+            .query_row("SELECT COUNT(*) FROM pictures", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(0, count);
+    }
+}
@@ -4,8 +4,31 @@
 
 use std::path::Path;
 
-const PICTURES_SUFFIXES: [&str; 11] = [
-    "avif", "exr", "heic", "jpeg", "jpg", "jxl", "png", "qoi", "tiff", "webp", "gif",
+const PICTURES_SUFFIXES: &[&str] = &[
+    #[cfg(feature = "avif")]
+    "avif",
+    "exr",
+    "heic",
+    "jpeg",
+    "jpg",
+    #[cfg(feature = "jxl")]
+    "jxl",
+    "png",
+    "qoi",
+    "tiff",
+    "webp",
+    "gif",
+];
+
+/// Camera RAW formats. See `photo::raw` for how they are decoded.
+const RAW_PICTURES_SUFFIXES: &[&str] = &["arw", "cr2", "dng", "nef"];
+
+/// Picture formats that are recognised, but whose feature is turned off in this build.
+const DISABLED_PICTURES_SUFFIXES: &[&str] = &[
+    #[cfg(not(feature = "avif"))]
+    "avif",
+    #[cfg(not(feature = "jxl"))]
+    "jxl",
 ];
 
 const VIDEO_SUFFIXES: [&str; 5] = ["m4v", "mov", "mp4", "avi", "mkv"];
@@ -15,7 +38,7 @@ pub fn is_supported_picture(path: &Path) -> bool {
         return false;
     };
 
-    for pic_ext in PICTURES_SUFFIXES {
+    for pic_ext in PICTURES_SUFFIXES.iter().chain(RAW_PICTURES_SUFFIXES) {
         if path_ext.eq_ignore_ascii_case(pic_ext) {
             return true;
         }
@@ -24,6 +47,25 @@ pub fn is_supported_picture(path: &Path) -> bool {
     return false;
 }
 
+/// Is a file a picture in a camera RAW format?
+pub fn is_raw_picture(path: &Path) -> bool {
+    path.extension().is_some_and(|path_ext| {
+        RAW_PICTURES_SUFFIXES
+            .iter()
+            .any(|pic_ext| path_ext.eq_ignore_ascii_case(pic_ext))
+    })
+}
+
+/// Is a file a picture in a format left out of this build?
+/// Such files are skipped rather than treated as broken.
+pub fn is_disabled_picture(path: &Path) -> bool {
+    path.extension().is_some_and(|path_ext| {
+        DISABLED_PICTURES_SUFFIXES
+            .iter()
+            .any(|pic_ext| path_ext.eq_ignore_ascii_case(pic_ext))
+    })
+}
+
 pub fn is_supported_video(path: &Path) -> bool {
     let Some(path_ext) = path.extension() else {
         return false;
@@ -37,3 +79,38 @@ pub fn is_supported_video(path: &Path) -> bool {
 
     return false;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognises_pictures_ignoring_case() {
+        assert!(is_supported_picture(Path::new("a.jpg")));
+        assert!(is_supported_picture(Path::new("a.JPG")));
+        assert!(!is_supported_picture(Path::new("a.txt")));
+        assert!(!is_supported_picture(Path::new("jpg")));
+    }
+
+    #[test]
+    fn recognises_raw_pictures() {
+        let path = Path::new("IMG_0001.CR2");
+        assert!(is_supported_picture(path));
+        assert!(is_raw_picture(path));
+        assert!(!is_raw_picture(Path::new("IMG_0001.JPG")));
+    }
+
+    #[test]
+    fn avif_supported_only_with_feature() {
+        let path = Path::new("a.avif");
+        assert_eq!(cfg!(feature = "avif"), is_supported_picture(path));
+        assert_eq!(!cfg!(feature = "avif"), is_disabled_picture(path));
+    }
+
+    #[test]
+    fn jxl_supported_only_with_feature() {
+        let path = Path::new("a.JXL");
+        assert_eq!(cfg!(feature = "jxl"), is_supported_picture(path));
+        assert_eq!(!cfg!(feature = "jxl"), is_disabled_picture(path));
+    }
+}
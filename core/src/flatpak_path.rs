@@ -5,6 +5,43 @@
 use crate::thumbnailify;
 use crate::thumbnailify::ThumbnailSize;
 use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// How long to wait for a file system to say whether a file exists. Files on a
+/// network share can take seconds to check when the share is struggling.
+pub const AVAILABILITY_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Can a file be read right now?
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Availability {
+    /// File exists.
+    Available,
+
+    /// File definitely doesn't exist.
+    Missing,
+
+    /// Couldn't tell if the file exists, such as when a network share is slow or
+    /// disconnected. The file might be back later, so this isn't the same as missing.
+    Unavailable,
+}
+
+/// Checks if a path exists, giving up after `timeout`.
+/// A check that times out is left running in the background until the file system
+/// answers, so it doesn't block the caller.
+pub fn availability(path: &Path, timeout: Duration) -> Availability {
+    let (tx, rx) = mpsc::channel();
+    let path = path.to_path_buf();
+    std::thread::spawn(move || {
+        let _ = tx.send(path.try_exists());
+    });
+
+    match rx.recv_timeout(timeout) {
+        Ok(Ok(true)) => Availability::Available,
+        Ok(Ok(false)) => Availability::Missing,
+        Ok(Err(_)) | Err(_) => Availability::Unavailable,
+    }
+}
 
 /// A path to a file that exists both inside and outside of the Flatpak sandbox.
 /// FIXME does Default make sense? It is here to make Settings compile.
@@ -42,4 +79,54 @@ impl FlatpakPathBuf {
     pub fn exists(&self) -> bool {
         self.sandbox_path.exists()
     }
+
+    /// Like `exists`, but doesn't block for long on a slow file system.
+    pub fn availability(&self) -> Availability {
+        availability(&self.sandbox_path, AVAILABILITY_TIMEOUT)
+    }
+
+    /// Does the file name contain `query`, ignoring case?
+    /// An empty query matches every file.
+    pub fn file_name_contains(&self, query: &str) -> bool {
+        let query = query.to_lowercase();
+        self.host_path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().to_lowercase().contains(&query))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_name_contains_ignores_case() {
+        let path = FlatpakPathBuf::build("/photos/Holiday/IMG_1234.JPG", "/sandbox/IMG_1234.JPG");
+        assert!(path.file_name_contains("img_12"));
+        assert!(path.file_name_contains("1234.jpg"));
+        assert!(path.file_name_contains(""));
+    }
+
+    #[test]
+    fn file_name_contains_ignores_directories() {
+        let path = FlatpakPathBuf::build("/photos/Holiday/IMG_1234.JPG", "/sandbox/IMG_1234.JPG");
+        assert!(!path.file_name_contains("holiday"));
+        assert!(!path.file_name_contains("sandbox"));
+    }
+
+    #[test]
+    fn availability_of_existing_and_missing_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("a.jpg");
+        std::fs::write(&file, b"").unwrap();
+
+        assert_eq!(
+            Availability::Available,
+            availability(&file, AVAILABILITY_TIMEOUT)
+        );
+        assert_eq!(
+            Availability::Missing,
+            availability(&dir.path().join("b.jpg"), AVAILABILITY_TIMEOUT)
+        );
+    }
 }
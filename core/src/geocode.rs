@@ -0,0 +1,150 @@
+// SPDX-FileCopyrightText: © 2025 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Offline reverse geocoding of GPS coordinates to place names.
+//!
+//! Coordinates are looked up in a table of cities bundled with Fotema, so
+//! no coordinates ever leave the device. The table is generated from the GeoNames
+//! cities with a population of at least 5000 by `build-aux/geocode-cities.sh`.
+
+use h3o::LatLng;
+use std::fmt::Display;
+use std::sync::LazyLock;
+
+use tracing::warn;
+
+/// Cities bundled with Fotema. GeoNames data is licensed under CC BY 4.0.
+const CITIES: &str = include_str!("../resources/geocode/cities.tsv");
+
+/// Coordinates further than this from every known city don't resolve to a place.
+pub const MAX_DISTANCE_KM: f64 = 50.0;
+
+static GEOCODER: LazyLock<Geocoder> = LazyLock::new(|| Geocoder::parse(CITIES));
+
+/// Place, such as a city, that coordinates resolve to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Place {
+    pub city: String,
+    pub country: String,
+}
+
+impl Display for Place {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}, {}", self.city, self.country)
+    }
+}
+
+#[derive(Debug)]
+struct City {
+    place: Place,
+    location: LatLng,
+}
+
+/// Resolves coordinates to the nearest city.
+#[derive(Debug)]
+pub struct Geocoder {
+    cities: Vec<City>,
+}
+
+impl Geocoder {
+    /// Parses a table of cities with one city per line and tab separated
+    /// name, country, latitude, and longitude. Lines starting with `#` are comments.
+    /// Malformed lines are skipped.
+    pub fn parse(table: &str) -> Self {
+        let cities = table
+            .lines()
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let city = Self::parse_city(line);
+                if city.is_none() {
+                    warn!("Skipping malformed city: {}", line);
+                }
+                city
+            })
+            .collect();
+
+        Geocoder { cities }
+    }
+
+    fn parse_city(line: &str) -> Option<City> {
+        let mut columns = line.split('\t');
+        let city = columns.next()?.trim();
+        let country = columns.next()?.trim();
+        let latitude: f64 = columns.next()?.trim().parse().ok()?;
+        let longitude: f64 = columns.next()?.trim().parse().ok()?;
+
+        Some(City {
+            place: Place {
+                city: city.to_string(),
+                country: country.to_string(),
+            },
+            location: LatLng::new(latitude, longitude).ok()?,
+        })
+    }
+
+    /// Nearest city to the coordinates, or None if no city is within `MAX_DISTANCE_KM`.
+    pub fn place(&self, latitude: f64, longitude: f64) -> Option<Place> {
+        let location = LatLng::new(latitude, longitude).ok()?;
+
+        self.cities
+            .iter()
+            .map(|city| (city, city.location.distance_km(location)))
+            .filter(|(_, distance)| *distance <= MAX_DISTANCE_KM)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(city, _)| city.place.clone())
+    }
+}
+
+/// Nearest bundled city to the coordinates, or None if no city is within `MAX_DISTANCE_KM`.
+pub fn place(latitude: f64, longitude: f64) -> Option<Place> {
+    GEOCODER.place(latitude, longitude)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TABLE: &str = "# name, country, latitude, longitude
+Paris\tFrance\t48.8566\t2.3522
+Versailles\tFrance\t48.8049\t2.1204
+not a city
+Lyon\tFrance\tnorth\t4.8357
+";
+
+    #[test]
+    fn skips_comments_and_malformed_lines() {
+        let geocoder = Geocoder::parse(TABLE);
+        assert_eq!(2, geocoder.cities.len());
+    }
+
+    #[test]
+    fn resolves_to_nearest_city() {
+        let geocoder = Geocoder::parse(TABLE);
+
+        // Eiffel Tower
+        let place = geocoder.place(48.8584, 2.2945).unwrap();
+        assert_eq!("Paris, France", place.to_string());
+
+        // Palace of Versailles
+        let place = geocoder.place(48.8049, 2.1204).unwrap();
+        assert_eq!("Versailles, France", place.to_string());
+    }
+
+    #[test]
+    fn far_from_every_city_is_none() {
+        let geocoder = Geocoder::parse(TABLE);
+
+        // Middle of the Atlantic
+        assert_eq!(None, geocoder.place(30.0, -40.0));
+    }
+
+    #[test]
+    fn bundled_cities_parse() {
+        assert!(GEOCODER.cities.len() > 100);
+        assert_eq!(
+            Some("Tokyo, Japan".to_string()),
+            place(35.6895, 139.6917).map(|p| p.to_string())
+        );
+    }
+}
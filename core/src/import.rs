@@ -0,0 +1,334 @@
+// SPDX-FileCopyrightText: © 2025 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Copies pictures and videos from outside the library into a library root, filed
+//! into folders by date.
+//!
+//! Originals are never moved or changed. Files whose contents are already in the
+//! library are skipped.
+
+use crate::file_types;
+use crate::photo::content_hash;
+use crate::photo::metadata::{self, NaiveTimeZone};
+use crate::{ScannedFile, Scanner};
+
+use anyhow::*;
+use chrono::{DateTime, Local, NaiveDate};
+use std::collections::HashSet;
+use std::fs::{self, File, FileTimes};
+use std::path::{Path, PathBuf};
+use std::result::Result::Ok;
+use strum::{AsRefStr, EnumString, FromRepr};
+use tracing::{debug, error};
+
+/// Folder structure that imported files are filed into.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, EnumString, AsRefStr, FromRepr)]
+#[repr(u32)]
+pub enum ImportLayout {
+    /// Folder per year, containing a folder per month. Such as `2024/07`.
+    #[default]
+    YearMonth,
+
+    /// Folder per year, month, and day. Such as `2024/07/31`.
+    YearMonthDay,
+
+    /// Folder per year. Such as `2024`.
+    Year,
+}
+
+impl ImportLayout {
+    /// Directory, relative to the library root, for a file created on a given date.
+    pub fn dir_for(&self, date: NaiveDate) -> PathBuf {
+        let format = match self {
+            ImportLayout::YearMonth => "%Y/%m",
+            ImportLayout::YearMonthDay => "%Y/%m/%d",
+            ImportLayout::Year => "%Y",
+        };
+        PathBuf::from(date.format(format).to_string())
+    }
+}
+
+/// Outcome of importing files.
+#[derive(Debug, Default)]
+pub struct ImportSummary {
+    /// Count of files copied into the library.
+    pub imported: usize,
+
+    /// Count of files skipped because their contents are already in the library.
+    pub duplicates: usize,
+
+    /// Files that couldn't be copied.
+    pub failed: Vec<PathBuf>,
+}
+
+/// Pictures and videos to import from a list of files and directories.
+/// Directories are searched recursively, skipping hidden files and directories.
+/// Anything that isn't a supported picture or video is left out.
+pub fn find_importable(sources: &[PathBuf]) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+
+    for source in sources {
+        if source.is_dir() {
+            match Scanner::build(source).and_then(|scanner| scanner.scan_all()) {
+                Ok(scanned) => files.extend(scanned.into_iter().map(|file| match file {
+                    ScannedFile::Photo(path) | ScannedFile::Video(path) => path,
+                })),
+                Err(e) => error!("Failed scanning {:?} for import: {:?}", source, e),
+            }
+        } else if file_types::is_supported_picture(source) || file_types::is_supported_video(source)
+        {
+            files.push(source.clone());
+        } else {
+            debug!("Not importing {:?}: not a picture or video", source);
+        }
+    }
+
+    files
+}
+
+/// Copy files into folders by date under `library_root`.
+/// A file is skipped if the hash of its contents is in `known_hashes`, which is
+/// updated with the hash of each imported file so that duplicates within the
+/// imported files are also skipped.
+/// `on_progress` is called after each file.
+pub fn import(
+    files: &[PathBuf],
+    library_root: &Path,
+    layout: ImportLayout,
+    known_hashes: &mut HashSet<String>,
+    mut on_progress: impl FnMut(),
+) -> Result<ImportSummary> {
+    let mut summary = ImportSummary::default();
+
+    for source in files {
+        match import_one(source, library_root, layout, known_hashes) {
+            Ok(true) => summary.imported += 1,
+            Ok(false) => summary.duplicates += 1,
+            Err(e) => {
+                error!("Failed importing {:?}: {:?}", source, e);
+                summary.failed.push(source.clone());
+            }
+        }
+        on_progress();
+    }
+
+    Ok(summary)
+}
+
+/// Copy a file into the library. Returns false if the file is a duplicate.
+fn import_one(
+    source: &Path,
+    library_root: &Path,
+    layout: ImportLayout,
+    known_hashes: &mut HashSet<String>,
+) -> Result<bool> {
+    let hash = content_hash::from_path(source)?.hash;
+    if known_hashes.contains(&hash) {
+        debug!("Not importing {:?}: already in library", source);
+        return Ok(false);
+    }
+
+    let file_name = source
+        .file_name()
+        .ok_or_else(|| anyhow!("No file name: {:?}", source))?;
+
+    let dir = library_root.join(layout.dir_for(created_on(source)?));
+    fs::create_dir_all(&dir)?;
+
+    // A file of the same name and contents might be in the library, but not yet scanned.
+    let existing = dir.join(file_name);
+    if existing.exists() && content_hash::from_path(&existing)?.hash == hash {
+        debug!("Not importing {:?}: already at {:?}", source, existing);
+        known_hashes.insert(hash);
+        return Ok(false);
+    }
+
+    let target = crate::photo::export::unique_destination(&dir, Path::new(file_name));
+    crate::photo::export::copy_file(source, &target)
+        .with_context(|| format!("Failed copying {:?} to {:?}", source, target))?;
+
+    // Keep the modification time so that files without EXIF data are still in date order.
+    let modified = fs::metadata(source)?.modified()?;
+    File::options()
+        .write(true)
+        .open(&target)?
+        .set_times(FileTimes::new().set_modified(modified))?;
+
+    known_hashes.insert(hash);
+    Ok(true)
+}
+
+/// Date a file was created. Taken from EXIF data for pictures, falling back to
+/// the file modification time.
+fn created_on(path: &Path) -> Result<NaiveDate> {
+    if file_types::is_supported_picture(path) {
+        // Only the date is used, and that is the same in any time zone.
+        let exif_date = metadata::from_path(path, NaiveTimeZone::default())
+            .ok()
+            .and_then(|metadata| metadata.exif_created_at.or(metadata.exif_modified_at));
+        if let Some(exif_date) = exif_date {
+            // EXIF timestamps are in the local time of wherever the picture was taken.
+            return Ok(exif_date.date_naive());
+        }
+    }
+
+    let modified: DateTime<Local> = fs::metadata(path)?.modified()?.into();
+    Ok(modified.date_naive())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+
+    /// Writes a file last modified at the given date.
+    fn write_file(path: &Path, contents: &[u8], date: NaiveDate) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+
+        let modified = date
+            .and_hms_opt(12, 0, 0)
+            .unwrap()
+            .and_local_timezone(Local)
+            .unwrap();
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(modified.timestamp() as u64);
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_times(FileTimes::new().set_modified(modified))
+            .unwrap();
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn layout_dirs() {
+        let date = date(2024, 7, 3);
+        assert_eq!(
+            PathBuf::from("2024/07"),
+            ImportLayout::YearMonth.dir_for(date)
+        );
+        assert_eq!(
+            PathBuf::from("2024/07/03"),
+            ImportLayout::YearMonthDay.dir_for(date)
+        );
+        assert_eq!(PathBuf::from("2024"), ImportLayout::Year.dir_for(date));
+    }
+
+    #[test]
+    fn finds_pictures_and_videos_in_directories() {
+        let source = tempfile::tempdir().unwrap();
+        write_file(&source.path().join("a.jpg"), b"a", date(2024, 1, 1));
+        write_file(&source.path().join("sub/b.mp4"), b"b", date(2024, 1, 1));
+        write_file(&source.path().join("notes.txt"), b"c", date(2024, 1, 1));
+        write_file(&source.path().join(".hidden/d.jpg"), b"d", date(2024, 1, 1));
+
+        let loose = tempfile::tempdir().unwrap();
+        write_file(&loose.path().join("e.png"), b"e", date(2024, 1, 1));
+
+        let mut files = find_importable(&[source.path().to_path_buf(), loose.path().join("e.png")]);
+        files.sort();
+
+        let mut expected = vec![
+            source.path().join("a.jpg"),
+            source.path().join("sub/b.mp4"),
+            loose.path().join("e.png"),
+        ];
+        expected.sort();
+        assert_eq!(expected, files);
+    }
+
+    #[test]
+    fn imports_into_date_folders() {
+        let source = tempfile::tempdir().unwrap();
+        let library = tempfile::tempdir().unwrap();
+        let a = source.path().join("a.jpg");
+        write_file(&a, b"a", date(2023, 12, 31));
+
+        let mut known_hashes = HashSet::new();
+        let summary = import(
+            &[a.clone()],
+            library.path(),
+            ImportLayout::YearMonth,
+            &mut known_hashes,
+            || {},
+        )
+        .unwrap();
+
+        assert_eq!(1, summary.imported);
+        assert_eq!(0, summary.duplicates);
+        assert_eq!(
+            b"a".to_vec(),
+            fs::read(library.path().join("2023/12/a.jpg")).unwrap()
+        );
+
+        // Original is left where it was.
+        assert!(a.exists());
+    }
+
+    #[test]
+    fn skips_duplicates() {
+        let source = tempfile::tempdir().unwrap();
+        let library = tempfile::tempdir().unwrap();
+
+        let in_library = source.path().join("in_library.jpg");
+        write_file(&in_library, b"known", date(2024, 1, 1));
+
+        // Same contents, different names.
+        let a = source.path().join("a.jpg");
+        let b = source.path().join("b.jpg");
+        write_file(&a, b"same", date(2024, 1, 1));
+        write_file(&b, b"same", date(2024, 1, 1));
+
+        let mut known_hashes = HashSet::from([content_hash::from_path(&in_library).unwrap().hash]);
+        let mut progress = 0;
+        let summary = import(
+            &[in_library, a, b],
+            library.path(),
+            ImportLayout::Year,
+            &mut known_hashes,
+            || progress += 1,
+        )
+        .unwrap();
+
+        assert_eq!(1, summary.imported);
+        assert_eq!(2, summary.duplicates);
+        assert_eq!(3, progress);
+        assert!(library.path().join("2024/a.jpg").exists());
+        assert!(!library.path().join("2024/b.jpg").exists());
+        assert!(!library.path().join("2024/in_library.jpg").exists());
+    }
+
+    #[test]
+    fn renames_different_file_with_same_name() {
+        let source = tempfile::tempdir().unwrap();
+        let library = tempfile::tempdir().unwrap();
+        write_file(
+            &library.path().join("2024/a.jpg"),
+            b"first",
+            date(2024, 1, 1),
+        );
+
+        let a = source.path().join("a.jpg");
+        write_file(&a, b"second", date(2024, 1, 1));
+
+        let summary = import(
+            &[a],
+            library.path(),
+            ImportLayout::Year,
+            &mut HashSet::new(),
+            || {},
+        )
+        .unwrap();
+
+        assert_eq!(1, summary.imported);
+        assert_eq!(
+            b"second".to_vec(),
+            fs::read(library.path().join("2024/a (1).jpg")).unwrap()
+        );
+    }
+}
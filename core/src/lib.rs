@@ -2,20 +2,30 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+pub mod burst;
+pub mod collation;
 pub mod database;
 pub mod file_types;
 pub mod flatpak_path;
+pub mod geocode;
+pub mod import;
+pub mod library_roots;
 pub mod machine_learning;
+pub mod number;
 pub mod path_encoding;
 pub mod people;
 pub mod photo;
 pub mod scanner;
+pub mod tag;
 pub mod thumbnailify;
 pub mod time;
+pub mod undo;
+pub mod user_album;
 pub mod video;
 pub mod visual;
 
-pub use flatpak_path::FlatpakPathBuf;
+pub use flatpak_path::{Availability, FlatpakPathBuf};
+pub use library_roots::LibraryRoots;
 pub use people::model::FaceId;
 pub use people::model::PersonId;
 pub use photo::model::PictureId;
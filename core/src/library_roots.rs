@@ -0,0 +1,279 @@
+// SPDX-FileCopyrightText: © 2025 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Directories that pictures and videos are scanned from.
+//!
+//! Pictures and videos under the primary root are stored in the database relative
+//! to the primary root, so the primary root can be moved without reprocessing.
+//! Pictures and videos under any other root are stored with their full sandbox path,
+//! which identifies the root they came from and can't clash with a relative path.
+
+use crate::FlatpakPathBuf;
+
+use anyhow::*;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LibraryRoots {
+    /// Root chosen during onboarding.
+    primary: FlatpakPathBuf,
+
+    /// Further roots chosen in preferences.
+    additional: Vec<FlatpakPathBuf>,
+}
+
+impl LibraryRoots {
+    /// Builds library roots. Additional roots nested inside, or containing, another root
+    /// are left out so that no directory is scanned twice.
+    pub fn build(primary: &FlatpakPathBuf, additional: &[FlatpakPathBuf]) -> LibraryRoots {
+        let mut roots = LibraryRoots {
+            primary: primary.clone(),
+            additional: Vec::new(),
+        };
+
+        for root in additional {
+            if let Err(e) = roots.add(root.clone()) {
+                warn!("Ignoring library root: {}", e);
+            }
+        }
+
+        roots
+    }
+
+    /// Adds another root. Fails if the root overlaps an existing root.
+    pub fn add(&mut self, root: FlatpakPathBuf) -> Result<()> {
+        if let Some(existing) = self.overlapping(&root.sandbox_path) {
+            bail!(
+                "{:?} overlaps library root {:?}",
+                root.sandbox_path,
+                existing.sandbox_path
+            );
+        }
+        self.additional.push(root);
+        Ok(())
+    }
+
+    /// Existing root that is the same as, nested inside, or contains the given directory.
+    pub fn overlapping(&self, dir: &Path) -> Option<&FlatpakPathBuf> {
+        self.iter()
+            .find(|root| dir.starts_with(&root.sandbox_path) || root.sandbox_path.starts_with(dir))
+    }
+
+    pub fn primary(&self) -> &FlatpakPathBuf {
+        &self.primary
+    }
+
+    /// All roots, primary first.
+    pub fn iter(&self) -> impl Iterator<Item = &FlatpakPathBuf> {
+        std::iter::once(&self.primary).chain(self.additional.iter())
+    }
+
+    /// Root containing the file, if any.
+    pub fn root_of(&self, sandbox_path: &Path) -> Option<&FlatpakPathBuf> {
+        self.iter()
+            .find(|root| sandbox_path.starts_with(&root.sandbox_path))
+    }
+
+    /// Is the file in one of the roots?
+    pub fn contains(&self, sandbox_path: &Path) -> bool {
+        self.root_of(sandbox_path).is_some()
+    }
+
+    /// Sandbox path under one of the roots of a file or folder that was picked with the
+    /// file chooser. The file chooser may give a document portal path that isn't under
+    /// any root, even for a file in the library, so the host paths are compared instead.
+    pub fn to_root_path(&self, picked: &FlatpakPathBuf) -> Option<PathBuf> {
+        if self.contains(&picked.sandbox_path) {
+            return Some(picked.sandbox_path.clone());
+        }
+
+        self.iter().find_map(|root| {
+            let relative = picked.host_path.strip_prefix(&root.host_path).ok()?;
+            Some(root.sandbox_path.join(relative))
+        })
+    }
+
+    /// Path to store in the database for a file in one of the roots.
+    pub fn to_stored_path(&self, sandbox_path: &Path) -> Result<PathBuf> {
+        if let std::result::Result::Ok(relative) =
+            sandbox_path.strip_prefix(&self.primary.sandbox_path)
+        {
+            Ok(relative.to_path_buf())
+        } else if self.contains(sandbox_path) {
+            Ok(sandbox_path.to_path_buf())
+        } else {
+            bail!("{:?} is not in the library", sandbox_path);
+        }
+    }
+
+    /// Host and sandbox paths of a path stored in the database.
+    /// A file from a root that is no longer in the library can't be mapped to a host
+    /// path, so its sandbox path is used for both.
+    pub fn resolve(&self, stored_path: &Path) -> FlatpakPathBuf {
+        if stored_path.is_relative() {
+            return FlatpakPathBuf::build(
+                self.primary.host_path.join(stored_path),
+                self.primary.sandbox_path.join(stored_path),
+            );
+        }
+
+        self.root_of(stored_path)
+            .and_then(|root| {
+                let relative = stored_path.strip_prefix(&root.sandbox_path).ok()?;
+                Some(FlatpakPathBuf::build(
+                    root.host_path.join(relative),
+                    stored_path,
+                ))
+            })
+            .unwrap_or_else(|| FlatpakPathBuf::build(stored_path, stored_path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roots() -> LibraryRoots {
+        LibraryRoots::build(
+            &FlatpakPathBuf::build("/home/user/Pictures", "/run/doc/1/Pictures"),
+            &[FlatpakPathBuf::build(
+                "/mnt/nas/Photos",
+                "/run/doc/2/Photos",
+            )],
+        )
+    }
+
+    #[test]
+    fn stores_primary_paths_relative() {
+        let path = roots()
+            .to_stored_path(Path::new("/run/doc/1/Pictures/2024/IMG_0001.JPG"))
+            .unwrap();
+        assert_eq!(PathBuf::from("2024/IMG_0001.JPG"), path);
+    }
+
+    #[test]
+    fn stores_additional_paths_in_full() {
+        let path = roots()
+            .to_stored_path(Path::new("/run/doc/2/Photos/IMG_0001.JPG"))
+            .unwrap();
+        assert_eq!(PathBuf::from("/run/doc/2/Photos/IMG_0001.JPG"), path);
+    }
+
+    #[test]
+    fn refuses_to_store_path_outside_library() {
+        assert!(
+            roots()
+                .to_stored_path(Path::new("/tmp/IMG_0001.JPG"))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn resolves_stored_paths() {
+        let roots = roots();
+
+        let primary = roots.resolve(Path::new("2024/IMG_0001.JPG"));
+        assert_eq!(
+            FlatpakPathBuf::build(
+                "/home/user/Pictures/2024/IMG_0001.JPG",
+                "/run/doc/1/Pictures/2024/IMG_0001.JPG"
+            ),
+            primary
+        );
+
+        let additional = roots.resolve(Path::new("/run/doc/2/Photos/IMG_0001.JPG"));
+        assert_eq!(
+            FlatpakPathBuf::build(
+                "/mnt/nas/Photos/IMG_0001.JPG",
+                "/run/doc/2/Photos/IMG_0001.JPG"
+            ),
+            additional
+        );
+    }
+
+    #[test]
+    fn resolves_path_from_removed_root() {
+        let path = roots().resolve(Path::new("/run/doc/3/Old/IMG_0001.JPG"));
+        assert_eq!(
+            FlatpakPathBuf::build("/run/doc/3/Old/IMG_0001.JPG", "/run/doc/3/Old/IMG_0001.JPG"),
+            path
+        );
+        assert!(!roots().contains(&path.sandbox_path));
+    }
+
+    #[test]
+    fn picked_paths_are_mapped_into_roots_by_host_path() {
+        let roots = roots();
+
+        let portal =
+            FlatpakPathBuf::build("/home/user/Pictures/2024", "/run/user/1000/doc/abc123/2024");
+        assert_eq!(
+            Some(PathBuf::from("/run/doc/1/Pictures/2024")),
+            roots.to_root_path(&portal)
+        );
+
+        let in_root = FlatpakPathBuf::build("/mnt/nas/Photos/2024", "/run/doc/2/Photos/2024");
+        assert_eq!(
+            Some(PathBuf::from("/run/doc/2/Photos/2024")),
+            roots.to_root_path(&in_root)
+        );
+
+        let outside = FlatpakPathBuf::build("/home/user/Documents", "/run/user/1000/doc/def456");
+        assert_eq!(None, roots.to_root_path(&outside));
+    }
+
+    #[test]
+    fn refuses_nested_roots() {
+        let mut roots = roots();
+        assert!(
+            roots
+                .add(FlatpakPathBuf::build(
+                    "/home/user/Pictures/2024",
+                    "/run/doc/1/Pictures/2024"
+                ))
+                .is_err()
+        );
+        assert!(
+            roots
+                .add(FlatpakPathBuf::build("/run/doc", "/run/doc"))
+                .is_err()
+        );
+        assert!(
+            roots
+                .add(FlatpakPathBuf::build(
+                    "/mnt/nas/Photos",
+                    "/run/doc/2/Photos"
+                ))
+                .is_err()
+        );
+        assert_eq!(2, roots.iter().count());
+    }
+
+    #[test]
+    fn sibling_with_common_prefix_is_not_nested() {
+        let mut roots = roots();
+        assert!(
+            roots
+                .add(FlatpakPathBuf::build(
+                    "/home/user/Pictures2",
+                    "/run/doc/1/Pictures2"
+                ))
+                .is_ok()
+        );
+        assert_eq!(3, roots.iter().count());
+    }
+
+    #[test]
+    fn build_leaves_out_nested_roots() {
+        let roots = LibraryRoots::build(
+            &FlatpakPathBuf::build("/home/user/Pictures", "/home/user/Pictures"),
+            &[
+                FlatpakPathBuf::build("/home/user/Pictures/Camera", "/home/user/Pictures/Camera"),
+                FlatpakPathBuf::build("/mnt/nas", "/mnt/nas"),
+            ],
+        );
+        assert_eq!(2, roots.iter().count());
+    }
+}
@@ -2,7 +2,7 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use crate::people::FaceDetectionCandidate;
+use crate::people::{self, FaceDetectionCandidate};
 use crate::thumbnailify::{ThumbnailSize, Thumbnailer};
 
 use anyhow::*;
@@ -19,8 +19,22 @@ use rust_faces::{
 
 use gdk4::prelude::TextureExt;
 use image::DynamicImage;
+use strum::{AsRefStr, EnumString, FromRepr};
 use tracing::{debug, error, info};
 
+/// Face detectors to run, trading speed for accuracy.
+/// Embeddings of faces detected by different models aren't comparable.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, EnumString, AsRefStr, FromRepr)]
+#[repr(u32)]
+pub enum FaceDetectionModel {
+    /// BlazeFace only. Suits laptops.
+    #[default]
+    Fast,
+
+    /// BlazeFace and MTCNN, which finds more faces, but is much slower. Suits desktops.
+    Accurate,
+}
+
 #[derive(Debug, Clone)]
 pub struct Rect {
     pub x: f32,
@@ -92,7 +106,11 @@ pub struct FaceExtractor {
 }
 
 impl FaceExtractor {
-    pub fn build(base_path: &Path, thumbnailer: Thumbnailer) -> Result<FaceExtractor> {
+    pub fn build(
+        base_path: &Path,
+        thumbnailer: Thumbnailer,
+        detection_model: FaceDetectionModel,
+    ) -> Result<FaceExtractor> {
         let faces_base_path = PathBuf::from(base_path).join("faces");
         let _ = std::fs::create_dir_all(&faces_base_path)?;
 
@@ -112,13 +130,15 @@ impl FaceExtractor {
 
         detectors.push((blaze_face_default, "blaze_face_640_default".into()));
 
-        let mtcnn_params = rust_faces::MtCnnParams::default();
+        if detection_model == FaceDetectionModel::Accurate {
+            let mtcnn_params = rust_faces::MtCnnParams::default();
 
-        let mtcnn = FaceDetectorBuilder::new(FaceDetection::MtCnn(mtcnn_params))
-            .download()
-            .build()?;
+            let mtcnn = FaceDetectorBuilder::new(FaceDetection::MtCnn(mtcnn_params))
+                .download()
+                .build()?;
 
-        detectors.push((mtcnn, "mtcnn".into()));
+            detectors.push((mtcnn, "mtcnn".into()));
+        }
 
         Ok(FaceExtractor {
             faces_base_path,
@@ -129,7 +149,12 @@ impl FaceExtractor {
     }
 
     /// Identify faces in a photo and return a vector of paths of extracted face images.
-    pub async fn extract_faces(&mut self, candidate: &FaceDetectionCandidate) -> Result<Vec<Face>> {
+    /// Face thumbnails are expanded by `head_margin`, a fraction of the face bounds.
+    pub async fn extract_faces(
+        &mut self,
+        candidate: &FaceDetectionCandidate,
+        head_margin: f32,
+    ) -> Result<Vec<Face>> {
         info!("Detecting faces in {:?}", candidate.host_path);
 
         let thumbnail_hash = candidate.thumbnail_hash();
@@ -173,51 +198,26 @@ impl FaceExtractor {
                 // Extract face and save to thumbnail.
                 // The bounding box is pretty tight, so make it a bit bigger.
                 // Also, make the box a square.
-
-                let longest: f32 = if f.rect.width < f.rect.height {
-                    f.rect.width
-                } else {
-                    f.rect.height
-                };
-
-                let mut longest = longest * 1.6;
-                let mut half_longest = longest / 2.0;
-
-                let (centre_x, centre_y) = Self::centre(&f);
-
-                // Normalize thumbnail to be a square.
-                if (original_image.width() as f32) < centre_x + half_longest {
-                    half_longest = original_image.width() as f32 - centre_x;
-                    longest = half_longest * 2.0;
-                }
-                if (original_image.height() as f32) < centre_y + half_longest {
-                    half_longest = original_image.height() as f32 - centre_y;
-                    longest = half_longest * 2.0;
-                }
-
-                if centre_x < half_longest {
-                    half_longest = centre_x;
-                    longest = half_longest * 2.0;
-                }
-
-                if centre_y < half_longest {
-                    half_longest = centre_y;
-                    longest = half_longest * 2.0;
-                }
-
-                // Don't panic when x or y would be < zero
-                let mut x = centre_x - half_longest;
-                if x < 0.0 {
-                    x = 0.0;
-                }
-                let mut y = centre_y - half_longest;
-                if y < 0.0 {
-                    y = 0.0;
+                let square = people::model::Rect {
+                    x: f.rect.x,
+                    y: f.rect.y,
+                    width: f.rect.width,
+                    height: f.rect.height,
                 }
+                .head_square(
+                    Self::centre(&f),
+                    head_margin,
+                    original_image.width() as f32,
+                    original_image.height() as f32,
+                );
 
                 // FIXME use fast_image_resize instead of image-rs
-                let thumbnail =
-                    original_image.crop_imm(x as u32, y as u32, longest as u32, longest as u32);
+                let thumbnail = original_image.crop_imm(
+                    square.x as u32,
+                    square.y as u32,
+                    square.width as u32,
+                    square.height as u32,
+                );
 
                 // 64x64 matches size in thumbnail list in picture view
                 let thumbnail = thumbnail.thumbnail(64, 64);
@@ -18,7 +18,7 @@ use reqwest::header::{ACCEPT, HeaderMap, HeaderValue};
 
 use tracing::info;
 
-use crate::people::model::{DetectedFace, PersonForRecognition, PersonId};
+use crate::people::model::{DetectedFace, Embedding, PersonForRecognition, PersonId};
 
 pub struct FaceRecognizer {
     /// Person recognition data and a opencv matrix of aligned face features.
@@ -74,15 +74,7 @@ impl FaceRecognizer {
         let mut face_recognizer =
             FaceRecognizerSF::create_def(&self.model_path.to_string_lossy(), "")?;
 
-        let face_img = imgcodecs::imread_def(&unknown_face.face_path.to_string_lossy())?;
-
-        let face_landmarks = unknown_face.landmarks_as_mat();
-
-        let mut aligned_face = Mat::default();
-        face_recognizer.align_crop(&face_img, &face_landmarks, &mut aligned_face)?;
-
-        let mut face_features = Mat::default();
-        face_recognizer.feature(&aligned_face, &mut face_features)?;
+        let face_features = Self::features(&mut face_recognizer, unknown_face)?;
 
         let best_person_and_score = self
             .people
@@ -112,6 +104,35 @@ impl FaceRecognizer {
         Ok(None)
     }
 
+    /// Computes face recognition features for a face so that it can be compared
+    /// with other faces without running the model again.
+    pub fn embedding(&self, face: &DetectedFace) -> Result<Embedding> {
+        let mut face_recognizer =
+            FaceRecognizerSF::create_def(&self.model_path.to_string_lossy(), "")?;
+
+        let face_features = Self::features(&mut face_recognizer, face)?;
+
+        let values = face_features.data_typed::<f32>()?.to_vec();
+        Ok(Embedding::new(values))
+    }
+
+    fn features(
+        face_recognizer: &mut opencv::core::Ptr<FaceRecognizerSF>,
+        face: &DetectedFace,
+    ) -> Result<Mat> {
+        let face_img = imgcodecs::imread_def(&face.face_path.to_string_lossy())?;
+
+        let face_landmarks = face.landmarks_as_mat();
+
+        let mut aligned_face = Mat::default();
+        face_recognizer.align_crop(&face_img, &face_landmarks, &mut aligned_face)?;
+
+        let mut face_features = Mat::default();
+        face_recognizer.feature(&aligned_face, &mut face_features)?;
+
+        Ok(face_features)
+    }
+
     fn download_model(url: &str, destination: &Path) -> Result<()> {
         if destination.exists() {
             info!("Face recognition model already downloaded.");
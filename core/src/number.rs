@@ -0,0 +1,69 @@
+// SPDX-FileCopyrightText: © 2025 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Formats numbers the way the user's locale writes them, such as "12,345" in English
+//! and "12.345" in German.
+
+use std::sync::LazyLock;
+
+use icu_decimal::input::Decimal;
+use icu_decimal::options::DecimalFormatterOptions;
+use icu_decimal::{DecimalFormatter, DecimalFormatterPreferences};
+use icu_locale_core::Locale;
+use tracing::{info, warn};
+
+use crate::collation;
+
+static FORMATTER: LazyLock<Option<DecimalFormatter>> = LazyLock::new(|| {
+    let locale = collation::system_locale("LC_NUMERIC");
+    info!("Formatting numbers for locale {:?}", locale);
+    formatter(locale.as_ref())
+});
+
+/// Formats a count with the user's locale's digit grouping.
+/// Falls back to plain digits if no formatter could be built.
+pub fn format_count(count: usize) -> String {
+    match FORMATTER.as_ref() {
+        Some(formatter) => format_with(formatter, count),
+        None => count.to_string(),
+    }
+}
+
+fn format_with(formatter: &DecimalFormatter, count: usize) -> String {
+    formatter.format(&Decimal::from(count)).to_string()
+}
+
+/// Decimal formatter for a locale, or for the root locale if there isn't one.
+fn formatter(locale: Option<&Locale>) -> Option<DecimalFormatter> {
+    let prefs = locale
+        .map(DecimalFormatterPreferences::from)
+        .unwrap_or_default();
+
+    DecimalFormatter::try_new(prefs, DecimalFormatterOptions::default())
+        .inspect_err(|e| warn!("Failed building number formatter: {:?}", e))
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(locale: &str, count: usize) -> String {
+        let locale = Locale::try_from_str(locale).unwrap();
+        format_with(&formatter(Some(&locale)).unwrap(), count)
+    }
+
+    #[test]
+    fn digits_are_grouped_for_locale() {
+        assert_eq!("12,345", format("en", 12345));
+        assert_eq!("12.345", format("de", 12345));
+        assert_eq!("1,234,567", format("en", 1234567));
+    }
+
+    #[test]
+    fn small_numbers_are_not_grouped() {
+        assert_eq!("0", format("en", 0));
+        assert_eq!("999", format("de", 999));
+    }
+}
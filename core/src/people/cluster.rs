@@ -0,0 +1,190 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::people::model::{Embedding, FaceId, PersonId};
+
+/// Default maximum cosine distance between two faces of the same person.
+/// Derived from the cosine similarity threshold of 0.363 recommended for the
+/// OpenCV SFace model.
+pub const DEFAULT_MAX_DISTANCE: f32 = 0.637;
+
+/// Groups faces whose embeddings are within `max_distance` of each other.
+/// Grouping uses complete linkage: every face in a cluster is within `max_distance` of
+/// every other face in it, so a chain of similar faces can't join two different people.
+/// The closest faces are grouped first. Faces similar to no other face are not clustered.
+/// Clusters are ordered largest first and faces keep their input order.
+pub fn cluster(faces: &[(FaceId, Embedding)], max_distance: f32) -> Vec<Vec<FaceId>> {
+    let distance = |i: usize, j: usize| faces[i].1.cosine_distance(&faces[j].1);
+
+    // Pairs of indices into faces that could share a cluster, closest first.
+    let mut pairs: Vec<(f32, usize, usize)> = Vec::new();
+    for i in 0..faces.len() {
+        for j in (i + 1)..faces.len() {
+            let d = distance(i, j);
+            if d <= max_distance {
+                pairs.push((d, i, j));
+            }
+        }
+    }
+    pairs.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    // Cluster of each face and members of each cluster, as indices into faces.
+    let mut cluster_of: Vec<usize> = (0..faces.len()).collect();
+    let mut members: Vec<Vec<usize>> = (0..faces.len()).map(|index| vec![index]).collect();
+
+    for (_, i, j) in pairs {
+        let (a, b) = (cluster_of[i], cluster_of[j]);
+        if a == b {
+            continue;
+        }
+
+        let is_within_distance = members[a]
+            .iter()
+            .all(|&x| members[b].iter().all(|&y| distance(x, y) <= max_distance));
+        if !is_within_distance {
+            continue;
+        }
+
+        let moved = std::mem::take(&mut members[b]);
+        for &index in &moved {
+            cluster_of[index] = a;
+        }
+        members[a].extend(moved);
+    }
+
+    let mut clusters: Vec<Vec<usize>> = members
+        .into_iter()
+        .filter(|cluster| cluster.len() > 1)
+        .map(|mut cluster| {
+            cluster.sort();
+            cluster
+        })
+        .collect();
+
+    // Order by first face, then stable sort so equally sized clusters keep input order.
+    clusters.sort_by_key(|cluster| cluster[0]);
+    clusters.sort_by_key(|cluster| std::cmp::Reverse(cluster.len()));
+
+    clusters
+        .into_iter()
+        .map(|cluster| cluster.into_iter().map(|index| faces[index].0).collect())
+        .collect()
+}
+
+/// Finds the person with a confirmed face nearest to an embedding.
+/// Returns `None` if no confirmed face is within `max_distance`.
+/// The returned score is 1.0 for an identical face, falling to 0.0 at `max_distance`.
+pub fn nearest_person(
+    embedding: &Embedding,
+    confirmed: &[(PersonId, Embedding)],
+    max_distance: f32,
+) -> Option<(PersonId, f32)> {
+    confirmed
+        .iter()
+        .map(|(person_id, other)| (*person_id, embedding.cosine_distance(other)))
+        .filter(|(_, distance)| *distance <= max_distance)
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(person_id, distance)| {
+            let score = if max_distance > 0.0 {
+                1.0 - distance / max_distance
+            } else {
+                1.0
+            };
+            (person_id, score.clamp(0.0, 1.0))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn face(id: i64, values: Vec<f32>) -> (FaceId, Embedding) {
+        (FaceId::new(id), Embedding::new(values))
+    }
+
+    #[test]
+    fn groups_similar_faces() {
+        let faces = vec![
+            face(1, vec![1.0, 0.0]),
+            face(2, vec![0.0, 1.0]),
+            face(3, vec![0.99, 0.01]),
+            face(4, vec![0.01, 0.99]),
+            face(5, vec![0.98, 0.02]),
+        ];
+
+        let clusters = cluster(&faces, 0.1);
+
+        assert_eq!(
+            clusters,
+            vec![
+                vec![FaceId::new(1), FaceId::new(3), FaceId::new(5)],
+                vec![FaceId::new(2), FaceId::new(4)],
+            ]
+        );
+    }
+
+    #[test]
+    fn excludes_lone_faces() {
+        let faces = vec![face(1, vec![1.0, 0.0]), face(2, vec![0.0, 1.0])];
+        assert!(cluster(&faces, 0.1).is_empty());
+    }
+
+    #[test]
+    fn grouping_is_not_transitive() {
+        // 1 and 3 are too far apart, but both are close to 2, which is closest to 3.
+        let faces = vec![
+            face(1, vec![1.0, 0.0]),
+            face(2, vec![1.0, 0.5]),
+            face(3, vec![1.0, 1.0]),
+        ];
+
+        let clusters = cluster(&faces, 0.11);
+
+        assert_eq!(clusters, vec![vec![FaceId::new(2), FaceId::new(3)]]);
+    }
+
+    #[test]
+    fn no_faces_no_clusters() {
+        assert!(cluster(&[], DEFAULT_MAX_DISTANCE).is_empty());
+    }
+
+    fn person(id: i64, values: Vec<f32>) -> (PersonId, Embedding) {
+        (PersonId::new(id), Embedding::new(values))
+    }
+
+    #[test]
+    fn suggests_nearest_person() {
+        let confirmed = vec![
+            person(1, vec![1.0, 0.0]),
+            person(2, vec![0.0, 1.0]),
+            person(2, vec![0.1, 1.0]),
+        ];
+
+        let (person_id, score) =
+            nearest_person(&Embedding::new(vec![0.05, 1.0]), &confirmed, 0.1).unwrap();
+
+        assert_eq!(PersonId::new(2), person_id);
+        assert!(score > 0.9);
+    }
+
+    #[test]
+    fn identical_face_scores_one() {
+        let confirmed = vec![person(1, vec![1.0, 0.0])];
+        let (_, score) = nearest_person(&Embedding::new(vec![2.0, 0.0]), &confirmed, 0.1).unwrap();
+        assert!((score - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn no_suggestion_beyond_max_distance() {
+        let confirmed = vec![person(1, vec![1.0, 0.0])];
+        assert!(nearest_person(&Embedding::new(vec![0.0, 1.0]), &confirmed, 0.5).is_none());
+    }
+
+    #[test]
+    fn no_suggestion_without_confirmed_faces() {
+        assert!(
+            nearest_person(&Embedding::new(vec![1.0, 0.0]), &[], DEFAULT_MAX_DISTANCE).is_none()
+        );
+    }
+}
@@ -2,15 +2,18 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+pub mod cluster;
 pub mod migrate;
 pub mod model;
 pub mod repo;
 pub mod thumbnailer;
 
+pub use model::Embedding;
 pub use model::Face;
 pub use model::FaceDetectionCandidate;
 pub use model::FaceId;
 pub use model::FaceToMigrate;
+pub use model::MergedPeople;
 pub use model::MigratedFace;
 pub use model::Person;
 pub use model::PersonId;
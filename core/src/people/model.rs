@@ -2,10 +2,13 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use crate::photo::model::PictureId;
+use crate::flatpak_path::{self, Availability};
+use crate::photo::model::{DisplayTransform, PictureId};
 use crate::thumbnailify;
 use chrono::{DateTime, Utc};
 use opencv::core::Mat;
+use rusqlite::ToSql;
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSqlOutput, ValueRef};
 use std::fmt::Display;
 use std::path::PathBuf;
 
@@ -22,6 +25,11 @@ impl FaceDetectionCandidate {
     pub fn thumbnail_hash(&self) -> String {
         thumbnailify::compute_hash_for_path(&self.host_path)
     }
+
+    /// Can the picture be read right now?
+    pub fn availability(&self) -> Availability {
+        flatpak_path::availability(&self.sandbox_path, flatpak_path::AVAILABILITY_TIMEOUT)
+    }
 }
 
 /// Database ID
@@ -33,7 +41,6 @@ impl FaceId {
         Self(id)
     }
 
-    /// FIXME replace this with a To/From SQL implementation.
     pub fn id(&self) -> i64 {
         self.0
     }
@@ -45,10 +52,29 @@ impl Display for FaceId {
     }
 }
 
+impl ToSql for FaceId {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        self.0.to_sql()
+    }
+}
+
+impl FromSql for FaceId {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        i64::column_result(value).map(Self)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Person {
     pub person_id: PersonId,
     pub name: String,
+
+    /// Hidden from the people album by the user.
+    pub is_ignored: bool,
+
+    /// Face shown as the person's avatar, if one has been chosen.
+    pub cover_face_id: Option<FaceId>,
+
     pub small_thumbnail_path: Option<PathBuf>,
     pub large_thumbnail_path: Option<PathBuf>,
 }
@@ -67,8 +93,28 @@ impl Person {
     }
 }
 
+/// A person merged into another, with what is needed to reverse the merge.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergedPeople {
+    /// Person that was kept.
+    pub keep: PersonId,
+
+    /// Time of last recognition of the kept person, before the merge reset it.
+    pub keep_recognized_at: DateTime<Utc>,
+
+    /// Person that was merged into the kept person and then deleted.
+    pub absorbed: PersonId,
+    pub absorbed_name: String,
+    pub absorbed_is_ignored: bool,
+    pub absorbed_recognized_at: DateTime<Utc>,
+
+    /// Faces moved from the absorbed person to the kept person, and whether
+    /// each face was the thumbnail of the absorbed person.
+    pub faces: Vec<(FaceId, bool)>,
+}
+
 /// Database ID
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct PersonId(i64);
 
 impl PersonId {
@@ -76,7 +122,6 @@ impl PersonId {
         Self(id)
     }
 
-    /// FIXME replace this with a To/From SQL implementation.
     pub fn id(&self) -> i64 {
         self.0
     }
@@ -88,7 +133,19 @@ impl Display for PersonId {
     }
 }
 
-#[derive(Debug, Clone)]
+impl ToSql for PersonId {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        self.0.to_sql()
+    }
+}
+
+impl FromSql for PersonId {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        i64::column_result(value).map(Self)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Rect {
     pub x: f32,
     pub y: f32,
@@ -105,6 +162,65 @@ impl Rect {
             height: self.height * ratio,
         }
     }
+
+    /// Takes a rectangle, as fractions of an image the right way up, to the same
+    /// rectangle in the image as stored, before `transform` is applied to display it.
+    pub fn untransform(&self, transform: &DisplayTransform) -> Rect {
+        // Undo the clockwise rotation, then the mirroring.
+        let unrotate = |(x, y): (f32, f32)| match transform.rotation {
+            90 => (y, 1.0 - x),
+            180 => (1.0 - x, 1.0 - y),
+            270 => (1.0 - y, x),
+            _ => (x, y),
+        };
+        let unmirror = |(x, y): (f32, f32)| {
+            if transform.is_mirrored {
+                (1.0 - x, y)
+            } else {
+                (x, y)
+            }
+        };
+
+        let (x1, y1) = unmirror(unrotate((self.x, self.y)));
+        let (x2, y2) = unmirror(unrotate((self.x + self.width, self.y + self.height)));
+
+        Rect {
+            x: f32::min(x1, x2),
+            y: f32::min(y1, y2),
+            width: (x2 - x1).abs(),
+            height: (y2 - y1).abs(),
+        }
+    }
+
+    /// Square for cropping a face thumbnail from an image.
+    /// Face detection bounds are pretty tight, so a margin captures more of the head.
+    /// The square is centred on `centre` and its edges are the longest edge of these bounds,
+    /// expanded by `margin` as a fraction of that edge. If the square would extend past
+    /// the edges of the image, it is shrunk to fit while staying centred.
+    pub fn head_square(
+        &self,
+        centre: (f32, f32),
+        margin: f32,
+        image_width: f32,
+        image_height: f32,
+    ) -> Rect {
+        let (centre_x, centre_y) = centre;
+
+        let edge = f32::max(self.width, self.height) * (1.0 + margin.max(0.0));
+        let half_edge = (edge / 2.0)
+            .min(centre_x)
+            .min(centre_y)
+            .min(image_width - centre_x)
+            .min(image_height - centre_y)
+            .max(0.0);
+
+        Rect {
+            x: (centre_x - half_edge).max(0.0),
+            y: (centre_y - half_edge).max(0.0),
+            width: half_edge * 2.0,
+            height: half_edge * 2.0,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -114,6 +230,44 @@ pub struct Face {
     /// Path to thumbnail generated from face bounds.
     /// Normalized to be square and expanded to capture the whole head.
     pub thumbnail_path: PathBuf,
+
+    /// Bounds around face in source image.
+    /// See `DetectedFace::is_source_original` for which image that is.
+    pub bounds: Rect,
+
+    /// Are the bounds relative to the original image rather than its x-large thumbnail?
+    pub is_source_original: bool,
+
+    /// Confidence (0.0 to 1.0) that the detected face is actually a face.
+    pub confidence: f32,
+}
+
+impl Face {
+    /// Bounds as fractions (0.0 to 1.0) of the width and height of the picture, whatever
+    /// image the face was detected in. `width` and `height` are of the original picture,
+    /// the right way up.
+    pub fn normalized_bounds(&self, width: u32, height: u32) -> Rect {
+        let longest_edge = u32::max(width, height) as f32;
+
+        // Thumbnails are only ever scaled down, so a picture smaller than the
+        // x-large thumbnail is its own thumbnail.
+        let source_edge = if self.is_source_original {
+            longest_edge
+        } else {
+            f32::min(
+                longest_edge,
+                thumbnailify::ThumbnailSize::XLarge.to_dimension() as f32,
+            )
+        };
+
+        let bounds = self.bounds.clone().scale(longest_edge / source_edge);
+        Rect {
+            x: bounds.x / width as f32,
+            y: bounds.y / height as f32,
+            width: bounds.width / width as f32,
+            height: bounds.height / height as f32,
+        }
+    }
 }
 
 /// A face hat has been detected, containing the appropriate landmarks to perform
@@ -150,6 +304,9 @@ pub struct DetectedFace {
     pub left_mouth_corner: (f32, f32),
 
     pub confidence: f32,
+
+    /// Face recognition features, if computed.
+    pub embedding: Option<Embedding>,
 }
 
 impl DetectedFace {
@@ -205,6 +362,66 @@ impl DetectedFace {
     }
 }
 
+/// Face recognition features for a face. Faces of the same person have embeddings
+/// that point in similar directions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Embedding(Vec<f32>);
+
+impl Embedding {
+    pub fn new(values: Vec<f32>) -> Self {
+        Self(values)
+    }
+
+    pub fn values(&self) -> &[f32] {
+        &self.0
+    }
+
+    /// Cosine distance between two embeddings, from 0.0 for the same direction
+    /// to 2.0 for opposite directions. Embeddings of different lengths, or with
+    /// no magnitude, are treated as being as far apart as possible.
+    pub fn cosine_distance(&self, other: &Embedding) -> f32 {
+        if self.0.len() != other.0.len() {
+            return 2.0;
+        }
+
+        let dot: f32 = self.0.iter().zip(&other.0).map(|(a, b)| a * b).sum();
+        let norm_a = self.0.iter().map(|a| a * a).sum::<f32>().sqrt();
+        let norm_b = other.0.iter().map(|b| b * b).sum::<f32>().sqrt();
+
+        if norm_a == 0.0 || norm_b == 0.0 {
+            return 2.0;
+        }
+
+        1.0 - (dot / (norm_a * norm_b))
+    }
+}
+
+impl ToSql for Embedding {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        let bytes: Vec<u8> = self.0.iter().flat_map(|x| x.to_le_bytes()).collect();
+        Ok(ToSqlOutput::from(bytes))
+    }
+}
+
+impl FromSql for Embedding {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let bytes = value.as_blob()?;
+        if bytes.len() % 4 != 0 {
+            return Err(FromSqlError::InvalidBlobSize {
+                expected_size: bytes.len() - bytes.len() % 4,
+                blob_size: bytes.len(),
+            });
+        }
+
+        let values = bytes
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect();
+
+        Ok(Self(values))
+    }
+}
+
 /// A person to perform face recognition for
 #[derive(Debug, Clone)]
 pub struct PersonForRecognition {
@@ -238,3 +455,280 @@ pub struct MigratedFace {
     pub bounds_path: PathBuf,
     pub thumbnail_path: PathBuf,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    #[test]
+    fn face_id_round_trip() {
+        let con = Connection::open_in_memory().unwrap();
+        let face_id = FaceId::new(42);
+
+        let result: FaceId = con
+            .query_row("SELECT ?1", [face_id], |row| row.get(0))
+            .unwrap();
+
+        assert_eq!(face_id, result);
+    }
+
+    #[test]
+    fn person_id_round_trip() {
+        let con = Connection::open_in_memory().unwrap();
+        let person_id = PersonId::new(7);
+
+        let result: PersonId = con
+            .query_row("SELECT ?1", [person_id], |row| row.get(0))
+            .unwrap();
+
+        assert_eq!(person_id, result);
+    }
+
+    #[test]
+    fn embedding_round_trip() {
+        let con = Connection::open_in_memory().unwrap();
+        let embedding = Embedding::new(vec![0.5, -1.25, 3.0]);
+
+        let result: Embedding = con
+            .query_row("SELECT ?1", [&embedding], |row| row.get(0))
+            .unwrap();
+
+        assert_eq!(embedding, result);
+    }
+
+    #[test]
+    fn cosine_distance_of_same_direction_is_zero() {
+        let a = Embedding::new(vec![1.0, 2.0, 3.0]);
+        let b = Embedding::new(vec![2.0, 4.0, 6.0]);
+        assert!(a.cosine_distance(&b).abs() < 1e-6);
+    }
+
+    #[test]
+    fn cosine_distance_of_orthogonal_is_one() {
+        let a = Embedding::new(vec![1.0, 0.0]);
+        let b = Embedding::new(vec![0.0, 1.0]);
+        assert!((a.cosine_distance(&b) - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn cosine_distance_of_mismatched_lengths_is_max() {
+        let a = Embedding::new(vec![1.0, 0.0]);
+        let b = Embedding::new(vec![1.0, 0.0, 0.0]);
+        assert_eq!(a.cosine_distance(&b), 2.0);
+    }
+
+    fn bounds() -> Rect {
+        Rect {
+            x: 40.0,
+            y: 30.0,
+            width: 20.0,
+            height: 40.0,
+        }
+    }
+
+    #[test]
+    fn head_square_expands_longest_edge() {
+        let square = bounds().head_square((50.0, 50.0), 0.5, 200.0, 200.0);
+        assert_eq!(
+            Rect {
+                x: 20.0,
+                y: 20.0,
+                width: 60.0,
+                height: 60.0
+            },
+            square
+        );
+    }
+
+    #[test]
+    fn head_square_without_margin_is_longest_edge() {
+        let square = bounds().head_square((50.0, 50.0), 0.0, 200.0, 200.0);
+        assert_eq!(40.0, square.width);
+        assert_eq!(40.0, square.height);
+    }
+
+    #[test]
+    fn head_square_shrinks_at_top_left_edges() {
+        // Only 10 pixels between centre and left edge.
+        let square = bounds().head_square((10.0, 50.0), 0.5, 200.0, 200.0);
+        assert_eq!(
+            Rect {
+                x: 0.0,
+                y: 40.0,
+                width: 20.0,
+                height: 20.0
+            },
+            square
+        );
+
+        // Only 5 pixels between centre and top edge.
+        let square = bounds().head_square((50.0, 5.0), 0.5, 200.0, 200.0);
+        assert_eq!(
+            Rect {
+                x: 45.0,
+                y: 0.0,
+                width: 10.0,
+                height: 10.0
+            },
+            square
+        );
+    }
+
+    #[test]
+    fn head_square_shrinks_at_bottom_right_edges() {
+        // Only 15 pixels between centre and right edge.
+        let square = bounds().head_square((85.0, 50.0), 0.5, 100.0, 200.0);
+        assert_eq!(
+            Rect {
+                x: 70.0,
+                y: 35.0,
+                width: 30.0,
+                height: 30.0
+            },
+            square
+        );
+
+        // Only 12 pixels between centre and bottom edge.
+        let square = bounds().head_square((50.0, 88.0), 0.5, 200.0, 100.0);
+        assert_eq!(
+            Rect {
+                x: 38.0,
+                y: 76.0,
+                width: 24.0,
+                height: 24.0
+            },
+            square
+        );
+    }
+
+    #[test]
+    fn head_square_with_centre_outside_image_is_empty() {
+        let square = bounds().head_square((-5.0, 50.0), 0.5, 200.0, 200.0);
+        assert_eq!(0.0, square.width);
+        assert_eq!(0.0, square.x);
+    }
+
+    fn face(bounds: Rect, is_source_original: bool) -> Face {
+        Face {
+            face_id: FaceId::new(1),
+            thumbnail_path: PathBuf::from("face.png"),
+            bounds,
+            is_source_original,
+            confidence: 1.0,
+        }
+    }
+
+    #[test]
+    fn normalized_bounds_scale_up_from_thumbnail() {
+        // x-large thumbnail of a 2048x1024 picture is 512x256.
+        let face = face(
+            Rect {
+                x: 128.0,
+                y: 64.0,
+                width: 64.0,
+                height: 32.0,
+            },
+            false,
+        );
+        assert_eq!(
+            Rect {
+                x: 0.25,
+                y: 0.25,
+                width: 0.125,
+                height: 0.125
+            },
+            face.normalized_bounds(2048, 1024)
+        );
+    }
+
+    #[test]
+    fn normalized_bounds_of_original_are_not_scaled() {
+        let bounds = Rect {
+            x: 512.0,
+            y: 256.0,
+            width: 256.0,
+            height: 128.0,
+        };
+        assert_eq!(
+            face(bounds.clone(), true).normalized_bounds(2048, 1024),
+            face(bounds.scale(0.25), false).normalized_bounds(2048, 1024)
+        );
+
+        // Picture smaller than an x-large thumbnail is its own thumbnail.
+        let small = face(
+            Rect {
+                x: 100.0,
+                y: 50.0,
+                width: 100.0,
+                height: 50.0,
+            },
+            false,
+        );
+        assert_eq!(
+            Rect {
+                x: 0.25,
+                y: 0.25,
+                width: 0.25,
+                height: 0.25
+            },
+            small.normalized_bounds(400, 200)
+        );
+    }
+
+    #[test]
+    fn untransform_rotated() {
+        let rect = Rect {
+            x: 0.25,
+            y: 0.5,
+            width: 0.25,
+            height: 0.125,
+        };
+        let transform = DisplayTransform {
+            rotation: 90,
+            is_mirrored: false,
+        };
+        assert_eq!(
+            Rect {
+                x: 0.5,
+                y: 0.5,
+                width: 0.125,
+                height: 0.25
+            },
+            rect.untransform(&transform)
+        );
+    }
+
+    #[test]
+    fn untransform_mirrored() {
+        let rect = Rect {
+            x: 0.25,
+            y: 0.5,
+            width: 0.25,
+            height: 0.125,
+        };
+        let transform = DisplayTransform {
+            rotation: 0,
+            is_mirrored: true,
+        };
+        assert_eq!(
+            Rect {
+                x: 0.5,
+                y: 0.5,
+                width: 0.25,
+                height: 0.125
+            },
+            rect.untransform(&transform)
+        );
+        assert_eq!(rect, rect.untransform(&DisplayTransform::default()));
+    }
+
+    #[test]
+    fn null_is_not_a_face_id() {
+        let con = Connection::open_in_memory().unwrap();
+
+        let result: rusqlite::Result<FaceId> = con.query_row("SELECT NULL", [], |row| row.get(0));
+
+        assert!(result.is_err());
+    }
+}
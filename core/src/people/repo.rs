@@ -2,27 +2,45 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use crate::database;
 use crate::photo::model::PictureId;
 
-use crate::machine_learning::face_extractor;
+use crate::machine_learning::face_extractor::{self, FaceDetectionModel};
 use crate::path_encoding;
 use crate::people::FaceId;
 use crate::people::FaceToMigrate;
 use crate::people::MigratedFace;
 use crate::people::PersonId;
+use crate::people::cluster;
 use crate::people::model;
 use crate::people::model::PersonForRecognition;
 use crate::people::model::Rect;
 
 use anyhow::*;
 use rusqlite;
+use rusqlite::OptionalExtension;
 use rusqlite::Row;
 use rusqlite::params;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 use std::result::Result::Ok;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, PoisonError};
 use tracing::warn;
 
+/// Unknown faces, with the detection model that found them, as clustered by
+/// `unconfirmed_clusters`.
+type UnknownFaces = Vec<(String, FaceId, model::Embedding)>;
+
+/// Clusters from the last call to `unconfirmed_clusters` and what they were made from.
+/// Clustering is quadratic in the number of unknown faces, so it is only redone when
+/// the faces or the settings change.
+#[derive(Debug)]
+struct ClusterCache {
+    faces: UnknownFaces,
+    max_distance: f32,
+    clusters: Vec<Vec<FaceId>>,
+}
+
 /// Repository of people data.
 /// Repository is backed by a Sqlite database.
 #[derive(Debug, Clone)]
@@ -35,6 +53,9 @@ pub struct Repository {
 
     /// Connection to backing Sqlite database.
     con: Arc<Mutex<rusqlite::Connection>>,
+
+    /// Most recent clusters of unknown faces. Shared by clones of the repository.
+    clusters: Arc<Mutex<Option<ClusterCache>>>,
 }
 
 impl Repository {
@@ -51,6 +72,7 @@ impl Repository {
             cache_dir_base_path,
             data_dir_base_path,
             con,
+            clusters: Arc::default(),
         };
 
         Ok(repo)
@@ -60,7 +82,7 @@ impl Repository {
     /// We must delete before re-scanning a picture for faces to avoid a unique constraint
     /// violation on the bounds_path.
     pub fn delete_faces(&self, picture_id: PictureId) -> Result<()> {
-        let con = self.con.lock().unwrap();
+        let con = database::lock(&self.con);
         let mut stmt = con.prepare(
             "DELETE FROM pictures_faces
             WHERE pictures_faces.picture_id = ?1",
@@ -72,29 +94,43 @@ impl Repository {
     }
 
     /// Finds faces and people for the thumbnail bar.
+    /// Faces with a detection confidence below `min_confidence` are excluded.
     /// Faces are ordered from left to right, top to bottom.
     pub fn find_faces(
         &self,
         picture_id: &PictureId,
+        min_confidence: f32,
     ) -> Result<Vec<(model::Face, Option<model::Person>)>> {
-        let con = self.con.lock().unwrap();
+        let con = database::lock(&self.con);
         let mut stmt = con.prepare(
             "SELECT
                 faces.face_id AS face_id,
                 faces.thumbnail_path AS face_thumbnail_path,
+                faces.bounds_x AS bounds_x,
+                faces.bounds_y AS bounds_y,
+                faces.bounds_width AS bounds_width,
+                faces.bounds_height AS bounds_height,
+                faces.is_source_original AS is_source_original,
+                faces.confidence AS confidence,
                 people.person_id AS person_id,
                 people.name AS person_name,
-                person_face.thumbnail_path AS person_thumbnail_path
+                people.is_ignored AS person_is_ignored,
+                person_face.thumbnail_path AS person_thumbnail_path,
+                person_face.face_id AS person_cover_face_id
             FROM pictures_faces AS faces
             LEFT OUTER JOIN people USING (person_id)
             LEFT OUTER JOIN pictures_faces AS person_face
                 ON (person_face.person_id = faces.person_id AND person_face.is_thumbnail = TRUE)
-            WHERE faces.picture_id = ?1 AND faces.is_ignored = FALSE
+            WHERE faces.picture_id = ?1
+            AND faces.is_ignored = FALSE
+            AND faces.confidence >= ?2
             ORDER BY faces.nose_x ASC, faces.nose_y ASC",
         )?;
 
         let result = stmt
-            .query_map([picture_id.id()], |row| self.to_face_and_person(row))?
+            .query_map(params![picture_id, min_confidence], |row| {
+                self.to_face_and_person(row)
+            })?
             .flatten()
             .collect();
 
@@ -102,7 +138,7 @@ impl Repository {
     }
 
     pub fn ignore_unknown_faces(&mut self, picture_id: PictureId) -> Result<()> {
-        let mut con = self.con.lock().unwrap();
+        let mut con = database::lock(&self.con);
         let tx = con.transaction()?;
 
         {
@@ -120,7 +156,7 @@ impl Repository {
     }
 
     pub fn restore_ignored_faces(&mut self, picture_id: PictureId) -> Result<()> {
-        let mut con = self.con.lock().unwrap();
+        let mut con = database::lock(&self.con);
         let tx = con.transaction()?;
 
         {
@@ -138,16 +174,18 @@ impl Repository {
     }
 
     pub fn get_person(&self, person_id: PersonId) -> Result<Option<model::Person>> {
-        let con = self.con.lock().unwrap();
+        let con = database::lock(&self.con);
         let mut stmt = con.prepare(
             "SELECT
                 p.person_id AS person_id,
                 p.name AS person_name,
-                f.thumbnail_path AS person_thumbnail_path
+                p.is_ignored AS person_is_ignored,
+                f.thumbnail_path AS person_thumbnail_path,
+                f.face_id AS person_cover_face_id
             FROM people AS p
             LEFT OUTER JOIN pictures_faces AS f
                 ON (f.person_id = p.person_id AND f.is_thumbnail = TRUE)
-            WHERE person_id = ?1",
+            WHERE p.person_id = ?1",
         )?;
 
         let result: Option<model::Person> = stmt
@@ -158,14 +196,18 @@ impl Repository {
         Ok(result)
     }
 
+    /// Deletes a person. The person's faces are kept, but become unknown faces again.
     pub fn delete_person(&mut self, person_id: PersonId) -> Result<()> {
-        let mut con = self.con.lock().unwrap();
+        let mut con = database::lock(&self.con);
         let tx = con.transaction()?;
 
         {
+            // NOTE: don't rely on the ON DELETE SET NULL foreign key action because SQLite
+            // only applies it if foreign keys are enabled for the connection.
             let mut stmt = tx.prepare_cached(
                 "UPDATE pictures_faces
                 SET
+                    person_id = NULL,
                     is_confirmed = FALSE,
                     is_thumbnail = FALSE
                 WHERE person_id = ?1",
@@ -180,8 +222,33 @@ impl Repository {
         Ok(())
     }
 
+    /// Hides or shows a person in the people album.
+    /// Faces of an ignored person stay assigned to them, so face recognition won't
+    /// present them as unknown faces again.
+    pub fn set_person_ignored(&mut self, person_id: PersonId, is_ignored: bool) -> Result<()> {
+        let mut con = database::lock(&self.con);
+        let tx = con.transaction()?;
+
+        {
+            let mut stmt = tx.prepare_cached(
+                "UPDATE people
+                SET
+                    is_ignored = ?2
+                WHERE person_id = ?1",
+            )?;
+            stmt.execute(params![person_id, is_ignored])?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Renames a person. Leading and trailing whitespace is removed from the name
+    /// and an empty name is rejected. Names need not be unique.
     pub fn rename_person(&mut self, person_id: PersonId, name: &str) -> Result<()> {
-        let mut con = self.con.lock().unwrap();
+        let name = validate_name(name)?;
+
+        let mut con = database::lock(&self.con);
         let tx = con.transaction()?;
 
         {
@@ -198,21 +265,134 @@ impl Repository {
         Ok(())
     }
 
-    pub fn all_people(&self) -> Result<Vec<model::Person>> {
-        let con = self.con.lock().unwrap();
+    /// Merges two people that are really the same person.
+    /// All faces of `absorb` are reassigned to `keep` and then `absorb` is deleted.
+    /// Returns what was merged, so that the merge can be undone.
+    pub fn merge_people(
+        &mut self,
+        keep: PersonId,
+        absorb: PersonId,
+    ) -> Result<model::MergedPeople> {
+        if keep == absorb {
+            bail!("Cannot merge person {} into themselves", keep);
+        }
+
+        let mut con = database::lock(&self.con);
+        let tx = con.transaction()?;
+
+        let merged = {
+            let mut stmt =
+                tx.prepare_cached("SELECT COUNT(*) FROM people WHERE person_id IN (?1, ?2)")?;
+            let count: usize = stmt.query_row(params![keep, absorb], |row| row.get(0))?;
+            if count != 2 {
+                bail!(
+                    "Cannot merge person {} into {} because one does not exist",
+                    absorb,
+                    keep
+                );
+            }
+
+            let mut stmt =
+                tx.prepare_cached("SELECT recognized_at FROM people WHERE person_id = ?1")?;
+            let keep_recognized_at = stmt.query_row(params![keep], |row| row.get(0))?;
+
+            let mut stmt = tx.prepare_cached(
+                "SELECT name, is_ignored, recognized_at FROM people WHERE person_id = ?1",
+            )?;
+            let (absorbed_name, absorbed_is_ignored, absorbed_recognized_at) = stmt
+                .query_row(params![absorb], |row| {
+                    Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+                })?;
+
+            let mut stmt = tx.prepare_cached(
+                "SELECT face_id, is_thumbnail FROM pictures_faces WHERE person_id = ?1",
+            )?;
+            let faces = stmt
+                .query_map(params![absorb], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<rusqlite::Result<Vec<(FaceId, bool)>>>()?;
+
+            // Keep the thumbnail of the person being kept.
+            let mut stmt = tx.prepare_cached(
+                "UPDATE pictures_faces
+                SET
+                    person_id = ?1,
+                    is_thumbnail = FALSE
+                WHERE person_id = ?2",
+            )?;
+            stmt.execute(params![keep, absorb])?;
+
+            let mut stmt = tx.prepare_cached("DELETE FROM people WHERE person_id = ?1")?;
+            stmt.execute(params![absorb])?;
+
+            // Kept person has gained confirmed faces, so redo face recognition.
+            let mut stmt = tx.prepare_cached(
+                "UPDATE people
+                SET
+                    recognized_at = '1970-01-01 00:00:00'
+                WHERE person_id = ?1",
+            )?;
+            stmt.execute(params![keep])?;
+
+            model::MergedPeople {
+                keep,
+                keep_recognized_at,
+                absorbed: absorb,
+                absorbed_name,
+                absorbed_is_ignored,
+                absorbed_recognized_at,
+                faces,
+            }
+        };
+
+        tx.commit()?;
+        Ok(merged)
+    }
+
+    /// All known people, ordered by name.
+    /// Ignored people are excluded unless `include_ignored` is set.
+    pub fn all_people(&self, include_ignored: bool) -> Result<Vec<model::Person>> {
+        let con = database::lock(&self.con);
         let mut stmt = con.prepare(
             "SELECT
                 p.person_id AS person_id,
                 p.name AS person_name,
-                f.thumbnail_path AS person_thumbnail_path
+                p.is_ignored AS person_is_ignored,
+                f.thumbnail_path AS person_thumbnail_path,
+                f.face_id AS person_cover_face_id
             FROM people AS p
             LEFT OUTER JOIN pictures_faces AS f
                 ON (f.person_id = p.person_id AND f.is_thumbnail = TRUE)
+            WHERE ?1 OR p.is_ignored = FALSE
             ORDER BY name ASC",
         )?;
 
         let result: Vec<model::Person> = stmt
-            .query_map([], |row| self.to_person(row))?
+            .query_map([include_ignored], |row| self.to_person(row))?
+            .flatten()
+            .collect();
+
+        Ok(result)
+    }
+
+    /// Number of faces of each known person, counting only faces detected with at
+    /// least `min_confidence`. People without any such faces are omitted.
+    pub fn face_counts(&self, min_confidence: f32) -> Result<HashMap<PersonId, usize>> {
+        let con = database::lock(&self.con);
+        let mut stmt = con.prepare(
+            "SELECT
+                person_id,
+                COUNT(*) AS face_count
+            FROM pictures_faces
+            WHERE person_id IS NOT NULL
+            AND is_ignored = FALSE
+            AND confidence >= ?1
+            GROUP BY person_id",
+        )?;
+
+        let result = stmt
+            .query_map([min_confidence], |row| {
+                std::result::Result::Ok((row.get("person_id")?, row.get("face_count")?))
+            })?
             .flatten()
             .collect();
 
@@ -223,7 +403,7 @@ impl Repository {
     /// Select the best face for recognition, where "best" is the face with
     /// the highest confidence for a face that the user has confirmed is a particular person.
     pub fn find_people_for_recognition(&self) -> Result<Vec<model::PersonForRecognition>> {
-        let con = self.con.lock().unwrap();
+        let con = database::lock(&self.con);
 
         // NOTE: this is non-standard SQL that might not work in DBs that aren't SQLite.
         let mut stmt = con.prepare(
@@ -259,6 +439,8 @@ impl Repository {
                 left_mouth_corner_x,
                 left_mouth_corner_y,
 
+                embedding,
+
                 max(confidence) AS confidence
             FROM  pictures_faces AS faces
             INNER JOIN people USING (person_id)
@@ -277,7 +459,7 @@ impl Repository {
     /// Find new faces as candidates for face recognition for a given person.
     /// Only returns faces that haven't been recognized before for the person.
     pub fn find_unknown_faces(&self) -> Result<Vec<model::DetectedFace>> {
-        let con = self.con.lock().unwrap();
+        let con = database::lock(&self.con);
 
         // NOTE: this is non-standard SQL that might not work in DBs that aren't SQLite.
         let mut stmt = con.prepare(
@@ -310,6 +492,8 @@ impl Repository {
                 left_mouth_corner_x,
                 left_mouth_corner_y,
 
+                embedding,
+
                 confidence
             FROM  pictures_faces AS faces
             WHERE faces.person_id IS NULL
@@ -324,18 +508,306 @@ impl Repository {
         Ok(result)
     }
 
+    /// Finds unknown faces that don't yet have an embedding for clustering.
+    pub fn find_faces_without_embedding(&self) -> Result<Vec<model::DetectedFace>> {
+        Ok(self
+            .find_unknown_faces()?
+            .into_iter()
+            .filter(|face| face.embedding.is_none())
+            .collect())
+    }
+
+    /// Stores face recognition features for a face.
+    pub fn set_face_embedding(
+        &mut self,
+        face_id: FaceId,
+        embedding: &model::Embedding,
+    ) -> Result<()> {
+        let mut con = database::lock(&self.con);
+        let tx = con.transaction()?;
+
+        {
+            let mut stmt = tx.prepare_cached(
+                "UPDATE pictures_faces
+                SET
+                    embedding = ?2
+                WHERE face_id = ?1",
+            )?;
+            stmt.execute(params![face_id, embedding])?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Groups unknown faces that look like the same person, as suggestions of
+    /// people for the user to name. Faces are in the same cluster if their embeddings
+    /// are within a cosine distance of `max_distance`, and were found by the same
+    /// detection model.
+    /// Faces with a detection confidence below `min_confidence` are excluded.
+    /// Clusters are remembered until the unknown faces or the settings change.
+    pub fn unconfirmed_clusters(
+        &self,
+        max_distance: f32,
+        min_confidence: f32,
+    ) -> Result<Vec<Vec<FaceId>>> {
+        let faces: UnknownFaces = {
+            let con = database::lock(&self.con);
+            let mut stmt = con.prepare(
+                "SELECT
+                    detection_model,
+                    face_id,
+                    embedding
+                FROM pictures_faces
+                WHERE person_id IS NULL
+                AND is_ignored = FALSE
+                AND embedding IS NOT NULL
+                AND confidence >= ?1
+                ORDER BY detected_at ASC",
+            )?;
+
+            stmt.query_map([min_confidence], |row| {
+                std::result::Result::Ok((
+                    row.get("detection_model")?,
+                    row.get("face_id")?,
+                    row.get("embedding")?,
+                ))
+            })?
+            .flatten()
+            .collect()
+        };
+
+        // Don't hold the database lock while clustering.
+        let mut cache = self.clusters.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let is_cached =
+            |cache: &&ClusterCache| cache.max_distance == max_distance && cache.faces == faces;
+        if let Some(cache) = cache.as_ref().filter(is_cached) {
+            return Ok(cache.clusters.clone());
+        }
+
+        // Embeddings from different models aren't comparable, so cluster each model apart.
+        let mut faces_by_model: BTreeMap<&str, Vec<(FaceId, model::Embedding)>> = BTreeMap::new();
+        for (detection_model, face_id, embedding) in &faces {
+            faces_by_model
+                .entry(detection_model.as_str())
+                .or_default()
+                .push((*face_id, embedding.clone()));
+        }
+
+        let clusters: Vec<Vec<FaceId>> = faces_by_model
+            .values()
+            .flat_map(|faces| cluster::cluster(faces, max_distance))
+            .collect();
+
+        *cache = Some(ClusterCache {
+            faces,
+            max_distance,
+            clusters: clusters.clone(),
+        });
+
+        Ok(clusters)
+    }
+
+    /// Suggests a person for an unknown face: the person with a confirmed face most like it,
+    /// found by the same detection model.
+    /// Returns the person and a score from 1.0 for an identical face to 0.0 for a face at
+    /// `max_distance`. Returns `None` if the face already has a person, has no embedding,
+    /// or isn't within `max_distance` of any confirmed face.
+    pub fn suggest_person_for_face(
+        &self,
+        face_id: FaceId,
+        max_distance: f32,
+    ) -> Result<Option<(PersonId, f32)>> {
+        let (embedding, confirmed) = {
+            let con = database::lock(&self.con);
+
+            let mut stmt = con.prepare(
+                "SELECT
+                    detection_model,
+                    embedding
+                FROM pictures_faces
+                WHERE face_id = ?1
+                AND person_id IS NULL
+                AND embedding IS NOT NULL",
+            )?;
+
+            let face: Option<(String, model::Embedding)> = stmt
+                .query_row([face_id], |row| {
+                    std::result::Result::Ok((row.get("detection_model")?, row.get("embedding")?))
+                })
+                .optional()?;
+
+            let Some((detection_model, embedding)) = face else {
+                return Ok(None);
+            };
+
+            let mut stmt = con.prepare(
+                "SELECT
+                    person_id,
+                    embedding
+                FROM pictures_faces
+                WHERE person_id IS NOT NULL
+                AND is_confirmed = TRUE
+                AND embedding IS NOT NULL
+                AND detection_model = ?1",
+            )?;
+
+            let confirmed: Vec<(PersonId, model::Embedding)> = stmt
+                .query_map([detection_model], |row| {
+                    std::result::Result::Ok((row.get("person_id")?, row.get("embedding")?))
+                })?
+                .flatten()
+                .collect();
+
+            (embedding, confirmed)
+        };
+
+        // Don't hold the lock while comparing.
+        Ok(cluster::nearest_person(
+            &embedding,
+            &confirmed,
+            max_distance,
+        ))
+    }
+
+    /// Names a whole cluster of faces as a person, confirming the suggestion.
+    pub fn confirm_cluster(&mut self, face_ids: &[FaceId], person_id: PersonId) -> Result<()> {
+        let mut con = database::lock(&self.con);
+        let tx = con.transaction()?;
+
+        {
+            let mut stmt = tx.prepare_cached(
+                "UPDATE pictures_faces
+                SET
+                    person_id = ?2,
+                    is_confirmed = TRUE
+                WHERE face_id = ?1
+                AND person_id IS NULL",
+            )?;
+
+            for face_id in face_ids {
+                stmt.execute(params![face_id, person_id])?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Names the unknown face in each of the given pictures as a person.
+    /// A picture is skipped unless it has exactly one unknown face, because otherwise
+    /// it isn't known which face is the person.
+    /// Returns the number of pictures assigned.
+    pub fn assign_pictures_to_person(
+        &mut self,
+        picture_ids: &[PictureId],
+        person_id: PersonId,
+    ) -> Result<usize> {
+        let mut con = database::lock(&self.con);
+        let tx = con.transaction()?;
+
+        let mut assigned = 0;
+
+        {
+            let mut stmt = tx.prepare_cached(
+                "UPDATE pictures_faces
+                SET
+                    person_id = ?2,
+                    is_confirmed = TRUE
+                WHERE picture_id = ?1
+                AND person_id IS NULL
+                AND is_ignored = FALSE
+                AND (
+                    SELECT COUNT(*)
+                    FROM pictures_faces AS unknown
+                    WHERE unknown.picture_id = ?1
+                    AND unknown.person_id IS NULL
+                    AND unknown.is_ignored = FALSE
+                ) = 1",
+            )?;
+
+            for picture_id in picture_ids {
+                assigned += stmt.execute(params![picture_id.id(), person_id.id()])?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(assigned)
+    }
+
+    /// Finds a single face.
+    pub fn get_face(&self, face_id: FaceId) -> Result<Option<model::Face>> {
+        let con = database::lock(&self.con);
+        let mut stmt = con.prepare(
+            "SELECT
+                face_id,
+                thumbnail_path AS face_thumbnail_path,
+                bounds_x,
+                bounds_y,
+                bounds_width,
+                bounds_height,
+                is_source_original,
+                confidence
+            FROM pictures_faces
+            WHERE face_id = ?1",
+        )?;
+
+        let result = stmt
+            .query_map([face_id], |row| self.to_face(row))?
+            .flatten()
+            .nth(0);
+
+        Ok(result)
+    }
+
+    /// Finds all faces detected in a picture, whatever their confidence, such as to
+    /// outline them over the picture. Faces are ordered from left to right.
+    pub fn faces_in_picture(&self, picture_id: PictureId) -> Result<Vec<model::Face>> {
+        let con = database::lock(&self.con);
+        let mut stmt = con.prepare(
+            "SELECT
+                face_id,
+                thumbnail_path AS face_thumbnail_path,
+                bounds_x,
+                bounds_y,
+                bounds_width,
+                bounds_height,
+                is_source_original,
+                confidence
+            FROM pictures_faces
+            WHERE picture_id = ?1
+            AND is_ignored = FALSE
+            ORDER BY nose_x ASC, nose_y ASC",
+        )?;
+
+        let result = stmt
+            .query_map([picture_id], |row| self.to_face(row))?
+            .flatten()
+            .collect();
+
+        Ok(result)
+    }
+
     /// Finds all pictures that feature a known person.
-    pub fn find_pictures_for_person(&self, person_id: PersonId) -> Result<Vec<PictureId>> {
-        let con = self.con.lock().unwrap();
+    /// Pictures where the person's face was detected with a confidence below
+    /// `min_confidence` are excluded.
+    pub fn find_pictures_for_person(
+        &self,
+        person_id: PersonId,
+        min_confidence: f32,
+    ) -> Result<Vec<PictureId>> {
+        let con = database::lock(&self.con);
         let mut stmt = con.prepare(
             "SELECT DISTINCT
                 picture_id
             FROM  pictures_faces
-            WHERE person_id == ?1",
+            WHERE person_id == ?1
+            AND confidence >= ?2",
         )?;
 
         let result: Vec<PictureId> = stmt
-            .query_map([person_id.id()], |row| {
+            .query_map(params![person_id, min_confidence], |row| {
                 row.get("picture_id").map(PictureId::new)
             })?
             .flatten()
@@ -344,9 +816,62 @@ impl Repository {
         Ok(result)
     }
 
+    /// Finds all faces assigned to a person, whether confirmed by the user or not.
+    /// Faces are assigned with `mark_as_person` and unassigned with `unassign_face`.
+    pub fn faces_for_person(&self, person_id: PersonId) -> Result<Vec<model::Face>> {
+        let con = database::lock(&self.con);
+        let mut stmt = con.prepare(
+            "SELECT
+                face_id,
+                thumbnail_path AS face_thumbnail_path,
+                bounds_x,
+                bounds_y,
+                bounds_width,
+                bounds_height,
+                is_source_original,
+                confidence
+            FROM pictures_faces
+            WHERE person_id = ?1
+            AND is_ignored = FALSE
+            ORDER BY detected_at ASC",
+        )?;
+
+        let result = stmt
+            .query_map([person_id], |row| self.to_face(row))?
+            .flatten()
+            .collect();
+
+        Ok(result)
+    }
+
+    /// Finds the person a face is assigned to, if any.
+    pub fn person_for_face(&self, face_id: FaceId) -> Result<Option<model::Person>> {
+        let con = database::lock(&self.con);
+        let mut stmt = con.prepare(
+            "SELECT
+                p.person_id AS person_id,
+                p.name AS person_name,
+                p.is_ignored AS person_is_ignored,
+                f.thumbnail_path AS person_thumbnail_path,
+                f.face_id AS person_cover_face_id
+            FROM pictures_faces AS face
+            INNER JOIN people AS p USING (person_id)
+            LEFT OUTER JOIN pictures_faces AS f
+                ON (f.person_id = p.person_id AND f.is_thumbnail = TRUE)
+            WHERE face.face_id = ?1",
+        )?;
+
+        let result: Option<model::Person> = stmt
+            .query_map([face_id], |row| self.to_person(row))?
+            .flatten()
+            .nth(0);
+
+        Ok(result)
+    }
+
     // FIXME probably need a mechanism to undo this in the likely event of user error.
     pub fn mark_ignore(&mut self, face_id: FaceId) -> Result<()> {
-        let mut con = self.con.lock().unwrap();
+        let mut con = database::lock(&self.con);
         let tx = con.transaction()?;
 
         {
@@ -367,8 +892,47 @@ impl Repository {
         Ok(())
     }
 
-    pub fn mark_face_scan_broken(&mut self, picture_id: &PictureId) -> Result<()> {
-        let mut con = self.con.lock().unwrap();
+    /// Forgets face scans made with other detection models, so those pictures are scanned
+    /// again. Pictures with a face confirmed as a person are kept as they are, so that
+    /// people aren't lost. Returns the number of pictures to scan again.
+    pub fn reset_face_scans(&mut self, detection_model: FaceDetectionModel) -> Result<usize> {
+        let mut con = database::lock(&self.con);
+        let tx = con.transaction()?;
+
+        let count = {
+            let mut stmt = tx.prepare_cached(
+                "DELETE FROM pictures_faces
+                WHERE picture_id IN (
+                    SELECT picture_id
+                    FROM pictures_face_scans
+                    WHERE detection_model != ?1
+                    AND picture_id NOT IN (
+                        SELECT picture_id FROM pictures_faces WHERE is_confirmed = TRUE
+                    )
+                )",
+            )?;
+            stmt.execute([detection_model.as_ref()])?;
+
+            let mut stmt = tx.prepare_cached(
+                "DELETE FROM pictures_face_scans
+                WHERE detection_model != ?1
+                AND picture_id NOT IN (
+                    SELECT picture_id FROM pictures_faces WHERE is_confirmed = TRUE
+                )",
+            )?;
+            stmt.execute([detection_model.as_ref()])?
+        };
+
+        tx.commit()?;
+        Ok(count)
+    }
+
+    pub fn mark_face_scan_broken(
+        &mut self,
+        picture_id: &PictureId,
+        detection_model: FaceDetectionModel,
+    ) -> Result<()> {
+        let mut con = database::lock(&self.con);
         let tx = con.transaction()?;
 
         {
@@ -377,17 +941,19 @@ impl Repository {
                     picture_id,
                     is_broken,
                     face_count,
-                    scan_ts
+                    scan_ts,
+                    detection_model
                 ) VALUES (
-                    ?1, TRUE, 0, CURRENT_TIMESTAMP
+                    ?1, TRUE, 0, CURRENT_TIMESTAMP, ?2
                 ) ON CONFLICT (picture_id) DO UPDATE SET
                     is_broken = true,
                     face_count = 0,
-                    scan_ts = CURRENT_TIMESTAMP
+                    scan_ts = CURRENT_TIMESTAMP,
+                    detection_model = ?2
                 ",
             )?;
 
-            stmt.execute(params![picture_id.id(),])?;
+            stmt.execute(params![picture_id.id(), detection_model.as_ref()])?;
         }
 
         tx.commit()?;
@@ -397,9 +963,10 @@ impl Repository {
     pub fn add_face_scans(
         &mut self,
         picture_id: &PictureId,
+        detection_model: FaceDetectionModel,
         faces: &Vec<face_extractor::Face>,
     ) -> Result<()> {
-        let mut con = self.con.lock().unwrap();
+        let mut con = database::lock(&self.con);
         let tx = con.transaction()?;
 
         // Create a scope to make borrowing of tx not be an error.
@@ -409,17 +976,24 @@ impl Repository {
                     picture_id,
                     is_broken,
                     face_count,
-                    scan_ts
+                    scan_ts,
+                    detection_model
                 ) VALUES (
-                    ?1, ?2, ?3, CURRENT_TIMESTAMP
+                    ?1, ?2, ?3, CURRENT_TIMESTAMP, ?4
                 ) ON CONFLICT (picture_id) DO UPDATE SET
                     is_broken = ?2,
                     face_count = ?3,
-                    scan_ts = CURRENT_TIMESTAMP
+                    scan_ts = CURRENT_TIMESTAMP,
+                    detection_model = ?4
                 ",
             )?;
 
-            scan_insert_stmt.execute(params![picture_id.id(), false, faces.len(),])?;
+            scan_insert_stmt.execute(params![
+                picture_id.id(),
+                false,
+                faces.len(),
+                detection_model.as_ref(),
+            ])?;
 
             let mut face_insert_stmt = tx.prepare_cached(
                 "INSERT INTO pictures_faces (
@@ -451,10 +1025,12 @@ impl Repository {
 
                     confidence,
 
-                    is_ignored
+                    is_ignored,
+
+                    detection_model
                 ) VALUES (
                     ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10,
-                    ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, false
+                    ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, false, ?20
                 )
                 ",
             )?;
@@ -489,7 +1065,8 @@ impl Repository {
                     right_mouth_corner.map(|x| x.1),
                     left_mouth_corner.map(|x| x.0),
                     left_mouth_corner.map(|x| x.1),
-                    face.confidence
+                    face.confidence,
+                    detection_model.as_ref(),
                 ])?;
             }
         }
@@ -500,7 +1077,9 @@ impl Repository {
 
     /// Add a new named person derived from a face.
     pub fn add_person(&mut self, face_id: FaceId, name: &str) -> Result<()> {
-        let mut con = self.con.lock().unwrap();
+        let name = validate_name(name)?;
+
+        let mut con = database::lock(&self.con);
         let tx = con.transaction()?;
 
         {
@@ -542,15 +1121,17 @@ impl Repository {
         Ok(())
     }
 
-    /// User is manually marking a face as a person
+    /// User is manually marking a face as a person.
+    /// A face moved from another person stops being that person's cover.
     pub fn mark_as_person(&mut self, face_id: FaceId, person_id: PersonId) -> Result<()> {
-        let mut con = self.con.lock().unwrap();
+        let mut con = database::lock(&self.con);
         let tx = con.transaction()?;
 
         {
             let mut stmt = tx.prepare_cached(
                 "UPDATE pictures_faces
                 SET
+                    is_thumbnail = (is_thumbnail AND person_id IS ?2),
                     person_id = ?2,
                     is_confirmed = TRUE
                 WHERE face_id = ?1",
@@ -569,7 +1150,7 @@ impl Repository {
         face_id: FaceId,
         person_id: PersonId,
     ) -> Result<()> {
-        let mut con = self.con.lock().unwrap();
+        let mut con = database::lock(&self.con);
         let tx = con.transaction()?;
 
         {
@@ -590,7 +1171,7 @@ impl Repository {
     }
 
     pub fn mark_face_recognition_complete(&mut self, person_id: PersonId) -> Result<()> {
-        let mut con = self.con.lock().unwrap();
+        let mut con = database::lock(&self.con);
         let tx = con.transaction()?;
 
         {
@@ -608,8 +1189,10 @@ impl Repository {
         Ok(())
     }
 
-    pub fn mark_not_person(&mut self, face_id: FaceId) -> Result<()> {
-        let mut con = self.con.lock().unwrap();
+    /// User is removing a face from the person it was assigned to, leaving it unknown.
+    /// If the face was the person's cover, the person is left without a cover.
+    pub fn unassign_face(&mut self, face_id: FaceId) -> Result<()> {
+        let mut con = database::lock(&self.con);
         let tx = con.transaction()?;
 
         {
@@ -630,7 +1213,7 @@ impl Repository {
     }
 
     pub fn set_person_thumbnail(&mut self, person_id: PersonId, face_id: FaceId) -> Result<()> {
-        let mut con = self.con.lock().unwrap();
+        let mut con = database::lock(&self.con);
         let tx = con.transaction()?;
 
         {
@@ -660,21 +1243,39 @@ impl Repository {
         Ok(())
     }
 
-    fn to_face_and_person(
-        &self,
-        row: &Row<'_>,
-    ) -> rusqlite::Result<(model::Face, Option<model::Person>)> {
-        let face_id = row.get("face_id").map(FaceId::new)?;
+    fn to_face(&self, row: &Row<'_>) -> rusqlite::Result<model::Face> {
+        let face_id = row.get("face_id")?;
 
-        let face_thumbnail_path = row
+        let thumbnail_path = row
             .get("face_thumbnail_path")
             .map(|p: String| self.data_dir_base_path.join(p))?;
 
-        let face = model::Face {
-            face_id,
-            thumbnail_path: face_thumbnail_path,
+        let bounds = Rect {
+            x: row.get("bounds_x")?,
+            y: row.get("bounds_y")?,
+            width: row.get("bounds_width")?,
+            height: row.get("bounds_height")?,
         };
 
+        let is_source_original = row.get("is_source_original")?;
+
+        let confidence = row.get("confidence")?;
+
+        std::result::Result::Ok(model::Face {
+            face_id,
+            thumbnail_path,
+            bounds,
+            is_source_original,
+            confidence,
+        })
+    }
+
+    fn to_face_and_person(
+        &self,
+        row: &Row<'_>,
+    ) -> rusqlite::Result<(model::Face, Option<model::Person>)> {
+        let face = self.to_face(row)?;
+
         let person_id = row.get("person_id").map(PersonId::new).ok();
 
         let person_name = row.get("person_name").ok();
@@ -701,9 +1302,15 @@ impl Repository {
                 None
             };
 
+            let is_ignored = row.get("person_is_ignored").unwrap_or(false);
+
+            let cover_face_id = row.get("person_cover_face_id").ok();
+
             Some(model::Person {
                 person_id,
                 name,
+                is_ignored,
+                cover_face_id,
                 small_thumbnail_path: person_thumbnail_path,
                 large_thumbnail_path: large_thumbnail_path,
             })
@@ -719,6 +1326,10 @@ impl Repository {
 
         let name = row.get("person_name")?;
 
+        let is_ignored = row.get("person_is_ignored")?;
+
+        let cover_face_id = row.get("person_cover_face_id")?;
+
         let small_thumbnail_path = row
             .get("person_thumbnail_path")
             .map(|p: String| self.data_dir_base_path.join(p))
@@ -743,6 +1354,8 @@ impl Repository {
         std::result::Result::Ok(model::Person {
             person_id,
             name,
+            is_ignored,
+            cover_face_id,
             small_thumbnail_path,
             large_thumbnail_path,
         })
@@ -787,6 +1400,8 @@ impl Repository {
 
         let is_source_original: bool = row.get("is_source_original")?;
 
+        let embedding = row.get("embedding")?;
+
         let face = model::DetectedFace {
             face_id,
             face_path,
@@ -800,6 +1415,7 @@ impl Repository {
             left_mouth_corner: (left_mouth_corner_x, left_mouth_corner_y),
             confidence,
             detected_at,
+            embedding,
         };
 
         std::result::Result::Ok(face)
@@ -823,7 +1439,7 @@ impl Repository {
     }
 
     pub fn migrate_get_all(&self) -> Result<Vec<FaceToMigrate>> {
-        let con = self.con.lock().unwrap();
+        let con = database::lock(&self.con);
         let mut stmt = con.prepare(
             "SELECT
                 migrate_faces.face_id AS face_id,
@@ -845,7 +1461,7 @@ impl Repository {
     }
 
     pub fn migrate_update_face_paths(&mut self, mf: MigratedFace) -> Result<()> {
-        let mut con = self.con.lock().unwrap();
+        let mut con = database::lock(&self.con);
         let tx = con.transaction()?;
 
         {
@@ -869,7 +1485,7 @@ impl Repository {
     }
 
     pub fn migrate_truncate(&mut self) -> Result<()> {
-        let mut con = self.con.lock().unwrap();
+        let mut con = database::lock(&self.con);
         let tx = con.transaction()?;
         {
             tx.execute("DELETE FROM migrate_faces", [])?;
@@ -907,3 +1523,246 @@ impl Repository {
         std::result::Result::Ok(face)
     }
 }
+
+/// Trims a person's name and checks it isn't empty.
+fn validate_name(name: &str) -> Result<&str> {
+    let name = name.trim();
+    if name.is_empty() {
+        bail!("Person name must not be empty");
+    }
+    Ok(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_name_trims() {
+        assert_eq!("Ada Lovelace", validate_name("  Ada Lovelace\t\n").unwrap());
+    }
+
+    #[test]
+    fn validate_name_rejects_empty() {
+        assert!(validate_name("").is_err());
+    }
+
+    #[test]
+    fn validate_name_rejects_whitespace() {
+        assert!(validate_name(" \t\n ").is_err());
+    }
+
+    /// Adds an unknown face with the same embedding as every other test face.
+    fn add_face(
+        repo: &mut Repository,
+        picture_id: PictureId,
+        index: usize,
+        detection_model: FaceDetectionModel,
+    ) -> FaceId {
+        let face_id = {
+            let con = database::lock(&repo.con);
+            con.execute(
+                "INSERT INTO pictures_faces (
+                    picture_id, model_name, thumbnail_path, bounds_path,
+                    bounds_x, bounds_y, bounds_width, bounds_height,
+                    right_eye_x, right_eye_y, left_eye_x, left_eye_y, nose_x, nose_y,
+                    right_mouth_corner_x, right_mouth_corner_y,
+                    left_mouth_corner_x, left_mouth_corner_y,
+                    confidence, detection_model
+                ) VALUES (
+                    ?1, 'test', ?2, ?3, 0, 0, 10, 10, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0.9, ?4
+                )",
+                params![
+                    picture_id.id(),
+                    format!("thumbnail_{}.png", index),
+                    format!("bounds_{}.png", index),
+                    detection_model.as_ref(),
+                ],
+            )
+            .unwrap();
+            FaceId::new(con.last_insert_rowid())
+        };
+
+        let embedding = model::Embedding::new(vec![1.0, 0.5, 0.25]);
+        repo.set_face_embedding(face_id, &embedding).unwrap();
+        face_id
+    }
+
+    #[test]
+    fn faces_from_different_models_are_not_clustered() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.png");
+        image::RgbImage::new(3, 2).save(&path).unwrap();
+
+        let root = crate::FlatpakPathBuf::build(dir.path(), dir.path());
+        let library_roots = crate::LibraryRoots::build(&root, &[]);
+        let con = Arc::new(Mutex::new(database::setup_in_memory().unwrap()));
+        let mut photo_repo =
+            crate::photo::Repository::open(&library_roots, dir.path(), dir.path(), con.clone())
+                .unwrap();
+        photo_repo
+            .add_all(&vec![crate::ScannedFile::Photo(path)])
+            .unwrap();
+        let picture_id = photo_repo.all().unwrap()[0].picture_id;
+
+        let mut repo = Repository::open(dir.path(), dir.path(), con).unwrap();
+        let fast_1 = add_face(&mut repo, picture_id, 1, FaceDetectionModel::Fast);
+        let accurate_1 = add_face(&mut repo, picture_id, 2, FaceDetectionModel::Accurate);
+        let fast_2 = add_face(&mut repo, picture_id, 3, FaceDetectionModel::Fast);
+        let accurate_2 = add_face(&mut repo, picture_id, 4, FaceDetectionModel::Accurate);
+
+        let mut clusters = repo.unconfirmed_clusters(0.1, 0.5).unwrap();
+        clusters
+            .iter_mut()
+            .for_each(|cluster| cluster.sort_by_key(|face_id| face_id.id()));
+        clusters.sort_by_key(|cluster| cluster[0].id());
+
+        assert_eq!(
+            vec![vec![fast_1, fast_2], vec![accurate_1, accurate_2]],
+            clusters
+        );
+    }
+
+    #[test]
+    fn unconfirmed_clusters_follow_changes_to_faces() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.png");
+        image::RgbImage::new(3, 2).save(&path).unwrap();
+
+        let root = crate::FlatpakPathBuf::build(dir.path(), dir.path());
+        let library_roots = crate::LibraryRoots::build(&root, &[]);
+        let con = Arc::new(Mutex::new(database::setup_in_memory().unwrap()));
+        let mut photo_repo =
+            crate::photo::Repository::open(&library_roots, dir.path(), dir.path(), con.clone())
+                .unwrap();
+        photo_repo
+            .add_all(&vec![crate::ScannedFile::Photo(path)])
+            .unwrap();
+        let picture_id = photo_repo.all().unwrap()[0].picture_id;
+
+        let mut repo = Repository::open(dir.path(), dir.path(), con).unwrap();
+        let clusters = |repo: &Repository| {
+            let mut clusters = repo.unconfirmed_clusters(0.1, 0.5).unwrap();
+            clusters
+                .iter_mut()
+                .for_each(|cluster| cluster.sort_by_key(|face_id| face_id.id()));
+            clusters
+        };
+
+        let face_1 = add_face(&mut repo, picture_id, 1, FaceDetectionModel::Fast);
+        let face_2 = add_face(&mut repo, picture_id, 2, FaceDetectionModel::Fast);
+        assert_eq!(vec![vec![face_1, face_2]], clusters(&repo));
+        assert_eq!(vec![vec![face_1, face_2]], clusters(&repo));
+
+        let face_3 = add_face(&mut repo, picture_id, 3, FaceDetectionModel::Fast);
+        assert_eq!(vec![vec![face_1, face_2, face_3]], clusters(&repo));
+
+        let unlike = model::Embedding::new(vec![-1.0, 0.5, 0.25]);
+        repo.set_face_embedding(face_3, &unlike).unwrap();
+        assert_eq!(vec![vec![face_1, face_2]], clusters(&repo));
+
+        // A clone shares the clusters, but still sees changes made through the original.
+        let clone = repo.clone();
+        repo.ignore_unknown_faces(picture_id).unwrap();
+        assert!(clusters(&clone).is_empty());
+    }
+
+    #[test]
+    fn merge_people_moves_faces_and_keeps_cover() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.png");
+        image::RgbImage::new(3, 2).save(&path).unwrap();
+
+        let root = crate::FlatpakPathBuf::build(dir.path(), dir.path());
+        let library_roots = crate::LibraryRoots::build(&root, &[]);
+        let con = Arc::new(Mutex::new(database::setup_in_memory().unwrap()));
+        let mut photo_repo =
+            crate::photo::Repository::open(&library_roots, dir.path(), dir.path(), con.clone())
+                .unwrap();
+        photo_repo
+            .add_all(&vec![crate::ScannedFile::Photo(path)])
+            .unwrap();
+        let picture_id = photo_repo.all().unwrap()[0].picture_id;
+
+        let mut repo = Repository::open(dir.path(), dir.path(), con).unwrap();
+        let ada_face = add_face(&mut repo, picture_id, 1, FaceDetectionModel::Fast);
+        let bob_face_1 = add_face(&mut repo, picture_id, 2, FaceDetectionModel::Fast);
+        let bob_face_2 = add_face(&mut repo, picture_id, 3, FaceDetectionModel::Fast);
+
+        repo.add_person(ada_face, "Ada").unwrap();
+        repo.add_person(bob_face_1, "Bob").unwrap();
+        let ada = repo.person_for_face(ada_face).unwrap().unwrap().person_id;
+        let bob = repo.person_for_face(bob_face_1).unwrap().unwrap().person_id;
+        repo.mark_as_person(bob_face_2, bob).unwrap();
+
+        assert!(repo.merge_people(ada, ada).is_err());
+
+        repo.merge_people(ada, bob).unwrap();
+
+        assert!(repo.get_person(bob).unwrap().is_none());
+
+        let mut faces: Vec<FaceId> = repo
+            .faces_for_person(ada)
+            .unwrap()
+            .into_iter()
+            .map(|face| face.face_id)
+            .collect();
+        faces.sort_by_key(|face_id| face_id.id());
+        assert_eq!(vec![ada_face, bob_face_1, bob_face_2], faces);
+
+        let ada = repo.get_person(ada).unwrap().unwrap();
+        assert_eq!(Some(ada_face), ada.cover_face_id);
+    }
+
+    #[test]
+    fn assign_pictures_to_person_only_when_one_unknown_face() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths: Vec<_> = ["a.png", "b.png", "c.png", "d.png"]
+            .iter()
+            .map(|name| dir.path().join(name))
+            .collect();
+        for path in &paths {
+            image::RgbImage::new(3, 2).save(path).unwrap();
+        }
+
+        let root = crate::FlatpakPathBuf::build(dir.path(), dir.path());
+        let library_roots = crate::LibraryRoots::build(&root, &[]);
+        let con = Arc::new(Mutex::new(database::setup_in_memory().unwrap()));
+        let mut photo_repo =
+            crate::photo::Repository::open(&library_roots, dir.path(), dir.path(), con.clone())
+                .unwrap();
+        let scanned: Vec<_> = paths
+            .iter()
+            .map(|path| crate::ScannedFile::Photo(path.clone()))
+            .collect();
+        photo_repo.add_all(&scanned).unwrap();
+        let mut pictures = photo_repo.all().unwrap();
+        pictures.sort_by_key(|picture| picture.path.sandbox_path.clone());
+        let ids: Vec<PictureId> = pictures.iter().map(|picture| picture.picture_id).collect();
+
+        let mut repo = Repository::open(dir.path(), dir.path(), con).unwrap();
+        let ada_face = add_face(&mut repo, ids[0], 1, FaceDetectionModel::Fast);
+        repo.add_person(ada_face, "Ada").unwrap();
+        let ada = repo.person_for_face(ada_face).unwrap().unwrap().person_id;
+
+        // One unknown face alongside a known face.
+        let single_face = add_face(&mut repo, ids[1], 2, FaceDetectionModel::Fast);
+        let known_face = add_face(&mut repo, ids[1], 3, FaceDetectionModel::Fast);
+        repo.mark_as_person(known_face, ada).unwrap();
+
+        // Two unknown faces.
+        let group_face_1 = add_face(&mut repo, ids[2], 4, FaceDetectionModel::Fast);
+        let group_face_2 = add_face(&mut repo, ids[2], 5, FaceDetectionModel::Fast);
+
+        // The last picture has no faces at all.
+        let assigned = repo
+            .assign_pictures_to_person(&[ids[1], ids[2], ids[3]], ada)
+            .unwrap();
+        assert_eq!(1, assigned);
+
+        let person_of = |face_id| repo.person_for_face(face_id).unwrap().map(|p| p.person_id);
+        assert_eq!(Some(ada), person_of(single_face));
+        assert_eq!(None, person_of(group_face_1));
+        assert_eq!(None, person_of(group_face_2));
+    }
+}
@@ -41,10 +41,13 @@ impl PersonThumbnailer {
         }
     }
 
+    /// Generates a large thumbnail for a face, expanded by `head_margin`, a fraction
+    /// of the face bounds.
     pub async fn thumbnail(
         &self,
         original_picture: &FlatpakPathBuf,
         face: &DetectedFace,
+        head_margin: f32,
     ) -> Result<()> {
         let large_thumbnail_path = self.cache_dir.join("face_thumbnails").join("large").join(
             face.small_thumbnail_path
@@ -106,47 +109,15 @@ impl PersonThumbnailer {
 
         let face = face.clone().scale(ratio);
 
-        // FIXME the rest of this code is pretty much a copy and paste from face_extractor.rs
-
         // Extract face and save to thumbnail.
         // The bounding box is pretty tight, so make it a bit bigger.
         // Also, make the box a square.
-
-        let longest: f32 = f32::max(face.bounds.width, face.bounds.height);
-        let mut longest = longest * 1.6;
-        let mut half_longest = longest / 2.0;
-
-        let (centre_x, centre_y) = face.centre();
-
-        // Normalize thumbnail to be a square.
-        if (original_image.details().width() as f32) < centre_x + half_longest {
-            half_longest = original_image.details().width() as f32 - centre_x;
-            longest = half_longest * 2.0;
-        }
-        if (original_image.details().height() as f32) < centre_y + half_longest {
-            half_longest = original_image.details().height() as f32 - centre_y;
-            longest = half_longest * 2.0;
-        }
-
-        if centre_x < half_longest {
-            half_longest = centre_x;
-            longest = half_longest * 2.0;
-        }
-
-        if centre_y < half_longest {
-            half_longest = centre_y;
-            longest = half_longest * 2.0;
-        }
-
-        // Don't panic when x or y would be < zero
-        let mut x = centre_x - half_longest;
-        if x < 0.0 {
-            x = 0.0;
-        }
-        let mut y = centre_y - half_longest;
-        if y < 0.0 {
-            y = 0.0;
-        }
+        let square = face.bounds.head_square(
+            face.centre(),
+            head_margin,
+            original_image.details().width() as f32,
+            original_image.details().height() as f32,
+        );
 
         let bytes = frame.texture().save_to_png_bytes();
 
@@ -158,7 +129,12 @@ impl PersonThumbnailer {
             })?;
 
         // FIXME use fast_image_resize instead of image-rs
-        let thumbnail = original_image.crop_imm(x as u32, y as u32, longest as u32, longest as u32);
+        let thumbnail = original_image.crop_imm(
+            square.x as u32,
+            square.y as u32,
+            square.width as u32,
+            square.height as u32,
+        );
         let thumbnail = thumbnail.thumbnail(256, 256);
 
         thumbnail.save(&large_thumbnail_path).map_err(|err| {
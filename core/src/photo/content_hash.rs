@@ -0,0 +1,94 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use md5::{Digest, Md5};
+use std::fs::File;
+use std::path::Path;
+
+/// File size and modification timestamp. If either changes, then so might the file contents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileStamp {
+    pub file_size: u64,
+    pub fs_modified_at: Option<DateTime<Utc>>,
+}
+
+impl FileStamp {
+    pub fn from_path(path: &Path) -> Result<FileStamp> {
+        let metadata = std::fs::metadata(path)?;
+        Ok(FileStamp {
+            file_size: metadata.len(),
+            fs_modified_at: metadata.modified().map(Into::<DateTime<Utc>>::into).ok(),
+        })
+    }
+}
+
+/// Hash of a file's bytes. Identical files have identical hashes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentHash {
+    pub hash: String,
+
+    /// Stamp of file when hashed.
+    pub stamp: FileStamp,
+}
+
+/// Computes the MD5 hash of a file's contents.
+/// MD5 is fine for finding duplicates in a personal library, which isn't adversarial.
+pub fn from_path(path: &Path) -> Result<ContentHash> {
+    let stamp = FileStamp::from_path(path)?;
+
+    let mut file = File::open(path)?;
+    let mut hasher = Md5::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    let hash = format!("{:x}", hasher.finalize());
+
+    Ok(ContentHash { hash, stamp })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn file_with(contents: &[u8]) -> tempfile::NamedTempFile {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(contents).unwrap();
+        file
+    }
+
+    #[test]
+    fn identical_files_have_same_hash() {
+        let a = file_with(b"picture");
+        let b = file_with(b"picture");
+
+        let a = from_path(a.path()).unwrap();
+        let b = from_path(b.path()).unwrap();
+
+        assert_eq!(a.hash, b.hash);
+        assert_eq!(7, a.stamp.file_size);
+    }
+
+    #[test]
+    fn different_files_have_different_hashes() {
+        let a = file_with(b"picture");
+        let b = file_with(b"another picture");
+
+        let a = from_path(a.path()).unwrap();
+        let b = from_path(b.path()).unwrap();
+
+        assert_ne!(a.hash, b.hash);
+    }
+
+    #[test]
+    fn stamp_changes_with_file_size() {
+        let mut file = file_with(b"picture");
+        let before = FileStamp::from_path(file.path()).unwrap();
+
+        file.write_all(b" with more bytes").unwrap();
+        let after = FileStamp::from_path(file.path()).unwrap();
+
+        assert_ne!(before, after);
+    }
+}
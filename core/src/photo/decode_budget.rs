@@ -0,0 +1,186 @@
+// SPDX-FileCopyrightText: © 2025 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Limits how much memory pictures being decoded may use at once.
+//!
+//! Thumbnails are generated on every CPU core, so a few huge panoramas decoded
+//! together can exhaust memory. Each decode reserves its estimated size from a
+//! shared budget first, so large pictures wait for each other while small ones
+//! still decode in parallel.
+
+use std::sync::{Condvar, LazyLock, Mutex, PoisonError};
+use tracing::info;
+
+/// Largest picture decoded at full size. Anything bigger is downsampled while
+/// decoding. 256 MiB is a little over 67 megapixels.
+pub const MAX_DECODED_BYTES: u64 = 256 * 1024 * 1024;
+
+/// Budget used when available memory can't be read.
+const FALLBACK_BUDGET_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+
+/// Budget shared by everything that decodes pictures.
+pub static DECODE_BUDGET: LazyLock<DecodeBudget> =
+    LazyLock::new(|| DecodeBudget::new(budget_from_available_memory()));
+
+/// Memory that may be used by decoded pictures, shared between threads.
+#[derive(Debug)]
+pub struct DecodeBudget {
+    total: u64,
+    available: Mutex<u64>,
+    released: Condvar,
+}
+
+/// Memory reserved for one decoded picture. Given back when dropped.
+#[derive(Debug)]
+pub struct DecodePermit<'a> {
+    budget: &'a DecodeBudget,
+    bytes: u64,
+}
+
+impl DecodeBudget {
+    pub fn new(total: u64) -> DecodeBudget {
+        DecodeBudget {
+            total,
+            available: Mutex::new(total),
+            released: Condvar::new(),
+        }
+    }
+
+    /// Reserves memory for a decoded picture, blocking until enough is free.
+    /// A picture bigger than the whole budget waits until nothing else is
+    /// decoding, then decodes alone.
+    pub fn acquire(&self, bytes: u64) -> DecodePermit<'_> {
+        let bytes = bytes.min(self.total);
+        let available = self
+            .available
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let mut available = self
+            .released
+            .wait_while(available, |available| *available < bytes)
+            .unwrap_or_else(PoisonError::into_inner);
+        *available -= bytes;
+        DecodePermit {
+            budget: self,
+            bytes,
+        }
+    }
+}
+
+impl Drop for DecodePermit<'_> {
+    fn drop(&mut self) {
+        *self
+            .budget
+            .available
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) += self.bytes;
+        self.budget.released.notify_all();
+    }
+}
+
+/// Estimated memory for a decoded picture, as four bytes per pixel.
+pub fn decoded_size(width: u32, height: u32) -> u64 {
+    width as u64 * height as u64 * 4
+}
+
+/// Dimensions to decode a picture at so that it fits within `MAX_DECODED_BYTES`,
+/// keeping its aspect ratio. `None` if it fits at full size.
+pub fn capped_dimensions(width: u32, height: u32) -> Option<(u32, u32)> {
+    let bytes = decoded_size(width, height);
+    if bytes <= MAX_DECODED_BYTES {
+        return None;
+    }
+
+    let scale = (MAX_DECODED_BYTES as f64 / bytes as f64).sqrt();
+    let width = ((width as f64 * scale).floor() as u32).max(1);
+    let height = ((height as f64 * scale).floor() as u32).max(1);
+    Some((width, height))
+}
+
+/// Half of the memory the system says is available, but always enough for
+/// one picture at the largest decoded size.
+fn budget_from_available_memory() -> u64 {
+    let budget = std::fs::read_to_string("/proc/meminfo")
+        .ok()
+        .and_then(|meminfo| available_memory(&meminfo))
+        .map(|bytes| bytes / 2)
+        .unwrap_or(FALLBACK_BUDGET_BYTES)
+        .max(MAX_DECODED_BYTES);
+    info!("Decoding pictures with a memory budget of {} bytes", budget);
+    budget
+}
+
+/// Bytes of available memory according to the contents of `/proc/meminfo`.
+fn available_memory(meminfo: &str) -> Option<u64> {
+    let line = meminfo
+        .lines()
+        .find(|line| line.starts_with("MemAvailable:"))?;
+    let kibibytes = line.split_whitespace().nth(1)?.parse::<u64>().ok()?;
+    Some(kibibytes * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Duration;
+
+    #[test]
+    fn waits_for_memory_to_be_released() {
+        let budget = Arc::new(DecodeBudget::new(100));
+        let first = budget.acquire(60);
+
+        let is_acquired = Arc::new(AtomicBool::new(false));
+        let second = {
+            let budget = budget.clone();
+            let is_acquired = is_acquired.clone();
+            std::thread::spawn(move || {
+                let _permit = budget.acquire(60);
+                is_acquired.store(true, Ordering::SeqCst);
+            })
+        };
+
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(!is_acquired.load(Ordering::SeqCst));
+
+        drop(first);
+        second.join().unwrap();
+        assert!(is_acquired.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn small_pictures_share_budget() {
+        let budget = DecodeBudget::new(100);
+        let _first = budget.acquire(40);
+        let _second = budget.acquire(40);
+    }
+
+    #[test]
+    fn oversized_picture_takes_whole_budget() {
+        let budget = DecodeBudget::new(100);
+        {
+            let permit = budget.acquire(1000);
+            assert_eq!(100, permit.bytes);
+        }
+        assert_eq!(100, *budget.available.lock().unwrap());
+    }
+
+    #[test]
+    fn caps_huge_pictures_keeping_aspect_ratio() {
+        assert_eq!(None, capped_dimensions(4000, 3000));
+
+        // 100 megapixel panorama
+        let (width, height) = capped_dimensions(20000, 5000).unwrap();
+        assert!(decoded_size(width, height) <= MAX_DECODED_BYTES);
+        assert_eq!(4, width / height);
+    }
+
+    #[test]
+    fn reads_available_memory() {
+        let meminfo = "MemTotal:       16000000 kB\nMemFree:         1000000 kB\nMemAvailable:    8000000 kB\n";
+        assert_eq!(Some(8000000 * 1024), available_memory(meminfo));
+        assert_eq!(None, available_memory("MemTotal: 16000000 kB\n"));
+    }
+}
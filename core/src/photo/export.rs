@@ -0,0 +1,215 @@
+// SPDX-FileCopyrightText: © 2025 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::model::Picture;
+use anyhow::*;
+use std::path::{Path, PathBuf};
+use tracing::{error, warn};
+
+/// Outcome of exporting pictures.
+#[derive(Debug, Default)]
+pub struct ExportSummary {
+    /// Count of pictures copied to the destination directory.
+    pub exported: usize,
+
+    /// Pictures skipped because their file no longer exists.
+    pub missing: Vec<PathBuf>,
+
+    /// Pictures that couldn't be copied.
+    pub failed: Vec<PathBuf>,
+}
+
+/// Copy the original files of pictures into a destination directory.
+/// File names are preserved, except that a numeric suffix is added if a file
+/// of the same name already exists in the destination.
+/// A picture that can't be copied is recorded in the summary and doesn't stop the export.
+/// `on_progress` is called after each picture.
+pub fn export(
+    pictures: &[Picture],
+    destination: &Path,
+    mut on_progress: impl FnMut(),
+) -> Result<ExportSummary> {
+    std::fs::create_dir_all(destination)?;
+
+    let mut summary = ExportSummary::default();
+
+    for picture in pictures {
+        let source = picture.sandbox_path();
+
+        if !source.exists() {
+            warn!("Skipping export of missing picture {:?}", source);
+            summary.missing.push(picture.host_path().clone());
+        } else if let Err(e) = export_one(source, destination) {
+            error!("Failed exporting {:?}: {:?}", source, e);
+            summary.failed.push(picture.host_path().clone());
+        } else {
+            summary.exported += 1;
+        }
+
+        on_progress();
+    }
+
+    Ok(summary)
+}
+
+/// Copy a file into the destination directory without overwriting anything.
+fn export_one(source: &Path, destination: &Path) -> Result<()> {
+    let file_name = source
+        .file_name()
+        .ok_or_else(|| anyhow!("No file name: {:?}", source))?;
+
+    let target = unique_destination(destination, Path::new(file_name));
+    copy_file(source, &target)
+        .with_context(|| format!("Failed copying {:?} to {:?}", source, target))?;
+
+    Ok(())
+}
+
+/// Copy a file without ever leaving part of it behind. The copy is written to a
+/// temporary file in the same directory as `to` and only renamed into place once
+/// complete, so a failed copy, such as on a full disk, leaves nothing at `to`.
+/// Fails rather than replace an existing file at `to`.
+pub(crate) fn copy_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    let dir = to.parent().unwrap_or(Path::new("."));
+    let temp = tempfile::NamedTempFile::new_in(dir)?;
+    std::fs::copy(from, temp.path())?;
+    temp.persist_noclobber(to)?;
+    Ok(())
+}
+
+/// Path in `dir` for `file_name` that doesn't exist yet.
+/// `IMG_0001.jpg` becomes `IMG_0001 (1).jpg`, `IMG_0001 (2).jpg`, and so on.
+pub(crate) fn unique_destination(dir: &Path, file_name: &Path) -> PathBuf {
+    let candidate = dir.join(file_name);
+    if !candidate.exists() {
+        return candidate;
+    }
+
+    let stem = file_name.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = file_name.extension().map(|ext| ext.to_string_lossy());
+
+    (1..)
+        .map(|n| {
+            let name = match extension {
+                Some(ref ext) => format!("{} ({}).{}", stem, n, ext),
+                None => format!("{} ({})", stem, n),
+            };
+            dir.join(name)
+        })
+        .find(|path| !path.exists())
+        .expect("Infinite candidates")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FlatpakPathBuf;
+    use crate::photo::PictureId;
+    use crate::photo::model::Orientation;
+    use chrono::Utc;
+
+    fn picture(path: PathBuf) -> Picture {
+        Picture {
+            path: FlatpakPathBuf::build(&path, &path),
+            picture_id: PictureId::new(1),
+            ordering_ts: Utc::now(),
+            is_selfie: None,
+            is_favorite: false,
+            blurhash: None,
+            orientation: Orientation::North,
+            width: None,
+            height: None,
+            byte_size: None,
+            is_broken: false,
+        }
+    }
+
+    #[test]
+    fn unique_destination_adds_suffix() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(
+            dir.path().join("a.jpg"),
+            unique_destination(dir.path(), Path::new("a.jpg"))
+        );
+
+        std::fs::write(dir.path().join("a.jpg"), b"").unwrap();
+        std::fs::write(dir.path().join("a (1).jpg"), b"").unwrap();
+        assert_eq!(
+            dir.path().join("a (2).jpg"),
+            unique_destination(dir.path(), Path::new("a.jpg"))
+        );
+    }
+
+    #[test]
+    fn failed_copy_leaves_nothing_behind() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("a.jpg");
+
+        assert!(copy_file(&dir.path().join("gone.jpg"), &target).is_err());
+        assert_eq!(0, std::fs::read_dir(dir.path()).unwrap().count());
+
+        std::fs::write(dir.path().join("b.jpg"), b"contents").unwrap();
+        copy_file(&dir.path().join("b.jpg"), &target).unwrap();
+        assert_eq!(b"contents".to_vec(), std::fs::read(&target).unwrap());
+        assert_eq!(2, std::fs::read_dir(dir.path()).unwrap().count());
+    }
+
+    #[test]
+    fn export_copies_and_reports_missing() {
+        let library = tempfile::tempdir().unwrap();
+        std::fs::create_dir(library.path().join("2024")).unwrap();
+        std::fs::write(library.path().join("a.jpg"), b"first").unwrap();
+        std::fs::write(library.path().join("2024").join("a.jpg"), b"second").unwrap();
+
+        let pictures = vec![
+            picture(library.path().join("a.jpg")),
+            picture(library.path().join("2024").join("a.jpg")),
+            picture(library.path().join("gone.jpg")),
+        ];
+
+        let destination = tempfile::tempdir().unwrap();
+        let mut progress = 0;
+        let summary = export(&pictures, destination.path(), || progress += 1).unwrap();
+
+        assert_eq!(2, summary.exported);
+        assert_eq!(vec![library.path().join("gone.jpg")], summary.missing);
+        assert!(summary.failed.is_empty());
+        assert_eq!(3, progress);
+        assert_eq!(
+            b"first".to_vec(),
+            std::fs::read(destination.path().join("a.jpg")).unwrap()
+        );
+        assert_eq!(
+            b"second".to_vec(),
+            std::fs::read(destination.path().join("a (1).jpg")).unwrap()
+        );
+    }
+    #[test]
+    fn export_continues_after_failed_copy() {
+        let library = tempfile::tempdir().unwrap();
+        // A directory exists but can't be copied like a file.
+        std::fs::create_dir(library.path().join("b.jpg")).unwrap();
+        std::fs::write(library.path().join("a.jpg"), b"first").unwrap();
+        std::fs::write(library.path().join("c.jpg"), b"third").unwrap();
+
+        let pictures = vec![
+            picture(library.path().join("a.jpg")),
+            picture(library.path().join("b.jpg")),
+            picture(library.path().join("c.jpg")),
+        ];
+
+        let destination = tempfile::tempdir().unwrap();
+        let mut progress = 0;
+        let summary = export(&pictures, destination.path(), || progress += 1).unwrap();
+
+        assert_eq!(2, summary.exported);
+        assert!(summary.missing.is_empty());
+        assert_eq!(vec![library.path().join("b.jpg")], summary.failed);
+        assert_eq!(3, progress);
+        assert_eq!(
+            b"third".to_vec(),
+            std::fs::read(destination.path().join("c.jpg")).unwrap()
+        );
+    }
+}
@@ -4,7 +4,10 @@
 
 use super::Metadata;
 use super::gps::GPSLocation;
+use super::model::CameraSettings;
+use super::model::DatePrecision;
 use super::model::Orientation;
+use super::xmp::{self, XmpDateTime};
 use anyhow::*;
 use chrono::prelude::*;
 use chrono::{DateTime, FixedOffset};
@@ -25,18 +28,102 @@ use std::result::Result::Ok;
 /// 1. Orientation.
 /// 2. Motion photos.
 /// 3. GPS coordinates.
-pub const VERSION: u32 = 3;
+/// 4. Camera and exposure settings.
+/// 5. Place names.
+/// 6. Capture time fallback chain. Timestamps without a UTC offset are local time.
+/// 7. Precision of XMP dates that leave out the time, day, or month.
+pub const VERSION: u32 = 7;
+
+/// Time zone of EXIF and XMP timestamps that don't record their UTC offset.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum NaiveTimeZone {
+    /// Time zone of this computer.
+    #[default]
+    Local,
+
+    /// Fixed offset from UTC, such as for a camera that was never set to local time.
+    Offset(FixedOffset),
+}
+
+impl NaiveTimeZone {
+    /// Timestamp for a date and time in this time zone.
+    /// None if the time doesn't exist, such as when clocks go forward.
+    pub fn resolve(&self, naive: NaiveDateTime) -> Option<DateTime<FixedOffset>> {
+        match self {
+            NaiveTimeZone::Local => Local
+                .from_local_datetime(&naive)
+                .earliest()
+                .map(|ts| ts.fixed_offset()),
+            NaiveTimeZone::Offset(offset) => offset.from_local_datetime(&naive).single(),
+        }
+    }
+}
+
+impl std::fmt::Display for NaiveTimeZone {
+    /// Empty for local time, otherwise an offset such as "+02:00".
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NaiveTimeZone::Local => Ok(()),
+            NaiveTimeZone::Offset(offset) => write!(f, "{}", offset),
+        }
+    }
+}
+
+impl std::str::FromStr for NaiveTimeZone {
+    type Err = anyhow::Error;
+
+    /// Parses an empty string as local time, otherwise an offset such as "+02:00" or "-0530".
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Ok(NaiveTimeZone::Local);
+        }
+
+        let (sign, digits) = match s.split_at_checked(1) {
+            Some(("+", digits)) => (1, digits),
+            Some(("-", digits)) => (-1, digits),
+            _ => bail!("UTC offset must start with + or -: {}", s),
+        };
+
+        let digits = digits.replace(':', "");
+        if digits.len() != 4 || !digits.chars().all(|c| c.is_ascii_digit()) {
+            bail!("UTC offset must be hours and minutes: {}", s);
+        }
 
-/// Extract EXIF metadata from file
-pub fn from_path(path: &Path) -> Result<Metadata> {
+        let hours: i32 = digits[..2].parse()?;
+        let minutes: i32 = digits[2..].parse()?;
+        let offset = FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+            .ok_or_else(|| anyhow!("UTC offset out of range: {}", s))?;
+
+        Ok(NaiveTimeZone::Offset(offset))
+    }
+}
+
+/// Extract EXIF and XMP metadata from file.
+/// Timestamps without a UTC offset are taken to be in `time_zone`.
+pub fn from_path(path: &Path, time_zone: NaiveTimeZone) -> Result<Metadata> {
     let file = fs::File::open(path)?;
     let file = &mut BufReader::new(file);
 
     let mut metadata = match exif::Reader::new().read_from_container(file) {
-        Ok(exif_data) => from_exif(exif_data)?,
+        Ok(exif_data) => from_exif(exif_data, time_zone)?,
         Err(_) => Metadata::default(),
     };
 
+    let xmp_created_at = xmp::created_at(path);
+
+    // A date without a time is taken to be midnight UTC rather than in `time_zone`,
+    // so that it stays in the same day, month, and year when shown in UTC.
+    metadata.xmp_created_at = xmp_created_at.and_then(|ts| match ts {
+        XmpDateTime::Offset(ts) => Some(ts),
+        XmpDateTime::Naive(ts) => time_zone.resolve(ts),
+        XmpDateTime::Partial(date, _) => date
+            .and_hms_opt(0, 0, 0)
+            .map(|ts| ts.and_utc().fixed_offset()),
+    });
+
+    metadata.xmp_created_precision = xmp_created_at.map(|ts| ts.precision()).unwrap_or_default();
+
     let fs_metadata = fs::metadata(path)?;
 
     metadata.fs_created_at = fs_metadata.created().map(Into::<DateTime<Utc>>::into).ok();
@@ -66,8 +153,9 @@ pub fn from_path(path: &Path) -> Result<Metadata> {
     Ok(metadata)
 }
 
-/// Extract EXIF metadata from raw buffer
-pub fn from_raw(data: Vec<u8>) -> Result<Metadata> {
+/// Extract EXIF metadata from raw buffer.
+/// Timestamps without a UTC offset are taken to be in `time_zone`.
+pub fn from_raw(data: Vec<u8>, time_zone: NaiveTimeZone) -> Result<Metadata> {
     let exif_data = {
         match exif::Reader::new().read_raw(data) {
             Ok(exif) => exif,
@@ -78,13 +166,14 @@ pub fn from_raw(data: Vec<u8>) -> Result<Metadata> {
         }
     };
 
-    from_exif(exif_data)
+    from_exif(exif_data, time_zone)
 }
 
-fn from_exif(exif_data: Exif) -> Result<Metadata> {
+fn from_exif(exif_data: Exif, time_zone: NaiveTimeZone) -> Result<Metadata> {
     fn parse_date_time(
         date_time_field: Option<&exif::Field>,
         time_offset_field: Option<&exif::Field>,
+        time_zone: NaiveTimeZone,
     ) -> Option<DateTime<FixedOffset>> {
         let date_time_field = date_time_field?;
 
@@ -104,9 +193,6 @@ fn from_exif(exif_data: Exif) -> Result<Metadata> {
             };
         }
 
-        let offset = date_time.offset.unwrap_or(0); // offset in minutes
-        let offset = FixedOffset::east_opt((offset as i32) * 60)?;
-
         let date = NaiveDate::from_ymd_opt(
             date_time.year.into(),
             date_time.month.into(),
@@ -119,18 +205,33 @@ fn from_exif(exif_data: Exif) -> Result<Metadata> {
             date_time.second.into(),
         )?;
 
+        // EXIF timestamps are in the local time of wherever the picture was taken,
+        // which is only known if the camera recorded the UTC offset.
         let naive_date_time = date.and_time(time);
-        Some(offset.from_utc_datetime(&naive_date_time))
+        match date_time.offset {
+            Some(offset) => FixedOffset::east_opt((offset as i32) * 60)?
+                .from_local_datetime(&naive_date_time)
+                .single(),
+            None => time_zone.resolve(naive_date_time),
+        }
     }
 
     let exif_created_at = parse_date_time(
         exif_data.get_field(exif::Tag::DateTimeOriginal, exif::In::PRIMARY),
         exif_data.get_field(exif::Tag::OffsetTimeOriginal, exif::In::PRIMARY),
+        time_zone,
     );
 
     let exif_modified_at = parse_date_time(
         exif_data.get_field(exif::Tag::DateTime, exif::In::PRIMARY),
         exif_data.get_field(exif::Tag::OffsetTime, exif::In::PRIMARY),
+        time_zone,
+    );
+
+    let exif_digitized_at = parse_date_time(
+        exif_data.get_field(exif::Tag::DateTimeDigitized, exif::In::PRIMARY),
+        exif_data.get_field(exif::Tag::OffsetTimeDigitized, exif::In::PRIMARY),
+        time_zone,
     );
 
     let lens_model = exif_data
@@ -149,20 +250,62 @@ fn from_exif(exif_data: Exif) -> Result<Metadata> {
 
     let location = gps_location(&exif_data);
 
+    let camera = camera_settings(&exif_data);
+
     let metadata = Metadata {
         fs_created_at: None,
         fs_modified_at: None,
         exif_created_at,
         exif_modified_at,
+        exif_digitized_at,
+        xmp_created_at: None,
+        xmp_created_precision: DatePrecision::Exact,
         lens_model,
         orientation,
         content_id,
         location,
+        camera,
     };
 
     Ok(metadata)
 }
 
+/// Parse camera and exposure settings from EXIF data
+fn camera_settings(exif_data: &Exif) -> CameraSettings {
+    let ascii = |tag| {
+        let field = exif_data.get_field(tag, exif::In::PRIMARY)?;
+        match field.value {
+            exif::Value::Ascii(ref vec) if !vec.is_empty() => {
+                let value = String::from_utf8_lossy(&vec[0]);
+                let value = value.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+                (!value.is_empty()).then(|| value.to_string())
+            }
+            _ => None,
+        }
+    };
+
+    let rational = |tag| {
+        let field = exif_data.get_field(tag, exif::In::PRIMARY)?;
+        match field.value {
+            exif::Value::Rational(ref vec) if !vec.is_empty() => {
+                Some(vec[0].to_f64()).filter(|x| x.is_finite() && *x > 0.0)
+            }
+            _ => None,
+        }
+    };
+
+    CameraSettings {
+        make: ascii(exif::Tag::Make),
+        model: ascii(exif::Tag::Model),
+        iso: exif_data
+            .get_field(exif::Tag::PhotographicSensitivity, exif::In::PRIMARY)
+            .and_then(|e| e.value.get_uint(0)),
+        f_number: rational(exif::Tag::FNumber),
+        exposure_time: rational(exif::Tag::ExposureTime),
+        focal_length: rational(exif::Tag::FocalLength),
+    }
+}
+
 /// Parse GPS latitude and longitude from EXIF data
 /// Mostly borrowed from Loupe.
 /// See https://gitlab.gnome.org/GNOME/loupe/-/blob/main/src/metadata.rs
@@ -259,4 +402,49 @@ mod tests {
             content_id
         );
     }
+
+    #[test]
+    fn test_camera_settings() {
+        let dir = env!("CARGO_MANIFEST_DIR");
+        let file = Path::new(dir).join("resources/test/Dandelion.jpg");
+        let file = fs::File::open(file).unwrap();
+        let file = &mut BufReader::new(file);
+
+        let exif_data = exif::Reader::new().read_from_container(file).ok().unwrap();
+        let camera = camera_settings(&exif_data);
+
+        assert_eq!(Some("Apple".to_string()), camera.make);
+        assert_eq!(Some("iPhone XS".to_string()), camera.model);
+        assert_eq!(Some(25), camera.iso);
+        assert_eq!(Some(1.8), camera.f_number);
+        assert_eq!(Some(1.0 / 1299.0), camera.exposure_time);
+        assert_eq!(Some(4.25), camera.focal_length);
+    }
+
+    #[test]
+    fn naive_time_zone_round_trip() {
+        for s in ["", "+02:00", "-05:30"] {
+            let time_zone: NaiveTimeZone = s.parse().unwrap();
+            assert_eq!(s, time_zone.to_string());
+        }
+
+        assert_eq!(
+            NaiveTimeZone::Offset(FixedOffset::east_opt(-(5 * 3600 + 30 * 60)).unwrap()),
+            "-0530".parse().unwrap()
+        );
+        assert!("02:00".parse::<NaiveTimeZone>().is_err());
+        assert!("+2".parse::<NaiveTimeZone>().is_err());
+    }
+
+    #[test]
+    fn naive_time_is_in_time_zone() {
+        let naive = NaiveDate::from_ymd_opt(2024, 7, 1)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+        let time_zone: NaiveTimeZone = "+02:00".parse().unwrap();
+
+        let ts = time_zone.resolve(naive).unwrap();
+        assert_eq!("2024-07-01T10:00:00+00:00", ts.to_utc().to_rfc3339());
+    }
 }
@@ -0,0 +1,248 @@
+// SPDX-FileCopyrightText: © 2025 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Metadata of pictures written as JSON lines or CSV, so a library can be
+//! backed up or analysed outside Fotema.
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// File format of exported metadata.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// One JSON object per line. Missing values are null.
+    JsonLines,
+
+    /// Comma-separated values with a header row. Missing values are empty.
+    /// Tags and people are separated by semicolons.
+    Csv,
+}
+
+impl ExportFormat {
+    /// Format for a file, from its extension. CSV for ".csv", otherwise JSON lines.
+    pub fn from_path(path: &Path) -> ExportFormat {
+        let is_csv = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+
+        if is_csv {
+            ExportFormat::Csv
+        } else {
+            ExportFormat::JsonLines
+        }
+    }
+}
+
+/// Exported metadata of a picture.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MetadataRecord {
+    /// Path to picture on the host, outside of any Flatpak sandbox.
+    pub path: PathBuf,
+
+    /// When the picture was taken. None if not known from embedded metadata or the file.
+    pub taken_at: Option<DateTime<Utc>>,
+
+    /// Width in pixels, if known.
+    pub width: Option<u32>,
+
+    /// Height in pixels, if known.
+    pub height: Option<u32>,
+
+    pub is_favorite: bool,
+
+    /// Names of tags, in alphabetical order.
+    pub tags: Vec<String>,
+
+    /// Names of people recognized in the picture, in alphabetical order.
+    pub people: Vec<String>,
+
+    pub latitude: Option<f64>,
+
+    pub longitude: Option<f64>,
+}
+
+const CSV_HEADER: &str = "path,taken_at,width,height,favorite,tags,people,latitude,longitude";
+
+/// Writes what comes before the first record, which is a header row for CSV.
+pub fn write_header(format: ExportFormat, writer: &mut impl Write) -> io::Result<()> {
+    match format {
+        ExportFormat::JsonLines => Ok(()),
+        ExportFormat::Csv => writeln!(writer, "{}", CSV_HEADER),
+    }
+}
+
+/// Writes a record as a single line.
+pub fn write_record(
+    format: ExportFormat,
+    record: &MetadataRecord,
+    writer: &mut impl Write,
+) -> io::Result<()> {
+    let path = record.path.to_string_lossy();
+    let taken_at = record
+        .taken_at
+        .map(|ts| ts.to_rfc3339_opts(SecondsFormat::Secs, true));
+    let latitude = record.latitude.filter(|x| x.is_finite());
+    let longitude = record.longitude.filter(|x| x.is_finite());
+
+    match format {
+        ExportFormat::JsonLines => {
+            let list = |names: &[String]| {
+                let names: Vec<String> = names.iter().map(|name| json_string(name)).collect();
+                format!("[{}]", names.join(","))
+            };
+
+            writeln!(
+                writer,
+                "{{\"path\":{},\"taken_at\":{},\"width\":{},\"height\":{},\"favorite\":{},\"tags\":{},\"people\":{},\"latitude\":{},\"longitude\":{}}}",
+                json_string(&path),
+                taken_at.map_or(String::from("null"), |ts| json_string(&ts)),
+                json_number(record.width),
+                json_number(record.height),
+                record.is_favorite,
+                list(&record.tags),
+                list(&record.people),
+                json_number(latitude),
+                json_number(longitude),
+            )
+        }
+        ExportFormat::Csv => {
+            let optional = |value: Option<String>| value.unwrap_or_default();
+
+            let fields = [
+                csv_field(&path),
+                csv_field(&optional(taken_at)),
+                optional(record.width.map(|x| x.to_string())),
+                optional(record.height.map(|x| x.to_string())),
+                record.is_favorite.to_string(),
+                csv_field(&record.tags.join(";")),
+                csv_field(&record.people.join(";")),
+                optional(latitude.map(|x| x.to_string())),
+                optional(longitude.map(|x| x.to_string())),
+            ];
+
+            writeln!(writer, "{}", fields.join(","))
+        }
+    }
+}
+
+fn json_number(value: Option<impl ToString>) -> String {
+    value.map_or(String::from("null"), |x| x.to_string())
+}
+
+fn json_string(value: &str) -> String {
+    let mut json = String::with_capacity(value.len() + 2);
+    json.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// Quotes a CSV field if it contains a separator, quote, or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record() -> MetadataRecord {
+        MetadataRecord {
+            path: PathBuf::from("/photos/a \"b\", c.jpg"),
+            taken_at: DateTime::parse_from_rfc3339("2024-06-01T10:20:30Z")
+                .ok()
+                .map(|ts| ts.to_utc()),
+            width: Some(4032),
+            height: Some(3024),
+            is_favorite: true,
+            tags: vec![String::from("beach"), String::from("summer")],
+            people: vec![String::from("Alex")],
+            latitude: Some(51.5),
+            longitude: Some(-0.125),
+        }
+    }
+
+    fn written(format: ExportFormat, record: &MetadataRecord) -> String {
+        let mut data = Vec::new();
+        write_header(format, &mut data).unwrap();
+        write_record(format, record, &mut data).unwrap();
+        String::from_utf8(data).unwrap()
+    }
+
+    #[test]
+    fn json_lines_record() {
+        assert_eq!(
+            "{\"path\":\"/photos/a \\\"b\\\", c.jpg\",\"taken_at\":\"2024-06-01T10:20:30Z\",\"width\":4032,\"height\":3024,\"favorite\":true,\"tags\":[\"beach\",\"summer\"],\"people\":[\"Alex\"],\"latitude\":51.5,\"longitude\":-0.125}\n",
+            written(ExportFormat::JsonLines, &record())
+        );
+    }
+
+    #[test]
+    fn json_lines_missing_values_are_null() {
+        let record = MetadataRecord {
+            path: PathBuf::from("/photos/a.jpg"),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            "{\"path\":\"/photos/a.jpg\",\"taken_at\":null,\"width\":null,\"height\":null,\"favorite\":false,\"tags\":[],\"people\":[],\"latitude\":null,\"longitude\":null}\n",
+            written(ExportFormat::JsonLines, &record)
+        );
+    }
+
+    #[test]
+    fn csv_record() {
+        assert_eq!(
+            "path,taken_at,width,height,favorite,tags,people,latitude,longitude\n\
+            \"/photos/a \"\"b\"\", c.jpg\",2024-06-01T10:20:30Z,4032,3024,true,beach;summer,Alex,51.5,-0.125\n",
+            written(ExportFormat::Csv, &record())
+        );
+    }
+
+    #[test]
+    fn csv_missing_values_are_empty() {
+        let record = MetadataRecord {
+            path: PathBuf::from("/photos/a.jpg"),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            "path,taken_at,width,height,favorite,tags,people,latitude,longitude\n\
+            /photos/a.jpg,,,,false,,,,\n",
+            written(ExportFormat::Csv, &record)
+        );
+    }
+
+    #[test]
+    fn json_escapes_control_characters() {
+        assert_eq!("\"a\\tb\\u0001\"", json_string("a\tb\u{1}"));
+    }
+
+    #[test]
+    fn format_from_extension() {
+        assert_eq!(
+            ExportFormat::Csv,
+            ExportFormat::from_path(Path::new("library.CSV"))
+        );
+        assert_eq!(
+            ExportFormat::JsonLines,
+            ExportFormat::from_path(Path::new("library.jsonl"))
+        );
+    }
+}
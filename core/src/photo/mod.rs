@@ -2,15 +2,24 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+pub mod content_hash;
+pub mod decode_budget;
+pub mod export;
 pub mod gps;
 pub mod metadata;
+pub mod metadata_export;
 pub mod model;
 pub mod motion_photo;
+pub mod perceptual_hash;
+pub mod raw;
 pub mod repo;
 pub mod thumbnailer;
+pub mod xmp;
 
 pub use model::PictureId;
 
+pub use export::ExportSummary;
+pub use metadata_export::ExportFormat;
 pub use model::Metadata;
 pub use motion_photo::MotionPhotoExtractor;
 pub use repo::Repository;
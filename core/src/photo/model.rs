@@ -5,13 +5,15 @@
 use super::gps::GPSLocation;
 use crate::FlatpakPathBuf;
 
-use chrono::{DateTime, FixedOffset, TimeDelta, Utc};
+use chrono::{DateTime, Days, FixedOffset, Months, TimeDelta, Utc};
+use rusqlite::ToSql;
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSqlOutput, ValueRef};
 use std::fmt::Display;
 use std::path::PathBuf;
-use strum::{AsRefStr, EnumIter};
+use strum::{AsRefStr, EnumIter, FromRepr};
 
 /// Database ID of picture
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct PictureId(i64);
 
 impl PictureId {
@@ -19,7 +21,6 @@ impl PictureId {
         Self(id)
     }
 
-    /// FIXME replace this with a To/From SQL implementation.
     pub fn id(&self) -> i64 {
         self.0
     }
@@ -31,6 +32,18 @@ impl Display for PictureId {
     }
 }
 
+impl ToSql for PictureId {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        self.0.to_sql()
+    }
+}
+
+impl FromSql for PictureId {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        i64::column_result(value).map(Self)
+    }
+}
+
 /// A picture in the repository
 #[derive(Debug, Clone)]
 pub struct Picture {
@@ -45,6 +58,27 @@ pub struct Picture {
 
     /// Was picture taken with front camera?
     pub is_selfie: Option<bool>,
+
+    /// Has user starred picture as a favorite?
+    pub is_favorite: bool,
+
+    /// Blurhash placeholder. None until the picture is thumbnailed.
+    pub blurhash: Option<String>,
+
+    /// EXIF orientation. North if the picture has no EXIF orientation.
+    pub orientation: Orientation,
+
+    /// Width in pixels. None if the picture couldn't be decoded when scanned.
+    pub width: Option<u32>,
+
+    /// Height in pixels. None if the picture couldn't be decoded when scanned.
+    pub height: Option<u32>,
+
+    /// File size in bytes, when scanned.
+    pub byte_size: Option<u64>,
+
+    /// Did thumbnailing the picture fail?
+    pub is_broken: bool,
 }
 
 impl Picture {
@@ -59,6 +93,39 @@ impl Picture {
     pub fn sandbox_path(&self) -> &PathBuf {
         &self.path.sandbox_path
     }
+
+    /// Rotation and mirroring to apply to display the picture the right way up.
+    pub fn display_transform(&self) -> DisplayTransform {
+        self.orientation.display_transform()
+    }
+}
+
+/// A picture left out of albums because it couldn't be processed.
+#[derive(Debug, Clone)]
+pub struct BrokenPicture {
+    pub picture_id: PictureId,
+
+    /// Path to picture
+    pub path: FlatpakPathBuf,
+
+    /// Why the picture couldn't be processed.
+    /// None if it was marked broken before reasons were kept.
+    pub reason: Option<String>,
+
+    /// When the picture was last found to be broken, if known.
+    pub broken_at: Option<DateTime<Utc>>,
+}
+
+/// A picture left out of albums because its file has gone from disk.
+#[derive(Debug, Clone)]
+pub struct MissingPicture {
+    pub picture_id: PictureId,
+
+    /// Path the picture was at when last seen.
+    pub path: FlatpakPathBuf,
+
+    /// When a library scan last found the picture's file, if known.
+    pub last_seen_at: Option<DateTime<Utc>>,
 }
 
 /// Extra (non-filesystem) metadata for videos
@@ -120,6 +187,44 @@ impl Orientation {
             _ => Self::default(),
         }
     }
+
+    /// Rotation and mirroring to apply to display an image with this orientation.
+    /// Matches the transformations in style.css.
+    pub fn display_transform(&self) -> DisplayTransform {
+        let (rotation, is_mirrored) = match self {
+            Orientation::North => (0, false),
+            Orientation::NorthMirrored => (0, true),
+            Orientation::South => (180, false),
+            Orientation::SouthMirrored => (180, true),
+            Orientation::WestMirrored => (90, true),
+            Orientation::West => (90, false),
+            Orientation::EastMirrored => (270, true),
+            Orientation::East => (270, false),
+        };
+
+        DisplayTransform {
+            rotation,
+            is_mirrored,
+        }
+    }
+}
+
+/// Transformation to display an image the right way up.
+/// Mirroring is applied before rotation.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DisplayTransform {
+    /// Clockwise rotation in degrees. One of 0, 90, 180, or 270.
+    pub rotation: u32,
+
+    /// Flip on the X axis?
+    pub is_mirrored: bool,
+}
+
+impl DisplayTransform {
+    /// Does the image need to be transformed at all?
+    pub fn is_identity(&self) -> bool {
+        self.rotation == 0 && !self.is_mirrored
+    }
 }
 
 impl From<u32> for Orientation {
@@ -138,6 +243,99 @@ impl From<u32> for Orientation {
     }
 }
 
+/// Where the time a picture was taken came from.
+/// Stored in the database, so the discriminants must not change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromRepr)]
+#[repr(u8)]
+pub enum TakenAtSource {
+    /// EXIF DateTimeOriginal. When the shutter was pressed.
+    DateTimeOriginal = 1,
+
+    /// EXIF DateTimeDigitized. When the picture was stored, which for most
+    /// cameras is when it was taken, but for a scan is when it was scanned.
+    DateTimeDigitized = 2,
+
+    /// XMP creation date, such as written by photo editors and scanning software.
+    Xmp = 3,
+
+    /// File modification time. Changes when a file is copied or edited, so
+    /// might be a long time after the picture was taken.
+    FileModified = 4,
+}
+
+impl TakenAtSource {
+    /// Is the time only a guess at when the picture was taken?
+    pub fn is_estimate(&self) -> bool {
+        *self == TakenAtSource::FileModified
+    }
+}
+
+impl ToSql for TakenAtSource {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        (*self as u8).to_sql()
+    }
+}
+
+impl FromSql for TakenAtSource {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        u8::column_result(value)
+            .and_then(|x| TakenAtSource::from_repr(x).ok_or(FromSqlError::OutOfRange(x.into())))
+    }
+}
+
+/// How much of the time a picture was taken is known. Scans of prints often
+/// record only the year or month, and the time is then the start of that period.
+/// Stored in the database, so the discriminants must not change.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, FromRepr)]
+#[repr(u8)]
+pub enum DatePrecision {
+    /// Only the year is known.
+    Year = 1,
+
+    /// Only the year and month are known.
+    Month = 2,
+
+    /// Only the date is known, not the time of day.
+    Day = 3,
+
+    /// Date and time are both known.
+    #[default]
+    Exact = 4,
+}
+
+impl DatePrecision {
+    /// Is less than the day known, so the time shouldn't be shown as a date?
+    pub fn is_imprecise(&self) -> bool {
+        *self < DatePrecision::Day
+    }
+
+    /// Last moment of the period that starts at `start` and is as long as this
+    /// precision, such as the end of the year for a year-only date.
+    pub fn period_end(&self, start: DateTime<Utc>) -> DateTime<Utc> {
+        let next = match self {
+            DatePrecision::Year => start.checked_add_months(Months::new(12)),
+            DatePrecision::Month => start.checked_add_months(Months::new(1)),
+            DatePrecision::Day => start.checked_add_days(Days::new(1)),
+            DatePrecision::Exact => return start,
+        };
+
+        next.map_or(start, |next| next - TimeDelta::nanoseconds(1))
+    }
+}
+
+impl ToSql for DatePrecision {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        (*self as u8).to_sql()
+    }
+}
+
+impl FromSql for DatePrecision {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        u8::column_result(value)
+            .and_then(|x| DatePrecision::from_repr(x).ok_or(FromSqlError::OutOfRange(x.into())))
+    }
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct Metadata {
     pub fs_created_at: Option<DateTime<Utc>>,
@@ -148,6 +346,13 @@ pub struct Metadata {
 
     pub exif_modified_at: Option<DateTime<FixedOffset>>,
 
+    pub exif_digitized_at: Option<DateTime<FixedOffset>>,
+
+    pub xmp_created_at: Option<DateTime<FixedOffset>>,
+
+    /// How much of `xmp_created_at` is known, such as only the year for a scan.
+    pub xmp_created_precision: DatePrecision,
+
     /// On iPhone the lens model tells you if it was the front or back camera.
     pub lens_model: Option<String>,
 
@@ -161,6 +366,9 @@ pub struct Metadata {
 
     // GPS location
     pub location: Option<GPSLocation>,
+
+    // Camera and exposure settings.
+    pub camera: CameraSettings,
 }
 
 impl Metadata {
@@ -169,6 +377,62 @@ impl Metadata {
             .as_ref()
             .is_some_and(|x| x.contains("front"))
     }
+
+    /// When the picture was taken, and where that time came from.
+    ///
+    /// Sources are tried from most to least reliable:
+    /// 1. EXIF DateTimeOriginal.
+    /// 2. EXIF DateTimeDigitized.
+    /// 3. XMP creation date.
+    /// 4. File modification time, which is only an estimate.
+    ///
+    /// EXIF DateTime isn't used, because it is when the file was last changed.
+    pub fn taken_at(&self) -> Option<(DateTime<Utc>, TakenAtSource)> {
+        let embedded = [
+            (self.exif_created_at, TakenAtSource::DateTimeOriginal),
+            (self.exif_digitized_at, TakenAtSource::DateTimeDigitized),
+            (self.xmp_created_at, TakenAtSource::Xmp),
+        ];
+
+        embedded
+            .into_iter()
+            .find_map(|(ts, source)| ts.map(|ts| (ts.to_utc(), source)))
+            .or_else(|| {
+                self.fs_modified_at
+                    .map(|ts| (ts, TakenAtSource::FileModified))
+            })
+    }
+
+    /// How much of the time the picture was taken is known.
+    /// Only XMP dates can leave out the month, day, or time.
+    pub fn taken_at_precision(&self) -> DatePrecision {
+        match self.taken_at() {
+            Some((_, TakenAtSource::Xmp)) => self.xmp_created_precision,
+            _ => DatePrecision::Exact,
+        }
+    }
+}
+
+/// Camera and exposure settings from EXIF tags. Any tag might be missing.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CameraSettings {
+    /// Camera manufacturer, such as "Apple".
+    pub make: Option<String>,
+
+    /// Camera model, such as "iPhone 12".
+    pub model: Option<String>,
+
+    /// ISO sensitivity.
+    pub iso: Option<u32>,
+
+    /// Aperture as an f-number. 1.8 means f/1.8.
+    pub f_number: Option<f64>,
+
+    /// Exposure time in seconds.
+    pub exposure_time: Option<f64>,
+
+    /// Focal length in millimetres.
+    pub focal_length: Option<f64>,
 }
 
 /// A video extracted from a motion photo
@@ -183,3 +447,161 @@ pub struct MotionPhotoVideo {
     // Should be 90, 180, 270, or the negative of those.
     pub rotation: Option<i32>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    #[test]
+    fn picture_id_round_trip() {
+        let con = Connection::open_in_memory().unwrap();
+        let picture_id = PictureId::new(1234);
+
+        let result: PictureId = con
+            .query_row("SELECT ?1", [picture_id], |row| row.get(0))
+            .unwrap();
+
+        assert_eq!(picture_id, result);
+    }
+
+    #[test]
+    fn unknown_orientation_is_identity() {
+        assert!(Orientation::from(0).display_transform().is_identity());
+        assert!(Orientation::from(9).display_transform().is_identity());
+        assert!(Orientation::default().display_transform().is_identity());
+    }
+
+    #[test]
+    fn display_transform_for_exif_orientation() {
+        let transform = |number: u32| {
+            let t = Orientation::from(number).display_transform();
+            (t.rotation, t.is_mirrored)
+        };
+
+        assert_eq!((0, false), transform(1));
+        assert_eq!((0, true), transform(2));
+        assert_eq!((180, false), transform(3));
+        assert_eq!((180, true), transform(4));
+        assert_eq!((90, true), transform(5));
+        assert_eq!((90, false), transform(6));
+        assert_eq!((270, true), transform(7));
+        assert_eq!((270, false), transform(8));
+    }
+
+    #[test]
+    fn taken_at_source_round_trip() {
+        let con = Connection::open_in_memory().unwrap();
+
+        let result: TakenAtSource = con
+            .query_row("SELECT ?1", [TakenAtSource::Xmp], |row| row.get(0))
+            .unwrap();
+
+        assert_eq!(TakenAtSource::Xmp, result);
+    }
+
+    #[test]
+    fn taken_at_prefers_most_reliable_source() {
+        let ts = |s: &str| DateTime::parse_from_rfc3339(s).unwrap();
+
+        let mut metadata = Metadata {
+            fs_modified_at: Some(ts("2024-04-04T04:04:04Z").to_utc()),
+            xmp_created_at: Some(ts("2023-03-03T03:03:03Z")),
+            exif_digitized_at: Some(ts("2022-02-02T02:02:02+02:00")),
+            exif_created_at: Some(ts("2021-01-01T01:01:01+01:00")),
+            ..Default::default()
+        };
+
+        let taken_at = |m: &Metadata| m.taken_at().map(|(ts, source)| (ts.to_rfc3339(), source));
+
+        assert_eq!(
+            Some((
+                "2021-01-01T00:01:01+00:00".to_string(),
+                TakenAtSource::DateTimeOriginal
+            )),
+            taken_at(&metadata)
+        );
+
+        metadata.exif_created_at = None;
+        assert_eq!(
+            Some((
+                "2022-02-02T00:02:02+00:00".to_string(),
+                TakenAtSource::DateTimeDigitized
+            )),
+            taken_at(&metadata)
+        );
+
+        metadata.exif_digitized_at = None;
+        assert_eq!(
+            Some(("2023-03-03T03:03:03+00:00".to_string(), TakenAtSource::Xmp)),
+            taken_at(&metadata)
+        );
+
+        metadata.xmp_created_at = None;
+        assert_eq!(
+            Some((
+                "2024-04-04T04:04:04+00:00".to_string(),
+                TakenAtSource::FileModified
+            )),
+            taken_at(&metadata)
+        );
+        assert!(TakenAtSource::FileModified.is_estimate());
+
+        metadata.fs_modified_at = None;
+        assert_eq!(None, taken_at(&metadata));
+    }
+
+    #[test]
+    fn taken_at_precision_only_from_xmp() {
+        let ts = |s: &str| DateTime::parse_from_rfc3339(s).unwrap();
+
+        let mut metadata = Metadata {
+            xmp_created_at: Some(ts("1985-01-01T00:00:00Z")),
+            xmp_created_precision: DatePrecision::Year,
+            exif_digitized_at: Some(ts("2022-02-02T02:02:02Z")),
+            ..Default::default()
+        };
+
+        // A scan's digitized time is preferred, and is when it was scanned.
+        assert_eq!(DatePrecision::Exact, metadata.taken_at_precision());
+
+        metadata.exif_digitized_at = None;
+        assert_eq!(DatePrecision::Year, metadata.taken_at_precision());
+        assert!(metadata.taken_at_precision().is_imprecise());
+        assert!(!DatePrecision::Day.is_imprecise());
+    }
+
+    #[test]
+    fn period_end_of_each_precision() {
+        let start = DateTime::parse_from_rfc3339("1985-02-01T00:00:00Z")
+            .unwrap()
+            .to_utc();
+
+        let end = |precision: DatePrecision| precision.period_end(start).to_rfc3339();
+
+        assert_eq!(
+            "1986-01-31T23:59:59.999999999+00:00",
+            end(DatePrecision::Year)
+        );
+        assert_eq!(
+            "1985-02-28T23:59:59.999999999+00:00",
+            end(DatePrecision::Month)
+        );
+        assert_eq!(
+            "1985-02-01T23:59:59.999999999+00:00",
+            end(DatePrecision::Day)
+        );
+        assert_eq!("1985-02-01T00:00:00+00:00", end(DatePrecision::Exact));
+    }
+
+    #[test]
+    fn date_precision_round_trip() {
+        let con = Connection::open_in_memory().unwrap();
+
+        let result: DatePrecision = con
+            .query_row("SELECT ?1", [DatePrecision::Month], |row| row.get(0))
+            .unwrap();
+
+        assert_eq!(DatePrecision::Month, result);
+    }
+}
@@ -0,0 +1,95 @@
+// SPDX-FileCopyrightText: © 2025 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Perceptual hashes for finding pictures that look alike, such as a resized or
+//! re-compressed copy, or a slightly edited version. Unlike a content hash, a small
+//! change to a picture makes a small change to its perceptual hash.
+//!
+//! A difference hash (dHash) records whether each pixel of a tiny greyscale copy of
+//! the picture is darker than the pixel to its right.
+//! See https://www.hackerfactor.com/blog/index.php?/archives/529-Kind-of-Like-That.html
+
+use image::DynamicImage;
+use image::imageops::FilterType;
+
+/// Width and height of the greyscale copy. One column wider than tall, so that each
+/// row gives eight comparisons.
+const HASH_WIDTH: u32 = 9;
+const HASH_HEIGHT: u32 = 8;
+
+/// 64-bit difference hash of a picture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PerceptualHash(u64);
+
+impl PerceptualHash {
+    /// Hashes an image. A thumbnail hashes the same as the full picture, so hashing a
+    /// thumbnail saves decoding the picture again.
+    pub fn from_image(image: &DynamicImage) -> PerceptualHash {
+        let small = image
+            .resize_exact(HASH_WIDTH, HASH_HEIGHT, FilterType::Triangle)
+            .to_luma8();
+
+        let mut hash = 0;
+        for y in 0..HASH_HEIGHT {
+            for x in 0..HASH_WIDTH - 1 {
+                let is_darker = small.get_pixel(x, y)[0] < small.get_pixel(x + 1, y)[0];
+                hash = (hash << 1) | u64::from(is_darker);
+            }
+        }
+
+        PerceptualHash(hash)
+    }
+
+    /// Number of bits that differ between two hashes, from 0 for pictures that look
+    /// the same up to 64. Pictures more than about 10 apart look different.
+    pub fn distance(&self, other: &PerceptualHash) -> u32 {
+        (self.0 ^ other.0).count_ones()
+    }
+
+    /// Bits of the hash as stored in the database, which only has signed integers.
+    pub fn to_i64(self) -> i64 {
+        self.0 as i64
+    }
+
+    pub fn from_i64(value: i64) -> PerceptualHash {
+        PerceptualHash(value as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GrayImage, Luma};
+
+    /// Image that gets lighter from left to right.
+    fn gradient(width: u32, height: u32) -> DynamicImage {
+        let image = GrayImage::from_fn(width, height, |x, _| Luma([(x * 200 / width) as u8 + 20]));
+        DynamicImage::ImageLuma8(image)
+    }
+
+    #[test]
+    fn resized_picture_looks_alike() {
+        let picture = PerceptualHash::from_image(&gradient(640, 480));
+        let resized = PerceptualHash::from_image(&gradient(64, 48));
+
+        assert_eq!(0, picture.distance(&resized));
+    }
+
+    #[test]
+    fn mirrored_picture_looks_different() {
+        let picture = gradient(640, 480);
+        let mirrored = picture.fliph();
+
+        let picture = PerceptualHash::from_image(&picture);
+        let mirrored = PerceptualHash::from_image(&mirrored);
+
+        assert_eq!(64, picture.distance(&mirrored));
+    }
+
+    #[test]
+    fn stored_hash_round_trips() {
+        let hash = PerceptualHash(u64::MAX - 1);
+        assert_eq!(hash, PerceptualHash::from_i64(hash.to_i64()));
+    }
+}
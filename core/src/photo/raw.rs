@@ -0,0 +1,396 @@
+// SPDX-FileCopyrightText: © 2025 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Pictures in camera RAW formats.
+//!
+//! The RAW formats recognised are all laid out as TIFF files, and hold one or more JPEG
+//! previews rendered by the camera. The largest preview is used in place of the RAW data,
+//! because it is far quicker to load and already has the camera's colour processing applied.
+//! Decoding the RAW data itself needs the `raw` feature.
+
+use anyhow::*;
+use image::{DynamicImage, ImageFormat, metadata::Orientation};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+use tracing::debug;
+
+const TAG_COMPRESSION: u16 = 0x0103;
+const TAG_STRIP_OFFSETS: u16 = 0x0111;
+const TAG_ORIENTATION: u16 = 0x0112;
+const TAG_STRIP_BYTE_COUNTS: u16 = 0x0117;
+const TAG_SUB_IFDS: u16 = 0x014A;
+const TAG_JPEG_OFFSET: u16 = 0x0201;
+const TAG_JPEG_LENGTH: u16 = 0x0202;
+
+const TYPE_SHORT: u16 = 3;
+const TYPE_LONG: u16 = 4;
+const TYPE_IFD: u16 = 13;
+
+/// Old-style and new-style JPEG compression.
+const COMPRESSION_JPEG: [u32; 2] = [6, 7];
+
+/// Limit on directories read, in case a malformed file links them in a loop.
+const MAX_IFDS: usize = 32;
+
+/// Limit on the size of a preview, so a malformed length can't exhaust memory.
+const MAX_PREVIEW_LENGTH: u32 = 128 * 1024 * 1024;
+
+/// A JPEG embedded in a RAW file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Preview {
+    offset: u32,
+    length: u32,
+}
+
+/// What the TIFF structure of a RAW file says, without reading any image data.
+#[derive(Debug, Default)]
+struct Layout {
+    previews: Vec<Preview>,
+    orientation: Option<u8>,
+}
+
+/// One entry of a TIFF image file directory.
+struct Entry {
+    tag: u16,
+    field_type: u16,
+    count: u32,
+    value: [u8; 4],
+}
+
+/// Reads values from a TIFF file in the file's byte order.
+struct TiffReader<R> {
+    reader: R,
+    is_little_endian: bool,
+}
+
+impl<R: Read + Seek> TiffReader<R> {
+    fn open(mut reader: R) -> Result<(TiffReader<R>, u32)> {
+        let mut header = [0; 4];
+        reader.read_exact(&mut header)?;
+        let is_little_endian = match header {
+            [b'I', b'I', 42, 0] => true,
+            [b'M', b'M', 0, 42] => false,
+            _ => bail!("Not a TIFF file"),
+        };
+        let mut tiff = TiffReader {
+            reader,
+            is_little_endian,
+        };
+        let first_ifd = tiff.read_u32()?;
+        Ok((tiff, first_ifd))
+    }
+
+    fn u16_from(&self, bytes: [u8; 2]) -> u16 {
+        if self.is_little_endian {
+            u16::from_le_bytes(bytes)
+        } else {
+            u16::from_be_bytes(bytes)
+        }
+    }
+
+    fn u32_from(&self, bytes: [u8; 4]) -> u32 {
+        if self.is_little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        }
+    }
+
+    fn read_u16(&mut self) -> Result<u16> {
+        let mut bytes = [0; 2];
+        self.reader.read_exact(&mut bytes)?;
+        Ok(self.u16_from(bytes))
+    }
+
+    fn read_u32(&mut self) -> Result<u32> {
+        let mut bytes = [0; 4];
+        self.reader.read_exact(&mut bytes)?;
+        Ok(self.u32_from(bytes))
+    }
+
+    /// Value of an entry holding a single number.
+    fn value(&self, entry: &Entry) -> Option<u32> {
+        if entry.count != 1 {
+            return None;
+        }
+        match entry.field_type {
+            TYPE_SHORT => Some(self.u16_from([entry.value[0], entry.value[1]]) as u32),
+            TYPE_LONG | TYPE_IFD => Some(self.u32_from(entry.value)),
+            _ => None,
+        }
+    }
+
+    /// Values of an entry holding a list of offsets, such as sub-directories.
+    fn offsets(&mut self, entry: &Entry) -> Result<Vec<u32>> {
+        if !matches!(entry.field_type, TYPE_LONG | TYPE_IFD) {
+            return Ok(vec![]);
+        }
+        if entry.count == 1 {
+            return Ok(vec![self.u32_from(entry.value)]);
+        }
+        self.reader
+            .seek(SeekFrom::Start(self.u32_from(entry.value) as u64))?;
+        (0..entry.count.min(MAX_IFDS as u32))
+            .map(|_| self.read_u32())
+            .collect()
+    }
+
+    /// Entries of the directory at an offset, and the offset of the next directory.
+    fn read_ifd(&mut self, offset: u32) -> Result<(Vec<Entry>, u32)> {
+        self.reader.seek(SeekFrom::Start(offset as u64))?;
+        let count = self.read_u16()?;
+        let mut entries = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let tag = self.read_u16()?;
+            let field_type = self.read_u16()?;
+            let count = self.read_u32()?;
+            let mut value = [0; 4];
+            self.reader.read_exact(&mut value)?;
+            entries.push(Entry {
+                tag,
+                field_type,
+                count,
+                value,
+            });
+        }
+        let next = self.read_u32()?;
+        Ok((entries, next))
+    }
+}
+
+impl Layout {
+    /// Finds every JPEG in the file that might be a preview. Some are the RAW data itself
+    /// compressed as lossless JPEG, which can't be decoded as a preview, so those are
+    /// weeded out when decoding.
+    fn read<R: Read + Seek>(reader: R) -> Result<Layout> {
+        let (mut tiff, first_ifd) = TiffReader::open(reader)?;
+        let mut layout = Layout::default();
+        let mut pending = vec![first_ifd];
+        let mut visited = 0;
+
+        while let Some(offset) = pending.pop() {
+            if offset == 0 || visited >= MAX_IFDS {
+                continue;
+            }
+            let is_first = visited == 0;
+            visited += 1;
+
+            let (entries, next) = tiff.read_ifd(offset)?;
+            pending.push(next);
+
+            let find = |tag: u16| {
+                entries
+                    .iter()
+                    .find(|entry| entry.tag == tag)
+                    .and_then(|entry| tiff.value(entry))
+            };
+
+            if is_first {
+                layout.orientation = find(TAG_ORIENTATION).map(|x| x as u8);
+            }
+
+            if let (Some(offset), Some(length)) = (find(TAG_JPEG_OFFSET), find(TAG_JPEG_LENGTH)) {
+                layout.previews.push(Preview { offset, length });
+            }
+
+            let is_jpeg = find(TAG_COMPRESSION).is_some_and(|x| COMPRESSION_JPEG.contains(&x));
+            if let (true, Some(offset), Some(length)) = (
+                is_jpeg,
+                find(TAG_STRIP_OFFSETS),
+                find(TAG_STRIP_BYTE_COUNTS),
+            ) {
+                layout.previews.push(Preview { offset, length });
+            }
+
+            if let Some(entry) = entries.iter().find(|entry| entry.tag == TAG_SUB_IFDS) {
+                pending.extend(tiff.offsets(entry)?);
+            }
+        }
+
+        layout.previews.retain(|p| p.length > 0);
+        layout.previews.dedup();
+        Ok(layout)
+    }
+}
+
+impl Preview {
+    fn decode<R: Read + Seek>(&self, reader: &mut R) -> Result<DynamicImage> {
+        if self.length > MAX_PREVIEW_LENGTH {
+            bail!("Preview of {} bytes is too large", self.length);
+        }
+        reader.seek(SeekFrom::Start(self.offset as u64))?;
+        let mut bytes = vec![0; self.length as usize];
+        reader.read_exact(&mut bytes)?;
+        if !bytes.starts_with(&[0xFF, 0xD8]) {
+            bail!("Preview is not a JPEG");
+        }
+        let image = image::load_from_memory_with_format(&bytes, ImageFormat::Jpeg)?;
+        Ok(image)
+    }
+}
+
+/// Does a RAW file have an embedded preview? Only reads the file's structure, so is
+/// cheap enough to call while scanning.
+pub fn has_embedded_preview(path: &Path) -> bool {
+    File::open(path)
+        .map_err(Error::from)
+        .and_then(|file| Layout::read(BufReader::new(file)))
+        .is_ok_and(|layout| !layout.previews.is_empty())
+}
+
+/// Can a RAW file be shown by this build? Either from an embedded preview, or by
+/// decoding the RAW data.
+pub fn is_displayable(path: &Path) -> bool {
+    cfg!(feature = "raw") || has_embedded_preview(path)
+}
+
+/// The largest embedded preview that can be decoded, turned the way the camera was held.
+/// `None` if there is no such preview.
+pub fn embedded_preview(path: &Path) -> Result<Option<DynamicImage>> {
+    let mut file = BufReader::new(File::open(path)?);
+    let layout = Layout::read(&mut file)?;
+
+    let mut previews = layout.previews;
+    previews.sort_by_key(|preview| std::cmp::Reverse(preview.length));
+
+    for preview in previews {
+        match preview.decode(&mut file) {
+            std::result::Result::Ok(mut image) => {
+                if let Some(orientation) = layout.orientation.and_then(Orientation::from_exif) {
+                    image.apply_orientation(orientation);
+                }
+                return Ok(Some(image));
+            }
+            Err(e) => debug!("Skipping preview in {:?}: {}", path, e),
+        }
+    }
+
+    Ok(None)
+}
+
+/// Decodes a RAW picture, preferring an embedded preview over the much slower
+/// decoding of the RAW data.
+pub fn decode(path: &Path) -> Result<DynamicImage> {
+    if let Some(image) = embedded_preview(path)? {
+        return Ok(image);
+    }
+    decode_raw_data(path)
+}
+
+#[cfg(feature = "raw")]
+fn decode_raw_data(path: &Path) -> Result<DynamicImage> {
+    let params = rawler::decoders::RawDecodeParams::default();
+    rawler::analyze::extract_full_pixels(path, &params)
+        .map_err(|e| anyhow!("Failed decoding RAW data of {:?}: {}", path, e))
+}
+
+#[cfg(not(feature = "raw"))]
+fn decode_raw_data(path: &Path) -> Result<DynamicImage> {
+    bail!(
+        "No embedded preview in {:?}, and RAW decoding is left out of this build",
+        path
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageEncoder, RgbImage, codecs::jpeg::JpegEncoder};
+    use std::io::Cursor;
+
+    fn jpeg(width: u32, height: u32) -> Vec<u8> {
+        let image = RgbImage::new(width, height);
+        let mut bytes = Vec::new();
+        JpegEncoder::new(&mut bytes)
+            .write_image(&image, width, height, image::ExtendedColorType::Rgb8)
+            .unwrap();
+        bytes
+    }
+
+    /// Little-endian TIFF with one directory made of (tag, type, value) entries,
+    /// followed by `data`, whose offset is passed to `entries`.
+    fn tiff(entries: impl Fn(u32) -> Vec<(u16, u16, u32)>, data: &[u8]) -> Vec<u8> {
+        // Header, then entry count, then entries, then next directory offset.
+        let entry_count = entries(0).len() as u32;
+        let data_offset = 8 + 2 + entry_count * 12 + 4;
+
+        let mut bytes = vec![b'I', b'I', 42, 0];
+        bytes.extend(8u32.to_le_bytes());
+        bytes.extend((entry_count as u16).to_le_bytes());
+        for (tag, field_type, value) in entries(data_offset) {
+            bytes.extend(tag.to_le_bytes());
+            bytes.extend(field_type.to_le_bytes());
+            bytes.extend(1u32.to_le_bytes());
+            bytes.extend(value.to_le_bytes());
+        }
+        bytes.extend(0u32.to_le_bytes());
+        bytes.extend(data);
+        bytes
+    }
+
+    #[test]
+    fn finds_jpeg_interchange_preview() {
+        let preview = jpeg(4, 2);
+        let length = preview.len() as u32;
+        let bytes = tiff(
+            |offset| {
+                vec![
+                    (TAG_ORIENTATION, TYPE_SHORT, 6),
+                    (TAG_JPEG_OFFSET, TYPE_LONG, offset),
+                    (TAG_JPEG_LENGTH, TYPE_LONG, length),
+                ]
+            },
+            &preview,
+        );
+
+        let layout = Layout::read(Cursor::new(&bytes)).unwrap();
+        assert_eq!(1, layout.previews.len());
+        assert_eq!(Some(6), layout.orientation);
+
+        let image = layout.previews[0].decode(&mut Cursor::new(&bytes)).unwrap();
+        assert_eq!((4, 2), (image.width(), image.height()));
+    }
+
+    #[test]
+    fn finds_jpeg_compressed_strip() {
+        let preview = jpeg(2, 2);
+        let length = preview.len() as u32;
+        let bytes = tiff(
+            |offset| {
+                vec![
+                    (TAG_COMPRESSION, TYPE_SHORT, 6),
+                    (TAG_STRIP_OFFSETS, TYPE_LONG, offset),
+                    (TAG_STRIP_BYTE_COUNTS, TYPE_LONG, length),
+                ]
+            },
+            &preview,
+        );
+
+        let layout = Layout::read(Cursor::new(&bytes)).unwrap();
+        assert_eq!(1, layout.previews.len());
+    }
+
+    #[test]
+    fn uncompressed_strip_is_not_a_preview() {
+        let bytes = tiff(
+            |offset| {
+                vec![
+                    (TAG_COMPRESSION, TYPE_SHORT, 1),
+                    (TAG_STRIP_OFFSETS, TYPE_LONG, offset),
+                    (TAG_STRIP_BYTE_COUNTS, TYPE_LONG, 4),
+                ]
+            },
+            &[0; 4],
+        );
+
+        let layout = Layout::read(Cursor::new(&bytes)).unwrap();
+        assert!(layout.previews.is_empty());
+    }
+
+    #[test]
+    fn rejects_non_tiff() {
+        assert!(Layout::read(Cursor::new(b"not a tiff file")).is_err());
+    }
+}
@@ -3,29 +3,50 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use crate::FlatpakPathBuf;
+use crate::LibraryRoots;
 use crate::ScannedFile;
+use crate::database;
+use crate::file_types;
+use crate::flatpak_path::{self, Availability};
+use crate::geocode;
 use crate::path_encoding;
-use crate::people::model::{DetectedFace, FaceDetectionCandidate, FaceId, Rect};
-use crate::photo::model::{Picture, PictureId};
+use crate::people::model::{DetectedFace, FaceDetectionCandidate, FaceId, PersonId, Rect};
+use crate::photo::model::{
+    BrokenPicture, CameraSettings, MissingPicture, Orientation, Picture, PictureId,
+};
+use crate::scanner::canonical_path;
+use crate::tag;
+use crate::thumbnailify::{self, ThumbnailSize};
+use crate::time;
 
 use super::Metadata;
+use super::content_hash::{self, ContentHash, FileStamp};
 use super::metadata;
+use super::metadata_export::{self, ExportFormat, MetadataRecord};
 use super::model::MotionPhotoVideo;
 use super::motion_photo;
-use anyhow::{Result, bail};
+use super::perceptual_hash::PerceptualHash;
+use super::xmp::{self, SidecarMetadata, SidecarPolicy};
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Datelike, Utc};
+use itertools::Itertools;
 use rusqlite;
+use rusqlite::OptionalExtension;
 use rusqlite::Row;
 use rusqlite::params;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{BufWriter, Write};
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
-use tracing::error;
+use tracing::{error, info};
 
 /// Repository of picture metadata.
 /// Repository is backed by a Sqlite database.
 #[derive(Debug, Clone)]
 pub struct Repository {
-    /// Base path to library
-    library_base_dir: FlatpakPathBuf,
+    /// Directories of the library
+    library_roots: LibraryRoots,
 
     /// Base path cache directory for motion photo videos
     cache_dir_base_path: PathBuf,
@@ -40,17 +61,17 @@ pub struct Repository {
 impl Repository {
     /// Builds a Repository and creates operational tables.
     pub fn open(
-        library_base_dir: &FlatpakPathBuf,
+        library_roots: &LibraryRoots,
         cache_dir_base_path: &Path,
         data_dir_base_path: &Path,
         con: Arc<Mutex<rusqlite::Connection>>,
     ) -> Result<Repository> {
-        if !library_base_dir.sandbox_path.is_dir() {
-            bail!("{:?} is not a directory", library_base_dir);
+        if !library_roots.primary().sandbox_path.is_dir() {
+            bail!("{:?} is not a directory", library_roots.primary());
         }
 
         let repo = Repository {
-            library_base_dir: library_base_dir.clone(),
+            library_roots: library_roots.clone(),
             cache_dir_base_path: cache_dir_base_path.into(),
             data_dir_base_path: data_dir_base_path.into(),
             con,
@@ -60,7 +81,7 @@ impl Repository {
     }
 
     pub fn add_metadatas(&mut self, pics: Vec<(PictureId, Metadata)>) -> Result<()> {
-        let mut con = self.con.lock().unwrap();
+        let mut con = database::lock(&self.con);
         let tx = con.transaction()?;
 
         {
@@ -74,7 +95,16 @@ impl Repository {
                     content_id = ?6,
                     orientation = ?7,
                     fs_created_ts = ?8,
-                    fs_modified_ts = ?9
+                    fs_modified_ts = ?9,
+                    camera_make = ?10,
+                    camera_model = ?11,
+                    iso = ?12,
+                    f_number = ?13,
+                    exposure_time = ?14,
+                    focal_length = ?15,
+                    taken_at_ts = ?16,
+                    taken_at_source = ?17,
+                    taken_at_precision = ?18
                 WHERE picture_id = ?1",
             )?;
 
@@ -82,16 +112,19 @@ impl Repository {
                 "INSERT INTO pictures_geo (
                     picture_id,
                     latitude,
-                    longitude
+                    longitude,
+                    place_name
                 ) VALUES (
-                    ?1, ?2, ?3
+                    ?1, ?2, ?3, ?4
                 ) ON CONFLICT (picture_id) DO UPDATE SET
                     latitude = ?2,
-                    longitude = ?3
+                    longitude = ?3,
+                    place_name = ?4
                 ",
             )?;
 
             for (picture_id, metadata) in pics {
+                let (taken_at, taken_at_source) = metadata.taken_at().unzip();
                 update_pictures.execute(params![
                     picture_id.id(),
                     metadata::VERSION,
@@ -99,9 +132,18 @@ impl Repository {
                     metadata.exif_modified_at,
                     metadata.is_selfie(),
                     metadata.content_id,
-                    metadata.orientation.map(|x| x as u8),
+                    metadata.orientation.unwrap_or_default() as u8,
                     metadata.fs_created_at,
                     metadata.fs_modified_at,
+                    metadata.camera.make,
+                    metadata.camera.model,
+                    metadata.camera.iso,
+                    metadata.camera.f_number,
+                    metadata.camera.exposure_time,
+                    metadata.camera.focal_length,
+                    taken_at,
+                    taken_at_source,
+                    metadata.taken_at_precision(),
                 ])?;
 
                 if let Some(location) = metadata.location {
@@ -110,8 +152,14 @@ impl Repository {
                     // the not-null constraint to be violated.
                     let latitude = location.latitude.to_f64_safe();
                     let longitude = location.longitude.to_f64_safe();
-                    if latitude.is_some() && longitude.is_some() {
-                        update_geo.execute(params![picture_id.id(), latitude, longitude,])?;
+                    if let (Some(latitude), Some(longitude)) = (latitude, longitude) {
+                        let place_name = geocode::place(latitude, longitude).map(|p| p.to_string());
+                        update_geo.execute(params![
+                            picture_id.id(),
+                            latitude,
+                            longitude,
+                            place_name,
+                        ])?;
                     }
                 }
             }
@@ -121,68 +169,1799 @@ impl Repository {
         Ok(())
     }
 
-    pub fn mark_broken(&mut self, picture_id: &PictureId) -> Result<()> {
-        let mut con = self.con.lock().unwrap();
+    /// Camera and exposure settings of a picture, as stored by `add_metadatas`.
+    pub fn camera_settings(&self, picture_id: &PictureId) -> Result<CameraSettings> {
+        let con = database::lock(&self.con);
+        let mut stmt = con.prepare_cached(
+            "SELECT
+                camera_make,
+                camera_model,
+                iso,
+                f_number,
+                exposure_time,
+                focal_length
+            FROM pictures
+            WHERE picture_id = ?1",
+        )?;
+
+        let camera = stmt.query_row([picture_id.id()], |row| {
+            std::result::Result::Ok(CameraSettings {
+                make: row.get("camera_make")?,
+                model: row.get("camera_model")?,
+                iso: row.get("iso")?,
+                f_number: row.get("f_number")?,
+                exposure_time: row.get("exposure_time")?,
+                focal_length: row.get("focal_length")?,
+            })
+        })?;
+
+        Ok(camera)
+    }
+
+    /// Leave a picture out of albums because it couldn't be processed.
+    /// The reason is kept, along with the time, to show the user.
+    pub fn mark_broken(&mut self, picture_id: &PictureId, reason: &str) -> Result<()> {
+        let mut con = database::lock(&self.con);
         let tx = con.transaction()?;
 
         {
             let mut stmt = tx.prepare_cached(
                 "UPDATE pictures
                 SET
-                    is_broken = TRUE
+                    is_broken = TRUE,
+                    broken_reason = ?2,
+                    broken_at = CURRENT_TIMESTAMP
                 WHERE picture_id = ?1",
             )?;
 
-            stmt.execute(params![picture_id.id(),])?;
+            stmt.execute(params![picture_id.id(), reason])?;
+
+            // A broken picture will never get a thumbnail, so stop waiting for one.
+            let mut stmt =
+                tx.prepare_cached("DELETE FROM pictures_thumbnail_queue WHERE picture_id = ?1")?;
+
+            stmt.execute(params![picture_id.id()])?;
         }
 
         tx.commit()?;
         Ok(())
     }
 
-    /// Add all Pictures received from a vector.
-    pub fn add_all(&mut self, pics: &Vec<ScannedFile>) -> Result<()> {
-        let mut con = self.con.lock().unwrap();
+    /// Forget a picture's thumbnail so that it is generated again.
+    /// Clears the blurhash, perceptual hash, and the broken flag, so a picture that
+    /// failed to thumbnail before is tried again.
+    pub fn reset_thumbnail(&mut self, picture_id: &PictureId) -> Result<()> {
+        let con = database::lock(&self.con);
+        let mut stmt = con.prepare_cached(
+            "UPDATE pictures
+            SET
+                blurhash = NULL,
+                perceptual_hash = NULL,
+                is_broken = FALSE,
+                broken_reason = NULL,
+                broken_at = NULL
+            WHERE picture_id = ?1",
+        )?;
+
+        stmt.execute(params![picture_id.id()])?;
+
+        Ok(())
+    }
+
+    /// Record whether a picture's file could be reached when last checked.
+    /// Unavailable pictures aren't broken, because a network share might come back.
+    pub fn set_availability(
+        &mut self,
+        picture_id: &PictureId,
+        availability: Availability,
+    ) -> Result<()> {
+        let con = database::lock(&self.con);
+        let mut stmt = con.prepare_cached(
+            "UPDATE pictures
+            SET
+                is_unavailable = ?2
+            WHERE picture_id = ?1",
+        )?;
+
+        stmt.execute(params![
+            picture_id.id(),
+            availability == Availability::Unavailable
+        ])?;
+
+        Ok(())
+    }
+
+    /// Star or unstar pictures as favorites.
+    /// Favorite is user data, so it isn't touched when the library is rescanned.
+    pub fn set_favorite(&mut self, picture_ids: &[PictureId], is_favorite: bool) -> Result<()> {
+        let mut con = database::lock(&self.con);
         let tx = con.transaction()?;
 
-        // Create a scope to make borrowing of tx not be an error.
         {
-            let mut pic_insert_stmt = tx.prepare_cached(
-                "INSERT INTO pictures (
-                    picture_path_b64,
-                    picture_path_lossy,
-                    link_path_b64,
-                    link_path_lossy,
-                    insert_ts
-                ) VALUES (
-                    ?1, ?2, ?3, ?4, CURRENT_TIMESTAMP
-                ) ON CONFLICT(picture_path_b64) DO NOTHING",
+            let mut stmt = tx.prepare_cached(
+                "UPDATE pictures
+                SET
+                    is_favorite = ?2
+                WHERE picture_id = ?1",
             )?;
 
-            for scanned_file in pics {
-                if let ScannedFile::Photo(path) = scanned_file {
-                    // convert to relative path before saving to database
-                    let picture_path = path.strip_prefix(&self.library_base_dir.sandbox_path)?;
-                    let picture_path_b64 = path_encoding::to_base64(picture_path);
+            for picture_id in picture_ids {
+                stmt.execute(params![picture_id.id(), is_favorite])?;
+            }
+        }
 
-                    // Path without suffix so sibling pictures and videos can be related
-                    let link_path = picture_path
-                        .file_stem()
-                        .and_then(|x| x.to_str())
-                        .expect("Must exist");
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Mark or unmark a picture as private, so that it is hidden while private pictures
+    /// are locked. Privacy is user data, so it isn't touched when the library is rescanned.
+    pub fn set_private(&mut self, picture_id: &PictureId, is_private: bool) -> Result<()> {
+        let con = database::lock(&self.con);
+        let mut stmt = con.prepare_cached(
+            "UPDATE pictures
+            SET
+                is_private = ?2
+            WHERE picture_id = ?1",
+        )?;
+
+        stmt.execute(params![picture_id.id(), is_private])?;
+
+        Ok(())
+    }
+
+    /// Choose a picture as the cover of a folder, in place of the folder's first item.
+    /// Cover is user data, so it isn't touched when the library is rescanned.
+    pub fn set_folder_cover(&mut self, folder_path: &Path, picture_id: &PictureId) -> Result<()> {
+        let con = database::lock(&self.con);
+        let mut stmt = con.prepare_cached(
+            "INSERT INTO folder_covers (folder_path_b64, picture_id)
+            VALUES (?1, ?2)
+            ON CONFLICT(folder_path_b64) DO UPDATE SET picture_id = excluded.picture_id",
+        )?;
+
+        stmt.execute(params![
+            path_encoding::to_base64(folder_path),
+            picture_id.id()
+        ])?;
+
+        Ok(())
+    }
+
+    /// Hide a folder, and the folders beneath it, from the folders album, or show it again.
+    /// Showing a folder also shows any hidden folder containing it, otherwise the folder
+    /// would stay hidden.
+    /// Hiding is user data, so it isn't touched when the library is rescanned.
+    pub fn set_folder_hidden(&mut self, folder_path: &Path, is_hidden: bool) -> Result<()> {
+        let con = database::lock(&self.con);
+
+        if is_hidden {
+            let mut stmt = con.prepare_cached(
+                "INSERT INTO hidden_folders (folder_path_b64) VALUES (?1)
+                ON CONFLICT DO NOTHING",
+            )?;
+            stmt.execute(params![path_encoding::to_base64(folder_path)])?;
+            return Ok(());
+        }
+
+        let mut stmt = con.prepare_cached("SELECT folder_path_b64 FROM hidden_folders")?;
+        let containing: Vec<String> = stmt
+            .query_map([], |row| row.get(0))?
+            .flatten()
+            .filter(|hidden: &String| {
+                path_encoding::from_base64(hidden)
+                    .is_ok_and(|hidden| folder_path.starts_with(hidden))
+            })
+            .collect();
+
+        let mut stmt =
+            con.prepare_cached("DELETE FROM hidden_folders WHERE folder_path_b64 = ?1")?;
+        for hidden in containing {
+            stmt.execute(params![hidden])?;
+        }
+
+        Ok(())
+    }
+
+    /// Store the blurhashes computed when pictures were thumbnailed, and remove the
+    /// pictures from the thumbnail queue.
+    /// Perceptual hashes are cleared, so that they are computed again from the new
+    /// thumbnails.
+    /// Written in a single transaction so thumbnailing doesn't contend on the database
+    /// with a write per picture.
+    pub fn set_blurhashes(&mut self, blurhashes: &[(PictureId, String)]) -> Result<()> {
+        let mut con = database::lock(&self.con);
+        let tx = con.transaction()?;
+
+        {
+            let mut stmt = tx.prepare_cached(
+                "UPDATE pictures
+                SET
+                    blurhash = ?2,
+                    perceptual_hash = NULL
+                WHERE picture_id = ?1",
+            )?;
+
+            for (picture_id, blurhash) in blurhashes {
+                stmt.execute(params![picture_id.id(), blurhash])?;
+            }
+
+            let mut stmt =
+                tx.prepare_cached("DELETE FROM pictures_thumbnail_queue WHERE picture_id = ?1")?;
+
+            for (picture_id, _) in blurhashes {
+                stmt.execute(params![picture_id.id()])?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Move pictures to the trash. The files on disk are untouched.
+    /// Returns the pictures that weren't already in the trash.
+    pub fn trash(&mut self, picture_ids: &[PictureId]) -> Result<Vec<PictureId>> {
+        self.set_trashed(
+            picture_ids,
+            "UPDATE pictures
+            SET
+                trashed_at = CURRENT_TIMESTAMP
+            WHERE picture_id = ?1
+            AND trashed_at IS NULL",
+        )
+    }
+
+    /// Move pictures to the trash on the user's behalf, such as pictures no longer in
+    /// the library. Emptying the trash only removes them from the database, because the
+    /// user never asked for their files to be deleted.
+    /// Returns the pictures that weren't already in the trash.
+    fn auto_trash(&mut self, picture_ids: &[PictureId]) -> Result<Vec<PictureId>> {
+        self.set_trashed(
+            picture_ids,
+            "UPDATE pictures
+            SET
+                trashed_at = CURRENT_TIMESTAMP,
+                is_auto_trashed = TRUE
+            WHERE picture_id = ?1
+            AND trashed_at IS NULL",
+        )
+    }
+
+    /// Restore pictures from the trash.
+    /// Returns the pictures that were in the trash.
+    pub fn restore(&mut self, picture_ids: &[PictureId]) -> Result<Vec<PictureId>> {
+        self.set_trashed(
+            picture_ids,
+            "UPDATE pictures
+            SET
+                trashed_at = NULL,
+                is_auto_trashed = FALSE
+            WHERE picture_id = ?1
+            AND trashed_at IS NOT NULL",
+        )
+    }
+
+    /// Runs a trash or restore statement for each picture, all or nothing.
+    /// Returns the pictures the statement changed.
+    fn set_trashed(&mut self, picture_ids: &[PictureId], sql: &str) -> Result<Vec<PictureId>> {
+        let mut con = database::lock(&self.con);
+        let tx = con.transaction()?;
+
+        let mut changed = Vec::new();
+
+        {
+            let mut stmt = tx.prepare_cached(sql)?;
+
+            for picture_id in picture_ids {
+                if stmt.execute(params![picture_id.id()])? > 0 {
+                    changed.push(*picture_id);
+                }
+            }
+        }
+
+        tx.commit()?;
+        Ok(changed)
+    }
+
+    /// Moves a picture's file into another folder of the library, along with the video
+    /// of a live photo, its XMP sidecar, and their thumbnails. A number is added to the
+    /// file names if the folder already has a file of the same name, as in
+    /// `IMG_0001 (1).jpg`, and the files that go with the picture are named to match.
+    /// The folder may be a document portal path from the folder chooser.
+    /// The database isn't changed if the files can't be moved, and the files are moved
+    /// back if the database can't be changed.
+    /// Returns the new path of the picture.
+    pub fn move_picture(
+        &mut self,
+        picture_id: &PictureId,
+        dest_folder: &FlatpakPathBuf,
+        thumbnailer: &thumbnailify::Thumbnailer,
+    ) -> Result<FlatpakPathBuf> {
+        let Some(picture) = self.get(picture_id)? else {
+            bail!("No picture with id {}", picture_id);
+        };
+
+        let Some(dest_folder) = self
+            .library_roots
+            .to_root_path(dest_folder)
+            .filter(|dir| dir.is_dir())
+        else {
+            bail!("{:?} is not a folder in the library", dest_folder.host_path);
+        };
+
+        let source = picture.sandbox_path();
+        if source.parent() == Some(dest_folder.as_path()) {
+            return Ok(picture.path);
+        }
+
+        let Some(stem) = source.file_stem() else {
+            bail!("{:?} has no file name", source);
+        };
+
+        let videos = self.live_photo_videos(picture_id)?;
+        let sidecar = xmp::find_sidecar(source);
+
+        let sources: Vec<&Path> = std::iter::once(source)
+            .chain(videos.iter().map(|(_, video)| video.sandbox_path.as_path()))
+            .chain(sidecar.as_deref())
+            .collect();
+        let targets = unique_destinations(&dest_folder, &stem.to_string_lossy(), &sources);
+
+        let canonical_dir = canonical_path(&dest_folder);
+        let canonical_target =
+            |target: &Path| canonical_dir.join(target.file_name().expect("Must have file name"));
+
+        let picture_path = self.library_roots.to_stored_path(&targets[0])?;
+        let link_path =
+            picture_path.with_file_name(picture_path.file_stem().expect("Must have file name"));
+
+        let mut con = database::lock(&self.con);
+        let tx = con.transaction()?;
+
+        set_picture_path(
+            &tx,
+            picture_id,
+            &picture_path,
+            &canonical_target(&targets[0]),
+        )?;
+
+        let mut moved_videos = Vec::new();
+        for ((video_id, video), target) in videos.iter().zip(&targets[1..]) {
+            let video_path = self.library_roots.to_stored_path(target)?;
+            set_video_path(
+                &tx,
+                *video_id,
+                &video_path,
+                &link_path,
+                &canonical_target(target),
+            )?;
+            moved_videos.push((video, self.library_roots.resolve(&video_path)));
+        }
+
+        // Dropping the transaction without committing rolls it back.
+        let moves: Vec<(&Path, &Path)> = sources
+            .iter()
+            .copied()
+            .zip(targets.iter().map(PathBuf::as_path))
+            .collect();
+        move_files(&moves)?;
+
+        if let Err(e) = tx.commit() {
+            move_files_back(&moves);
+            return Err(e.into());
+        }
+
+        drop(con);
+
+        // Thumbnails that can't be moved are generated again for the new path.
+        let moved = self.library_roots.resolve(&picture_path);
+        for (from, to) in std::iter::once((&picture.path, &moved))
+            .chain(moved_videos.iter().map(|(from, to)| (*from, to)))
+        {
+            if let Err(e) = thumbnailer.move_thumbnails(from, to) {
+                error!(
+                    "Failed moving thumbnails of {:?}: {:?}",
+                    from.sandbox_path, e
+                );
+            }
+        }
+
+        Ok(moved)
+    }
+
+    /// Videos of a live photo, which share the picture's name and content ID.
+    fn live_photo_videos(&self, picture_id: &PictureId) -> Result<Vec<(i64, FlatpakPathBuf)>> {
+        let con = database::lock(&self.con);
+        let mut stmt = con.prepare(
+            "SELECT videos.video_id, videos.video_path_b64
+            FROM pictures
+            INNER JOIN videos USING (link_path_b64, content_id)
+            WHERE pictures.picture_id = ?1",
+        )?;
+
+        let videos = stmt
+            .query_map(params![picture_id.id()], |row| {
+                let video_id: i64 = row.get(0)?;
+                let video_path: String = row.get(1)?;
+                std::result::Result::Ok((video_id, video_path))
+            })?
+            .flatten()
+            .filter_map(|(video_id, video_path)| {
+                let video_path = path_encoding::from_base64(&video_path).ok()?;
+                Some((video_id, self.library_roots.resolve(&video_path)))
+            })
+            .collect();
+
+        Ok(videos)
+    }
+
+    /// Flags pictures as missing if their file no longer exists, so that the user can see
+    /// what has gone missing and point Fotema to where it went, rather than pictures
+    /// silently disappearing. Pictures whose file is found again are no longer missing,
+    /// and have when they were last seen updated.
+    /// Pictures whose directory has been removed from the library are moved to the trash,
+    /// but emptying the trash won't delete their files.
+    /// Pictures that can't be reached, such as on a disconnected network share, are
+    /// marked unavailable rather than missing. Every picture's availability is checked
+    /// again, so pictures become available again once the share is back.
+    /// Returns the number of pictures newly missing or trashed.
+    pub fn reconcile_missing(&mut self) -> Result<usize> {
+        // A whole directory is unavailable when its share is, so check each
+        // directory first rather than waiting for every file in it to time out.
+        let mut directories: HashMap<PathBuf, Availability> = HashMap::new();
+
+        let mut outside = Vec::new();
+        let mut missing = Vec::new();
+        let mut seen = Vec::new();
+
+        for (picture_id, path) in self.paths_including_missing()? {
+            if !self.library_roots.contains(&path.sandbox_path) {
+                outside.push(picture_id);
+                continue;
+            }
+
+            let directory = path
+                .sandbox_path
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_default();
+
+            let directory_availability =
+                *directories.entry(directory.clone()).or_insert_with(|| {
+                    flatpak_path::availability(&directory, flatpak_path::AVAILABILITY_TIMEOUT)
+                });
+
+            let availability = match directory_availability {
+                Availability::Available => path.availability(),
+                other => other,
+            };
+
+            match availability {
+                Availability::Available => seen.push(picture_id),
+                Availability::Missing => missing.push(picture_id),
+                Availability::Unavailable => self.set_availability(&picture_id, availability)?,
+            }
+        }
+
+        self.auto_trash(&outside)?;
+
+        let now = Utc::now();
+        let mut newly_missing = 0;
+
+        let mut con = database::lock(&self.con);
+        let tx = con.transaction()?;
+
+        {
+            let mut stmt = tx.prepare_cached(
+                "UPDATE pictures
+                SET
+                    is_missing = FALSE,
+                    is_unavailable = FALSE,
+                    last_seen_ts = ?2
+                WHERE picture_id = ?1",
+            )?;
+
+            for picture_id in &seen {
+                stmt.execute(params![picture_id.id(), now])?;
+            }
+
+            let mut stmt = tx.prepare_cached(
+                "UPDATE pictures
+                SET
+                    is_missing = TRUE
+                WHERE picture_id = ?1
+                AND is_missing IS FALSE",
+            )?;
+
+            for picture_id in &missing {
+                newly_missing += stmt.execute(params![picture_id.id()])?;
+            }
+        }
+
+        tx.commit()?;
+
+        Ok(outside.len() + newly_missing)
+    }
+
+    /// Paths of all pictures, including missing pictures, except trashed and broken pictures.
+    fn paths_including_missing(&self) -> Result<Vec<(PictureId, FlatpakPathBuf)>> {
+        let con = database::lock(&self.con);
+        let mut stmt = con.prepare(
+            "SELECT
+                    picture_id,
+                    picture_path_b64
+                FROM pictures
+                WHERE COALESCE(is_broken, FALSE) IS FALSE
+                AND trashed_at IS NULL",
+        )?;
+
+        let result = stmt
+            .query_map([], |row| {
+                let relative_path: String = row.get("picture_path_b64")?;
+                let relative_path = path_encoding::from_base64(&relative_path)
+                    .map_err(|_| rusqlite::Error::InvalidQuery)?;
+
+                std::result::Result::Ok((
+                    row.get("picture_id").map(PictureId::new)?,
+                    self.library_roots.resolve(&relative_path),
+                ))
+            })?
+            .flatten()
+            .collect();
+
+        Ok(result)
+    }
+
+    /// Flags a picture as missing because its file has gone from disk.
+    /// The picture is left out of albums until its file is found again.
+    pub fn mark_missing(&mut self, picture_id: &PictureId) -> Result<()> {
+        let con = database::lock(&self.con);
+        let mut stmt = con.prepare_cached(
+            "UPDATE pictures
+            SET
+                is_missing = TRUE
+            WHERE picture_id = ?1",
+        )?;
+
+        stmt.execute(params![picture_id.id()])?;
+
+        Ok(())
+    }
+
+    /// Pictures whose file has gone from disk, except trashed pictures,
+    /// most recently seen first.
+    pub fn missing(&self) -> Result<Vec<MissingPicture>> {
+        let con = database::lock(&self.con);
+        let mut stmt = con.prepare(
+            "SELECT
+                    picture_id,
+                    picture_path_b64,
+                    last_seen_ts
+                FROM pictures
+                WHERE is_missing IS TRUE
+                AND trashed_at IS NULL
+                ORDER BY last_seen_ts DESC, picture_id ASC",
+        )?;
+
+        let result = stmt
+            .query_map([], |row| {
+                let relative_path: String = row.get("picture_path_b64")?;
+                let relative_path = path_encoding::from_base64(&relative_path)
+                    .map_err(|_| rusqlite::Error::InvalidQuery)?;
+
+                std::result::Result::Ok(MissingPicture {
+                    picture_id: row.get("picture_id").map(PictureId::new)?,
+                    path: self.library_roots.resolve(&relative_path),
+                    last_seen_at: row.get("last_seen_ts")?,
+                })
+            })?
+            .flatten()
+            .collect();
+
+        Ok(result)
+    }
+
+    /// Points a missing picture to where its file has gone, such as after being moved or
+    /// renamed outside Fotema. The file must be in the library and have the same contents
+    /// as when the picture was hashed, so that a different picture can't take over the
+    /// favorite, tags, and faces of the missing one. Thumbnails are moved rather than
+    /// generated again.
+    /// If the file has already been scanned into the library as another picture, that
+    /// picture is removed in favor of the missing one.
+    /// The file may be a document portal path from the file chooser.
+    /// Returns the new path of the picture, or None if the file's contents don't match.
+    pub fn locate(
+        &mut self,
+        picture_id: &PictureId,
+        found: &FlatpakPathBuf,
+        thumbnailer: &thumbnailify::Thumbnailer,
+    ) -> Result<Option<FlatpakPathBuf>> {
+        let Some(picture) = self.get(picture_id)? else {
+            bail!("No picture with id {}", picture_id);
+        };
+
+        let Some(found) = self.library_roots.to_root_path(found) else {
+            bail!("{:?} is not in the library", found.host_path);
+        };
+        let found = found.as_path();
+
+        let Some(file_name) = found.file_name() else {
+            bail!("{:?} has no file name", found);
+        };
+
+        let expected_hash: Option<String> = database::lock(&self.con).query_row(
+            "SELECT content_hash FROM pictures WHERE picture_id = ?1",
+            params![picture_id.id()],
+            |row| row.get(0),
+        )?;
+
+        let Some(expected_hash) = expected_hash else {
+            bail!("Picture {} was never hashed", picture_id);
+        };
+
+        let content_hash = content_hash::from_path(found)?;
+        if content_hash.hash != expected_hash {
+            return Ok(None);
+        }
+
+        let picture_path = self.library_roots.to_stored_path(found)?;
+        let canonical_path = found
+            .parent()
+            .map(canonical_path)
+            .unwrap_or_default()
+            .join(file_name);
+
+        let duplicate: Option<PictureId> = database::lock(&self.con)
+            .query_row(
+                "SELECT picture_id FROM pictures
+                WHERE picture_path_b64 = ?1
+                AND picture_id != ?2",
+                params![path_encoding::to_base64(&picture_path), picture_id.id()],
+                |row| row.get(0).map(PictureId::new),
+            )
+            .optional()?;
+
+        if let Some(duplicate) = duplicate {
+            info!(
+                "Removing picture {} in favor of located picture {}",
+                duplicate, picture_id
+            );
+            self.remove_derived_files(duplicate);
+            self.remove(duplicate)?;
+        }
+
+        let mut con = database::lock(&self.con);
+        let tx = con.transaction()?;
+
+        set_picture_path(&tx, picture_id, &picture_path, &canonical_path)?;
+
+        // The file's stamp may have changed with the move, but its contents haven't,
+        // so record the new stamp rather than hashing the file again.
+        tx.execute(
+            "UPDATE pictures
+            SET
+                is_missing = FALSE,
+                is_unavailable = FALSE,
+                last_seen_ts = ?2,
+                content_hash_file_size = ?3,
+                content_hash_fs_modified_ts = ?4
+            WHERE picture_id = ?1",
+            params![
+                picture_id.id(),
+                Utc::now(),
+                content_hash.stamp.file_size,
+                content_hash.stamp.fs_modified_at,
+            ],
+        )?;
+
+        tx.commit()?;
+        drop(con);
+
+        // Thumbnails that can't be moved are generated again for the new path.
+        let located = self.library_roots.resolve(&picture_path);
+        if let Err(e) = thumbnailer.move_thumbnails(&picture.path, &located) {
+            error!("Failed moving thumbnails of {:?}: {:?}", found, e);
+        }
+
+        Ok(Some(located))
+    }
+
+    /// Removes a picture from the library, along with its thumbnails, wherever they are
+    /// saved, and any files derived from it, such as the video of a motion photo.
+    /// The picture's own file isn't touched. For a missing picture whose file is gone for good.
+    pub fn remove_from_library(
+        &mut self,
+        picture_id: &PictureId,
+        thumbnailer: &thumbnailify::Thumbnailer,
+    ) -> Result<()> {
+        let Some(picture) = self.get(picture_id)? else {
+            bail!("No picture with id {}", picture_id);
+        };
+
+        self.remove_derived_files(*picture_id);
+        self.remove(*picture_id)?;
+
+        if let Err(e) = thumbnailer.remove_thumbnails(&picture.path) {
+            error!("Failed removing thumbnails of {}: {:?}", picture_id, e);
+        }
+
+        Ok(())
+    }
+
+    /// Deletes files derived from a picture, such as the video of a motion photo.
+    fn remove_derived_files(&self, picture_id: PictureId) {
+        let paths = match self.find_files_to_cleanup(picture_id) {
+            Ok(paths) => paths,
+            Err(e) => {
+                error!("Failed finding files of picture {}: {:?}", picture_id, e);
+                return;
+            }
+        };
+
+        for path in paths.iter().filter(|path| path.exists()) {
+            if let Err(e) = fs::remove_file(path) {
+                error!("Failed deleting {:?} with {}", path, e);
+            }
+        }
+    }
+
+    /// Move pictures to the trash if their file matches a pattern of files and folders
+    /// to leave out of the library, such as a pattern added since the picture was scanned.
+    /// Returns the number of pictures trashed.
+    pub fn trash_ignored(&mut self, is_ignored: impl Fn(&Path) -> bool) -> Result<usize> {
+        let ignored: Vec<PictureId> = self
+            .all()?
+            .into_iter()
+            .filter(|pic| is_ignored(pic.sandbox_path()))
+            .map(|pic| pic.picture_id)
+            .collect();
+
+        self.auto_trash(&ignored)?;
+
+        Ok(ignored.len())
+    }
+
+    /// Gets all trashed pictures, in ascending order of modification timestamp.
+    pub fn trashed(&self) -> Result<Vec<Picture>> {
+        let con = database::lock(&self.con);
+        let mut stmt = con.prepare(
+            "SELECT
+                    pictures.picture_id,
+                    pictures.picture_path_b64,
+                    COALESCE(
+                        pictures.taken_at_ts,
+                        pictures.exif_created_ts,
+                        pictures.exif_modified_ts,
+                        pictures.fs_created_ts,
+                        pictures.fs_modified_ts,
+                        pictures.insert_ts
+                      ) AS ordering_ts,
+                    pictures.is_selfie,
+                    pictures.orientation,
+                    pictures.is_favorite
+                FROM pictures
+                WHERE trashed_at IS NOT NULL
+                ORDER BY ordering_ts ASC",
+        )?;
+
+        let result = stmt
+            .query_map([], |row| self.to_picture(row))?
+            .flatten()
+            .collect();
+
+        Ok(result)
+    }
+
+    /// Remove all trashed pictures from the database, along with their thumbnails and any
+    /// cached files derived from them. If `delete_files` is true, then pictures the user
+    /// trashed are also deleted from disk. Otherwise a later library scan will add them back.
+    /// Pictures trashed automatically are never deleted from disk.
+    /// Returns the number of pictures removed.
+    pub fn empty_trash(
+        &mut self,
+        delete_files: bool,
+        thumbnailer: &thumbnailify::Thumbnailer,
+    ) -> Result<usize> {
+        let trashed: Vec<(PictureId, FlatpakPathBuf, bool)> = {
+            let con = database::lock(&self.con);
+            let mut stmt = con.prepare(
+                "SELECT
+                    picture_id,
+                    picture_path_b64,
+                    is_auto_trashed
+                FROM pictures
+                WHERE trashed_at IS NOT NULL",
+            )?;
+
+            stmt.query_map([], |row| {
+                let relative_path: String = row.get("picture_path_b64")?;
+                let relative_path = path_encoding::from_base64(&relative_path)
+                    .map_err(|_| rusqlite::Error::InvalidQuery)?;
+
+                std::result::Result::Ok((
+                    row.get("picture_id").map(PictureId::new)?,
+                    self.library_roots.resolve(&relative_path),
+                    row.get("is_auto_trashed")?,
+                ))
+            })?
+            .flatten()
+            .collect()
+        };
+
+        for (picture_id, path, is_auto_trashed) in &trashed {
+            let mut paths = self.find_files_to_cleanup(*picture_id)?;
+            if delete_files && !is_auto_trashed {
+                paths.push(path.sandbox_path.clone());
+            }
+
+            if let Err(e) = thumbnailer.remove_thumbnails(path) {
+                error!("Failed removing thumbnails of {}: {:?}", picture_id, e);
+            }
+
+            for path in paths {
+                if !path.exists() {
+                    continue;
+                }
+                if let Err(e) = std::fs::remove_file(&path) {
+                    error!("Failed deleting {:?} with {}", path, e);
+                }
+            }
+
+            self.remove(*picture_id)?;
+        }
+
+        Ok(trashed.len())
+    }
+
+    /// Add all Pictures received from a vector.
+    pub fn add_all(&mut self, pics: &Vec<ScannedFile>) -> Result<()> {
+        let mut con = database::lock(&self.con);
+        let tx = con.transaction()?;
+
+        // Create a scope to make borrowing of tx not be an error.
+        {
+            // A picture already in the library by another path isn't added again.
+            let mut pic_insert_stmt = tx.prepare_cached(
+                "INSERT INTO pictures (
+                    picture_path_b64,
+                    picture_path_lossy,
+                    link_path_b64,
+                    link_path_lossy,
+                    canonical_path_b64,
+                    insert_ts
+                ) VALUES (
+                    ?1, ?2, ?3, ?4, ?5, CURRENT_TIMESTAMP
+                ) ON CONFLICT DO NOTHING",
+            )?;
+
+            // Pictures added before canonical paths were recorded.
+            let mut canonical_update_stmt = tx.prepare_cached(
+                "UPDATE OR IGNORE pictures
+                SET canonical_path_b64 = ?2
+                WHERE picture_path_b64 = ?1
+                AND canonical_path_b64 IS NULL",
+            )?;
+
+            // A picture added twice before canonical paths were recorded. The other path
+            // has already taken the canonical path, so this path is a duplicate.
+            // It is trashed rather than deleted so that its favorite, tags, faces, and
+            // albums aren't lost. Emptying the trash won't delete the file, which both
+            // paths share.
+            let mut duplicate_trash_stmt = tx.prepare_cached(
+                "UPDATE pictures
+                SET
+                    trashed_at = CURRENT_TIMESTAMP,
+                    is_auto_trashed = TRUE
+                WHERE picture_path_b64 = ?1
+                AND canonical_path_b64 IS NULL
+                AND trashed_at IS NULL
+                AND EXISTS (SELECT 1 FROM pictures WHERE canonical_path_b64 = ?2)",
+            )?;
+
+            let mut byte_size_stmt =
+                tx.prepare_cached("SELECT byte_size FROM pictures WHERE picture_path_b64 = ?1")?;
+
+            let mut file_details_update_stmt = tx.prepare_cached(
+                "UPDATE pictures
+                SET
+                    width = ?2,
+                    height = ?3,
+                    byte_size = ?4
+                WHERE picture_path_b64 = ?1",
+            )?;
+
+            for scanned_file in pics {
+                if let ScannedFile::Photo(path) = scanned_file {
+                    // convert to relative path before saving to database
+                    let picture_path = self.library_roots.to_stored_path(path)?;
+                    let picture_path_b64 = path_encoding::to_base64(&picture_path);
+
+                    let canonical_path_b64 = path_encoding::to_base64(&canonical_path(path));
+
+                    // Path without suffix so sibling pictures and videos can be related
+                    let link_path = picture_path
+                        .file_stem()
+                        .and_then(|x| x.to_str())
+                        .expect("Must exist");
+
+                    let link_path = picture_path.with_file_name(link_path);
+                    let link_path_b64 = path_encoding::to_base64(&link_path);
+
+                    pic_insert_stmt.execute(params![
+                        picture_path_b64,
+                        picture_path.to_string_lossy(),
+                        link_path_b64,
+                        link_path.to_string_lossy(),
+                        canonical_path_b64,
+                    ])?;
+
+                    canonical_update_stmt.execute(params![picture_path_b64, canonical_path_b64])?;
+                    duplicate_trash_stmt.execute(params![picture_path_b64, canonical_path_b64])?;
+
+                    // Reading dimensions means opening the file, so is only done for new
+                    // or changed files. A changed file is assumed to have changed size.
+                    let byte_size = fs::metadata(path).ok().map(|m| m.len());
+                    let stored_byte_size: Option<u64> = byte_size_stmt
+                        .query_row([&picture_path_b64], |row| row.get(0))
+                        .optional()?
+                        .flatten();
+
+                    if byte_size.is_some() && byte_size != stored_byte_size {
+                        let dimensions = dimensions(path);
+                        file_details_update_stmt.execute(params![
+                            picture_path_b64,
+                            dimensions.map(|(width, _)| width),
+                            dimensions.map(|(_, height)| height),
+                            byte_size,
+                        ])?;
+                    }
+                } else {
+                    error!("Expected a photo, but got: {:?}", scanned_file);
+                }
+            }
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Gets all pictures in the repository, except trashed and missing pictures,
+    /// in ascending order of modification timestamp.
+    pub fn all(&self) -> Result<Vec<Picture>> {
+        let mut pictures = Vec::new();
+        self.for_each(|pic| pictures.push(pic))?;
+        Ok(pictures)
+    }
+
+    /// Gets a picture, or `None` if there is no picture with the ID.
+    /// Broken and trashed pictures are returned too, because a picture is fetched by ID
+    /// to act on it, such as to restore it from the trash or to regenerate its thumbnail.
+    /// Callers that only want pictures shown in the library must check `is_broken`
+    /// themselves.
+    pub fn get(&self, picture_id: &PictureId) -> Result<Option<Picture>> {
+        let con = database::lock(&self.con);
+        let mut stmt = con.prepare_cached(
+            "SELECT
+                    pictures.picture_id,
+                    pictures.picture_path_b64,
+                    COALESCE(
+                        pictures.taken_at_ts,
+                        pictures.exif_created_ts,
+                        pictures.exif_modified_ts,
+                        pictures.fs_created_ts,
+                        pictures.fs_modified_ts,
+                        pictures.insert_ts
+                      ) AS ordering_ts,
+                    pictures.is_selfie,
+                    pictures.orientation,
+                    pictures.is_favorite,
+                    pictures.blurhash,
+                    pictures.width,
+                    pictures.height,
+                    pictures.byte_size,
+                    pictures.is_broken
+                FROM pictures
+                WHERE picture_id = ?1",
+        )?;
+
+        let picture = stmt
+            .query_row([picture_id.id()], |row| self.to_picture(row))
+            .optional()?;
+
+        Ok(picture)
+    }
+
+    /// Pictures left out of albums because they couldn't be processed, except trashed
+    /// pictures, most recently broken first.
+    pub fn broken(&self) -> Result<Vec<BrokenPicture>> {
+        let con = database::lock(&self.con);
+        let mut stmt = con.prepare(
+            "SELECT
+                    picture_id,
+                    picture_path_b64,
+                    broken_reason,
+                    broken_at
+                FROM pictures
+                WHERE is_broken IS TRUE
+                AND trashed_at IS NULL
+                ORDER BY broken_at DESC, picture_id ASC",
+        )?;
+
+        let result = stmt
+            .query_map([], |row| {
+                let relative_path: String = row.get("picture_path_b64")?;
+                let relative_path = path_encoding::from_base64(&relative_path)
+                    .map_err(|_| rusqlite::Error::InvalidQuery)?;
+
+                std::result::Result::Ok(BrokenPicture {
+                    picture_id: row.get("picture_id").map(PictureId::new)?,
+                    path: self.library_roots.resolve(&relative_path),
+                    reason: row.get("broken_reason")?,
+                    broken_at: row.get("broken_at")?,
+                })
+            })?
+            .flatten()
+            .collect();
+
+        Ok(result)
+    }
+
+    /// Calls `f` for every picture in the repository, except trashed and missing pictures,
+    /// in ascending order of modification timestamp.
+    /// Pictures are read lazily from the database cursor, so the library is never
+    /// held in memory all at once. The repository is locked until `f` has seen every picture,
+    /// so `f` mustn't be slow, such as by reading files.
+    pub fn for_each(&self, f: impl FnMut(Picture)) -> Result<()> {
+        let con = database::lock(&self.con);
+        let mut stmt = con.prepare(
+            "SELECT
+                    pictures.picture_id,
+                    pictures.picture_path_b64,
+                    COALESCE(
+                        pictures.taken_at_ts,
+                        pictures.exif_created_ts,
+                        pictures.exif_modified_ts,
+                        pictures.fs_created_ts,
+                        pictures.fs_modified_ts,
+                        pictures.insert_ts
+                      ) AS ordering_ts,
+                    pictures.is_selfie,
+                    pictures.orientation,
+                    pictures.is_favorite,
+                    pictures.blurhash,
+                    pictures.width,
+                    pictures.height,
+                    pictures.byte_size
+                FROM pictures
+                WHERE COALESCE(is_broken, FALSE) IS FALSE
+                AND trashed_at IS NULL
+                AND is_missing IS FALSE
+                ORDER BY ordering_ts ASC",
+        )?;
+
+        stmt.query_map([], |row| self.to_picture(row))?
+            .flatten()
+            .for_each(f);
+
+        Ok(())
+    }
+
+    /// Gets all pictures, except trashed, missing, and broken pictures, whose largest
+    /// thumbnail is missing, corrupt, or was generated from an older version of the
+    /// picture file.
+    /// Thumbnails record the modification time of the source file when generated.
+    /// Pictures thumbnailed before blurhashes existed are included so they get one.
+    /// Pictures whose thumbnails were evicted from the cache are left out, because they are
+    /// regenerated when next shown.
+    /// Pictures in a format left out of this build are left out, rather than failing and
+    /// being marked as broken.
+    /// Files are checked after the repository is unlocked, so the database isn't blocked
+    /// while the library is read from disk.
+    pub fn needs_rethumbnail(
+        &self,
+        thumbnailer: &thumbnailify::Thumbnailer,
+    ) -> Result<Vec<Picture>> {
+        // Only what is needed to check the files is kept, rather than every picture.
+        let mut candidates = Vec::new();
+        self.for_each(|pic| {
+            candidates.push((pic.picture_id, pic.path, pic.blurhash.is_some()));
+        })?;
+
+        let needed: HashSet<PictureId> = candidates
+            .into_iter()
+            .filter(|(_, path, has_blurhash)| {
+                if !path.exists() {
+                    return false;
+                }
+                if file_types::is_disabled_picture(&path.sandbox_path) {
+                    info!(
+                        "Not thumbnailing picture in format left out of this build: {:?}",
+                        path
+                    );
+                    return false;
+                }
+                if thumbnailer.is_evicted(&path.thumbnail_hash()) {
+                    return false;
+                }
+                !has_blurhash || !thumbnailer.is_thumbnail_up_to_date(path, ThumbnailSize::XLarge)
+            })
+            .map(|(picture_id, _, _)| picture_id)
+            .collect();
+
+        let mut pictures = Vec::new();
+        if !needed.is_empty() {
+            self.for_each(|pic| {
+                if needed.contains(&pic.picture_id) {
+                    pictures.push(pic);
+                }
+            })?;
+        }
+
+        Ok(pictures)
+    }
+
+    /// Replaces the thumbnail queue with pictures found to need a thumbnail.
+    pub fn queue_thumbnails(&mut self, picture_ids: &[PictureId]) -> Result<()> {
+        let mut con = database::lock(&self.con);
+        let tx = con.transaction()?;
+
+        tx.execute("DELETE FROM pictures_thumbnail_queue", [])?;
+
+        {
+            let mut stmt = tx.prepare_cached(
+                "INSERT INTO pictures_thumbnail_queue (picture_id) VALUES (?1)
+                ON CONFLICT DO NOTHING",
+            )?;
+
+            for picture_id in picture_ids {
+                stmt.execute(params![picture_id.id()])?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Removes a picture from the thumbnail queue without thumbnailing it, such as
+    /// one on a network share that can't be reached right now.
+    pub fn dequeue_thumbnail(&mut self, picture_id: &PictureId) -> Result<()> {
+        let con = database::lock(&self.con);
+        let mut stmt =
+            con.prepare_cached("DELETE FROM pictures_thumbnail_queue WHERE picture_id = ?1")?;
+
+        stmt.execute(params![picture_id.id()])?;
+
+        Ok(())
+    }
+
+    /// Gets the pictures left in the thumbnail queue by an earlier run of thumbnailing,
+    /// in ascending order of modification timestamp.
+    /// Broken and trashed pictures, and pictures no longer on disk, are left out.
+    pub fn queued_thumbnails(&self) -> Result<Vec<Picture>> {
+        let con = database::lock(&self.con);
+        let mut stmt = con.prepare(
+            "SELECT
+                    pictures.picture_id,
+                    pictures.picture_path_b64,
+                    COALESCE(
+                        pictures.taken_at_ts,
+                        pictures.exif_created_ts,
+                        pictures.exif_modified_ts,
+                        pictures.fs_created_ts,
+                        pictures.fs_modified_ts,
+                        pictures.insert_ts
+                      ) AS ordering_ts,
+                    pictures.is_selfie,
+                    pictures.orientation,
+                    pictures.is_favorite,
+                    pictures.blurhash,
+                    pictures.width,
+                    pictures.height,
+                    pictures.byte_size
+                FROM pictures_thumbnail_queue
+                INNER JOIN pictures USING (picture_id)
+                WHERE COALESCE(pictures.is_broken, FALSE) IS FALSE
+                AND pictures.trashed_at IS NULL
+                AND pictures.is_missing IS FALSE
+                ORDER BY ordering_ts ASC",
+        )?;
+
+        let pictures = stmt
+            .query_map([], |row| self.to_picture(row))?
+            .flatten()
+            .filter(|pic| pic.path.exists())
+            .collect();
+
+        Ok(pictures)
+    }
+
+    /// Latitude and longitude of all pictures with GPS coordinates, except trashed pictures.
+    /// Pictures without GPS coordinates are excluded.
+    pub fn geotagged(&self) -> Result<Vec<(PictureId, f64, f64)>> {
+        let con = database::lock(&self.con);
+        let mut stmt = con.prepare(
+            "SELECT
+                    pictures_geo.picture_id,
+                    pictures_geo.latitude,
+                    pictures_geo.longitude
+                FROM pictures_geo
+                INNER JOIN pictures USING (picture_id)
+                WHERE COALESCE(pictures.is_broken, FALSE) IS FALSE
+                AND pictures.trashed_at IS NULL",
+        )?;
+
+        let result = stmt
+            .query_map([], |row| {
+                std::result::Result::Ok((
+                    row.get("picture_id").map(PictureId::new)?,
+                    row.get("latitude")?,
+                    row.get("longitude")?,
+                ))
+            })?
+            .flatten()
+            .collect();
+
+        Ok(result)
+    }
+
+    /// Gets all pictures, except trashed pictures, featuring a known person,
+    /// in ascending order of modification timestamp.
+    /// Pictures where the person's face was detected with a confidence below
+    /// `min_confidence` are excluded, as are private pictures unless `include_private`.
+    pub fn pictures_for_person(
+        &self,
+        person_id: PersonId,
+        min_confidence: f32,
+        include_private: bool,
+    ) -> Result<Vec<Picture>> {
+        let con = database::lock(&self.con);
+        let mut stmt = con.prepare(
+            "SELECT DISTINCT
+                    pictures.picture_id,
+                    pictures.picture_path_b64,
+                    COALESCE(
+                        pictures.taken_at_ts,
+                        pictures.exif_created_ts,
+                        pictures.exif_modified_ts,
+                        pictures.fs_created_ts,
+                        pictures.fs_modified_ts,
+                        pictures.insert_ts
+                      ) AS ordering_ts,
+                    pictures.is_selfie,
+                    pictures.orientation,
+                    pictures.is_favorite,
+                    pictures.blurhash
+                FROM pictures
+                INNER JOIN pictures_faces USING (picture_id)
+                WHERE pictures_faces.person_id = ?1
+                AND pictures_faces.confidence >= ?2
+                AND pictures.trashed_at IS NULL
+                AND (?3 OR pictures.is_private IS FALSE)
+                ORDER BY ordering_ts ASC",
+        )?;
+
+        let result = stmt
+            .query_map(params![person_id, min_confidence, include_private], |row| {
+                self.to_picture(row)
+            })?
+            .flatten()
+            .collect();
+
+        Ok(result)
+    }
+
+    /// Writes the metadata of all pictures, except trashed and broken pictures, and
+    /// private pictures unless `include_private`.
+    /// People are those recognized with at least `min_confidence`, and not ignored.
+    /// `on_progress` is called after each picture.
+    /// Returns the number of pictures written.
+    pub fn export_metadata(
+        &self,
+        format: ExportFormat,
+        min_confidence: f32,
+        include_private: bool,
+        writer: impl Write,
+        mut on_progress: impl FnMut(),
+    ) -> Result<usize> {
+        // Read everything up front so the database isn't locked while writing.
+        let records = self.metadata_records(min_confidence, include_private)?;
+
+        let mut writer = BufWriter::new(writer);
+        metadata_export::write_header(format, &mut writer)?;
+        for record in &records {
+            metadata_export::write_record(format, record, &mut writer)?;
+            on_progress();
+        }
+        writer.flush()?;
+
+        Ok(records.len())
+    }
+
+    fn metadata_records(
+        &self,
+        min_confidence: f32,
+        include_private: bool,
+    ) -> Result<Vec<MetadataRecord>> {
+        let con = database::lock(&self.con);
+
+        let mut tags = tag::all_tags(&con)?;
+
+        let mut people: HashMap<PictureId, Vec<String>> = HashMap::new();
+        {
+            let mut stmt = con.prepare(
+                "SELECT DISTINCT
+                    pictures_faces.picture_id,
+                    people.name
+                FROM pictures_faces
+                INNER JOIN people USING (person_id)
+                WHERE pictures_faces.confidence >= ?1
+                AND pictures_faces.is_ignored IS FALSE
+                AND people.is_ignored IS FALSE
+                ORDER BY people.name ASC",
+            )?;
+
+            let rows = stmt.query_map([min_confidence], |row| {
+                std::result::Result::Ok((PictureId::new(row.get(0)?), row.get(1)?))
+            })?;
+
+            for row in rows {
+                let (picture_id, name) = row?;
+                people.entry(picture_id).or_default().push(name);
+            }
+        }
+
+        let mut stmt = con.prepare(
+            "SELECT
+                    pictures.picture_id,
+                    pictures.picture_path_b64,
+                    pictures.taken_at_ts,
+                    pictures.width,
+                    pictures.height,
+                    pictures.is_favorite,
+                    pictures_geo.latitude,
+                    pictures_geo.longitude
+                FROM pictures
+                LEFT JOIN pictures_geo USING (picture_id)
+                WHERE COALESCE(pictures.is_broken, FALSE) IS FALSE
+                AND pictures.trashed_at IS NULL
+                AND (?1 OR pictures.is_private IS FALSE)
+                ORDER BY COALESCE(
+                    pictures.taken_at_ts,
+                    pictures.exif_created_ts,
+                    pictures.exif_modified_ts,
+                    pictures.fs_created_ts,
+                    pictures.fs_modified_ts,
+                    pictures.insert_ts
+                ) ASC",
+        )?;
+
+        let rows = stmt.query_map([include_private], |row| {
+            let picture_id = row.get("picture_id").map(PictureId::new)?;
+
+            let relative_path: String = row.get("picture_path_b64")?;
+            let relative_path = path_encoding::from_base64(&relative_path)
+                .map_err(|_| rusqlite::Error::InvalidQuery)?;
+
+            let record = MetadataRecord {
+                path: self.library_roots.resolve(&relative_path).host_path,
+                taken_at: row.get("taken_at_ts").ok().flatten(),
+                width: row.get("width").ok().flatten(),
+                height: row.get("height").ok().flatten(),
+                is_favorite: row.get("is_favorite").unwrap_or(false),
+                latitude: row.get("latitude").ok().flatten(),
+                longitude: row.get("longitude").ok().flatten(),
+                ..Default::default()
+            };
+
+            std::result::Result::Ok((picture_id, record))
+        })?;
+
+        let records = rows
+            .flatten()
+            .map(|(picture_id, mut record)| {
+                record.tags = tags.remove(&picture_id).unwrap_or_default();
+                record.people = people.remove(&picture_id).unwrap_or_default();
+                record
+            })
+            .collect();
+
+        Ok(records)
+    }
+
+    /// Writes the favorite and tags of pictures to XMP sidecar files next to the pictures,
+    /// so that other photo managers can see them. Pictures are written if they are a favorite,
+    /// are tagged, or have had a sidecar written or imported before, so that removing a
+    /// favorite or tag reaches the sidecar too. Trashed and broken pictures are left out.
+    /// `on_progress` is called after each picture.
+    /// Returns the number of sidecars written.
+    pub fn write_sidecars(&mut self, mut on_progress: impl FnMut()) -> Result<usize> {
+        let pictures = self.sidecar_candidates()?;
+
+        let mut written = Vec::with_capacity(pictures.len());
+        for (picture_id, path, metadata) in pictures {
+            let sidecar = xmp::write_sidecar(&path, &metadata)
+                .and_then(|sidecar| sidecar_modified_ts(&sidecar));
+
+            match sidecar {
+                Ok(modified_ts) => written.push((picture_id, modified_ts)),
+                Err(e) => error!("Failed writing sidecar of {:?}: {:?}", path, e),
+            }
+
+            on_progress();
+        }
+
+        let mut con = database::lock(&self.con);
+        let tx = con.transaction()?;
+        for (picture_id, modified_ts) in &written {
+            record_sidecar(&tx, picture_id, modified_ts)?;
+        }
+        tx.commit()?;
+
+        Ok(written.len())
+    }
+
+    fn sidecar_candidates(&self) -> Result<Vec<(PictureId, PathBuf, SidecarMetadata)>> {
+        let con = database::lock(&self.con);
+
+        let mut tags = tag::all_tags(&con)?;
+
+        let mut stmt = con.prepare(
+            "SELECT
+                    pictures.picture_id,
+                    pictures.picture_path_b64,
+                    pictures.is_favorite
+                FROM pictures
+                WHERE COALESCE(pictures.is_broken, FALSE) IS FALSE
+                AND pictures.trashed_at IS NULL
+                AND (
+                    pictures.is_favorite IS TRUE
+                    OR pictures.picture_id IN (SELECT picture_id FROM pictures_tags)
+                    OR pictures.picture_id IN (SELECT picture_id FROM pictures_sidecars)
+                )",
+        )?;
+
+        let rows = stmt.query_map([], |row| {
+            let picture_id = row.get("picture_id").map(PictureId::new)?;
+
+            let relative_path: String = row.get("picture_path_b64")?;
+            let relative_path = path_encoding::from_base64(&relative_path)
+                .map_err(|_| rusqlite::Error::InvalidQuery)?;
+            let path = self.library_roots.resolve(&relative_path).sandbox_path;
+
+            let is_favorite = row.get("is_favorite").unwrap_or(false);
+
+            std::result::Result::Ok((picture_id, path, is_favorite))
+        })?;
+
+        let pictures = rows
+            .flatten()
+            .map(|(picture_id, path, is_favorite)| {
+                let metadata = SidecarMetadata {
+                    is_favorite,
+                    tags: tags.remove(&picture_id).unwrap_or_default(),
+                };
+                (picture_id, path, metadata)
+            })
+            .collect();
+
+        Ok(pictures)
+    }
+
+    /// Imports the favorite and tags of pictures from XMP sidecar files that another photo
+    /// manager has changed since Fotema last wrote or imported them.
+    /// For pictures that are already a favorite or tagged, `policy` decides whether the
+    /// sidecar or the library is kept. Trashed and broken pictures are left out.
+    /// `on_progress` is called after each picture.
+    /// Returns the number of pictures updated.
+    pub fn import_sidecars(
+        &mut self,
+        policy: SidecarPolicy,
+        mut on_progress: impl FnMut(),
+    ) -> Result<usize> {
+        let pictures = {
+            let con = database::lock(&self.con);
+            let mut stmt = con.prepare(
+                "SELECT
+                        pictures.picture_id,
+                        pictures.picture_path_b64,
+                        pictures.is_favorite,
+                        EXISTS (
+                            SELECT 1 FROM pictures_tags
+                            WHERE pictures_tags.picture_id = pictures.picture_id
+                        ) AS is_tagged,
+                        pictures_sidecars.sidecar_modified_ts
+                    FROM pictures
+                    LEFT JOIN pictures_sidecars USING (picture_id)
+                    WHERE COALESCE(pictures.is_broken, FALSE) IS FALSE
+                    AND pictures.trashed_at IS NULL",
+            )?;
+
+            let rows = stmt.query_map([], |row| {
+                let picture_id = row.get("picture_id").map(PictureId::new)?;
+
+                let relative_path: String = row.get("picture_path_b64")?;
+                let relative_path = path_encoding::from_base64(&relative_path)
+                    .map_err(|_| rusqlite::Error::InvalidQuery)?;
+                let path = self.library_roots.resolve(&relative_path).sandbox_path;
+
+                let is_favorite: bool = row.get("is_favorite").unwrap_or(false);
+                let is_tagged: bool = row.get("is_tagged").unwrap_or(false);
+                let recorded_ts: Option<DateTime<Utc>> =
+                    row.get("sidecar_modified_ts").ok().flatten();
+
+                std::result::Result::Ok((picture_id, path, is_favorite || is_tagged, recorded_ts))
+            })?;
+
+            rows.flatten().collect::<Vec<_>>()
+        };
+
+        // Sidecars changed since last seen, with the metadata to import
+        // or None if the library is kept.
+        let mut changed = Vec::new();
+        for (picture_id, path, has_user_metadata, recorded_ts) in pictures {
+            let sidecar = xmp::find_sidecar(&path)
+                .map(|sidecar| sidecar_modified_ts(&sidecar).map(|ts| (sidecar, ts)))
+                .transpose();
+
+            match sidecar {
+                Ok(Some((sidecar, modified_ts)))
+                    if recorded_ts.is_none_or(|recorded_ts| modified_ts > recorded_ts) =>
+                {
+                    let keep_library = policy == SidecarPolicy::DatabaseWins && has_user_metadata;
+                    if keep_library {
+                        changed.push((picture_id, modified_ts, None));
+                    } else {
+                        match xmp::read_sidecar(&sidecar) {
+                            Ok(metadata) => changed.push((picture_id, modified_ts, Some(metadata))),
+                            Err(e) => error!("Failed importing sidecar {:?}: {:?}", sidecar, e),
+                        }
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => error!("Failed checking sidecar of {:?}: {:?}", path, e),
+            }
+
+            on_progress();
+        }
+
+        let mut con = database::lock(&self.con);
+        let tx = con.transaction()?;
+
+        let mut updated = 0;
+        for (picture_id, modified_ts, metadata) in &changed {
+            if let Some(metadata) = metadata {
+                tx.execute(
+                    "UPDATE pictures SET is_favorite = ?2 WHERE picture_id = ?1",
+                    params![picture_id.id(), metadata.is_favorite],
+                )?;
+
+                tx.execute(
+                    "DELETE FROM pictures_tags WHERE picture_id = ?1",
+                    params![picture_id.id()],
+                )?;
+
+                for tag in &metadata.tags {
+                    if let Some((name, folded_name)) = tag::normalize(tag) {
+                        tag::add_normalized_tag(&tx, picture_id, &name, &folded_name)?;
+                    }
+                }
+
+                updated += 1;
+            }
+
+            record_sidecar(&tx, picture_id, modified_ts)?;
+        }
+
+        tx.commit()?;
+
+        Ok(updated)
+    }
+
+    /// Gets all pictures with a file name containing `query`, ignoring case,
+    /// in ascending order of modification timestamp.
+    /// Paths are stored base64 encoded, so matching can't be done in SQL.
+    pub fn search_by_filename(&self, query: &str) -> Result<Vec<Picture>> {
+        let mut pictures = Vec::new();
+        self.for_each(|picture| {
+            if picture.path.file_name_contains(query) {
+                pictures.push(picture);
+            }
+        })?;
+        Ok(pictures)
+    }
+
+    /// Gets all pictures, except trashed pictures, taken on a month and day in any year.
+    /// Newest year first, then in ascending order of modification timestamp within a year.
+    /// See `time::is_on_this_day` for how February 29th is handled.
+    pub fn on_this_day(&self, month: u32, day: u32) -> Result<Vec<Picture>> {
+        let mut pictures = Vec::new();
+        self.for_each(|picture| {
+            if time::is_on_this_day(picture.ordering_ts.date_naive(), month, day) {
+                pictures.push(picture);
+            }
+        })?;
+
+        // Stable sort, so pictures in the same year stay in ascending order.
+        pictures.sort_by_key(|picture| std::cmp::Reverse(picture.ordering_ts.year()));
+        Ok(pictures)
+    }
+
+    /// Gets all pictures that haven't had their metadata extracted.
+    /// Will return all pictures that are not broken and have a metadata version
+    /// lower than the current metadata scanner.
+    pub fn find_need_metadata_update(&self) -> Result<Vec<Picture>> {
+        let con = database::lock(&self.con);
+        let mut stmt = con.prepare(
+            "SELECT
+                    pictures.picture_id,
+                    pictures.picture_path_b64,
+                    COALESCE(
+                        pictures.taken_at_ts,
+                        pictures.exif_created_ts,
+                        pictures.exif_modified_ts,
+                        pictures.fs_created_ts,
+                        pictures.fs_modified_ts,
+                        pictures.insert_ts
+                      ) AS ordering_ts,
+                    pictures.is_selfie,
+                    pictures.orientation,
+                    pictures.is_favorite
+                FROM pictures
+                WHERE metadata_version < ?1
+                AND COALESCE(is_broken, FALSE) IS FALSE
+                AND is_missing IS FALSE
+                ORDER BY ordering_ts ASC",
+        )?;
+
+        let result = stmt
+            .query_map([metadata::VERSION], |row| self.to_picture(row))?
+            .flatten()
+            .collect();
+
+        Ok(result)
+    }
+
+    /// Gets all pictures that haven't been inspected for containing a motion photo.
+    pub fn find_need_motion_photo_extract(&self) -> Result<Vec<Picture>> {
+        let con = database::lock(&self.con);
+        let mut stmt = con.prepare(
+            "SELECT
+                    pictures.picture_id,
+                    pictures.picture_path_b64,
+                    COALESCE(
+                        pictures.taken_at_ts,
+                        pictures.exif_created_ts,
+                        pictures.exif_modified_ts,
+                        pictures.fs_created_ts,
+                        pictures.fs_modified_ts,
+                        pictures.insert_ts
+                      ) AS ordering_ts,
+                    pictures.is_selfie,
+                    pictures.orientation,
+                    pictures.is_favorite
+                FROM pictures
+                FULL OUTER JOIN motion_photos USING (picture_id)
+                WHERE COALESCE(motion_photos.extract_version, 0) < ?1
+                AND COALESCE(is_broken, FALSE) IS FALSE
+                AND is_missing IS FALSE",
+        )?;
+
+        let result = stmt
+            .query_map([motion_photo::VERSION], |row| self.to_picture(row))?
+            .flatten()
+            .collect();
+
+        Ok(result)
+    }
+
+    /// Gets all pictures together with the stamp of the file when its contents were
+    /// last hashed. The stamp is `None` if the contents have never been hashed.
+    pub fn find_content_hash_stamps(&self) -> Result<Vec<(Picture, Option<FileStamp>)>> {
+        let con = database::lock(&self.con);
+        let mut stmt = con.prepare(
+            "SELECT
+                    pictures.picture_id,
+                    pictures.picture_path_b64,
+                    COALESCE(
+                        pictures.taken_at_ts,
+                        pictures.exif_created_ts,
+                        pictures.exif_modified_ts,
+                        pictures.fs_created_ts,
+                        pictures.fs_modified_ts,
+                        pictures.insert_ts
+                      ) AS ordering_ts,
+                    pictures.is_selfie,
+                    pictures.orientation,
+                    pictures.is_favorite,
+                    pictures.content_hash,
+                    pictures.content_hash_file_size,
+                    pictures.content_hash_fs_modified_ts
+                FROM pictures
+                WHERE COALESCE(is_broken, FALSE) IS FALSE
+                AND is_missing IS FALSE
+                ORDER BY ordering_ts ASC",
+        )?;
+
+        let result = stmt
+            .query_map([], |row| {
+                let picture = self.to_picture(row)?;
+                let hash: Option<String> = row.get("content_hash")?;
+                let file_size: Option<u64> = row.get("content_hash_file_size")?;
+                let fs_modified_at = row.get("content_hash_fs_modified_ts")?;
+                let stamp = hash.and(file_size).map(|file_size| FileStamp {
+                    file_size,
+                    fs_modified_at,
+                });
+                std::result::Result::Ok((picture, stamp))
+            })?
+            .flatten()
+            .collect();
+
+        Ok(result)
+    }
+
+    /// Stored hash of a picture's contents, as long as the file hasn't changed since it was
+    /// hashed, so that copies of the picture can be found without hashing it again.
+    /// The file is checked after the repository is unlocked.
+    pub fn current_content_hash(&self, picture_id: &PictureId) -> Result<Option<String>> {
+        let stored = database::lock(&self.con)
+            .query_row(
+                "SELECT
+                    picture_path_b64,
+                    content_hash,
+                    content_hash_file_size,
+                    content_hash_fs_modified_ts
+                FROM pictures
+                WHERE picture_id = ?1",
+                params![picture_id.id()],
+                |row| {
+                    let path: String = row.get("picture_path_b64")?;
+                    let hash: Option<String> = row.get("content_hash")?;
+                    let file_size: Option<u64> = row.get("content_hash_file_size")?;
+                    let fs_modified_at = row.get("content_hash_fs_modified_ts")?;
+                    std::result::Result::Ok((path, hash, file_size, fs_modified_at))
+                },
+            )
+            .optional()?;
+
+        let Some((path, Some(hash), Some(file_size), fs_modified_at)) = stored else {
+            return Ok(None);
+        };
+
+        let path = self
+            .library_roots
+            .resolve(&path_encoding::from_base64(&path)?);
+        let stamp = FileStamp {
+            file_size,
+            fs_modified_at,
+        };
+        let is_current = FileStamp::from_path(&path.sandbox_path).is_ok_and(|now| now == stamp);
+
+        Ok(is_current.then_some(hash))
+    }
+
+    /// Save hashes of picture file contents.
+    pub fn set_content_hashes(&mut self, hashes: Vec<(PictureId, ContentHash)>) -> Result<()> {
+        let mut con = database::lock(&self.con);
+        let tx = con.transaction()?;
 
-                    let link_path = picture_path.with_file_name(link_path);
-                    let link_path_b64 = path_encoding::to_base64(&link_path);
+        {
+            let mut stmt = tx.prepare_cached(
+                "UPDATE pictures
+                SET
+                    content_hash = ?2,
+                    content_hash_file_size = ?3,
+                    content_hash_fs_modified_ts = ?4
+                WHERE picture_id = ?1",
+            )?;
 
-                    pic_insert_stmt.execute(params![
-                        picture_path_b64,
-                        picture_path.to_string_lossy(),
-                        link_path_b64,
-                        link_path.to_string_lossy(),
-                    ])?;
-                } else {
-                    error!("Expected a photo, but got: {:?}", scanned_file);
-                }
+            for (picture_id, content_hash) in hashes {
+                stmt.execute(params![
+                    picture_id,
+                    content_hash.hash,
+                    content_hash.stamp.file_size,
+                    content_hash.stamp.fs_modified_at,
+                ])?;
             }
         }
 
@@ -190,97 +1969,193 @@ impl Repository {
         Ok(())
     }
 
-    /// Gets all pictures in the repository, in ascending order of modification timestamp.
-    pub fn all(&self) -> Result<Vec<Picture>> {
-        let con = self.con.lock().unwrap();
+    /// Hashes of the contents of every picture file in the library, including trashed pictures.
+    pub fn content_hashes(&self) -> Result<HashSet<String>> {
+        let con = database::lock(&self.con);
+        let mut stmt = con.prepare(
+            "SELECT content_hash
+                FROM pictures
+                WHERE content_hash IS NOT NULL",
+        )?;
+
+        let result = stmt
+            .query_map([], |row| row.get("content_hash"))?
+            .flatten()
+            .collect();
+
+        Ok(result)
+    }
+
+    /// Groups of pictures with identical file contents.
+    /// Pictures in a group are in ascending order of modification timestamp.
+    pub fn duplicates(&self) -> Result<Vec<Vec<Picture>>> {
+        let con = database::lock(&self.con);
         let mut stmt = con.prepare(
             "SELECT
                     pictures.picture_id,
                     pictures.picture_path_b64,
                     COALESCE(
+                        pictures.taken_at_ts,
                         pictures.exif_created_ts,
                         pictures.exif_modified_ts,
                         pictures.fs_created_ts,
                         pictures.fs_modified_ts,
                         pictures.insert_ts
                       ) AS ordering_ts,
-                    pictures.is_selfie
+                    pictures.is_selfie,
+                    pictures.orientation,
+                    pictures.is_favorite,
+                    pictures.content_hash
                 FROM pictures
                 WHERE COALESCE(is_broken, FALSE) IS FALSE
-                ORDER BY ordering_ts ASC",
+                AND trashed_at IS NULL
+                AND content_hash IN (
+                    SELECT content_hash
+                    FROM pictures
+                    WHERE content_hash IS NOT NULL
+                    AND COALESCE(is_broken, FALSE) IS FALSE
+                    AND trashed_at IS NULL
+                    GROUP BY content_hash
+                    HAVING COUNT(*) > 1
+                )
+                ORDER BY content_hash, ordering_ts ASC",
         )?;
 
         let result = stmt
-            .query_map([], |row| self.to_picture(row))?
+            .query_map([], |row| {
+                let hash: String = row.get("content_hash")?;
+                self.to_picture(row).map(|picture| (hash, picture))
+            })?
             .flatten()
+            .chunk_by(|(hash, _)| hash.clone())
+            .into_iter()
+            .map(|(_, group)| group.map(|(_, picture)| picture).collect())
             .collect();
 
         Ok(result)
     }
 
-    /// Gets all pictures that haven't had their metadata extracted.
-    /// Will return all pictures that are not broken and have a metadata version
-    /// lower than the current metadata scanner.
-    pub fn find_need_metadata_update(&self) -> Result<Vec<Picture>> {
-        let con = self.con.lock().unwrap();
+    /// Pictures that have been thumbnailed but have no perceptual hash yet, except
+    /// trashed and broken pictures.
+    pub fn find_need_perceptual_hash(&self) -> Result<Vec<Picture>> {
+        let con = database::lock(&self.con);
         let mut stmt = con.prepare(
             "SELECT
                     pictures.picture_id,
                     pictures.picture_path_b64,
                     COALESCE(
+                        pictures.taken_at_ts,
                         pictures.exif_created_ts,
                         pictures.exif_modified_ts,
                         pictures.fs_created_ts,
                         pictures.fs_modified_ts,
                         pictures.insert_ts
                       ) AS ordering_ts,
-                    pictures.is_selfie
+                    pictures.is_selfie,
+                    pictures.orientation,
+                    pictures.is_favorite
                 FROM pictures
-                WHERE metadata_version < ?1
+                WHERE perceptual_hash IS NULL
+                AND blurhash IS NOT NULL
                 AND COALESCE(is_broken, FALSE) IS FALSE
-                ORDER BY ordering_ts ASC",
+                AND trashed_at IS NULL
+                AND is_missing IS FALSE",
         )?;
 
         let result = stmt
-            .query_map([metadata::VERSION], |row| self.to_picture(row))?
+            .query_map([], |row| self.to_picture(row))?
             .flatten()
             .collect();
 
         Ok(result)
     }
 
-    /// Gets all pictures that haven't been inspected for containing a motion photo.
-    pub fn find_need_motion_photo_extract(&self) -> Result<Vec<Picture>> {
-        let con = self.con.lock().unwrap();
+    /// Store perceptual hashes computed from the thumbnails of pictures.
+    pub fn set_perceptual_hashes(&mut self, hashes: &[(PictureId, PerceptualHash)]) -> Result<()> {
+        let mut con = database::lock(&self.con);
+        let tx = con.transaction()?;
+
+        {
+            let mut stmt = tx.prepare_cached(
+                "UPDATE pictures
+                SET
+                    perceptual_hash = ?2
+                WHERE picture_id = ?1",
+            )?;
+
+            for (picture_id, hash) in hashes {
+                stmt.execute(params![picture_id.id(), hash.to_i64()])?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Pictures that look like a picture, most alike first, except trashed and broken
+    /// pictures. Pictures are alike if their perceptual hashes differ by no more than
+    /// `max_distance` bits. Copies with identical contents are alike too, so they are
+    /// included alongside the duplicates found by content hash.
+    /// Empty if the picture has no perceptual hash yet.
+    pub fn similar_to(&self, picture_id: &PictureId, max_distance: u32) -> Result<Vec<Picture>> {
+        let con = database::lock(&self.con);
+
+        let hash: Option<i64> = con
+            .query_row(
+                "SELECT perceptual_hash FROM pictures WHERE picture_id = ?1",
+                params![picture_id.id()],
+                |row| row.get(0),
+            )
+            .optional()?
+            .flatten();
+
+        let Some(hash) = hash.map(PerceptualHash::from_i64) else {
+            return Ok(Vec::new());
+        };
+
+        // Hamming distance can't be indexed, but comparing 64-bit integers is quick
+        // even for a large library.
         let mut stmt = con.prepare(
             "SELECT
                     pictures.picture_id,
                     pictures.picture_path_b64,
                     COALESCE(
+                        pictures.taken_at_ts,
                         pictures.exif_created_ts,
                         pictures.exif_modified_ts,
                         pictures.fs_created_ts,
                         pictures.fs_modified_ts,
                         pictures.insert_ts
                       ) AS ordering_ts,
-                    pictures.is_selfie
+                    pictures.is_selfie,
+                    pictures.orientation,
+                    pictures.is_favorite,
+                    pictures.perceptual_hash
                 FROM pictures
-                FULL OUTER JOIN motion_photos USING (picture_id)
-                WHERE COALESCE(motion_photos.extract_version, 0) < ?1
-                AND COALESCE(is_broken, FALSE) IS FALSE",
+                WHERE perceptual_hash IS NOT NULL
+                AND picture_id != ?1
+                AND COALESCE(is_broken, FALSE) IS FALSE
+                AND trashed_at IS NULL",
         )?;
 
-        let result = stmt
-            .query_map([motion_photo::VERSION], |row| self.to_picture(row))?
+        let mut similar: Vec<(u32, Picture)> = stmt
+            .query_map([picture_id.id()], |row| {
+                let other = PerceptualHash::from_i64(row.get("perceptual_hash")?);
+                self.to_picture(row)
+                    .map(|picture| (hash.distance(&other), picture))
+            })?
             .flatten()
+            .filter(|(distance, _)| *distance <= max_distance)
             .collect();
 
-        Ok(result)
+        similar.sort_by_key(|(distance, _)| *distance);
+
+        Ok(similar.into_iter().map(|(_, picture)| picture).collect())
     }
 
     /// Gets paths of files to delete when a picture is no longer present.
     pub fn find_files_to_cleanup(&self, picture_id: PictureId) -> Result<Vec<PathBuf>> {
-        let con = self.con.lock().unwrap();
+        let con = database::lock(&self.con);
         let mut stmt =
             con.prepare("SELECT root_name, path FROM pictures_cleanup WHERE picture_id = ?1")?;
 
@@ -297,7 +2172,7 @@ impl Repository {
         picture_id: &PictureId,
         video: Option<MotionPhotoVideo>,
     ) -> Result<()> {
-        let mut con = self.con.lock().unwrap();
+        let mut con = database::lock(&self.con);
         let tx = con.transaction()?;
 
         {
@@ -375,17 +2250,35 @@ impl Repository {
         let relative_path = path_encoding::from_base64(&relative_path)
             .map_err(|_| rusqlite::Error::InvalidQuery)?;
 
-        let sandbox_path = self.library_base_dir.sandbox_path.join(&relative_path);
-        let host_path = self.library_base_dir.host_path.join(&relative_path);
+        let path = self.library_roots.resolve(&relative_path);
 
         let ordering_ts = row.get("ordering_ts").expect("must have ordering_ts");
         let is_selfie = row.get("is_selfie").ok();
+        let is_favorite = row.get("is_favorite").unwrap_or(false);
+        let blurhash = row.get("blurhash").ok().flatten();
+        let orientation = row
+            .get("orientation")
+            .ok()
+            .flatten()
+            .map(|x: u32| Orientation::from(x))
+            .unwrap_or_default();
+        let width = row.get("width").ok().flatten();
+        let height = row.get("height").ok().flatten();
+        let byte_size = row.get("byte_size").ok().flatten();
+        let is_broken = row.get("is_broken").ok().flatten().unwrap_or(false);
 
         std::result::Result::Ok(Picture {
             picture_id,
-            path: FlatpakPathBuf::build(host_path, sandbox_path),
+            path,
             ordering_ts,
             is_selfie,
+            is_favorite,
+            blurhash,
+            orientation,
+            width,
+            height,
+            byte_size,
+            is_broken,
         })
     }
 
@@ -402,7 +2295,7 @@ impl Repository {
     }
 
     pub fn remove(&mut self, picture_id: PictureId) -> Result<()> {
-        let con = self.con.lock().unwrap();
+        let con = database::lock(&self.con);
         let mut stmt = con.prepare("DELETE FROM pictures WHERE picture_id = ?1")?;
 
         stmt.execute([picture_id.id()])?;
@@ -413,7 +2306,7 @@ impl Repository {
     /// Find all people and associated face.
     /// FIXME move to people repo
     pub fn find_people_for_thumbnails(&self) -> Result<Vec<(FlatpakPathBuf, DetectedFace)>> {
-        let con = self.con.lock().unwrap();
+        let con = database::lock(&self.con);
 
         // NOTE: this is non-standard SQL that might not work in DBs that aren't SQLite.
         let mut stmt = con.prepare(
@@ -469,13 +2362,7 @@ impl Repository {
         let relative_path = path_encoding::from_base64(&relative_path)
             .map_err(|_| rusqlite::Error::InvalidQuery)?;
 
-        let sandbox_path = self.library_base_dir.sandbox_path.join(&relative_path);
-        let host_path = self.library_base_dir.host_path.join(&relative_path);
-
-        std::result::Result::Ok(FlatpakPathBuf {
-            host_path,
-            sandbox_path,
-        })
+        std::result::Result::Ok(self.library_roots.resolve(&relative_path))
     }
 
     /// FIXME a copy-n-paste from people repo :-()
@@ -531,6 +2418,7 @@ impl Repository {
             left_mouth_corner: (left_mouth_corner_x, left_mouth_corner_y),
             confidence,
             detected_at,
+            embedding: None,
         };
 
         std::result::Result::Ok(face)
@@ -541,12 +2429,13 @@ impl Repository {
     /// to need a pic_base_dir.
     /// FIXME move to people repo
     pub fn find_face_detection_candidates(&self) -> Result<Vec<FaceDetectionCandidate>> {
-        let con = self.con.lock().unwrap();
+        let con = database::lock(&self.con);
         let mut stmt = con.prepare(
             "SELECT
                     pictures.picture_id,
                     pictures.picture_path_b64,
                     COALESCE(
+                        pictures.taken_at_ts,
                         pictures.exif_created_ts,
                         pictures.exif_modified_ts,
                         pictures.fs_created_ts,
@@ -557,6 +2446,8 @@ impl Repository {
                 LEFT OUTER JOIN pictures_face_scans USING (picture_id)
                 WHERE pictures_face_scans.picture_id IS NULL
                 AND COALESCE(pictures.is_broken, FALSE) IS FALSE
+                AND pictures.trashed_at IS NULL
+                AND pictures.is_missing IS FALSE
                 ORDER BY ordering_ts DESC",
         )?;
 
@@ -576,7 +2467,7 @@ impl Repository {
         &self,
         picture_id: &PictureId,
     ) -> Result<Option<FaceDetectionCandidate>> {
-        let con = self.con.lock().unwrap();
+        let con = database::lock(&self.con);
         let mut stmt = con.prepare(
             "SELECT
                     pictures.picture_id,
@@ -607,16 +2498,1194 @@ impl Repository {
             .ok()
             .and_then(|x: String| path_encoding::from_base64(&x).ok());
 
-        let host_path = relative_path
-            .as_ref()
-            .map(|x| self.library_base_dir.host_path.join(x));
-
-        let sandbox_path = relative_path.map(|x| self.library_base_dir.sandbox_path.join(x));
+        let path = relative_path
+            .map(|x| self.library_roots.resolve(&x))
+            .expect("Must have path");
 
         Ok(FaceDetectionCandidate {
             picture_id,
-            host_path: host_path.expect("Must have host path"),
-            sandbox_path: sandbox_path.expect("Must have sandbox path"),
+            host_path: path.host_path,
+            sandbox_path: path.sandbox_path,
+        })
+    }
+}
+
+/// Width and height in pixels of a picture, read from the file header.
+/// None if the picture is in a format that can't be decoded here, such as HEIC.
+fn dimensions(path: &Path) -> Option<(u32, u32)> {
+    image::ImageReader::open(path)
+        .ok()?
+        .with_guessed_format()
+        .ok()?
+        .into_dimensions()
+        .ok()
+}
+
+/// Points a picture at a new path, relative to a library root.
+fn set_picture_path(
+    con: &rusqlite::Connection,
+    picture_id: &PictureId,
+    picture_path: &Path,
+    canonical_path: &Path,
+) -> Result<()> {
+    let Some(file_stem) = picture_path.file_stem() else {
+        bail!("{:?} has no file name", picture_path);
+    };
+    let link_path = picture_path.with_file_name(file_stem);
+
+    con.execute(
+        "UPDATE pictures
+        SET
+            picture_path_b64 = ?2,
+            picture_path_lossy = ?3,
+            link_path_b64 = ?4,
+            link_path_lossy = ?5,
+            canonical_path_b64 = ?6
+        WHERE picture_id = ?1",
+        params![
+            picture_id.id(),
+            path_encoding::to_base64(picture_path),
+            picture_path.to_string_lossy(),
+            path_encoding::to_base64(&link_path),
+            link_path.to_string_lossy(),
+            path_encoding::to_base64(canonical_path),
+        ],
+    )?;
+
+    // A picture can only be the cover of the folder it is in.
+    con.execute(
+        "DELETE FROM folder_covers WHERE picture_id = ?1",
+        params![picture_id.id()],
+    )?;
+
+    Ok(())
+}
+
+/// Points a video at a new path, keeping it linked to the picture of its live photo.
+fn set_video_path(
+    con: &rusqlite::Connection,
+    video_id: i64,
+    video_path: &Path,
+    link_path: &Path,
+    canonical_path: &Path,
+) -> Result<()> {
+    con.execute(
+        "UPDATE videos
+        SET
+            video_path_b64 = ?2,
+            video_path_lossy = ?3,
+            link_path_b64 = ?4,
+            link_path_lossy = ?5,
+            canonical_path_b64 = ?6
+        WHERE video_id = ?1",
+        params![
+            video_id,
+            path_encoding::to_base64(video_path),
+            video_path.to_string_lossy(),
+            path_encoding::to_base64(link_path),
+            link_path.to_string_lossy(),
+            path_encoding::to_base64(canonical_path),
+        ],
+    )?;
+
+    Ok(())
+}
+
+/// Paths in `dir` that don't exist yet for a picture and the files that go with it,
+/// all of which are named after the picture's `stem`. The files keep matching names,
+/// so `IMG_0001.jpg` and `IMG_0001.jpg.xmp` become `IMG_0001 (1).jpg` and
+/// `IMG_0001 (1).jpg.xmp` together.
+fn unique_destinations(dir: &Path, stem: &str, files: &[&Path]) -> Vec<PathBuf> {
+    // Whatever follows the stem, such as the extension.
+    let suffixes: Vec<String> = files
+        .iter()
+        .map(|file| {
+            let name = file.file_name().unwrap_or_default().to_string_lossy();
+            name.strip_prefix(stem).unwrap_or(&name).to_string()
+        })
+        .collect();
+
+    (0..)
+        .map(|n| {
+            let stem = if n == 0 {
+                stem.to_string()
+            } else {
+                format!("{} ({})", stem, n)
+            };
+            suffixes
+                .iter()
+                .map(|suffix| dir.join(format!("{}{}", stem, suffix)))
+                .collect::<Vec<_>>()
         })
+        .find(|paths| paths.iter().all(|path| !path.exists()))
+        .expect("Infinite candidates")
+}
+
+/// Moves files, all or nothing: if one can't be moved, those already moved are moved back.
+fn move_files(moves: &[(&Path, &Path)]) -> Result<()> {
+    for (i, (from, to)) in moves.iter().enumerate() {
+        if let Err(e) = move_file(from, to) {
+            move_files_back(&moves[..i]);
+            return Err(e).with_context(|| format!("Failed moving {:?} to {:?}", from, to));
+        }
+    }
+    Ok(())
+}
+
+/// Reverses moves made by `move_files`.
+fn move_files_back(moves: &[(&Path, &Path)]) {
+    for (from, to) in moves.iter().rev() {
+        if let Err(e) = move_file(to, from) {
+            error!("Failed moving {:?} back to {:?}: {:?}", to, from, e);
+        }
+    }
+}
+
+/// Moves a file, copying it and deleting the original when moving to another file
+/// system, which can't be done with a rename.
+fn move_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    match fs::rename(from, to) {
+        Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+            super::export::copy_file(from, to)?;
+
+            fs::remove_file(from).inspect_err(|_| {
+                let _ = fs::remove_file(to);
+            })
+        }
+        result => result,
+    }
+}
+
+/// Remembers when a sidecar was last written or imported.
+fn record_sidecar(
+    con: &rusqlite::Connection,
+    picture_id: &PictureId,
+    modified_ts: &DateTime<Utc>,
+) -> Result<()> {
+    con.execute(
+        "INSERT INTO pictures_sidecars (picture_id, sidecar_modified_ts) VALUES (?1, ?2)
+        ON CONFLICT (picture_id) DO UPDATE SET sidecar_modified_ts = ?2",
+        params![picture_id.id(), modified_ts],
+    )?;
+    Ok(())
+}
+
+fn sidecar_modified_ts(sidecar: &Path) -> Result<DateTime<Utc>> {
+    let modified = fs::metadata(sidecar)?.modified()?;
+    Ok(DateTime::from(modified))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn test_repo() -> (TempDir, Repository) {
+        let dir = tempfile::tempdir().unwrap();
+        let root = FlatpakPathBuf::build(dir.path(), dir.path());
+        let library_roots = LibraryRoots::build(&root, &[]);
+        let con = Arc::new(Mutex::new(database::setup_in_memory().unwrap()));
+        let repo = Repository::open(&library_roots, dir.path(), dir.path(), con).unwrap();
+        (dir, repo)
+    }
+
+    #[test]
+    fn add_all_records_file_details() {
+        let (dir, mut repo) = test_repo();
+        let decodable = dir.path().join("a.png");
+        image::RgbImage::new(3, 2).save(&decodable).unwrap();
+        let undecodable = dir.path().join("b.jpg");
+        std::fs::write(&undecodable, b"not a jpeg").unwrap();
+
+        repo.add_all(&vec![
+            ScannedFile::Photo(decodable.clone()),
+            ScannedFile::Photo(undecodable.clone()),
+        ])
+        .unwrap();
+
+        let pictures = repo.all().unwrap();
+        let find = |path: &Path| {
+            pictures
+                .iter()
+                .find(|pic| pic.sandbox_path() == path)
+                .unwrap()
+        };
+
+        let png = find(&decodable);
+        assert_eq!((Some(3), Some(2)), (png.width, png.height));
+        assert_eq!(
+            fs::metadata(&decodable).ok().map(|m| m.len()),
+            png.byte_size
+        );
+
+        let not_jpeg = find(&undecodable);
+        assert_eq!((None, None), (not_jpeg.width, not_jpeg.height));
+        assert_eq!(Some(10), not_jpeg.byte_size);
+    }
+
+    #[test]
+    fn duplicate_added_before_canonical_paths_is_trashed_not_deleted() {
+        let (dir, mut repo) = test_repo();
+        let real = dir.path().join("real");
+        fs::create_dir(&real).unwrap();
+        std::os::unix::fs::symlink(&real, dir.path().join("alias")).unwrap();
+
+        let path = real.join("a.png");
+        let alias = dir.path().join("alias").join("a.png");
+        image::RgbImage::new(1, 1).save(&path).unwrap();
+
+        // Added by its alias before canonical paths were recorded.
+        repo.add_all(&vec![ScannedFile::Photo(alias.clone())])
+            .unwrap();
+        let duplicate = repo.all().unwrap()[0].picture_id;
+        repo.set_favorite(&[duplicate], true).unwrap();
+        database::lock(&repo.con)
+            .execute("UPDATE pictures SET canonical_path_b64 = NULL", [])
+            .unwrap();
+
+        repo.add_all(&vec![ScannedFile::Photo(path.clone())])
+            .unwrap();
+        repo.add_all(&vec![ScannedFile::Photo(alias.clone())])
+            .unwrap();
+
+        let pictures = repo.all().unwrap();
+        assert_eq!(1, pictures.len());
+        assert_eq!(&path, pictures[0].sandbox_path());
+
+        let trashed = repo.trashed().unwrap();
+        assert_eq!(1, trashed.len());
+        assert_eq!(duplicate, trashed[0].picture_id);
+        assert!(trashed[0].is_favorite);
+
+        let thumbnailer = thumbnailify::Thumbnailer::build(&dir.path().join("thumbnails"));
+        assert_eq!(1, repo.empty_trash(true, &thumbnailer).unwrap());
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn export_metadata_writes_one_line_per_picture() {
+        let (dir, mut repo) = test_repo();
+        image::RgbImage::new(3, 2)
+            .save(dir.path().join("a.png"))
+            .unwrap();
+        std::fs::write(dir.path().join("b.jpg"), b"not a jpeg").unwrap();
+
+        repo.add_all(&vec![
+            ScannedFile::Photo(dir.path().join("a.png")),
+            ScannedFile::Photo(dir.path().join("b.jpg")),
+        ])
+        .unwrap();
+
+        let png = repo
+            .all()
+            .unwrap()
+            .into_iter()
+            .find(|pic| pic.sandbox_path().ends_with("a.png"))
+            .unwrap();
+        repo.set_favorite(&[png.picture_id], true).unwrap();
+
+        let mut progress = 0;
+        let mut data = Vec::new();
+        let count = repo
+            .export_metadata(ExportFormat::Csv, 0.0, true, &mut data, || progress += 1)
+            .unwrap();
+
+        assert_eq!(2, count);
+        assert_eq!(2, progress);
+
+        let data = String::from_utf8(data).unwrap();
+        let mut lines: Vec<&str> = data.lines().collect();
+        assert_eq!(
+            "path,taken_at,width,height,favorite,tags,people,latitude,longitude",
+            lines.remove(0)
+        );
+
+        lines.sort();
+        let png_line = format!("{},,3,2,true,,,,", dir.path().join("a.png").display());
+        let jpg_line = format!("{},,,,false,,,,", dir.path().join("b.jpg").display());
+        assert_eq!(vec![png_line.as_str(), jpg_line.as_str()], lines);
+    }
+
+    #[test]
+    fn export_metadata_leaves_out_private_pictures_unless_included() {
+        let (dir, mut repo) = test_repo();
+        for name in ["a.png", "b.png"] {
+            image::RgbImage::new(1, 1)
+                .save(dir.path().join(name))
+                .unwrap();
+        }
+
+        repo.add_all(&vec![
+            ScannedFile::Photo(dir.path().join("a.png")),
+            ScannedFile::Photo(dir.path().join("b.png")),
+        ])
+        .unwrap();
+
+        let private = repo
+            .all()
+            .unwrap()
+            .into_iter()
+            .find(|pic| pic.sandbox_path().ends_with("a.png"))
+            .unwrap();
+        repo.set_private(&private.picture_id, true).unwrap();
+
+        let export = |include_private: bool| {
+            let mut data = Vec::new();
+            repo.export_metadata(ExportFormat::Csv, 0.0, include_private, &mut data, || {})
+                .unwrap();
+            String::from_utf8(data).unwrap()
+        };
+
+        let locked = export(false);
+        assert!(!locked.contains("a.png"));
+        assert!(locked.contains("b.png"));
+
+        let unlocked = export(true);
+        assert!(unlocked.contains("a.png"));
+        assert!(unlocked.contains("b.png"));
+    }
+
+    #[test]
+    fn sidecars_changed_by_other_tools_are_imported() {
+        let (dir, mut repo) = test_repo();
+        for name in ["a.png", "b.png"] {
+            image::RgbImage::new(1, 1)
+                .save(dir.path().join(name))
+                .unwrap();
+        }
+
+        let mut tag_repo = tag::Repository::open(repo.con.clone()).unwrap();
+
+        repo.add_all(&vec![
+            ScannedFile::Photo(dir.path().join("a.png")),
+            ScannedFile::Photo(dir.path().join("b.png")),
+        ])
+        .unwrap();
+
+        let find = |name: &str| {
+            repo.all()
+                .unwrap()
+                .into_iter()
+                .find(|pic| pic.sandbox_path().ends_with(name))
+                .unwrap()
+                .picture_id
+        };
+        let (a, b) = (find("a.png"), find("b.png"));
+
+        repo.set_favorite(&[a], true).unwrap();
+        tag_repo.add_tag(&a, "dog").unwrap();
+
+        assert_eq!(1, repo.write_sidecars(|| {}).unwrap());
+        let imported = repo
+            .import_sidecars(SidecarPolicy::SidecarWins, || {})
+            .unwrap();
+        assert_eq!(0, imported);
+
+        // Another photo manager changes the sidecars.
+        let edit = |name: &str, is_favorite: bool, tag: &str, delay: u64| {
+            let picture = dir.path().join(name);
+            let metadata = SidecarMetadata {
+                is_favorite,
+                tags: vec![String::from(tag)],
+            };
+            let sidecar = xmp::write_sidecar(&picture, &metadata).unwrap();
+            let modified = std::time::SystemTime::now() + std::time::Duration::from_secs(delay);
+            fs::File::options()
+                .write(true)
+                .open(sidecar)
+                .unwrap()
+                .set_modified(modified)
+                .unwrap();
+        };
+        edit("a.png", false, "cat", 60);
+        edit("b.png", true, "bird", 60);
+
+        // Only the picture without a favorite or tags takes the sidecar.
+        let imported = repo
+            .import_sidecars(SidecarPolicy::DatabaseWins, || {})
+            .unwrap();
+        assert_eq!(1, imported);
+        assert!(repo.get(&a).unwrap().unwrap().is_favorite);
+        assert_eq!(vec!["dog"], tag_repo.tags_for(&a).unwrap());
+        assert!(repo.get(&b).unwrap().unwrap().is_favorite);
+        assert_eq!(vec!["bird"], tag_repo.tags_for(&b).unwrap());
+
+        edit("a.png", false, "cat", 120);
+
+        let imported = repo
+            .import_sidecars(SidecarPolicy::SidecarWins, || {})
+            .unwrap();
+        assert_eq!(1, imported);
+        assert!(!repo.get(&a).unwrap().unwrap().is_favorite);
+        assert_eq!(vec!["cat"], tag_repo.tags_for(&a).unwrap());
+    }
+
+    #[test]
+    fn pictures_of_removed_root_are_trashed_but_never_deleted() {
+        let dir = tempfile::tempdir().unwrap();
+        let primary = dir.path().join("primary");
+        let extra = dir.path().join("extra");
+        fs::create_dir(&primary).unwrap();
+        fs::create_dir(&extra).unwrap();
+
+        let kept = primary.join("a.png");
+        let removed = extra.join("b.png");
+        for path in [&kept, &removed] {
+            image::RgbImage::new(1, 1).save(path).unwrap();
+        }
+
+        let primary_root = FlatpakPathBuf::build(&primary, &primary);
+        let extra_root = FlatpakPathBuf::build(&extra, &extra);
+        let con = Arc::new(Mutex::new(database::setup_in_memory().unwrap()));
+
+        let library_roots = LibraryRoots::build(&primary_root, &[extra_root]);
+        let mut repo =
+            Repository::open(&library_roots, dir.path(), dir.path(), con.clone()).unwrap();
+        repo.add_all(&vec![
+            ScannedFile::Photo(kept.clone()),
+            ScannedFile::Photo(removed.clone()),
+        ])
+        .unwrap();
+        assert_eq!(0, repo.reconcile_missing().unwrap());
+
+        let library_roots = LibraryRoots::build(&primary_root, &[]);
+        let mut repo = Repository::open(&library_roots, dir.path(), dir.path(), con).unwrap();
+        assert_eq!(1, repo.reconcile_missing().unwrap());
+        assert_eq!(1, repo.trashed().unwrap().len());
+
+        let thumbnailer = thumbnailify::Thumbnailer::build(&dir.path().join("thumbnails"));
+        assert_eq!(1, repo.empty_trash(true, &thumbnailer).unwrap());
+        assert!(removed.exists());
+        assert!(kept.exists());
+        assert_eq!(1, repo.all().unwrap().len());
+    }
+
+    #[test]
+    fn missing_picture_is_located_by_content() {
+        let (dir, mut repo) = test_repo();
+        let original = dir.path().join("a.png");
+        image::RgbImage::new(1, 1).save(&original).unwrap();
+
+        repo.add_all(&vec![ScannedFile::Photo(original.clone())])
+            .unwrap();
+        let picture_id = repo.all().unwrap()[0].picture_id;
+        let hash = content_hash::from_path(&original).unwrap();
+        repo.set_content_hashes(vec![(picture_id, hash)]).unwrap();
+        repo.set_favorite(&[picture_id], true).unwrap();
+
+        assert_eq!(0, repo.reconcile_missing().unwrap());
+
+        // Moved outside Fotema, then found by the next scan as a new picture.
+        fs::create_dir(dir.path().join("moved")).unwrap();
+        let moved = dir.path().join("moved").join("a.png");
+        fs::rename(&original, &moved).unwrap();
+
+        assert_eq!(1, repo.reconcile_missing().unwrap());
+        assert!(repo.all().unwrap().is_empty());
+
+        let missing = repo.missing().unwrap();
+        assert_eq!(1, missing.len());
+        assert!(missing[0].last_seen_at.is_some());
+
+        repo.add_all(&vec![ScannedFile::Photo(moved.clone())])
+            .unwrap();
+
+        let other = dir.path().join("b.png");
+        image::RgbImage::new(2, 2).save(&other).unwrap();
+        let thumbnailer = thumbnailify::Thumbnailer::build(&dir.path().join("thumbnails"));
+
+        let other = FlatpakPathBuf::build(&other, &other);
+        let located = repo.locate(&picture_id, &other, &thumbnailer).unwrap();
+        assert!(located.is_none());
+
+        // Picked with the file chooser, so not a path under the library root.
+        let picked = FlatpakPathBuf::build(&moved, "/run/user/1000/doc/abc123/a.png");
+        let located = repo.locate(&picture_id, &picked, &thumbnailer).unwrap();
+        assert_eq!(Some(moved.clone()), located.map(|path| path.sandbox_path));
+
+        let pictures = repo.all().unwrap();
+        assert_eq!(1, pictures.len());
+        assert_eq!(picture_id, pictures[0].picture_id);
+        assert!(pictures[0].is_favorite);
+        assert!(repo.missing().unwrap().is_empty());
+    }
+
+    #[test]
+    fn needs_rethumbnail_finds_missing_and_stale_thumbnails() {
+        let (dir, mut repo) = test_repo();
+        let current = dir.path().join("a.png");
+        let missing = dir.path().join("b.png");
+        let stale = dir.path().join("c.png");
+        for path in [&current, &missing, &stale] {
+            image::RgbImage::new(1, 1).save(path).unwrap();
+        }
+
+        repo.add_all(&vec![
+            ScannedFile::Photo(current.clone()),
+            ScannedFile::Photo(missing.clone()),
+            ScannedFile::Photo(stale.clone()),
+        ])
+        .unwrap();
+        let pictures = repo.all().unwrap();
+
+        let thumbnailer = thumbnailify::Thumbnailer::build(&dir.path().join("thumbnails"));
+        for pic in pictures.iter().filter(|pic| *pic.sandbox_path() != missing) {
+            thumbnailer
+                .generate_thumbnail(
+                    &pic.path,
+                    ThumbnailSize::XLarge,
+                    image::DynamicImage::new_rgb8(1, 1),
+                )
+                .unwrap();
+        }
+        let blurhashes: Vec<_> = pictures
+            .iter()
+            .map(|pic| (pic.picture_id, "LEHV6nWB2yk8pyo0adR*.7kCMdnj".to_string()))
+            .collect();
+        repo.set_blurhashes(&blurhashes).unwrap();
+
+        // Changed since it was thumbnailed.
+        fs::write(&stale, b"changed").unwrap();
+
+        let needed: HashSet<PathBuf> = repo
+            .needs_rethumbnail(&thumbnailer)
+            .unwrap()
+            .into_iter()
+            .map(|pic| pic.sandbox_path().clone())
+            .collect();
+        assert_eq!(HashSet::from([missing, stale]), needed);
+    }
+
+    #[test]
+    fn thumbnail_queue_survives_until_thumbnailed() {
+        let (dir, mut repo) = test_repo();
+        for name in ["a.png", "b.png", "c.png"] {
+            image::RgbImage::new(1, 1)
+                .save(dir.path().join(name))
+                .unwrap();
+        }
+
+        repo.add_all(&vec![
+            ScannedFile::Photo(dir.path().join("a.png")),
+            ScannedFile::Photo(dir.path().join("b.png")),
+            ScannedFile::Photo(dir.path().join("c.png")),
+        ])
+        .unwrap();
+
+        let ids: Vec<PictureId> = repo.all().unwrap().iter().map(|p| p.picture_id).collect();
+        repo.queue_thumbnails(&ids).unwrap();
+
+        repo.set_blurhashes(&[(ids[0], "hash".into())]).unwrap();
+        repo.mark_broken(&ids[1], "Unreadable").unwrap();
+
+        let queued: Vec<PictureId> = repo
+            .queued_thumbnails()
+            .unwrap()
+            .iter()
+            .map(|p| p.picture_id)
+            .collect();
+        assert_eq!(vec![ids[2]], queued);
+
+        repo.dequeue_thumbnail(&ids[2]).unwrap();
+        assert!(repo.queued_thumbnails().unwrap().is_empty());
+    }
+
+    #[test]
+    fn get_missing_picture_is_none() {
+        let (_dir, repo) = test_repo();
+
+        assert!(repo.get(&PictureId::new(1)).unwrap().is_none());
+    }
+
+    #[test]
+    fn get_trashed_picture() {
+        let (dir, mut repo) = test_repo();
+        let path = dir.path().join("a.png");
+        image::RgbImage::new(1, 1).save(&path).unwrap();
+
+        repo.add_all(&vec![ScannedFile::Photo(path.clone())])
+            .unwrap();
+        let picture_id = repo.all().unwrap()[0].picture_id;
+
+        assert_eq!(vec![picture_id], repo.trash(&[picture_id]).unwrap());
+        assert!(repo.all().unwrap().is_empty());
+
+        let picture = repo.get(&picture_id).unwrap().unwrap();
+        assert_eq!(picture_id, picture.picture_id);
+        assert_eq!(&path, picture.sandbox_path());
+    }
+
+    #[test]
+    fn trash_and_restore_round_trip() {
+        let (dir, mut repo) = test_repo();
+        for name in ["a.png", "b.png"] {
+            image::RgbImage::new(1, 1)
+                .save(dir.path().join(name))
+                .unwrap();
+        }
+
+        let files: Vec<_> = ["a.png", "b.png"]
+            .iter()
+            .map(|name| ScannedFile::Photo(dir.path().join(name)))
+            .collect();
+        repo.add_all(&files).unwrap();
+
+        let id = |name: &str| {
+            repo.all()
+                .unwrap()
+                .into_iter()
+                .find(|pic| pic.sandbox_path().ends_with(name))
+                .unwrap()
+                .picture_id
+        };
+        let (a, b) = (id("a.png"), id("b.png"));
+
+        assert_eq!(vec![a], repo.trash(&[a]).unwrap());
+
+        // Only pictures that move in or out of the trash are changed.
+        assert_eq!(vec![b], repo.trash(&[a, b]).unwrap());
+        assert!(repo.all().unwrap().is_empty());
+
+        let trashed: Vec<_> = repo
+            .trashed()
+            .unwrap()
+            .iter()
+            .map(|pic| pic.picture_id)
+            .collect();
+        assert!(trashed.contains(&a) && trashed.contains(&b));
+
+        assert_eq!(vec![a], repo.restore(&[a]).unwrap());
+        assert!(repo.restore(&[a]).unwrap().is_empty());
+
+        let all: Vec<_> = repo
+            .all()
+            .unwrap()
+            .iter()
+            .map(|pic| pic.picture_id)
+            .collect();
+        assert_eq!(vec![a], all);
+        let trashed: Vec<_> = repo
+            .trashed()
+            .unwrap()
+            .iter()
+            .map(|pic| pic.picture_id)
+            .collect();
+        assert_eq!(vec![b], trashed);
+    }
+
+    #[test]
+    fn empty_trash_deletes_trashed_files() {
+        let (dir, mut repo) = test_repo();
+        let kept = dir.path().join("a.png");
+        let trashed = dir.path().join("b.png");
+        for path in [&kept, &trashed] {
+            image::RgbImage::new(1, 1).save(path).unwrap();
+        }
+
+        repo.add_all(&vec![
+            ScannedFile::Photo(kept.clone()),
+            ScannedFile::Photo(trashed.clone()),
+        ])
+        .unwrap();
+        let trashed_id = repo
+            .all()
+            .unwrap()
+            .into_iter()
+            .find(|pic| pic.sandbox_path() == &trashed)
+            .unwrap()
+            .picture_id;
+        repo.trash(&[trashed_id]).unwrap();
+
+        // Thumbnail saved beside the picture rather than centrally.
+        let sidecar_thumbnail = thumbnailify::get_thumbnail_hash_output(
+            &dir.path().join(".fotema"),
+            &FlatpakPathBuf::build(&trashed, &trashed).thumbnail_hash(),
+            ThumbnailSize::XLarge,
+        );
+        fs::create_dir_all(sidecar_thumbnail.parent().unwrap()).unwrap();
+        fs::write(&sidecar_thumbnail, b"thumbnail").unwrap();
+
+        let thumbnailer = thumbnailify::Thumbnailer::build(&dir.path().join("thumbnails"));
+        assert_eq!(1, repo.empty_trash(true, &thumbnailer).unwrap());
+
+        assert!(repo.trashed().unwrap().is_empty());
+        assert!(repo.get(&trashed_id).unwrap().is_none());
+        assert!(!trashed.exists());
+        assert!(!sidecar_thumbnail.exists());
+        assert!(kept.exists());
+        assert_eq!(1, repo.all().unwrap().len());
+
+        assert_eq!(0, repo.empty_trash(true, &thumbnailer).unwrap());
+    }
+
+    #[test]
+    fn remove_from_library_removes_thumbnails() {
+        let (dir, mut repo) = test_repo();
+        let path = dir.path().join("a.png");
+        image::RgbImage::new(1, 1).save(&path).unwrap();
+
+        repo.add_all(&vec![ScannedFile::Photo(path.clone())])
+            .unwrap();
+        let picture = repo.all().unwrap().remove(0);
+
+        let thumbnails_path = dir.path().join("thumbnails");
+        let central_thumbnail = picture
+            .path
+            .thumbnail_path(&thumbnails_path, ThumbnailSize::XLarge);
+        let sidecar_thumbnail = picture
+            .path
+            .thumbnail_path(&dir.path().join(".fotema"), ThumbnailSize::XLarge);
+        for thumbnail in [&central_thumbnail, &sidecar_thumbnail] {
+            fs::create_dir_all(thumbnail.parent().unwrap()).unwrap();
+            fs::write(thumbnail, b"thumbnail").unwrap();
+        }
+
+        let thumbnailer = thumbnailify::Thumbnailer::build(&thumbnails_path);
+        repo.remove_from_library(&picture.picture_id, &thumbnailer)
+            .unwrap();
+
+        assert!(repo.get(&picture.picture_id).unwrap().is_none());
+        assert!(path.exists());
+        assert!(!central_thumbnail.exists());
+        assert!(!sidecar_thumbnail.exists());
+    }
+
+    #[test]
+    fn empty_trash_keeps_files_unless_deleting_and_never_deletes_auto_trashed_files() {
+        let (dir, mut repo) = test_repo();
+        let kept = dir.path().join("a.png");
+        let ignored = dir.path().join("b.png");
+        for path in [&kept, &ignored] {
+            image::RgbImage::new(1, 1).save(path).unwrap();
+        }
+
+        repo.add_all(&vec![
+            ScannedFile::Photo(kept.clone()),
+            ScannedFile::Photo(ignored.clone()),
+        ])
+        .unwrap();
+        let kept_id = repo
+            .all()
+            .unwrap()
+            .into_iter()
+            .find(|pic| pic.sandbox_path() == &kept)
+            .unwrap()
+            .picture_id;
+        repo.trash(&[kept_id]).unwrap();
+        assert_eq!(1, repo.trash_ignored(|path| path == ignored).unwrap());
+
+        let thumbnailer = thumbnailify::Thumbnailer::build(&dir.path().join("thumbnails"));
+        assert_eq!(2, repo.empty_trash(false, &thumbnailer).unwrap());
+        assert!(repo.trashed().unwrap().is_empty());
+        assert!(kept.exists());
+        assert!(ignored.exists());
+
+        // Restoring forgets that a picture was trashed automatically.
+        repo.add_all(&vec![ScannedFile::Photo(ignored.clone())])
+            .unwrap();
+        let ignored_id = repo.all().unwrap()[0].picture_id;
+        assert_eq!(1, repo.trash_ignored(|path| path == ignored).unwrap());
+        repo.restore(&[ignored_id]).unwrap();
+        repo.trash(&[ignored_id]).unwrap();
+
+        assert_eq!(1, repo.empty_trash(true, &thumbnailer).unwrap());
+        assert!(!ignored.exists());
+    }
+
+    #[test]
+    fn similar_pictures_are_within_distance() {
+        let (dir, mut repo) = test_repo();
+        for name in ["a.png", "b.png", "c.png", "d.png"] {
+            image::RgbImage::new(1, 1)
+                .save(dir.path().join(name))
+                .unwrap();
+        }
+
+        let files: Vec<_> = ["a.png", "b.png", "c.png", "d.png"]
+            .iter()
+            .map(|name| ScannedFile::Photo(dir.path().join(name)))
+            .collect();
+        repo.add_all(&files).unwrap();
+
+        let id = |name: &str| {
+            repo.all()
+                .unwrap()
+                .into_iter()
+                .find(|pic| pic.sandbox_path().ends_with(name))
+                .unwrap()
+                .picture_id
+        };
+        let (a, b, c, d) = (id("a.png"), id("b.png"), id("c.png"), id("d.png"));
+
+        assert!(repo.similar_to(&a, 10).unwrap().is_empty());
+
+        // b differs from a by three bits, c by twenty, and d is identical but trashed.
+        repo.set_perceptual_hashes(&[
+            (a, PerceptualHash::from_i64(0)),
+            (b, PerceptualHash::from_i64(0b111)),
+            (c, PerceptualHash::from_i64(0xfffff)),
+            (d, PerceptualHash::from_i64(0)),
+        ])
+        .unwrap();
+        repo.trash(&[d]).unwrap();
+
+        let similar: Vec<_> = repo
+            .similar_to(&a, 10)
+            .unwrap()
+            .into_iter()
+            .map(|pic| pic.picture_id)
+            .collect();
+        assert_eq!(vec![b], similar);
+
+        let similar: Vec<_> = repo
+            .similar_to(&a, 32)
+            .unwrap()
+            .into_iter()
+            .map(|pic| pic.picture_id)
+            .collect();
+        assert_eq!(vec![b, c], similar);
+    }
+
+    #[test]
+    fn broken_keeps_reason_until_reset() {
+        let (dir, mut repo) = test_repo();
+        let path = dir.path().join("a.png");
+        image::RgbImage::new(1, 1).save(&path).unwrap();
+
+        repo.add_all(&vec![ScannedFile::Photo(path.clone())])
+            .unwrap();
+        let picture_id = repo.all().unwrap()[0].picture_id;
+
+        repo.mark_broken(&picture_id, "Unsupported format").unwrap();
+
+        let broken = repo.broken().unwrap();
+        assert_eq!(1, broken.len());
+        assert_eq!(picture_id, broken[0].picture_id);
+        assert_eq!(path, broken[0].path.sandbox_path);
+        assert_eq!(Some("Unsupported format".into()), broken[0].reason);
+        assert!(broken[0].broken_at.is_some());
+
+        repo.reset_thumbnail(&picture_id).unwrap();
+        assert!(repo.broken().unwrap().is_empty());
+    }
+
+    #[test]
+    fn search_by_filename_matches_file_name_ignoring_case() {
+        let (dir, mut repo) = test_repo();
+        std::fs::create_dir(dir.path().join("Holiday")).unwrap();
+        let paths = [
+            dir.path().join("IMG_1234.png"),
+            dir.path().join("Holiday").join("beach.png"),
+        ];
+        for path in &paths {
+            image::RgbImage::new(1, 1).save(path).unwrap();
+        }
+        repo.add_all(&paths.iter().cloned().map(ScannedFile::Photo).collect())
+            .unwrap();
+
+        let found = repo.search_by_filename("img_12").unwrap();
+        assert_eq!(1, found.len());
+        assert_eq!(paths[0], *found[0].sandbox_path());
+
+        // Folder names aren't searched.
+        assert!(repo.search_by_filename("holiday").unwrap().is_empty());
+
+        assert_eq!(2, repo.search_by_filename("").unwrap().len());
+    }
+
+    #[test]
+    fn geotagged_leaves_out_pictures_without_location_and_trashed_pictures() {
+        let (dir, mut repo) = test_repo();
+        let paths = ["a.png", "b.png", "c.png"].map(|name| dir.path().join(name));
+        for path in &paths {
+            image::RgbImage::new(1, 1).save(path).unwrap();
+        }
+        repo.add_all(&paths.iter().cloned().map(ScannedFile::Photo).collect())
+            .unwrap();
+
+        let ids: Vec<PictureId> = paths
+            .iter()
+            .map(|path| {
+                repo.all()
+                    .unwrap()
+                    .into_iter()
+                    .find(|pic| pic.sandbox_path() == path)
+                    .unwrap()
+                    .picture_id
+            })
+            .collect();
+
+        {
+            let con = database::lock(&repo.con);
+            for picture_id in [ids[0], ids[2]] {
+                con.execute(
+                    "INSERT INTO pictures_geo (picture_id, latitude, longitude)
+                    VALUES (?1, 48.85, 2.35)",
+                    params![picture_id.id()],
+                )
+                .unwrap();
+            }
+        }
+
+        repo.trash(&[ids[2]]).unwrap();
+
+        assert_eq!(vec![(ids[0], 48.85, 2.35)], repo.geotagged().unwrap());
+    }
+
+    #[test]
+    fn on_this_day_finds_same_day_in_any_year_newest_year_first() {
+        let (dir, mut repo) = test_repo();
+        let taken = [
+            ("a.png", "2020-03-05T10:00:00Z"),
+            ("b.png", "2023-03-05T09:00:00Z"),
+            ("c.png", "2023-04-05T09:00:00Z"),
+            ("d.png", "2023-03-05T08:00:00Z"),
+        ];
+        for (name, _) in taken {
+            image::RgbImage::new(1, 1)
+                .save(dir.path().join(name))
+                .unwrap();
+        }
+        repo.add_all(
+            &taken
+                .iter()
+                .map(|(name, _)| ScannedFile::Photo(dir.path().join(name)))
+                .collect(),
+        )
+        .unwrap();
+
+        {
+            let con = database::lock(&repo.con);
+            for (name, taken_at) in taken {
+                let taken_at: DateTime<Utc> = taken_at.parse().unwrap();
+                con.execute(
+                    "UPDATE pictures SET taken_at_ts = ?2 WHERE picture_path_b64 = ?1",
+                    params![path_encoding::to_base64(Path::new(name)), taken_at],
+                )
+                .unwrap();
+            }
+        }
+
+        let names: Vec<String> = repo
+            .on_this_day(3, 5)
+            .unwrap()
+            .iter()
+            .map(|pic| {
+                pic.sandbox_path()
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .to_string()
+            })
+            .collect();
+
+        assert_eq!(vec!["d.png", "b.png", "a.png"], names);
+    }
+
+    #[test]
+    fn current_content_hash_is_none_once_file_changes() {
+        let (dir, mut repo) = test_repo();
+        let path = dir.path().join("a.png");
+        image::RgbImage::new(1, 1).save(&path).unwrap();
+        repo.add_all(&vec![ScannedFile::Photo(path.clone())])
+            .unwrap();
+        let picture_id = repo.all().unwrap()[0].picture_id;
+
+        assert_eq!(None, repo.current_content_hash(&picture_id).unwrap());
+
+        let hash = content_hash::from_path(&path).unwrap();
+        repo.set_content_hashes(vec![(picture_id, hash.clone())])
+            .unwrap();
+        assert_eq!(
+            Some(hash.hash),
+            repo.current_content_hash(&picture_id).unwrap()
+        );
+
+        image::RgbImage::new(2, 2).save(&path).unwrap();
+        assert_eq!(None, repo.current_content_hash(&picture_id).unwrap());
+    }
+
+    #[test]
+    fn camera_settings_round_trip() {
+        let (dir, mut repo) = test_repo();
+        let path = dir.path().join("a.png");
+        image::RgbImage::new(1, 1).save(&path).unwrap();
+        repo.add_all(&vec![ScannedFile::Photo(path)]).unwrap();
+        let picture_id = repo.all().unwrap()[0].picture_id;
+
+        // Tags missing from a picture are read back as missing.
+        assert_eq!(
+            CameraSettings::default(),
+            repo.camera_settings(&picture_id).unwrap()
+        );
+
+        let camera = CameraSettings {
+            make: Some("Apple".into()),
+            model: Some("iPhone XS".into()),
+            iso: Some(25),
+            f_number: Some(1.8),
+            exposure_time: Some(1.0 / 1299.0),
+            focal_length: Some(4.25),
+        };
+        let metadata = Metadata {
+            camera: camera.clone(),
+            ..Default::default()
+        };
+        repo.add_metadatas(vec![(picture_id, metadata)]).unwrap();
+
+        assert_eq!(camera, repo.camera_settings(&picture_id).unwrap());
+    }
+
+    #[test]
+    fn move_picture_adds_suffix_on_collision() {
+        let (dir, mut repo) = test_repo();
+        let source = dir.path().join("a.png");
+        image::RgbImage::new(1, 1).save(&source).unwrap();
+
+        let dest_folder = dir.path().join("holiday");
+        std::fs::create_dir(&dest_folder).unwrap();
+        std::fs::write(dest_folder.join("a.png"), b"already here").unwrap();
+
+        repo.add_all(&vec![ScannedFile::Photo(source.clone())])
+            .unwrap();
+        let picture = repo.all().unwrap().remove(0);
+
+        let thumbnails_path = dir.path().join("thumbnails");
+        let thumb_path = thumbnailify::get_thumbnail_hash_output(
+            &thumbnails_path,
+            &picture.thumbnail_hash(),
+            ThumbnailSize::XLarge,
+        );
+        std::fs::create_dir_all(thumb_path.parent().unwrap()).unwrap();
+        std::fs::write(&thumb_path, b"thumbnail").unwrap();
+
+        let thumbnailer = thumbnailify::Thumbnailer::build(&thumbnails_path);
+        let moved = repo
+            .move_picture(
+                &picture.picture_id,
+                &FlatpakPathBuf::build(&dest_folder, &dest_folder),
+                &thumbnailer,
+            )
+            .unwrap();
+
+        assert_eq!(dest_folder.join("a (1).png"), moved.sandbox_path);
+        assert!(moved.exists());
+        assert!(!source.exists());
+        assert_eq!(
+            b"already here".as_slice(),
+            std::fs::read(dest_folder.join("a.png")).unwrap()
+        );
+
+        let stored = repo.get(&picture.picture_id).unwrap().unwrap();
+        assert_eq!(moved, stored.path);
+
+        assert!(!thumb_path.exists());
+        assert!(
+            moved
+                .thumbnail_path(&thumbnails_path, ThumbnailSize::XLarge)
+                .exists()
+        );
+    }
+
+    #[test]
+    fn failed_move_leaves_picture_in_place() {
+        let (dir, mut repo) = test_repo();
+        let source = dir.path().join("a.png");
+        image::RgbImage::new(1, 1).save(&source).unwrap();
+
+        let dest_folder = dir.path().join("holiday");
+        std::fs::create_dir(&dest_folder).unwrap();
+
+        repo.add_all(&vec![ScannedFile::Photo(source.clone())])
+            .unwrap();
+        let picture = repo.all().unwrap().remove(0);
+
+        // File can't be moved once it is gone.
+        std::fs::remove_file(&source).unwrap();
+
+        let thumbnailer = thumbnailify::Thumbnailer::build(&dir.path().join("thumbnails"));
+        assert!(
+            repo.move_picture(
+                &picture.picture_id,
+                &FlatpakPathBuf::build(&dest_folder, &dest_folder),
+                &thumbnailer
+            )
+            .is_err()
+        );
+
+        let stored = repo.get(&picture.picture_id).unwrap().unwrap();
+        assert_eq!(source, *stored.sandbox_path());
+    }
+
+    #[test]
+    fn move_picture_takes_live_photo_video_and_sidecar() {
+        let (dir, mut repo) = test_repo();
+        let source = dir.path().join("a.png");
+        image::RgbImage::new(1, 1).save(&source).unwrap();
+        let video = dir.path().join("a.mov");
+        std::fs::write(&video, b"video").unwrap();
+        let sidecar = dir.path().join("a.png.xmp");
+        std::fs::write(&sidecar, b"<rdf:Description/>").unwrap();
+
+        let dest_folder = dir.path().join("holiday");
+        std::fs::create_dir(&dest_folder).unwrap();
+        std::fs::write(dest_folder.join("a.mov"), b"already here").unwrap();
+
+        repo.add_all(&vec![ScannedFile::Photo(source.clone())])
+            .unwrap();
+        let picture = repo.all().unwrap().remove(0);
+
+        {
+            let con = database::lock(&repo.con);
+            con.execute(
+                "UPDATE pictures SET content_id = 'live' WHERE picture_id = ?1",
+                params![picture.picture_id.id()],
+            )
+            .unwrap();
+            con.execute(
+                "INSERT INTO videos (
+                    video_path_b64, video_path_lossy, link_path_b64, link_path_lossy,
+                    fs_created_ts, content_id
+                ) VALUES (?1, 'a.mov', ?2, 'a', ?3, 'live')",
+                params![
+                    path_encoding::to_base64(Path::new("a.mov")),
+                    path_encoding::to_base64(Path::new("a")),
+                    Utc::now(),
+                ],
+            )
+            .unwrap();
+        }
+
+        // Picked with the folder chooser, so not a path under the library root.
+        let picked = FlatpakPathBuf::build(&dest_folder, "/run/user/1000/doc/abc123/holiday");
+
+        let thumbnailer = thumbnailify::Thumbnailer::build(&dir.path().join("thumbnails"));
+        let moved = repo
+            .move_picture(&picture.picture_id, &picked, &thumbnailer)
+            .unwrap();
+
+        // Named to match the video, which couldn't keep its name.
+        assert_eq!(dest_folder.join("a (1).png"), moved.sandbox_path);
+        assert_eq!(
+            b"video".as_slice(),
+            std::fs::read(dest_folder.join("a (1).mov")).unwrap()
+        );
+        assert!(dest_folder.join("a (1).png.xmp").exists());
+        assert!(!video.exists());
+        assert!(!sidecar.exists());
+
+        let video_path: String = database::lock(&repo.con)
+            .query_row(
+                "SELECT videos.video_path_b64 FROM pictures
+                INNER JOIN videos USING (link_path_b64, content_id)
+                WHERE pictures.picture_id = ?1",
+                params![picture.picture_id.id()],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(
+            PathBuf::from("holiday/a (1).mov"),
+            path_encoding::from_base64(&video_path).unwrap()
+        );
     }
 }
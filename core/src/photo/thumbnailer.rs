@@ -4,15 +4,56 @@
 
 use anyhow::*;
 
-use image::ImageReader;
+use image::{DynamicImage, ImageReader};
 
 use gdk4::prelude::TextureExt;
 use glycin;
+use std::cmp::Reverse;
 use std::io::Cursor;
-use tracing::error;
+use strum::{AsRefStr, EnumString, FromRepr};
+use tracing::{error, info, warn};
 
-use crate::FlatpakPathBuf;
-use crate::thumbnailify;
+use crate::file_types;
+use crate::photo::decode_budget::{self, DECODE_BUDGET, DecodePermit};
+use crate::photo::model::Picture;
+use crate::photo::perceptual_hash::PerceptualHash;
+use crate::photo::raw;
+use crate::thumbnailify::{self, ThumbnailSize};
+use crate::{Availability, FlatpakPathBuf};
+
+/// Order that pictures are thumbnailed in. Only changes which pictures get
+/// thumbnails first, not which pictures get thumbnails.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, EnumString, AsRefStr, FromRepr)]
+#[repr(u32)]
+pub enum ThumbnailOrder {
+    /// Most recent pictures first, so that the top of the library fills in first.
+    #[default]
+    NewestFirst,
+
+    /// Oldest pictures first, such as for an imported archive.
+    OldestFirst,
+
+    /// Biggest files first, because they take longest.
+    LargestFirst,
+}
+
+impl ThumbnailOrder {
+    /// Sorts pictures into the order they should be thumbnailed in.
+    /// Pictures of the same size are thumbnailed newest first.
+    pub fn sort(&self, pictures: &mut [Picture]) {
+        match self {
+            ThumbnailOrder::NewestFirst => {
+                pictures.sort_by_key(|pic| Reverse(pic.ordering_ts));
+            }
+            ThumbnailOrder::OldestFirst => {
+                pictures.sort_by_key(|pic| pic.ordering_ts);
+            }
+            ThumbnailOrder::LargestFirst => {
+                pictures.sort_by_key(|pic| Reverse((pic.byte_size.unwrap_or(0), pic.ordering_ts)));
+            }
+        }
+    }
+}
 
 /// Thumbnail operations for photos.
 #[derive(Debug, Clone)]
@@ -25,20 +66,119 @@ impl PhotoThumbnailer {
         Ok(PhotoThumbnailer { thumbnailer })
     }
 
+    /// Thumbnails of every kind of file, such as for finding where they are saved.
+    pub fn thumbnailer(&self) -> &thumbnailify::Thumbnailer {
+        &self.thumbnailer
+    }
+
+    /// Checks thumbnails can be saved.
+    pub fn check_writable(&self) -> std::result::Result<(), thumbnailify::ThumbnailError> {
+        self.thumbnailer.check_writable()
+    }
+
+    /// Deletes thumbnails that are truncated or corrupt, so that they are generated again.
+    pub fn verify_cache(&self) -> std::result::Result<Vec<String>, thumbnailify::ThumbnailError> {
+        self.thumbnailer.verify_cache()
+    }
+
     /// Computes a preview square for an image that has been inserted
-    /// into the Repository. Preview image will be written to file system and path returned.
-    pub async fn thumbnail(&self, path: &FlatpakPathBuf) -> Result<()> {
+    /// into the Repository. Preview image will be written to file system.
+    /// Returns a blurhash of the image to use as a placeholder.
+    /// `content_hash` is the stored hash of the picture's contents, if it is up to date,
+    /// which finds thumbnails of a copy of the picture to reuse.
+    pub async fn thumbnail(
+        &self,
+        path: &FlatpakPathBuf,
+        content_hash: Option<&str>,
+    ) -> Result<String> {
         if self.thumbnailer.is_failed(&path.host_path) {
             anyhow::bail!("Failed thumbnail marker exists for {:?}", path.host_path);
         }
 
-        self.thumbnail_internal(path).await.map_err(|err| {
-            let _ = self.thumbnailer.write_failed_thumbnail(path);
-            err
-        })
+        self.thumbnail_internal(path, content_hash)
+            .await
+            .map_err(|err| {
+                // A picture on a network share that can't be reached right now should be
+                // tried again later, rather than being remembered as failed.
+                if path.availability() != Availability::Unavailable {
+                    let _ = self.thumbnailer.write_failed_thumbnail(path);
+                }
+                err
+            })
+    }
+
+    /// Deletes the thumbnails of an image, and forgets that thumbnailing failed,
+    /// so that the next call to `thumbnail` starts from scratch.
+    /// Thumbnails shared with copies of the image are deleted too, so they aren't reused.
+    pub fn remove_thumbnails(
+        &self,
+        path: &FlatpakPathBuf,
+        content_hash: Option<&str>,
+    ) -> Result<()> {
+        if let Some(content_hash) = content_hash {
+            self.thumbnailer.unshare_thumbnails(path, content_hash)?;
+        }
+        self.thumbnailer.remove_thumbnails(path)?;
+        Ok(())
+    }
+
+    /// Perceptual hash of an image that has been thumbnailed, for finding images that
+    /// look alike. Computed from the largest thumbnail, rather than decoding the image again.
+    pub fn perceptual_hash(&self, path: &FlatpakPathBuf) -> Result<PerceptualHash> {
+        let thumb_path = self
+            .thumbnailer
+            .get_thumbnail_path(&path.host_path, ThumbnailSize::XLarge);
+        let thumbnail = image::open(&thumb_path)
+            .with_context(|| format!("Failed opening thumbnail {:?}", thumb_path))?;
+        Ok(PerceptualHash::from_image(&thumbnail))
     }
 
-    async fn thumbnail_internal(&self, path: &FlatpakPathBuf) -> Result<()> {
+    async fn thumbnail_internal(
+        &self,
+        path: &FlatpakPathBuf,
+        content_hash: Option<&str>,
+    ) -> Result<String> {
+        // A copy of the picture, or the picture before it moved, may already have thumbnails.
+        let reused = match content_hash {
+            Some(content_hash) => self.thumbnailer.reuse_thumbnails(path, content_hash)?,
+            None => None,
+        };
+
+        if let Some(thumb_path) = reused {
+            info!(
+                "Reusing thumbnails of identical picture for {:?}",
+                path.sandbox_path
+            );
+            let thumbnail = image::open(thumb_path)?;
+            return Ok(thumbnailify::blurhash::encode(&thumbnail));
+        }
+
+        // Memory for the decoded picture stays reserved until its thumbnails are made.
+        let (src_image, _permit) = if file_types::is_raw_picture(&path.sandbox_path) {
+            (raw::decode(&path.sandbox_path)?, None)
+        } else {
+            let (image, permit) = Self::load(path).await?;
+            (image, Some(permit))
+        };
+
+        let blurhash = thumbnailify::blurhash::encode(&src_image);
+
+        let _ = self.thumbnailer.generate_all_thumbnails(path, src_image)?;
+
+        if let Some(content_hash) = content_hash {
+            let _ = self
+                .thumbnailer
+                .share_thumbnails(path, content_hash)
+                .inspect_err(|e| warn!("Failed sharing thumbnails of {:?}: {:?}", path, e));
+        }
+
+        Ok(blurhash)
+    }
+
+    /// Loads a picture with glycin, which handles every format except camera RAW.
+    /// Blocks until the decode budget has room for the picture, and downsamples
+    /// pictures too big to decode at full size.
+    async fn load(path: &FlatpakPathBuf) -> Result<(DynamicImage, DecodePermit<'static>)> {
         let file = gio::File::for_path(&path.sandbox_path);
         let loader = glycin::Loader::new(file);
         let image = loader.load().await.map_err(|err| {
@@ -46,7 +186,28 @@ impl PhotoThumbnailer {
             err
         })?;
 
-        let frame = image.next_frame().await.map_err(|err| {
+        let width = image.details().width();
+        let height = image.details().height();
+        let (frame_request, bytes) = match decode_budget::capped_dimensions(width, height) {
+            Some((capped_width, capped_height)) => {
+                info!(
+                    "Downsampling {:?} from {}x{} to {}x{} while decoding",
+                    path.sandbox_path, width, height, capped_width, capped_height
+                );
+                (
+                    glycin::FrameRequest::new().scale(capped_width, capped_height),
+                    decode_budget::decoded_size(capped_width, capped_height),
+                )
+            }
+            None => (
+                glycin::FrameRequest::new(),
+                decode_budget::decoded_size(width, height),
+            ),
+        };
+
+        let permit = DECODE_BUDGET.acquire(bytes);
+
+        let frame = image.specific_frame(frame_request).await.map_err(|err| {
             error!(
                 "Glycin failed to fetch next frame from {:?}",
                 path.sandbox_path
@@ -58,15 +219,62 @@ impl PhotoThumbnailer {
 
         let src_image =
             ImageReader::with_format(Cursor::new(bytes), image::ImageFormat::Png).decode()?;
-        /*
-                let _ = self.thumbnailer.generate_thumbnail(
-                    path,
-                    thumbnailify::ThumbnailSize::Large,
-                    src_image.clone(),
-                )?;
-        */
-        let _ = self.thumbnailer.generate_all_thumbnails(path, src_image)?;
 
-        Ok(())
+        Ok((src_image, permit))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::photo::model::{Orientation, PictureId};
+    use chrono::{DateTime, TimeDelta};
+
+    fn picture(id: i64, days: i64, byte_size: Option<u64>) -> Picture {
+        let path = format!("/{}.jpg", id);
+        Picture {
+            path: FlatpakPathBuf::build(&path, &path),
+            picture_id: PictureId::new(id),
+            ordering_ts: DateTime::UNIX_EPOCH + TimeDelta::days(days),
+            is_selfie: None,
+            is_favorite: false,
+            blurhash: None,
+            orientation: Orientation::North,
+            width: None,
+            height: None,
+            byte_size,
+            is_broken: false,
+        }
+    }
+
+    fn sorted_ids(order: ThumbnailOrder) -> Vec<i64> {
+        let mut pictures = vec![
+            picture(1, 1, Some(100)),
+            picture(2, 3, None),
+            picture(3, 2, Some(300)),
+            picture(4, 4, Some(100)),
+        ];
+        order.sort(&mut pictures);
+        pictures.iter().map(|pic| pic.picture_id.id()).collect()
+    }
+
+    #[test]
+    fn newest_first() {
+        assert_eq!(vec![4, 2, 3, 1], sorted_ids(ThumbnailOrder::NewestFirst));
+    }
+
+    #[test]
+    fn oldest_first() {
+        assert_eq!(vec![1, 3, 2, 4], sorted_ids(ThumbnailOrder::OldestFirst));
+    }
+
+    #[test]
+    fn largest_first_then_newest() {
+        assert_eq!(vec![3, 4, 1, 2], sorted_ids(ThumbnailOrder::LargestFirst));
+    }
+
+    #[test]
+    fn default_is_newest_first() {
+        assert_eq!(ThumbnailOrder::NewestFirst, ThumbnailOrder::default());
     }
 }
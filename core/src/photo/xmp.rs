@@ -0,0 +1,793 @@
+// SPDX-FileCopyrightText: © 2025 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Creation date from an XMP packet embedded in a picture, and favorites and
+//! tags in XMP sidecar files shared with other photo managers.
+//!
+//! Only enough of XMP is understood to find a date. A packet is found by
+//! searching the start of a file, rather than by parsing each container format.
+//!
+//! Sidecars are updated in place so that properties written by other tools are kept.
+//! Original pictures are never written to.
+
+use super::model::DatePrecision;
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime};
+use quick_xml::events::{BytesEnd, BytesStart, BytesText, Event};
+use quick_xml::{Reader, Writer};
+use std::ffi::OsString;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use strum::{AsRefStr, EnumString, FromRepr};
+
+/// How much of the start of a file to search for an XMP packet.
+/// Packets are near the start of JPEG, PNG, WebP, and HEIF files.
+const SEARCH_LIMIT: u64 = 1024 * 1024;
+
+const PACKET_START: &str = "<x:xmpmeta";
+const PACKET_END: &str = "</x:xmpmeta>";
+
+/// Properties holding a creation date, from most to least preferred.
+const DATE_PROPERTIES: &[&str] = &[
+    "exif:DateTimeOriginal",
+    "photoshop:DateCreated",
+    "xmp:CreateDate",
+];
+
+/// Rating that marks a favorite. Ratings are from 1 to 5 stars.
+const FAVORITE_RATING: i32 = 5;
+
+const RATING_PROPERTY: &str = "xmp:Rating";
+const SUBJECT_START: &str = "<dc:subject>";
+const SUBJECT_END: &str = "</dc:subject>";
+
+const DESCRIPTION_ELEMENT: &[u8] = b"rdf:Description";
+const SUBJECT_ELEMENT: &[u8] = b"dc:subject";
+
+/// Namespaces declared for properties added to a sidecar, as attributes.
+const XMP_NAMESPACE: (&str, &str) = ("xmlns:xmp", "http://ns.adobe.com/xap/1.0/");
+const DC_NAMESPACE: (&str, &str) = ("xmlns:dc", "http://purl.org/dc/elements/1.1/");
+
+/// Packet for a new sidecar. Properties are added to the description.
+const SIDECAR_TEMPLATE: &str = concat!(
+    "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n",
+    r#"<x:xmpmeta xmlns:x="adobe:ns:meta/" x:xmptk="Fotema">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about=""
+    xmlns:xmp="http://ns.adobe.com/xap/1.0/"
+    xmlns:dc="http://purl.org/dc/elements/1.1/">
+  </rdf:Description>
+ </rdf:RDF>
+</x:xmpmeta>
+<?xpacket end="w"?>
+"#
+);
+
+/// Which of a sidecar and the library is kept when a sidecar has been changed by another tool.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, EnumString, AsRefStr, FromRepr)]
+#[repr(u32)]
+pub enum SidecarPolicy {
+    /// Favorite and tags from the sidecar are only imported for pictures that are
+    /// neither a favorite nor tagged in the library.
+    #[default]
+    DatabaseWins,
+
+    /// Favorite and tags from the sidecar replace those in the library.
+    SidecarWins,
+}
+
+/// User metadata of a picture kept in an XMP sidecar.
+/// A favorite is a five star rating, and tags are keywords (`dc:subject`).
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SidecarMetadata {
+    pub is_favorite: bool,
+
+    /// Names of tags.
+    pub tags: Vec<String>,
+}
+
+/// Date from an XMP packet. XMP dates can leave out the UTC offset,
+/// or the time of day, day, and month.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XmpDateTime {
+    Offset(DateTime<FixedOffset>),
+    Naive(NaiveDateTime),
+
+    /// Date without a time of day. For a year-only or year and month date,
+    /// the date is the first day of that period.
+    Partial(NaiveDate, DatePrecision),
+}
+
+impl XmpDateTime {
+    /// How much of the date and time is known.
+    pub fn precision(&self) -> DatePrecision {
+        match self {
+            XmpDateTime::Partial(_, precision) => *precision,
+            _ => DatePrecision::Exact,
+        }
+    }
+}
+
+/// Creation date from the XMP packet of a file, if it has one.
+pub fn created_at(path: &Path) -> Option<XmpDateTime> {
+    let file = fs::File::open(path).ok()?;
+    let mut data = Vec::new();
+    file.take(SEARCH_LIMIT).read_to_end(&mut data).ok()?;
+
+    let data = String::from_utf8_lossy(&data);
+    let start = data.find(PACKET_START)?;
+    let end = data[start..].find(PACKET_END)? + start;
+
+    created_at_in_packet(&data[start..end])
+}
+
+/// Creation date from an XMP packet.
+/// Properties can be written as attributes or as elements.
+pub fn created_at_in_packet(packet: &str) -> Option<XmpDateTime> {
+    DATE_PROPERTIES
+        .iter()
+        .filter_map(|property| property_value(packet, property))
+        .find_map(parse_date_time)
+}
+
+fn property_value<'a>(packet: &'a str, property: &str) -> Option<&'a str> {
+    property_value_range(packet, property).map(|(start, end)| &packet[start..end])
+}
+
+/// Range of the value of a property, written as either an attribute or an element.
+fn property_value_range(packet: &str, property: &str) -> Option<(usize, usize)> {
+    let attribute = format!("{}=\"", property);
+    if let Some(start) = packet.find(&attribute) {
+        let start = start + attribute.len();
+        let end = packet[start..]
+            .find('"')
+            .map_or(packet.len(), |end| start + end);
+        return Some((start, end));
+    }
+
+    let element = format!("<{}>", property);
+    let start = packet.find(&element)? + element.len();
+    let end = packet[start..]
+        .find('<')
+        .map_or(packet.len(), |end| start + end);
+    Some((start, end))
+}
+
+fn parse_date_time(value: &str) -> Option<XmpDateTime> {
+    let value = value.trim();
+
+    if let Ok(ts) = DateTime::parse_from_rfc3339(value) {
+        return Some(XmpDateTime::Offset(ts));
+    }
+
+    // XMP allows the seconds to be left out.
+    if let Ok(ts) = DateTime::parse_from_str(value, "%Y-%m-%dT%H:%M%:z") {
+        return Some(XmpDateTime::Offset(ts));
+    }
+
+    ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%dT%H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .map(XmpDateTime::Naive)
+        .or_else(|| parse_partial_date(value))
+}
+
+/// Parses a date without a time, which XMP allows to be just "YYYY-MM" or "YYYY".
+fn parse_partial_date(value: &str) -> Option<XmpDateTime> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Some(XmpDateTime::Partial(date, DatePrecision::Day));
+    }
+
+    let (year, month) = match value.split_once('-') {
+        Some((year, month)) => (year, Some(month)),
+        None => (value, None),
+    };
+
+    let is_digits = |s: &str, len: usize| s.len() == len && s.chars().all(|c| c.is_ascii_digit());
+    if !is_digits(year, 4) || month.is_some_and(|month| !is_digits(month, 2)) {
+        return None;
+    }
+
+    let year: i32 = year.parse().ok()?;
+    match month {
+        Some(month) => {
+            let month: u32 = month.parse().ok()?;
+            NaiveDate::from_ymd_opt(year, month, 1)
+                .map(|date| XmpDateTime::Partial(date, DatePrecision::Month))
+        }
+        None => NaiveDate::from_ymd_opt(year, 1, 1)
+            .map(|date| XmpDateTime::Partial(date, DatePrecision::Year)),
+    }
+}
+
+/// Path of the existing sidecar of a picture, if it has one.
+/// Both `name.ext.xmp`, as written by digiKam and darktable, and `name.xmp`,
+/// as written by Lightroom, are found.
+pub fn find_sidecar(picture: &Path) -> Option<PathBuf> {
+    let appended = appended_sidecar_path(picture);
+    if appended.is_file() {
+        return Some(appended);
+    }
+
+    // A sidecar without the picture's extension can't be told apart when another
+    // picture shares its name, such as the JPEG of a RAW+JPEG pair.
+    let stem_sidecar = picture.with_extension("xmp");
+    (stem_sidecar.is_file() && !has_namesake(picture)).then_some(stem_sidecar)
+}
+
+/// Is there another file, other than a sidecar, with the same name as the picture
+/// apart from its extension?
+fn has_namesake(picture: &Path) -> bool {
+    let (Some(dir), Some(stem)) = (picture.parent(), picture.file_stem()) else {
+        return false;
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return false;
+    };
+
+    entries.flatten().map(|entry| entry.path()).any(|path| {
+        path != picture
+            && path.file_stem() == Some(stem)
+            && !path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("xmp"))
+    })
+}
+
+/// Path of a new sidecar, which is the name of the picture with ".xmp" appended.
+fn appended_sidecar_path(picture: &Path) -> PathBuf {
+    let mut path = OsString::from(picture.as_os_str());
+    path.push(".xmp");
+    PathBuf::from(path)
+}
+
+/// Reads the favorite and tags from a sidecar file.
+pub fn read_sidecar(sidecar: &Path) -> Result<SidecarMetadata> {
+    let packet = fs::read_to_string(sidecar)
+        .with_context(|| format!("Failed reading sidecar {:?}", sidecar))?;
+    Ok(sidecar_in_packet(&packet))
+}
+
+/// Writes the favorite and tags of a picture to its sidecar, creating the sidecar
+/// if there isn't one. Other properties of an existing sidecar are kept.
+/// The picture itself is never written to.
+/// Returns the path of the sidecar.
+pub fn write_sidecar(picture: &Path, metadata: &SidecarMetadata) -> Result<PathBuf> {
+    let (sidecar, packet, permissions) = match find_sidecar(picture) {
+        Some(sidecar) => {
+            let packet = fs::read_to_string(&sidecar)
+                .with_context(|| format!("Failed reading sidecar {:?}", sidecar))?;
+            let permissions = fs::metadata(&sidecar).ok().map(|m| m.permissions());
+            (sidecar, packet, permissions)
+        }
+        None => (
+            appended_sidecar_path(picture),
+            String::from(SIDECAR_TEMPLATE),
+            fs::metadata(picture).ok().map(|m| m.permissions()),
+        ),
+    };
+
+    let packet = update_packet(&packet, metadata)
+        .with_context(|| format!("Failed updating sidecar {:?}", sidecar))?;
+
+    // Write to a temporary file first so that a failed write can't leave a truncated sidecar.
+    let dir = sidecar
+        .parent()
+        .context("Sidecar must have a parent directory")?;
+    let mut file = tempfile::NamedTempFile::new_in(dir)?;
+    file.write_all(packet.as_bytes())?;
+
+    // Temporary files are only readable by their owner, unlike the sidecar they replace.
+    if let Some(permissions) = permissions {
+        fs::set_permissions(file.path(), permissions)?;
+    }
+
+    file.persist(&sidecar)
+        .with_context(|| format!("Failed writing sidecar {:?}", sidecar))?;
+
+    Ok(sidecar)
+}
+
+/// Favorite and tags from an XMP packet.
+pub fn sidecar_in_packet(packet: &str) -> SidecarMetadata {
+    let is_favorite = rating(packet).is_some_and(|rating| rating >= FAVORITE_RATING);
+
+    let tags = subject(packet)
+        .map(|(start, end)| list_items(&packet[start..end]))
+        .unwrap_or_default();
+
+    SidecarMetadata { is_favorite, tags }
+}
+
+/// Sets the favorite and tags of an XMP packet.
+/// A favorite is rated five stars. When a picture is no longer a favorite a five star
+/// rating is cleared, but lower ratings given by other tools are kept.
+/// Keywords are written to the first `rdf:Description`, replacing any written elsewhere.
+/// Everything else in the packet is copied unchanged.
+fn update_packet(packet: &str, metadata: &SidecarMetadata) -> Result<String> {
+    let current_rating = rating(packet);
+    let new_rating = if metadata.is_favorite {
+        Some(FAVORITE_RATING)
+    } else if current_rating.is_some_and(|rating| rating >= FAVORITE_RATING) {
+        Some(0)
+    } else {
+        None
+    }
+    .filter(|rating| Some(*rating) != current_rating);
+
+    // A rating is changed where it is, or else added to the description.
+    let is_rating_added = new_rating.is_some() && !has_rating(packet)?;
+
+    let mut reader = Reader::from_str(packet);
+    let mut writer = Writer::new(Vec::new());
+
+    // Namespaces declared before the description is reached, or by it.
+    let mut namespaces: Vec<Vec<u8>> = Vec::new();
+    let mut is_description_found = false;
+
+    // Depth of nesting within an element that is left out, such as old keywords.
+    let mut skip_depth = 0;
+
+    loop {
+        let event = reader.read_event()?;
+
+        if skip_depth > 0 {
+            match event {
+                Event::Start(_) => skip_depth += 1,
+                Event::End(_) => skip_depth -= 1,
+                Event::Eof => bail!("Unterminated element in XMP packet"),
+                _ => {}
+            }
+            continue;
+        }
+
+        match &event {
+            Event::Eof => break,
+            Event::Start(e) | Event::Empty(e) if e.name().as_ref() == SUBJECT_ELEMENT => {
+                if matches!(event, Event::Start(_)) {
+                    skip_depth = 1;
+                }
+                continue;
+            }
+            Event::Start(e) if e.name().as_ref() == RATING_PROPERTY.as_bytes() => {
+                if let Some(rating) = new_rating {
+                    writer.write_event(Event::Start(e.to_owned()))?;
+                    writer.write_event(Event::Text(BytesText::new(&rating.to_string())))?;
+                    writer.write_event(Event::End(e.to_end().into_owned()))?;
+                    skip_depth = 1;
+                    continue;
+                }
+            }
+            Event::Start(e) | Event::Empty(e) => {
+                let is_empty = matches!(event, Event::Empty(_));
+                let mut start = copy_start(e, new_rating)?;
+
+                if !is_description_found {
+                    namespaces.extend(
+                        e.attributes()
+                            .flatten()
+                            .filter(|attr| attr.key.as_ref().starts_with(b"xmlns:"))
+                            .map(|attr| attr.key.as_ref().to_vec()),
+                    );
+                }
+
+                if is_description_found || e.name().as_ref() != DESCRIPTION_ELEMENT {
+                    writer.write_event(if is_empty {
+                        Event::Empty(start)
+                    } else {
+                        Event::Start(start)
+                    })?;
+                    continue;
+                }
+
+                is_description_found = true;
+                let is_declared =
+                    |namespace: &str| namespaces.iter().any(|n| n == namespace.as_bytes());
+
+                if let Some(rating) = new_rating.filter(|_| is_rating_added) {
+                    if !is_declared(XMP_NAMESPACE.0) {
+                        start.push_attribute(XMP_NAMESPACE);
+                    }
+                    start.push_attribute((RATING_PROPERTY, rating.to_string().as_str()));
+                }
+
+                if metadata.tags.is_empty() {
+                    writer.write_event(if is_empty {
+                        Event::Empty(start)
+                    } else {
+                        Event::Start(start)
+                    })?;
+                    continue;
+                }
+
+                if !is_declared(DC_NAMESPACE.0) {
+                    start.push_attribute(DC_NAMESPACE);
+                }
+
+                let end = start.to_end().into_owned();
+                writer.write_event(Event::Start(start))?;
+                write_subject(&mut writer, &metadata.tags)?;
+                if is_empty {
+                    writer.write_event(Event::End(end))?;
+                }
+                continue;
+            }
+            _ => {}
+        }
+
+        writer.write_event(event)?;
+    }
+
+    if !is_description_found {
+        bail!("No rdf:Description in XMP packet");
+    }
+
+    Ok(String::from_utf8(writer.into_inner())?)
+}
+
+/// Copies the start tag of an element, with any rating attribute changed to `rating`.
+fn copy_start(start: &BytesStart, rating: Option<i32>) -> Result<BytesStart<'static>> {
+    let mut copy = start.to_owned();
+    copy.clear_attributes();
+
+    for attr in start.attributes() {
+        let attr = attr?;
+        let key = attr.key.as_ref();
+
+        if let Some(rating) = rating.filter(|_| key == RATING_PROPERTY.as_bytes()) {
+            copy.push_attribute((RATING_PROPERTY, rating.to_string().as_str()));
+        } else if attr.value.contains(&b'"') {
+            // Written with single quotes, but copied with double quotes.
+            let value = String::from_utf8_lossy(&attr.value).replace('"', "&quot;");
+            copy.push_attribute((key, value.as_bytes()));
+        } else {
+            copy.push_attribute(attr);
+        }
+    }
+
+    Ok(copy)
+}
+
+/// Writes keywords as a `dc:subject` bag.
+fn write_subject(writer: &mut Writer<Vec<u8>>, tags: &[String]) -> Result<()> {
+    writer.write_event(Event::Start(BytesStart::new("dc:subject")))?;
+    writer.write_event(Event::Start(BytesStart::new("rdf:Bag")))?;
+    for tag in tags {
+        writer.write_event(Event::Start(BytesStart::new("rdf:li")))?;
+        writer.write_event(Event::Text(BytesText::new(tag)))?;
+        writer.write_event(Event::End(BytesEnd::new("rdf:li")))?;
+    }
+    writer.write_event(Event::End(BytesEnd::new("rdf:Bag")))?;
+    writer.write_event(Event::End(BytesEnd::new("dc:subject")))?;
+    Ok(())
+}
+
+/// Does an XMP packet have a rating, written as either an attribute or an element?
+fn has_rating(packet: &str) -> Result<bool> {
+    let mut reader = Reader::from_str(packet);
+    loop {
+        match reader.read_event()? {
+            Event::Eof => return Ok(false),
+            Event::Start(e) | Event::Empty(e) => {
+                let is_rating = e.name().as_ref() == RATING_PROPERTY.as_bytes()
+                    || e.attributes()
+                        .flatten()
+                        .any(|attr| attr.key.as_ref() == RATING_PROPERTY.as_bytes());
+                if is_rating {
+                    return Ok(true);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Star rating of an XMP packet. XMP ratings are real numbers, but are whole in practice.
+fn rating(packet: &str) -> Option<i32> {
+    let value = property_value(packet, RATING_PROPERTY)?;
+    let rating: f32 = value.trim().parse().ok()?;
+    Some(rating.round() as i32)
+}
+
+/// Range of the `dc:subject` element, including its start and end tags.
+fn subject(packet: &str) -> Option<(usize, usize)> {
+    if let Some(start) = packet.find("<dc:subject/>") {
+        return Some((start, start + "<dc:subject/>".len()));
+    }
+
+    let start = packet.find(SUBJECT_START)?;
+    let end = packet[start..].find(SUBJECT_END)? + start + SUBJECT_END.len();
+    Some((start, end))
+}
+
+/// Values of the `rdf:li` items of an RDF container.
+fn list_items(container: &str) -> Vec<String> {
+    container
+        .split("<rdf:li")
+        .skip(1)
+        .filter_map(|item| {
+            let (_, value) = item.split_once('>')?;
+            let (value, _) = value.split_once("</rdf:li>")?;
+            Some(unescape(value.trim()))
+        })
+        .filter(|value| !value.is_empty())
+        .collect()
+}
+
+fn unescape(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn naive(s: &str) -> XmpDateTime {
+        XmpDateTime::Naive(NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap())
+    }
+
+    fn partial(s: &str, precision: DatePrecision) -> XmpDateTime {
+        XmpDateTime::Partial(NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap(), precision)
+    }
+
+    #[test]
+    fn date_from_attribute() {
+        let packet = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+            <rdf:Description xmp:CreateDate="2021-06-01T10:20:30+02:00"/>"#;
+
+        assert_eq!(
+            Some(XmpDateTime::Offset(
+                DateTime::parse_from_rfc3339("2021-06-01T10:20:30+02:00").unwrap()
+            )),
+            created_at_in_packet(packet)
+        );
+    }
+
+    #[test]
+    fn date_from_element() {
+        let packet = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+            <photoshop:DateCreated>2021-06-01T10:20:30</photoshop:DateCreated>"#;
+
+        assert_eq!(
+            Some(naive("2021-06-01 10:20:30")),
+            created_at_in_packet(packet)
+        );
+    }
+
+    #[test]
+    fn prefers_date_time_original() {
+        let packet = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+            <rdf:Description
+                xmp:CreateDate="2022-01-01T00:00:00"
+                exif:DateTimeOriginal="2021-01-01T00:00:00"/>"#;
+
+        assert_eq!(
+            Some(naive("2021-01-01 00:00:00")),
+            created_at_in_packet(packet)
+        );
+    }
+
+    #[test]
+    fn skips_unparseable_dates() {
+        let packet = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+            <rdf:Description
+                exif:DateTimeOriginal="yesterday"
+                xmp:CreateDate="2021-06-01"/>"#;
+
+        assert_eq!(
+            Some(partial("2021-06-01", DatePrecision::Day)),
+            created_at_in_packet(packet)
+        );
+    }
+
+    #[test]
+    fn year_and_month_only() {
+        let packet = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+            <photoshop:DateCreated>1985-06</photoshop:DateCreated>"#;
+
+        let date = created_at_in_packet(packet);
+        assert_eq!(Some(partial("1985-06-01", DatePrecision::Month)), date);
+        assert_eq!(
+            Some(DatePrecision::Month),
+            date.map(|date| date.precision())
+        );
+    }
+
+    #[test]
+    fn year_only() {
+        let packet = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+            <rdf:Description photoshop:DateCreated="1985"/>"#;
+
+        assert_eq!(
+            Some(partial("1985-01-01", DatePrecision::Year)),
+            created_at_in_packet(packet)
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_partial_dates() {
+        assert_eq!(None, parse_date_time("85"));
+        assert_eq!(None, parse_date_time("1985-6"));
+        assert_eq!(None, parse_date_time("1985-13"));
+        assert_eq!(None, parse_date_time("+985"));
+    }
+
+    #[test]
+    fn no_date() {
+        let packet = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/"></x:xmpmeta>"#;
+        assert_eq!(None, created_at_in_packet(packet));
+    }
+
+    fn tagged(is_favorite: bool, tags: &[&str]) -> SidecarMetadata {
+        SidecarMetadata {
+            is_favorite,
+            tags: tags.iter().map(|tag| String::from(*tag)).collect(),
+        }
+    }
+
+    #[test]
+    fn new_sidecar_round_trip() {
+        let metadata = tagged(true, &["beach", "Tom & Jerry <3"]);
+        let packet = update_packet(SIDECAR_TEMPLATE, &metadata).unwrap();
+
+        assert!(packet.contains("xmp:Rating=\"5\""));
+        assert!(packet.contains("<rdf:li>Tom &amp; Jerry &lt;3</rdf:li>"));
+        assert_eq!(metadata, sidecar_in_packet(&packet));
+    }
+
+    #[test]
+    fn reads_ratings_and_keywords_from_other_tools() {
+        let packet = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+            <rdf:Description xmp:Rating="5.0">
+                <dc:subject>
+                    <rdf:Bag>
+                        <rdf:li>holiday</rdf:li>
+                        <rdf:li xml:lang="x-default">Rock &amp; Roll</rdf:li>
+                    </rdf:Bag>
+                </dc:subject>
+            </rdf:Description>"#;
+
+        assert_eq!(
+            tagged(true, &["holiday", "Rock & Roll"]),
+            sidecar_in_packet(packet)
+        );
+    }
+
+    #[test]
+    fn updates_existing_sidecar_in_place() {
+        let packet = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+            <rdf:Description rdf:about="" xmlns:xmp="http://ns.adobe.com/xap/1.0/"
+                xmp:Rating="2" darktable:history_end="3">
+                <dc:subject><rdf:Bag><rdf:li>old</rdf:li></rdf:Bag></dc:subject>
+            </rdf:Description>"#;
+
+        let packet = update_packet(packet, &tagged(true, &["new"])).unwrap();
+
+        assert!(packet.contains("darktable:history_end=\"3\""));
+        assert!(!packet.contains("old"));
+        assert_eq!(tagged(true, &["new"]), sidecar_in_packet(&packet));
+    }
+
+    #[test]
+    fn adds_keywords_to_self_closing_description() {
+        let packet = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+            <rdf:Description rdf:about="" exif:ExposureTime="1/60"/></x:xmpmeta>"#;
+
+        let packet = update_packet(packet, &tagged(false, &["cat"])).unwrap();
+
+        assert!(packet.contains("xmlns:dc="));
+        assert!(packet.contains("exif:ExposureTime=\"1/60\""));
+        assert_eq!(tagged(false, &["cat"]), sidecar_in_packet(&packet));
+    }
+
+    #[test]
+    fn unfavorite_clears_five_stars_but_keeps_lower_ratings() {
+        let five = r#"<rdf:Description xmp:Rating="5"/>"#;
+        let three = r#"<rdf:Description xmp:Rating="3"/>"#;
+
+        assert_eq!(
+            "<rdf:Description xmp:Rating=\"0\"/>",
+            update_packet(five, &tagged(false, &[])).unwrap()
+        );
+        assert_eq!(three, update_packet(three, &tagged(false, &[])).unwrap());
+    }
+
+    #[test]
+    fn no_description() {
+        let packet = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/"></x:xmpmeta>"#;
+        assert!(update_packet(packet, &tagged(true, &[])).is_err());
+    }
+
+    #[test]
+    fn write_sidecar_leaves_picture_untouched() {
+        let dir = tempfile::tempdir().unwrap();
+        let picture = dir.path().join("a.jpg");
+        fs::write(&picture, b"not really a jpeg").unwrap();
+
+        let sidecar = write_sidecar(&picture, &tagged(true, &["dog"])).unwrap();
+
+        assert_eq!(dir.path().join("a.jpg.xmp"), sidecar);
+        assert_eq!(Some(sidecar.clone()), find_sidecar(&picture));
+        assert_eq!(b"not really a jpeg".to_vec(), fs::read(&picture).unwrap());
+        assert_eq!(tagged(true, &["dog"]), read_sidecar(&sidecar).unwrap());
+    }
+
+    #[test]
+    fn finds_sidecar_without_picture_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        let picture = dir.path().join("a.nef");
+        fs::write(dir.path().join("a.xmp"), "<rdf:Description/>").unwrap();
+
+        let sidecar = write_sidecar(&picture, &tagged(false, &["raw"])).unwrap();
+
+        assert_eq!(dir.path().join("a.xmp"), sidecar);
+        assert_eq!(tagged(false, &["raw"]), read_sidecar(&sidecar).unwrap());
+    }
+
+    #[test]
+    fn raw_sidecar_is_not_used_for_jpeg_of_pair() {
+        let dir = tempfile::tempdir().unwrap();
+        let raw = dir.path().join("a.nef");
+        let jpeg = dir.path().join("a.jpg");
+        fs::write(&raw, b"raw").unwrap();
+        fs::write(&jpeg, b"jpeg").unwrap();
+        fs::write(dir.path().join("a.xmp"), "<rdf:Description/>").unwrap();
+
+        assert_eq!(None, find_sidecar(&jpeg));
+
+        let sidecar = write_sidecar(&jpeg, &tagged(false, &["jpeg"])).unwrap();
+
+        assert_eq!(dir.path().join("a.jpg.xmp"), sidecar);
+        assert_eq!(
+            "<rdf:Description/>",
+            fs::read_to_string(dir.path().join("a.xmp")).unwrap()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn write_sidecar_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let picture = dir.path().join("a.jpg");
+        fs::write(&picture, b"jpeg").unwrap();
+        fs::set_permissions(&picture, fs::Permissions::from_mode(0o644)).unwrap();
+
+        let sidecar = write_sidecar(&picture, &tagged(false, &["new"])).unwrap();
+        let mode = fs::metadata(&sidecar).unwrap().permissions().mode();
+        assert_eq!(0o644, mode & 0o777);
+
+        fs::set_permissions(&sidecar, fs::Permissions::from_mode(0o664)).unwrap();
+        write_sidecar(&picture, &tagged(true, &["new"])).unwrap();
+        let mode = fs::metadata(&sidecar).unwrap().permissions().mode();
+        assert_eq!(0o664, mode & 0o777);
+    }
+
+    #[test]
+    fn updates_rating_element_and_uses_declared_namespaces() {
+        let packet = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:xmp="http://ns.adobe.com/xap/1.0/">
+<rdf:Description rdf:about="" title='Say "hi"'><xmp:Rating>2</xmp:Rating></rdf:Description>
+<rdf:Description rdf:about=""><dc:subject><rdf:Bag><rdf:li>old</rdf:li></rdf:Bag></dc:subject></rdf:Description>
+</rdf:RDF>
+</x:xmpmeta>"#;
+
+        let updated = update_packet(packet, &tagged(true, &["new"])).unwrap();
+
+        assert!(updated.contains("<xmp:Rating>5</xmp:Rating>"));
+        assert!(!updated.contains("xmp:Rating=\""));
+        assert!(!updated.contains("old"));
+        assert_eq!(1, updated.matches("xmlns:dc=").count());
+        assert_eq!(1, updated.matches("xmlns:xmp=").count());
+        assert!(updated.contains(r#"title="Say &quot;hi&quot;""#));
+        assert_eq!(tagged(true, &["new"]), sidecar_in_packet(&updated));
+    }
+}
@@ -0,0 +1,236 @@
+// SPDX-FileCopyrightText: © 2025 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Files and folders to leave out of the library, such as `@eaDir` folders made by
+//! Synology NAS drives, or `__MACOSX` folders unpacked from zip files.
+//!
+//! Patterns come from settings and from a `.fotemaignore` file in a library root,
+//! one pattern per line. Patterns are a subset of `.gitignore` syntax:
+//!
+//! * `*` matches anything except `/`, `?` matches one character except `/`,
+//!   and `**` matches anything including `/`.
+//! * A pattern without a `/` matches a file or folder of that name at any depth.
+//! * A pattern with a `/` at the start or in the middle matches a path relative to
+//!   the library root.
+//! * A pattern ending in `/` only matches folders.
+//! * Blank lines and lines starting with `#` are skipped.
+//!
+//! Negated patterns, starting with `!`, aren't supported and are skipped.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+/// Name of file in a library root with patterns to ignore.
+pub const IGNORE_FILE_NAME: &str = ".fotemaignore";
+
+#[derive(Debug, Clone)]
+struct Pattern {
+    /// Glob, without any leading or trailing `/`.
+    glob: Vec<char>,
+
+    /// Does the glob match a path relative to the root, rather than a file name?
+    is_anchored: bool,
+
+    /// Does the glob only match folders?
+    is_dir_only: bool,
+}
+
+impl Pattern {
+    fn parse(line: &str) -> Option<Pattern> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+
+        if line.starts_with('!') {
+            warn!("Negated ignore patterns aren't supported: {}", line);
+            return None;
+        }
+
+        let is_dir_only = line.ends_with('/');
+        let line = line.trim_end_matches('/');
+        let is_anchored = line.contains('/');
+        let glob: Vec<char> = line.trim_start_matches('/').chars().collect();
+
+        if glob.is_empty() {
+            return None;
+        }
+
+        Some(Pattern {
+            glob,
+            is_anchored,
+            is_dir_only,
+        })
+    }
+
+    /// Does the pattern match an entry with a path relative to the root?
+    fn matches(&self, relative_path: &Path, is_dir: bool) -> bool {
+        if self.is_dir_only && !is_dir {
+            return false;
+        }
+
+        let subject = if self.is_anchored {
+            relative_path
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/")
+        } else {
+            let Some(name) = relative_path.file_name() else {
+                return false;
+            };
+            name.to_string_lossy().to_string()
+        };
+
+        let subject: Vec<char> = subject.chars().collect();
+        glob_match(&self.glob, &subject)
+    }
+}
+
+/// Matches a glob against a whole string.
+fn glob_match(glob: &[char], s: &[char]) -> bool {
+    match glob {
+        [] => s.is_empty(),
+        ['*', '*', '/', rest @ ..] => {
+            // Zero or more whole folders.
+            glob_match(rest, s)
+                || (0..s.len()).any(|i| s[i] == '/' && glob_match(rest, &s[i + 1..]))
+        }
+        ['*', '*', rest @ ..] => (0..=s.len()).any(|i| glob_match(rest, &s[i..])),
+        ['*', rest @ ..] => {
+            let end = s.iter().position(|c| *c == '/').unwrap_or(s.len());
+            (0..=end).any(|i| glob_match(rest, &s[i..]))
+        }
+        ['?', rest @ ..] => s.first().is_some_and(|c| *c != '/') && glob_match(rest, &s[1..]),
+        [c, rest @ ..] => s.first() == Some(c) && glob_match(rest, &s[1..]),
+    }
+}
+
+/// Patterns of files and folders to leave out of a library root.
+#[derive(Debug, Clone, Default)]
+pub struct IgnorePatterns {
+    /// Library root that anchored patterns are relative to.
+    base: PathBuf,
+
+    patterns: Vec<Pattern>,
+}
+
+impl IgnorePatterns {
+    /// Patterns for a library root from settings, without reading any ignore file.
+    pub fn build(base: &Path, patterns: &[String]) -> Self {
+        Self {
+            base: base.to_path_buf(),
+            patterns: patterns.iter().filter_map(|p| Pattern::parse(p)).collect(),
+        }
+    }
+
+    /// Patterns for a library root from settings, along with those from the
+    /// `.fotemaignore` file in the library root, if there is one.
+    pub fn load(base: &Path, patterns: &[String]) -> Self {
+        let mut ignore = Self::build(base, patterns);
+
+        let path = base.join(IGNORE_FILE_NAME);
+        if let Ok(text) = fs::read_to_string(&path) {
+            debug!("Reading ignore patterns from {:?}", path);
+            ignore
+                .patterns
+                .extend(text.lines().filter_map(Pattern::parse));
+        }
+
+        ignore
+    }
+
+    /// Are there no patterns, so that nothing is ignored?
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Does a pattern match a file or folder? Folders containing the entry aren't checked,
+    /// because a scan never enters an ignored folder.
+    pub fn is_ignored_entry(&self, path: &Path, is_dir: bool) -> bool {
+        let Ok(relative_path) = path.strip_prefix(&self.base) else {
+            return false;
+        };
+
+        if relative_path.as_os_str().is_empty() {
+            // Never ignore the library root itself.
+            return false;
+        }
+
+        self.patterns
+            .iter()
+            .any(|pattern| pattern.matches(relative_path, is_dir))
+    }
+
+    /// Does a pattern match a file, or any folder containing it within the library root?
+    /// For checking files that were scanned before a pattern was added.
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        if self.is_empty() || !path.starts_with(&self.base) {
+            return false;
+        }
+
+        self.is_ignored_entry(path, false)
+            || path
+                .ancestors()
+                .skip(1)
+                .take_while(|dir| dir.starts_with(&self.base))
+                .any(|dir| self.is_ignored_entry(dir, true))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ignore(patterns: &[&str]) -> IgnorePatterns {
+        let patterns: Vec<String> = patterns.iter().map(|p| p.to_string()).collect();
+        IgnorePatterns::build(Path::new("/lib"), &patterns)
+    }
+
+    #[test]
+    fn name_pattern_matches_at_any_depth() {
+        let ignore = ignore(&["@eaDir", "*.tmp.jpg"]);
+        assert!(ignore.is_ignored_entry(Path::new("/lib/@eaDir"), true));
+        assert!(ignore.is_ignored_entry(Path::new("/lib/2024/@eaDir"), true));
+        assert!(ignore.is_ignored_entry(Path::new("/lib/2024/a.tmp.jpg"), false));
+        assert!(!ignore.is_ignored_entry(Path::new("/lib/2024/a.jpg"), false));
+    }
+
+    #[test]
+    fn anchored_pattern_matches_from_root() {
+        let ignore = ignore(&["/proofs", "exports/**/draft-?.jpg"]);
+        assert!(ignore.is_ignored_entry(Path::new("/lib/proofs"), true));
+        assert!(!ignore.is_ignored_entry(Path::new("/lib/2024/proofs"), true));
+        assert!(ignore.is_ignored_entry(Path::new("/lib/exports/draft-1.jpg"), false));
+        assert!(ignore.is_ignored_entry(Path::new("/lib/exports/a/b/draft-2.jpg"), false));
+        assert!(!ignore.is_ignored_entry(Path::new("/lib/exports/draft-10.jpg"), false));
+    }
+
+    #[test]
+    fn dir_only_pattern_skips_files() {
+        let ignore = ignore(&["cache/"]);
+        assert!(ignore.is_ignored_entry(Path::new("/lib/cache"), true));
+        assert!(!ignore.is_ignored_entry(Path::new("/lib/cache"), false));
+    }
+
+    #[test]
+    fn file_in_ignored_folder_is_ignored() {
+        let ignore = ignore(&["__MACOSX", "#comment", "", "!keep"]);
+        assert!(ignore.is_ignored(Path::new("/lib/trip/__MACOSX/a.jpg")));
+        assert!(!ignore.is_ignored(Path::new("/lib/trip/a.jpg")));
+        assert!(!ignore.is_ignored(Path::new("/elsewhere/__MACOSX/a.jpg")));
+        assert_eq!(1, ignore.patterns.len());
+    }
+
+    #[test]
+    fn loads_ignore_file() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join(IGNORE_FILE_NAME), "# Junk\nproofs/\n").unwrap();
+
+        let ignore = IgnorePatterns::load(dir.path(), &["@eaDir".into()]);
+        assert!(ignore.is_ignored(&dir.path().join("proofs").join("a.jpg")));
+        assert!(ignore.is_ignored(&dir.path().join("@eaDir").join("a.jpg")));
+    }
+}
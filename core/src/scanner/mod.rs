@@ -2,8 +2,11 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+pub mod ignore;
 pub mod model;
 pub mod scanner;
 
+pub use ignore::IgnorePatterns;
 pub use model::ScannedFile;
 pub use scanner::Scanner;
+pub use scanner::canonical_path;
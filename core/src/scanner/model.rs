@@ -2,10 +2,18 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone)]
 pub enum ScannedFile {
     Photo(PathBuf),
     Video(PathBuf),
 }
+
+impl ScannedFile {
+    pub fn path(&self) -> &Path {
+        match self {
+            ScannedFile::Photo(path) | ScannedFile::Video(path) => path,
+        }
+    }
+}
@@ -2,38 +2,152 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use super::ScannedFile;
+use super::{IgnorePatterns, ScannedFile};
 use crate::file_types;
+use crate::photo::raw;
 
 use anyhow::*;
+use chrono::{DateTime, Utc};
+use std::cell::RefCell;
+use std::collections::HashSet;
 use std::fs;
+use std::os::unix::fs::MetadataExt;
 use std::path::Path;
 use std::path::PathBuf;
-use tracing::error;
+use tracing::{debug, error, info};
 use walkdir::{DirEntry, WalkDir};
 
+/// Path of a file with symbolic links and relative components resolved.
+/// Identifies a file however it is reached, such as through a symbolic link or a bind mount.
+/// Falls back to the path itself if it can't be resolved.
+pub fn canonical_path(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
 /// Scans a file system for pictures.
 #[derive(Debug, Clone)]
 pub struct Scanner {
     /// File system path to scan.
     scan_base: PathBuf,
+
+    /// Patterns from settings of files and folders to skip.
+    ignore_patterns: Vec<String>,
+
+    /// Follow symbolic links to files and folders, rather than skipping them.
+    follow_symlinks: bool,
 }
 
 impl Scanner {
     pub fn build(scan_base: &Path) -> Result<Self> {
         fs::create_dir_all(scan_base)?;
         let scan_base = PathBuf::from(scan_base);
-        Ok(Self { scan_base })
+        Ok(Self {
+            scan_base,
+            ignore_patterns: Vec::new(),
+            follow_symlinks: false,
+        })
+    }
+
+    /// Directory scanned for pictures and videos.
+    pub fn scan_base(&self) -> &Path {
+        &self.scan_base
+    }
+
+    /// Skip files and folders matching these patterns, as well as those matching the
+    /// patterns in the `.fotemaignore` file in the base directory.
+    pub fn set_ignore_patterns(&mut self, patterns: &[String]) {
+        self.ignore_patterns = patterns.to_vec();
+    }
+
+    /// Follow symbolic links found while scanning. If not followed, symbolic links to
+    /// files and folders are skipped, although the base directory may itself be a link.
+    ///
+    /// Either way, a file is only scanned once however many paths lead to it, as files
+    /// are identified by their canonical path. When following links, the path kept is
+    /// the first one found, which might be through a link.
+    /// Following links never loops, because a folder that has already been scanned
+    /// through another path is skipped.
+    pub fn set_follow_symlinks(&mut self, follow_symlinks: bool) {
+        self.follow_symlinks = follow_symlinks;
+    }
+
+    /// Patterns of files and folders to skip. The `.fotemaignore` file is read afresh,
+    /// so that changes to it apply to the next scan.
+    pub fn ignore(&self) -> IgnorePatterns {
+        IgnorePatterns::load(&self.scan_base, &self.ignore_patterns)
     }
 
     /// Scans all pictures in the base directory for function `func` to visit.
+    /// A file reachable by more than one path is visited once, by the first path found.
     pub fn scan_all_visit<F>(&self, func: F)
     where
         F: FnMut(ScannedFile),
     {
-        WalkDir::new(&self.scan_base)
+        let ignore = self.ignore();
+        let visited = RefCell::new(HashSet::new());
+        self.visit(
+            &self.scan_base,
+            &ignore,
+            &visited,
+            Self::once_per_file(func),
+        );
+    }
+
+    /// Scans pictures in the base directory that were added or changed since a given time,
+    /// for function `func` to visit.
+    /// A directory changed since then is scanned in full, because it might have been
+    /// renamed or moved into the library, which doesn't change the files within it.
+    pub fn scan_changed_since_visit<F>(&self, since: DateTime<Utc>, func: F)
+    where
+        F: FnMut(ScannedFile),
+    {
+        let mut func = Self::once_per_file(func);
+        let ignore = self.ignore();
+        let visited = RefCell::new(HashSet::new());
+
+        let mut walker = WalkDir::new(&self.scan_base)
+            .follow_links(self.follow_symlinks)
+            .into_iter()
+            .filter_entry(|e| !self.is_skipped(e, &ignore, &visited));
+
+        while let Some(entry) = walker.next() {
+            Self::inspect_err(&entry);
+            let std::result::Result::Ok(entry) = entry else {
+                continue;
+            };
+
+            if !Self::is_changed_since(&entry, since) {
+                continue;
+            }
+
+            if entry.file_type().is_dir() {
+                // Base directory changes whenever a top-level folder is added or removed,
+                // so only look inside it.
+                if entry.depth() > 0 {
+                    walker.skip_current_dir();
+                    self.visit(entry.path(), &ignore, &visited, &mut func);
+                }
+            } else if entry.path().is_file() {
+                // Ignore anything that isn't a picture or video.
+                Self::to_scanned_file(entry).into_iter().for_each(&mut func);
+            }
+        }
+    }
+
+    /// Directories already walked are identified by device and inode in `visited`.
+    fn visit<F>(
+        &self,
+        dir: &Path,
+        ignore: &IgnorePatterns,
+        visited: &RefCell<HashSet<(u64, u64)>>,
+        func: F,
+    ) where
+        F: FnMut(ScannedFile),
+    {
+        WalkDir::new(dir)
+            .follow_links(self.follow_symlinks)
             .into_iter()
-            .filter_entry(|e| !Scanner::is_hidden(e))
+            .filter_entry(|e| !self.is_skipped(e, ignore, visited))
             .inspect(Self::inspect_err)
             .filter_map(|e| e.ok()) // skip files we failed to read
             .filter(|x| x.path().is_file()) // only process files
@@ -42,6 +156,21 @@ impl Scanner {
             .for_each(func); // visit
     }
 
+    /// Wraps a visitor so that it only visits a file once, however many paths lead to it.
+    fn once_per_file<F>(mut func: F) -> impl FnMut(ScannedFile)
+    where
+        F: FnMut(ScannedFile),
+    {
+        let mut seen = HashSet::new();
+        move |scanned_file| {
+            if seen.insert(canonical_path(scanned_file.path())) {
+                func(scanned_file);
+            } else {
+                debug!("Already scanned by another path: {:?}", scanned_file.path());
+            }
+        }
+    }
+
     fn inspect_err(entry: &std::result::Result<DirEntry, walkdir::Error>) {
         let _ = entry
             .as_ref()
@@ -51,16 +180,87 @@ impl Scanner {
     fn to_scanned_file(entry: DirEntry) -> Result<ScannedFile> {
         // only process supported image types
         let path = entry.path();
-        let scanned_file = if file_types::is_supported_picture(path) {
+        let scanned_file = if file_types::is_raw_picture(path) && !raw::is_displayable(path) {
+            info!(
+                "Skipping RAW picture without an embedded preview: {:?}",
+                path
+            );
+            Err(anyhow!(
+                "RAW picture can't be shown by this build: {:?}",
+                path
+            ))
+        } else if file_types::is_supported_picture(path) {
             Ok(ScannedFile::Photo(path.into()))
         } else if file_types::is_supported_video(path) {
             Ok(ScannedFile::Video(path.into()))
+        } else if file_types::is_disabled_picture(path) {
+            info!(
+                "Skipping picture in format left out of this build: {:?}",
+                path
+            );
+            Err(anyhow!(
+                "Picture format not supported by this build: {:?}",
+                path
+            ))
         } else {
             Err(anyhow!("Not a picture or video: {:?}", path))
         };
         scanned_file
     }
 
+    /// Has an entry been modified, or had its metadata changed, since the given time?
+    /// The metadata change time catches files moved into the library, which keep their
+    /// original modification time.
+    fn is_changed_since(entry: &DirEntry, since: DateTime<Utc>) -> bool {
+        let std::result::Result::Ok(metadata) = entry.metadata() else {
+            // Can't tell, so assume changed.
+            return true;
+        };
+
+        let modified = metadata.modified().ok().map(DateTime::<Utc>::from);
+        let changed = DateTime::from_timestamp(metadata.ctime(), metadata.ctime_nsec() as u32);
+
+        modified.is_none_or(|ts| ts >= since) || changed.is_none_or(|ts| ts >= since)
+    }
+
+    /// Should an entry, and everything in it if it is a folder, be left out of the scan?
+    fn is_skipped(
+        &self,
+        entry: &DirEntry,
+        ignore: &IgnorePatterns,
+        visited: &RefCell<HashSet<(u64, u64)>>,
+    ) -> bool {
+        // Where a walk starts has already been checked, or is the base directory.
+        if entry.depth() == 0 {
+            return false;
+        }
+
+        let is_skipped = Scanner::is_hidden(entry)
+            || ignore.is_ignored_entry(entry.path(), entry.file_type().is_dir())
+            || (!self.follow_symlinks && entry.path_is_symlink())
+            || Scanner::is_visited_dir(entry, visited);
+        if is_skipped {
+            debug!("Skipping {:?}", entry.path());
+        }
+        is_skipped
+    }
+
+    /// Has a directory already been walked through another path? Records the directory
+    /// as walked if not.
+    fn is_visited_dir(entry: &DirEntry, visited: &RefCell<HashSet<(u64, u64)>>) -> bool {
+        if !entry.file_type().is_dir() {
+            return false;
+        }
+
+        let std::result::Result::Ok(metadata) = entry.metadata() else {
+            return false;
+        };
+
+        !visited
+            .borrow_mut()
+            .insert((metadata.dev(), metadata.ino()))
+    }
+
     fn is_hidden(entry: &DirEntry) -> bool {
         entry
             .file_name()
@@ -77,4 +277,142 @@ impl Scanner {
         self.scan_all_visit(|pic| pics.push(pic));
         Ok(pics)
     }
+
+    /// Pictures and videos added or changed since the given time.
+    pub fn scan_changed_since(&self, since: DateTime<Utc>) -> Result<Vec<ScannedFile>> {
+        let mut pics = Vec::new();
+        self.scan_changed_since_visit(since, |pic| pics.push(pic));
+        Ok(pics)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+
+    #[test]
+    fn scan_changed_since_skips_unchanged_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.jpg"), b"").unwrap();
+        let scanner = Scanner::build(dir.path()).unwrap();
+
+        let future = Utc::now() + TimeDelta::hours(1);
+        assert!(scanner.scan_changed_since(future).unwrap().is_empty());
+    }
+
+    #[test]
+    fn scan_changed_since_finds_changed_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("holiday")).unwrap();
+        std::fs::write(dir.path().join("holiday").join("a.jpg"), b"").unwrap();
+        std::fs::write(dir.path().join("b.mp4"), b"").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), b"").unwrap();
+        let scanner = Scanner::build(dir.path()).unwrap();
+
+        let past = Utc::now() - TimeDelta::hours(1);
+        let scanned = scanner.scan_changed_since(past).unwrap();
+        assert_eq!(2, scanned.len());
+    }
+
+    #[test]
+    fn scans_file_reachable_by_two_paths_once() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("holiday")).unwrap();
+        std::fs::write(dir.path().join("holiday").join("a.jpg"), b"").unwrap();
+        std::os::unix::fs::symlink(
+            dir.path().join("holiday").join("a.jpg"),
+            dir.path().join("b.jpg"),
+        )
+        .unwrap();
+        let scanner = Scanner::build(dir.path()).unwrap();
+
+        assert_eq!(1, scanner.scan_all().unwrap().len());
+
+        let past = Utc::now() - TimeDelta::hours(1);
+        assert_eq!(1, scanner.scan_changed_since(past).unwrap().len());
+    }
+
+    #[test]
+    fn scans_symlinked_directory_once() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("holiday")).unwrap();
+        std::fs::write(dir.path().join("holiday").join("a.jpg"), b"").unwrap();
+        std::os::unix::fs::symlink(dir.path().join("holiday"), dir.path().join("trip")).unwrap();
+        let scanner = Scanner::build(dir.path()).unwrap();
+
+        let scanned = scanner.scan_all().unwrap();
+        assert_eq!(1, scanned.len());
+        assert_eq!(dir.path().join("holiday").join("a.jpg"), scanned[0].path());
+    }
+
+    #[test]
+    fn skips_symlinks_unless_following() {
+        let dir = tempfile::tempdir().unwrap();
+        let elsewhere = tempfile::tempdir().unwrap();
+        std::fs::write(elsewhere.path().join("a.jpg"), b"").unwrap();
+        std::fs::create_dir(elsewhere.path().join("holiday")).unwrap();
+        std::fs::write(elsewhere.path().join("holiday").join("b.jpg"), b"").unwrap();
+        std::os::unix::fs::symlink(elsewhere.path().join("a.jpg"), dir.path().join("a.jpg"))
+            .unwrap();
+        std::os::unix::fs::symlink(elsewhere.path().join("holiday"), dir.path().join("trip"))
+            .unwrap();
+        let mut scanner = Scanner::build(dir.path()).unwrap();
+
+        assert!(scanner.scan_all().unwrap().is_empty());
+
+        scanner.set_follow_symlinks(true);
+        let mut scanned: Vec<_> = scanner
+            .scan_all()
+            .unwrap()
+            .iter()
+            .map(|file| file.path().to_path_buf())
+            .collect();
+        scanned.sort();
+        assert_eq!(
+            vec![
+                dir.path().join("a.jpg"),
+                dir.path().join("trip").join("b.jpg")
+            ],
+            scanned
+        );
+
+        let past = Utc::now() - TimeDelta::hours(1);
+        assert_eq!(2, scanner.scan_changed_since(past).unwrap().len());
+    }
+
+    #[test]
+    fn following_self_referential_symlink_does_not_loop() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("holiday")).unwrap();
+        std::fs::write(dir.path().join("holiday").join("a.jpg"), b"").unwrap();
+        std::os::unix::fs::symlink(dir.path(), dir.path().join("holiday").join("again")).unwrap();
+        let mut scanner = Scanner::build(dir.path()).unwrap();
+        scanner.set_follow_symlinks(true);
+
+        assert_eq!(1, scanner.scan_all().unwrap().len());
+
+        let past = Utc::now() - TimeDelta::hours(1);
+        assert_eq!(1, scanner.scan_changed_since(past).unwrap().len());
+    }
+
+    #[test]
+    fn skips_ignored_folders_and_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("holiday").join("@eaDir")).unwrap();
+        std::fs::write(dir.path().join("holiday").join("a.jpg"), b"").unwrap();
+        std::fs::write(dir.path().join("holiday").join("@eaDir").join("b.jpg"), b"").unwrap();
+        std::fs::create_dir(dir.path().join("proofs")).unwrap();
+        std::fs::write(dir.path().join("proofs").join("c.jpg"), b"").unwrap();
+        std::fs::write(dir.path().join(".fotemaignore"), b"/proofs\n").unwrap();
+        let mut scanner = Scanner::build(dir.path()).unwrap();
+        scanner.set_ignore_patterns(&["@eaDir".to_string()]);
+
+        let scanned = scanner.scan_all().unwrap();
+        assert_eq!(1, scanned.len());
+        assert_eq!(dir.path().join("holiday").join("a.jpg"), scanned[0].path());
+
+        let past = Utc::now() - TimeDelta::hours(1);
+        assert_eq!(1, scanner.scan_changed_since(past).unwrap().len());
+    }
 }
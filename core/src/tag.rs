@@ -0,0 +1,265 @@
+// SPDX-FileCopyrightText: © 2025 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Freeform tags that users give pictures, beyond folders and people.
+//!
+//! Tag names are matched ignoring case and surrounding white space, but are shown
+//! as the user first typed them. So tagging with "holiday " after "Holiday" gives
+//! the same "Holiday" tag.
+
+use crate::database;
+use crate::photo::model::PictureId;
+
+use anyhow::*;
+use rusqlite::OptionalExtension;
+use rusqlite::params;
+use std::collections::HashMap;
+use std::result::Result::Ok;
+use std::sync::{Arc, Mutex};
+
+/// Name of a tag as shown, and as matched.
+/// None if the name is only white space.
+pub fn normalize(name: &str) -> Option<(String, String)> {
+    let name = name.trim();
+    if name.is_empty() {
+        return None;
+    }
+    Some((name.to_string(), fold(name)))
+}
+
+/// Name of a tag for matching regardless of case.
+pub fn fold(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+/// Tags of pictures.
+/// Repository is backed by a Sqlite database.
+#[derive(Debug, Clone)]
+pub struct Repository {
+    /// Connection to backing Sqlite database.
+    con: Arc<Mutex<rusqlite::Connection>>,
+}
+
+impl Repository {
+    pub fn open(con: Arc<Mutex<rusqlite::Connection>>) -> Result<Repository> {
+        Ok(Repository { con })
+    }
+
+    /// Tags a picture, creating the tag if it doesn't yet exist.
+    /// Tagging a picture twice with the same tag does nothing.
+    pub fn add_tag(&mut self, picture_id: &PictureId, name: &str) -> Result<()> {
+        let Some((name, folded_name)) = normalize(name) else {
+            bail!("Tag name is empty");
+        };
+
+        let mut con = database::lock(&self.con);
+        let tx = con.transaction()?;
+        add_normalized_tag(&tx, picture_id, &name, &folded_name)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Removes a tag from a picture. The tag is kept for other pictures.
+    pub fn remove_tag(&mut self, picture_id: &PictureId, name: &str) -> Result<()> {
+        let con = database::lock(&self.con);
+        con.execute(
+            "DELETE FROM pictures_tags
+            WHERE picture_id = ?1
+            AND tag_id IN (SELECT tag_id FROM tags WHERE folded_name = ?2)",
+            params![picture_id, fold(name)],
+        )?;
+        Ok(())
+    }
+
+    /// Deletes a tag and removes it from every picture. The pictures are kept.
+    /// Returns false if there was no such tag.
+    pub fn delete_tag(&mut self, name: &str) -> Result<bool> {
+        let mut con = database::lock(&self.con);
+        let tx = con.transaction()?;
+
+        let tag_id: Option<i64> = tx
+            .query_row(
+                "SELECT tag_id FROM tags WHERE folded_name = ?1",
+                params![fold(name)],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        let Some(tag_id) = tag_id else {
+            return Ok(false);
+        };
+
+        tx.execute(
+            "DELETE FROM pictures_tags WHERE tag_id = ?1",
+            params![tag_id],
+        )?;
+        tx.execute("DELETE FROM tags WHERE tag_id = ?1", params![tag_id])?;
+
+        tx.commit()?;
+        Ok(true)
+    }
+
+    /// Names of the tags of a picture, in alphabetical order.
+    pub fn tags_for(&self, picture_id: &PictureId) -> Result<Vec<String>> {
+        let con = database::lock(&self.con);
+        let mut stmt = con.prepare(
+            "SELECT tags.name
+            FROM pictures_tags
+            INNER JOIN tags USING (tag_id)
+            WHERE pictures_tags.picture_id = ?1
+            ORDER BY tags.folded_name ASC",
+        )?;
+
+        let names = stmt
+            .query_map(params![picture_id], |row| row.get(0))?
+            .flatten()
+            .collect();
+
+        Ok(names)
+    }
+
+    /// Pictures with a tag, excluding trashed pictures.
+    pub fn pictures_with_tag(&self, name: &str) -> Result<Vec<PictureId>> {
+        let con = database::lock(&self.con);
+        let mut stmt = con.prepare(
+            "SELECT pictures.picture_id
+            FROM pictures_tags
+            INNER JOIN tags USING (tag_id)
+            INNER JOIN pictures USING (picture_id)
+            WHERE tags.folded_name = ?1
+            AND pictures.trashed_at IS NULL",
+        )?;
+
+        let picture_ids = stmt
+            .query_map(params![fold(name)], |row| row.get(0).map(PictureId::new))?
+            .flatten()
+            .collect();
+
+        Ok(picture_ids)
+    }
+
+    /// Names of the tags of every tagged picture, in alphabetical order.
+    /// For loading the tags of the whole library at once.
+    pub fn all_tags(&self) -> Result<HashMap<PictureId, Vec<String>>> {
+        all_tags(&database::lock(&self.con))
+    }
+}
+
+/// Tags a picture with a tag name that has already been normalized.
+pub(crate) fn add_normalized_tag(
+    con: &rusqlite::Connection,
+    picture_id: &PictureId,
+    name: &str,
+    folded_name: &str,
+) -> Result<()> {
+    con.execute(
+        "INSERT INTO tags (name, folded_name) VALUES (?1, ?2)
+        ON CONFLICT (folded_name) DO NOTHING",
+        params![name, folded_name],
+    )?;
+
+    con.execute(
+        "INSERT INTO pictures_tags (picture_id, tag_id)
+        SELECT ?1, tag_id FROM tags WHERE folded_name = ?2
+        ON CONFLICT DO NOTHING",
+        params![picture_id, folded_name],
+    )?;
+
+    Ok(())
+}
+
+/// Names of the tags of every tagged picture, in alphabetical order.
+pub(crate) fn all_tags(con: &rusqlite::Connection) -> Result<HashMap<PictureId, Vec<String>>> {
+    let mut stmt = con.prepare(
+        "SELECT pictures_tags.picture_id, tags.name
+        FROM pictures_tags
+        INNER JOIN tags USING (tag_id)
+        ORDER BY tags.folded_name ASC",
+    )?;
+
+    let mut tags: HashMap<PictureId, Vec<String>> = HashMap::new();
+    for row in stmt.query_map([], |row| Ok((PictureId::new(row.get(0)?), row.get(1)?)))? {
+        let (picture_id, name) = row?;
+        tags.entry(picture_id).or_default().push(name);
+    }
+
+    Ok(tags)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::photo;
+    use crate::{FlatpakPathBuf, LibraryRoots, ScannedFile};
+    use std::path::{Path, PathBuf};
+
+    fn open() -> (Repository, PictureId, PictureId) {
+        let con = Arc::new(Mutex::new(database::setup_in_memory().unwrap()));
+        let library_roots = LibraryRoots::build(&FlatpakPathBuf::build("/", "/"), &[]);
+        let mut photo_repo =
+            photo::Repository::open(&library_roots, Path::new("/"), Path::new("/"), con.clone())
+                .unwrap();
+        photo_repo
+            .add_all(&vec![
+                ScannedFile::Photo(PathBuf::from("/a.jpg")),
+                ScannedFile::Photo(PathBuf::from("/b.jpg")),
+            ])
+            .unwrap();
+        let pictures = photo_repo.all().unwrap();
+        let tag_repo = Repository::open(con).unwrap();
+        (tag_repo, pictures[0].picture_id, pictures[1].picture_id)
+    }
+
+    #[test]
+    fn normalize_trims_and_folds() {
+        assert_eq!(
+            Some(("Holiday".to_string(), "holiday".to_string())),
+            normalize("  Holiday ")
+        );
+        assert_eq!(None, normalize(" \t"));
+    }
+
+    #[test]
+    fn tag_matches_ignoring_case() {
+        let (mut repo, a, b) = open();
+        repo.add_tag(&a, "Holiday").unwrap();
+        repo.add_tag(&b, " holiday").unwrap();
+        repo.add_tag(&b, "HOLIDAY").unwrap();
+
+        // Shown as first typed.
+        assert_eq!(vec!["Holiday".to_string()], repo.tags_for(&b).unwrap());
+
+        let mut tagged = repo.pictures_with_tag("hOLIDAY").unwrap();
+        tagged.sort_by_key(|id| id.id());
+        let mut expected = vec![a, b];
+        expected.sort_by_key(|id| id.id());
+        assert_eq!(expected, tagged);
+    }
+
+    #[test]
+    fn remove_tag_keeps_tag_for_other_pictures() {
+        let (mut repo, a, b) = open();
+        repo.add_tag(&a, "Holiday").unwrap();
+        repo.add_tag(&b, "Holiday").unwrap();
+
+        repo.remove_tag(&a, "holiday").unwrap();
+
+        assert!(repo.tags_for(&a).unwrap().is_empty());
+        assert_eq!(vec![b], repo.pictures_with_tag("Holiday").unwrap());
+    }
+
+    #[test]
+    fn delete_tag_keeps_pictures() {
+        let (mut repo, a, _) = open();
+        repo.add_tag(&a, "Receipts").unwrap();
+        repo.add_tag(&a, "Holiday").unwrap();
+
+        assert!(repo.delete_tag("receipts").unwrap());
+        assert!(!repo.delete_tag("receipts").unwrap());
+
+        assert_eq!(vec!["Holiday".to_string()], repo.tags_for(&a).unwrap());
+        assert!(repo.pictures_with_tag("Receipts").unwrap().is_empty());
+        assert_eq!(1, repo.all_tags().unwrap().len());
+    }
+}
@@ -0,0 +1,226 @@
+// SPDX-FileCopyrightText: © 2025 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Blurhash placeholders. A blurhash is a ~20 character string encoding a handful of
+//! cosine components of an image, which decodes to a smooth blur of the image's colours.
+//! See https://github.com/woltapp/blurhash/blob/master/Algorithm.md
+
+use image::{DynamicImage, RgbImage};
+use std::f32::consts::PI;
+
+const CHARACTERS: &[u8; 83] =
+    b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz#$%*+,-.:;=?@[]^_{|}~";
+
+/// Number of horizontal and vertical components. Enough to suggest the image's layout
+/// while keeping the hash short.
+const COMPONENTS_X: u32 = 4;
+const COMPONENTS_Y: u32 = 3;
+
+/// Encodes a blurhash for an image.
+/// The image is shrunk first because a blurhash only keeps the broad colours.
+pub fn encode(image: &DynamicImage) -> String {
+    let small = image.thumbnail(32, 32).to_rgb8();
+    encode_components(&small, COMPONENTS_X, COMPONENTS_Y)
+}
+
+fn encode_components(image: &RgbImage, components_x: u32, components_y: u32) -> String {
+    let (width, height) = image.dimensions();
+
+    let mut factors: Vec<[f32; 3]> = Vec::with_capacity((components_x * components_y) as usize);
+    for j in 0..components_y {
+        for i in 0..components_x {
+            let normalisation = if i == 0 && j == 0 { 1.0 } else { 2.0 };
+            let mut factor = [0.0; 3];
+            for (x, y, pixel) in image.enumerate_pixels() {
+                let basis = normalisation
+                    * (PI * i as f32 * x as f32 / width as f32).cos()
+                    * (PI * j as f32 * y as f32 / height as f32).cos();
+                for (f, channel) in factor.iter_mut().zip(pixel.0) {
+                    *f += basis * srgb_to_linear(channel);
+                }
+            }
+            let scale = 1.0 / (width * height) as f32;
+            factors.push(factor.map(|f| f * scale));
+        }
+    }
+
+    let mut hash = String::with_capacity(4 + 2 * factors.len());
+
+    let size_flag = (components_x - 1) + (components_y - 1) * 9;
+    encode83(size_flag, 1, &mut hash);
+
+    let (dc, ac) = factors.split_first().expect("At least one component");
+
+    let max_value = if ac.is_empty() {
+        encode83(0, 1, &mut hash);
+        1.0
+    } else {
+        let actual_max = ac
+            .iter()
+            .flatten()
+            .fold(0.0_f32, |max, value| max.max(value.abs()));
+        let quantised_max = (actual_max * 166.0 - 0.5).clamp(0.0, 82.0).floor() as u32;
+        encode83(quantised_max, 1, &mut hash);
+        (quantised_max + 1) as f32 / 166.0
+    };
+
+    let dc_value =
+        (linear_to_srgb(dc[0]) << 16) + (linear_to_srgb(dc[1]) << 8) + linear_to_srgb(dc[2]);
+    encode83(dc_value, 4, &mut hash);
+
+    for factor in ac {
+        let quantise = |value: f32| {
+            (sign_pow(value / max_value, 0.5) * 9.0 + 9.5)
+                .clamp(0.0, 18.0)
+                .floor() as u32
+        };
+        let ac_value =
+            quantise(factor[0]) * 19 * 19 + quantise(factor[1]) * 19 + quantise(factor[2]);
+        encode83(ac_value, 2, &mut hash);
+    }
+
+    hash
+}
+
+/// Decodes a blurhash to RGBA pixels of the given size.
+/// Returns None if the blurhash is malformed.
+pub fn decode(hash: &str, width: u32, height: u32) -> Option<Vec<u8>> {
+    if hash.len() < 6 || !hash.is_ascii() {
+        return None;
+    }
+
+    let size_flag = decode83(&hash[0..1])?;
+    let components_x = size_flag % 9 + 1;
+    let components_y = size_flag / 9 + 1;
+
+    if hash.len() != (4 + 2 * components_x * components_y) as usize {
+        return None;
+    }
+
+    let quantised_max = decode83(&hash[1..2])?;
+    let max_value = (quantised_max + 1) as f32 / 166.0;
+
+    let dc_value = decode83(&hash[2..6])?;
+    let mut colours: Vec<[f32; 3]> = vec![[
+        srgb_to_linear((dc_value >> 16) as u8),
+        srgb_to_linear((dc_value >> 8) as u8),
+        srgb_to_linear(dc_value as u8),
+    ]];
+
+    for i in 1..(components_x * components_y) as usize {
+        let ac_value = decode83(&hash[4 + i * 2..6 + i * 2])?;
+        let unquantise = |q: u32| sign_pow((q as f32 - 9.0) / 9.0, 2.0) * max_value;
+        colours.push([
+            unquantise(ac_value / (19 * 19)),
+            unquantise((ac_value / 19) % 19),
+            unquantise(ac_value % 19),
+        ]);
+    }
+
+    let mut pixels = Vec::with_capacity((width * height * 4) as usize);
+    for y in 0..height {
+        for x in 0..width {
+            let mut pixel = [0.0; 3];
+            for j in 0..components_y {
+                for i in 0..components_x {
+                    let basis = (PI * x as f32 * i as f32 / width as f32).cos()
+                        * (PI * y as f32 * j as f32 / height as f32).cos();
+                    let colour = colours[(i + j * components_x) as usize];
+                    for (p, c) in pixel.iter_mut().zip(colour) {
+                        *p += c * basis;
+                    }
+                }
+            }
+            pixels.extend(pixel.map(|c| linear_to_srgb(c) as u8));
+            pixels.push(255);
+        }
+    }
+
+    Some(pixels)
+}
+
+fn encode83(value: u32, length: u32, hash: &mut String) {
+    for i in 1..=length {
+        let digit = (value / 83_u32.pow(length - i)) % 83;
+        hash.push(CHARACTERS[digit as usize] as char);
+    }
+}
+
+fn decode83(s: &str) -> Option<u32> {
+    s.bytes().try_fold(0, |value, c| {
+        let digit = CHARACTERS.iter().position(|x| *x == c)?;
+        Some(value * 83 + digit as u32)
+    })
+}
+
+fn srgb_to_linear(value: u8) -> f32 {
+    let v = value as f32 / 255.0;
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f32) -> u32 {
+    let v = value.clamp(0.0, 1.0);
+    if v <= 0.0031308 {
+        (v * 12.92 * 255.0 + 0.5) as u32
+    } else {
+        ((1.055 * v.powf(1.0 / 2.4) - 0.055) * 255.0 + 0.5) as u32
+    }
+}
+
+fn sign_pow(value: f32, exp: f32) -> f32 {
+    value.abs().powf(exp).copysign(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgb;
+
+    #[test]
+    fn encode83_round_trip() {
+        let mut hash = String::new();
+        encode83(123456, 4, &mut hash);
+        assert_eq!(4, hash.len());
+        assert_eq!(Some(123456), decode83(&hash));
+    }
+
+    #[test]
+    fn hash_length_matches_components() {
+        let image = DynamicImage::ImageRgb8(RgbImage::from_pixel(64, 48, Rgb([200, 40, 40])));
+        let hash = encode(&image);
+        assert_eq!((4 + 2 * COMPONENTS_X * COMPONENTS_Y) as usize, hash.len());
+    }
+
+    #[test]
+    fn average_colour_round_trip() {
+        // With one component, the hash is just the average colour.
+        let image = RgbImage::from_pixel(8, 6, Rgb([200, 40, 40]));
+        let hash = encode_components(&image, 1, 1);
+        assert_eq!(6, hash.len());
+
+        let pixels = decode(&hash, 4, 4).unwrap();
+        assert_eq!(4 * 4 * 4, pixels.len());
+        for pixel in pixels.chunks(4) {
+            assert_eq!(&[200, 40, 40, 255], pixel);
+        }
+    }
+
+    #[test]
+    fn decode_rejects_malformed_hash() {
+        assert_eq!(None, decode("", 4, 4));
+        assert_eq!(None, decode("LEHV6nWB2y", 4, 4));
+        assert_eq!(None, decode("L\u{e9}HV6nWB2yk8pyo0adR*.7kCMdnj", 4, 4));
+    }
+
+    #[test]
+    fn decodes_reference_hash() {
+        // Example hash from the blurhash project.
+        let pixels = decode("LEHV6nWB2yk8pyo0adR*.7kCMdnj", 32, 32);
+        assert_eq!(Some(32 * 32 * 4), pixels.map(|p| p.len()));
+    }
+}
@@ -0,0 +1,356 @@
+// SPDX-FileCopyrightText: © 2025 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Keeps the thumbnail cache within a size limit by evicting the thumbnails that were
+//! least recently shown.
+//!
+//! An evicted thumbnail leaves a marker behind so that background tasks don't
+//! regenerate it straight away. It is regenerated when it is next shown instead.
+//!
+//! Also sweeps the cache for thumbnails that are truncated or corrupt, so they can be
+//! generated again, and for shared thumbnails that no file uses anymore.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fs::{self, File, FileTimes};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tracing::info;
+
+use crate::thumbnailify::{error::ThumbnailError, file, sizes::ThumbnailSize};
+
+/// All thumbnails of one source file.
+#[derive(Debug)]
+struct CacheEntry {
+    files: Vec<PathBuf>,
+    bytes: u64,
+    last_accessed: SystemTime,
+}
+
+const SIZES: [ThumbnailSize; 5] = [
+    ThumbnailSize::Small,
+    ThumbnailSize::Normal,
+    ThumbnailSize::Large,
+    ThumbnailSize::XLarge,
+    ThumbnailSize::XXLarge,
+];
+
+/// Directories of thumbnails that can be evicted. Fail markers are never evicted.
+fn evictable_dirs(thumbnails_base_dir: &Path) -> Vec<PathBuf> {
+    SIZES
+        .iter()
+        .map(|size| thumbnails_base_dir.join(size.to_string()))
+        .chain(std::iter::once(thumbnails_base_dir.join("animated")))
+        .collect()
+}
+
+/// Records that a thumbnail has just been shown.
+pub fn touch(thumb_path: &Path) -> io::Result<()> {
+    let file = File::open(thumb_path)?;
+    file.set_times(FileTimes::new().set_accessed(SystemTime::now()))
+}
+
+/// Deletes the least recently shown thumbnails until the cache is no larger than `max_bytes`.
+/// All sizes of a thumbnail are evicted together.
+/// Returns the hashes of the evicted thumbnails.
+pub fn enforce_cache_limit(
+    thumbnails_base_dir: &Path,
+    max_bytes: u64,
+) -> Result<Vec<String>, ThumbnailError> {
+    let mut entries: HashMap<String, CacheEntry> = HashMap::new();
+
+    for dir in evictable_dirs(thumbnails_base_dir) {
+        let read_dir = match fs::read_dir(&dir) {
+            Ok(read_dir) => read_dir,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        };
+
+        for dir_entry in read_dir {
+            let path = dir_entry?.path();
+
+            // Skip temporary files of thumbnails being written.
+            let is_thumbnail = path
+                .extension()
+                .is_some_and(|ext| ext == "png" || ext == "webp");
+            if !is_thumbnail {
+                continue;
+            }
+            let Some(hash) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+
+            let metadata = fs::metadata(&path)?;
+            let last_accessed = metadata
+                .accessed()
+                .or_else(|_| metadata.modified())
+                .unwrap_or(UNIX_EPOCH);
+
+            let entry = entries.entry(hash.to_string()).or_insert(CacheEntry {
+                files: Vec::new(),
+                bytes: 0,
+                last_accessed: UNIX_EPOCH,
+            });
+            entry.files.push(path);
+            entry.bytes += metadata.len();
+            entry.last_accessed = entry.last_accessed.max(last_accessed);
+        }
+    }
+
+    let mut total_bytes: u64 = entries.values().map(|entry| entry.bytes).sum();
+    if total_bytes <= max_bytes {
+        return Ok(Vec::new());
+    }
+
+    let mut entries: Vec<(String, CacheEntry)> = entries.into_iter().collect();
+    entries.sort_by_key(|(_, entry)| entry.last_accessed);
+
+    let mut evicted = Vec::new();
+    for (hash, entry) in entries {
+        if total_bytes <= max_bytes {
+            break;
+        }
+
+        // Write the marker first so that a missing thumbnail always has a marker.
+        write_evicted_marker(thumbnails_base_dir, &hash)?;
+
+        for path in entry.files {
+            match fs::remove_file(&path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        }
+
+        total_bytes = total_bytes.saturating_sub(entry.bytes);
+        evicted.push(hash);
+    }
+
+    // Evicted thumbnails only free space once no other file shares them.
+    file::remove_unshared_thumbnails(thumbnails_base_dir)?;
+
+    info!(
+        "Evicted {} thumbnails. Thumbnail cache is now {} bytes.",
+        evicted.len(),
+        total_bytes
+    );
+
+    Ok(evicted)
+}
+
+/// Deletes thumbnails that are truncated, corrupt, or too big for their size, such as
+/// those left behind by a crash. All sizes of a corrupt thumbnail are deleted together,
+/// so that they are all generated again.
+/// Returns the hashes of the deleted thumbnails.
+pub fn verify_cache(thumbnails_base_dir: &Path) -> Result<Vec<String>, ThumbnailError> {
+    let mut corrupt: BTreeSet<String> = BTreeSet::new();
+
+    for size in SIZES {
+        let dir = thumbnails_base_dir.join(size.to_string());
+        let read_dir = match fs::read_dir(&dir) {
+            Ok(read_dir) => read_dir,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        };
+
+        for dir_entry in read_dir {
+            let path = dir_entry?.path();
+
+            // Skip temporary files of thumbnails being written.
+            if !path.extension().is_some_and(|ext| ext == "png") {
+                continue;
+            }
+            let Some(hash) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+
+            if !file::is_thumbnail_intact(&path, size) {
+                info!("Thumbnail {:?} is corrupt", path);
+                corrupt.insert(hash.to_string());
+            }
+        }
+    }
+
+    for hash in &corrupt {
+        file::remove_thumbnails(thumbnails_base_dir, hash)?;
+    }
+
+    info!("Deleted {} corrupt thumbnails.", corrupt.len());
+
+    let unshared = file::remove_unshared_thumbnails(thumbnails_base_dir)?;
+    info!("Deleted {} unused shared thumbnails.", unshared);
+
+    Ok(corrupt.into_iter().collect())
+}
+
+fn write_evicted_marker(thumbnails_base_dir: &Path, hash: &str) -> io::Result<()> {
+    let marker_path = file::get_evicted_thumbnail_output(thumbnails_base_dir, hash);
+    if let Some(marker_dir) = marker_path.parent() {
+        fs::create_dir_all(marker_dir)?;
+    }
+    File::create(marker_path)?;
+    Ok(())
+}
+
+/// Removes the marker of an evicted thumbnail once it has been regenerated.
+pub fn clear_evicted(thumbnails_base_dir: &Path, hash: &str) -> io::Result<()> {
+    let marker_path = file::get_evicted_thumbnail_output(thumbnails_base_dir, hash);
+    match fs::remove_file(marker_path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// Writes a thumbnail of `len` bytes, last accessed `age_secs` seconds ago.
+    fn write_thumbnail(base: &Path, size: ThumbnailSize, hash: &str, len: usize, age_secs: u64) {
+        let path = file::get_thumbnail_hash_output(base, hash, size);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, vec![0; len]).unwrap();
+
+        let accessed = SystemTime::now() - Duration::from_secs(age_secs);
+        File::open(&path)
+            .unwrap()
+            .set_times(FileTimes::new().set_accessed(accessed))
+            .unwrap();
+    }
+
+    #[test]
+    fn does_nothing_within_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        write_thumbnail(dir.path(), ThumbnailSize::Large, "a", 100, 60);
+
+        let evicted = enforce_cache_limit(dir.path(), 100).unwrap();
+        assert!(evicted.is_empty());
+        assert!(file::get_thumbnail_hash_output(dir.path(), "a", ThumbnailSize::Large).exists());
+    }
+
+    #[test]
+    fn evicts_least_recently_accessed_first() {
+        let dir = tempfile::tempdir().unwrap();
+        write_thumbnail(dir.path(), ThumbnailSize::Large, "old", 100, 300);
+        write_thumbnail(dir.path(), ThumbnailSize::Normal, "old", 50, 300);
+        write_thumbnail(dir.path(), ThumbnailSize::Large, "middle", 100, 200);
+        write_thumbnail(dir.path(), ThumbnailSize::Large, "new", 100, 100);
+
+        let evicted = enforce_cache_limit(dir.path(), 200).unwrap();
+        assert_eq!(vec!["old".to_string()], evicted);
+
+        // Every size of an evicted thumbnail is deleted and marked as evicted.
+        assert!(!file::get_thumbnail_hash_output(dir.path(), "old", ThumbnailSize::Large).exists());
+        assert!(
+            !file::get_thumbnail_hash_output(dir.path(), "old", ThumbnailSize::Normal).exists()
+        );
+        assert!(file::is_evicted(dir.path(), "old"));
+
+        assert!(
+            file::get_thumbnail_hash_output(dir.path(), "middle", ThumbnailSize::Large).exists()
+        );
+        assert!(!file::is_evicted(dir.path(), "middle"));
+    }
+
+    #[test]
+    fn touch_protects_from_eviction() {
+        let dir = tempfile::tempdir().unwrap();
+        write_thumbnail(dir.path(), ThumbnailSize::Large, "a", 100, 300);
+        write_thumbnail(dir.path(), ThumbnailSize::Large, "b", 100, 200);
+
+        touch(&file::get_thumbnail_hash_output(
+            dir.path(),
+            "a",
+            ThumbnailSize::Large,
+        ))
+        .unwrap();
+
+        let evicted = enforce_cache_limit(dir.path(), 100).unwrap();
+        assert_eq!(vec!["b".to_string()], evicted);
+    }
+
+    #[test]
+    fn never_evicts_fail_markers() {
+        let dir = tempfile::tempdir().unwrap();
+        let fail_path = file::get_failed_thumbnail_output(dir.path(), "a");
+        fs::create_dir_all(fail_path.parent().unwrap()).unwrap();
+        fs::write(&fail_path, vec![0; 100]).unwrap();
+
+        let evicted = enforce_cache_limit(dir.path(), 0).unwrap();
+        assert!(evicted.is_empty());
+        assert!(fail_path.exists());
+    }
+
+    #[test]
+    fn clear_evicted_removes_marker() {
+        let dir = tempfile::tempdir().unwrap();
+        write_thumbnail(dir.path(), ThumbnailSize::Large, "a", 100, 60);
+        enforce_cache_limit(dir.path(), 0).unwrap();
+        assert!(file::is_evicted(dir.path(), "a"));
+
+        clear_evicted(dir.path(), "a").unwrap();
+        assert!(!file::is_evicted(dir.path(), "a"));
+
+        // Clearing a thumbnail that wasn't evicted is fine.
+        assert!(clear_evicted(dir.path(), "b").is_ok());
+    }
+
+    /// Writes a real PNG thumbnail of `width` by `height` pixels.
+    fn write_png_thumbnail(base: &Path, size: ThumbnailSize, hash: &str, width: u32, height: u32) {
+        let path = file::get_thumbnail_hash_output(base, hash, size);
+        let data = vec![0; (width * height * 4) as usize];
+        file::write_png(&path, width, height, &HashMap::new(), &data).unwrap();
+    }
+
+    #[test]
+    fn verify_cache_keeps_intact_thumbnails() {
+        let dir = tempfile::tempdir().unwrap();
+        write_png_thumbnail(dir.path(), ThumbnailSize::Small, "a", 64, 48);
+        write_png_thumbnail(dir.path(), ThumbnailSize::Large, "a", 256, 192);
+
+        let deleted = verify_cache(dir.path()).unwrap();
+        assert!(deleted.is_empty());
+
+        // Nothing is left behind by writing, not even temporary files.
+        let small_dir = dir.path().join(ThumbnailSize::Small.to_string());
+        assert_eq!(1, fs::read_dir(small_dir).unwrap().count());
+    }
+
+    #[test]
+    fn verify_cache_deletes_corrupt_thumbnails() {
+        let dir = tempfile::tempdir().unwrap();
+        write_png_thumbnail(dir.path(), ThumbnailSize::Large, "intact", 256, 192);
+
+        // Truncated, such as by a full disk.
+        write_png_thumbnail(dir.path(), ThumbnailSize::Large, "truncated", 256, 192);
+        write_png_thumbnail(dir.path(), ThumbnailSize::Small, "truncated", 64, 48);
+        let path = file::get_thumbnail_hash_output(dir.path(), "truncated", ThumbnailSize::Large);
+        let len = fs::metadata(&path).unwrap().len();
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_len(len - 20)
+            .unwrap();
+
+        // Too big for its size.
+        write_png_thumbnail(dir.path(), ThumbnailSize::Small, "oversized", 256, 192);
+
+        // Not a PNG at all.
+        write_thumbnail(dir.path(), ThumbnailSize::Normal, "garbage", 100, 60);
+
+        let deleted = verify_cache(dir.path()).unwrap();
+        assert_eq!(vec!["garbage", "oversized", "truncated"], deleted);
+
+        // Every size of a corrupt thumbnail is deleted, even intact ones.
+        assert!(
+            !file::get_thumbnail_hash_output(dir.path(), "truncated", ThumbnailSize::Small)
+                .exists()
+        );
+        assert!(
+            file::get_thumbnail_hash_output(dir.path(), "intact", ThumbnailSize::Large).exists()
+        );
+    }
+}
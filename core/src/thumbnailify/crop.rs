@@ -0,0 +1,146 @@
+// SPDX-FileCopyrightText: © 2025 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Square crops of thumbnails, such as for folder covers.
+//!
+//! Cropping around the centre can cut faces out of a group photo, so when faces
+//! have been detected the crop is moved to include the largest face.
+
+use crate::people::model::Rect;
+
+/// Square region of an image, in pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SquareCrop {
+    pub x: u32,
+    pub y: u32,
+    pub edge_length: u32,
+}
+
+/// Largest square crop of a `width` by `height` image.
+///
+/// Centred on the largest face, if there are any faces, otherwise on the centre of the
+/// image. Face bounds must be relative to the same image. The crop never extends past
+/// the edges of the image, so a face near an edge is included but not centred.
+pub fn smart_crop(width: u32, height: u32, faces: &[Rect]) -> SquareCrop {
+    let edge_length = width.min(height);
+
+    let largest_face = faces
+        .iter()
+        .max_by(|a, b| (a.width * a.height).total_cmp(&(b.width * b.height)));
+
+    let (centre_x, centre_y) = largest_face
+        .map(|face| (face.x + face.width / 2.0, face.y + face.height / 2.0))
+        .unwrap_or((width as f32 / 2.0, height as f32 / 2.0));
+
+    let offset = |centre: f32, length: u32| -> u32 {
+        let offset = centre - edge_length as f32 / 2.0;
+        offset.clamp(0.0, (length - edge_length) as f32).round() as u32
+    };
+
+    SquareCrop {
+        x: offset(centre_x, width),
+        y: offset(centre_y, height),
+        edge_length,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: f32, y: f32, width: f32, height: f32) -> Rect {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn centre_crop_without_faces() {
+        assert_eq!(
+            SquareCrop {
+                x: 50,
+                y: 0,
+                edge_length: 100
+            },
+            smart_crop(200, 100, &[])
+        );
+        assert_eq!(
+            SquareCrop {
+                x: 0,
+                y: 50,
+                edge_length: 100
+            },
+            smart_crop(100, 200, &[])
+        );
+    }
+
+    #[test]
+    fn crop_centred_on_face() {
+        let faces = [rect(110.0, 20.0, 20.0, 20.0)];
+        assert_eq!(
+            SquareCrop {
+                x: 70,
+                y: 0,
+                edge_length: 100
+            },
+            smart_crop(300, 100, &faces)
+        );
+    }
+
+    #[test]
+    fn crop_includes_largest_face() {
+        let faces = [
+            rect(10.0, 10.0, 10.0, 10.0),
+            rect(250.0, 40.0, 40.0, 40.0),
+            rect(140.0, 10.0, 20.0, 20.0),
+        ];
+        assert_eq!(
+            SquareCrop {
+                x: 200,
+                y: 0,
+                edge_length: 100
+            },
+            smart_crop(300, 100, &faces)
+        );
+    }
+
+    #[test]
+    fn crop_stays_within_image() {
+        let faces = [rect(0.0, 0.0, 10.0, 10.0)];
+        assert_eq!(
+            SquareCrop {
+                x: 0,
+                y: 0,
+                edge_length: 100
+            },
+            smart_crop(300, 100, &faces)
+        );
+
+        let faces = [rect(290.0, 90.0, 10.0, 10.0)];
+        assert_eq!(
+            SquareCrop {
+                x: 200,
+                y: 0,
+                edge_length: 100
+            },
+            smart_crop(300, 100, &faces)
+        );
+    }
+
+    #[test]
+    fn square_image_is_not_cropped() {
+        let faces = [rect(0.0, 0.0, 10.0, 10.0)];
+        assert_eq!(
+            SquareCrop {
+                x: 0,
+                y: 0,
+                edge_length: 100
+            },
+            smart_crop(100, 100, &faces)
+        );
+    }
+}
@@ -2,6 +2,7 @@
 // SPDX-FileCopyrightText: © 2025 David Bliss
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
+use std::path::PathBuf;
 use thiserror::Error;
 
 /// A unified error type for the thumbnail library.
@@ -30,4 +31,11 @@ pub enum ThumbnailError {
 
     #[error("Image resize error: {0}")]
     ResizeError(#[from] fast_image_resize::ResizeError),
+
+    /// Thumbnails can't be saved, such as when the disk is full or read-only.
+    #[error("Thumbnail directory {path:?} is not writable: {source}")]
+    Unwritable {
+        path: PathBuf,
+        source: std::io::Error,
+    },
 }
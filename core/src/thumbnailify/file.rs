@@ -4,9 +4,11 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use std::{
+    collections::HashMap,
     fs,
     fs::File,
-    io::{BufReader, BufWriter},
+    io,
+    io::{BufReader, BufWriter, Read, Seek, SeekFrom},
     path::{Path, PathBuf},
     time::UNIX_EPOCH,
 };
@@ -20,6 +22,9 @@ use crate::FlatpakPathBuf;
 use crate::thumbnailify::hash;
 use crate::thumbnailify::{error::ThumbnailError, sizes::ThumbnailSize};
 
+/// Every complete PNG file ends with an empty `IEND` chunk.
+const PNG_TRAILER: [u8; 12] = [0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xAE, 0x42, 0x60, 0x82];
+
 pub fn get_thumbnail_path(
     thumbnails_base_dir: &Path,
     host_path: &Path,
@@ -48,6 +53,46 @@ pub fn get_thumbnail_hash_output(
     path
 }
 
+/// Gets the output path of an animated preview using hash.
+/// Format: `{cache_dir}/thumbnails/animated/{md5_hash}.webp`
+pub fn get_animated_thumbnail_hash_output(thumbnails_base_dir: &Path, hash: &str) -> PathBuf {
+    let output_dir = thumbnails_base_dir.join("animated");
+    let output_file = format!("{}.webp", hash);
+    output_dir.join(output_file)
+}
+
+/// Gets the path of the marker left when a thumbnail is evicted from the cache.
+/// Format: `{cache_dir}/thumbnails/evicted/{md5_hash}`
+pub fn get_evicted_thumbnail_output(thumbnails_base_dir: &Path, hash: &str) -> PathBuf {
+    thumbnails_base_dir.join("evicted").join(hash)
+}
+
+/// Gets the path of the blurred thumbnail shown in place of a redacted thumbnail.
+/// Format: `{cache_dir}/thumbnails/redacted/{md5_hash}.png`
+pub fn get_redacted_thumbnail_hash_output(thumbnails_base_dir: &Path, hash: &str) -> PathBuf {
+    thumbnails_base_dir
+        .join("redacted")
+        .join(format!("{}.png", hash))
+}
+
+/// Gets the path of a thumbnail shared by every file with the same contents.
+/// Format: `{cache_dir}/thumbnails/content/{size}/{content_hash}.png`
+pub fn get_shared_thumbnail_output(
+    thumbnails_base_dir: &Path,
+    content_hash: &str,
+    size: ThumbnailSize,
+) -> PathBuf {
+    thumbnails_base_dir
+        .join("content")
+        .join(size.to_string())
+        .join(format!("{}.png", content_hash))
+}
+
+/// Was the thumbnail evicted from the cache, and not yet regenerated?
+pub fn is_evicted(thumbnails_base_dir: &Path, hash: &str) -> bool {
+    get_evicted_thumbnail_output(thumbnails_base_dir, hash).exists()
+}
+
 pub fn get_failed_thumbnail_output(thumbnails_base_dir: &Path, hash: &str) -> PathBuf {
     // FIXME don't hardcode app-id.
     let fail_dir = thumbnails_base_dir.join("fail").join("app.fotema.Fotema");
@@ -91,38 +136,373 @@ pub fn write_failed_thumbnail(
 
     let failed_img: RgbaImage = failed_img.to_rgba8();
 
-    // FIXME write to temporary file first and then move to final path.
-    let file = File::create(&fail_path)?;
-    let mut encoder = Encoder::new(BufWriter::new(file), 1, 1);
-    encoder.set_color(png::ColorType::Rgba);
-    encoder.set_depth(png::BitDepth::Eight);
+    let labels = thumbnail_labels(path)?;
+    write_png(&fail_path, 1, 1, &labels, &failed_img.into_raw())?;
 
-    // FIXME metadata is copy-and-pasted from thumbnailer.rs
+    debug!("Successfully wrote failure marker file to {:?}", fail_path);
+    return Ok(());
+}
 
+/// Text chunks recording which version of a file a thumbnail was generated from.
+/// See https://specifications.freedesktop.org/thumbnail-spec/latest/creation.html
+pub fn thumbnail_labels(path: &FlatpakPathBuf) -> Result<HashMap<String, String>, ThumbnailError> {
+    let mut labels: HashMap<String, String> = HashMap::with_capacity(4);
     // FIXME hard-coded app-id
-    encoder.add_text_chunk("Software".to_string(), "app.fotema.Fotema".to_string())?;
+    labels.insert("Software".into(), "app.fotema.Fotema".into());
 
     let uri = get_file_uri(&path.host_path)?;
-    encoder.add_text_chunk("Thumb::URI".to_string(), uri)?;
+    labels.insert("Thumb::URI".into(), uri);
 
     let metadata = std::fs::metadata(&path.sandbox_path)?;
-
     let size = metadata.len();
-    encoder.add_text_chunk("Thumb::Size".to_string(), size.to_string())?;
+    labels.insert("Thumb::Size".into(), size.to_string());
 
     let modified_time = metadata.modified()?;
     let mtime_unix = modified_time
         .duration_since(UNIX_EPOCH)
         .unwrap_or_default()
         .as_secs();
-    encoder.add_text_chunk("Thumb::MTime".to_string(), mtime_unix.to_string())?;
+    labels.insert("Thumb::MTime".into(), mtime_unix.to_string());
+
+    Ok(labels)
+}
+
+/// Writes an RGBA PNG to a temporary file in the same directory as `path`, and only
+/// renames it to `path` once it is completely written. A crash or a full disk leaves
+/// a stray temporary file behind rather than a truncated thumbnail.
+pub fn write_png(
+    path: &Path,
+    width: u32,
+    height: u32,
+    labels: &HashMap<String, String>,
+    data: &[u8],
+) -> Result<(), ThumbnailError> {
+    // Using `tempfile_in` ensures that the temp file is on the same filesystem
+    // so that we can atomically persist (rename) it.
+    let dir = path
+        .parent()
+        .ok_or_else(|| io::Error::other("Thumbnail path has no parent directory"))?;
+
+    fs::create_dir_all(dir)?;
+
+    let mut named_temp = tempfile::Builder::new()
+        .prefix("thumb-")
+        .suffix(".png.tmp")
+        .tempfile_in(dir)?;
+
+    let mut file = BufWriter::new(named_temp.as_file_mut());
+
+    let mut encoder = Encoder::new(&mut file, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    for (key, value) in labels.iter() {
+        encoder.add_text_chunk(key.into(), value.into())?;
+    }
 
-    // Write out the PNG header
     let mut writer = encoder.write_header()?;
-    writer.write_image_data(&failed_img.into_raw())?;
+    writer.write_image_data(data)?;
 
-    debug!("Successfully wrote failure marker file to {:?}", fail_path);
-    return Ok(());
+    // Finish and flush explicitly, because dropping the writers ignores errors.
+    writer.finish()?;
+    let file = file.into_inner().map_err(io::IntoInnerError::into_error)?;
+    file.sync_all()?;
+
+    named_temp.persist(path)?;
+    Ok(())
+}
+
+/// Checks that a thumbnail is a complete PNG that is no bigger than `size`, without
+/// decoding the image. A thumbnail cut short by a crash or full disk has no trailer.
+pub fn is_thumbnail_intact(thumb_path: &Path, size: ThumbnailSize) -> bool {
+    let mut file = match File::open(thumb_path) {
+        Ok(f) => f,
+        Err(e) => {
+            debug!("Failed to open thumbnail {:?}: {}", thumb_path, e);
+            return false;
+        }
+    };
+
+    let mut trailer = [0; PNG_TRAILER.len()];
+    let is_complete = file
+        .seek(SeekFrom::End(-(PNG_TRAILER.len() as i64)))
+        .and_then(|_| file.read_exact(&mut trailer))
+        .is_ok_and(|_| trailer == PNG_TRAILER);
+
+    if !is_complete {
+        debug!("Thumbnail {:?} is truncated", thumb_path);
+        return false;
+    }
+
+    if let Err(e) = file.rewind() {
+        debug!("Failed to rewind thumbnail {:?}: {}", thumb_path, e);
+        return false;
+    }
+
+    let decoder = Decoder::new(BufReader::new(file));
+    let reader = match decoder.read_info() {
+        Ok(r) => r,
+        Err(e) => {
+            debug!("Failed to read PNG info for {:?}: {}", thumb_path, e);
+            return false;
+        }
+    };
+
+    let info = reader.info();
+    let dimension = size.to_dimension();
+    if info.width == 0 || info.height == 0 || info.width > dimension || info.height > dimension {
+        debug!(
+            "Thumbnail {:?} is {}x{}, but should fit within {}x{}",
+            thumb_path, info.width, info.height, dimension, dimension
+        );
+        return false;
+    }
+
+    true
+}
+
+/// Deletes the thumbnails of every size for a hash, along with any failed or evicted
+/// marker, so that the thumbnail is generated again from scratch.
+pub fn remove_thumbnails(thumbnails_base_dir: &Path, hash: &str) -> std::io::Result<()> {
+    for path in thumbnail_files(thumbnails_base_dir, hash) {
+        match fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+            _ => debug!("Removed thumbnail {:?}", path),
+        }
+    }
+
+    Ok(())
+}
+
+/// Renames the thumbnails of every size for a hash, along with any failed or evicted
+/// marker and animated preview, to another hash, such as when a file has moved.
+/// Thumbnails record the size and modification time of a file, which don't change when
+/// it moves, so moved thumbnails are still up to date.
+pub fn move_thumbnails(
+    thumbnails_base_dir: &Path,
+    from_hash: &str,
+    to_hash: &str,
+) -> std::io::Result<()> {
+    let animated = (
+        get_animated_thumbnail_hash_output(thumbnails_base_dir, from_hash),
+        get_animated_thumbnail_hash_output(thumbnails_base_dir, to_hash),
+    );
+    let files = thumbnail_files(thumbnails_base_dir, from_hash)
+        .zip(thumbnail_files(thumbnails_base_dir, to_hash))
+        .chain([animated]);
+
+    for (from, to) in files {
+        match fs::rename(&from, &to) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+            _ => debug!("Moved thumbnail {:?} to {:?}", from, to),
+        }
+    }
+
+    Ok(())
+}
+
+/// Moves the thumbnails of every size for a hash, along with any failed or evicted
+/// marker and animated preview, from one thumbnail directory to another, such as when
+/// thumbnails are saved beside pictures instead of centrally.
+/// The directories may be on different volumes, so files that can't be renamed are
+/// copied and then deleted.
+pub fn relocate_thumbnails(
+    from_base_dir: &Path,
+    to_base_dir: &Path,
+    hash: &str,
+) -> std::io::Result<()> {
+    transfer_thumbnails(from_base_dir, hash, to_base_dir, hash)
+}
+
+/// Moves the thumbnails of every size for a hash, along with any failed or evicted
+/// marker and animated preview, to another thumbnail directory and hash, such as when
+/// a file with thumbnails beside it moves to another folder.
+/// Files that can't be renamed are copied and then deleted.
+pub fn transfer_thumbnails(
+    from_base_dir: &Path,
+    from_hash: &str,
+    to_base_dir: &Path,
+    to_hash: &str,
+) -> std::io::Result<()> {
+    let animated = (
+        get_animated_thumbnail_hash_output(from_base_dir, from_hash),
+        get_animated_thumbnail_hash_output(to_base_dir, to_hash),
+    );
+    let files = thumbnail_files(from_base_dir, from_hash)
+        .zip(thumbnail_files(to_base_dir, to_hash))
+        .chain([animated]);
+
+    for (from, to) in files {
+        if !from.exists() {
+            continue;
+        }
+
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        if fs::rename(&from, &to).is_err() {
+            fs::copy(&from, &to)?;
+            fs::remove_file(&from)?;
+        }
+        debug!("Moved thumbnail {:?} to {:?}", from, to);
+    }
+
+    Ok(())
+}
+
+/// Deletes a directory if it holds nothing but empty directories, such as a directory
+/// of thumbnails beside pictures once its thumbnails have been moved elsewhere.
+pub fn remove_empty_dirs(dir: &Path) {
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                remove_empty_dirs(&entry.path());
+            }
+        }
+    }
+
+    // Fails if anything is left, which is fine.
+    let _ = fs::remove_dir(dir);
+}
+
+/// Makes `dest` the same file as `src`, so that both take up the space of one, or a
+/// copy of `src` if they are on different file systems. Replaces any existing `dest`.
+pub fn link_thumbnail(src: &Path, dest: &Path) -> std::io::Result<()> {
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    match fs::remove_file(dest) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+        _ => {}
+    }
+
+    if fs::hard_link(src, dest).is_err() {
+        fs::copy(src, dest)?;
+    }
+    debug!("Linked thumbnail {:?} to {:?}", dest, src);
+    Ok(())
+}
+
+/// Writes a copy of a thumbnail with different text chunks, such as for a copy of a
+/// file that was modified at a different time.
+pub fn relabel_thumbnail(
+    src: &Path,
+    dest: &Path,
+    labels: &HashMap<String, String>,
+) -> Result<(), ThumbnailError> {
+    let image = image::open(src)?.to_rgba8();
+    let (width, height) = image.dimensions();
+    write_png(dest, width, height, labels, image.as_raw())
+}
+
+/// Could the thumbnail have been made from the source file? Thumbnails record the size
+/// of their source file, which must match, and their dimensions must match the
+/// dimensions of the source file scaled down. Source files whose dimensions can't be
+/// read cheaply, such as those in formats only Glycin can read, are only checked by size.
+/// Guards against two files with different contents having the same hash.
+pub fn is_thumbnail_of(thumb_path: &Path, source_path: &Path, size: ThumbnailSize) -> bool {
+    let Ok(metadata) = fs::metadata(source_path) else {
+        return false;
+    };
+    let is_same_size = read_label(thumb_path, "Thumb::Size")
+        .is_some_and(|thumb_size| thumb_size == metadata.len().to_string());
+    if !is_same_size {
+        debug!("Thumbnail {:?} is of a file of another size", thumb_path);
+        return false;
+    }
+
+    let Ok((thumb_width, thumb_height)) = image::image_dimensions(thumb_path) else {
+        return false;
+    };
+    let Ok((width, height)) = image::image_dimensions(source_path) else {
+        return true;
+    };
+
+    let dimension = size.to_dimension() as f32;
+    let scale = f32::min(1.0, dimension / u32::max(width, height) as f32);
+    let expected_width = (width as f32 * scale) as u32;
+    let expected_height = (height as f32 * scale) as u32;
+
+    // Pictures may be rotated when decoded, and may be downsampled first, which can
+    // round their thumbnails a pixel or two differently.
+    let is_close = |a: u32, b: u32| a.abs_diff(thumb_width) <= 2 && b.abs_diff(thumb_height) <= 2;
+    let is_match =
+        is_close(expected_width, expected_height) || is_close(expected_height, expected_width);
+    if !is_match {
+        debug!(
+            "Thumbnail {:?} is {}x{}, but expected {}x{}",
+            thumb_path, thumb_width, thumb_height, expected_width, expected_height
+        );
+    }
+    is_match
+}
+
+/// Value of one text chunk of a thumbnail.
+fn read_label(thumb_path: &Path, keyword: &str) -> Option<String> {
+    let file = File::open(thumb_path).ok()?;
+    let reader = Decoder::new(BufReader::new(file)).read_info().ok()?;
+    reader
+        .info()
+        .uncompressed_latin1_text
+        .iter()
+        .find(|chunk| chunk.keyword == keyword)
+        .map(|chunk| chunk.text.clone())
+}
+
+/// Deletes shared thumbnails that no file's thumbnails are the same file as anymore,
+/// such as when every copy of a picture has been removed or its thumbnails evicted.
+/// Returns how many were deleted.
+pub fn remove_unshared_thumbnails(thumbnails_base_dir: &Path) -> std::io::Result<usize> {
+    use std::os::unix::fs::MetadataExt;
+
+    let content_dir = thumbnails_base_dir.join("content");
+    let mut count = 0;
+    for size in [
+        ThumbnailSize::Small,
+        ThumbnailSize::Normal,
+        ThumbnailSize::Large,
+        ThumbnailSize::XLarge,
+        ThumbnailSize::XXLarge,
+    ] {
+        let read_dir = match fs::read_dir(content_dir.join(size.to_string())) {
+            Ok(read_dir) => read_dir,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+
+        for dir_entry in read_dir {
+            let path = dir_entry?.path();
+            if fs::metadata(&path)?.nlink() <= 1 {
+                fs::remove_file(&path)?;
+                count += 1;
+            }
+        }
+    }
+
+    Ok(count)
+}
+
+/// Thumbnails of every size for a hash, its blurred thumbnail, and its failed and
+/// evicted markers.
+fn thumbnail_files(thumbnails_base_dir: &Path, hash: &str) -> impl Iterator<Item = PathBuf> {
+    let sizes = [
+        ThumbnailSize::Small,
+        ThumbnailSize::Normal,
+        ThumbnailSize::Large,
+        ThumbnailSize::XLarge,
+        ThumbnailSize::XXLarge,
+    ];
+
+    sizes
+        .into_iter()
+        .map(|size| get_thumbnail_hash_output(thumbnails_base_dir, hash, size))
+        .chain([
+            get_redacted_thumbnail_hash_output(thumbnails_base_dir, hash),
+            get_failed_thumbnail_output(thumbnails_base_dir, hash),
+            get_evicted_thumbnail_output(thumbnails_base_dir, hash),
+        ])
 }
 
 /// Attempts to convert the file path into a file URI.
@@ -167,19 +547,11 @@ pub fn write_out_thumbnail(
         image_path, source_image_path
     );
 
-    // FIXME write to temporary file first and then move to final path.
-    let file = File::create(image_path)?;
-
     let rgba_image: RgbaImage = img.to_rgba8();
     let (width, height) = rgba_image.dimensions();
     let buffer = rgba_image.into_raw();
 
-    let mut encoder = Encoder::new(BufWriter::new(file), width, height);
-    encoder.set_color(png::ColorType::Rgba);
-    encoder.set_depth(png::BitDepth::Eight);
-
-    let mut writer = encoder.write_header()?;
-    writer.write_image_data(&buffer)?;
+    write_png(image_path, width, height, &HashMap::new(), &buffer)?;
 
     debug!("Successfully wrote thumbnail file to {:?}", image_path);
     Ok(())
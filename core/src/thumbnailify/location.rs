@@ -0,0 +1,72 @@
+// SPDX-FileCopyrightText: © 2025 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::path::{Path, PathBuf};
+use strum::{AsRefStr, EnumString, FromRepr};
+
+/// Name of the hidden directory beside pictures that holds their thumbnails.
+pub const SIDECAR_DIR_NAME: &str = ".fotema";
+
+/// Where thumbnails are saved.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, EnumString, AsRefStr, FromRepr)]
+#[repr(u32)]
+pub enum ThumbnailLocation {
+    /// In the cache directory of the app.
+    #[default]
+    Central,
+
+    /// In a hidden directory in each folder of pictures, so that thumbnails travel with
+    /// the pictures, such as on an external drive.
+    /// Folders that can't be written to, such as on a read-only volume, have their
+    /// thumbnails saved centrally instead.
+    Sidecar,
+}
+
+/// Hidden directory of thumbnails for the folder a picture or video is in.
+pub fn sidecar_dir(sandbox_path: &Path) -> Option<PathBuf> {
+    sandbox_path
+        .parent()
+        .map(|folder| folder.join(SIDECAR_DIR_NAME))
+}
+
+/// Can thumbnails be saved in the directory? Creates the directory if necessary, but
+/// not its parents, so that nothing is created for a folder on a drive that isn't mounted.
+pub fn is_writable(dir: &Path) -> bool {
+    let is_created = match std::fs::create_dir(dir) {
+        Ok(()) => true,
+        Err(e) => e.kind() == std::io::ErrorKind::AlreadyExists,
+    };
+
+    // Temporary file is deleted when dropped.
+    is_created && tempfile::tempfile_in(dir).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sidecar_dir_is_beside_file() {
+        assert_eq!(
+            Some(PathBuf::from("/photos/holiday/.fotema")),
+            sidecar_dir(Path::new("/photos/holiday/a.jpg"))
+        );
+    }
+
+    #[test]
+    fn unwritable_dir() {
+        // A directory can't be created beneath a file.
+        let file = tempfile::NamedTempFile::new().unwrap();
+        assert!(!is_writable(&file.path().join(SIDECAR_DIR_NAME)));
+
+        let dir = tempfile::tempdir().unwrap();
+        assert!(!is_writable(
+            &dir.path().join("unmounted").join(SIDECAR_DIR_NAME)
+        ));
+
+        let sidecar = dir.path().join(SIDECAR_DIR_NAME);
+        assert!(is_writable(&sidecar));
+        assert_eq!(0, std::fs::read_dir(&sidecar).unwrap().count());
+    }
+}
@@ -0,0 +1,208 @@
+// SPDX-FileCopyrightText: © 2025 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Keeps recently shown thumbnails decoded in memory, so that scrolling back over
+//! them doesn't decode them from disk again.
+//!
+//! Bounded by the memory used by decoded thumbnails. The least recently used
+//! thumbnails are dropped first.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug)]
+struct Entry<V> {
+    value: V,
+    bytes: usize,
+
+    // When the entry was last used. Key into `MemoryCache::recency`.
+    tick: u64,
+}
+
+/// Least recently used cache of decoded thumbnails, keyed by thumbnail path.
+#[derive(Debug)]
+pub struct MemoryCache<V> {
+    entries: HashMap<PathBuf, Entry<V>>,
+
+    // Paths by when they were last used, least recent first.
+    recency: BTreeMap<u64, PathBuf>,
+
+    next_tick: u64,
+    bytes: usize,
+    max_bytes: usize,
+}
+
+impl<V: Clone> MemoryCache<V> {
+    /// Cache holding values totalling no more than `max_bytes`.
+    pub fn new(max_bytes: usize) -> Self {
+        MemoryCache {
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            next_tick: 0,
+            bytes: 0,
+            max_bytes,
+        }
+    }
+
+    /// Value cached for a thumbnail path. Marks the value as most recently used.
+    pub fn get(&mut self, path: &Path) -> Option<V> {
+        let tick = self.tick();
+        let entry = self.entries.get_mut(path)?;
+
+        self.recency.remove(&entry.tick);
+        self.recency.insert(tick, path.to_path_buf());
+        entry.tick = tick;
+
+        Some(entry.value.clone())
+    }
+
+    /// Caches a value using `bytes` of memory, replacing any value for the same path.
+    /// Drops least recently used values to make room.
+    /// A value larger than the whole cache isn't cached.
+    pub fn insert(&mut self, path: PathBuf, value: V, bytes: usize) {
+        self.remove(&path);
+
+        if bytes > self.max_bytes {
+            return;
+        }
+
+        while self.bytes + bytes > self.max_bytes {
+            let Some((_, oldest)) = self.recency.pop_first() else {
+                break;
+            };
+            if let Some(entry) = self.entries.remove(&oldest) {
+                self.bytes -= entry.bytes;
+            }
+        }
+
+        let tick = self.tick();
+        self.recency.insert(tick, path.clone());
+        self.entries.insert(path, Entry { value, bytes, tick });
+        self.bytes += bytes;
+    }
+
+    /// Drops the value for a thumbnail path, such as when the thumbnail has changed.
+    pub fn remove(&mut self, path: &Path) {
+        if let Some(entry) = self.entries.remove(path) {
+            self.recency.remove(&entry.tick);
+            self.bytes -= entry.bytes;
+        }
+    }
+
+    /// Drops every value.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+        self.bytes = 0;
+    }
+
+    /// Count of cached values.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Memory used by cached values.
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    fn tick(&mut self) -> u64 {
+        let tick = self.next_tick;
+        self.next_tick += 1;
+        tick
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gets_inserted_value() {
+        let mut cache = MemoryCache::new(100);
+        cache.insert(PathBuf::from("a.png"), 1, 10);
+
+        assert_eq!(Some(1), cache.get(Path::new("a.png")));
+        assert_eq!(None, cache.get(Path::new("b.png")));
+        assert_eq!(10, cache.bytes());
+    }
+
+    #[test]
+    fn drops_least_recently_used_when_full() {
+        let mut cache = MemoryCache::new(30);
+        cache.insert(PathBuf::from("a.png"), 1, 10);
+        cache.insert(PathBuf::from("b.png"), 2, 10);
+        cache.insert(PathBuf::from("c.png"), 3, 10);
+
+        // Using "a" makes "b" the least recently used.
+        cache.get(Path::new("a.png"));
+        cache.insert(PathBuf::from("d.png"), 4, 10);
+
+        assert_eq!(Some(1), cache.get(Path::new("a.png")));
+        assert_eq!(None, cache.get(Path::new("b.png")));
+        assert_eq!(Some(3), cache.get(Path::new("c.png")));
+        assert_eq!(Some(4), cache.get(Path::new("d.png")));
+        assert_eq!(30, cache.bytes());
+    }
+
+    #[test]
+    fn drops_as_many_as_needed_for_large_value() {
+        let mut cache = MemoryCache::new(30);
+        cache.insert(PathBuf::from("a.png"), 1, 10);
+        cache.insert(PathBuf::from("b.png"), 2, 10);
+        cache.insert(PathBuf::from("c.png"), 3, 10);
+        cache.insert(PathBuf::from("d.png"), 4, 25);
+
+        assert_eq!(1, cache.len());
+        assert_eq!(Some(4), cache.get(Path::new("d.png")));
+        assert_eq!(25, cache.bytes());
+    }
+
+    #[test]
+    fn replaces_value_for_same_path() {
+        let mut cache = MemoryCache::new(30);
+        cache.insert(PathBuf::from("a.png"), 1, 10);
+        cache.insert(PathBuf::from("a.png"), 2, 20);
+
+        assert_eq!(1, cache.len());
+        assert_eq!(Some(2), cache.get(Path::new("a.png")));
+        assert_eq!(20, cache.bytes());
+    }
+
+    #[test]
+    fn never_caches_value_larger_than_cache() {
+        let mut cache = MemoryCache::new(30);
+        cache.insert(PathBuf::from("a.png"), 1, 10);
+        cache.insert(PathBuf::from("b.png"), 2, 31);
+
+        assert_eq!(Some(1), cache.get(Path::new("a.png")));
+        assert_eq!(None, cache.get(Path::new("b.png")));
+    }
+
+    #[test]
+    fn remove_frees_memory() {
+        let mut cache = MemoryCache::new(30);
+        cache.insert(PathBuf::from("a.png"), 1, 10);
+        cache.remove(Path::new("a.png"));
+
+        assert!(cache.is_empty());
+        assert_eq!(0, cache.bytes());
+    }
+
+    #[test]
+    fn clear_drops_everything() {
+        let mut cache = MemoryCache::new(30);
+        cache.insert(PathBuf::from("a.png"), 1, 10);
+        cache.insert(PathBuf::from("b.png"), 2, 10);
+        cache.clear();
+
+        assert!(cache.is_empty());
+        assert_eq!(0, cache.bytes());
+        assert_eq!(None, cache.get(Path::new("a.png")));
+    }
+}
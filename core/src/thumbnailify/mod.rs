@@ -3,57 +3,385 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use image::DynamicImage;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, PoisonError, RwLock};
+use tracing::{info, warn};
 
+pub mod blurhash;
+pub mod cache;
+pub mod crop;
 pub mod error;
 pub mod file;
 pub mod hash;
+pub mod location;
+pub mod memory_cache;
 pub mod sizes;
 pub mod thumbnailer;
 
+pub use crop::SquareCrop;
 pub use error::ThumbnailError;
+pub use file::get_animated_thumbnail_hash_output;
 pub use file::get_file_uri;
 pub use file::get_thumbnail_hash_output;
 pub use file::get_thumbnail_path;
+pub use file::is_evicted;
 pub use file::is_failed;
+pub use file::is_thumbnail_intact;
 pub use file::write_failed_thumbnail;
 pub use hash::compute_hash;
+pub use location::ThumbnailLocation;
+pub use memory_cache::MemoryCache;
 pub use sizes::ThumbnailSize;
 pub use thumbnailer::generate_thumbnail;
+pub use thumbnailer::is_thumbnail_up_to_date;
 
 use crate::FlatpakPathBuf;
+use crate::people::model::Rect;
 
 pub fn compute_hash_for_path(host_path: &Path) -> String {
     let file_uri = file::get_file_uri(host_path).unwrap();
     hash::compute_hash(&file_uri)
 }
 
+/// Edge length of blurred thumbnails. Small, because they show no detail.
+const REDACTED_DIMENSION: u32 = 64;
+
+/// Sizes of thumbnail that are shared by files with the same contents, largest first.
+/// These are the sizes generated for every picture.
+const SHARED_SIZES: [ThumbnailSize; 4] = [
+    ThumbnailSize::XLarge,
+    ThumbnailSize::Large,
+    ThumbnailSize::Normal,
+    ThumbnailSize::Small,
+];
+
 #[derive(Clone, Debug)]
 pub struct Thumbnailer {
+    /// Central directory of thumbnails, in the cache directory of the app.
     thumbnails_path: PathBuf,
+
+    /// Where thumbnails are saved. Shared by every clone.
+    location: Arc<RwLock<ThumbnailLocation>>,
+
+    /// Directories of thumbnails saved beside pictures, by hash. Thumbnails of any other
+    /// hash are in the central directory. Shared by every clone.
+    sidecars: Arc<RwLock<HashMap<String, PathBuf>>>,
+
+    /// Directories of thumbnails beside pictures, and whether thumbnails can be saved in
+    /// them, so that each is only checked once. Shared by every clone.
+    sidecar_dirs: Arc<RwLock<HashMap<PathBuf, bool>>>,
+
+    /// Hashes of thumbnails shown blurred, such as of private pictures while locked.
+    /// Shared by every clone.
+    redacted: Arc<RwLock<HashSet<String>>>,
 }
 
 impl Thumbnailer {
     pub fn build(thumbnails_path: &Path) -> Thumbnailer {
         Thumbnailer {
             thumbnails_path: thumbnails_path.into(),
+            location: Arc::default(),
+            sidecars: Arc::default(),
+            sidecar_dirs: Arc::default(),
+            redacted: Arc::default(),
+        }
+    }
+
+    pub fn location(&self) -> ThumbnailLocation {
+        *self.location.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Save thumbnails centrally, or beside pictures.
+    /// Thumbnails already saved stay where they are unless moved with `relocate_thumbnails`.
+    pub fn set_location(&self, location: ThumbnailLocation) {
+        *self
+            .location
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = location;
+
+        if location == ThumbnailLocation::Central {
+            self.sidecars
+                .write()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clear();
+        }
+    }
+
+    /// Resolves the directory that thumbnails of a file are saved in, and remembers it
+    /// so that the thumbnails can be found from their hash alone.
+    pub fn add_thumbnail(&self, path: &FlatpakPathBuf) -> PathBuf {
+        let base_dir = self.resolve(self.location(), path);
+        let hash = path.thumbnail_hash();
+
+        let mut sidecars = self
+            .sidecars
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        if base_dir == self.thumbnails_path {
+            sidecars.remove(&hash);
+        } else {
+            sidecars.insert(hash, base_dir.clone());
+        }
+
+        base_dir
+    }
+
+    /// Moves the thumbnails of a file from where they were saved for another location
+    /// to where they are saved now.
+    pub fn relocate_thumbnails(
+        &self,
+        path: &FlatpakPathBuf,
+        from: ThumbnailLocation,
+    ) -> std::io::Result<()> {
+        let from_base_dir = self.resolve(from, path);
+        let to_base_dir = self.add_thumbnail(path);
+        if from_base_dir != to_base_dir {
+            file::relocate_thumbnails(&from_base_dir, &to_base_dir, &path.thumbnail_hash())?;
+        }
+        Ok(())
+    }
+
+    /// Deletes every thumbnail of a file, along with its animated preview, both centrally
+    /// and from beside the file, and forgets that it failed or was evicted, so that the
+    /// next thumbnail is generated from scratch. Also for when the file is no longer in
+    /// the library.
+    pub fn remove_thumbnails(&self, path: &FlatpakPathBuf) -> std::io::Result<()> {
+        let hash = path.thumbnail_hash();
+        let base_dirs = [
+            Some(self.thumbnails_path.clone()),
+            location::sidecar_dir(&path.sandbox_path),
+        ];
+
+        for base_dir in base_dirs.into_iter().flatten() {
+            file::remove_thumbnails(&base_dir, &hash)?;
+
+            let animated = file::get_animated_thumbnail_hash_output(&base_dir, &hash);
+            match std::fs::remove_file(&animated) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+                _ => {}
+            }
+        }
+
+        self.sidecars
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&hash);
+
+        Ok(())
+    }
+
+    /// Moves the thumbnails of a file, along with its animated preview, when the file
+    /// moves. Thumbnails beside the file are moved to wherever thumbnails of the file
+    /// are saved at its new path.
+    pub fn move_thumbnails(
+        &self,
+        from: &FlatpakPathBuf,
+        to: &FlatpakPathBuf,
+    ) -> std::io::Result<()> {
+        let from_hash = from.thumbnail_hash();
+        let to_hash = to.thumbnail_hash();
+        let to_base_dir = self.add_thumbnail(to);
+
+        let from_base_dirs = [
+            Some(self.thumbnails_path.clone()),
+            location::sidecar_dir(&from.sandbox_path),
+        ];
+
+        for from_base_dir in from_base_dirs.into_iter().flatten() {
+            file::transfer_thumbnails(&from_base_dir, &from_hash, &to_base_dir, &to_hash)?;
+        }
+
+        if from_hash != to_hash {
+            self.sidecars
+                .write()
+                .unwrap_or_else(PoisonError::into_inner)
+                .remove(&from_hash);
+        }
+
+        Ok(())
+    }
+
+    /// Deletes directories beside pictures that no longer hold any thumbnails.
+    pub fn remove_empty_sidecars(&self) {
+        let sidecar_dirs = self
+            .sidecar_dirs
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        for (dir, _) in sidecar_dirs.iter().filter(|(_, is_writable)| **is_writable) {
+            file::remove_empty_dirs(dir);
+        }
+    }
+
+    /// Directory that thumbnails of a file are saved in for a location.
+    /// Thumbnails of files in folders that can't be written to are saved centrally.
+    fn resolve(&self, location: ThumbnailLocation, path: &FlatpakPathBuf) -> PathBuf {
+        let sidecar_dir = match location {
+            ThumbnailLocation::Central => None,
+            ThumbnailLocation::Sidecar => location::sidecar_dir(&path.sandbox_path),
+        };
+        let Some(sidecar_dir) = sidecar_dir else {
+            return self.thumbnails_path.clone();
+        };
+
+        let is_checked = self
+            .sidecar_dirs
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&sidecar_dir)
+            .copied();
+
+        let is_writable = is_checked.unwrap_or_else(|| {
+            let is_writable = location::is_writable(&sidecar_dir);
+            if !is_writable {
+                info!(
+                    "Saving thumbnails centrally for read-only {:?}",
+                    sidecar_dir
+                );
+            }
+            self.sidecar_dirs
+                .write()
+                .unwrap_or_else(PoisonError::into_inner)
+                .insert(sidecar_dir.clone(), is_writable);
+            is_writable
+        });
+
+        if is_writable {
+            sidecar_dir
+        } else {
+            self.thumbnails_path.clone()
         }
     }
 
+    /// Directory the thumbnails of a hash are saved in.
+    fn base_dir(&self, hash: &str) -> PathBuf {
+        self.sidecars
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(hash)
+            .cloned()
+            .unwrap_or_else(|| self.thumbnails_path.clone())
+    }
+
+    /// Show the thumbnails of these hashes blurred, and show all others as they are.
+    pub fn set_redacted(&self, hashes: HashSet<String>) {
+        *self
+            .redacted
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = hashes;
+    }
+
+    /// Is the thumbnail shown blurred?
+    pub fn is_redacted(&self, hash: &str) -> bool {
+        self.redacted
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .contains(hash)
+    }
+
+    /// Checks a file can be written to the thumbnail directory, so that a full or read-only
+    /// disk is reported once rather than as a failure for every thumbnail.
+    pub fn check_writable(&self) -> Result<(), ThumbnailError> {
+        let unwritable = |source| ThumbnailError::Unwritable {
+            path: self.thumbnails_path.clone(),
+            source,
+        };
+
+        std::fs::create_dir_all(&self.thumbnails_path).map_err(unwritable)?;
+
+        // Temporary file is deleted when dropped.
+        let mut file = tempfile::tempfile_in(&self.thumbnails_path).map_err(unwritable)?;
+        file.write_all(b"fotema").map_err(unwritable)?;
+        file.sync_all().map_err(unwritable)?;
+
+        Ok(())
+    }
+
     pub fn is_failed(&self, host_path: &Path) -> bool {
-        file::is_failed(&self.thumbnails_path, host_path)
+        let hash = compute_hash_for_path(host_path);
+        file::is_failed(&self.base_dir(&hash), host_path)
+    }
+
+    /// Was the thumbnail evicted to keep the cache within its size limit?
+    pub fn is_evicted(&self, hash: &str) -> bool {
+        file::is_evicted(&self.base_dir(hash), hash)
     }
 
-    pub fn is_thumbnail_up_to_date(&self, host_path: &Path) -> bool {
-        thumbnailer::is_thumbnail_up_to_date(&self.thumbnails_path, host_path)
+    /// Forget that a thumbnail was evicted, once it has been regenerated.
+    pub fn clear_evicted(&self, hash: &str) -> std::io::Result<()> {
+        cache::clear_evicted(&self.base_dir(hash), hash)
+    }
+
+    /// Records that a thumbnail has just been shown, so it is evicted last.
+    pub fn touch(&self, thumb_path: &Path) -> std::io::Result<()> {
+        cache::touch(thumb_path)
+    }
+
+    /// Deletes the least recently shown thumbnails until the cache is no larger than
+    /// `max_bytes`. Returns the hashes of the evicted thumbnails.
+    pub fn enforce_cache_limit(&self, max_bytes: u64) -> Result<Vec<String>, ThumbnailError> {
+        cache::enforce_cache_limit(&self.thumbnails_path, max_bytes)
+    }
+
+    /// Deletes thumbnails that are truncated or corrupt, so that they are generated again.
+    /// Returns the hashes of the deleted thumbnails.
+    pub fn verify_cache(&self) -> Result<Vec<String>, ThumbnailError> {
+        cache::verify_cache(&self.thumbnails_path)
+    }
+
+    /// Whether a thumbnail of the given size exists, is intact, and was generated from
+    /// the current version of the source file.
+    pub fn is_thumbnail_up_to_date(&self, path: &FlatpakPathBuf, size: ThumbnailSize) -> bool {
+        let thumb_path = get_thumbnail_path(&self.add_thumbnail(path), &path.host_path, size);
+        file::is_thumbnail_intact(&thumb_path, size)
+            && thumbnailer::is_thumbnail_up_to_date(&thumb_path, &path.sandbox_path)
     }
 
     pub fn get_thumbnail_hash_output(&self, hash: &str, size: ThumbnailSize) -> PathBuf {
-        get_thumbnail_hash_output(&self.thumbnails_path, hash, size)
+        get_thumbnail_hash_output(&self.base_dir(hash), hash, size)
     }
 
     pub fn get_thumbnail_path(&self, host_path: &Path, size: ThumbnailSize) -> PathBuf {
-        get_thumbnail_path(&self.thumbnails_path, host_path, size)
+        let hash = compute_hash_for_path(host_path);
+        get_thumbnail_hash_output(&self.base_dir(&hash), &hash, size)
+    }
+
+    /// Path of the looping preview for a video or animated image.
+    pub fn get_animated_thumbnail_path(&self, path: &FlatpakPathBuf) -> PathBuf {
+        let hash = path.thumbnail_hash();
+        file::get_animated_thumbnail_hash_output(&self.add_thumbnail(path), &hash)
+    }
+
+    /// Looping preview for a video or animated image, if one has been generated.
+    /// Blurred thumbnails have no preview.
+    pub fn animated_thumbnail(&self, hash: &str) -> Option<PathBuf> {
+        if self.is_redacted(hash) {
+            return None;
+        }
+        let path = file::get_animated_thumbnail_hash_output(&self.base_dir(hash), hash);
+        path.exists().then_some(path)
+    }
+
+    /// Delete all looping previews, including those saved beside pictures.
+    pub fn remove_animated_thumbnails(&self) -> std::io::Result<()> {
+        let sidecar_dirs: Vec<PathBuf> = self
+            .sidecar_dirs
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .filter(|(_, is_writable)| **is_writable)
+            .map(|(dir, _)| dir.clone())
+            .collect();
+
+        for base_dir in std::iter::once(self.thumbnails_path.clone()).chain(sidecar_dirs) {
+            let animated_dir = base_dir.join("animated");
+            if animated_dir.exists() {
+                std::fs::remove_dir_all(animated_dir)?;
+            }
+        }
+        std::io::Result::Ok(())
     }
 
     //pub fn nearest_thumbnail_by_dimension(&self, hash: &str, dimension: u32) -> Option<PathBuf> {
@@ -62,26 +390,24 @@ impl Thumbnailer {
     /**
      * Compute thumbnail path, or sensible fallback if preferred size does not exist.
      * If no thumbnails exist, then return preferred path pointing to absent file.
+     * A blurred thumbnail is returned in place of a redacted thumbnail of any size.
      */
     pub fn nearest_thumbnail(&self, hash: &str, size: ThumbnailSize) -> Option<PathBuf> {
-        let preferred = file::get_thumbnail_hash_output(&self.thumbnails_path, hash, size);
+        if self.is_redacted(hash) {
+            return self.redacted_thumbnail(hash);
+        }
+
+        let base_dir = self.base_dir(hash);
+        let preferred = file::get_thumbnail_hash_output(&base_dir, hash, size);
 
         if preferred.exists() {
             Some(preferred)
         } else {
-            let xxlarge = file::get_thumbnail_hash_output(
-                &self.thumbnails_path,
-                hash,
-                ThumbnailSize::XXLarge,
-            );
-            let xlarge =
-                file::get_thumbnail_hash_output(&self.thumbnails_path, hash, ThumbnailSize::XLarge);
-            let large =
-                file::get_thumbnail_hash_output(&self.thumbnails_path, hash, ThumbnailSize::Large);
-            let normal =
-                file::get_thumbnail_hash_output(&self.thumbnails_path, hash, ThumbnailSize::Normal);
-            let small =
-                file::get_thumbnail_hash_output(&self.thumbnails_path, hash, ThumbnailSize::Small);
+            let xxlarge = file::get_thumbnail_hash_output(&base_dir, hash, ThumbnailSize::XXLarge);
+            let xlarge = file::get_thumbnail_hash_output(&base_dir, hash, ThumbnailSize::XLarge);
+            let large = file::get_thumbnail_hash_output(&base_dir, hash, ThumbnailSize::Large);
+            let normal = file::get_thumbnail_hash_output(&base_dir, hash, ThumbnailSize::Normal);
+            let small = file::get_thumbnail_hash_output(&base_dir, hash, ThumbnailSize::Small);
 
             let paths = match size {
                 // TODO figure out if some fallback sizes should be excluded?
@@ -97,13 +423,45 @@ impl Thumbnailer {
         }
     }
 
+    /// Blurred copy of the smallest thumbnail, made when first needed.
+    fn redacted_thumbnail(&self, hash: &str) -> Option<PathBuf> {
+        let path = file::get_redacted_thumbnail_hash_output(&self.base_dir(hash), hash);
+        if path.exists() {
+            return Some(path);
+        }
+
+        let smallest = self.nearest_thumbnail_unredacted(hash)?;
+        match write_blurred(&smallest, &path) {
+            Ok(()) => Some(path),
+            Err(e) => {
+                warn!("Failed blurring thumbnail {:?}: {:?}", smallest, e);
+                None
+            }
+        }
+    }
+
+    /// Smallest thumbnail that exists for a hash.
+    fn nearest_thumbnail_unredacted(&self, hash: &str) -> Option<PathBuf> {
+        let base_dir = self.base_dir(hash);
+        [
+            ThumbnailSize::Small,
+            ThumbnailSize::Normal,
+            ThumbnailSize::Large,
+            ThumbnailSize::XLarge,
+            ThumbnailSize::XXLarge,
+        ]
+        .into_iter()
+        .map(|size| file::get_thumbnail_hash_output(&base_dir, hash, size))
+        .find(|path| path.exists())
+    }
+
     pub fn generate_thumbnail(
         &self,
         path: &FlatpakPathBuf,
         size: ThumbnailSize,
         src_image: DynamicImage,
     ) -> Result<PathBuf, ThumbnailError> {
-        thumbnailer::generate_thumbnail(&self.thumbnails_path, path, size, src_image)
+        thumbnailer::generate_thumbnail(&self.add_thumbnail(path), path, size, src_image)
     }
 
     pub fn generate_all_thumbnails(
@@ -111,10 +469,391 @@ impl Thumbnailer {
         path: &FlatpakPathBuf,
         src_image: DynamicImage,
     ) -> Result<(), ThumbnailError> {
-        thumbnailer::generate_all_thumbnails(&self.thumbnails_path, path, src_image)
+        thumbnailer::generate_all_thumbnails(&self.add_thumbnail(path), path, src_image)
     }
 
     pub fn write_failed_thumbnail(&self, path: &FlatpakPathBuf) -> Result<(), ThumbnailError> {
-        file::write_failed_thumbnail(&self.thumbnails_path, path)
+        file::write_failed_thumbnail(&self.add_thumbnail(path), path)
+    }
+
+    /// Shares the thumbnails of a file with every file with the same contents, so that
+    /// they aren't generated again for a copy of the file, or for the file once it has
+    /// moved. Shared thumbnails are keyed by the hash of the file contents, and are the
+    /// same file as the thumbnails they were shared from, so take up no extra space.
+    pub fn share_thumbnails(
+        &self,
+        path: &FlatpakPathBuf,
+        content_hash: &str,
+    ) -> std::io::Result<()> {
+        let base_dir = self.add_thumbnail(path);
+        let hash = path.thumbnail_hash();
+        for size in SHARED_SIZES {
+            let thumb_path = get_thumbnail_hash_output(&base_dir, &hash, size);
+            if thumb_path.exists() {
+                let shared_path = file::get_shared_thumbnail_output(&base_dir, content_hash, size);
+                file::link_thumbnail(&thumb_path, &shared_path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Uses the thumbnails shared by a file with the same contents as a file's own, rather
+    /// than generating them. Returns the path of the largest thumbnail, or `None` if
+    /// there are no shared thumbnails, or they weren't made from a file like this one.
+    pub fn reuse_thumbnails(
+        &self,
+        path: &FlatpakPathBuf,
+        content_hash: &str,
+    ) -> Result<Option<PathBuf>, ThumbnailError> {
+        let base_dir = self.add_thumbnail(path);
+        let shared_paths = SHARED_SIZES
+            .map(|size| file::get_shared_thumbnail_output(&base_dir, content_hash, size));
+
+        let is_intact = SHARED_SIZES
+            .iter()
+            .zip(&shared_paths)
+            .all(|(size, shared_path)| file::is_thumbnail_intact(shared_path, *size));
+        if !is_intact {
+            return Ok(None);
+        }
+
+        // Different contents with the same hash are very unlikely, but would show the
+        // wrong picture.
+        if !file::is_thumbnail_of(&shared_paths[0], &path.sandbox_path, SHARED_SIZES[0]) {
+            warn!(
+                "Not reusing thumbnails of {} for {:?}, which look like another file",
+                content_hash, path.sandbox_path
+            );
+            return Ok(None);
+        }
+
+        let hash = path.thumbnail_hash();
+        let labels = file::thumbnail_labels(path)?;
+        for (size, shared_path) in SHARED_SIZES.iter().zip(&shared_paths) {
+            let thumb_path = get_thumbnail_hash_output(&base_dir, &hash, *size);
+            if thumbnailer::is_thumbnail_up_to_date(shared_path, &path.sandbox_path) {
+                file::link_thumbnail(shared_path, &thumb_path)?;
+            } else {
+                // Copies of a file may have been modified at different times, which
+                // thumbnails record, so can't always be the same file.
+                file::relabel_thumbnail(shared_path, &thumb_path, &labels)?;
+            }
+        }
+        self.clear_evicted(&hash)?;
+
+        Ok(Some(get_thumbnail_hash_output(
+            &base_dir,
+            &hash,
+            SHARED_SIZES[0],
+        )))
+    }
+
+    /// Stops sharing thumbnails between files with the same contents, such as when they
+    /// look wrong and are being generated again.
+    pub fn unshare_thumbnails(
+        &self,
+        path: &FlatpakPathBuf,
+        content_hash: &str,
+    ) -> std::io::Result<()> {
+        let base_dir = self.add_thumbnail(path);
+        for size in SHARED_SIZES {
+            let shared_path = file::get_shared_thumbnail_output(&base_dir, content_hash, size);
+            match std::fs::remove_file(&shared_path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Square crop of a thumbnail that includes the largest face, or a centre crop
+    /// if there are no faces.
+    /// Faces are detected in the x-large thumbnail, so face bounds are scaled from
+    /// the x-large thumbnail to the thumbnail being cropped.
+    pub fn smart_crop(
+        &self,
+        thumb_path: &Path,
+        hash: &str,
+        faces: &[Rect],
+    ) -> Result<SquareCrop, ThumbnailError> {
+        let (width, height) = image::image_dimensions(thumb_path)?;
+        if faces.is_empty() {
+            return Ok(crop::smart_crop(width, height, faces));
+        }
+
+        let xlarge_path = self.get_thumbnail_hash_output(hash, ThumbnailSize::XLarge);
+        let (xlarge_width, _) = image::image_dimensions(xlarge_path)?;
+        let ratio = width as f32 / xlarge_width as f32;
+
+        let faces: Vec<Rect> = faces.iter().map(|face| face.clone().scale(ratio)).collect();
+        Ok(crop::smart_crop(width, height, &faces))
+    }
+}
+
+/// Writes a copy of a thumbnail that is too blurred to make out, but keeps its colours.
+fn write_blurred(src: &Path, dest: &Path) -> Result<(), ThumbnailError> {
+    use image::imageops::{self, FilterType};
+
+    // Shrinking first throws the detail away, so the blur can't be undone.
+    let tiny = image::open(src)?.thumbnail(8, 8).to_rgba8();
+    let blurred = imageops::resize(
+        &imageops::blur(&tiny, 1.5),
+        REDACTED_DIMENSION,
+        REDACTED_DIMENSION,
+        FilterType::Triangle,
+    );
+
+    let (width, height) = blurred.dimensions();
+    file::write_png(dest, width, height, &HashMap::new(), blurred.as_raw())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_writable_creates_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let thumbnails_path = dir.path().join("thumbnails");
+        let thumbnailer = Thumbnailer::build(&thumbnails_path);
+
+        assert!(thumbnailer.check_writable().is_ok());
+        assert!(thumbnails_path.is_dir());
+
+        // Nothing is left behind.
+        assert_eq!(0, std::fs::read_dir(&thumbnails_path).unwrap().count());
+    }
+
+    #[test]
+    fn check_writable_reports_path() {
+        // A directory can't be created beneath a file.
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let thumbnails_path = file.path().join("thumbnails");
+        let thumbnailer = Thumbnailer::build(&thumbnails_path);
+
+        let result = thumbnailer.check_writable();
+        assert!(
+            matches!(result, Err(ThumbnailError::Unwritable { path, .. }) if path == thumbnails_path)
+        );
+    }
+
+    #[test]
+    fn remove_thumbnails_of_one_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let thumbnailer = Thumbnailer::build(dir.path());
+
+        let path = FlatpakPathBuf::build("/photos/a.jpg", "/photos/a.jpg");
+        let hash = path.thumbnail_hash();
+        let other_hash = compute_hash_for_path(Path::new("/photos/b.jpg"));
+
+        let paths = [
+            thumbnailer.get_thumbnail_hash_output(&hash, ThumbnailSize::Small),
+            thumbnailer.get_thumbnail_hash_output(&hash, ThumbnailSize::XLarge),
+            file::get_failed_thumbnail_output(dir.path(), &hash),
+            file::get_evicted_thumbnail_output(dir.path(), &hash),
+        ];
+        let other_path = thumbnailer.get_thumbnail_hash_output(&other_hash, ThumbnailSize::XLarge);
+
+        for path in paths.iter().chain([&other_path]) {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, b"fotema").unwrap();
+        }
+
+        thumbnailer.remove_thumbnails(&path).unwrap();
+
+        assert!(paths.iter().all(|path| !path.exists()));
+        assert!(other_path.exists());
+
+        // Nothing to remove isn't an error.
+        assert!(thumbnailer.remove_thumbnails(&path).is_ok());
+    }
+
+    #[test]
+    fn redacted_thumbnail_is_blurred_copy() {
+        let dir = tempfile::tempdir().unwrap();
+        let thumbnailer = Thumbnailer::build(dir.path());
+
+        let hash = compute_hash_for_path(Path::new("/photos/a.jpg"));
+        let small = thumbnailer.get_thumbnail_hash_output(&hash, ThumbnailSize::Small);
+        let data: Vec<u8> = (0..64 * 64 * 4).map(|i| (i % 251) as u8).collect();
+        file::write_png(&small, 64, 64, &HashMap::new(), &data).unwrap();
+
+        // Clones share what is redacted.
+        thumbnailer
+            .clone()
+            .set_redacted(HashSet::from([hash.clone()]));
+        assert!(thumbnailer.is_redacted(&hash));
+
+        let redacted = thumbnailer
+            .nearest_thumbnail(&hash, ThumbnailSize::Large)
+            .unwrap();
+        assert_eq!(
+            file::get_redacted_thumbnail_hash_output(dir.path(), &hash),
+            redacted
+        );
+        assert_ne!(
+            std::fs::read(&small).unwrap(),
+            std::fs::read(&redacted).unwrap()
+        );
+
+        thumbnailer.set_redacted(HashSet::new());
+        assert_eq!(
+            Some(small),
+            thumbnailer.nearest_thumbnail(&hash, ThumbnailSize::Small)
+        );
+    }
+
+    #[test]
+    fn relocate_thumbnails_beside_pictures() {
+        let dir = tempfile::tempdir().unwrap();
+        let thumbnails_path = dir.path().join("thumbnails");
+        let thumbnailer = Thumbnailer::build(&thumbnails_path);
+
+        let folder = dir.path().join("photos");
+        std::fs::create_dir(&folder).unwrap();
+        let path = FlatpakPathBuf::build(folder.join("a.jpg"), folder.join("a.jpg"));
+        let hash = path.thumbnail_hash();
+
+        let central = thumbnailer.get_thumbnail_hash_output(&hash, ThumbnailSize::Small);
+        std::fs::create_dir_all(central.parent().unwrap()).unwrap();
+        std::fs::write(&central, b"fotema").unwrap();
+
+        thumbnailer.set_location(ThumbnailLocation::Sidecar);
+        thumbnailer
+            .relocate_thumbnails(&path, ThumbnailLocation::Central)
+            .unwrap();
+
+        let sidecar = folder
+            .join(location::SIDECAR_DIR_NAME)
+            .join("small")
+            .join(format!("{}.png", hash));
+        assert!(!central.exists());
+        assert_eq!(
+            Some(sidecar),
+            thumbnailer.nearest_thumbnail(&hash, ThumbnailSize::Large)
+        );
+
+        thumbnailer.set_location(ThumbnailLocation::Central);
+        thumbnailer
+            .relocate_thumbnails(&path, ThumbnailLocation::Sidecar)
+            .unwrap();
+        thumbnailer.remove_empty_sidecars();
+
+        assert_eq!(
+            Some(central),
+            thumbnailer.nearest_thumbnail(&hash, ThumbnailSize::Large)
+        );
+        assert!(!folder.join(location::SIDECAR_DIR_NAME).exists());
+    }
+
+    #[test]
+    fn read_only_folder_falls_back_to_central() {
+        let dir = tempfile::tempdir().unwrap();
+        let thumbnailer = Thumbnailer::build(dir.path());
+        thumbnailer.set_location(ThumbnailLocation::Sidecar);
+
+        // A directory can't be created beneath a file.
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let path = FlatpakPathBuf::build(file.path().join("a.jpg"), file.path().join("a.jpg"));
+
+        assert_eq!(dir.path(), thumbnailer.add_thumbnail(&path));
+    }
+
+    #[test]
+    fn move_thumbnails_of_moved_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let thumbnailer = Thumbnailer::build(dir.path());
+
+        let from_hash = compute_hash_for_path(Path::new("/photos/a.jpg"));
+        let to_hash = compute_hash_for_path(Path::new("/photos/holiday/a.jpg"));
+
+        let from_path = thumbnailer.get_thumbnail_hash_output(&from_hash, ThumbnailSize::XLarge);
+        std::fs::create_dir_all(from_path.parent().unwrap()).unwrap();
+        std::fs::write(&from_path, b"fotema").unwrap();
+
+        file::move_thumbnails(dir.path(), &from_hash, &to_hash).unwrap();
+
+        let to_path = thumbnailer.get_thumbnail_hash_output(&to_hash, ThumbnailSize::XLarge);
+        assert!(!from_path.exists());
+        assert_eq!(b"fotema".as_slice(), std::fs::read(&to_path).unwrap());
+    }
+
+    /// Writes a file, and thumbnails of every shared size as though generated from it.
+    fn write_picture(thumbnailer: &Thumbnailer, path: &Path, contents: &[u8]) -> FlatpakPathBuf {
+        std::fs::write(path, contents).unwrap();
+        let path = FlatpakPathBuf::build(path, path);
+
+        let labels = file::thumbnail_labels(&path).unwrap();
+        let data = vec![0; 8 * 8 * 4];
+        for size in SHARED_SIZES {
+            let thumb_path = thumbnailer.get_thumbnail_path(&path.host_path, size);
+            file::write_png(&thumb_path, 8, 8, &labels, &data).unwrap();
+        }
+        path
+    }
+
+    #[test]
+    fn copies_share_thumbnails() {
+        let dir = tempfile::tempdir().unwrap();
+        let thumbnails_path = dir.path().join("thumbnails");
+        let thumbnailer = Thumbnailer::build(&thumbnails_path);
+
+        let original = write_picture(&thumbnailer, &dir.path().join("a.jpg"), b"picture");
+        thumbnailer.share_thumbnails(&original, "content").unwrap();
+
+        // A copy modified at the same time can be the same file as the original.
+        let copy = dir.path().join("b.jpg");
+        std::fs::copy(&original.sandbox_path, &copy).unwrap();
+        let modified = std::fs::metadata(&original.sandbox_path)
+            .unwrap()
+            .modified()
+            .unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&copy)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        let copy = FlatpakPathBuf::build(&copy, &copy);
+
+        let reused = thumbnailer.reuse_thumbnails(&copy, "content").unwrap();
+        assert_eq!(
+            Some(thumbnailer.get_thumbnail_path(&copy.host_path, ThumbnailSize::XLarge)),
+            reused
+        );
+        assert!(thumbnailer.is_thumbnail_up_to_date(&copy, ThumbnailSize::XLarge));
+
+        // Shared thumbnails are kept while any file uses them.
+        thumbnailer.remove_thumbnails(&original).unwrap();
+        assert_eq!(
+            0,
+            file::remove_unshared_thumbnails(&thumbnails_path).unwrap()
+        );
+
+        thumbnailer.remove_thumbnails(&copy).unwrap();
+        assert_eq!(
+            4,
+            file::remove_unshared_thumbnails(&thumbnails_path).unwrap()
+        );
+    }
+
+    #[test]
+    fn thumbnails_of_another_file_are_not_reused() {
+        let dir = tempfile::tempdir().unwrap();
+        let thumbnailer = Thumbnailer::build(&dir.path().join("thumbnails"));
+
+        let original = write_picture(&thumbnailer, &dir.path().join("a.jpg"), b"picture");
+        thumbnailer.share_thumbnails(&original, "content").unwrap();
+
+        // Same hash, but not the same size, so not the same contents.
+        let other = dir.path().join("b.jpg");
+        std::fs::write(&other, b"another picture").unwrap();
+        let other = FlatpakPathBuf::build(&other, &other);
+
+        assert_eq!(
+            None,
+            thumbnailer.reuse_thumbnails(&other, "content").unwrap()
+        );
+        assert!(!thumbnailer.is_thumbnail_up_to_date(&other, ThumbnailSize::XLarge));
     }
 }
@@ -4,11 +4,9 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use std::collections::HashMap;
-use std::io::{BufReader, BufWriter};
+use std::io::BufReader;
 use std::{
-    fs,
     fs::File,
-    io,
     path::{Path, PathBuf},
     time::UNIX_EPOCH,
 };
@@ -18,7 +16,10 @@ use tracing::{debug, info};
 use crate::FlatpakPathBuf;
 use crate::thumbnailify::{
     error::ThumbnailError,
-    file::{get_failed_thumbnail_output, get_file_uri, get_thumbnail_hash_output},
+    file::{
+        get_failed_thumbnail_output, get_file_uri, get_thumbnail_hash_output, is_thumbnail_intact,
+        thumbnail_labels, write_png,
+    },
     hash::compute_hash,
     sizes::ThumbnailSize,
 };
@@ -31,10 +32,6 @@ use fast_image_resize as fr;
 use fr::images::Image;
 use fr::{ResizeOptions, Resizer};
 
-use png::Encoder as ExtendedPngEncoder;
-
-use tempfile;
-
 /// Checks whether the thumbnail file at `thumb_path` is up to date with respect
 /// to the source image at `source_path`. It verifies two metadata fields in the PNG:
 ///
@@ -43,10 +40,10 @@ use tempfile;
 ///
 /// Returns true if "Thumb::MTime" is present and matches the source file's modification time,
 /// and if "Thumb::Size" is present it must match the source file's size.
-pub fn is_thumbnail_up_to_date(thumb_path: &Path, host_path: &Path) -> bool {
+pub fn is_thumbnail_up_to_date(thumb_path: &Path, source_path: &Path) -> bool {
     debug!(
         "Checking if thumbnail at {:?} is up-to-date with source {:?}",
-        thumb_path, host_path
+        thumb_path, source_path
     );
 
     let file = match File::open(thumb_path) {
@@ -78,10 +75,10 @@ pub fn is_thumbnail_up_to_date(thumb_path: &Path, host_path: &Path) -> bool {
     };
     let thumb_mtime = thumb_mtime_str.parse::<u64>().unwrap_or(0);
 
-    let source_metadata = match std::fs::metadata(host_path) {
+    let source_metadata = match std::fs::metadata(source_path) {
         Ok(m) => m,
         Err(e) => {
-            debug!("Failed to get metadata of source {:?}: {}", host_path, e);
+            debug!("Failed to get metadata of source {:?}: {}", source_path, e);
             return false;
         }
     };
@@ -91,7 +88,7 @@ pub fn is_thumbnail_up_to_date(thumb_path: &Path, host_path: &Path) -> bool {
         Err(e) => {
             debug!(
                 "Failed to read modified time of source {:?}: {}",
-                host_path, e
+                source_path, e
             );
             return false;
         }
@@ -119,7 +116,7 @@ pub fn is_thumbnail_up_to_date(thumb_path: &Path, host_path: &Path) -> bool {
 
     debug!(
         "Thumbnail at {:?} is up-to-date with source {:?}",
-        thumb_path, host_path
+        thumb_path, source_path
     );
     true
 }
@@ -128,24 +125,7 @@ pub fn generate_all_thumbnails(
     path: &FlatpakPathBuf,
     src_image: DynamicImage,
 ) -> Result<(), ThumbnailError> {
-    let mut labels: HashMap<String, String> = HashMap::with_capacity(3);
-    // FIXME hard-coded app-id
-    labels.insert("Software".into(), "app.fotema.Fotema".into());
-
-    let uri = get_file_uri(&path.host_path)?;
-    labels.insert("Thumb::URI".into(), uri);
-
-    let metadata = std::fs::metadata(&path.sandbox_path)?;
-    let size = metadata.len();
-    labels.insert("Thumb::Size".into(), size.to_string());
-
-    let modified_time = metadata.modified()?;
-    let mtime_unix = modified_time
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs();
-
-    labels.insert("Thumb::MTime".into(), mtime_unix.to_string());
+    let labels = thumbnail_labels(path)?;
 
     let sizes = &[
         ThumbnailSize::XLarge,
@@ -222,8 +202,11 @@ fn generate_thumbnail_recursive(
     // Determine the expected output thumbnail path.
     let thumb_path = get_thumbnail_hash_output(thumbnails_base_dir, &hash, size);
 
-    // If the thumbnail already exists and is up to date, return it immediately.
-    if thumb_path.exists() && is_thumbnail_up_to_date(&thumb_path, &path.host_path) {
+    // If the thumbnail already exists, is intact, and is up to date, return it immediately.
+    if thumb_path.exists()
+        && is_thumbnail_intact(&thumb_path, size)
+        && is_thumbnail_up_to_date(&thumb_path, &path.sandbox_path)
+    {
         info!(
             "Cached thumbnail at {:?} is up-to-date, returning it",
             thumb_path
@@ -284,32 +267,17 @@ pub fn generate_thumbnail(
     // Determine the expected output thumbnail path.
     let thumb_path = get_thumbnail_hash_output(thumbnails_base_dir, &hash, size);
 
-    // If the thumbnail already exists and is up to date, return it immediately.
-    if thumb_path.exists() && is_thumbnail_up_to_date(&thumb_path, &path.host_path) {
+    // If the thumbnail already exists, is intact, and is up to date, return it immediately.
+    if thumb_path.exists()
+        && is_thumbnail_intact(&thumb_path, size)
+        && is_thumbnail_up_to_date(&thumb_path, &path.sandbox_path)
+    {
         info!(
             "Cached thumbnail at {:?} is up-to-date, returning it",
             thumb_path
         );
         return Ok(thumb_path);
     }
-    // Prepare a temporary file in the same directory as the final thumbnail.
-    // Using `tempfile_in` ensures that the temp file is on the same filesystem
-    // so that we can atomically persist (rename) it.
-    let thumb_dir = thumb_path.parent().ok_or_else(|| {
-        io::Error::new(
-            io::ErrorKind::Other,
-            "Thumbnail path has no parent directory",
-        )
-    })?;
-
-    fs::create_dir_all(thumb_dir)?;
-
-    let named_temp = tempfile::Builder::new()
-        .prefix("thumb-")
-        .suffix(".png.tmp")
-        .tempfile_in(thumb_dir)?;
-
-    let temp_path = named_temp.path().to_owned();
 
     let dimension = size.to_dimension() as f32;
 
@@ -326,40 +294,10 @@ pub fn generate_thumbnail(
 
     let dst_image = resize(src_image, dst_width, dst_height)?;
 
-    let file = std::fs::File::create(&temp_path)?;
-    let file = BufWriter::new(file);
-
-    let mut encoder = ExtendedPngEncoder::new(file, dst_width, dst_height);
-    encoder.set_color(png::ColorType::Rgba);
-    encoder.set_depth(png::BitDepth::Eight);
-
-    // FIXME hard-coded app-id
-    encoder.add_text_chunk("Software".to_string(), "app.fotema.Fotema".to_string())?;
-
-    let uri = get_file_uri(&path.host_path)?;
-    encoder.add_text_chunk("Thumb::URI".to_string(), uri)?;
-
-    let metadata = std::fs::metadata(&path.sandbox_path)?;
-
-    let size = metadata.len();
-    encoder.add_text_chunk("Thumb::Size".to_string(), size.to_string())?;
-
-    let modified_time = metadata.modified()?;
-    let mtime_unix = modified_time
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs();
-    encoder.add_text_chunk("Thumb::MTime".to_string(), mtime_unix.to_string())?;
-
     // TODO image width/height, video duration.
     // See https://specifications.freedesktop.org/thumbnail-spec/latest/creation.html
-
-    // Write out the PNG header
-    let mut writer = encoder.write_header()?;
-    writer.write_image_data(&dst_image.buffer())?;
-    drop(writer); // flush
-
-    named_temp.persist(&thumb_path)?;
+    let labels = thumbnail_labels(path)?;
+    write_thumbnail(&thumb_path, &dst_image, &labels)?;
 
     return Ok(thumb_path.into());
 }
@@ -448,41 +386,11 @@ fn write_thumbnail(
     thumbnail: &Image<'static>,
     labels: &HashMap<String, String>,
 ) -> Result<(), ThumbnailError> {
-    // Prepare a temporary file in the same directory as the final thumbnail.
-    // Using `tempfile_in` ensures that the temp file is on the same filesystem
-    // so that we can atomically persist (rename) it.
-    let thumb_dir = thumb_path.parent().ok_or_else(|| {
-        io::Error::new(
-            io::ErrorKind::Other,
-            "Thumbnail path has no parent directory",
-        )
-    })?;
-
-    fs::create_dir_all(thumb_dir)?;
-
-    let named_temp = tempfile::Builder::new()
-        .prefix("thumb-")
-        .suffix(".png.tmp")
-        .tempfile_in(thumb_dir)?;
-
-    let temp_path = named_temp.path().to_owned();
-
-    let file = std::fs::File::create(&temp_path)?;
-    let file = BufWriter::new(file);
-
-    let mut encoder = ExtendedPngEncoder::new(file, thumbnail.width(), thumbnail.height());
-    encoder.set_color(png::ColorType::Rgba);
-    encoder.set_depth(png::BitDepth::Eight);
-
-    for (key, value) in labels.iter() {
-        encoder.add_text_chunk(key.into(), value.into())?;
-    }
-
-    // Write out the PNG header
-    let mut writer = encoder.write_header()?;
-    writer.write_image_data(&thumbnail.buffer())?;
-    drop(writer); // flush
-
-    named_temp.persist(&thumb_path)?;
-    Ok(())
+    write_png(
+        thumb_path,
+        thumbnail.width(),
+        thumbnail.height(),
+        labels,
+        thumbnail.buffer(),
+    )
 }
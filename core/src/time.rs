@@ -2,7 +2,7 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use chrono::{Month, TimeDelta};
+use chrono::{DateTime, Datelike, Month, NaiveDate, NaiveDateTime, TimeDelta, TimeZone, Utc};
 use std::fmt::Display;
 
 pub type Year = i32;
@@ -38,10 +38,99 @@ pub fn format_hhmmss(delta: &TimeDelta) -> String {
     }
 }
 
+/// Is the date on the month and day, in any year?
+/// February 28th counts as February 29th in years without a leap day,
+/// so pictures taken on a leap day still come up every year.
+pub fn is_on_this_day(date: NaiveDate, month: u32, day: u32) -> bool {
+    if date.month() == month && date.day() == day {
+        return true;
+    }
+
+    let is_leap_year = NaiveDate::from_ymd_opt(date.year(), 2, 29).is_some();
+    month == 2 && day == 29 && date.month() == 2 && date.day() == 28 && !is_leap_year
+}
+
+/// Converts a local date and time, such as a date picked from a calendar, to UTC.
+/// A time that happens twice, when clocks go back, is the first of the two. A time
+/// that never happens, when clocks go forward, is the first moment after the gap,
+/// so that a range from the start of a day begins when the day actually begins.
+pub fn local_to_utc<Tz: TimeZone>(date_time: NaiveDateTime, tz: &Tz) -> Option<DateTime<Utc>> {
+    // Gaps are usually an hour, but a few time zones have skipped a whole day.
+    let step = TimeDelta::try_minutes(15)?;
+    let mut candidate = date_time;
+    for _ in 0..=(4 * 48) {
+        if let Some(local) = tz.from_local_datetime(&candidate).earliest() {
+            return Some(local.with_timezone(&Utc));
+        }
+        candidate = candidate.checked_add_signed(step)?;
+    }
+    None
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn on_this_day_matches_any_year() {
+        let date = NaiveDate::from_ymd_opt(2019, 7, 14).unwrap();
+        assert!(is_on_this_day(date, 7, 14));
+        assert!(!is_on_this_day(date, 7, 15));
+        assert!(!is_on_this_day(date, 8, 14));
+    }
+
+    #[test]
+    fn on_this_day_leap_day() {
+        let leap_day = NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
+        assert!(is_on_this_day(leap_day, 2, 29));
+        assert!(!is_on_this_day(leap_day, 2, 28));
+
+        // Feb 28th stands in for Feb 29th, but only in years without a leap day.
+        let feb_28_common_year = NaiveDate::from_ymd_opt(2023, 2, 28).unwrap();
+        assert!(is_on_this_day(feb_28_common_year, 2, 29));
+        assert!(is_on_this_day(feb_28_common_year, 2, 28));
+
+        let feb_28_leap_year = NaiveDate::from_ymd_opt(2024, 2, 28).unwrap();
+        assert!(!is_on_this_day(feb_28_leap_year, 2, 29));
+    }
+
+    #[test]
+    fn on_this_day_invalid_day() {
+        let date = NaiveDate::from_ymd_opt(2023, 2, 28).unwrap();
+        assert!(!is_on_this_day(date, 2, 30));
+        assert!(!is_on_this_day(date, 13, 28));
+    }
+
+    #[test]
+    fn local_midnight_to_utc() {
+        let midnight = NaiveDate::from_ymd_opt(2024, 7, 1)
+            .and_then(|date| date.and_hms_opt(0, 0, 0))
+            .unwrap();
+        let utc = local_to_utc(midnight, &chrono_tz::Europe::Paris).unwrap();
+        assert_eq!("2024-06-30T22:00:00+00:00", utc.to_rfc3339());
+    }
+
+    #[test]
+    fn local_midnight_in_dst_gap_is_first_moment_of_day() {
+        // Clocks in São Paulo went forward from midnight to 1am on 4th November 2018,
+        // so that day's midnight never happened.
+        let midnight = NaiveDate::from_ymd_opt(2018, 11, 4)
+            .and_then(|date| date.and_hms_opt(0, 0, 0))
+            .unwrap();
+        let utc = local_to_utc(midnight, &chrono_tz::America::Sao_Paulo).unwrap();
+        assert_eq!("2018-11-04T03:00:00+00:00", utc.to_rfc3339());
+    }
+
+    #[test]
+    fn repeated_local_time_is_earliest() {
+        // Clocks in Paris went back from 3am to 2am on 27th October 2024.
+        let repeated = NaiveDate::from_ymd_opt(2024, 10, 27)
+            .and_then(|date| date.and_hms_opt(2, 30, 0))
+            .unwrap();
+        let utc = local_to_utc(repeated, &chrono_tz::Europe::Paris).unwrap();
+        assert_eq!("2024-10-27T00:30:00+00:00", utc.to_rfc3339());
+    }
+
     #[test]
     fn test_timedelta_format() {
         let one_sec = TimeDelta::try_seconds(1).unwrap();
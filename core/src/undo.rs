@@ -0,0 +1,246 @@
+// SPDX-FileCopyrightText: © 2025 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Undoing destructive actions, such as trashing pictures or merging people.
+//!
+//! Each undoable action records how to reverse it once it has been done.
+//! Only the most recent actions are kept, and only for as long as Fotema is running.
+//! Actions that can't be reversed cleanly, such as emptying the trash, aren't recorded.
+
+use crate::database;
+use crate::people::MergedPeople;
+use crate::photo::model::PictureId;
+
+use anyhow::*;
+use rusqlite::params;
+use std::collections::VecDeque;
+use std::result::Result::Ok;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use tracing::info;
+
+/// Maximum number of actions that can be undone.
+pub const MAX_UNDO: usize = 20;
+
+/// How to reverse an action.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Undo {
+    /// Restore pictures that were moved to the trash.
+    Restore(Vec<PictureId>),
+
+    /// Move pictures back to the trash after they were restored.
+    Trash(Vec<PictureId>),
+
+    /// Separate a person from the person they were merged into.
+    Unmerge(MergedPeople),
+}
+
+/// Most recent undoable actions.
+/// Repository is backed by a Sqlite database.
+#[derive(Debug, Clone)]
+pub struct Repository {
+    /// Connection to backing Sqlite database.
+    con: Arc<Mutex<rusqlite::Connection>>,
+
+    /// Newest action last. Shared between clones of the repository.
+    stack: Arc<Mutex<VecDeque<Undo>>>,
+}
+
+impl Repository {
+    pub fn open(con: Arc<Mutex<rusqlite::Connection>>) -> Result<Repository> {
+        Ok(Repository {
+            con,
+            stack: Arc::new(Mutex::new(VecDeque::new())),
+        })
+    }
+
+    /// Records how to reverse an action that has just been done.
+    /// The oldest action is forgotten once there are more than `MAX_UNDO`.
+    pub fn push(&self, undo: Undo) {
+        let mut stack = self.stack.lock().unwrap_or_else(PoisonError::into_inner);
+        stack.push_back(undo);
+        while stack.len() > MAX_UNDO {
+            stack.pop_front();
+        }
+    }
+
+    /// Is there anything to undo?
+    pub fn can_undo(&self) -> bool {
+        let stack = self.stack.lock().unwrap_or_else(PoisonError::into_inner);
+        !stack.is_empty()
+    }
+
+    /// Forgets every action, such as after trashed pictures have been deleted.
+    pub fn clear(&self) {
+        let mut stack = self.stack.lock().unwrap_or_else(PoisonError::into_inner);
+        stack.clear();
+    }
+
+    /// Reverses the most recent action in a single transaction.
+    /// Returns the reversed action, or None if there was nothing to undo.
+    /// An action that fails to reverse is forgotten rather than tried again.
+    pub fn undo_last(&mut self) -> Result<Option<Undo>> {
+        let undo = {
+            let mut stack = self.stack.lock().unwrap_or_else(PoisonError::into_inner);
+            stack.pop_back()
+        };
+
+        let Some(undo) = undo else {
+            return Ok(None);
+        };
+
+        info!("Undoing {:?}", undo);
+
+        let mut con = database::lock(&self.con);
+        let tx = con.transaction()?;
+
+        match &undo {
+            Undo::Restore(picture_ids) => {
+                let mut stmt = tx.prepare_cached(
+                    "UPDATE pictures
+                    SET
+                        trashed_at = NULL
+                    WHERE picture_id = ?1",
+                )?;
+
+                for picture_id in picture_ids {
+                    stmt.execute(params![picture_id])?;
+                }
+            }
+            Undo::Trash(picture_ids) => {
+                let mut stmt = tx.prepare_cached(
+                    "UPDATE pictures
+                    SET
+                        trashed_at = CURRENT_TIMESTAMP
+                    WHERE picture_id = ?1
+                    AND trashed_at IS NULL",
+                )?;
+
+                for picture_id in picture_ids {
+                    stmt.execute(params![picture_id])?;
+                }
+            }
+            Undo::Unmerge(merged) => {
+                let mut stmt = tx.prepare_cached(
+                    "INSERT INTO people (
+                        person_id,
+                        name,
+                        is_ignored,
+                        recognized_at
+                    ) VALUES (
+                        ?1, ?2, ?3, ?4
+                    )",
+                )?;
+                stmt.execute(params![
+                    merged.absorbed,
+                    merged.absorbed_name,
+                    merged.absorbed_is_ignored,
+                    merged.absorbed_recognized_at,
+                ])?;
+
+                // Faces moved on to yet another person since the merge stay where they are.
+                let mut stmt = tx.prepare_cached(
+                    "UPDATE pictures_faces
+                    SET
+                        person_id = ?2,
+                        is_thumbnail = ?3
+                    WHERE face_id = ?1
+                    AND person_id = ?4",
+                )?;
+
+                for (face_id, is_thumbnail) in &merged.faces {
+                    stmt.execute(params![face_id, merged.absorbed, is_thumbnail, merged.keep])?;
+                }
+
+                let mut stmt = tx.prepare_cached(
+                    "UPDATE people
+                    SET
+                        recognized_at = ?2
+                    WHERE person_id = ?1",
+                )?;
+                stmt.execute(params![merged.keep, merged.keep_recognized_at])?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(Some(undo))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::photo;
+    use crate::{FlatpakPathBuf, LibraryRoots, ScannedFile};
+    use std::path::{Path, PathBuf};
+
+    fn open() -> (Repository, photo::Repository) {
+        let con = Arc::new(Mutex::new(database::setup_in_memory().unwrap()));
+        let library_roots = LibraryRoots::build(&FlatpakPathBuf::build("/", "/"), &[]);
+        let photo_repo =
+            photo::Repository::open(&library_roots, Path::new("/"), Path::new("/"), con.clone())
+                .unwrap();
+        let undo_repo = Repository::open(con).unwrap();
+        (undo_repo, photo_repo)
+    }
+
+    #[test]
+    fn nothing_to_undo() {
+        let (mut undo_repo, _) = open();
+        assert!(!undo_repo.can_undo());
+        assert_eq!(None, undo_repo.undo_last().unwrap());
+    }
+
+    #[test]
+    fn undo_trash() {
+        let (mut undo_repo, mut photo_repo) = open();
+        photo_repo
+            .add_all(&vec![ScannedFile::Photo(PathBuf::from("/a.jpg"))])
+            .unwrap();
+        let picture_id = photo_repo.all().unwrap()[0].picture_id;
+
+        assert_eq!(vec![picture_id], photo_repo.trash(&[picture_id]).unwrap());
+        undo_repo.push(Undo::Restore(vec![picture_id]));
+        assert!(photo_repo.all().unwrap().is_empty());
+
+        assert_eq!(
+            Some(Undo::Restore(vec![picture_id])),
+            undo_repo.undo_last().unwrap()
+        );
+        assert_eq!(1, photo_repo.all().unwrap().len());
+        assert!(!undo_repo.can_undo());
+    }
+
+    #[test]
+    fn undo_most_recent_first() {
+        let (mut undo_repo, _) = open();
+        undo_repo.push(Undo::Restore(vec![PictureId::new(1)]));
+        undo_repo.push(Undo::Trash(vec![PictureId::new(2)]));
+
+        assert_eq!(
+            Some(Undo::Trash(vec![PictureId::new(2)])),
+            undo_repo.undo_last().unwrap()
+        );
+        assert_eq!(
+            Some(Undo::Restore(vec![PictureId::new(1)])),
+            undo_repo.undo_last().unwrap()
+        );
+    }
+
+    #[test]
+    fn oldest_action_is_forgotten() {
+        let (mut undo_repo, _) = open();
+        for id in 0..=MAX_UNDO as i64 {
+            undo_repo.push(Undo::Trash(vec![PictureId::new(id)]));
+        }
+
+        let mut undone = Vec::new();
+        while let Some(undo) = undo_repo.undo_last().unwrap() {
+            undone.push(undo);
+        }
+
+        assert_eq!(MAX_UNDO, undone.len());
+        assert_eq!(Some(&Undo::Trash(vec![PictureId::new(1)])), undone.last());
+    }
+}
@@ -0,0 +1,484 @@
+// SPDX-FileCopyrightText: © 2025 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Albums that users make and fill with pictures themselves, beyond folders and people.
+//!
+//! Pictures in an album are in an order the user arranges by hand, which is kept when
+//! the library is rescanned. Album names are matched like tag names, ignoring case and
+//! surrounding white space.
+
+use crate::database;
+use crate::photo::model::PictureId;
+use crate::tag;
+
+use anyhow::*;
+use rusqlite::OptionalExtension;
+use rusqlite::params;
+use std::collections::HashMap;
+use std::result::Result::Ok;
+use std::sync::{Arc, Mutex};
+
+/// Database ID of a user album
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct UserAlbumId(i64);
+
+impl UserAlbumId {
+    pub fn new(id: i64) -> Self {
+        Self(id)
+    }
+
+    pub fn id(&self) -> i64 {
+        self.0
+    }
+}
+
+impl std::fmt::Display for UserAlbumId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A user album, for listing albums.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserAlbum {
+    pub album_id: UserAlbumId,
+
+    pub name: String,
+
+    /// Picture shown for the album. The picture the user chose, otherwise the first
+    /// picture in the album. None if the album is empty.
+    pub cover: Option<PictureId>,
+
+    /// Number of pictures in the album, except trashed pictures.
+    pub len: usize,
+}
+
+/// How a picture belongs to a user album.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub album_id: UserAlbumId,
+
+    /// Name of the album, for showing which albums a picture is in.
+    pub name: String,
+
+    /// Position of the picture in the album's order. Positions only order the
+    /// pictures of an album, so may have gaps.
+    pub position: i64,
+
+    /// Has the user chosen the picture as the cover of the album?
+    pub is_cover: bool,
+}
+
+/// User albums and the pictures in them.
+/// Repository is backed by a Sqlite database.
+#[derive(Debug, Clone)]
+pub struct Repository {
+    /// Connection to backing Sqlite database.
+    con: Arc<Mutex<rusqlite::Connection>>,
+}
+
+impl Repository {
+    pub fn open(con: Arc<Mutex<rusqlite::Connection>>) -> Result<Repository> {
+        Ok(Repository { con })
+    }
+
+    /// Creates an empty album. If there is already an album with the name, then that
+    /// album is returned instead.
+    pub fn create_album(&mut self, name: &str) -> Result<UserAlbumId> {
+        let Some((name, folded_name)) = tag::normalize(name) else {
+            bail!("Album name is empty");
+        };
+
+        let con = database::lock(&self.con);
+        con.execute(
+            "INSERT INTO albums (name, folded_name) VALUES (?1, ?2)
+            ON CONFLICT (folded_name) DO NOTHING",
+            params![name, folded_name],
+        )?;
+
+        let album_id = con.query_row(
+            "SELECT album_id FROM albums WHERE folded_name = ?1",
+            params![folded_name],
+            |row| row.get(0).map(UserAlbumId::new),
+        )?;
+
+        Ok(album_id)
+    }
+
+    /// Deletes an album. The pictures in it are kept.
+    /// Returns false if there was no such album.
+    pub fn delete_album(&mut self, album_id: &UserAlbumId) -> Result<bool> {
+        let mut con = database::lock(&self.con);
+        let tx = con.transaction()?;
+
+        tx.execute(
+            "DELETE FROM albums_pictures WHERE album_id = ?1",
+            params![album_id.id()],
+        )?;
+        let deleted = tx.execute(
+            "DELETE FROM albums WHERE album_id = ?1",
+            params![album_id.id()],
+        )?;
+
+        tx.commit()?;
+        Ok(deleted > 0)
+    }
+
+    /// Adds pictures to the end of an album, in the order given.
+    /// Pictures already in the album keep their place.
+    pub fn add_to_album(
+        &mut self,
+        album_id: &UserAlbumId,
+        picture_ids: &[PictureId],
+    ) -> Result<()> {
+        let mut con = database::lock(&self.con);
+        let tx = con.transaction()?;
+
+        {
+            let mut stmt = tx.prepare_cached(
+                "INSERT INTO albums_pictures (album_id, picture_id, sort_index)
+                SELECT ?1, ?2, COALESCE(MAX(sort_index) + 1, 0)
+                FROM albums_pictures
+                WHERE album_id = ?1
+                ON CONFLICT DO NOTHING",
+            )?;
+
+            for picture_id in picture_ids {
+                stmt.execute(params![album_id.id(), picture_id])?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Removes pictures from an album. The pictures are kept in the library.
+    /// A removed picture that was the album's cover stops being the cover.
+    pub fn remove_from_album(
+        &mut self,
+        album_id: &UserAlbumId,
+        picture_ids: &[PictureId],
+    ) -> Result<()> {
+        let mut con = database::lock(&self.con);
+        let tx = con.transaction()?;
+
+        {
+            let mut remove_stmt = tx.prepare_cached(
+                "DELETE FROM albums_pictures WHERE album_id = ?1 AND picture_id = ?2",
+            )?;
+            let mut cover_stmt = tx.prepare_cached(
+                "UPDATE albums SET cover_picture_id = NULL
+                WHERE album_id = ?1 AND cover_picture_id = ?2",
+            )?;
+
+            for picture_id in picture_ids {
+                remove_stmt.execute(params![album_id.id(), picture_id])?;
+                cover_stmt.execute(params![album_id.id(), picture_id])?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Arranges the pictures of an album in the order given.
+    /// Pictures in the album that aren't given, such as trashed pictures that weren't
+    /// shown, follow in their current order. Pictures not in the album are ignored.
+    pub fn set_album_order(&mut self, album_id: &UserAlbumId, order: Vec<PictureId>) -> Result<()> {
+        let mut con = database::lock(&self.con);
+        let tx = con.transaction()?;
+
+        let current: Vec<PictureId> = {
+            let mut stmt = tx.prepare(
+                "SELECT picture_id FROM albums_pictures
+                WHERE album_id = ?1
+                ORDER BY sort_index ASC",
+            )?;
+            stmt.query_map(params![album_id.id()], |row| row.get(0).map(PictureId::new))?
+                .flatten()
+                .collect()
+        };
+
+        let mut arranged: Vec<PictureId> = Vec::with_capacity(current.len());
+        for picture_id in order.into_iter().chain(current.iter().copied()) {
+            if current.contains(&picture_id) && !arranged.contains(&picture_id) {
+                arranged.push(picture_id);
+            }
+        }
+
+        {
+            let mut stmt = tx.prepare_cached(
+                "UPDATE albums_pictures SET sort_index = ?3
+                WHERE album_id = ?1 AND picture_id = ?2",
+            )?;
+
+            for (index, picture_id) in arranged.iter().enumerate() {
+                stmt.execute(params![album_id.id(), picture_id, index as i64])?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Chooses a picture in an album to show for the album, in place of its first picture.
+    pub fn set_album_cover(
+        &mut self,
+        album_id: &UserAlbumId,
+        picture_id: &PictureId,
+    ) -> Result<()> {
+        let con = database::lock(&self.con);
+
+        let is_member: Option<i64> = con
+            .query_row(
+                "SELECT 1 FROM albums_pictures WHERE album_id = ?1 AND picture_id = ?2",
+                params![album_id.id(), picture_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        if is_member.is_none() {
+            bail!("Picture {} isn't in album {}", picture_id, album_id);
+        }
+
+        con.execute(
+            "UPDATE albums SET cover_picture_id = ?2 WHERE album_id = ?1",
+            params![album_id.id(), picture_id],
+        )?;
+
+        Ok(())
+    }
+
+    /// Every album, in alphabetical order.
+    pub fn albums(&self) -> Result<Vec<UserAlbum>> {
+        let con = database::lock(&self.con);
+        let mut stmt = con.prepare(
+            "SELECT album_id, name, cover_picture_id
+            FROM albums
+            ORDER BY folded_name ASC",
+        )?;
+
+        let albums: Vec<(UserAlbumId, String, Option<PictureId>)> = stmt
+            .query_map([], |row| {
+                Ok((
+                    UserAlbumId::new(row.get(0)?),
+                    row.get(1)?,
+                    row.get::<_, Option<i64>>(2)?.map(PictureId::new),
+                ))
+            })?
+            .flatten()
+            .collect();
+
+        let mut result = Vec::with_capacity(albums.len());
+        for (album_id, name, chosen_cover) in albums {
+            let picture_ids = pictures_in_album(&con, &album_id)?;
+            let cover = chosen_cover
+                .filter(|cover| picture_ids.contains(cover))
+                .or_else(|| picture_ids.first().copied());
+
+            result.push(UserAlbum {
+                album_id,
+                name,
+                cover,
+                len: picture_ids.len(),
+            });
+        }
+
+        Ok(result)
+    }
+
+    /// Pictures in an album, in the album's order, except trashed pictures.
+    pub fn pictures_in_album(&self, album_id: &UserAlbumId) -> Result<Vec<PictureId>> {
+        pictures_in_album(&database::lock(&self.con), album_id)
+    }
+
+    /// Albums a picture is in, in alphabetical order.
+    pub fn entries_for(&self, picture_id: &PictureId) -> Result<Vec<Entry>> {
+        let con = database::lock(&self.con);
+        let mut stmt = con.prepare(
+            "SELECT
+                albums.album_id,
+                albums.name,
+                albums_pictures.sort_index,
+                COALESCE(albums.cover_picture_id = albums_pictures.picture_id, FALSE) AS is_cover
+            FROM albums_pictures
+            INNER JOIN albums USING (album_id)
+            WHERE albums_pictures.picture_id = ?1
+            ORDER BY albums.folded_name ASC",
+        )?;
+
+        let entries = stmt
+            .query_map(params![picture_id], to_entry)?
+            .flatten()
+            .collect();
+
+        Ok(entries)
+    }
+
+    /// Albums of every picture that is in an album, in alphabetical order.
+    /// For loading the albums of the whole library at once.
+    pub fn all_entries(&self) -> Result<HashMap<PictureId, Vec<Entry>>> {
+        all_entries(&database::lock(&self.con))
+    }
+}
+
+/// Pictures in an album, in the album's order, except trashed pictures.
+fn pictures_in_album(con: &rusqlite::Connection, album_id: &UserAlbumId) -> Result<Vec<PictureId>> {
+    let mut stmt = con.prepare(
+        "SELECT pictures.picture_id
+        FROM albums_pictures
+        INNER JOIN pictures USING (picture_id)
+        WHERE albums_pictures.album_id = ?1
+        AND pictures.trashed_at IS NULL
+        ORDER BY albums_pictures.sort_index ASC",
+    )?;
+
+    let picture_ids = stmt
+        .query_map(params![album_id.id()], |row| row.get(0).map(PictureId::new))?
+        .flatten()
+        .collect();
+
+    Ok(picture_ids)
+}
+
+/// Albums of every picture that is in an album, in alphabetical order.
+pub(crate) fn all_entries(con: &rusqlite::Connection) -> Result<HashMap<PictureId, Vec<Entry>>> {
+    let mut stmt = con.prepare(
+        "SELECT
+            albums_pictures.picture_id,
+            albums.album_id,
+            albums.name,
+            albums_pictures.sort_index,
+            COALESCE(albums.cover_picture_id = albums_pictures.picture_id, FALSE) AS is_cover
+        FROM albums_pictures
+        INNER JOIN albums USING (album_id)
+        ORDER BY albums.folded_name ASC",
+    )?;
+
+    let mut entries: HashMap<PictureId, Vec<Entry>> = HashMap::new();
+    for row in stmt.query_map([], |row| {
+        Ok((PictureId::new(row.get("picture_id")?), to_entry(row)?))
+    })? {
+        let (picture_id, entry) = row?;
+        entries.entry(picture_id).or_default().push(entry);
+    }
+
+    Ok(entries)
+}
+
+fn to_entry(row: &rusqlite::Row<'_>) -> rusqlite::Result<Entry> {
+    Ok(Entry {
+        album_id: UserAlbumId::new(row.get("album_id")?),
+        name: row.get("name")?,
+        position: row.get("sort_index")?,
+        is_cover: row.get("is_cover")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::photo;
+    use crate::{FlatpakPathBuf, LibraryRoots, ScannedFile};
+    use std::path::{Path, PathBuf};
+
+    fn open() -> (Repository, photo::Repository, Vec<PictureId>) {
+        let con = Arc::new(Mutex::new(database::setup_in_memory().unwrap()));
+        let library_roots = LibraryRoots::build(&FlatpakPathBuf::build("/", "/"), &[]);
+        let mut photo_repo =
+            photo::Repository::open(&library_roots, Path::new("/"), Path::new("/"), con.clone())
+                .unwrap();
+        let files = vec![
+            ScannedFile::Photo(PathBuf::from("/a.jpg")),
+            ScannedFile::Photo(PathBuf::from("/b.jpg")),
+            ScannedFile::Photo(PathBuf::from("/c.jpg")),
+        ];
+        photo_repo.add_all(&files).unwrap();
+        let mut picture_ids: Vec<_> = photo_repo
+            .all()
+            .unwrap()
+            .iter()
+            .map(|pic| pic.picture_id)
+            .collect();
+        picture_ids.sort_by_key(|id| id.id());
+        let album_repo = Repository::open(con).unwrap();
+        (album_repo, photo_repo, picture_ids)
+    }
+
+    #[test]
+    fn create_album_matches_name_ignoring_case() {
+        let (mut repo, _, _) = open();
+        let holiday = repo.create_album("Holiday").unwrap();
+        assert_eq!(holiday, repo.create_album(" holiday ").unwrap());
+        assert!(repo.create_album(" ").is_err());
+
+        let albums = repo.albums().unwrap();
+        assert_eq!(1, albums.len());
+        assert_eq!("Holiday", albums[0].name);
+        assert_eq!(None, albums[0].cover);
+    }
+
+    #[test]
+    fn manual_order_survives_rescan() {
+        let (mut repo, mut photo_repo, ids) = open();
+        let album = repo.create_album("Holiday").unwrap();
+        repo.add_to_album(&album, &ids).unwrap();
+        assert_eq!(ids, repo.pictures_in_album(&album).unwrap());
+
+        // Pictures left out of the order follow in their current order.
+        repo.set_album_order(&album, vec![ids[2], ids[0]]).unwrap();
+        let arranged = vec![ids[2], ids[0], ids[1]];
+        assert_eq!(arranged, repo.pictures_in_album(&album).unwrap());
+
+        photo_repo
+            .add_all(&vec![
+                ScannedFile::Photo(PathBuf::from("/a.jpg")),
+                ScannedFile::Photo(PathBuf::from("/b.jpg")),
+                ScannedFile::Photo(PathBuf::from("/c.jpg")),
+            ])
+            .unwrap();
+        assert_eq!(arranged, repo.pictures_in_album(&album).unwrap());
+
+        // Pictures added later go at the end, and adding a picture twice keeps its place.
+        repo.remove_from_album(&album, &[ids[0]]).unwrap();
+        repo.add_to_album(&album, &[ids[0], ids[2]]).unwrap();
+        assert_eq!(
+            vec![ids[2], ids[1], ids[0]],
+            repo.pictures_in_album(&album).unwrap()
+        );
+    }
+
+    #[test]
+    fn cover_is_chosen_picture_or_first() {
+        let (mut repo, _, ids) = open();
+        let album = repo.create_album("Holiday").unwrap();
+        repo.add_to_album(&album, &ids[..2]).unwrap();
+        assert_eq!(Some(ids[0]), repo.albums().unwrap()[0].cover);
+
+        assert!(repo.set_album_cover(&album, &ids[2]).is_err());
+        repo.set_album_cover(&album, &ids[1]).unwrap();
+        assert_eq!(Some(ids[1]), repo.albums().unwrap()[0].cover);
+
+        let entries = repo.entries_for(&ids[1]).unwrap();
+        assert!(entries[0].is_cover);
+        assert!(!repo.entries_for(&ids[0]).unwrap()[0].is_cover);
+
+        repo.remove_from_album(&album, &[ids[1]]).unwrap();
+        assert_eq!(Some(ids[0]), repo.albums().unwrap()[0].cover);
+    }
+
+    #[test]
+    fn delete_album_keeps_pictures() {
+        let (mut repo, photo_repo, ids) = open();
+        let album = repo.create_album("Receipts").unwrap();
+        repo.add_to_album(&album, &ids).unwrap();
+
+        assert!(repo.delete_album(&album).unwrap());
+        assert!(!repo.delete_album(&album).unwrap());
+
+        assert!(repo.albums().unwrap().is_empty());
+        assert!(repo.all_entries().unwrap().is_empty());
+        assert_eq!(3, photo_repo.all().unwrap().len());
+    }
+}
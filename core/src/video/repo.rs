@@ -5,16 +5,21 @@
 use super::Metadata;
 use super::metadata;
 use crate::FlatpakPathBuf;
+use crate::LibraryRoots;
 use crate::ScannedFile;
+use crate::database;
 use crate::file_types;
 use crate::path_encoding;
+use crate::scanner::canonical_path;
 use crate::video::model::{Video, VideoId};
 
 use anyhow::*;
 use chrono::*;
 use rusqlite;
+use rusqlite::OptionalExtension;
 use rusqlite::Row;
 use rusqlite::params;
+use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use tracing::error;
@@ -23,8 +28,8 @@ use tracing::error;
 /// Repository is backed by a Sqlite database.
 #[derive(Debug, Clone)]
 pub struct Repository {
-    /// Base path to library
-    library_base_dir: FlatpakPathBuf,
+    /// Directories of the library
+    library_roots: LibraryRoots,
 
     /// Base path for transcoded videos
     cache_dir_base_path: PathBuf,
@@ -39,7 +44,7 @@ pub struct Repository {
 impl Repository {
     /// Builds a Repository and creates operational tables.
     pub fn open(
-        library_base_dir: &FlatpakPathBuf,
+        library_roots: &LibraryRoots,
         cache_dir_base_path: &Path,
         data_dir_base_path: &Path,
         con: Arc<Mutex<rusqlite::Connection>>,
@@ -47,7 +52,7 @@ impl Repository {
         std::fs::create_dir_all(cache_dir_base_path)?;
 
         let repo = Repository {
-            library_base_dir: library_base_dir.clone(),
+            library_roots: library_roots.clone(),
             cache_dir_base_path: cache_dir_base_path.into(),
             data_dir_base_path: data_dir_base_path.into(),
             con,
@@ -57,7 +62,7 @@ impl Repository {
     }
 
     pub fn mark_broken(&mut self, video_id: &VideoId) -> Result<()> {
-        let mut con = self.con.lock().unwrap();
+        let mut con = database::lock(&self.con);
         let tx = con.transaction()?;
 
         {
@@ -76,7 +81,7 @@ impl Repository {
     }
 
     pub fn add_transcode(&mut self, video_id: VideoId, transcoded_path: &Path) -> Result<()> {
-        let mut con = self.con.lock().unwrap();
+        let mut con = database::lock(&self.con);
         let tx = con.transaction()?;
 
         {
@@ -101,7 +106,7 @@ impl Repository {
     }
 
     pub fn add_metadata(&mut self, vids: Vec<(VideoId, Metadata)>) -> Result<()> {
-        let mut con = self.con.lock().unwrap();
+        let mut con = database::lock(&self.con);
         let tx = con.transaction()?;
 
         {
@@ -162,31 +167,67 @@ impl Repository {
         Ok(())
     }
 
-    pub fn add_all(&mut self, vids: &Vec<ScannedFile>) -> Result<()> {
-        let mut con = self.con.lock().unwrap();
+    /// Add all videos received from a vector.
+    /// Returns the paths of videos removed because they had been added twice, by
+    /// different paths, so that their thumbnails can be deleted too.
+    pub fn add_all(&mut self, vids: &Vec<ScannedFile>) -> Result<Vec<FlatpakPathBuf>> {
+        let mut con = database::lock(&self.con);
         let tx = con.transaction()?;
 
+        let mut duplicates = Vec::new();
+        let mut derived_files = Vec::new();
+
         // Create a scope to make borrowing of tx not be an error.
         {
+            // A video already in the library by another path isn't added again.
             let mut vid_stmt = tx.prepare_cached(
                 "INSERT INTO videos (
                         video_path_b64,
                         video_path_lossy,
                         link_path_b64,
                         link_path_lossy,
+                        canonical_path_b64,
                         insert_ts
                     ) VALUES (
-                        ?1, ?2, ?3, ?4, CURRENT_TIMESTAMP
+                        ?1, ?2, ?3, ?4, ?5, CURRENT_TIMESTAMP
                     ) ON CONFLICT(video_path_b64) DO UPDATE SET
                         link_path_b64 = ?3,
-                        link_path_lossy = ?4",
+                        link_path_lossy = ?4
+                    ON CONFLICT DO NOTHING",
+            )?;
+
+            // Videos added before canonical paths were recorded.
+            let mut canonical_update_stmt = tx.prepare_cached(
+                "UPDATE OR IGNORE videos
+                SET canonical_path_b64 = ?2
+                WHERE video_path_b64 = ?1
+                AND canonical_path_b64 IS NULL",
+            )?;
+
+            // A video added twice before canonical paths were recorded. The other path
+            // has already taken the canonical path, so this path is a duplicate.
+            let mut duplicate_stmt = tx.prepare_cached(
+                "SELECT video_id FROM videos
+                WHERE video_path_b64 = ?1
+                AND canonical_path_b64 IS NULL
+                AND EXISTS (SELECT 1 FROM videos WHERE canonical_path_b64 = ?2)",
             )?;
 
+            let mut cleanup_stmt = tx
+                .prepare_cached("SELECT root_name, path FROM videos_cleanup WHERE video_id = ?1")?;
+
+            let mut delete_stmt = tx.prepare_cached("DELETE FROM videos WHERE video_id = ?1")?;
+
+            let mut byte_size_update_stmt =
+                tx.prepare_cached("UPDATE videos SET byte_size = ?2 WHERE video_path_b64 = ?1")?;
+
             for scanned_file in vids {
                 if let ScannedFile::Video(path) = scanned_file {
                     // convert to relative path before saving to database
-                    let video_path = path.strip_prefix(&self.library_base_dir.sandbox_path)?;
-                    let video_path_b64 = path_encoding::to_base64(video_path);
+                    let video_path = self.library_roots.to_stored_path(path)?;
+                    let video_path_b64 = path_encoding::to_base64(&video_path);
+
+                    let canonical_path_b64 = path_encoding::to_base64(&canonical_path(path));
 
                     // Path without suffix so sibling pictures and videos can be related
                     // Some Apple photo exports name the video component after the
@@ -211,7 +252,28 @@ impl Repository {
                         video_path.to_string_lossy(),
                         link_path_b64,
                         link_path.to_string_lossy(),
+                        canonical_path_b64,
                     ])?;
+
+                    canonical_update_stmt.execute(params![video_path_b64, canonical_path_b64])?;
+                    let duplicate: Option<i64> = duplicate_stmt
+                        .query_row(params![video_path_b64, canonical_path_b64], |row| {
+                            row.get(0)
+                        })
+                        .optional()?;
+
+                    if let Some(video_id) = duplicate {
+                        derived_files.extend(
+                            cleanup_stmt
+                                .query_map([video_id], |row| self.to_cleanup_path(row))?
+                                .flatten(),
+                        );
+                        delete_stmt.execute([video_id])?;
+                        duplicates.push(self.library_roots.resolve(&video_path));
+                    }
+
+                    let byte_size = fs::metadata(path).ok().map(|m| m.len());
+                    byte_size_update_stmt.execute(params![video_path_b64, byte_size])?;
                 } else {
                     error!("Expected a video, but got: {:?}", scanned_file);
                 }
@@ -219,12 +281,20 @@ impl Repository {
         }
 
         tx.commit()?;
-        Ok(())
+        drop(con);
+
+        for path in derived_files.iter().filter(|path| path.exists()) {
+            if let Err(e) = fs::remove_file(path) {
+                error!("Failed deleting {:?} with {}", path, e);
+            }
+        }
+
+        Ok(duplicates)
     }
 
     /// Gets all videos in the repository, in ascending order of modification timestamp.
     pub fn all(&self) -> Result<Vec<Video>> {
-        let con = self.con.lock().unwrap();
+        let con = database::lock(&self.con);
         let mut stmt = con.prepare(
             "SELECT
                     video_id,
@@ -250,7 +320,7 @@ impl Repository {
 
     /// Gets all videos in the repository, in ascending order of modification timestamp.
     pub fn find_need_metadata_update(&self) -> Result<Vec<Video>> {
-        let con = self.con.lock().unwrap();
+        let con = database::lock(&self.con);
         let mut stmt = con.prepare(
             "SELECT
                     video_id,
@@ -277,7 +347,7 @@ impl Repository {
 
     /// Gets paths of files to delete when a video is no longer present.
     pub fn find_files_to_cleanup(&self, video_id: VideoId) -> Result<Vec<PathBuf>> {
-        let con = self.con.lock().unwrap();
+        let con = database::lock(&self.con);
         let mut stmt =
             con.prepare("SELECT root_name, path FROM videos_cleanup WHERE video_id = ?1")?;
 
@@ -295,8 +365,7 @@ impl Repository {
         let relative_path: String = row.get("video_path_b64")?;
         let relative_path = path_encoding::from_base64(&relative_path)
             .map_err(|_| rusqlite::Error::InvalidQuery)?;
-        let sandbox_path = self.library_base_dir.sandbox_path.join(&relative_path);
-        let host_path = self.library_base_dir.host_path.join(&relative_path);
+        let path = self.library_roots.resolve(&relative_path);
 
         let ordering_ts = row.get("ordering_ts").expect("must have ordering_ts");
 
@@ -314,7 +383,7 @@ impl Repository {
 
         std::result::Result::Ok(Video {
             video_id,
-            path: FlatpakPathBuf::build(host_path, sandbox_path),
+            path,
             ordering_ts,
             stream_duration,
             video_codec,
@@ -335,7 +404,7 @@ impl Repository {
     }
 
     pub fn remove(&mut self, video_id: VideoId) -> Result<()> {
-        let con = self.con.lock().unwrap();
+        let con = database::lock(&self.con);
         let mut stmt = con.prepare("DELETE FROM videos WHERE video_id = ?1")?;
 
         stmt.execute([video_id.id()])?;
@@ -6,6 +6,7 @@ use crate::FlatpakPathBuf;
 use crate::thumbnailify;
 
 use anyhow::*;
+use chrono::TimeDelta;
 use image::ImageReader;
 use std::process::Command;
 use std::result::Result::Ok;
@@ -22,33 +23,44 @@ impl VideoThumbnailer {
         Ok(VideoThumbnailer { thumbnailer })
     }
 
-    /// Computes a preview for a video
-    pub fn thumbnail(&self, path: &FlatpakPathBuf) -> Result<()> {
+    /// Thumbnails of every kind of file, such as for finding where they are saved.
+    pub fn thumbnailer(&self) -> &thumbnailify::Thumbnailer {
+        &self.thumbnailer
+    }
+
+    /// Computes a preview for a video from a representative frame 10% of the way
+    /// into the video. Opening frames are often black or blurred. If the duration
+    /// is unknown, then the first frame is used.
+    pub fn thumbnail(&self, path: &FlatpakPathBuf, duration: Option<TimeDelta>) -> Result<()> {
         if self.thumbnailer.is_failed(&path.host_path) {
             anyhow::bail!("Failed thumbnail marker exists for {:?}", path.host_path);
         }
 
-        self.thumbnail_internal(path).map_err(|err| {
-            let _ = self.thumbnailer.write_failed_thumbnail(path);
-            err
-        })
+        self.thumbnail_internal(path, frame_offset(duration))
+            .map_err(|err| {
+                let _ = self.thumbnailer.write_failed_thumbnail(path);
+                err
+            })
     }
 
-    pub fn thumbnail_internal(&self, path: &FlatpakPathBuf) -> Result<()> {
-        // Extract first frame of video for thumbnail
+    pub fn thumbnail_internal(&self, path: &FlatpakPathBuf, offset: TimeDelta) -> Result<()> {
+        // Extract a single frame of video for thumbnail
         let temporary_png_file = tempfile::Builder::new().suffix(".png").tempfile()?;
 
-        // ffmpeg command will extract the first frame and save it as a PNG file.
+        // ffmpeg command will extract the frame at the offset and save it as a PNG file.
+        // Seeking before the input is fast because it jumps to the nearest key frame.
         let status = Command::new("ffmpeg")
             .arg("-loglevel")
             .arg("error")
             .arg("-y") // temp file will already exist, so allow overwriting
+            .arg("-ss")
+            .arg(format!("{:.3}", offset.as_seconds_f64()))
             .arg("-i")
             .arg(path.sandbox_path.as_os_str())
             .arg("-update")
             .arg("true")
-            .arg("-vf")
-            .arg(r"select=eq(n\,0)") // select frame zero
+            .arg("-frames:v")
+            .arg("1")
             .arg(temporary_png_file.path())
             .status()?;
 
@@ -65,3 +77,28 @@ impl VideoThumbnailer {
         Ok(())
     }
 }
+
+/// Offset of frame to use as a thumbnail.
+pub(crate) fn frame_offset(duration: Option<TimeDelta>) -> TimeDelta {
+    duration
+        .filter(|duration| *duration > TimeDelta::zero())
+        .map(|duration| duration / 10)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_offset_is_tenth_of_duration() {
+        let offset = frame_offset(Some(TimeDelta::seconds(30)));
+        assert_eq!(offset, TimeDelta::seconds(3));
+    }
+
+    #[test]
+    fn frame_offset_without_duration_is_first_frame() {
+        assert_eq!(frame_offset(None), TimeDelta::zero());
+        assert_eq!(frame_offset(Some(TimeDelta::zero())), TimeDelta::zero());
+    }
+}
@@ -0,0 +1,142 @@
+// SPDX-FileCopyrightText: © 2025 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Short looping previews of videos and animated GIFs, shown in place of the still
+//! thumbnail while the pointer is over a grid item.
+
+use crate::thumbnailify;
+use crate::video::thumbnailer::frame_offset;
+use crate::visual::Visual;
+
+use anyhow::*;
+use chrono::TimeDelta;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::result::Result::Ok;
+use tempfile;
+
+/// Length of preview.
+const PREVIEW_SECONDS: i64 = 3;
+
+/// Frames per second of preview. Low to keep previews small.
+const PREVIEW_FPS: u32 = 10;
+
+/// Length of the preview's edge. Previews are square, like the still thumbnails.
+const PREVIEW_EDGE_LENGTH: u32 = 256;
+
+/// Is the file a GIF? Only GIFs are given animated previews. Other formats that might
+/// be animated are almost always still photos.
+pub fn is_gif(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("gif"))
+}
+
+/// Generates looping previews for videos and animated GIFs.
+#[derive(Debug, Clone)]
+pub struct AnimatedThumbnailer {
+    thumbnailer: thumbnailify::Thumbnailer,
+}
+
+impl AnimatedThumbnailer {
+    pub fn build(thumbnailer: thumbnailify::Thumbnailer) -> Result<AnimatedThumbnailer> {
+        Ok(AnimatedThumbnailer { thumbnailer })
+    }
+
+    /// Does the visual item need a preview generating?
+    /// Previews evicted from the thumbnail cache are only generated again after the
+    /// still thumbnail has been regenerated.
+    pub fn is_missing(&self, visual: &Visual) -> bool {
+        let hash = visual.thumbnail_hash();
+        visual.is_animated()
+            && !self.thumbnailer.is_failed(visual.host_path())
+            && !self.thumbnailer.is_evicted(&hash)
+            && self.thumbnailer.animated_thumbnail(&hash).is_none()
+    }
+
+    /// Writes a looping animated WebP preview of a video or animated GIF.
+    /// Video previews start at the same frame as the still thumbnail.
+    pub fn thumbnail(&self, visual: &Visual) -> Result<()> {
+        if !visual.is_animated() {
+            bail!("Not an animated item: {:?}", visual.host_path());
+        }
+
+        let offset = if visual.is_video_only() {
+            frame_offset(visual.video_duration)
+        } else {
+            TimeDelta::zero()
+        };
+
+        let preview_path = self.thumbnailer.get_animated_thumbnail_path(visual.path());
+
+        let preview_dir = preview_path
+            .parent()
+            .ok_or_else(|| anyhow!("No parent for {:?}", preview_path))?;
+        fs::create_dir_all(preview_dir)?;
+
+        // Write to a temporary file in the same directory so the finished preview
+        // can be atomically moved into place.
+        let temporary_preview_file = tempfile::Builder::new()
+            .suffix(".webp")
+            .tempfile_in(preview_dir)?;
+
+        let status = Command::new("ffmpeg")
+            .arg("-loglevel")
+            .arg("error")
+            .arg("-y") // temp file will already exist, so allow overwriting
+            .arg("-ss")
+            .arg(format!("{:.3}", offset.as_seconds_f64()))
+            .arg("-t")
+            .arg(PREVIEW_SECONDS.to_string())
+            .arg("-i")
+            .arg(visual.sandbox_path().as_os_str())
+            .arg("-an")
+            .arg("-vf")
+            .arg(video_filter(PREVIEW_FPS, PREVIEW_EDGE_LENGTH))
+            .arg("-loop")
+            .arg("0")
+            .arg("-c:v")
+            .arg("libwebp")
+            .arg("-quality")
+            .arg("60")
+            .arg(temporary_preview_file.path())
+            .status()?;
+
+        if !status.success() {
+            bail!("FFMPEG exited with status {:?}", status.code());
+        }
+
+        temporary_preview_file.persist(&preview_path)?;
+
+        Ok(())
+    }
+}
+
+/// ffmpeg filter to reduce the frame rate and crop to a square.
+fn video_filter(fps: u32, edge_length: u32) -> String {
+    format!(
+        "fps={fps},scale={edge_length}:{edge_length}:force_original_aspect_ratio=increase,crop={edge_length}:{edge_length}"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_gif_ignores_case() {
+        assert!(is_gif(Path::new("/pictures/cat.gif")));
+        assert!(is_gif(Path::new("/pictures/cat.GIF")));
+        assert!(!is_gif(Path::new("/pictures/cat.jpg")));
+        assert!(!is_gif(Path::new("/pictures/gif")));
+    }
+
+    #[test]
+    fn video_filter_crops_to_square() {
+        assert_eq!(
+            "fps=10,scale=256:256:force_original_aspect_ratio=increase,crop=256:256",
+            video_filter(10, 256)
+        );
+    }
+}
@@ -0,0 +1,484 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use itertools::Itertools;
+
+use crate::collation;
+use crate::visual::model::Visual;
+
+/// A directory of visual items, either held directly or in subdirectories.
+#[derive(Debug, Clone)]
+pub struct Folder {
+    /// Path to directory.
+    pub path: PathBuf,
+
+    /// Visual item the user chose as the cover, otherwise the first visual item found
+    /// in, or beneath, the directory.
+    pub cover: Arc<Visual>,
+
+    /// Does the directory have subdirectories containing visual items?
+    pub has_subfolders: bool,
+
+    /// Number of visual items shown when the folder is opened, including any in subfolders.
+    pub count: usize,
+}
+
+impl Folder {
+    /// Name of the directory.
+    pub fn name(&self) -> Option<String> {
+        name(&self.path)
+    }
+
+    /// Has the user hidden the directory, or a directory containing it?
+    /// Every item in a hidden directory is hidden by the same, or an outer, directory,
+    /// so the cover is enough to tell.
+    pub fn is_hidden(&self) -> bool {
+        self.cover
+            .hidden_folder
+            .as_ref()
+            .is_some_and(|hidden| self.path.starts_with(hidden))
+    }
+}
+
+/// Path, and whether it has subfolders, of the folder at each position of a grid.
+/// Replaced together with the grid, so that activating a position looks up the folder
+/// shown there at that moment. A grid index kept until later might point at another
+/// folder once the grid has been refreshed.
+#[derive(Debug, Clone, Default)]
+pub struct FolderPositions(Vec<(PathBuf, bool)>);
+
+impl FolderPositions {
+    pub fn new(folders: &[Folder]) -> Self {
+        Self(
+            folders
+                .iter()
+                .map(|folder| (folder.path.clone(), folder.has_subfolders))
+                .collect(),
+        )
+    }
+
+    /// Path, and whether it has subfolders, of the folder at a grid position.
+    pub fn get(&self, index: u32) -> Option<(PathBuf, bool)> {
+        self.0.get(index as usize).cloned()
+    }
+}
+
+/// Name of a directory, or `None` if the path has no last component, such as `/`.
+///
+/// Paths are never resolved, so a symlinked directory is named after the link.
+pub fn name(path: &Path) -> Option<String> {
+    path.file_name().map(|x| x.to_string_lossy().to_string())
+}
+
+/// One folder for every directory that directly contains visual items, ordered by name.
+pub fn flat(visuals: &[Arc<Visual>]) -> Vec<Folder> {
+    let folders = visuals
+        .iter()
+        .sorted_by_key(|visual| visual.parent_path.clone())
+        .chunk_by(|visual| visual.parent_path.clone())
+        .into_iter()
+        .filter_map(|(path, group)| {
+            let group: Vec<_> = group.collect();
+            group.first().map(|first| Folder {
+                path,
+                cover: (*first).clone(),
+                has_subfolders: false,
+                count: group.len(),
+            })
+        })
+        .sorted_by(compare_names)
+        .collect();
+
+    with_chosen_covers(folders, visuals)
+}
+
+/// Deepest directory that contains all visual items. This is the top of the folder tree.
+pub fn root(visuals: &[Arc<Visual>]) -> Option<PathBuf> {
+    let mut paths = visuals.iter().map(|visual| &visual.parent_path);
+    let first = paths.next()?.clone();
+
+    let root = paths.fold(first, |root, path| {
+        root.ancestors()
+            .find(|ancestor| path.starts_with(ancestor))
+            .map(PathBuf::from)
+            .unwrap_or_default()
+    });
+
+    Some(root)
+}
+
+/// One folder for each library root that has visual items somewhere beneath it,
+/// in the order of `roots`. This is the top of the folder tree for a library with
+/// more than one root.
+pub fn roots(visuals: &[Arc<Visual>], roots: &[PathBuf]) -> Vec<Folder> {
+    let folders = roots
+        .iter()
+        .filter_map(|root| {
+            let beneath: Vec<_> = visuals
+                .iter()
+                .filter(|visual| visual.parent_path.starts_with(root))
+                .collect();
+
+            beneath.first().map(|first| Folder {
+                path: root.clone(),
+                cover: (*first).clone(),
+                has_subfolders: beneath.iter().any(|visual| visual.parent_path != *root),
+                count: beneath.len(),
+            })
+        })
+        .collect();
+
+    with_chosen_covers(folders, visuals)
+}
+
+/// One level of the folder tree. Contains a folder for each subdirectory immediately
+/// beneath `parent` that has visual items somewhere beneath it, ordered by name.
+///
+/// If `parent` also directly contains visual items, then `parent` itself leads the
+/// level so that those items can still be reached.
+pub fn tree_level(visuals: &[Arc<Visual>], parent: &Path) -> Vec<Folder> {
+    let mut children: Vec<Folder> = Vec::new();
+    let mut own_cover: Option<Arc<Visual>> = None;
+    let mut own_count = 0;
+
+    for visual in visuals {
+        let Ok(relative) = visual.parent_path.strip_prefix(parent) else {
+            continue;
+        };
+
+        let mut components = relative.components();
+        let Some(child_name) = components.next() else {
+            // Visual item is directly in parent.
+            if own_cover.is_none() {
+                own_cover = Some(visual.clone());
+            }
+            own_count += 1;
+            continue;
+        };
+
+        let child_path = parent.join(child_name);
+        let is_nested = components.next().is_some();
+
+        if let Some(child) = children.iter_mut().find(|c| c.path == child_path) {
+            child.has_subfolders |= is_nested;
+            child.count += 1;
+        } else {
+            children.push(Folder {
+                path: child_path,
+                cover: visual.clone(),
+                has_subfolders: is_nested,
+                count: 1,
+            });
+        }
+    }
+
+    children.sort_by(compare_names);
+
+    if let Some(cover) = own_cover {
+        children.insert(
+            0,
+            Folder {
+                path: parent.to_path_buf(),
+                cover,
+                has_subfolders: false,
+                count: own_count,
+            },
+        );
+    }
+
+    with_chosen_covers(children, visuals)
+}
+
+/// Orders folders by name for the user's locale, with numbers compared by value.
+/// The top of the library has no name, so comes first.
+fn compare_names(a: &Folder, b: &Folder) -> Ordering {
+    match (a.name(), b.name()) {
+        (Some(a), Some(b)) => collation::compare(&a, &b),
+        (a, b) => a.cmp(&b),
+    }
+}
+
+/// Use the visual item the user chose as a folder's cover in place of the first item.
+/// A folder whose chosen cover has been deleted keeps its first item.
+fn with_chosen_covers(mut folders: Vec<Folder>, visuals: &[Arc<Visual>]) -> Vec<Folder> {
+    let chosen: HashMap<&Path, &Arc<Visual>> = visuals
+        .iter()
+        .filter(|visual| visual.is_folder_cover)
+        .map(|visual| (visual.parent_path.as_path(), visual))
+        .collect();
+
+    for folder in folders.iter_mut() {
+        if let Some(cover) = chosen.get(folder.path.as_path()) {
+            folder.cover = (*cover).clone();
+        }
+    }
+
+    folders
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FlatpakPathBuf;
+    use crate::PictureId;
+    use crate::visual::model::VisualId;
+    use chrono::Utc;
+
+    fn picture(id: i64, path: &str, is_folder_cover: bool) -> Arc<Visual> {
+        let path = PathBuf::from(path);
+        Arc::new(Visual {
+            visual_id: VisualId::new(format!("{}_x", id)),
+            parent_path: path.parent().map(PathBuf::from).unwrap_or_default(),
+            video_id: None,
+            video_path: None,
+            video_transcoded_path: None,
+            video_duration: None,
+            video_orientation: None,
+            picture_id: Some(PictureId::new(id)),
+            picture_path: Some(FlatpakPathBuf::build(path.clone(), path)),
+            picture_orientation: None,
+            motion_photo_video_path: None,
+            ordering_ts: Utc::now(),
+            taken_at_source: None,
+            taken_at_precision: None,
+            is_selfie: None,
+            is_live_photo: false,
+            is_favorite: false,
+            is_private: false,
+            is_transcode_required: None,
+            location: None,
+            place_name: None,
+            blurhash: None,
+            picture_dimensions: None,
+            picture_byte_size: None,
+            video_byte_size: None,
+            is_folder_cover,
+            hidden_folder: None,
+            tags: Vec::new(),
+            user_albums: Vec::new(),
+            burst: None,
+        })
+    }
+
+    fn hidden_picture(id: i64, path: &str, hidden_folder: &str) -> Arc<Visual> {
+        let mut visual = (*picture(id, path, false)).clone();
+        visual.hidden_folder = Some(PathBuf::from(hidden_folder));
+        Arc::new(visual)
+    }
+
+    fn cover_ids(folders: &[Folder]) -> Vec<Option<PictureId>> {
+        folders
+            .iter()
+            .map(|folder| folder.cover.picture_id)
+            .collect()
+    }
+
+    #[test]
+    fn first_item_is_cover() {
+        let visuals = [
+            picture(1, "/a/1.jpg", false),
+            picture(2, "/a/2.jpg", false),
+            picture(3, "/b/3.jpg", false),
+        ];
+        assert_eq!(
+            vec![Some(PictureId::new(1)), Some(PictureId::new(3))],
+            cover_ids(&flat(&visuals))
+        );
+    }
+
+    #[test]
+    fn flat_groups_interleaved_parent_paths() {
+        // Items from the same directory aren't next to each other, as happens when
+        // items are ordered by time rather than by path.
+        let visuals = [
+            picture(1, "/b/1.jpg", false),
+            picture(2, "/a/2.jpg", false),
+            picture(3, "/b/3.jpg", false),
+            picture(4, "/a/4.jpg", false),
+            picture(5, "/b/5.jpg", false),
+        ];
+        let folders = flat(&visuals);
+
+        let paths: Vec<_> = folders.iter().map(|folder| folder.path.clone()).collect();
+        assert_eq!(vec![PathBuf::from("/a"), PathBuf::from("/b")], paths);
+
+        let counts: Vec<_> = folders.iter().map(|folder| folder.count).collect();
+        assert_eq!(vec![2, 3], counts);
+
+        assert_eq!(
+            vec![Some(PictureId::new(2)), Some(PictureId::new(1))],
+            cover_ids(&folders)
+        );
+    }
+
+    #[test]
+    fn flat_single_item_folders() {
+        let visuals = [
+            picture(1, "/a/1.jpg", false),
+            picture(2, "/b/2.jpg", false),
+            picture(3, "/a/c/3.jpg", false),
+        ];
+        let folders = flat(&visuals);
+
+        let paths: Vec<_> = folders.iter().map(|folder| folder.path.clone()).collect();
+        assert_eq!(
+            vec![
+                PathBuf::from("/a"),
+                PathBuf::from("/b"),
+                PathBuf::from("/a/c")
+            ],
+            paths
+        );
+        assert!(folders.iter().all(|folder| folder.count == 1));
+        assert!(folders.iter().all(|folder| !folder.has_subfolders));
+    }
+
+    #[test]
+    fn folders_with_numbers_sort_by_value() {
+        let visuals = [
+            picture(1, "/Folder 10/1.jpg", false),
+            picture(2, "/Folder 2/2.jpg", false),
+            picture(3, "/Folder 1/3.jpg", false),
+        ];
+        let expected = vec![
+            Some("Folder 1".to_string()),
+            Some("Folder 2".to_string()),
+            Some("Folder 10".to_string()),
+        ];
+
+        let names: Vec<_> = flat(&visuals).iter().map(|folder| folder.name()).collect();
+        assert_eq!(expected, names);
+
+        let names: Vec<_> = tree_level(&visuals, Path::new("/"))
+            .iter()
+            .map(|folder| folder.name())
+            .collect();
+        assert_eq!(expected, names);
+    }
+
+    #[test]
+    fn flat_empty() {
+        assert!(flat(&[]).is_empty());
+    }
+
+    #[test]
+    fn chosen_cover_replaces_first_item() {
+        let visuals = [
+            picture(1, "/a/1.jpg", false),
+            picture(2, "/a/2.jpg", true),
+            picture(3, "/b/3.jpg", false),
+        ];
+        assert_eq!(
+            vec![Some(PictureId::new(2)), Some(PictureId::new(3))],
+            cover_ids(&flat(&visuals))
+        );
+        assert_eq!(
+            vec![Some(PictureId::new(2)), Some(PictureId::new(3))],
+            cover_ids(&tree_level(&visuals, Path::new("/")))
+        );
+    }
+
+    #[test]
+    fn chosen_cover_only_applies_to_its_own_folder() {
+        // Picture 3 is the cover of "/a/b", not of "/a".
+        let visuals = [
+            picture(1, "/a/1.jpg", false),
+            picture(3, "/a/b/3.jpg", true),
+        ];
+        let folders = tree_level(&visuals, Path::new("/a"));
+        assert_eq!(
+            vec![Some(PictureId::new(1)), Some(PictureId::new(3))],
+            cover_ids(&folders)
+        );
+
+        let folders = roots(&visuals, &[PathBuf::from("/a")]);
+        assert_eq!(vec![Some(PictureId::new(1))], cover_ids(&folders));
+    }
+
+    #[test]
+    fn filesystem_root_has_no_name() {
+        assert_eq!(None, name(Path::new("/")));
+    }
+
+    #[test]
+    fn single_component_name() {
+        assert_eq!(Some("Holiday".to_string()), name(Path::new("Holiday")));
+    }
+
+    #[test]
+    fn nested_name_is_last_component() {
+        assert_eq!(Some("Holiday".to_string()), name(Path::new("2024/Holiday")));
+    }
+
+    #[test]
+    fn trailing_slash_is_ignored() {
+        assert_eq!(
+            Some("Holiday".to_string()),
+            name(Path::new("2024/Holiday/"))
+        );
+    }
+
+    #[test]
+    fn symlinked_directory_is_named_after_link() {
+        // "Latest" could be a symlink to "2024/Holiday", but paths aren't resolved.
+        assert_eq!(Some("Latest".to_string()), name(Path::new("Latest")));
+    }
+
+    #[test]
+    fn folders_beneath_hidden_folder_are_hidden() {
+        let visuals = [
+            hidden_picture(1, "/a/b/1.jpg", "/a"),
+            picture(2, "/c/2.jpg", false),
+            hidden_picture(3, "/d/e/3.jpg", "/d/e"),
+            picture(4, "/d/4.jpg", false),
+        ];
+
+        let hidden: Vec<_> = tree_level(&visuals, Path::new("/"))
+            .iter()
+            .map(|folder| (folder.path.clone(), folder.is_hidden()))
+            .collect();
+        assert_eq!(
+            vec![
+                (PathBuf::from("/a"), true),
+                (PathBuf::from("/c"), false),
+                (PathBuf::from("/d"), false),
+            ],
+            hidden
+        );
+
+        let hidden: Vec<_> = tree_level(&visuals, Path::new("/d"))
+            .iter()
+            .map(|folder| (folder.path.clone(), folder.is_hidden()))
+            .collect();
+        assert_eq!(
+            vec![(PathBuf::from("/d"), false), (PathBuf::from("/d/e"), true)],
+            hidden
+        );
+    }
+    #[test]
+    fn folder_positions_keep_activated_folder_across_refresh() {
+        let before = flat(&[picture(1, "/a/1.jpg", false), picture(2, "/b/2.jpg", false)]);
+        let mut positions = FolderPositions::new(&before);
+
+        // Activated before a refresh...
+        let activated = positions.get(1);
+
+        // ...that adds a folder ahead of the activated one.
+        let after = flat(&[
+            picture(1, "/a/1.jpg", false),
+            picture(2, "/b/2.jpg", false),
+            picture(3, "/aa/3.jpg", false),
+        ]);
+        positions = FolderPositions::new(&after);
+
+        assert_eq!(Some((PathBuf::from("/b"), false)), activated);
+        assert_eq!(Some((PathBuf::from("/aa"), false)), positions.get(1));
+        assert_eq!(None, positions.get(3));
+    }
+}
@@ -2,9 +2,17 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+pub mod animated;
+pub mod folder;
 pub mod model;
 pub mod repo;
+pub mod stats;
+pub mod thumbnailer;
 
+pub use animated::AnimatedThumbnailer;
+pub use folder::Folder;
 pub use model::Visual;
 pub use model::VisualId;
 pub use repo::Repository;
+pub use stats::LibraryStats;
+pub use thumbnailer::VisualThumbnailer;
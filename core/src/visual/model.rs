@@ -6,8 +6,12 @@ use std::fmt::Display;
 use std::path::PathBuf;
 
 use crate::FlatpakPathBuf;
-use crate::photo::model::Orientation;
+use crate::LibraryRoots;
+use crate::burst;
+use crate::photo::model::{DatePrecision, Orientation, TakenAtSource};
 use crate::thumbnailify;
+use crate::user_album;
+use crate::visual::{animated, folder};
 use crate::{PictureId, VideoId, YearMonth};
 
 use chrono::*;
@@ -69,17 +73,59 @@ pub struct Visual {
     /// Best candidate for ordering visual items. With a final fallback of the current timestamp.
     pub ordering_ts: DateTime<Utc>,
 
+    /// Where the time a picture was taken came from, if known.
+    pub taken_at_source: Option<TakenAtSource>,
+
+    /// How much of the time a picture was taken is known, if recorded.
+    pub taken_at_precision: Option<DatePrecision>,
+
     // Is this a selfie?
     pub is_selfie: Option<bool>,
 
     // Is this an iOS live photo?
     pub is_live_photo: bool,
 
+    // Has user starred this as a favorite?
+    pub is_favorite: bool,
+
+    // Has user marked this as private, to be hidden while private pictures are locked?
+    pub is_private: bool,
+
     // Does the video_code require the video is transcoded?
     pub is_transcode_required: Option<bool>,
 
     // Where photo was taken
     pub location: Option<LatLng>,
+
+    // Name of place photo was taken, such as "Paris, France".
+    pub place_name: Option<String>,
+
+    // Blurhash of picture, drawn as a placeholder while the thumbnail loads.
+    pub blurhash: Option<String>,
+
+    // Width and height of picture in pixels, if known.
+    pub picture_dimensions: Option<(u32, u32)>,
+
+    // File size of picture in bytes, if known.
+    pub picture_byte_size: Option<u64>,
+
+    // File size of video in bytes, if known.
+    pub video_byte_size: Option<u64>,
+
+    // Has user chosen this as the cover of its folder?
+    pub is_folder_cover: bool,
+
+    // Outermost folder containing this that the user has hidden from the folders album.
+    pub hidden_folder: Option<PathBuf>,
+
+    // Names of the tags the user has given the picture, in alphabetical order.
+    pub tags: Vec<String>,
+
+    // User albums the picture is in, in alphabetical order of album name.
+    pub user_albums: Vec<user_album::Entry>,
+
+    // Burst the picture is a frame of, if grouped with other pictures taken at the same time.
+    pub burst: Option<burst::Frame>,
 }
 
 impl Visual {
@@ -98,6 +144,15 @@ impl Visual {
         &self.path().host_path
     }
 
+    /// File size in bytes of the file at `path()`, if known.
+    pub fn byte_size(&self) -> Option<u64> {
+        if self.picture_path.is_some() {
+            self.picture_byte_size
+        } else {
+            self.video_byte_size
+        }
+    }
+
     pub fn thumbnail_hash(&self) -> String {
         thumbnailify::compute_hash_for_path(self.host_path())
     }
@@ -106,6 +161,34 @@ impl Visual {
         self.is_selfie.is_some_and(|x| x)
     }
 
+    /// Is the time the picture was taken only a guess from the file modification time?
+    pub fn is_taken_at_estimate(&self) -> bool {
+        self.taken_at_source
+            .is_some_and(|source| source.is_estimate())
+    }
+
+    /// How much of the time the item was taken is known.
+    /// Items without a recorded precision are taken to be exact.
+    pub fn taken_at_precision(&self) -> DatePrecision {
+        self.taken_at_precision.unwrap_or_default()
+    }
+
+    /// Is only the year, or the year and month, known for when the item was taken?
+    pub fn is_taken_at_imprecise(&self) -> bool {
+        self.taken_at_precision().is_imprecise()
+    }
+
+    /// First and last moments the item might have been taken.
+    /// An item dated to only a year could have been taken any time in that year.
+    pub fn taken_at_span(&self) -> (DateTime<Utc>, DateTime<Utc>) {
+        let end = self.taken_at_precision().period_end(self.ordering_ts);
+        (self.ordering_ts, end)
+    }
+
+    pub fn is_favorite(&self) -> bool {
+        self.is_favorite
+    }
+
     pub fn is_motion_photo(&self) -> bool {
         self.is_live_photo
     }
@@ -118,6 +201,16 @@ impl Visual {
         self.picture_id.is_none() && self.video_id.is_some()
     }
 
+    /// Is the item a video or an animated GIF? A still frame doesn't represent these well.
+    pub fn is_animated(&self) -> bool {
+        self.is_video_only()
+            || (self.is_photo_only()
+                && self
+                    .picture_path
+                    .as_ref()
+                    .is_some_and(|path| animated::is_gif(&path.sandbox_path)))
+    }
+
     pub fn year(&self) -> u32 {
         self.ordering_ts.date_naive().year_ce().1
     }
@@ -130,10 +223,16 @@ impl Visual {
         YearMonth { year, month }
     }
 
-    // TODO should really just compute this in photo_info.rs
+    /// Name of the containing directory.
     pub fn folder_name(&self) -> Option<String> {
-        self.parent_path
-            .file_name()
-            .map(|x| x.to_string_lossy().to_string())
+        folder::name(&self.parent_path)
+    }
+
+    /// Is the item directly in one of the library roots, rather than in a subdirectory?
+    pub fn is_in_library_root(&self, library_roots: &LibraryRoots) -> bool {
+        let sandbox_path = self.sandbox_path();
+        library_roots
+            .root_of(sandbox_path)
+            .is_some_and(|root| sandbox_path.parent() == Some(root.sandbox_path.as_path()))
     }
 }
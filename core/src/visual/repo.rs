@@ -2,17 +2,25 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use crate::FlatpakPathBuf;
+use crate::LibraryRoots;
+use crate::burst;
+use crate::database;
 use crate::photo::PictureId;
+use crate::photo::model::{DatePrecision, TakenAtSource};
+use crate::tag;
+use crate::user_album;
 use crate::video::VideoId;
 use crate::visual::model::{PictureOrientation, Visual, VisualId};
+use crate::visual::stats::{self, LibraryStats};
 
 use crate::path_encoding;
 use anyhow::*;
 use chrono::*;
 use h3o::LatLng;
 use rusqlite;
+use rusqlite::OptionalExtension;
 use rusqlite::Row;
+use std::collections::{HashMap, HashSet};
 use std::path;
 use std::path::PathBuf;
 use std::result::Result::Ok;
@@ -22,8 +30,8 @@ use std::sync::{Arc, Mutex};
 /// Repository is backed by a Sqlite database.
 #[derive(Debug, Clone)]
 pub struct Repository {
-    /// Base path to library
-    library_base_dir: FlatpakPathBuf,
+    /// Directories of the library
+    library_roots: LibraryRoots,
 
     /// Base path for transcoded videos
     cache_dir_base_path: path::PathBuf,
@@ -35,21 +43,113 @@ pub struct Repository {
 impl Repository {
     /// Builds a Repository and creates operational tables.
     pub fn open(
-        library_base_dir: &FlatpakPathBuf,
+        library_roots: &LibraryRoots,
         cache_dir_base_path: &path::Path,
         con: Arc<Mutex<rusqlite::Connection>>,
     ) -> Result<Repository> {
         let repo = Repository {
-            library_base_dir: library_base_dir.clone(),
+            library_roots: library_roots.clone(),
             cache_dir_base_path: cache_dir_base_path.into(),
             con,
         };
         Ok(repo)
     }
 
-    /// Gets all visual artefacts.
+    /// Gets all visual artefacts, except those with a trashed picture or from a
+    /// directory that has been removed from the library.
     pub fn all(&self) -> Result<Vec<Visual>> {
-        let con = self.con.lock().unwrap();
+        self.find(false)
+    }
+
+    /// Visual items with a trashed picture.
+    pub fn trashed(&self) -> Result<Vec<Visual>> {
+        self.find(true)
+    }
+
+    /// Counts and disk usage for the whole library.
+    /// Walks the thumbnails directory and reads the metadata of every original file,
+    /// so this is slow for large libraries.
+    pub fn stats(&self, thumbnails_path: &path::Path) -> Result<LibraryStats> {
+        let (photos, videos, oldest, newest, people) = {
+            let con = database::lock(&self.con);
+
+            let (photos, videos, oldest, newest) = con.query_row(
+                "SELECT
+                    COUNT(picture_id),
+                    COUNT(video_id),
+                    MIN(ordering_ts),
+                    MAX(ordering_ts)
+                FROM visual
+                WHERE trashed_at IS NULL",
+                [],
+                |row| {
+                    Ok((
+                        row.get::<_, u64>(0)?,
+                        row.get::<_, u64>(1)?,
+                        row.get::<_, Option<DateTime<Utc>>>(2)?,
+                        row.get::<_, Option<DateTime<Utc>>>(3)?,
+                    ))
+                },
+            )?;
+
+            let people = con.query_row(
+                "SELECT COUNT(*) FROM people WHERE is_ignored IS FALSE",
+                [],
+                |row| row.get::<_, u64>(0),
+            )?;
+
+            (photos, videos, oldest, newest, people)
+        };
+
+        let originals_size = self
+            .all()?
+            .iter()
+            .flat_map(|visual| [visual.picture_path.as_ref(), visual.video_path.as_ref()])
+            .flatten()
+            .filter_map(|path| std::fs::metadata(&path.sandbox_path).ok())
+            .map(|metadata| metadata.len())
+            .sum();
+
+        Ok(LibraryStats {
+            photos,
+            videos,
+            people,
+            originals_size,
+            thumbnails_size: stats::dir_size(thumbnails_path),
+            date_span: oldest.zip(newest),
+        })
+    }
+
+    /// Time the last scan of a library root started, or None if the root has never been scanned.
+    pub fn last_scan_time(&self, root: &path::Path) -> Result<Option<DateTime<Utc>>> {
+        let con = database::lock(&self.con);
+        let scanned_at = con
+            .query_row(
+                "SELECT scanned_at FROM library_scans WHERE root_path_b64 = ?1",
+                [path_encoding::to_base64(root)],
+                |row| row.get::<_, DateTime<Utc>>(0),
+            )
+            .optional()?;
+        Ok(scanned_at)
+    }
+
+    /// Record the time a scan of a library root started.
+    pub fn set_last_scan_time(
+        &mut self,
+        root: &path::Path,
+        scanned_at: DateTime<Utc>,
+    ) -> Result<()> {
+        let con = database::lock(&self.con);
+        con.execute(
+            "INSERT INTO library_scans (root_path_b64, scanned_at) VALUES (?1, ?2)
+            ON CONFLICT(root_path_b64) DO UPDATE SET scanned_at = excluded.scanned_at",
+            rusqlite::params![path_encoding::to_base64(root), scanned_at],
+        )?;
+        Ok(())
+    }
+
+    fn find(&self, is_trashed: bool) -> Result<Vec<Visual>> {
+        let con = database::lock(&self.con);
         let mut stmt = con.prepare(
             "SELECT
                     visual_id,
@@ -59,13 +159,21 @@ impl Repository {
                     picture_path_b64,
                     picture_orientation,
                     is_selfie,
+                    is_favorite,
+                    blurhash,
+                    picture_width,
+                    picture_height,
+                    picture_byte_size,
 
                     video_id,
                     video_path_b64,
+                    video_byte_size,
 
                     motion_photo_video_path,
 
                     ordering_ts,
+                    taken_at_source,
+                    taken_at_precision,
                     is_live_photo,
 
                     video_transcoded_path,
@@ -74,16 +182,107 @@ impl Repository {
                     video_rotation,
 
                     latitude,
-                    longitude
+                    longitude,
+                    place_name
                 FROM visual
+                WHERE (trashed_at IS NOT NULL) = ?1
                 ORDER BY ordering_ts ASC",
         )?;
 
-        let result = stmt.query_map([], |row| self.to_visual(row))?;
-        let visuals = result.flatten().collect();
+        let result = stmt.query_map([is_trashed], |row| self.to_visual(row))?;
+
+        let folder_covers = self.folder_covers(&con)?;
+
+        let hidden_folders = self.hidden_folders(&con)?;
+
+        let private_pictures = self.private_pictures(&con)?;
+
+        let mut tags = tag::all_tags(&con)?;
+
+        let mut user_albums = user_album::all_entries(&con)?;
+
+        let mut bursts = burst::all_frames(&con)?;
+
+        // Pictures from a removed directory are trashed by the next library scan,
+        // but videos can't be trashed so are hidden until the directory is added back.
+        let visuals = result
+            .flatten()
+            .filter(|visual| is_trashed || self.library_roots.contains(visual.sandbox_path()))
+            .map(|mut visual| {
+                visual.is_folder_cover = visual.picture_id.is_some()
+                    && folder_covers.get(&visual.parent_path) == visual.picture_id.as_ref();
+                visual.is_private = visual
+                    .picture_id
+                    .is_some_and(|picture_id| private_pictures.contains(&picture_id));
+                visual.hidden_folder = hidden_folders
+                    .iter()
+                    .find(|hidden| visual.parent_path.starts_with(hidden))
+                    .cloned();
+                visual.tags = visual
+                    .picture_id
+                    .and_then(|picture_id| tags.remove(&picture_id))
+                    .unwrap_or_default();
+                visual.user_albums = visual
+                    .picture_id
+                    .and_then(|picture_id| user_albums.remove(&picture_id))
+                    .unwrap_or_default();
+                visual.burst = visual
+                    .picture_id
+                    .and_then(|picture_id| bursts.remove(&picture_id));
+                visual
+            })
+            .collect();
         Ok(visuals)
     }
 
+    /// Pictures chosen as folder covers, by folder path.
+    fn folder_covers(&self, con: &rusqlite::Connection) -> Result<HashMap<PathBuf, PictureId>> {
+        let mut stmt = con.prepare("SELECT folder_path_b64, picture_id FROM folder_covers")?;
+
+        let covers = stmt
+            .query_map([], |row| {
+                let folder_path: String = row.get(0)?;
+                let picture_id = PictureId::new(row.get(1)?);
+                Ok((folder_path, picture_id))
+            })?
+            .flatten()
+            .filter_map(|(folder_path, picture_id)| {
+                path_encoding::from_base64(&folder_path)
+                    .ok()
+                    .map(|folder_path| (folder_path, picture_id))
+            })
+            .collect();
+
+        Ok(covers)
+    }
+
+    /// Folders hidden from the folders album, outermost first.
+    fn hidden_folders(&self, con: &rusqlite::Connection) -> Result<Vec<PathBuf>> {
+        let mut stmt = con.prepare("SELECT folder_path_b64 FROM hidden_folders")?;
+
+        let mut hidden: Vec<PathBuf> = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .flatten()
+            .filter_map(|folder_path| path_encoding::from_base64(&folder_path).ok())
+            .collect();
+
+        hidden.sort_by_key(|folder_path| folder_path.components().count());
+
+        Ok(hidden)
+    }
+
+    /// Pictures the user has marked as private.
+    fn private_pictures(&self, con: &rusqlite::Connection) -> Result<HashSet<PictureId>> {
+        let mut stmt = con.prepare("SELECT picture_id FROM pictures WHERE is_private IS TRUE")?;
+
+        let private = stmt
+            .query_map([], |row| row.get(0).map(PictureId::new))?
+            .flatten()
+            .collect();
+
+        Ok(private)
+    }
+
     fn to_visual(&self, row: &Row<'_>) -> rusqlite::Result<Visual> {
         let visual_id = row
             .get("visual_id")
@@ -93,7 +292,7 @@ impl Repository {
         let link_path: String = row.get("link_path_b64")?;
         let link_path =
             path_encoding::from_base64(&link_path).map_err(|_| rusqlite::Error::InvalidQuery)?;
-        let link_path = self.library_base_dir.host_path.join(link_path);
+        let link_path = self.library_roots.resolve(&link_path).host_path;
 
         let picture_id: Option<PictureId> = row.get("picture_id").map(PictureId::new).ok();
 
@@ -102,20 +301,7 @@ impl Repository {
             .ok()
             .and_then(|x: String| path_encoding::from_base64(&x).ok());
 
-        let picture_host_path = picture_relative_path
-            .as_ref()
-            .map(|x| self.library_base_dir.host_path.join(x));
-
-        let picture_sandbox_path =
-            picture_relative_path.map(|x| self.library_base_dir.sandbox_path.join(x));
-
-        let picture_path = if let (Some(host_path), Some(sandbox_path)) =
-            (picture_host_path, picture_sandbox_path)
-        {
-            Some(FlatpakPathBuf::build(host_path, sandbox_path))
-        } else {
-            None
-        };
+        let picture_path = picture_relative_path.map(|x| self.library_roots.resolve(&x));
 
         let picture_orientation: Option<PictureOrientation> = row
             .get("picture_orientation")
@@ -124,6 +310,16 @@ impl Repository {
 
         let is_selfie: Option<bool> = row.get("is_selfie").ok();
 
+        let is_favorite: bool = row.get("is_favorite").unwrap_or(false);
+
+        let blurhash: Option<String> = row.get("blurhash").ok().flatten();
+
+        let picture_width: Option<u32> = row.get("picture_width").ok().flatten();
+        let picture_height: Option<u32> = row.get("picture_height").ok().flatten();
+        let picture_dimensions = picture_width.zip(picture_height);
+
+        let picture_byte_size: Option<u64> = row.get("picture_byte_size").ok().flatten();
+
         let video_id: Option<VideoId> = row.get("video_id").map(VideoId::new).ok();
 
         let video_relative_path: Option<PathBuf> = row
@@ -131,19 +327,9 @@ impl Repository {
             .ok()
             .and_then(|x: String| path_encoding::from_base64(&x).ok());
 
-        let video_host_path = video_relative_path
-            .as_ref()
-            .map(|x| self.library_base_dir.host_path.join(x));
-
-        let video_sandbox_path =
-            video_relative_path.map(|x| self.library_base_dir.sandbox_path.join(x));
+        let video_path = video_relative_path.map(|x| self.library_roots.resolve(&x));
 
-        let video_path =
-            if let (Some(host_path), Some(sandbox_path)) = (video_host_path, video_sandbox_path) {
-                Some(FlatpakPathBuf::build(host_path, sandbox_path))
-            } else {
-                None
-            };
+        let video_byte_size: Option<u64> = row.get("video_byte_size").ok().flatten();
 
         let video_orientation: Option<PictureOrientation> = row
             .get("video_rotation")
@@ -158,6 +344,11 @@ impl Repository {
 
         let ordering_ts: DateTime<Utc> = row.get("ordering_ts").expect("Must have ordering_ts");
 
+        let taken_at_source: Option<TakenAtSource> = row.get("taken_at_source").ok().flatten();
+
+        let taken_at_precision: Option<DatePrecision> =
+            row.get("taken_at_precision").ok().flatten();
+
         let is_live_photo: Option<bool> = row.get("is_live_photo").ok();
 
         let is_live_photo = is_live_photo.is_some_and(|x| x);
@@ -184,6 +375,8 @@ impl Repository {
             None
         };
 
+        let place_name: Option<String> = row.get("place_name").ok().flatten();
+
         let v = Visual {
             visual_id,
             parent_path: link_path.parent().map(PathBuf::from).expect("Parent path"),
@@ -193,15 +386,338 @@ impl Repository {
             video_id,
             video_path,
             ordering_ts,
+            taken_at_source,
+            taken_at_precision,
             is_selfie,
             is_live_photo,
+            is_favorite,
+            is_private: false,
             video_transcoded_path,
             video_orientation,
             is_transcode_required,
             video_duration,
             motion_photo_video_path,
             location,
+            place_name,
+            blurhash,
+            picture_dimensions,
+            picture_byte_size,
+            video_byte_size,
+            is_folder_cover: false,
+            hidden_folder: None,
+            tags: Vec::new(),
+            user_albums: Vec::new(),
+            burst: None,
         };
         Ok(v)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::photo;
+    use crate::video;
+    use crate::{FlatpakPathBuf, ScannedFile};
+    use tempfile::TempDir;
+
+    fn test_repo() -> (TempDir, Repository) {
+        let dir = tempfile::tempdir().unwrap();
+        let root = FlatpakPathBuf::build(dir.path(), dir.path());
+        let library_roots = LibraryRoots::build(&root, &[]);
+        let con = Arc::new(Mutex::new(database::setup_in_memory().unwrap()));
+        let repo = Repository::open(&library_roots, dir.path(), con).unwrap();
+        (dir, repo)
+    }
+
+    #[test]
+    fn all_works_after_poisoning() {
+        let (_dir, repo) = test_repo();
+        let con = repo.con.clone();
+
+        // Simulate a background task panicking while using the database.
+        let _ = std::thread::spawn(move || {
+            let _con = con.lock().unwrap();
+            panic!("Poison the connection mutex");
+        })
+        .join();
+
+        assert!(repo.all().unwrap().is_empty());
+    }
+
+    #[test]
+    fn all_fails_without_panicking_on_database_error() {
+        let (_dir, repo) = test_repo();
+
+        repo.con
+            .lock()
+            .unwrap()
+            .execute_batch("DROP VIEW visual")
+            .unwrap();
+
+        assert!(repo.all().is_err());
+        assert!(repo.trashed().is_err());
+    }
+
+    #[test]
+    fn all_hides_videos_from_removed_root() {
+        let con = Arc::new(Mutex::new(database::setup_in_memory().unwrap()));
+        let cache_dir = std::env::temp_dir();
+        let primary = FlatpakPathBuf::build("/home/user/Pictures", "/home/user/Pictures");
+        let nas = FlatpakPathBuf::build("/mnt/nas", "/mnt/nas");
+
+        let with_nas = LibraryRoots::build(&primary, &[nas]);
+        let mut video_repo =
+            video::Repository::open(&with_nas, &cache_dir, &cache_dir, con.clone()).unwrap();
+        video_repo
+            .add_all(&vec![ScannedFile::Video(PathBuf::from(
+                "/mnt/nas/clip.mp4",
+            ))])
+            .unwrap();
+
+        let repo = Repository::open(&with_nas, &cache_dir, con.clone()).unwrap();
+        assert_eq!(1, repo.all().unwrap().len());
+
+        let without_nas = LibraryRoots::build(&primary, &[]);
+        let repo = Repository::open(&without_nas, &cache_dir, con.clone()).unwrap();
+        assert!(repo.all().unwrap().is_empty());
+    }
+
+    #[test]
+    fn all_has_file_sizes_recorded_by_scan() {
+        let (dir, repo) = test_repo();
+
+        let video_path = dir.path().join("clip.mp4");
+        std::fs::write(&video_path, [0u8; 42]).unwrap();
+
+        let mut video_repo = video::Repository::open(
+            &repo.library_roots,
+            dir.path(),
+            dir.path(),
+            repo.con.clone(),
+        )
+        .unwrap();
+        video_repo
+            .add_all(&vec![ScannedFile::Video(video_path)])
+            .unwrap();
+
+        let visuals = repo.all().unwrap();
+        assert_eq!(Some(42), visuals[0].video_byte_size);
+        assert_eq!(Some(42), visuals[0].byte_size());
+    }
+
+    #[test]
+    fn stats_counts_library() {
+        let (dir, repo) = test_repo();
+        let roots = repo.library_roots.clone();
+
+        let empty = repo.stats(&dir.path().join("thumbnails")).unwrap();
+        assert_eq!(0, empty.photos);
+        assert_eq!(None, empty.date_span);
+
+        let pictures = [("a.jpg", 10), ("b.jpg", 20), ("trashed.jpg", 30)];
+        for (name, size) in pictures {
+            std::fs::write(dir.path().join(name), vec![0u8; size]).unwrap();
+        }
+        std::fs::write(dir.path().join("clip.mp4"), [0u8; 42]).unwrap();
+
+        let mut photo_repo =
+            photo::Repository::open(&roots, dir.path(), dir.path(), repo.con.clone()).unwrap();
+        photo_repo
+            .add_all(
+                &pictures
+                    .iter()
+                    .map(|(name, _)| ScannedFile::Photo(dir.path().join(name)))
+                    .collect(),
+            )
+            .unwrap();
+        let trashed = photo_repo
+            .all()
+            .unwrap()
+            .into_iter()
+            .find(|pic| pic.sandbox_path().ends_with("trashed.jpg"))
+            .unwrap();
+        photo_repo.trash(&[trashed.picture_id]).unwrap();
+
+        let mut video_repo =
+            video::Repository::open(&roots, dir.path(), dir.path(), repo.con.clone()).unwrap();
+        video_repo
+            .add_all(&vec![ScannedFile::Video(dir.path().join("clip.mp4"))])
+            .unwrap();
+
+        repo.con
+            .lock()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO people (name, is_ignored) VALUES
+                    ('Alice', FALSE), ('Bob', FALSE), ('Stranger', TRUE)",
+            )
+            .unwrap();
+
+        let thumbnails_path = dir.path().join("thumbnails");
+        std::fs::create_dir_all(thumbnails_path.join("large")).unwrap();
+        std::fs::write(thumbnails_path.join("large").join("a.png"), [0u8; 7]).unwrap();
+
+        let stats = repo.stats(&thumbnails_path).unwrap();
+        assert_eq!(2, stats.photos);
+        assert_eq!(1, stats.videos);
+        assert_eq!(2, stats.people);
+        assert_eq!(10 + 20 + 42, stats.originals_size);
+        assert_eq!(7, stats.thumbnails_size);
+        assert!(
+            stats
+                .date_span
+                .is_some_and(|(oldest, newest)| oldest <= newest)
+        );
+    }
+
+    #[test]
+    fn is_in_library_root_for_each_root() {
+        let con = Arc::new(Mutex::new(database::setup_in_memory().unwrap()));
+        let dir = tempfile::tempdir().unwrap();
+        let pictures_dir = dir.path().join("Pictures");
+        let nas_dir = dir.path().join("nas");
+        let roots = LibraryRoots::build(
+            &FlatpakPathBuf::build(&pictures_dir, &pictures_dir),
+            &[FlatpakPathBuf::build(&nas_dir, &nas_dir)],
+        );
+
+        let mut photo_repo =
+            photo::Repository::open(&roots, dir.path(), dir.path(), con.clone()).unwrap();
+        photo_repo
+            .add_all(&vec![
+                ScannedFile::Photo(pictures_dir.join("top.jpg")),
+                ScannedFile::Photo(pictures_dir.join("Holiday/nested.jpg")),
+                ScannedFile::Photo(nas_dir.join("top.jpg")),
+                ScannedFile::Photo(nas_dir.join("Holiday/nested.jpg")),
+            ])
+            .unwrap();
+
+        let repo = Repository::open(&roots, dir.path(), con).unwrap();
+        let mut in_root: Vec<_> = repo
+            .all()
+            .unwrap()
+            .iter()
+            .map(|visual| {
+                (
+                    visual.sandbox_path().clone(),
+                    visual.is_in_library_root(&roots),
+                )
+            })
+            .collect();
+        in_root.sort();
+
+        assert_eq!(
+            vec![
+                (pictures_dir.join("Holiday/nested.jpg"), false),
+                (pictures_dir.join("top.jpg"), true),
+                (nas_dir.join("Holiday/nested.jpg"), false),
+                (nas_dir.join("top.jpg"), true),
+            ],
+            in_root
+        );
+    }
+
+    #[test]
+    fn last_scan_time_round_trip() {
+        let (_dir, mut repo) = test_repo();
+
+        let pictures = path::Path::new("/home/user/Pictures");
+        let nas = path::Path::new("/mnt/nas/Photos");
+
+        assert_eq!(None, repo.last_scan_time(pictures).unwrap());
+
+        let first = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        repo.set_last_scan_time(pictures, first).unwrap();
+        assert_eq!(Some(first), repo.last_scan_time(pictures).unwrap());
+
+        let second = DateTime::from_timestamp(1_800_000_000, 0).unwrap();
+        repo.set_last_scan_time(pictures, second).unwrap();
+        assert_eq!(Some(second), repo.last_scan_time(pictures).unwrap());
+
+        // Each root is scanned separately.
+        assert_eq!(None, repo.last_scan_time(nas).unwrap());
+    }
+
+    #[test]
+    fn all_marks_items_in_hidden_folders() {
+        let (dir, repo) = test_repo();
+        let roots = repo.library_roots.clone();
+
+        let mut video_repo =
+            video::Repository::open(&roots, dir.path(), dir.path(), repo.con.clone()).unwrap();
+        video_repo
+            .add_all(&vec![
+                ScannedFile::Video(dir.path().join("Screenshots/2024/a.mp4")),
+                ScannedFile::Video(dir.path().join("Holiday/b.mp4")),
+            ])
+            .unwrap();
+
+        let mut photo_repo =
+            photo::Repository::open(&roots, dir.path(), dir.path(), repo.con.clone()).unwrap();
+        let hidden_folders = || {
+            let mut hidden: Vec<_> = repo
+                .all()
+                .unwrap()
+                .into_iter()
+                .map(|visual| (visual.parent_path, visual.hidden_folder))
+                .collect();
+            hidden.sort();
+            hidden
+        };
+
+        // Parent paths are full paths, so folders are hidden by full path.
+        let screenshots = dir.path().join("Screenshots");
+        let screenshots_2024 = screenshots.join("2024");
+
+        photo_repo
+            .set_folder_hidden(&screenshots_2024, true)
+            .unwrap();
+        photo_repo.set_folder_hidden(&screenshots, true).unwrap();
+        assert_eq!(
+            vec![
+                (dir.path().join("Holiday"), None),
+                (screenshots_2024.clone(), Some(screenshots.clone())),
+            ],
+            hidden_folders()
+        );
+
+        // Showing a folder also shows the hidden folder containing it.
+        photo_repo
+            .set_folder_hidden(&screenshots_2024, false)
+            .unwrap();
+        assert!(hidden_folders().iter().all(|(_, hidden)| hidden.is_none()));
+    }
+
+    #[test]
+    fn all_marks_private_pictures() {
+        let (dir, repo) = test_repo();
+        let roots = repo.library_roots.clone();
+
+        let mut photo_repo =
+            photo::Repository::open(&roots, dir.path(), dir.path(), repo.con.clone()).unwrap();
+        photo_repo
+            .add_all(&vec![
+                ScannedFile::Photo(dir.path().join("a.jpg")),
+                ScannedFile::Photo(dir.path().join("b.jpg")),
+            ])
+            .unwrap();
+
+        let private = || {
+            repo.all()
+                .unwrap()
+                .into_iter()
+                .filter(|visual| visual.is_private)
+                .filter_map(|visual| visual.picture_id)
+                .collect::<Vec<_>>()
+        };
+        assert!(private().is_empty());
+
+        let picture_id = repo.all().unwrap()[0].picture_id.unwrap();
+        photo_repo.set_private(&picture_id, true).unwrap();
+        assert_eq!(vec![picture_id], private());
+
+        photo_repo.set_private(&picture_id, false).unwrap();
+        assert!(private().is_empty());
+    }
+}
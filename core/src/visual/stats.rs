@@ -0,0 +1,61 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use chrono::{DateTime, Utc};
+use std::path::Path;
+use walkdir::WalkDir;
+
+/// Overview of the whole library. Trashed items are not counted.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LibraryStats {
+    /// Number of photos.
+    pub photos: u64,
+
+    /// Number of videos, including the video part of live photos.
+    pub videos: u64,
+
+    /// Number of people, not including ignored people.
+    pub people: u64,
+
+    /// Bytes used by original photo and video files.
+    pub originals_size: u64,
+
+    /// Bytes used by generated thumbnails.
+    pub thumbnails_size: u64,
+
+    /// Capture timestamps of the oldest and newest items. None for an empty library.
+    pub date_span: Option<(DateTime<Utc>, DateTime<Utc>)>,
+}
+
+/// Total size in bytes of all files under a directory.
+pub fn dir_size(path: &Path) -> u64 {
+    WalkDir::new(path)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dir_size_sums_nested_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.png"), b"12345").unwrap();
+        std::fs::create_dir(dir.path().join("large")).unwrap();
+        std::fs::write(dir.path().join("large").join("b.png"), b"123").unwrap();
+
+        assert_eq!(8, dir_size(dir.path()));
+    }
+
+    #[test]
+    fn dir_size_of_missing_dir_is_zero() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(0, dir_size(&dir.path().join("missing")));
+    }
+}
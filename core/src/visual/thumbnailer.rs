@@ -0,0 +1,54 @@
+// SPDX-FileCopyrightText: © 2025 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Regenerates thumbnails that were evicted from the thumbnail cache, when they are
+//! next shown.
+
+use crate::photo::PhotoThumbnailer;
+use crate::thumbnailify::{self, ThumbnailSize};
+use crate::video::VideoThumbnailer;
+use crate::visual::Visual;
+
+use anyhow::*;
+use futures::executor::block_on;
+use std::path::PathBuf;
+
+/// Thumbnail operations for pictures and videos alike.
+#[derive(Debug, Clone)]
+pub struct VisualThumbnailer {
+    thumbnailer: thumbnailify::Thumbnailer,
+    photo_thumbnailer: PhotoThumbnailer,
+    video_thumbnailer: VideoThumbnailer,
+}
+
+impl VisualThumbnailer {
+    pub fn build(thumbnailer: thumbnailify::Thumbnailer) -> Result<VisualThumbnailer> {
+        Ok(VisualThumbnailer {
+            photo_thumbnailer: PhotoThumbnailer::build(thumbnailer.clone())?,
+            video_thumbnailer: VideoThumbnailer::build(thumbnailer.clone())?,
+            thumbnailer,
+        })
+    }
+
+    /// Regenerates all sizes of an evicted thumbnail and returns the path of the
+    /// thumbnail nearest the requested size.
+    /// Blocks until the thumbnail is written, so don't call from the main thread.
+    pub fn regenerate(&self, visual: &Visual, size: ThumbnailSize) -> Result<PathBuf> {
+        if let Some(ref picture_path) = visual.picture_path {
+            // Blurhash was stored when the picture was first thumbnailed.
+            // Without the library, there is no stored hash to find a copy's thumbnails by.
+            let _ = block_on(self.photo_thumbnailer.thumbnail(picture_path, None))?;
+        } else if let Some(ref video_path) = visual.video_path {
+            self.video_thumbnailer
+                .thumbnail(video_path, visual.video_duration)?;
+        }
+
+        let hash = visual.thumbnail_hash();
+        self.thumbnailer.clear_evicted(&hash)?;
+
+        self.thumbnailer
+            .nearest_thumbnail(&hash, size)
+            .ok_or_else(|| anyhow!("No thumbnail regenerated for {:?}", visual.host_path()))
+    }
+}
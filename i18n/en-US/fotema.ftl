@@ -27,12 +27,244 @@ selfies-album = Selfies
 # Title for album of iOS live photos and Android motion photos.
 animated-album = Animated
 
+# Title for album showing photos starred as favorites.
+favorites-album = Favorites
+
+# Title for album showing photos taken on today's date in previous years.
+on-this-day-album = On This Day
+
 # Title for album showing all folders.
 folders-album = Folders
 
 # Title for album showing contents of one folder.
 folder-album = Folder
 
+# Name for photos and videos at the top of the library, rather than in a folder.
+folder-library-root = Library
+
+# Number of photos and videos in a folder, shown under the folder name.
+# Variables:
+#   $count - number of items, for selecting plural form.
+#   $formatted_count - number of items formatted with thousands separators.
+folder-item-count = { $count ->
+    [one] {$formatted_count} item
+   *[other] {$formatted_count} items
+}
+
+# Menu shown when right-clicking a folder.
+# Attributes:
+#   .open - menu item to show the photos and videos in the folder.
+#   .reveal - menu item that shows the folder in the file manager.
+#   .copy-path - menu item that copies the path of the folder.
+#   .hide - toggle menu item that hides the folder from the folders album.
+folder-item-menu =
+  .open = Open
+  .reveal = Show in Files
+  .copy-path = Copy Path
+  .hide = Hide
+
+# Popover for choosing a range of dates to show photos and videos for.
+# Attributes:
+#   .tooltip - tooltip text for button that shows the popover.
+#   .from - label for toggling the start date of the range.
+#   .to - label for toggling the end date of the range.
+#   .apply - label for button that shows photos and videos in the range.
+date-range-picker =
+  .tooltip = Show photos and videos between dates
+  .from = From
+  .to = To
+  .apply = Show
+
+# Menu for narrowing the photos and videos shown in an album.
+# Attributes:
+#   .tooltip - tooltip text for button that shows the menu.
+#   .photos - menu item to only show photos.
+#   .videos - menu item to only show videos.
+#   .favorites - menu item to only show favorites.
+album-filter-menu =
+  .tooltip = Filter photos and videos
+  .photos = Only Photos
+  .videos = Only Videos
+  .favorites = Only Favorites
+  .private = Only Private
+
+# Searching for photos and videos by file name.
+# Attributes:
+#   .tooltip - tooltip text for button that starts a search of the whole library.
+#   .placeholder - placeholder text for the search entry.
+album-search =
+  .tooltip = Search by file name
+  .placeholder = Search file names
+
+# Showing an album fullscreen, one photo or video after another.
+# Attributes:
+#   .tooltip - tooltip text for button that starts a slideshow.
+#   .exit - tooltip text for button that leaves the slideshow.
+album-slideshow =
+  .tooltip = Slideshow
+  .exit = Exit Slideshow
+
+# Selecting several photos and videos in an album at once.
+# Attributes:
+#   .tooltip - tooltip text for button that toggles selection mode.
+#   .select-all - label for button that selects every item.
+#   .select-none - label for button that deselects every item.
+#   .favorite - tooltip text for button that stars selected pictures as favorites.
+#   .trash - tooltip text for button that moves selected pictures to the trash.
+#   .move - tooltip text for button that moves selected pictures to another folder.
+#   .person - tooltip text for button that names the person in selected pictures.
+#   .restore - label for button that restores selected pictures from the trash.
+album-selection =
+  .tooltip = Select photos and videos
+  .select-all = Select All
+  .select-none = Select None
+  .favorite = Add to favorites
+  .trash = Move to trash
+  .move = Move to folder
+  .person = Assign to person
+  .restore = Restore
+
+# Number of selected photos and videos.
+# Variables:
+#   $count - number of selected items.
+album-selection-count = { $count ->
+    [0] Nothing selected
+    [one] {$count} selected
+   *[other] {$count} selected
+}
+
+# Title of folder chooser for moving selected photos to another folder
+album-move-folder-dialog = Move photos to folder
+
+# Menu shown when right-clicking a photo or video.
+# Attributes:
+#   .open - menu item to view the item.
+#   .info - menu item that shows basic facts about the file of the item.
+#   .folder-cover - menu item that makes a picture the cover of its folder in the
+#                   folders album.
+#   .favorite - menu item that stars or unstars a picture as a favorite.
+#   .regenerate-thumbnail - menu item that deletes and remakes the thumbnail of a
+#                           picture, such as when it looks wrong.
+#   .collapse-burst - menu item that shows the pictures of an expanded burst as one
+#                     stacked item again.
+#   .ungroup-burst - menu item that shows the pictures of a burst as separate pictures
+#                    from now on.
+#   .open-default - menu item that opens the file in the default app for its type.
+#   .open-with - menu item that asks which app to open the file in.
+#   .reveal - menu item that shows the file in the file manager.
+#   .copy-path - menu item that copies the path of the file.
+#   .trash - menu item that moves a picture to the trash.
+album-item-menu =
+  .open = Open
+  .info = Show Info
+  .folder-cover = Set as Folder Cover
+  .favorite = Toggle Favorite
+  .regenerate-thumbnail = Regenerate Thumbnail
+  .collapse-burst = Collapse Burst
+  .ungroup-burst = Ungroup Burst
+  .open-default = Open in Default App
+  .open-with = Open With…
+  .reveal = Show in Files
+  .copy-path = Copy Path
+  .trash = Move to Trash
+
+# Tooltip of the badge on a stacked item standing for a burst of pictures taken in
+# quick succession. Clicking the item shows every picture of the burst.
+# Variables:
+#   $count - number of pictures in the burst.
+album-item-burst = { $count ->
+    [one] Burst of {$count} picture
+   *[other] Burst of {$count} pictures
+}
+
+# Notifications when a file couldn't be shown or opened outside of Fotema.
+# Attributes:
+#   .reveal - no file manager could show the file.
+#   .open - no app could open the file.
+album-item-error =
+  .reveal = No file manager available to show the file
+  .open = No app available to open the file
+
+# Panel shown over an album with basic facts about the file of a photo or video.
+# Attributes:
+#   .close - tooltip text for button that hides the panel.
+#   .file-name - name of the file.
+#   .dimensions - width and height of the picture in pixels.
+#   .dimensions-unknown - shown when the picture couldn't be read to find its dimensions.
+#   .file-size - size of the file, such as "2.4 MB".
+#   .file-size-unknown - shown when the size of the file isn't known.
+#   .path - full path of the file.
+#   .favorite - has the user starred the item as a favorite?
+#   .broken - did making a thumbnail of the item fail?
+album-info =
+  .close = Hide Info
+  .file-name = File name: { $name }
+  .dimensions = Dimensions: { $width } × { $height }
+  .dimensions-unknown = Dimensions: unknown
+  .file-size = File size: { $size }
+  .file-size-unknown = File size: unknown
+  .path = Path: { $path }
+  .favorite = Favorite: { $is_favorite ->
+      [yes] Yes
+     *[no] No
+  }
+  .broken = Broken: { $is_broken ->
+      [yes] Yes
+     *[no] No
+  }
+
+# Removable chips describing each filter applied to an album.
+# Attributes:
+#   .remove - tooltip text for removing a filter.
+#   .clear - label for button that removes all filters.
+#   .photos - chip for filter showing only photos.
+#   .videos - chip for filter showing only videos.
+#   .favorites - chip for filter showing only favorites.
+#   .motion - chip for filter showing only motion photos.
+#   .selfies - chip for filter showing only selfies.
+#   .tag - chip for filter showing only pictures with a tag. $name is the tag name.
+album-filter-chip =
+  .remove = Remove filter
+  .clear = Clear All
+  .photos = Photos
+  .videos = Videos
+  .favorites = Favorites
+  .private = Private
+  .motion = Motion Photos
+  .selfies = Selfies
+  .tag = Tag: { $name }
+
+# Title for page showing trashed photos.
+# Attributes:
+#   .empty - label for button that permanently deletes trashed photos.
+trash-page = Trash
+  .empty = Empty Trash
+
+# Dialog to ask whether to detect faces again after changing the face detection model.
+redetect-faces-dialog =
+  .heading = Detect faces again?
+  .body = Faces found by the previous model can't be grouped with faces found by the new one. Photos without named people will be scanned for faces again.
+  .cancel-button = Not Now
+  .redetect-button = Detect Faces
+
+# Dialog to ask whether to move existing thumbnails after changing where thumbnails are saved.
+relocate-thumbnails-dialog =
+  .heading = Move existing thumbnails?
+  .body = Thumbnails will now be saved in the new location. Existing thumbnails can be moved there, or left where they are and made again.
+  .keep-button = Make Again
+  .move-button = Move Thumbnails
+
+# Dialog to confirm emptying the trash.
+# Attributes:
+#   .remove-button - Remove photos from the library, but keep their files.
+#   .delete-button - Remove photos from the library and delete their files.
+empty-trash-dialog =
+  .heading = Empty trash?
+  .body = Photos you moved to the trash can be permanently deleted from disk, or only removed from the library. Photos trashed because they are no longer in the library are never deleted from disk.
+  .cancel-button = Cancel
+  .remove-button = Remove from Library
+  .delete-button = Delete
+
 # Title for places page which shows photos overlayed onto a map.
 places-page = Places
 
@@ -52,6 +284,20 @@ people-page-status-no-people =
   .description = { -app-name } will look for faces in new photos when launched.
   Name the people in your photos so { -app-name } can make an album for each person.
 
+# Toggle at bottom of people album to also show people the user has hidden.
+people-page-show-hidden = Show hidden people
+
+# Label for a suggested person made from a group of similar unknown faces.
+# Variables:
+#   $count - number of faces in group.
+people-page-suggested-person = { $count ->
+    [one] Unknown, one face
+   *[other] Unknown, { $count } faces
+}
+
+# Heading above people suggested from groups of similar unknown faces.
+people-page-suggestions = Unknown people
+
 ## Thumbnail decorations
 
 # Label on month album thumbnails.
@@ -95,6 +341,7 @@ viewer-faces-menu =
   .restore-ignored = Restore all ignored faces
   .ignore-unknown = Ignore all unknown faces
   .scan = Scan for more faces
+  .show-bounds = Outline detected faces
 
 # Go to next button when viewing photo or video.
 # Attributes:
@@ -147,6 +394,13 @@ viewer-error-failed-to-load = Failed to load
 viewer-error-missing-file = Cannot display file because it is missing:
   {$file_name}
 
+# Viewer could not display an image or video because it couldn't be reached,
+# such as on a disconnected network share. It might be back later.
+# Variables:
+#  file_name - (String) path of unavailable file.
+viewer-error-unavailable-file = Cannot display file because it is unavailable:
+  {$file_name}
+
 # Viewer could not display a file because database entry doesn't have file path.
 # If this situation occurs, then I've mucked up the SQL view query and a bug should
 # be raised.
@@ -163,6 +417,24 @@ infobar-folder = Folder
 # File name of photo or video
 infobar-file-name = File Name
 
+# When photo or video was taken. Albums are sorted by this time.
+# Attributes:
+#  .estimate - shown when the time is only a guess from when the file was last modified. $timestamp is the time.
+#  .imprecise - shown when only the year, or year and month, is known. $timestamp is the year or month, such as "1985-06".
+#  .month-tooltip - tooltip text for button showing all photos taken in the same month.
+#  .year-tooltip - tooltip text for button showing all photos taken in the same year, when only the year is known.
+infobar-taken-at = Date Taken
+  .estimate = { $timestamp } (estimated from file)
+  .imprecise = { $timestamp } (approximate)
+  .month-tooltip = Show All Photos Taken the Same Month
+  .year-tooltip = Show All Photos Taken the Same Year
+
+# Name of place, such as "Paris, France", where photo was taken.
+# Attributes:
+#  .tooltip - tooltip text for button showing all photos taken in the same place.
+infobar-place = Place
+  .tooltip = Show All Photos Taken Here
+
 # File creation timestamp from file system metadata.
 infobar-file-created = File Created
 
@@ -181,6 +453,24 @@ infobar-originally-created = Originally Created
 # File modification timestamp from image or video embedded metadata.
 infobar-originally-modified = Originally Modified
 
+# Camera make and model from image embedded metadata.
+infobar-camera = Camera
+
+# Lens model from image embedded metadata.
+infobar-lens = Lens
+
+# Aperture f-number from image embedded metadata.
+infobar-aperture = Aperture
+
+# Exposure time (shutter speed) from image embedded metadata.
+infobar-exposure-time = Exposure Time
+
+# Lens focal length from image embedded metadata.
+infobar-focal-length = Focal Length
+
+# ISO sensitivity from image embedded metadata.
+infobar-iso = ISO
+
 # Duration (HH:MM) of video.
 infobar-video-duration = Duration
 
@@ -196,6 +486,43 @@ infobar-audio-codec = Audio Codec
 # Width and height of photo or video.
 infobar-dimensions = Dimensions
 
+# Freeform tags the user has given a picture, such as "Holiday".
+# Attributes:
+#   .add - title of text entry for adding a tag.
+#   .view-tooltip - tooltip text for a tag, which shows all pictures with the tag.
+#   .remove-tooltip - tooltip text for button that removes a tag from the picture.
+infobar-tags =
+  .add = Add Tag
+  .view-tooltip = Show all pictures with this tag
+  .remove-tooltip = Remove tag
+
+# Albums the user has put a picture in, such as "Wedding". An album keeps its
+# pictures in the order the user drags them into.
+# Attributes:
+#   .add - title of text entry for adding the picture to an album by name. An album
+#          is made if there isn't one with the name.
+#   .view-tooltip - tooltip text for an album, which shows all pictures in the album.
+#   .cover - subtitle of an album the picture is the cover of.
+#   .cover-tooltip - tooltip text for button that makes the picture the album cover.
+#   .remove-tooltip - tooltip text for button that takes the picture out of an album.
+infobar-albums =
+  .add = Add to Album
+  .view-tooltip = Show all pictures in this album
+  .cover = Album cover
+  .cover-tooltip = Use as album cover
+  .remove-tooltip = Remove from album
+
+# Row that shows photos that look like the photo being viewed, such as resized
+# or edited copies.
+# Attributes:
+#  .tooltip - tooltip text for the row.
+infobar-similar = Similar Photos
+  .tooltip = Show Photos That Look Like This One
+
+# Switch to mark a photo private. Private photos are hidden or blurred until unlocked.
+infobar-private = Private
+  .subtitle = Hide until private photos are unlocked
+
 ## Faces and People
 
 # Menu item to mark a face as the most import face for a person
@@ -210,6 +537,11 @@ people-set-name = Set name
 people-person-search =
         .placeholder = Person name
 
+# Subtitle of the person most like a face, shown first when choosing
+# who a face is.
+# $confidence (Number) - how alike the face is to the person, as a percentage.
+people-person-suggested = Suggested, { $confidence }% match
+
 # Menu item to ignore a face match because it is a random face or not a face.
 people-face-ignore = Ignore
 
@@ -233,11 +565,44 @@ prefs-albums-section = Albums
 prefs-albums-selfies = Selfies
   .subtitle = Shows a separate album for selfies taken on iOS devices. Restart {-app-name} to apply.
 
+# Animated previews enabled or disabled.
+# Attributes:
+#   .subtitle - Description of toggle button action.
+prefs-albums-animated-previews = Animated previews
+  .subtitle = Play a short preview of videos and GIFs when the pointer is over them. Uses more disk space and processing time.
+
+# Number of seconds each picture is shown for in a slideshow.
+prefs-albums-slideshow-interval = Slideshow interval
+  .subtitle = Seconds each picture is shown for. Videos play through before moving on.
+
+# How different photos may be and still be shown as similar to a photo.
+prefs-albums-similar-distance = Similar photo difference
+  .subtitle = How different photos may look and still be shown as similar. Zero shows only photos that look the same.
+
 # Album sort drop-down menu
-prefs-albums-chronological-sort = Sort order
-  .subtitle = Chronological sort order for albums.
-  .ascending = Ascending
-  .descending = Descending
+prefs-albums-sort = Sort order
+  .subtitle = Order of photos and videos in albums.
+  .capture-date-ascending = Oldest First
+  .capture-date-descending = Newest First
+  .file-name = File Name
+  .file-size = File Size
+
+# Album thumbnail size drop-down menu
+prefs-albums-zoom = Thumbnail size
+  .subtitle = Size of photos and videos in albums. Also changed with Ctrl+Plus, Ctrl+Minus, or Ctrl+scroll.
+  .small = Small
+  .medium = Medium
+  .large = Large
+
+# Folders album layout drop-down menu
+prefs-albums-folders-view = Folders layout
+  .subtitle = Show folders nested as they are on disk, or all together in one list.
+  .tree = Nested
+  .flat = Flat
+
+# Toggle to show folders that have been hidden from the folders album
+prefs-albums-show-hidden-folders = Show hidden folders
+  .subtitle = Show folders hidden from the folders album. Their photos and videos are always shown in other albums.
 
 # Preferences related to machine learning, such as face detection.
 # Machine learning is CPU intensive so capabilities can be turned on or off by
@@ -249,12 +614,64 @@ prefs-processing-section = Photo and video processing
 prefs-processing-face-detection = Face detection
   .subtitle = Detect faces and recognize people you've named. This is a time consuming process.
 
+# Combo box to choose how faces are detected.
+# Attributes:
+#   .subtitle - Description of combo box.
+#   .fast - Quicker, for laptops.
+#   .accurate - Finds more faces, but is slower, for desktops.
+prefs-processing-face-detection-model = Face detection model
+  .subtitle = Accurate finds more faces, but is much slower. Faces found by different models aren't grouped together.
+  .fast = Fast
+  .accurate = Accurate
+
+# Minimum confidence (0.0 to 1.0) for a detected face to be shown.
+# Attributes:
+#   .subtitle - Description of spin button.
+prefs-processing-face-confidence = Face confidence
+  .subtitle = Hide detected faces that are less certain to be faces than this. Raise it if patterns are mistaken for faces.
+
+# Maximum difference (0.0 to 2.0) between unknown faces suggested as the same person.
+# Attributes:
+#   .subtitle - Description of spin button.
+prefs-processing-face-grouping = Face grouping
+  .subtitle = How different unknown faces can look and still be suggested as the same person. Lower it if different people are grouped together.
+
+# Fraction (0.0 to 2.0) that face thumbnails are enlarged by around a detected face.
+# Attributes:
+#   .subtitle - Description of spin button.
+prefs-processing-face-thumbnail-margin = Face thumbnail margin
+  .subtitle = How much of the head and surroundings to show around faces. Zero crops tightly to the face. Applies to faces found from now on.
+
 # Motion photo processing enabled or disabled.
 # Attributes:
 #   .subtitle - Description of toggle button action.
 prefs-processing-motion-photos = Motion photos
   .subtitle = Detect Android motion photos and extract the videos.
 
+# Preferences for private photos, which are hidden or blurred until unlocked.
+# Attributes:
+#   .description - Shown while private photos are unlocked.
+#   .locked - Shown while private photos are locked, when their preferences can't be changed.
+prefs-privacy-section = Private photos
+  .description = Private photos are locked each time { -app-name } starts.
+  .locked = Unlock private photos to change how they are shown or the PIN.
+
+# How private photos are shown while locked.
+# Attributes:
+#   .subtitle - Description of combo box.
+#   .hide - Private photos are left out of albums, search, and people.
+#   .blur - Private photos are shown in albums with blurred thumbnails.
+prefs-privacy-mode = While locked
+  .subtitle = How private photos are shown until they are unlocked.
+  .hide = Hide
+  .blur = Blur
+
+# PIN needed to unlock private photos.
+# Attributes:
+#   .tooltip - Description of password entry.
+prefs-privacy-pin = New PIN
+  .tooltip = PIN needed to unlock private photos. Apply an empty PIN to unlock without one.
+
 prefs-library-section =
   .title = Library
   .description = Configure library directory.
@@ -264,8 +681,110 @@ prefs-library-section-pictures-dir =
   .title = Pictures Directory
   .tooltip = Choose pictures directory.
 
+# Directories scanned alongside the pictures directory.
+# Attributes:
+#   .add-tooltip - Tooltip for button to add a directory.
+#   .remove-tooltip - Tooltip for button to remove a directory.
+#   .overlapping - Error shown when a directory is inside, or contains, another directory of the library.
+prefs-library-dirs-section =
+  .title = Other Directories
+  .description = Also show pictures and videos from these directories.
+  .add-tooltip = Add directory.
+  .remove-tooltip = Remove directory. Its pictures are moved to the trash.
+  .overlapping = That directory is inside, or contains, a directory already in the library.
+
+# Watching the pictures directory for changes enabled or disabled.
+# Attributes:
+#   .subtitle - Description of toggle button action.
+prefs-library-section-watch = Watch for changes
+  .subtitle = Rescan the library when pictures are added, changed, or deleted. Network folders might not report changes.
+
+# Following symbolic links when scanning the library enabled or disabled.
+# Attributes:
+#   .subtitle - Description of toggle button action.
+prefs-library-section-follow-symlinks = Follow links
+  .subtitle = Include pictures in linked files and folders. A picture reached through more than one link is only added once.
+
+# Maximum size of thumbnail cache.
+# Attributes:
+#   .subtitle - Description of spin button.
+prefs-library-section-thumbnail-cache = Thumbnail storage limit
+  .subtitle = Maximum gigabytes of disk space for thumbnails. The least recently viewed thumbnails are deleted first and made again when next viewed. Zero for no limit.
+
+# Order that pictures are thumbnailed in.
+# Attributes:
+#   .subtitle - Description of combo box.
+#   .newest-first - Most recently taken pictures first.
+#   .oldest-first - Least recently taken pictures first.
+#   .largest-first - Biggest files first.
+prefs-library-section-thumbnail-order = Thumbnail order
+  .subtitle = Which pictures get thumbnails first. Every picture gets a thumbnail whatever the order.
+  .newest-first = Newest first
+  .oldest-first = Oldest first
+  .largest-first = Largest first
+
+# Where thumbnails are saved.
+# Attributes:
+#   .subtitle - Description of combo box.
+#   .central - In the app's cache folder.
+#   .sidecar - In a hidden ".fotema" folder in each folder of photos. Don't translate ".fotema".
+prefs-library-section-thumbnail-location = Thumbnail location
+  .subtitle = Saving thumbnails beside photos keeps them with photos on external drives. Folders that can't be written to have their thumbnails saved in the app's cache.
+  .central = App cache
+  .sidecar = Beside photos
+
+# When photo thumbnails are generated, to save battery on laptops.
+# Attributes:
+#   .subtitle - Description of combo box.
+#   .always - Whenever photos need thumbnails.
+#   .ac-power - Only while plugged in, not on battery.
+#   .ac-power-idle - Only while plugged in and the computer isn't being used.
+prefs-library-section-thumbnail-power = Generate thumbnails
+  .subtitle = Generating thumbnails for many photos at once uses a lot of power, which can drain a laptop's battery. Idle is only known on desktops that report it, such as GNOME.
+  .always = Always
+  .ac-power = When plugged in
+  .ac-power-idle = When plugged in and idle
+
+# Folders that imported photos and videos are copied into, by date taken.
+# Attributes:
+#   .subtitle - Description of combo box.
+#   .year-month - Folder per year, then per month.
+#   .year-month-day - Folder per year, then per month, then per day.
+#   .year - Folder per year.
+prefs-library-section-import-layout = Import folders
+  .subtitle = Folders in the pictures directory that imported photos and videos are copied into.
+  .year-month = Year and month
+  .year-month-day = Year, month, and day
+  .year = Year
+
+# Which is kept when another app, such as digiKam or Lightroom, has changed the
+# favorite or tags in an XMP sidecar file next to a photo. Don't translate "XMP".
+# Attributes:
+#   .subtitle - Description of combo box.
+#   .database-wins - Only photos without a favorite or tags in Fotema take them from the sidecar.
+#   .sidecar-wins - Favorite and tags from the sidecar replace those in Fotema.
+prefs-library-section-sidecar-policy = XMP sidecar changes
+  .subtitle = How favorites and tags changed by other apps are imported when the library is scanned.
+  .database-wins = Keep Fotema's
+  .sidecar-wins = Use the sidecar's
+
+# Files and folders to leave out of the library.
+# Attributes:
+#   .tooltip - Description of text entry. Don't translate ".fotemaignore", "@eaDir", or "__MACOSX".
+prefs-library-section-ignore = Ignore files and folders
+  .tooltip = Comma separated patterns, such as "@eaDir, __MACOSX, *.tmp". Patterns in a .fotemaignore file in a library directory are ignored too. Pictures already in the library that match a pattern are moved to the trash.
+
 ## Progress bar for background tasks
 
+# Looking for photos and videos in the library folders. The total isn't known
+# until the scan completes, so a running count is shown.
+# Variables:
+#   $count - number of files found so far.
+progress-scan = { $count ->
+    [one] Scanning library. Found {$count} file.
+   *[other] Scanning library. Found {$count} files.
+}
+
 # Extracting details from photo EXIF data
 progress-metadata-photos = Processing photo metadata.
 
@@ -281,6 +800,9 @@ progress-thumbnails-videos = Generating video thumbnails.
 # Generating thumbnails from faces
 progress-thumbnails-faces = Generating face thumbnails.
 
+# Generating looping previews of videos and GIFs
+progress-thumbnails-animated = Generating animated previews.
+
 # Transcoding videos to a compatible format
 progress-convert-videos = Converting videos.
 
@@ -293,9 +815,41 @@ progress-detect-faces-photos = Detecting faces in photos.
 # Recognize faces in photos as known people
 progress-recognize-faces-photos = Recognizing people in photos.
 
+# Copying a person's photos to a folder
+progress-export-photos = Exporting photos.
+
+# Saving details of every photo to a file
+progress-export-metadata = Exporting library metadata.
+
+# Copying photos and videos into the library
+progress-import = Importing photos and videos.
+
+# Saving favorites and tags to XMP sidecar files next to photos. Don't translate "XMP".
+progress-write-sidecars = Writing XMP sidecars.
+
+# Reading favorites and tags from XMP sidecar files changed by other apps. Don't translate "XMP".
+progress-import-sidecars = Reading XMP sidecars.
+
+# Moving thumbnails between the app's cache and hidden folders beside photos.
+progress-relocate-thumbnails = Moving thumbnails.
+
 # Not doing any background work
 progress-idle = Idle.
 
+# Background work has been paused by the user
+progress-paused = Paused.
+
+# Progress bar for a background task that is carrying on from where it got to
+# before the app last exited.
+# Variables:
+#   $count - number of items processed, including before the app exited.
+#   $total - number of items to process.
+progress-resuming = Resuming after interruption ({ $count } / { $total })
+
+# Button to pause or resume background work.
+progress-pause-button =
+  .tooltip = Pause or resume background tasks.
+
 ## Notification banner for background tasks
 
 # Similar to the progress bar, but allows for longer messages.
@@ -315,6 +869,9 @@ banner-thumbnails-photos = Generating photo thumbnails. This will take a while.
 # Generating thumbnails for all videos.
 banner-thumbnails-videos = Generating video thumbnails. This will take a while.
 
+# Generating looping previews for all videos and GIFs.
+banner-thumbnails-animated = Generating animated previews. This will take a while.
+
 # Updating the database to remove details of absent photos.
 banner-clean-photos = Photo database maintenance.
 
@@ -330,6 +887,27 @@ banner-detect-faces-photos = Detecting faces in photos. This will take a while.
 # Recognize faces as people
 banner-recognize-faces-photos = Recognizing people in photos. This will take a while.
 
+# Grouping pictures taken in quick succession into bursts
+banner-bursts = Grouping burst pictures.
+
+# Copying a person's photos to a folder
+banner-export-photos = Exporting photos.
+
+# Saving details of every photo to a file
+banner-export-metadata = Exporting library metadata.
+
+# Copying photos and videos into the library
+banner-import = Importing photos and videos.
+
+# Saving favorites and tags to XMP sidecar files next to photos. Don't translate "XMP".
+banner-write-sidecars = Writing XMP sidecars.
+
+# Reading favorites and tags from XMP sidecar files changed by other apps. Don't translate "XMP".
+banner-import-sidecars = Reading XMP sidecars.
+
+# Moving thumbnails between the app's cache and hidden folders beside photos.
+banner-relocate-thumbnails = Moving thumbnails.
+
 # Transcoding videos to a compatible format
 banner-convert-videos = Converting videos.
 
@@ -341,9 +919,24 @@ banner-button-stop =
   .label = Stop
   .tooltip = Stop all background tasks.
 
+# Button to hide an error banner.
+banner-button-dismiss = Dismiss
+
+# Thumbnails can't be saved, so no thumbnails were generated.
+# Variables:
+#   $path - Directory thumbnails are saved to.
+banner-cache-unwritable = Can't save thumbnails to { $path }. Check the disk isn't full or read-only, then restart { -app-name }.
+
+# Toast shown when the library couldn't be read from the database.
+# Albums keep showing the photos and videos loaded before.
+library-load-failed-toast = Couldn't load the library
+
 # Background tasks are in the process of being stopped
 banner-stopping = Stopping tasks...
 
+# Background tasks have been paused by the user
+banner-paused = Background tasks paused.
+
 ## Primary menu
 
 # The "hamburger" menu on the main app navigation sidebar.
@@ -351,9 +944,141 @@ banner-stopping = Stopping tasks...
 # Menu item to show preferences dialog
 primary-menu-preferences = Preferences
 
+# Menu item to show library statistics dialog
+primary-menu-stats = Library Statistics
+
+# Menu item to show dialog of photos that couldn't be processed
+primary-menu-broken-photos = Broken Photos
+
+# Menu item to show dialog of photos whose files can no longer be found
+primary-menu-missing-photos = Missing Photos
+
+# Menu item to show an album of photos that are identical copies of other photos
+primary-menu-duplicate-photos = Duplicate Photos
+
 # Menu item to show "about" dialog
 primary-menu-about = About {-app-name}
 
+# Menu item to copy photos and videos into the library
+primary-menu-import-files = Import Files…
+
+# Menu item to copy folders of photos and videos into the library
+primary-menu-import-folders = Import Folders…
+
+# Menu item to save details of every photo, such as when it was taken and its tags, to a file
+primary-menu-export-metadata = Export Library Metadata…
+
+# Menu item to save favorites and tags to XMP sidecar files next to photos, so that
+# other apps such as digiKam and Lightroom can see them. Photos themselves aren't changed.
+# Don't translate "XMP".
+primary-menu-write-sidecars = Write XMP Sidecars
+
+# Menu item to show private photos, after asking for the PIN if there is one
+primary-menu-unlock-private = Unlock Private Photos…
+
+# Menu item to hide or blur private photos again
+primary-menu-lock-private = Lock Private Photos
+
+# Dialog asking for the PIN that unlocks private photos.
+# Attributes:
+#   .wrong-pin - Body of dialog shown again after a wrong PIN.
+#   .pin - Placeholder text of the PIN entry.
+unlock-private-dialog =
+  .heading = Unlock private photos?
+  .body = Enter your PIN to show private photos.
+  .wrong-pin = Wrong PIN. Try again.
+  .pin = PIN
+  .cancel-button = Cancel
+  .unlock-button = Unlock
+
+# File chooser for exporting library metadata.
+# Attributes:
+#   .title - Title of file chooser.
+#   .json-lines-filter - Name of file type for JSON lines files.
+#   .csv-filter - Name of file type for CSV files.
+export-metadata-dialog =
+  .title = Export Library Metadata
+  .json-lines-filter = JSON Lines
+  .csv-filter = CSV (Comma-Separated Values)
+
+# Dialog shown once library metadata has been exported.
+# Attributes:
+#   .heading - Title of dialog.
+#   .body - Count of photos written.
+#   .failed - Shown instead of the body if the file couldn't be written.
+#   .ok-button - Close dialog.
+export-metadata-done-dialog =
+  .heading = Library metadata exported
+  .body = { $count ->
+      [one] Details of one photo saved.
+     *[other] Details of { $count } photos saved.
+  }
+  .failed = The file couldn't be written.
+  .ok-button = OK
+
+# Menu item to scan the library for files added, changed, or deleted since the last scan
+primary-menu-rescan = Rescan Library
+
+# Menu item to scan every file in the library, which is slower than a rescan
+primary-menu-rebuild = Rebuild Library
+
+# Menu item to count thumbnails that need generating, then ask whether to generate them
+primary-menu-generate-thumbnails = Generate Thumbnails…
+
+# Dialog asking whether to generate thumbnails, once they have been counted.
+# Attributes:
+#   .heading - Title of dialog.
+#   .up-to-date - Body when there are no thumbnails to generate.
+#   .cancel-button - Close dialog without generating thumbnails.
+#   .start-button - Start generating thumbnails.
+#   .ok-button - Close dialog when there are no thumbnails to generate.
+generate-thumbnails-dialog =
+  .heading = Generate Thumbnails
+  .up-to-date = All thumbnails are up to date.
+  .cancel-button = Cancel
+  .start-button = Start
+  .ok-button = OK
+
+# Body of dialog asking whether to generate thumbnails.
+# Variables:
+#   $count - number of thumbnails, for selecting plural form.
+#   $formatted_count - number of thumbnails formatted with thousands separators.
+generate-thumbnails-dialog-count = { $count ->
+    [one] {$formatted_count} thumbnail to generate. Generating thumbnails for a large library can take hours.
+   *[other] {$formatted_count} thumbnails to generate. Generating thumbnails for a large library can take hours.
+}
+
+# Titles of file choosers for importing photos and videos.
+# Attributes:
+#   .files-title - Choosing files to import.
+#   .folders-title - Choosing folders to import.
+import-dialog =
+  .files-title = Import photos and videos
+  .folders-title = Import folders of photos and videos
+
+# Dialog shown once photos and videos have been imported.
+# Attributes:
+#   .heading - Title of dialog.
+#   .body - Count of files copied.
+#   .duplicates - Count of files skipped because they are already in the library.
+#   .failed - Count of files that couldn't be copied.
+#   .ok-button - Close dialog.
+import-done-dialog =
+  .heading = Import finished
+  .body = { $count ->
+      [one] One photo or video copied.
+     *[other] { $count } photos and videos copied.
+  }
+  .duplicates = { $count ->
+      [one] One file was skipped because it is already in the library.
+     *[other] { $count } files were skipped because they are already in the library.
+  }
+  .failed = { $count ->
+      [one] One file couldn't be copied.
+     *[other] { $count } files couldn't be copied.
+  }
+  .ok-button = OK
+
 ## Person menu
 
 # Menu item to rename a person
@@ -362,6 +1087,57 @@ person-menu-rename = Rename person
 # Menu item to delete a person
 person-menu-delete = Delete person
 
+# Menu item to hide a person from the people album. Shown with a check mark when hidden.
+person-menu-hide = Hide person
+
+# Menu item to copy all photos of a person to a folder
+person-menu-export = Export photos
+
+# Menu item to review the faces assigned to a person
+person-menu-faces = Review faces
+
+# Dialog for reviewing the faces assigned to a person.
+# Attributes:
+#   .description - Explains how to remove faces that aren't the person.
+#   .unassign-button - Remove deselected faces from the person.
+#   .reassign-button - Choose another person for deselected faces.
+person-faces =
+  .description = Deselect any faces that aren't this person, then remove them or move them to someone else.
+  .unassign-button = Remove from person
+  .reassign-button = Move to…
+
+# Face in the dialog for reviewing faces.
+# Attributes:
+#   .cover-tooltip - Use the face as the person's avatar.
+person-faces-item =
+  .cover-tooltip = Use as avatar
+
+# Title of folder chooser for exporting a person's photos
+person-export-folder-dialog = Export photos to folder
+
+# Dialog shown once a person's photos have been exported.
+# Attributes:
+#   .heading - Title of dialog.
+#   .body - Count of photos copied.
+#   .missing - Count of photos skipped because their file no longer exists.
+#   .failed - Count of photos that couldn't be copied.
+#   .ok-button - Close dialog.
+person-export-done-dialog =
+  .heading = Photos exported
+  .body = { $count ->
+      [one] One photo copied.
+     *[other] { $count } photos copied.
+  }
+  .missing = { $count ->
+      [one] One photo was skipped because its file no longer exists.
+     *[other] { $count } photos were skipped because their files no longer exist.
+  }
+  .failed = { $count ->
+      [one] One photo couldn't be copied.
+     *[other] { $count } photos couldn't be copied.
+  }
+  .ok-button = OK
+
 # Person delete dialog
 person-delete-dialog =
   .heading = Delete person?
@@ -384,3 +1160,90 @@ onboard-select-pictures =
     If you have used an earlier version of { -app-name } where your picture library was automatically discovered, then please select the same directory here to avoid any duplicate processing of pictures.
 
   .button = Select Directory
+
+## Library statistics dialog
+
+# Title of dialog and labels for each statistic.
+library-stats = Library Statistics
+  .photos = Photos
+  .videos = Videos
+  .people = People
+  .date-span = Date Span
+  .disk-space = Disk Space
+  .originals-size = Originals
+  .thumbnails-size = Thumbnails
+
+# Dates of oldest and newest items in library.
+# Variables:
+#   $oldest - date of oldest item.
+#   $newest - date of newest item.
+library-stats-date-span = { $oldest } – { $newest }
+
+## Broken photos dialog
+
+# Title of dialog listing photos that couldn't be processed.
+# Attributes:
+#   .description - explanation shown above the list.
+broken-photos = Broken Photos
+  .description = These photos couldn't be processed, so they are left out of albums.
+
+# Shown when there are no broken photos.
+broken-photos-empty =
+  .title = No Broken Photos
+  .description = Every photo in the library could be processed.
+
+# Labels for a broken photo.
+# Attributes:
+#   .unknown-reason - shown for photos that broke before reasons were kept.
+#   .retry-tooltip - tooltip for button that tries to process the photo again.
+#   .reveal-tooltip - tooltip for button that shows the photo in the file manager.
+#   .trash-tooltip - tooltip for button that moves the photo to the trash.
+broken-photos-item =
+  .unknown-reason = Unknown reason
+  .retry-tooltip = Try Again
+  .reveal-tooltip = Show in Files
+  .trash-tooltip = Move to Trash
+
+# Why a photo couldn't be processed, and when.
+# Variables:
+#   $reason - error message.
+#   $date - date and time the photo was last found to be broken.
+broken-photos-item-reason = { $reason } ({ $date })
+
+# Title of dialog listing photos whose files can no longer be found.
+# Attributes:
+#   .description - explanation shown above the list.
+#   .locate-title - title of file chooser for finding where a photo was moved to.
+missing-photos = Missing Photos
+  .description = The files of these photos can no longer be found. Locate a photo if it was moved, or remove it from the library if it was deleted.
+  .locate-title = Locate Photo
+
+# Shown when there are no missing photos.
+missing-photos-empty =
+  .title = No Missing Photos
+  .description = The file of every photo in the library can be found.
+
+# Labels for a missing photo.
+# Attributes:
+#   .never-seen - shown for photos that went missing before Fotema kept track.
+#   .locate-tooltip - tooltip for button that finds where the photo was moved to.
+#   .remove-tooltip - tooltip for button that removes the photo from the library.
+missing-photos-item =
+  .never-seen = Last seen at an unknown time
+  .locate-tooltip = Locate
+  .remove-tooltip = Remove from Library
+
+# When a missing photo was last found in the library.
+# Variables:
+#   $date - date and time the photo was last seen.
+missing-photos-item-last-seen = Last seen { $date }
+
+# Messages shown after locating a missing photo.
+# Attributes:
+#   .located - the chosen file is the photo.
+#   .mismatch - the chosen file isn't the same as the photo.
+#   .failed - something went wrong.
+missing-photos-toast =
+  .located = Photo located
+  .mismatch = That file is a different photo
+  .failed = Couldn't locate photo
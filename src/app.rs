@@ -9,7 +9,7 @@ use relm4::prelude::*;
 use relm4::{
     Component, ComponentController, Controller,
     WorkerController,
-    actions::{RelmAction, RelmActionGroup},
+    actions::{AccelsPlus, RelmAction, RelmActionGroup},
     adw,
     component::{AsyncComponent, AsyncComponentController},
     gtk::{
@@ -27,16 +27,33 @@ use crate::fl;
 
 use fotema_core::PictureId;
 use fotema_core::VisualId;
+use fotema_core::burst::BurstId;
 use fotema_core::database;
 use fotema_core::path_encoding;
+use fotema_core::number;
 use fotema_core::people;
-use fotema_core::thumbnailify::Thumbnailer;
+use fotema_core::import::{ImportLayout, ImportSummary};
+use fotema_core::photo::thumbnailer::ThumbnailOrder;
+use fotema_core::machine_learning::face_extractor::FaceDetectionModel;
+use fotema_core::photo::xmp::SidecarPolicy;
+use fotema_core::user_album::UserAlbumId;
+use fotema_core::photo::{ExportFormat, ExportSummary};
+use fotema_core::photo::metadata::NaiveTimeZone;
+use fotema_core::thumbnailify::{ThumbnailLocation, Thumbnailer};
 use fotema_core::FlatpakPathBuf;
+use fotema_core::LibraryRoots;
 
+#[cfg(feature = "map")]
 use h3o::CellIndex;
 
+use chrono::{DateTime, Utc};
+
+use argon2::Argon2;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+
 use std::path::PathBuf;
 use std::str::FromStr;
+use std::time::Duration;
 use std::sync::{Arc, Mutex};
 use std::rc::Rc;
 
@@ -50,31 +67,42 @@ mod components;
 
 use crate::host_path;
 
+#[cfg(feature = "map")]
+use self::components::albums::places_album::{PlacesAlbum, PlacesAlbumInput, PlacesAlbumOutput};
+
 use self::components::{
     about::AboutDialog,
+    broken_photos::{BrokenPhotosDialog, BrokenPhotosInput, BrokenPhotosOutput},
+    date_range_picker::{DateRangePicker, DateRangePickerOutput},
     albums::{
         album::{Album, AlbumInput, AlbumOutput},
         album_filter::AlbumFilter,
-        album_sort::AlbumSort,
-        folders_album::{FoldersAlbum, FoldersAlbumInput, FoldersAlbumOutput},
+        album_sort::SortKey,
+        grid_zoom::GridZoom,
+        folders_album::{FoldersAlbum, FoldersAlbumInput, FoldersAlbumOutput, FoldersViewMode},
         people_album::{PeopleAlbum, PeopleAlbumInput, PeopleAlbumOutput},
         person_album::{PersonAlbum, PersonAlbumInput, PersonAlbumOutput},
-        places_album::{PlacesAlbum, PlacesAlbumInput, PlacesAlbumOutput},
     },
     library::{Library, LibraryInput, LibraryOutput},
+    missing_photos::{MissingPhotosDialog, MissingPhotosInput, MissingPhotosOutput},
     onboard::{Onboard, OnboardOutput},
     preferences::{PreferencesDialog, PreferencesInput, PreferencesOutput},
+    stats::{StatsDialog, StatsInput},
+    viewer::person_select::{PersonSelect, PersonSelectInput, PersonSelectOutput},
     viewer::view_nav::{ViewNav, ViewNavInput, ViewNavOutput},
 };
 
 mod background;
 
+pub mod headless;
+
 use self::background::bootstrap::{
     Bootstrap, BootstrapInput, BootstrapOutput, MediaType, ThumbnailType, TaskName,
 };
+use self::background::power_monitor::ThumbnailPowerMode;
 
-use self::components::progress_monitor::ProgressMonitor;
-use self::components::progress_panel::ProgressPanel;
+use self::components::progress_monitor::{self, ProgressMonitor};
+use self::components::progress_panel::{ProgressPanel, ProgressPanelOutput};
 
 /// Name of a view that can be displayed
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq, EnumString, IntoStaticStr)]
@@ -87,12 +115,15 @@ pub enum ViewName {
     Year,
     Videos,
     Animated,
+    Favorites,
+    OnThisDay,
     Folders,
     Folder,
     People,
     Person,
     Places,
     Selfies,
+    Trash,
 }
 
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq, EnumString, AsRefStr, FromRepr)]
@@ -106,6 +137,29 @@ pub enum FaceDetectionMode {
     On,
 }
 
+/// How private pictures are shown while they are locked.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, EnumString, AsRefStr, FromRepr)]
+#[repr(u32)]
+pub enum PrivacyMode {
+    /// Leave private pictures out of albums, search, and people.
+    #[default]
+    Hide,
+
+    /// Show private pictures in albums with blurred thumbnails.
+    Blur,
+}
+
+/// Can private pictures be seen?
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum Privacy {
+    /// Private pictures are hidden or blurred, depending on the privacy mode.
+    #[default]
+    Locked,
+
+    /// Private pictures are shown like any other.
+    Unlocked,
+}
+
 /// Settings the user can change in the preferences dialog.
 /// Should not include any non-preference dialog settings like window size or maximization state.
 #[derive(Clone, Debug, Default)]
@@ -116,13 +170,70 @@ pub struct Settings {
     /// Enable or disable face detection.
     pub face_detection_mode: FaceDetectionMode,
 
+    /// Face detectors to run, trading speed for accuracy.
+    pub face_detection_model: FaceDetectionModel,
+
+    /// Detected faces with a lower confidence than this are hidden.
+    pub face_confidence_threshold: f32,
+
+    /// Maximum distance between unknown faces suggested as the same person.
+    pub face_cluster_distance: f32,
+
+    /// Fraction that detected face bounds are expanded by for face thumbnails.
+    pub face_thumbnail_margin: f32,
+
     /// Sorting for albums.
     /// NOTE: doesn't include folder's album.
-    pub album_sort: AlbumSort,
+    pub album_sort: SortKey,
+
+    /// Size of items in album grids.
+    pub album_zoom: GridZoom,
 
     /// Enable processing of Android motion photos.
     pub process_motion_photos: bool,
 
+    /// Rescan the library when files are added, changed, or deleted.
+    pub watch_library: bool,
+
+    /// Follow symbolic links to files and folders when scanning the library.
+    pub follow_symlinks: bool,
+
+    /// Play a looping preview of videos and GIFs when hovering over them in albums.
+    pub animated_previews: bool,
+
+    /// Show folders as a tree or as a flat list.
+    pub folders_view_mode: FoldersViewMode,
+
+    /// Show folders the user has hidden in the folders album.
+    pub show_hidden_folders: bool,
+
+    /// Maximum size of the thumbnail cache in gigabytes. Zero for no limit.
+    pub thumbnail_cache_limit_gb: f64,
+
+    /// Order that pictures are thumbnailed in.
+    pub thumbnail_order: ThumbnailOrder,
+
+    /// Where thumbnails are saved.
+    pub thumbnail_location: ThumbnailLocation,
+
+    /// Whether photo thumbnails are only generated on AC power, or while idle.
+    pub thumbnail_power_mode: ThumbnailPowerMode,
+
+    /// Folders that imported pictures and videos are copied into.
+    pub import_layout: ImportLayout,
+
+    /// Whether a changed XMP sidecar or the library is kept when they disagree.
+    pub sidecar_policy: SidecarPolicy,
+
+    /// Seconds each picture is shown for in a slideshow.
+    pub slideshow_interval_secs: u32,
+
+    /// Most bits by which the perceptual hashes of similar photos may differ.
+    pub similar_photos_distance: u32,
+
+    /// Time zone of picture timestamps that don't record their UTC offset.
+    pub naive_time_zone: NaiveTimeZone,
+
     /// Has the user completed the onboarding processes to select
     /// the picture library root directory?
     pub is_onboarding_complete: bool,
@@ -130,11 +241,69 @@ pub struct Settings {
     /// Base path of pictures directory inside Flatpak sandbox.
     /// Will be under `/run/users/<uid>/docs/<doc-id>/...`
     pub library_base_dir: FlatpakPathBuf,
+
+    /// Further directories to scan for pictures and videos, alongside the pictures directory.
+    pub library_dirs: Vec<FlatpakPathBuf>,
+
+    /// Glob patterns of files and folders to leave out of the library.
+    pub ignore_patterns: Vec<String>,
+
+    /// How private pictures are shown while locked.
+    pub privacy_mode: PrivacyMode,
+
+    /// Argon2 hash of the PIN that unlocks private pictures, as a PHC string.
+    /// Empty if private pictures unlock without a PIN.
+    pub privacy_pin: String,
+}
+
+impl Settings {
+    /// Every directory scanned for pictures and videos.
+    pub fn library_roots(&self) -> LibraryRoots {
+        LibraryRoots::build(&self.library_base_dir, &self.library_dirs)
+    }
+
+    /// Maximum size of the thumbnail cache in bytes, if limited.
+    pub fn thumbnail_cache_limit_bytes(&self) -> Option<u64> {
+        (self.thumbnail_cache_limit_gb > 0.0)
+            .then(|| (self.thumbnail_cache_limit_gb * 1024.0 * 1024.0 * 1024.0) as u64)
+    }
+
+    /// Does the PIN unlock private pictures?
+    pub fn is_privacy_pin(&self, pin: &str) -> bool {
+        if self.privacy_pin.is_empty() {
+            return true;
+        }
+
+        PasswordHash::new(&self.privacy_pin).is_ok_and(|hash| {
+            Argon2::default()
+                .verify_password(pin.as_bytes(), &hash)
+                .is_ok()
+        })
+    }
+
+    /// Change the PIN that unlocks private pictures. An empty PIN removes it.
+    pub fn set_privacy_pin(&mut self, pin: &str) -> Result<()> {
+        self.privacy_pin = if pin.is_empty() {
+            String::new()
+        } else {
+            // A salt only has to be unique, not secret.
+            let salt = SaltString::encode_b64(glib::uuid_string_random().as_bytes())
+                .map_err(|e| anyhow!("Invalid salt: {e}"))?;
+            Argon2::default()
+                .hash_password(pin.as_bytes(), &salt)
+                .map_err(|e| anyhow!("Failed hashing PIN: {e}"))?
+                .to_string()
+        };
+        Ok(())
+    }
 }
 
 /// Active settings
 type SettingsState = Arc<relm4::SharedState<Settings>>;
 
+/// Whether private pictures are locked. Always locked when the app starts.
+type PrivacyState = Arc<relm4::SharedState<Privacy>>;
+
 /// Currently visible view
 /// This allows a view to know if it is visible or not and to lazily load
 /// images into the photo grids. Without lazy loading Fotema will take too long to
@@ -149,8 +318,23 @@ type SharedState = Arc<relm4::SharedState<Vec<Arc<fotema_core::Visual>>>>;
 pub(super) struct App {
     adaptive_layout: Arc<adaptive::LayoutState>,
 
+    state: SharedState,
+
+    active_view: ActiveView,
+
+    thumbnailer: Rc<Thumbnailer>,
+
+    people_repo: people::Repository,
+
     about_dialog: Controller<AboutDialog>,
     preferences_dialog: AsyncController<PreferencesDialog>,
+    stats_dialog: AsyncController<StatsDialog>,
+    broken_photos_dialog: AsyncController<BrokenPhotosDialog>,
+    missing_photos_dialog: AsyncController<MissingPhotosDialog>,
+
+    // Choose a person for the pictures selected in an album.
+    person_dialog: adw::Dialog,
+    person_select: AsyncController<PersonSelect>,
 
     bootstrap: WorkerController<Bootstrap>,
 
@@ -166,6 +350,9 @@ pub(super) struct App {
     selfies_page: Controller<Album>,
     videos_page: Controller<Album>,
     motion_page: Controller<Album>,
+    favorites_page: Controller<Album>,
+    on_this_day_page: Controller<Album>,
+    trash_page: Controller<Album>,
 
     /// Album with photos overlayed onto a map
     people_page: Controller<PeopleAlbum>,
@@ -174,14 +361,27 @@ pub(super) struct App {
     person_album: Controller<PersonAlbum>,
 
     /// Album with photos overlayed onto a map
+    #[cfg(feature = "map")]
     places_page: Controller<PlacesAlbum>,
 
     // Grid of folders of photos
     folders_album: Controller<FoldersAlbum>,
 
+    // Grids of subfolders opened beneath the folders album. One per level of the folder tree.
+    subfolders_albums: Vec<Controller<FoldersAlbum>>,
+
+    // Navigation for drilling down through the folder tree.
+    folders_navigation_view: adw::NavigationView,
+
     // Folder album currently being viewed
     folder_album: Controller<Album>,
 
+    // Folder, and scroll offset, of the folder album if it is open. Saved at shutdown.
+    last_folder: Option<(PathBuf, f64)>,
+
+    // Folder album open at the last shutdown. Reopened once the library has loaded.
+    restore_folder: Option<(PathBuf, f64)>,
+
     // Main navigation. Parent of library stack.
     main_navigation: adw::OverlaySplitView,
 
@@ -197,12 +397,33 @@ pub(super) struct App {
     // Activity indicator. Only shown when progress bar is hidden.
     spinner: adw::Spinner,
 
+    // Choose a date range of photos to view.
+    date_range_picker: Controller<DateRangePicker>,
+
+    // Search for file names on album page
+    album_search_entry: gtk::SearchEntry,
+
+    // Toggle selection mode on album page
+    album_select_button: gtk::ToggleButton,
+
     bootstrap_progress: Controller<ProgressPanel>,
 
     // Message banner
     banner: adw::Banner,
 
+    // Toasts shown over the whole window.
+    toasts: adw::ToastOverlay,
+
+    // Banner title to restore when background tasks are resumed.
+    banner_title_before_pause: Option<String>,
+
+    // Banner for problems the user needs to fix. Stays until dismissed, unlike the
+    // banner for background tasks.
+    error_banner: adw::Banner,
+
     settings_state: SettingsState,
+
+    privacy_state: PrivacyState,
 }
 
 #[derive(Debug)]
@@ -228,14 +449,51 @@ pub(super) enum AppMsg {
 
     ViewFolder(PathBuf),
 
+    // Folder album has scrolled. Offset in pixels.
+    FolderScrolled(f64),
+
+    // Show next level of folder tree beneath a folder.
+    ViewSubfolders(PathBuf),
+
+    // Level of folder tree is dismissed.
+    SubfoldersHidden,
+
+    #[cfg(feature = "map")]
     ViewGeographicArea(CellIndex),
 
+    ViewDateRange(Option<DateTime<Utc>>, Option<DateTime<Utc>>),
+
+    // Show items taken in a named place, such as "Paris, France".
+    ViewPlace(String),
+
+    // Show items taken in a year or month, from the viewer.
+    ViewTakenIn(AlbumFilter),
+
+    // Narrow the album page with an additional filter.
+    AddAlbumFilter(AlbumFilter),
+
+    // Show the album page for the whole library, ready for searching by file name.
+    SearchLibrary,
+
+    // Search album page by file name.
+    SearchAlbum(String),
+
+    // Enter or exit selection mode on album page.
+    AlbumSelectionMode(bool),
+
+    // Show a slideshow of the visible album.
+    Slideshow,
+
     ViewPerson(people::Person),
 
     PersonDeleted,
 
     PersonRenamed,
 
+    PersonHidden,
+
+    PersonFacesChanged,
+
     // A background task has started.
     TaskStarted(TaskName),
 
@@ -244,14 +502,192 @@ pub(super) enum AppMsg {
 
     TranscodeAll,
 
+    // Copy a person's photos to a folder.
+    ExportPerson(people::PersonId, PathBuf),
+
+    // Person's photos have been copied.
+    PersonExported(ExportSummary),
+
+    // Ask user to choose a file to export library metadata to.
+    ExportMetadataDialog,
+
+    // Write the metadata of every picture to a file.
+    ExportMetadata(PathBuf),
+
+    // Library metadata has been written. None if the export failed.
+    MetadataExported(Option<usize>),
+
+    // Write favorites and tags to XMP sidecar files next to the pictures.
+    WriteSidecars,
+
+    // Ask user to choose files to import.
+    ImportFilesDialog,
+
+    // Ask user to choose folders to import.
+    ImportFoldersDialog,
+
+    // Copy pictures and videos from files and folders into the library.
+    Import(Vec<PathBuf>),
+
+    // Pictures and videos have been copied into the library.
+    Imported(ImportSummary),
+
+    // Thumbnails can't be saved to the directory.
+    ThumbnailCacheUnwritable(PathBuf),
+
+    // Library couldn't be loaded from the database.
+    LibraryLoadFailed,
+
+    // Hide the error banner.
+    DismissErrorBanner,
+
+    // Scan library for changed files.
+    Rescan,
+
+    // Scan every file in the library.
+    Rebuild,
+
+    // Count the thumbnails to generate, so the user can decide whether to start.
+    CountPhotoThumbnails,
+
+    // Ask user to confirm generating the counted thumbnails.
+    PhotoThumbnailsCounted(usize),
+
+    // Generate thumbnails of pictures that need them.
+    GeneratePhotoThumbnails,
+
     ScanPictureForFaces(PictureId),
     ScanPicturesForFaces,
 
+    // Ask user whether to scan pictures for faces again with a new face detection model.
+    FaceDetectionModelChanged,
+
+    // Scan pictures for faces again if they were scanned with another face detection model.
+    RedetectFaces,
+
+    // Star or unstar pictures as favorites.
+    SetFavorite(Vec<PictureId>, bool),
+
+    // Mark a picture as private, or not.
+    SetPrivate(PictureId, bool),
+
+    // Show private pictures, after asking for the PIN if there is one.
+    UnlockPrivate,
+
+    // User has entered a PIN to unlock private pictures.
+    PrivatePinEntered(String),
+
+    // Hide or blur private pictures again.
+    LockPrivate,
+
+    // Move pictures to, or restore pictures from, the trash.
+    SetTrashed(Vec<PictureId>, bool),
+
+    // Choose a picture as the cover of a folder.
+    SetFolderCover(PathBuf, PictureId),
+
+    // Hide a folder from the folders album, or show it again.
+    SetFolderHidden(PathBuf, bool),
+
+    // Delete and regenerate the thumbnail of a picture.
+    RegenerateThumbnail(PictureId),
+
+    // Show the frames of a burst as separate pictures.
+    UngroupBurst(BurstId),
+
+    // Move pictures to another folder of the library.
+    MovePictures(Vec<PictureId>, PathBuf),
+
+    // Name the one unknown face of each picture as a person.
+    AssignToPerson(Vec<PictureId>),
+
+    // Pictures have been named as a person.
+    PersonAssigned,
+
+    // Record that a missing picture was moved to a file, if the file has the same content.
+    LocateMissing(PictureId, PathBuf),
+
+    // A missing picture has been looked for. Some(true) if found, Some(false) if the
+    // file has different content, and None if locating failed.
+    MissingLocated(PictureId, Option<bool>),
+
+    // Remove a missing picture from the library.
+    RemoveMissing(PictureId),
+
+    // Tag a picture.
+    AddTag(PictureId, String),
+
+    // Remove a tag from a picture.
+    RemoveTag(PictureId, String),
+
+    // View album of all pictures with a tag.
+    ViewTag(String),
+
+    // Put a picture in a user album, by album name as typed.
+    AddToUserAlbum(PictureId, String),
+
+    // Take a picture out of a user album.
+    RemoveFromUserAlbum(PictureId, UserAlbumId),
+
+    // Choose a picture as the cover of a user album.
+    SetUserAlbumCover(UserAlbumId, PictureId),
+
+    // Arrange the pictures of a user album in an order.
+    SetUserAlbumOrder(UserAlbumId, Vec<PictureId>),
+
+    // View a user album, in the order the user arranged.
+    ViewUserAlbum(UserAlbumId),
+
+    // Find pictures that look like a picture.
+    FindSimilar(PictureId),
+
+    // View album of a picture and the pictures that look like it.
+    SimilarFound(PictureId, Vec<PictureId>),
+
+    // Find pictures that are identical copies of other pictures.
+    FindDuplicates,
+
+    // View album of pictures that are identical copies of other pictures.
+    DuplicatesFound(Vec<PictureId>),
+
+    // Ask user to confirm emptying the trash.
+    EmptyTrash,
+
+    // Remove trashed pictures from the library, and delete their files if true.
+    EmptyTrashConfirmed(bool),
+
+    // Reverse the most recent trash, restore, or merge.
+    Undo,
+
+    // Make items in album grids bigger.
+    ZoomIn,
+
+    // Make items in album grids smaller.
+    ZoomOut,
+
     ProcessMotionPhotos,
 
+    // Generate looping previews of videos and GIFs.
+    GenerateAnimatedPreviews,
+
+    // Delete looping previews of videos and GIFs.
+    RemoveAnimatedPreviews,
+
+    // Delete thumbnails until the thumbnail cache is within its size limit.
+    LimitThumbnailCache,
+
+    // Ask user whether to move existing thumbnails to where thumbnails are now saved.
+    ThumbnailLocationChanged,
+
+    // Save thumbnails where the settings say, moving existing thumbnails there if true.
+    RelocateThumbnails(bool),
+
     // Stop all background tasks
     StopBackgroundTasks,
 
+    // Pause or resume background tasks.
+    PauseBackgroundTasks(bool),
+
     // Stopping background tasks is in progress
     StoppingBackgroundTasks,
 
@@ -268,6 +704,34 @@ pub(super) enum AppMsg {
 relm4::new_action_group!(pub(super) WindowActionGroup, "win");
 relm4::new_stateless_action!(PreferencesAction, WindowActionGroup, "preferences");
 relm4::new_stateless_action!(AboutAction, WindowActionGroup, "about");
+relm4::new_stateless_action!(StatsAction, WindowActionGroup, "stats");
+relm4::new_stateless_action!(BrokenPhotosAction, WindowActionGroup, "broken-photos");
+relm4::new_stateless_action!(MissingPhotosAction, WindowActionGroup, "missing-photos");
+relm4::new_stateless_action!(DuplicatePhotosAction, WindowActionGroup, "duplicate-photos");
+relm4::new_stateless_action!(ImportFilesAction, WindowActionGroup, "import-files");
+relm4::new_stateless_action!(ImportFoldersAction, WindowActionGroup, "import-folders");
+relm4::new_stateless_action!(ExportMetadataAction, WindowActionGroup, "export-metadata");
+relm4::new_stateless_action!(WriteSidecarsAction, WindowActionGroup, "write-sidecars");
+relm4::new_stateless_action!(RescanAction, WindowActionGroup, "rescan");
+relm4::new_stateless_action!(RebuildAction, WindowActionGroup, "rebuild");
+relm4::new_stateless_action!(
+    GenerateThumbnailsAction,
+    WindowActionGroup,
+    "generate-thumbnails"
+);
+relm4::new_stateless_action!(FilterPhotosAction, WindowActionGroup, "filter-photos");
+relm4::new_stateless_action!(FilterVideosAction, WindowActionGroup, "filter-videos");
+relm4::new_stateless_action!(FilterFavoritesAction, WindowActionGroup, "filter-favorites");
+relm4::new_stateless_action!(FilterPrivateAction, WindowActionGroup, "filter-private");
+relm4::new_stateless_action!(UnlockPrivateAction, WindowActionGroup, "unlock-private");
+relm4::new_stateless_action!(LockPrivateAction, WindowActionGroup, "lock-private");
+relm4::new_stateless_action!(UndoAction, WindowActionGroup, "undo");
+relm4::new_stateless_action!(ZoomInAction, WindowActionGroup, "zoom-in");
+relm4::new_stateless_action!(ZoomOutAction, WindowActionGroup, "zoom-out");
+
+// Application action, so that it can be activated over DBus by `fotema --rescan`
+// or by scripts, without a window.
+relm4::new_stateless_action!(pub(crate) RemoteRescanAction, crate::AppActionGroup, "rescan");
 
 #[relm4::component(pub async)]
 impl SimpleAsyncComponent for App {
@@ -280,7 +744,34 @@ impl SimpleAsyncComponent for App {
         primary_menu: {
             section! {
                 &fl!("primary-menu-preferences") => PreferencesAction,
+                &fl!("primary-menu-stats") => StatsAction,
+                &fl!("primary-menu-broken-photos") => BrokenPhotosAction,
+                &fl!("primary-menu-missing-photos") => MissingPhotosAction,
+                &fl!("primary-menu-duplicate-photos") => DuplicatePhotosAction,
                 &fl!("primary-menu-about") => AboutAction,
+            },
+            section! {
+                &fl!("primary-menu-import-files") => ImportFilesAction,
+                &fl!("primary-menu-import-folders") => ImportFoldersAction,
+                &fl!("primary-menu-export-metadata") => ExportMetadataAction,
+                &fl!("primary-menu-write-sidecars") => WriteSidecarsAction,
+            },
+            section! {
+                &fl!("primary-menu-unlock-private") => UnlockPrivateAction,
+                &fl!("primary-menu-lock-private") => LockPrivateAction,
+            },
+            section! {
+                &fl!("primary-menu-rescan") => RescanAction,
+                &fl!("primary-menu-rebuild") => RebuildAction,
+                &fl!("primary-menu-generate-thumbnails") => GenerateThumbnailsAction,
+            }
+        },
+        album_filter_menu: {
+            section! {
+                &fl!("album-filter-menu", "photos") => FilterPhotosAction,
+                &fl!("album-filter-menu", "videos") => FilterVideosAction,
+                &fl!("album-filter-menu", "favorites") => FilterFavoritesAction,
+                &fl!("album-filter-menu", "private") => FilterPrivateAction,
             }
         }
     }
@@ -321,7 +812,10 @@ impl SimpleAsyncComponent for App {
                 connect_unapply => AppMsg::Adapt(adaptive::Layout::Wide),
             },
 
-            gtk::Box {
+            #[local_ref]
+            toasts -> adw::ToastOverlay {
+            #[wrap(Some)]
+            set_child = &gtk::Box {
                 set_orientation: gtk::Orientation::Vertical,
 
             // Top-level navigation view containing:
@@ -375,6 +869,20 @@ impl SimpleAsyncComponent for App {
 
                                     #[local_ref]
                                     pack_end = &spinner -> adw::Spinner,
+
+                                    pack_end = model.date_range_picker.widget(),
+
+                                    pack_end = &gtk::Button {
+                                        set_icon_name: "system-search-symbolic",
+                                        set_tooltip_text: Some(&fl!("album-search", "tooltip")),
+                                        connect_clicked => AppMsg::SearchLibrary,
+                                    },
+
+                                    pack_end = &gtk::Button {
+                                        set_icon_name: "play-symbolic",
+                                        set_tooltip_text: Some(&fl!("album-slideshow", "tooltip")),
+                                        connect_clicked => AppMsg::Slideshow,
+                                    },
                                 },
 
                                 // NOTE I would like this to be an adw::ViewStack
@@ -390,6 +898,11 @@ impl SimpleAsyncComponent for App {
                                         connect_button_clicked => AppMsg::StopBackgroundTasks,
                                     },
 
+                                    #[local_ref]
+                                    error_banner -> adw::Banner {
+                                        connect_button_clicked => AppMsg::DismissErrorBanner,
+                                    },
+
                                     #[local_ref]
                                     main_stack -> gtk::Stack {
                                         connect_visible_child_notify => AppMsg::SwitchView,
@@ -430,6 +943,26 @@ impl SimpleAsyncComponent for App {
                                             set_icon_name: "sonar-symbolic",
                                         },
 
+                                        add_child = &gtk::Box {
+                                            set_orientation: gtk::Orientation::Vertical,
+                                            container_add: model.favorites_page.widget(),
+                                        } -> {
+                                            set_title: &fl!("favorites-album"),
+                                            set_name: ViewName::Favorites.into(),
+                                            // NOTE gtk::StackSidebar doesn't show icon :-/
+                                            set_icon_name: "starred-symbolic",
+                                        },
+
+                                        add_child = &gtk::Box {
+                                            set_orientation: gtk::Orientation::Vertical,
+                                            container_add: model.on_this_day_page.widget(),
+                                        } -> {
+                                            set_title: &fl!("on-this-day-album"),
+                                            set_name: ViewName::OnThisDay.into(),
+                                            // NOTE gtk::StackSidebar doesn't show icon :-/
+                                            set_icon_name: "today-symbolic",
+                                        },
+
                                         add_child = &gtk::Box {
                                             set_orientation: gtk::Orientation::Vertical,
                                             container_add: model.people_page.widget(),
@@ -438,10 +971,12 @@ impl SimpleAsyncComponent for App {
                                             set_name: ViewName::People.into(),
                                         },
 
+                                        // Map is added after view_output!, if built with the map feature.
+                                        #[name(places_box)]
                                         add_child = &gtk::Box {
                                             set_orientation: gtk::Orientation::Vertical,
-                                            container_add: model.places_page.widget(),
                                         } -> {
+                                            set_visible: cfg!(feature = "map"),
                                             set_title: &fl!("places-page"),
                                             set_name: ViewName::Places.into(),
                                         },
@@ -457,8 +992,10 @@ impl SimpleAsyncComponent for App {
                                             set_icon_name: "sentiment-very-satisfied-symbolic",
                                         },
 
-                                        add_child = &adw::NavigationView {
+                                        #[local_ref]
+                                        add_child = &folders_navigation_view -> adw::NavigationView {
                                             set_pop_on_escape: true,
+                                            connect_popped[sender] => move |_,_| sender.input(AppMsg::SubfoldersHidden),
 
                                             adw::NavigationPage {
                                                 //set_tag: Some("folders"),
@@ -471,6 +1008,24 @@ impl SimpleAsyncComponent for App {
                                             // NOTE gtk::StackSidebar doesn't show icon :-/
                                             set_icon_name: "folder-symbolic",
                                         },
+
+                                        add_child = &gtk::Box {
+                                            set_orientation: gtk::Orientation::Vertical,
+                                            container_add: model.trash_page.widget(),
+
+                                            gtk::ActionBar {
+                                                pack_end = &gtk::Button {
+                                                    set_label: &fl!("trash-page", "empty"),
+                                                    add_css_class: "destructive-action",
+                                                    connect_clicked => AppMsg::EmptyTrash,
+                                                },
+                                            },
+                                        } -> {
+                                            set_title: &fl!("trash-page"),
+                                            set_name: ViewName::Trash.into(),
+                                            // NOTE gtk::StackSidebar doesn't show icon :-/
+                                            set_icon_name: "user-trash-symbolic",
+                                        },
                                     },
                                 },
                             },
@@ -486,7 +1041,37 @@ impl SimpleAsyncComponent for App {
                             set_title_widget = &gtk::Label {
                                 set_label: &fl!("folder-album"),
                                 add_css_class: "title",
-                            }
+                            },
+
+                            #[local_ref]
+                            pack_start = &album_search_entry -> gtk::SearchEntry {
+                                set_placeholder_text: Some(&fl!("album-search", "placeholder")),
+                                set_search_delay: 250,
+                                connect_search_changed[sender] => move |entry| {
+                                    sender.input(AppMsg::SearchAlbum(entry.text().to_string()));
+                                },
+                            },
+
+                            #[local_ref]
+                            pack_end = &album_select_button -> gtk::ToggleButton {
+                                set_icon_name: "selection-mode-symbolic",
+                                set_tooltip_text: Some(&fl!("album-selection", "tooltip")),
+                                connect_toggled[sender] => move |button| {
+                                    sender.input(AppMsg::AlbumSelectionMode(button.is_active()));
+                                },
+                            },
+
+                            pack_end = &gtk::MenuButton {
+                                set_icon_name: "funnel-symbolic",
+                                set_tooltip_text: Some(&fl!("album-filter-menu", "tooltip")),
+                                set_menu_model: Some(&album_filter_menu),
+                            },
+
+                            pack_end = &gtk::Button {
+                                set_icon_name: "play-symbolic",
+                                set_tooltip_text: Some(&fl!("album-slideshow", "tooltip")),
+                                connect_clicked => AppMsg::Slideshow,
+                            },
                         },
 
                         #[wrap(Some)]
@@ -523,6 +1108,7 @@ impl SimpleAsyncComponent for App {
                 set_content = model.onboard.widget(),
             }
             }
+            }
         }
     }
 
@@ -552,6 +1138,7 @@ impl SimpleAsyncComponent for App {
         let people_repo = people::Repository::open(&cache_dir, &data_dir, con.clone()).unwrap();
 
         let state = SharedState::new(relm4::SharedState::new());
+        let trash_state = SharedState::new(relm4::SharedState::new());
         let active_view = ActiveView::new(relm4::SharedState::new());
         let adaptive_layout = Arc::new(adaptive::LayoutState::new());
 
@@ -559,6 +1146,7 @@ impl SimpleAsyncComponent for App {
         match App::load_settings().await {
             std::result::Result::Ok(settings) => {
                 info!("Loaded settings: {:?}", settings);
+                thumbnailer.set_location(settings.thumbnail_location);
                 *settings_state.write() = settings;
             }
             Err(e) => error!("Failed loading settings: {}", e),
@@ -568,24 +1156,48 @@ impl SimpleAsyncComponent for App {
             AppMsg::SettingsChanged(settings.clone())
         });
 
+        let privacy_state = PrivacyState::new(relm4::SharedState::new());
+
         let bootstrap_progress_monitor: Reducer<ProgressMonitor> = Reducer::new();
         let bootstrap_progress_monitor = Arc::new(bootstrap_progress_monitor);
 
         let bootstrap_progress = self::components::progress_panel::ProgressPanel::builder()
             .launch(bootstrap_progress_monitor.clone())
-            .detach();
+            .forward(sender.input_sender(), |msg| match msg {
+                ProgressPanelOutput::Pause(is_paused) => AppMsg::PauseBackgroundTasks(is_paused),
+            });
 
         let bootstrap = Bootstrap::builder()
             .detach_worker((
                 con.clone(),
                 state.clone(),
+                trash_state.clone(),
                 settings_state.clone(),
+                privacy_state.clone(),
+                (*thumbnailer).clone(),
                 bootstrap_progress_monitor.clone(),
             ))
             .forward(sender.input_sender(), |msg| match msg {
                 BootstrapOutput::TaskStarted(msg) => AppMsg::TaskStarted(msg),
                 BootstrapOutput::Completed => AppMsg::BootstrapCompleted,
                 BootstrapOutput::Stopping => AppMsg::StoppingBackgroundTasks,
+                BootstrapOutput::Exported(summary) => AppMsg::PersonExported(summary),
+                BootstrapOutput::MetadataExported(count) => AppMsg::MetadataExported(count),
+                BootstrapOutput::Imported(summary) => AppMsg::Imported(summary),
+                BootstrapOutput::CacheUnwritable(path) => AppMsg::ThumbnailCacheUnwritable(path),
+                BootstrapOutput::LibraryLoadFailed => AppMsg::LibraryLoadFailed,
+                BootstrapOutput::PhotoThumbnailsCounted(count) => {
+                    AppMsg::PhotoThumbnailsCounted(count)
+                }
+                BootstrapOutput::MissingLocated(picture_id, located) => {
+                    AppMsg::MissingLocated(picture_id, located)
+                }
+                BootstrapOutput::SimilarFound(picture_id, similar) => {
+                    AppMsg::SimilarFound(picture_id, similar)
+                }
+                BootstrapOutput::DuplicatesFound(picture_ids) => {
+                    AppMsg::DuplicatesFound(picture_ids)
+                }
             });
 
         let onboard =
@@ -601,11 +1213,31 @@ impl SimpleAsyncComponent for App {
             .launch((state.clone(), active_view.clone(), adaptive_layout.clone(), thumbnailer.clone()))
             .forward(sender.input_sender(), |msg| match msg {
                 LibraryOutput::View(id) => AppMsg::View(id, AlbumFilter::All),
+                LibraryOutput::SetFavorite(picture_ids, is_favorite) => {
+                    AppMsg::SetFavorite(picture_ids, is_favorite)
+                }
+                LibraryOutput::SetTrashed(picture_ids, is_trashed) => {
+                    AppMsg::SetTrashed(picture_ids, is_trashed)
+                }
+                LibraryOutput::SetFolderCover(folder_path, picture_id) => {
+                    AppMsg::SetFolderCover(folder_path, picture_id)
+                }
+                LibraryOutput::RegenerateThumbnail(picture_id) => {
+                    AppMsg::RegenerateThumbnail(picture_id)
+                }
+                LibraryOutput::UngroupBurst(burst_id) => AppMsg::UngroupBurst(burst_id),
+                LibraryOutput::MovePictures(picture_ids, dest_folder) => {
+                    AppMsg::MovePictures(picture_ids, dest_folder)
+                }
+                LibraryOutput::AssignToPerson(picture_ids) => AppMsg::AssignToPerson(picture_ids),
             });
 
         settings_state.subscribe(library.sender(), |settings| {
             LibraryInput::Sort(settings.album_sort)
         });
+        settings_state.subscribe(library.sender(), |settings| {
+            LibraryInput::Zoom(settings.album_zoom)
+        });
 
         let view_nav = ViewNav::builder()
             .launch((
@@ -613,15 +1245,37 @@ impl SimpleAsyncComponent for App {
                 bootstrap_progress_monitor,
                 adaptive_layout.clone(),
                 people_repo.clone(),
+                settings_state.clone(),
             ))
             .forward(sender.input_sender(), |msg| match msg {
                 ViewNavOutput::TranscodeAll => AppMsg::TranscodeAll,
+                ViewNavOutput::ViewPlace(place_name) => AppMsg::ViewPlace(place_name),
+                ViewNavOutput::ViewTakenIn(filter) => AppMsg::ViewTakenIn(filter),
+                ViewNavOutput::ViewTag(name) => AppMsg::ViewTag(name),
+                ViewNavOutput::FindSimilar(picture_id) => AppMsg::FindSimilar(picture_id),
+                ViewNavOutput::AddTag(picture_id, name) => AppMsg::AddTag(picture_id, name),
+                ViewNavOutput::RemoveTag(picture_id, name) => AppMsg::RemoveTag(picture_id, name),
+                ViewNavOutput::AddToUserAlbum(picture_id, name) => {
+                    AppMsg::AddToUserAlbum(picture_id, name)
+                }
+                ViewNavOutput::RemoveFromUserAlbum(picture_id, album_id) => {
+                    AppMsg::RemoveFromUserAlbum(picture_id, album_id)
+                }
+                ViewNavOutput::SetUserAlbumCover(album_id, picture_id) => {
+                    AppMsg::SetUserAlbumCover(album_id, picture_id)
+                }
+                ViewNavOutput::ViewUserAlbum(album_id) => AppMsg::ViewUserAlbum(album_id),
+                ViewNavOutput::SetPrivate(picture_id, is_private) => {
+                    AppMsg::SetPrivate(picture_id, is_private)
+                }
             });
 
         settings_state.subscribe(view_nav.sender(), |settings| {
             ViewNavInput::Sort(settings.album_sort)
         });
 
+        privacy_state.subscribe(view_nav.sender(), |privacy| ViewNavInput::Privacy(*privacy));
+
         let selfies_page = Album::builder()
             .launch((
                 state.clone(),
@@ -633,12 +1287,35 @@ impl SimpleAsyncComponent for App {
             .forward(sender.input_sender(), |msg| match msg {
                 AlbumOutput::Selected(id, filter) => AppMsg::View(id, filter),
                 AlbumOutput::ScrollOffset(_) => AppMsg::Ignore,
+                AlbumOutput::SetFavorite(picture_ids, is_favorite) => {
+                    AppMsg::SetFavorite(picture_ids, is_favorite)
+                }
+                AlbumOutput::SetTrashed(picture_ids, is_trashed) => {
+                    AppMsg::SetTrashed(picture_ids, is_trashed)
+                }
+                AlbumOutput::SetFolderCover(folder_path, picture_id) => {
+                    AppMsg::SetFolderCover(folder_path, picture_id)
+                }
+                AlbumOutput::RegenerateThumbnail(picture_id) => {
+                    AppMsg::RegenerateThumbnail(picture_id)
+                }
+                AlbumOutput::UngroupBurst(burst_id) => AppMsg::UngroupBurst(burst_id),
+                AlbumOutput::MovePictures(picture_ids, dest_folder) => {
+                    AppMsg::MovePictures(picture_ids, dest_folder)
+                }
+                AlbumOutput::AssignToPerson(picture_ids) => AppMsg::AssignToPerson(picture_ids),
+                AlbumOutput::SetUserAlbumOrder(album_id, order) => {
+                    AppMsg::SetUserAlbumOrder(album_id, order)
+                }
             });
 
         state.subscribe(selfies_page.sender(), |_| AlbumInput::Refresh);
         adaptive_layout.subscribe(selfies_page.sender(), |layout| AlbumInput::Adapt(*layout));
         settings_state.subscribe(selfies_page.sender(), |settings| {
-            AlbumInput::Sort(settings.album_sort)
+            AlbumInput::SortBy(settings.album_sort)
+        });
+        settings_state.subscribe(selfies_page.sender(), |settings| {
+            AlbumInput::Zoom(settings.album_zoom)
         });
 
         let show_selfies = AppWidgets::show_selfies();
@@ -654,12 +1331,35 @@ impl SimpleAsyncComponent for App {
             .forward(sender.input_sender(), |msg| match msg {
                 AlbumOutput::Selected(id, filter) => AppMsg::View(id, filter),
                 AlbumOutput::ScrollOffset(_) => AppMsg::Ignore,
+                AlbumOutput::SetFavorite(picture_ids, is_favorite) => {
+                    AppMsg::SetFavorite(picture_ids, is_favorite)
+                }
+                AlbumOutput::SetTrashed(picture_ids, is_trashed) => {
+                    AppMsg::SetTrashed(picture_ids, is_trashed)
+                }
+                AlbumOutput::SetFolderCover(folder_path, picture_id) => {
+                    AppMsg::SetFolderCover(folder_path, picture_id)
+                }
+                AlbumOutput::RegenerateThumbnail(picture_id) => {
+                    AppMsg::RegenerateThumbnail(picture_id)
+                }
+                AlbumOutput::UngroupBurst(burst_id) => AppMsg::UngroupBurst(burst_id),
+                AlbumOutput::MovePictures(picture_ids, dest_folder) => {
+                    AppMsg::MovePictures(picture_ids, dest_folder)
+                }
+                AlbumOutput::AssignToPerson(picture_ids) => AppMsg::AssignToPerson(picture_ids),
+                AlbumOutput::SetUserAlbumOrder(album_id, order) => {
+                    AppMsg::SetUserAlbumOrder(album_id, order)
+                }
             });
 
         state.subscribe(motion_page.sender(), |_| AlbumInput::Refresh);
         adaptive_layout.subscribe(motion_page.sender(), |layout| AlbumInput::Adapt(*layout));
         settings_state.subscribe(motion_page.sender(), |settings| {
-            AlbumInput::Sort(settings.album_sort)
+            AlbumInput::SortBy(settings.album_sort)
+        });
+        settings_state.subscribe(motion_page.sender(), |settings| {
+            AlbumInput::Zoom(settings.album_zoom)
         });
 
         let videos_page = Album::builder()
@@ -667,41 +1367,223 @@ impl SimpleAsyncComponent for App {
                 state.clone(),
                 active_view.clone(),
                 ViewName::Videos,
-                AlbumFilter::Videos,
+                AlbumFilter::MediaType(progress_monitor::MediaType::Video),
                 thumbnailer.clone(),
             ))
             .forward(sender.input_sender(), |msg| match msg {
                 AlbumOutput::Selected(id, filter) => AppMsg::View(id, filter),
                 AlbumOutput::ScrollOffset(_) => AppMsg::Ignore,
+                AlbumOutput::SetFavorite(picture_ids, is_favorite) => {
+                    AppMsg::SetFavorite(picture_ids, is_favorite)
+                }
+                AlbumOutput::SetTrashed(picture_ids, is_trashed) => {
+                    AppMsg::SetTrashed(picture_ids, is_trashed)
+                }
+                AlbumOutput::SetFolderCover(folder_path, picture_id) => {
+                    AppMsg::SetFolderCover(folder_path, picture_id)
+                }
+                AlbumOutput::RegenerateThumbnail(picture_id) => {
+                    AppMsg::RegenerateThumbnail(picture_id)
+                }
+                AlbumOutput::UngroupBurst(burst_id) => AppMsg::UngroupBurst(burst_id),
+                AlbumOutput::MovePictures(picture_ids, dest_folder) => {
+                    AppMsg::MovePictures(picture_ids, dest_folder)
+                }
+                AlbumOutput::AssignToPerson(picture_ids) => AppMsg::AssignToPerson(picture_ids),
+                AlbumOutput::SetUserAlbumOrder(album_id, order) => {
+                    AppMsg::SetUserAlbumOrder(album_id, order)
+                }
             });
 
         state.subscribe(videos_page.sender(), |_| AlbumInput::Refresh);
         adaptive_layout.subscribe(videos_page.sender(), |layout| AlbumInput::Adapt(*layout));
         settings_state.subscribe(videos_page.sender(), |settings| {
-            AlbumInput::Sort(settings.album_sort)
+            AlbumInput::SortBy(settings.album_sort)
+        });
+        settings_state.subscribe(videos_page.sender(), |settings| {
+            AlbumInput::Zoom(settings.album_zoom)
         });
 
-        let people_page = PeopleAlbum::builder()
+        let trash_page = Album::builder()
             .launch((
-                people_repo.clone(),
+                trash_state.clone(),
                 active_view.clone(),
-                settings_state.clone(),
+                ViewName::Trash,
+                AlbumFilter::All,
+                thumbnailer.clone(),
             ))
             .forward(sender.input_sender(), |msg| match msg {
-                PeopleAlbumOutput::Selected(person) => AppMsg::ViewPerson(person),
-                PeopleAlbumOutput::EnableFaceDetection => AppMsg::ScanPicturesForFaces,
+                AlbumOutput::Selected(_, _) => AppMsg::Ignore,
+                AlbumOutput::ScrollOffset(_) => AppMsg::Ignore,
+                AlbumOutput::SetFavorite(picture_ids, is_favorite) => {
+                    AppMsg::SetFavorite(picture_ids, is_favorite)
+                }
+                AlbumOutput::SetTrashed(picture_ids, is_trashed) => {
+                    AppMsg::SetTrashed(picture_ids, is_trashed)
+                }
+                AlbumOutput::SetFolderCover(folder_path, picture_id) => {
+                    AppMsg::SetFolderCover(folder_path, picture_id)
+                }
+                AlbumOutput::RegenerateThumbnail(picture_id) => {
+                    AppMsg::RegenerateThumbnail(picture_id)
+                }
+                AlbumOutput::UngroupBurst(burst_id) => AppMsg::UngroupBurst(burst_id),
+                AlbumOutput::MovePictures(picture_ids, dest_folder) => {
+                    AppMsg::MovePictures(picture_ids, dest_folder)
+                }
+                AlbumOutput::AssignToPerson(picture_ids) => AppMsg::AssignToPerson(picture_ids),
+                AlbumOutput::SetUserAlbumOrder(album_id, order) => {
+                    AppMsg::SetUserAlbumOrder(album_id, order)
+                }
             });
 
-        adaptive_layout.subscribe(people_page.sender(), |layout| {
-            PeopleAlbumInput::Adapt(*layout)
+        // Trashed items can only be selected for restoring, not opened.
+        trash_page.emit(AlbumInput::EnterSelectionMode);
+
+        trash_state.subscribe(trash_page.sender(), |_| AlbumInput::Refresh);
+        adaptive_layout.subscribe(trash_page.sender(), |layout| AlbumInput::Adapt(*layout));
+        settings_state.subscribe(trash_page.sender(), |settings| {
+            AlbumInput::SortBy(settings.album_sort)
+        });
+        settings_state.subscribe(trash_page.sender(), |settings| {
+            AlbumInput::Zoom(settings.album_zoom)
         });
 
-        let person_album = PersonAlbum::builder()
-            .launch((state.clone(), people_repo.clone(), active_view.clone(), thumbnailer.clone()))
-            .forward(sender.input_sender(), |msg| match msg {
-                PersonAlbumOutput::Selected(id, filter) => AppMsg::View(id, filter),
-                PersonAlbumOutput::Deleted => AppMsg::PersonDeleted,
+        let favorites_page = Album::builder()
+            .launch((
+                state.clone(),
+                active_view.clone(),
+                ViewName::Favorites,
+                AlbumFilter::Favorites,
+                thumbnailer.clone(),
+            ))
+            .forward(sender.input_sender(), |msg| match msg {
+                AlbumOutput::Selected(id, filter) => AppMsg::View(id, filter),
+                AlbumOutput::ScrollOffset(_) => AppMsg::Ignore,
+                AlbumOutput::SetFavorite(picture_ids, is_favorite) => {
+                    AppMsg::SetFavorite(picture_ids, is_favorite)
+                }
+                AlbumOutput::SetTrashed(picture_ids, is_trashed) => {
+                    AppMsg::SetTrashed(picture_ids, is_trashed)
+                }
+                AlbumOutput::SetFolderCover(folder_path, picture_id) => {
+                    AppMsg::SetFolderCover(folder_path, picture_id)
+                }
+                AlbumOutput::RegenerateThumbnail(picture_id) => {
+                    AppMsg::RegenerateThumbnail(picture_id)
+                }
+                AlbumOutput::UngroupBurst(burst_id) => AppMsg::UngroupBurst(burst_id),
+                AlbumOutput::MovePictures(picture_ids, dest_folder) => {
+                    AppMsg::MovePictures(picture_ids, dest_folder)
+                }
+                AlbumOutput::AssignToPerson(picture_ids) => AppMsg::AssignToPerson(picture_ids),
+                AlbumOutput::SetUserAlbumOrder(album_id, order) => {
+                    AppMsg::SetUserAlbumOrder(album_id, order)
+                }
+            });
+
+        state.subscribe(favorites_page.sender(), |_| AlbumInput::Refresh);
+        adaptive_layout.subscribe(favorites_page.sender(), |layout| AlbumInput::Adapt(*layout));
+        settings_state.subscribe(favorites_page.sender(), |settings| {
+            AlbumInput::SortBy(settings.album_sort)
+        });
+        settings_state.subscribe(favorites_page.sender(), |settings| {
+            AlbumInput::Zoom(settings.album_zoom)
+        });
+
+        let on_this_day_page = Album::builder()
+            .launch((
+                state.clone(),
+                active_view.clone(),
+                ViewName::OnThisDay,
+                AlbumFilter::on_this_day(),
+                thumbnailer.clone(),
+            ))
+            .forward(sender.input_sender(), |msg| match msg {
+                AlbumOutput::Selected(id, filter) => AppMsg::View(id, filter),
+                AlbumOutput::ScrollOffset(_) => AppMsg::Ignore,
+                AlbumOutput::SetFavorite(picture_ids, is_favorite) => {
+                    AppMsg::SetFavorite(picture_ids, is_favorite)
+                }
+                AlbumOutput::SetTrashed(picture_ids, is_trashed) => {
+                    AppMsg::SetTrashed(picture_ids, is_trashed)
+                }
+                AlbumOutput::SetFolderCover(folder_path, picture_id) => {
+                    AppMsg::SetFolderCover(folder_path, picture_id)
+                }
+                AlbumOutput::RegenerateThumbnail(picture_id) => {
+                    AppMsg::RegenerateThumbnail(picture_id)
+                }
+                AlbumOutput::UngroupBurst(burst_id) => AppMsg::UngroupBurst(burst_id),
+                AlbumOutput::MovePictures(picture_ids, dest_folder) => {
+                    AppMsg::MovePictures(picture_ids, dest_folder)
+                }
+                AlbumOutput::AssignToPerson(picture_ids) => AppMsg::AssignToPerson(picture_ids),
+                AlbumOutput::SetUserAlbumOrder(album_id, order) => {
+                    AppMsg::SetUserAlbumOrder(album_id, order)
+                }
+            });
+
+        state.subscribe(on_this_day_page.sender(), |_| AlbumInput::Refresh);
+        adaptive_layout.subscribe(on_this_day_page.sender(), |layout| AlbumInput::Adapt(*layout));
+        settings_state.subscribe(on_this_day_page.sender(), |settings| {
+            AlbumInput::SortBy(settings.album_sort)
+        });
+        settings_state.subscribe(on_this_day_page.sender(), |settings| {
+            AlbumInput::Zoom(settings.album_zoom)
+        });
+
+        let people_page = PeopleAlbum::builder()
+            .launch((
+                people_repo.clone(),
+                active_view.clone(),
+                settings_state.clone(),
+            ))
+            .forward(sender.input_sender(), |msg| match msg {
+                PeopleAlbumOutput::Selected(person) => AppMsg::ViewPerson(person),
+                PeopleAlbumOutput::EnableFaceDetection => AppMsg::ScanPicturesForFaces,
+            });
+
+        adaptive_layout.subscribe(people_page.sender(), |layout| {
+            PeopleAlbumInput::Adapt(*layout)
+        });
+
+        let person_album = PersonAlbum::builder()
+            .launch((
+                state.clone(),
+                people_repo.clone(),
+                active_view.clone(),
+                settings_state.clone(),
+                thumbnailer.clone(),
+            ))
+            .forward(sender.input_sender(), |msg| match msg {
+                PersonAlbumOutput::Selected(id, filter) => AppMsg::View(id, filter),
+                PersonAlbumOutput::Deleted => AppMsg::PersonDeleted,
                 PersonAlbumOutput::Renamed => AppMsg::PersonRenamed,
+                PersonAlbumOutput::Hidden => AppMsg::PersonHidden,
+                PersonAlbumOutput::FacesChanged => AppMsg::PersonFacesChanged,
+                PersonAlbumOutput::Export(person_id, destination) => {
+                    AppMsg::ExportPerson(person_id, destination)
+                }
+                PersonAlbumOutput::SetFavorite(picture_ids, is_favorite) => {
+                    AppMsg::SetFavorite(picture_ids, is_favorite)
+                }
+                PersonAlbumOutput::SetTrashed(picture_ids, is_trashed) => {
+                    AppMsg::SetTrashed(picture_ids, is_trashed)
+                }
+                PersonAlbumOutput::SetFolderCover(folder_path, picture_id) => {
+                    AppMsg::SetFolderCover(folder_path, picture_id)
+                }
+                PersonAlbumOutput::RegenerateThumbnail(picture_id) => {
+                    AppMsg::RegenerateThumbnail(picture_id)
+                }
+                PersonAlbumOutput::UngroupBurst(burst_id) => AppMsg::UngroupBurst(burst_id),
+                PersonAlbumOutput::MovePictures(picture_ids, dest_folder) => {
+                    AppMsg::MovePictures(picture_ids, dest_folder)
+                }
+                PersonAlbumOutput::AssignToPerson(picture_ids) => {
+                    AppMsg::AssignToPerson(picture_ids)
+                }
             });
 
         state.subscribe(person_album.sender(), |_| PersonAlbumInput::Refresh);
@@ -711,27 +1593,45 @@ impl SimpleAsyncComponent for App {
         settings_state.subscribe(person_album.sender(), |settings| {
             PersonAlbumInput::Sort(settings.album_sort)
         });
+        settings_state.subscribe(person_album.sender(), |settings| {
+            PersonAlbumInput::Zoom(settings.album_zoom)
+        });
 
-        let places_page = PlacesAlbum::builder()
-            .launch((state.clone(), active_view.clone(), thumbnailer.clone()))
-            .forward(sender.input_sender(), |msg| match msg {
-                PlacesAlbumOutput::View(visual_id) => {
-                    AppMsg::View(visual_id.clone(), AlbumFilter::One(visual_id))
-                }
-                PlacesAlbumOutput::GeographicArea(cell_index) => {
-                    AppMsg::ViewGeographicArea(cell_index)
-                }
-            });
+        #[cfg(feature = "map")]
+        let places_page = {
+            let places_page = PlacesAlbum::builder()
+                .launch((state.clone(), active_view.clone(), thumbnailer.clone()))
+                .forward(sender.input_sender(), |msg| match msg {
+                    PlacesAlbumOutput::View(visual_id) => {
+                        AppMsg::View(visual_id.clone(), AlbumFilter::One(visual_id))
+                    }
+                    PlacesAlbumOutput::GeographicArea(cell_index) => {
+                        AppMsg::ViewGeographicArea(cell_index)
+                    }
+                });
 
-        state.subscribe(places_page.sender(), |_| PlacesAlbumInput::Refresh);
-        adaptive_layout.subscribe(places_page.sender(), |layout| {
-            PlacesAlbumInput::Adapt(*layout)
-        });
+            state.subscribe(places_page.sender(), |_| PlacesAlbumInput::Refresh);
+            adaptive_layout.subscribe(places_page.sender(), |layout| {
+                PlacesAlbumInput::Adapt(*layout)
+            });
+            places_page
+        };
 
         let folders_album = FoldersAlbum::builder()
-            .launch((state.clone(), active_view.clone(), thumbnailer.clone()))
+            .launch((
+                state.clone(),
+                settings_state.clone(),
+                active_view.clone(),
+                thumbnailer.clone(),
+                people_repo.clone(),
+                None,
+            ))
             .forward(sender.input_sender(), |msg| match msg {
                 FoldersAlbumOutput::FolderSelected(path) => AppMsg::ViewFolder(path),
+                FoldersAlbumOutput::SubfoldersSelected(path) => AppMsg::ViewSubfolders(path),
+                FoldersAlbumOutput::SetHidden(path, is_hidden) => {
+                    AppMsg::SetFolderHidden(path, is_hidden)
+                }
             });
 
         state.subscribe(folders_album.sender(), |_| FoldersAlbumInput::Refresh);
@@ -749,45 +1649,162 @@ impl SimpleAsyncComponent for App {
             ))
             .forward(sender.input_sender(), |msg| match msg {
                 AlbumOutput::Selected(id, filter) => AppMsg::View(id, filter),
-                AlbumOutput::ScrollOffset(_) => AppMsg::Ignore,
+                AlbumOutput::ScrollOffset(offset) => AppMsg::FolderScrolled(offset),
+                AlbumOutput::SetFavorite(picture_ids, is_favorite) => {
+                    AppMsg::SetFavorite(picture_ids, is_favorite)
+                }
+                AlbumOutput::SetTrashed(picture_ids, is_trashed) => {
+                    AppMsg::SetTrashed(picture_ids, is_trashed)
+                }
+                AlbumOutput::SetFolderCover(folder_path, picture_id) => {
+                    AppMsg::SetFolderCover(folder_path, picture_id)
+                }
+                AlbumOutput::RegenerateThumbnail(picture_id) => {
+                    AppMsg::RegenerateThumbnail(picture_id)
+                }
+                AlbumOutput::UngroupBurst(burst_id) => AppMsg::UngroupBurst(burst_id),
+                AlbumOutput::MovePictures(picture_ids, dest_folder) => {
+                    AppMsg::MovePictures(picture_ids, dest_folder)
+                }
+                AlbumOutput::AssignToPerson(picture_ids) => AppMsg::AssignToPerson(picture_ids),
+                AlbumOutput::SetUserAlbumOrder(album_id, order) => {
+                    AppMsg::SetUserAlbumOrder(album_id, order)
+                }
             });
 
         state.subscribe(folder_album.sender(), |_| AlbumInput::Refresh);
         adaptive_layout.subscribe(folder_album.sender(), |layout| AlbumInput::Adapt(*layout));
         settings_state.subscribe(folder_album.sender(), |settings| {
-            AlbumInput::Sort(settings.album_sort)
+            AlbumInput::SortBy(settings.album_sort)
+        });
+        settings_state.subscribe(folder_album.sender(), |settings| {
+            AlbumInput::Zoom(settings.album_zoom)
         });
 
         let about_dialog = AboutDialog::builder().launch(root.clone()).detach();
 
         let preferences_dialog = PreferencesDialog::builder()
-            .launch((settings_state.clone(), root.clone()))
+            .launch((settings_state.clone(), privacy_state.clone(), root.clone()))
             .forward(sender.input_sender(), |msg| match msg {
                 PreferencesOutput::EnableFaceDetection => AppMsg::ScanPicturesForFaces,
+                PreferencesOutput::FaceDetectionModelChanged => AppMsg::FaceDetectionModelChanged,
                 PreferencesOutput::ProcessMotionPhotos=> AppMsg::ProcessMotionPhotos,
+                PreferencesOutput::GenerateAnimatedPreviews => AppMsg::GenerateAnimatedPreviews,
+                PreferencesOutput::RemoveAnimatedPreviews => AppMsg::RemoveAnimatedPreviews,
+                PreferencesOutput::LimitThumbnailCache => AppMsg::LimitThumbnailCache,
+                PreferencesOutput::ThumbnailLocationChanged => AppMsg::ThumbnailLocationChanged,
+                PreferencesOutput::Rescan => AppMsg::Rescan,
+                PreferencesOutput::Rebuild => AppMsg::Rebuild,
             });
 
+        privacy_state.subscribe(preferences_dialog.sender(), |privacy| {
+            PreferencesInput::Privacy(*privacy)
+        });
+
+        let stats_dialog = StatsDialog::builder()
+            .launch((
+                con.clone(),
+                settings_state.clone(),
+                cache_dir.clone(),
+                thumbnail_dir.clone(),
+                root.clone(),
+            ))
+            .detach();
+
+        let broken_photos_dialog = BrokenPhotosDialog::builder()
+            .launch((
+                con.clone(),
+                settings_state.clone(),
+                cache_dir.clone(),
+                data_dir.clone(),
+                root.clone(),
+            ))
+            .forward(sender.input_sender(), |msg| match msg {
+                BrokenPhotosOutput::RegenerateThumbnail(picture_id) => {
+                    AppMsg::RegenerateThumbnail(picture_id)
+                }
+                BrokenPhotosOutput::SetTrashed(picture_ids, is_trashed) => {
+                    AppMsg::SetTrashed(picture_ids, is_trashed)
+                }
+            });
+
+        let missing_photos_dialog = MissingPhotosDialog::builder()
+            .launch((
+                con.clone(),
+                settings_state.clone(),
+                cache_dir.clone(),
+                data_dir.clone(),
+                root.clone(),
+            ))
+            .forward(sender.input_sender(), |msg| match msg {
+                MissingPhotosOutput::Locate(picture_id, path) => {
+                    AppMsg::LocateMissing(picture_id, path)
+                }
+                MissingPhotosOutput::RemoveFromLibrary(picture_id) => {
+                    AppMsg::RemoveMissing(picture_id)
+                }
+            });
+
+        let person_select = PersonSelect::builder()
+            .launch((people_repo.clone(), settings_state.clone()))
+            .forward(sender.input_sender(), |msg| match msg {
+                PersonSelectOutput::Done => AppMsg::PersonAssigned,
+            });
+
+        let person_dialog = adw::Dialog::builder()
+            .child(person_select.widget())
+            .presentation_mode(adw::DialogPresentationMode::BottomSheet)
+            .height_request(400)
+            .build();
+
         let picture_navigation_view = adw::NavigationView::builder().build();
 
         let main_navigation = adw::OverlaySplitView::builder().build();
 
         let main_stack = gtk::Stack::new();
 
+        let folders_navigation_view = adw::NavigationView::new();
+
         let header_bar = adw::HeaderBar::new();
 
         let spinner = adw::Spinner::builder().visible(false).build();
 
+        let album_search_entry = gtk::SearchEntry::new();
+
+        let album_select_button = gtk::ToggleButton::new();
+
+        let date_range_picker = DateRangePicker::builder()
+            .launch(())
+            .forward(sender.input_sender(), |msg| match msg {
+                DateRangePickerOutput::Selected(from, to) => AppMsg::ViewDateRange(from, to),
+            });
+
         let banner = adw::Banner::builder()
             .button_label(fl!("banner-button-stop", "label"))
             .tooltip_text(fl!("banner-button-stop", "tooltip"))
             .build();
 
+        let error_banner = adw::Banner::builder()
+            .button_label(fl!("banner-button-dismiss"))
+            .build();
+
+        let toasts = adw::ToastOverlay::new();
+
         let model = Self {
             adaptive_layout,
+            state: state.clone(),
+            active_view: active_view.clone(),
+            thumbnailer: thumbnailer.clone(),
+            people_repo: people_repo.clone(),
             bootstrap,
 
             about_dialog,
             preferences_dialog,
+            stats_dialog,
+            broken_photos_dialog,
+            missing_photos_dialog,
+            person_dialog,
+            person_select,
 
             onboard,
             onboard_view: onboard_view.clone(),
@@ -797,13 +1814,21 @@ impl SimpleAsyncComponent for App {
             view_nav,
             motion_page,
             videos_page,
+            favorites_page,
+            on_this_day_page,
+            trash_page,
             people_page,
             person_album,
+            #[cfg(feature = "map")]
             places_page,
             selfies_page,
             show_selfies,
             folders_album,
+            subfolders_albums: Vec::new(),
+            folders_navigation_view: folders_navigation_view.clone(),
             folder_album,
+            last_folder: None,
+            restore_folder: App::load_last_folder(),
 
             main_navigation: main_navigation.clone(),
             main_stack: main_stack.clone(),
@@ -811,16 +1836,26 @@ impl SimpleAsyncComponent for App {
             picture_navigation_view: picture_navigation_view.clone(),
             header_bar: header_bar.clone(),
             spinner: spinner.clone(),
+            album_search_entry: album_search_entry.clone(),
+            album_select_button: album_select_button.clone(),
+            date_range_picker,
 
             bootstrap_progress,
 
             banner: banner.clone(),
+            banner_title_before_pause: None,
+            error_banner: error_banner.clone(),
+            toasts: toasts.clone(),
 
             settings_state: settings_state.clone(),
+            privacy_state,
         };
 
         let widgets = view_output!();
 
+        #[cfg(feature = "map")]
+        widgets.places_box.append(model.places_page.widget());
+
         let mut actions = RelmActionGroup::<WindowActionGroup>::new();
 
         let about_action = {
@@ -837,11 +1872,198 @@ impl SimpleAsyncComponent for App {
             })
         };
 
+        let stats_action = {
+            let sender = model.stats_dialog.sender().clone();
+            RelmAction::<StatsAction>::new_stateless(move |_| {
+                sender.send(StatsInput::Present).unwrap();
+            })
+        };
+
+        let broken_photos_action = {
+            let sender = model.broken_photos_dialog.sender().clone();
+            RelmAction::<BrokenPhotosAction>::new_stateless(move |_| {
+                sender.send(BrokenPhotosInput::Present).unwrap();
+            })
+        };
+
+        let missing_photos_action = {
+            let sender = model.missing_photos_dialog.sender().clone();
+            RelmAction::<MissingPhotosAction>::new_stateless(move |_| {
+                sender.send(MissingPhotosInput::Present).unwrap();
+            })
+        };
+
+        let duplicate_photos_action = {
+            let sender = sender.input_sender().clone();
+            RelmAction::<DuplicatePhotosAction>::new_stateless(move |_| {
+                sender.emit(AppMsg::FindDuplicates);
+            })
+        };
+
+        let import_files_action = {
+            let sender = sender.input_sender().clone();
+            RelmAction::<ImportFilesAction>::new_stateless(move |_| {
+                sender.emit(AppMsg::ImportFilesDialog);
+            })
+        };
+
+        let import_folders_action = {
+            let sender = sender.input_sender().clone();
+            RelmAction::<ImportFoldersAction>::new_stateless(move |_| {
+                sender.emit(AppMsg::ImportFoldersDialog);
+            })
+        };
+
+        let export_metadata_action = {
+            let sender = sender.input_sender().clone();
+            RelmAction::<ExportMetadataAction>::new_stateless(move |_| {
+                sender.emit(AppMsg::ExportMetadataDialog);
+            })
+        };
+
+        let write_sidecars_action = {
+            let sender = sender.input_sender().clone();
+            RelmAction::<WriteSidecarsAction>::new_stateless(move |_| {
+                sender.emit(AppMsg::WriteSidecars);
+            })
+        };
+
+        let rescan_action = {
+            let sender = sender.input_sender().clone();
+            RelmAction::<RescanAction>::new_stateless(move |_| {
+                sender.emit(AppMsg::Rescan);
+            })
+        };
+
+        let rebuild_action = {
+            let sender = sender.input_sender().clone();
+            RelmAction::<RebuildAction>::new_stateless(move |_| {
+                sender.emit(AppMsg::Rebuild);
+            })
+        };
+
+        let generate_thumbnails_action = {
+            let sender = sender.input_sender().clone();
+            RelmAction::<GenerateThumbnailsAction>::new_stateless(move |_| {
+                sender.emit(AppMsg::CountPhotoThumbnails);
+            })
+        };
+
+        let filter_photos_action = {
+            let sender = sender.input_sender().clone();
+            RelmAction::<FilterPhotosAction>::new_stateless(move |_| {
+                sender.emit(AppMsg::AddAlbumFilter(AlbumFilter::MediaType(
+                    progress_monitor::MediaType::Photo,
+                )));
+            })
+        };
+
+        let filter_videos_action = {
+            let sender = sender.input_sender().clone();
+            RelmAction::<FilterVideosAction>::new_stateless(move |_| {
+                sender.emit(AppMsg::AddAlbumFilter(AlbumFilter::MediaType(
+                    progress_monitor::MediaType::Video,
+                )));
+            })
+        };
+
+        let filter_favorites_action = {
+            let sender = sender.input_sender().clone();
+            RelmAction::<FilterFavoritesAction>::new_stateless(move |_| {
+                sender.emit(AppMsg::AddAlbumFilter(AlbumFilter::Favorites));
+            })
+        };
+
+        let filter_private_action = {
+            let sender = sender.input_sender().clone();
+            RelmAction::<FilterPrivateAction>::new_stateless(move |_| {
+                sender.emit(AppMsg::AddAlbumFilter(AlbumFilter::Private));
+            })
+        };
+
+        let unlock_private_action = {
+            let sender = sender.input_sender().clone();
+            RelmAction::<UnlockPrivateAction>::new_stateless(move |_| {
+                sender.emit(AppMsg::UnlockPrivate);
+            })
+        };
+
+        let lock_private_action = {
+            let sender = sender.input_sender().clone();
+            RelmAction::<LockPrivateAction>::new_stateless(move |_| {
+                sender.emit(AppMsg::LockPrivate);
+            })
+        };
+
+        let undo_action = {
+            let sender = sender.input_sender().clone();
+            RelmAction::<UndoAction>::new_stateless(move |_| {
+                sender.emit(AppMsg::Undo);
+            })
+        };
+
+        let zoom_in_action = {
+            let sender = sender.input_sender().clone();
+            RelmAction::<ZoomInAction>::new_stateless(move |_| {
+                sender.emit(AppMsg::ZoomIn);
+            })
+        };
+
+        let zoom_out_action = {
+            let sender = sender.input_sender().clone();
+            RelmAction::<ZoomOutAction>::new_stateless(move |_| {
+                sender.emit(AppMsg::ZoomOut);
+            })
+        };
+
         actions.add_action(about_action);
         actions.add_action(preferences_action);
+        actions.add_action(stats_action);
+        actions.add_action(broken_photos_action);
+        actions.add_action(missing_photos_action);
+        actions.add_action(duplicate_photos_action);
+        actions.add_action(import_files_action);
+        actions.add_action(import_folders_action);
+        actions.add_action(export_metadata_action);
+        actions.add_action(write_sidecars_action);
+        actions.add_action(rescan_action);
+        actions.add_action(rebuild_action);
+        actions.add_action(generate_thumbnails_action);
+        actions.add_action(filter_photos_action);
+        actions.add_action(filter_videos_action);
+        actions.add_action(filter_favorites_action);
+        actions.add_action(filter_private_action);
+        actions.add_action(unlock_private_action);
+        actions.add_action(lock_private_action);
+        actions.add_action(undo_action);
+        actions.add_action(zoom_in_action);
+        actions.add_action(zoom_out_action);
 
         actions.register_for_widget(&widgets.main_window);
 
+        let remote_rescan_action = {
+            let sender = sender.input_sender().clone();
+            RelmAction::<RemoteRescanAction>::new_stateless(move |_| {
+                info!("Rescan requested from outside the app");
+                sender.emit(AppMsg::Rescan);
+            })
+        };
+
+        let mut app_actions = RelmActionGroup::<crate::AppActionGroup>::new();
+        app_actions.add_action(remote_rescan_action);
+        app_actions.register_for_main_application();
+
+        main_application().set_accelerators_for_action::<UndoAction>(&["<Control>z"]);
+        main_application().set_accelerators_for_action::<ZoomInAction>(&[
+            "<Control>plus",
+            "<Control>equal",
+            "<Control>KP_Add",
+        ]);
+        main_application().set_accelerators_for_action::<ZoomOutAction>(&[
+            "<Control>minus",
+            "<Control>KP_Subtract",
+        ]);
+
         widgets.load_window_size();
 
         // Get startup window size and propagate so all components have correct narrow/wide layout.
@@ -927,11 +2149,17 @@ impl SimpleAsyncComponent for App {
                     ViewName::Videos => self.videos_page.emit(AlbumInput::Activate),
                     ViewName::Selfies => self.selfies_page.emit(AlbumInput::Activate),
                     ViewName::Animated => self.motion_page.emit(AlbumInput::Activate),
+                    ViewName::Favorites => self.favorites_page.emit(AlbumInput::Activate),
+                    ViewName::OnThisDay => self.on_this_day_page.emit(AlbumInput::Activate),
                     ViewName::Folders => self.folders_album.emit(FoldersAlbumInput::Activate),
                     ViewName::Folder => self.folder_album.emit(AlbumInput::Activate),
                     ViewName::People => self.people_page.emit(PeopleAlbumInput::Activate),
                     ViewName::Person => self.person_album.emit(PersonAlbumInput::Activate),
+                    #[cfg(feature = "map")]
                     ViewName::Places => self.places_page.emit(PlacesAlbumInput::Activate),
+                    #[cfg(not(feature = "map"))]
+                    ViewName::Places => {}
+                    ViewName::Trash => self.trash_page.emit(AlbumInput::Activate),
                     ViewName::Nothing => warn!("Nothing activated... which should not happen"),
                 }
             }
@@ -944,18 +2172,138 @@ impl SimpleAsyncComponent for App {
             }
             AppMsg::ViewHidden => {
                 self.view_nav.emit(ViewNavInput::Hidden);
+
+                let is_album_visible = self
+                    .picture_navigation_view
+                    .visible_page()
+                    .and_then(|page| page.tag())
+                    .is_some_and(|tag| tag == "album");
+                if !is_album_visible {
+                    self.last_folder = None;
+                }
             }
             AppMsg::ViewFolder(path) => {
-                self.folder_album.emit(AlbumInput::Activate);
-                self.folder_album
-                    .emit(AlbumInput::Filter(AlbumFilter::Folder(path)));
-                self.picture_navigation_view.push_by_tag("album");
+                self.show_album(AlbumFilter::Folder(path.clone()));
+                self.last_folder = Some((path, 0.0));
+            }
+            AppMsg::FolderScrolled(offset) => {
+                if let Some((_, scroll_offset)) = self.last_folder.as_mut() {
+                    *scroll_offset = offset;
+                }
             }
+            AppMsg::ViewSubfolders(path) => {
+                let subfolders_album = FoldersAlbum::builder()
+                    .launch((
+                        self.state.clone(),
+                        self.settings_state.clone(),
+                        self.active_view.clone(),
+                        self.thumbnailer.clone(),
+                        self.people_repo.clone(),
+                        Some(path.clone()),
+                    ))
+                    .forward(sender.input_sender(), |msg| match msg {
+                        FoldersAlbumOutput::FolderSelected(path) => AppMsg::ViewFolder(path),
+                        FoldersAlbumOutput::SubfoldersSelected(path) => {
+                            AppMsg::ViewSubfolders(path)
+                        }
+                        FoldersAlbumOutput::SetHidden(path, is_hidden) => {
+                            AppMsg::SetFolderHidden(path, is_hidden)
+                        }
+                    });
+
+                self.state
+                    .subscribe(subfolders_album.sender(), |_| FoldersAlbumInput::Refresh);
+                self.adaptive_layout
+                    .subscribe(subfolders_album.sender(), |layout| {
+                        FoldersAlbumInput::Adapt(*layout)
+                    });
+
+                subfolders_album.emit(FoldersAlbumInput::Adapt(*self.adaptive_layout.read()));
+                subfolders_album.emit(FoldersAlbumInput::Activate);
+
+                let title = path
+                    .file_name()
+                    .map(|x| x.to_string_lossy().to_string())
+                    .unwrap_or_default();
+
+                let page = adw::NavigationPage::new(subfolders_album.widget(), &title);
+                self.subfolders_albums.push(subfolders_album);
+                self.folders_navigation_view.push(&page);
+            }
+            AppMsg::SubfoldersHidden => {
+                self.subfolders_albums.pop();
+            }
+            #[cfg(feature = "map")]
             AppMsg::ViewGeographicArea(cell_index) => {
-                self.folder_album.emit(AlbumInput::Activate);
-                self.folder_album
-                    .emit(AlbumInput::Filter(AlbumFilter::GeographicArea(cell_index)));
-                self.picture_navigation_view.push_by_tag("album");
+                self.show_album(AlbumFilter::GeographicArea(cell_index));
+            }
+            AppMsg::ViewDateRange(from, to) => {
+                self.show_album(AlbumFilter::DateRange { from, to });
+            }
+            AppMsg::ViewPlace(place_name) => {
+                self.show_album_from_viewer(AlbumFilter::Place(place_name));
+            }
+            AppMsg::ViewTakenIn(filter) => {
+                self.show_album_from_viewer(filter);
+            }
+            AppMsg::ViewTag(name) => {
+                self.show_album_from_viewer(AlbumFilter::Tag(name));
+            }
+            AppMsg::ViewUserAlbum(album_id) => {
+                self.show_album_from_viewer(AlbumFilter::UserAlbum(album_id));
+            }
+            AppMsg::FindSimilar(picture_id) => {
+                self.bootstrap.emit(BootstrapInput::FindSimilar(picture_id));
+            }
+            AppMsg::SimilarFound(picture_id, similar) => {
+                // Show the picture too, to compare it with the pictures like it.
+                let picture_ids = std::iter::once(picture_id).chain(similar).collect();
+                self.show_album_from_viewer(AlbumFilter::Any(picture_ids));
+            }
+            AppMsg::FindDuplicates => {
+                self.bootstrap.emit(BootstrapInput::FindDuplicates);
+            }
+            AppMsg::DuplicatesFound(picture_ids) => {
+                self.show_album(AlbumFilter::Any(picture_ids));
+            }
+            AppMsg::AddAlbumFilter(filter) => {
+                self.folder_album.emit(AlbumInput::AddFilter(filter));
+            }
+            AppMsg::SearchLibrary => {
+                self.show_album(AlbumFilter::All);
+                self.album_search_entry.grab_focus();
+            }
+            AppMsg::SearchAlbum(query) => {
+                self.folder_album.emit(AlbumInput::Search(query));
+            }
+            AppMsg::AlbumSelectionMode(true) => {
+                self.folder_album.emit(AlbumInput::EnterSelectionMode);
+            }
+            AppMsg::AlbumSelectionMode(false) => {
+                self.folder_album.emit(AlbumInput::ExitSelectionMode);
+            }
+            AppMsg::Slideshow => {
+                let interval = self.settings_state.read().slideshow_interval_secs;
+                let interval = Duration::from_secs(interval.into());
+
+                let view_name = *self.active_view.read();
+                info!("Slideshow of {:?} view", view_name);
+
+                match view_name {
+                    ViewName::All => self.library.emit(LibraryInput::Slideshow(interval)),
+                    ViewName::Videos => self.videos_page.emit(AlbumInput::Slideshow(interval)),
+                    ViewName::Animated => self.motion_page.emit(AlbumInput::Slideshow(interval)),
+                    ViewName::Favorites => {
+                        self.favorites_page.emit(AlbumInput::Slideshow(interval))
+                    }
+                    ViewName::OnThisDay => {
+                        self.on_this_day_page.emit(AlbumInput::Slideshow(interval))
+                    }
+                    ViewName::Selfies => self.selfies_page.emit(AlbumInput::Slideshow(interval)),
+                    ViewName::Trash => self.trash_page.emit(AlbumInput::Slideshow(interval)),
+                    ViewName::Folder => self.folder_album.emit(AlbumInput::Slideshow(interval)),
+                    _ => info!("No slideshow for {:?} view", view_name),
+                }
             }
             AppMsg::ViewPerson(person) => {
                 //info!("picture_ids = {:?}", picture_ids);
@@ -971,6 +2319,12 @@ impl SimpleAsyncComponent for App {
             AppMsg::PersonRenamed => {
                 self.people_page.emit(PeopleAlbumInput::Refresh);
             }
+            AppMsg::PersonHidden => {
+                self.people_page.emit(PeopleAlbumInput::Refresh);
+            }
+            AppMsg::PersonFacesChanged => {
+                self.people_page.emit(PeopleAlbumInput::Refresh);
+            }
             AppMsg::TaskStarted(task_name) => {
                 self.spinner
                     .set_visible(!self.main_navigation.shows_sidebar());
@@ -1003,6 +2357,9 @@ impl SimpleAsyncComponent for App {
                     TaskName::Thumbnail(ThumbnailType::Face) => {
                         self.banner.set_title(&fl!("banner-face-thumbnails"));
                     }
+                    TaskName::Thumbnail(ThumbnailType::Animated) => {
+                        self.banner.set_title(&fl!("banner-thumbnails-animated"));
+                    }
                     TaskName::DetectFaces => {
                         self.banner.set_title(&fl!("banner-detect-faces-photos"));
                     }
@@ -1018,18 +2375,283 @@ impl SimpleAsyncComponent for App {
                     TaskName::Transcode => {
                         self.banner.set_title(&fl!("banner-convert-videos"));
                     }
+                    TaskName::Bursts => {
+                        self.banner.set_title(&fl!("banner-bursts"));
+                    }
                     TaskName::Tidy => {
                         // Show nothing
                     }
                     TaskName::Migrate => {
                         // Show nothing
                     }
+                    TaskName::Export => {
+                        self.banner.set_title(&fl!("banner-export-photos"));
+                    }
+                    TaskName::ExportMetadata => {
+                        self.banner.set_title(&fl!("banner-export-metadata"));
+                    }
+                    TaskName::Import => {
+                        self.banner.set_title(&fl!("banner-import"));
+                    }
+                    TaskName::WriteSidecars => {
+                        self.banner.set_title(&fl!("banner-write-sidecars"));
+                    }
+                    TaskName::ImportSidecars => {
+                        self.banner.set_title(&fl!("banner-import-sidecars"));
+                    }
+                    TaskName::RelocateThumbnails => {
+                        self.banner.set_title(&fl!("banner-relocate-thumbnails"));
+                    }
                 };
             }
             AppMsg::BootstrapCompleted => {
                 info!("Bootstrap completed.");
                 self.spinner.set_visible(false);
                 self.banner.set_revealed(false);
+
+                if let Some((path, offset)) = self.restore_folder.take() {
+                    // Don't navigate away from anything the user opened while loading.
+                    let is_root_visible = self
+                        .picture_navigation_view
+                        .visible_page()
+                        .is_some_and(|page| page.tag().is_none());
+
+                    // Folder might have been deleted or moved since the last run.
+                    let is_folder_present =
+                        self.state.read().iter().any(|visual| visual.parent_path == path);
+
+                    if is_root_visible && is_folder_present {
+                        info!("Restoring folder album for {:?}", path);
+                        self.show_album(AlbumFilter::Folder(path.clone()));
+                        self.folder_album.emit(AlbumInput::RestoreScrollOffset(offset));
+                        self.last_folder = Some((path, offset));
+                    }
+                }
+            }
+            AppMsg::ThumbnailCacheUnwritable(path) => {
+                error!("Thumbnail directory is not writable: {:?}", path);
+                self.error_banner.set_title(&fl!(
+                    "banner-cache-unwritable",
+                    path = path.to_string_lossy().to_string()
+                ));
+                self.error_banner.set_revealed(true);
+            }
+            AppMsg::DismissErrorBanner => {
+                self.error_banner.set_revealed(false);
+            }
+            AppMsg::LibraryLoadFailed => {
+                self.toasts
+                    .add_toast(adw::Toast::new(&fl!("library-load-failed-toast")));
+            }
+            AppMsg::ExportPerson(person_id, destination) => {
+                self.bootstrap.emit(BootstrapInput::ExportPerson(person_id, destination));
+            }
+            AppMsg::PersonExported(summary) => {
+                let mut body = fl!("person-export-done-dialog", "body", count = summary.exported);
+                if !summary.missing.is_empty() {
+                    body.push('\n');
+                    body.push_str(&fl!(
+                        "person-export-done-dialog",
+                        "missing",
+                        count = summary.missing.len()
+                    ));
+                }
+                if !summary.failed.is_empty() {
+                    body.push('\n');
+                    body.push_str(&fl!(
+                        "person-export-done-dialog",
+                        "failed",
+                        count = summary.failed.len()
+                    ));
+                }
+
+                let dialog = adw::AlertDialog::builder()
+                    .heading(fl!("person-export-done-dialog", "heading"))
+                    .body(body)
+                    .build();
+
+                dialog.add_response("ok", &fl!("person-export-done-dialog", "ok-button"));
+                dialog.present(Some(&self.main_navigation));
+            }
+            AppMsg::ExportMetadataDialog => {
+                let json_lines = gtk::FileFilter::new();
+                json_lines.set_name(Some(&fl!("export-metadata-dialog", "json-lines-filter")));
+                json_lines.add_suffix("jsonl");
+
+                let csv = gtk::FileFilter::new();
+                csv.set_name(Some(&fl!("export-metadata-dialog", "csv-filter")));
+                csv.add_suffix("csv");
+
+                let filters = gio::ListStore::new::<gtk::FileFilter>();
+                filters.append(&json_lines);
+                filters.append(&csv);
+
+                // Format is chosen by the extension of the file name.
+                let dialog = gtk::FileDialog::builder()
+                    .title(fl!("export-metadata-dialog", "title"))
+                    .initial_name("fotema-library.jsonl")
+                    .filters(&filters)
+                    .modal(true)
+                    .build();
+
+                let window = self
+                    .main_stack
+                    .root()
+                    .and_then(|root| root.downcast::<gtk::Window>().ok());
+                let sender = sender.clone();
+                dialog.save(
+                    window.as_ref(),
+                    gio::Cancellable::NONE,
+                    move |result| match result.map(|file| file.path()) {
+                        Ok(Some(path)) => sender.input(AppMsg::ExportMetadata(path)),
+                        Ok(None) => info!("Chosen metadata export file has no path"),
+                        Err(e) => info!("No file chosen to export metadata to: {}", e),
+                    },
+                );
+            }
+            AppMsg::ExportMetadata(destination) => {
+                let format = ExportFormat::from_path(&destination);
+                self.bootstrap
+                    .emit(BootstrapInput::ExportMetadata(format, destination));
+            }
+            AppMsg::MetadataExported(count) => {
+                let body = match count {
+                    Some(count) => fl!("export-metadata-done-dialog", "body", count = count),
+                    None => fl!("export-metadata-done-dialog", "failed"),
+                };
+
+                let dialog = adw::AlertDialog::builder()
+                    .heading(fl!("export-metadata-done-dialog", "heading"))
+                    .body(body)
+                    .build();
+
+                dialog.add_response("ok", &fl!("export-metadata-done-dialog", "ok-button"));
+                dialog.present(Some(&self.main_navigation));
+            }
+            AppMsg::ImportFilesDialog => {
+                let dialog = gtk::FileDialog::builder()
+                    .title(fl!("import-dialog", "files-title"))
+                    .modal(true)
+                    .build();
+
+                let window = self
+                    .main_stack
+                    .root()
+                    .and_then(|root| root.downcast::<gtk::Window>().ok());
+                let sender = sender.clone();
+                dialog.open_multiple(window.as_ref(), gio::Cancellable::NONE, move |result| {
+                    match result {
+                        Ok(files) => sender.input(AppMsg::Import(Self::file_paths(&files))),
+                        Err(e) => info!("No files chosen to import: {}", e),
+                    }
+                });
+            }
+            AppMsg::ImportFoldersDialog => {
+                let dialog = gtk::FileDialog::builder()
+                    .title(fl!("import-dialog", "folders-title"))
+                    .modal(true)
+                    .build();
+
+                let window = self
+                    .main_stack
+                    .root()
+                    .and_then(|root| root.downcast::<gtk::Window>().ok());
+                let sender = sender.clone();
+                dialog.select_multiple_folders(window.as_ref(), gio::Cancellable::NONE, move |result| {
+                    match result {
+                        Ok(folders) => sender.input(AppMsg::Import(Self::file_paths(&folders))),
+                        Err(e) => info!("No folders chosen to import: {}", e),
+                    }
+                });
+            }
+            AppMsg::Import(sources) => {
+                if sources.is_empty() {
+                    return;
+                }
+                info!("Import {} files and folders", sources.len());
+                self.bootstrap.emit(BootstrapInput::Import(sources));
+            }
+            AppMsg::Imported(summary) => {
+                let mut body = fl!("import-done-dialog", "body", count = summary.imported);
+                if summary.duplicates > 0 {
+                    body.push('\n');
+                    body.push_str(&fl!(
+                        "import-done-dialog",
+                        "duplicates",
+                        count = summary.duplicates
+                    ));
+                }
+                if !summary.failed.is_empty() {
+                    body.push('\n');
+                    body.push_str(&fl!(
+                        "import-done-dialog",
+                        "failed",
+                        count = summary.failed.len()
+                    ));
+                }
+
+                let dialog = adw::AlertDialog::builder()
+                    .heading(fl!("import-done-dialog", "heading"))
+                    .body(body)
+                    .build();
+
+                dialog.add_response("ok", &fl!("import-done-dialog", "ok-button"));
+                dialog.present(Some(&self.main_navigation));
+            }
+            AppMsg::WriteSidecars => {
+                info!("Write XMP sidecars");
+                self.bootstrap.emit(BootstrapInput::WriteSidecars);
+            }
+            AppMsg::Rescan => {
+                info!("Rescan library");
+                self.bootstrap.emit(BootstrapInput::Rescan);
+            }
+            AppMsg::Rebuild => {
+                info!("Rebuild library");
+                self.bootstrap.emit(BootstrapInput::Rebuild);
+            }
+            AppMsg::CountPhotoThumbnails => {
+                self.bootstrap.emit(BootstrapInput::CountPhotoThumbnails);
+            }
+            AppMsg::PhotoThumbnailsCounted(count) => {
+                let dialog = adw::AlertDialog::builder()
+                    .heading(fl!("generate-thumbnails-dialog", "heading"))
+                    .close_response("cancel")
+                    .default_response("cancel")
+                    .build();
+
+                if count == 0 {
+                    dialog.set_body(&fl!("generate-thumbnails-dialog", "up-to-date"));
+                    dialog.add_response("cancel", &fl!("generate-thumbnails-dialog", "ok-button"));
+                } else {
+                    dialog.set_body(&fl!(
+                        "generate-thumbnails-dialog-count",
+                        count = count,
+                        formatted_count = number::format_count(count)
+                    ));
+                    dialog.add_response(
+                        "cancel",
+                        &fl!("generate-thumbnails-dialog", "cancel-button"),
+                    );
+                    dialog
+                        .add_response("start", &fl!("generate-thumbnails-dialog", "start-button"));
+                    dialog.set_response_appearance("start", adw::ResponseAppearance::Suggested);
+                    dialog.set_default_response(Some("start"));
+                }
+
+                let sender = sender.clone();
+                dialog.connect_response(None, move |_, response| {
+                    if response == "start" {
+                        sender.input(AppMsg::GeneratePhotoThumbnails);
+                    }
+                });
+
+                if let Some(root) = self.main_stack.root() {
+                    dialog.present(Some(&root));
+                }
+            }
+            AppMsg::GeneratePhotoThumbnails => {
+                self.bootstrap.emit(BootstrapInput::GeneratePhotoThumbnails);
             }
             AppMsg::TranscodeAll => {
                 info!("Transcode all");
@@ -1044,12 +2666,248 @@ impl SimpleAsyncComponent for App {
                 info!("Scan pictures for faces");
                 self.bootstrap.emit(BootstrapInput::ScanPicturesForFaces);
             }
+            AppMsg::FaceDetectionModelChanged => {
+                let dialog = adw::AlertDialog::builder()
+                    .heading(fl!("redetect-faces-dialog", "heading"))
+                    .body(fl!("redetect-faces-dialog", "body"))
+                    .close_response("cancel")
+                    .default_response("redetect")
+                    .build();
+
+                dialog.add_response("cancel", &fl!("redetect-faces-dialog", "cancel-button"));
+                dialog.add_response("redetect", &fl!("redetect-faces-dialog", "redetect-button"));
+                dialog.set_response_appearance("redetect", adw::ResponseAppearance::Suggested);
+
+                let sender = sender.clone();
+                dialog.connect_response(None, move |_, response| {
+                    if response == "redetect" {
+                        sender.input(AppMsg::RedetectFaces);
+                    }
+                });
+
+                if let Some(root) = self.main_stack.root() {
+                    dialog.present(Some(&root));
+                }
+            }
+            AppMsg::RedetectFaces => {
+                info!("Scan pictures for faces again");
+                self.bootstrap.emit(BootstrapInput::RedetectFaces);
+            }
+            AppMsg::SetFavorite(picture_ids, is_favorite) => {
+                self.bootstrap
+                    .emit(BootstrapInput::SetFavorite(picture_ids, is_favorite));
+            }
+            AppMsg::SetPrivate(picture_id, is_private) => {
+                self.bootstrap
+                    .emit(BootstrapInput::SetPrivate(picture_id, is_private));
+            }
+            AppMsg::UnlockPrivate => {
+                if *self.privacy_state.read() == Privacy::Unlocked {
+                    return;
+                }
+
+                if self.settings_state.read().privacy_pin.is_empty() {
+                    info!("Unlocking private pictures");
+                    *self.privacy_state.write() = Privacy::Unlocked;
+                } else {
+                    self.present_unlock_dialog(&sender, fl!("unlock-private-dialog", "body"));
+                }
+            }
+            AppMsg::PrivatePinEntered(pin) => {
+                if self.settings_state.read().is_privacy_pin(&pin) {
+                    info!("Unlocking private pictures");
+                    *self.privacy_state.write() = Privacy::Unlocked;
+                } else {
+                    warn!("Wrong PIN for private pictures");
+                    self.present_unlock_dialog(&sender, fl!("unlock-private-dialog", "wrong-pin"));
+                }
+            }
+            AppMsg::LockPrivate => {
+                if *self.privacy_state.read() == Privacy::Locked {
+                    return;
+                }
+                info!("Locking private pictures");
+                *self.privacy_state.write() = Privacy::Locked;
+            }
+            AppMsg::SetTrashed(picture_ids, is_trashed) => {
+                self.bootstrap
+                    .emit(BootstrapInput::SetTrashed(picture_ids, is_trashed));
+            }
+            AppMsg::SetFolderCover(folder_path, picture_id) => {
+                self.bootstrap
+                    .emit(BootstrapInput::SetFolderCover(folder_path, picture_id));
+            }
+            AppMsg::SetFolderHidden(folder_path, is_hidden) => {
+                self.bootstrap
+                    .emit(BootstrapInput::SetFolderHidden(folder_path, is_hidden));
+            }
+            AppMsg::RegenerateThumbnail(picture_id) => {
+                self.bootstrap
+                    .emit(BootstrapInput::RegenerateThumbnail(picture_id));
+            }
+            AppMsg::UngroupBurst(burst_id) => {
+                self.bootstrap.emit(BootstrapInput::UngroupBurst(burst_id));
+            }
+            AppMsg::MovePictures(picture_ids, dest_folder) => {
+                // The folder chooser gives a document portal path, which is only
+                // known to be in the library by its host path.
+                let dest_folder = host_path::host_path(&dest_folder)
+                    .await
+                    .unwrap_or(FlatpakPathBuf::build(&dest_folder, &dest_folder));
+                self.bootstrap
+                    .emit(BootstrapInput::MovePictures(picture_ids, dest_folder));
+            }
+            AppMsg::AssignToPerson(picture_ids) => {
+                self.person_select
+                    .emit(PersonSelectInput::ActivateForPictures(picture_ids));
+                if let Some(root) = self.main_stack.root() {
+                    self.person_dialog.present(Some(&root));
+                }
+            }
+            AppMsg::PersonAssigned => {
+                self.person_dialog.close();
+                self.people_page.emit(PeopleAlbumInput::Refresh);
+            }
+            AppMsg::LocateMissing(picture_id, path) => {
+                // The file chooser gives a document portal path, which is only
+                // known to be in the library by its host path.
+                let path = host_path::host_path(&path)
+                    .await
+                    .unwrap_or(FlatpakPathBuf::build(&path, &path));
+                self.bootstrap
+                    .emit(BootstrapInput::LocateMissing(picture_id, path));
+            }
+            AppMsg::MissingLocated(picture_id, located) => {
+                self.missing_photos_dialog
+                    .emit(MissingPhotosInput::Located(picture_id, located));
+            }
+            AppMsg::RemoveMissing(picture_id) => {
+                self.bootstrap
+                    .emit(BootstrapInput::RemoveMissing(picture_id));
+            }
+            AppMsg::AddTag(picture_id, name) => {
+                self.bootstrap.emit(BootstrapInput::AddTag(picture_id, name));
+            }
+            AppMsg::RemoveTag(picture_id, name) => {
+                self.bootstrap
+                    .emit(BootstrapInput::RemoveTag(picture_id, name));
+            }
+            AppMsg::AddToUserAlbum(picture_id, name) => {
+                self.bootstrap
+                    .emit(BootstrapInput::AddToUserAlbum(picture_id, name));
+            }
+            AppMsg::RemoveFromUserAlbum(picture_id, album_id) => {
+                self.bootstrap
+                    .emit(BootstrapInput::RemoveFromUserAlbum(picture_id, album_id));
+            }
+            AppMsg::SetUserAlbumCover(album_id, picture_id) => {
+                self.bootstrap
+                    .emit(BootstrapInput::SetUserAlbumCover(album_id, picture_id));
+            }
+            AppMsg::SetUserAlbumOrder(album_id, order) => {
+                self.bootstrap
+                    .emit(BootstrapInput::SetUserAlbumOrder(album_id, order));
+            }
+            AppMsg::EmptyTrash => {
+                let dialog = adw::AlertDialog::builder()
+                    .heading(fl!("empty-trash-dialog", "heading"))
+                    .body(fl!("empty-trash-dialog", "body"))
+                    .close_response("cancel")
+                    .default_response("cancel")
+                    .build();
+
+                dialog.add_response("cancel", &fl!("empty-trash-dialog", "cancel-button"));
+                dialog.add_response("remove", &fl!("empty-trash-dialog", "remove-button"));
+                dialog.add_response("delete", &fl!("empty-trash-dialog", "delete-button"));
+                dialog.set_response_appearance("delete", adw::ResponseAppearance::Destructive);
+
+                let sender = sender.clone();
+                dialog.connect_response(None, move |_, response| match response {
+                    "remove" => sender.input(AppMsg::EmptyTrashConfirmed(false)),
+                    "delete" => sender.input(AppMsg::EmptyTrashConfirmed(true)),
+                    _ => {}
+                });
+
+                if let Some(root) = self.main_stack.root() {
+                    dialog.present(Some(&root));
+                }
+            }
+            AppMsg::EmptyTrashConfirmed(delete_files) => {
+                self.bootstrap
+                    .emit(BootstrapInput::EmptyTrash(delete_files));
+            }
+            AppMsg::Undo => {
+                self.bootstrap.emit(BootstrapInput::Undo);
+            }
+            AppMsg::ZoomIn => {
+                let zoom = self.settings_state.read().album_zoom.zoom_in();
+                self.set_album_zoom(zoom);
+            }
+            AppMsg::ZoomOut => {
+                let zoom = self.settings_state.read().album_zoom.zoom_out();
+                self.set_album_zoom(zoom);
+            }
             AppMsg::ProcessMotionPhotos => {
                 info!("Process motion photos");
                 self.bootstrap.emit(BootstrapInput::ProcessMotionPhotos);
             }
+            AppMsg::GenerateAnimatedPreviews => {
+                info!("Generate animated previews");
+                self.bootstrap.emit(BootstrapInput::GenerateAnimatedPreviews);
+            }
+            AppMsg::RemoveAnimatedPreviews => {
+                info!("Remove animated previews");
+                self.bootstrap.emit(BootstrapInput::RemoveAnimatedPreviews);
+            }
+            AppMsg::LimitThumbnailCache => {
+                info!("Limit thumbnail cache");
+                self.bootstrap.emit(BootstrapInput::LimitThumbnailCache);
+            }
+            AppMsg::ThumbnailLocationChanged => {
+                let dialog = adw::AlertDialog::builder()
+                    .heading(fl!("relocate-thumbnails-dialog", "heading"))
+                    .body(fl!("relocate-thumbnails-dialog", "body"))
+                    .close_response("keep")
+                    .default_response("move")
+                    .build();
+
+                dialog.add_response("keep", &fl!("relocate-thumbnails-dialog", "keep-button"));
+                dialog.add_response("move", &fl!("relocate-thumbnails-dialog", "move-button"));
+                dialog.set_response_appearance("move", adw::ResponseAppearance::Suggested);
+
+                // Thumbnails are saved in the new location whatever the response.
+                let sender = sender.clone();
+                dialog.connect_response(None, move |_, response| {
+                    sender.input(AppMsg::RelocateThumbnails(response == "move"));
+                });
+
+                if let Some(root) = self.main_stack.root() {
+                    dialog.present(Some(&root));
+                }
+            }
+            AppMsg::RelocateThumbnails(is_moved) => {
+                info!("Relocate thumbnails. Move existing: {}", is_moved);
+                self.bootstrap
+                    .emit(BootstrapInput::RelocateThumbnails(is_moved));
+            }
+            AppMsg::PauseBackgroundTasks(is_paused) => {
+                info!("Pause background tasks: {}", is_paused);
+                if is_paused {
+                    self.banner_title_before_pause = Some(self.banner.title().into());
+                    self.banner.set_title(&fl!("banner-paused"));
+                    self.banner.add_css_class("dim-label");
+                } else {
+                    if let Some(title) = self.banner_title_before_pause.take() {
+                        self.banner.set_title(&title);
+                    }
+                    self.banner.remove_css_class("dim-label");
+                }
+                self.bootstrap.emit(BootstrapInput::SetPaused(is_paused));
+            }
             AppMsg::StopBackgroundTasks => {
                 info!("Stop all background tasks");
+                self.banner_title_before_pause = None;
+                self.banner.remove_css_class("dim-label");
                 self.banner.set_button_label(None);
                 self.banner.set_title(&fl!("banner-stopping"));
                 self.bootstrap.emit(BootstrapInput::Stop);
@@ -1081,7 +2939,7 @@ impl SimpleAsyncComponent for App {
                     .unwrap_or(FlatpakPathBuf::build(&library_base_dir, &library_base_dir));
                 *self.settings_state.write() = settings.clone();
 
-                self.bootstrap.emit(BootstrapInput::Configure(settings.library_base_dir.clone()));
+                self.bootstrap.emit(BootstrapInput::Configure(settings.library_roots()));
                 self.picture_navigation_view.set_visible(true);
                 self.onboard_view.set_visible(false);
             }
@@ -1090,10 +2948,114 @@ impl SimpleAsyncComponent for App {
 
     fn shutdown(&mut self, widgets: &mut Self::Widgets, _output: relm4::Sender<Self::Output>) {
         widgets.save_window_size().unwrap();
+
+        // If the library never finished loading, keep the folder to restore for next time.
+        let last_folder = self.last_folder.as_ref().or(self.restore_folder.as_ref());
+        if let Err(e) = App::save_last_folder(last_folder) {
+            error!("Failed saving last folder: {:?}", e);
+        }
     }
 }
 
 impl App {
+    /// Change the size of items in every album grid, and remember it.
+    fn set_album_zoom(&self, zoom: GridZoom) {
+        let mut settings = self.settings_state.read().clone();
+        if settings.album_zoom != zoom {
+            info!("Album zoom is now {:?}", zoom);
+            settings.album_zoom = zoom;
+            *self.settings_state.write() = settings;
+        }
+    }
+
+    /// Ask the user for the PIN that unlocks private pictures.
+    fn present_unlock_dialog(&self, sender: &AsyncComponentSender<Self>, body: String) {
+        let dialog = adw::AlertDialog::builder()
+            .heading(fl!("unlock-private-dialog", "heading"))
+            .body(body)
+            .close_response("cancel")
+            .default_response("unlock")
+            .build();
+
+        let entry = gtk::PasswordEntry::builder()
+            .placeholder_text(fl!("unlock-private-dialog", "pin"))
+            .activates_default(true)
+            .build();
+        dialog.set_extra_child(Some(&entry));
+
+        dialog.add_response("cancel", &fl!("unlock-private-dialog", "cancel-button"));
+        dialog.add_response("unlock", &fl!("unlock-private-dialog", "unlock-button"));
+        dialog.set_response_appearance("unlock", adw::ResponseAppearance::Suggested);
+
+        let sender = sender.clone();
+        dialog.connect_response(None, move |_, response| {
+            if response == "unlock" {
+                sender.input(AppMsg::PrivatePinEntered(entry.text().into()));
+            }
+        });
+
+        if let Some(root) = self.main_stack.root() {
+            dialog.present(Some(&root));
+        }
+    }
+
+    /// Show the album page with a new filter and no search text.
+    fn show_album(&self, filter: AlbumFilter) {
+        self.album_search_entry.set_text("");
+        self.album_select_button.set_active(false);
+        self.folder_album.emit(AlbumInput::Activate);
+        self.folder_album.emit(AlbumInput::Filter(filter));
+        self.picture_navigation_view.push_by_tag("album");
+    }
+
+    /// Show the album page with a filter chosen in the viewer.
+    fn show_album_from_viewer(&self, filter: AlbumFilter) {
+        // Leave the viewer, which might have been opened from the album page.
+        self.picture_navigation_view.pop();
+
+        let is_album_visible = self
+            .picture_navigation_view
+            .visible_page()
+            .and_then(|page| page.tag())
+            .is_some_and(|tag| tag == "album");
+        if is_album_visible {
+            self.album_search_entry.set_text("");
+            self.folder_album.emit(AlbumInput::Filter(filter));
+        } else {
+            self.show_album(filter);
+        }
+    }
+
+    /// Paths of files chosen in a file dialog.
+    fn file_paths(files: &gio::ListModel) -> Vec<PathBuf> {
+        files
+            .iter::<gio::File>()
+            .flatten()
+            .filter_map(|file| file.path())
+            .collect()
+    }
+
+    /// Folder album, and scroll offset, that was open at the last shutdown.
+    fn load_last_folder() -> Option<(PathBuf, f64)> {
+        let gio_settings = gio::Settings::new(APP_ID);
+        let folder: String = gio_settings.string("last-folder-b64").into();
+        if folder.is_empty() {
+            return None;
+        }
+        let path = path_encoding::from_base64(&folder).ok()?;
+        Some((path, gio_settings.double("last-folder-scroll")))
+    }
+
+    fn save_last_folder(last_folder: Option<&(PathBuf, f64)>) -> Result<()> {
+        let gio_settings = gio::Settings::new(APP_ID);
+        let (folder, offset) = last_folder
+            .map(|(path, offset)| (path_encoding::to_base64(path), *offset))
+            .unwrap_or_default();
+        gio_settings.set_string("last-folder-b64", &folder)?;
+        gio_settings.set_double("last-folder-scroll", offset)?;
+        Ok(())
+    }
+
     pub async fn load_settings() -> Result<Settings> {
         info!("Loading settings");
 
@@ -1105,17 +3067,67 @@ impl App {
         let library_base_dir = host_path::host_path(&pic_base_dir).await
             .unwrap_or(FlatpakPathBuf::build(&pic_base_dir, &pic_base_dir));
 
+        let mut library_dirs = Vec::new();
+        for dir_b64 in gio_settings.strv("library-dirs-b64") {
+            let dir: PathBuf = path_encoding::from_base64(&dir_b64.to_string())?;
+            let dir = host_path::host_path(&dir).await
+                .unwrap_or(FlatpakPathBuf::build(&dir, &dir));
+            library_dirs.push(dir);
+        }
+
         Ok(Settings {
             show_selfies: gio_settings.boolean("show-selfies"),
             process_motion_photos: gio_settings.boolean("process-motion-photos"),
+            watch_library: gio_settings.boolean("watch-library"),
+            follow_symlinks: gio_settings.boolean("follow-symlinks"),
+            animated_previews: gio_settings.boolean("animated-previews"),
+            folders_view_mode: FoldersViewMode::from_str(&gio_settings.string("folders-view-mode"))
+                .unwrap_or_default(),
+            show_hidden_folders: gio_settings.boolean("show-hidden-folders"),
+            thumbnail_cache_limit_gb: gio_settings.double("thumbnail-cache-limit-gb"),
+            thumbnail_order: ThumbnailOrder::from_str(&gio_settings.string("thumbnail-order"))
+                .unwrap_or_default(),
+            thumbnail_location: ThumbnailLocation::from_str(
+                &gio_settings.string("thumbnail-location"),
+            )
+            .unwrap_or_default(),
+            thumbnail_power_mode: ThumbnailPowerMode::from_str(
+                &gio_settings.string("thumbnail-power-mode"),
+            )
+            .unwrap_or_default(),
+            import_layout: ImportLayout::from_str(&gio_settings.string("import-layout"))
+                .unwrap_or_default(),
+            sidecar_policy: SidecarPolicy::from_str(&gio_settings.string("sidecar-policy"))
+                .unwrap_or_default(),
+            slideshow_interval_secs: gio_settings.uint("slideshow-interval-secs"),
+            similar_photos_distance: gio_settings.uint("similar-photos-distance"),
+            naive_time_zone: NaiveTimeZone::from_str(&gio_settings.string("naive-time-zone"))
+                .unwrap_or_default(),
             face_detection_mode: FaceDetectionMode::from_str(
                 &gio_settings.string("face-detection-mode"),
             )
             .unwrap_or(FaceDetectionMode::Off),
-            album_sort: AlbumSort::from_str(&gio_settings.string("album-sort"))
-                .unwrap_or(AlbumSort::Ascending),
+            face_detection_model: FaceDetectionModel::from_str(
+                &gio_settings.string("face-detection-model"),
+            )
+            .unwrap_or_default(),
+            face_confidence_threshold: gio_settings.double("face-confidence-threshold") as f32,
+            face_cluster_distance: gio_settings.double("face-cluster-distance") as f32,
+            face_thumbnail_margin: gio_settings.double("face-thumbnail-margin") as f32,
+            album_sort: SortKey::from_str(&gio_settings.string("album-sort"))
+                .unwrap_or_default(),
+            album_zoom: GridZoom::from_str(&gio_settings.string("album-zoom")).unwrap_or_default(),
             is_onboarding_complete: gio_settings.boolean("onboarding-complete"),
             library_base_dir,
+            library_dirs,
+            ignore_patterns: gio_settings
+                .strv("ignore-patterns")
+                .iter()
+                .map(|pattern| pattern.to_string())
+                .collect(),
+            privacy_mode: PrivacyMode::from_str(&gio_settings.string("privacy-mode"))
+                .unwrap_or_default(),
+            privacy_pin: gio_settings.string("privacy-pin").into(),
         })
     }
 
@@ -1124,13 +3136,55 @@ impl App {
         let gio_settings = gio::Settings::new(APP_ID);
         gio_settings.set_boolean("show-selfies", settings.show_selfies)?;
         gio_settings.set_boolean("process-motion-photos", settings.process_motion_photos)?;
+        gio_settings.set_boolean("watch-library", settings.watch_library)?;
+        gio_settings.set_boolean("follow-symlinks", settings.follow_symlinks)?;
+        gio_settings.set_boolean("animated-previews", settings.animated_previews)?;
         gio_settings.set_string("face-detection-mode", settings.face_detection_mode.as_ref())?;
+        gio_settings.set_string(
+            "face-detection-model",
+            settings.face_detection_model.as_ref(),
+        )?;
+        gio_settings.set_double(
+            "face-confidence-threshold",
+            settings.face_confidence_threshold.into(),
+        )?;
+        gio_settings.set_double("face-cluster-distance", settings.face_cluster_distance.into())?;
+        gio_settings.set_double("face-thumbnail-margin", settings.face_thumbnail_margin.into())?;
         gio_settings.set_string("album-sort", settings.album_sort.as_ref())?;
+        gio_settings.set_string("album-zoom", settings.album_zoom.as_ref())?;
+        gio_settings.set_string("folders-view-mode", settings.folders_view_mode.as_ref())?;
+        gio_settings.set_boolean("show-hidden-folders", settings.show_hidden_folders)?;
+        gio_settings.set_double("thumbnail-cache-limit-gb", settings.thumbnail_cache_limit_gb)?;
+        gio_settings.set_string("thumbnail-order", settings.thumbnail_order.as_ref())?;
+        gio_settings.set_string("thumbnail-location", settings.thumbnail_location.as_ref())?;
+        gio_settings.set_string(
+            "thumbnail-power-mode",
+            settings.thumbnail_power_mode.as_ref(),
+        )?;
+        gio_settings.set_string("import-layout", settings.import_layout.as_ref())?;
+        gio_settings.set_string("sidecar-policy", settings.sidecar_policy.as_ref())?;
+        gio_settings.set_uint("slideshow-interval-secs", settings.slideshow_interval_secs)?;
+        gio_settings.set_uint("similar-photos-distance", settings.similar_photos_distance)?;
+        gio_settings.set_string("naive-time-zone", &settings.naive_time_zone.to_string())?;
         gio_settings.set_boolean("onboarding-complete", settings.is_onboarding_complete)?;
         gio_settings.set_string(
             "pictures-base-dir-b64",
             &path_encoding::to_base64(&settings.library_base_dir.sandbox_path),
         )?;
+        let library_dirs: Vec<String> = settings
+            .library_dirs
+            .iter()
+            .map(|dir| path_encoding::to_base64(&dir.sandbox_path))
+            .collect();
+        gio_settings.set_strv("library-dirs-b64", library_dirs.as_slice())?;
+        let ignore_patterns: Vec<&str> = settings
+            .ignore_patterns
+            .iter()
+            .map(String::as_str)
+            .collect();
+        gio_settings.set_strv("ignore-patterns", ignore_patterns.as_slice())?;
+        gio_settings.set_string("privacy-mode", settings.privacy_mode.as_ref())?;
+        gio_settings.set_string("privacy-pin", &settings.privacy_pin)?;
         Ok(())
     }
 }
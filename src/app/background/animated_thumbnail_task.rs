@@ -0,0 +1,192 @@
+// SPDX-FileCopyrightText: © 2025 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use anyhow::*;
+use rayon::prelude::*;
+use relm4::Reducer;
+use relm4::Worker;
+use relm4::prelude::*;
+use std::panic;
+use std::result::Result::Ok;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::{error, info};
+
+use fotema_core::visual::{AnimatedThumbnailer, Repository, Visual};
+
+use super::pause::{self, Pause};
+use crate::app::components::progress_monitor::{
+    ProgressMonitor, ProgressMonitorInput, TaskName, ThumbnailType,
+};
+
+#[derive(Debug)]
+pub enum AnimatedThumbnailTaskInput {
+    Start,
+}
+
+#[derive(Debug)]
+pub enum AnimatedThumbnailTaskOutput {
+    // Preview generation has started
+    Started,
+
+    // Preview generation has completed
+    Completed(usize),
+}
+
+/// Generates looping previews of videos and GIFs.
+pub struct AnimatedThumbnailTask {
+    // Stop flag
+    stop: Arc<AtomicBool>,
+
+    // Pause flag
+    pause: Arc<Pause>,
+
+    thumbnailer: AnimatedThumbnailer,
+
+    repo: Repository,
+
+    progress_monitor: Arc<Reducer<ProgressMonitor>>,
+}
+
+impl AnimatedThumbnailTask {
+    fn enrich(
+        stop: Arc<AtomicBool>,
+        pause: Arc<Pause>,
+        repo: Repository,
+        thumbnailer: AnimatedThumbnailer,
+        progress_monitor: Arc<Reducer<ProgressMonitor>>,
+        sender: ComponentSender<AnimatedThumbnailTask>,
+    ) -> Result<()> {
+        let start = std::time::Instant::now();
+
+        let mut unprocessed: Vec<Visual> = repo
+            .all()?
+            .into_iter()
+            .filter(|visual| visual.sandbox_path().exists())
+            .filter(|visual| thumbnailer.is_missing(visual))
+            .collect();
+
+        // should be ascending time order from database, so reverse to process newest items first
+        unprocessed.reverse();
+
+        let count = unprocessed.len();
+        info!(
+            "Found {} videos and GIFs to generate animated previews for",
+            count
+        );
+
+        // Short-circuit before sending progress messages to stop
+        // banner from appearing and disappearing.
+        if count == 0 {
+            let _ = sender.output(AnimatedThumbnailTaskOutput::Completed(count));
+            return Ok(());
+        }
+
+        let _ = sender.output(AnimatedThumbnailTaskOutput::Started);
+
+        progress_monitor.emit(ProgressMonitorInput::Start(
+            TaskName::Thumbnail(ThumbnailType::Animated),
+            count,
+        ));
+
+        for batch in unprocessed.chunks(pause::batch_size()) {
+            pause.wait_while_paused();
+            if stop.load(Ordering::Relaxed) {
+                break;
+            }
+
+            batch
+                .par_iter()
+                .take_any_while(|_| !stop.load(Ordering::Relaxed))
+                .for_each(|visual| {
+                    // Careful! panic::catch_unwind returns Ok(Err) if the evaluated expression returns
+                    // an error but doesn't panic.
+                    let result = panic::catch_unwind(|| thumbnailer.thumbnail(visual));
+
+                    // A missing preview isn't a problem because the still thumbnail is shown instead,
+                    // so don't mark the item as broken.
+                    if let Ok(Err(e)) = result {
+                        error!(
+                            "Failed generate animated preview: {:?}: Path: {:?}",
+                            e.root_cause(),
+                            visual.sandbox_path()
+                        );
+                    } else if result.is_err() {
+                        error!(
+                            "Panicked generate animated preview: Path: {:?}",
+                            visual.sandbox_path()
+                        );
+                    }
+
+                    progress_monitor.emit(ProgressMonitorInput::Advance);
+                });
+        }
+
+        info!(
+            "Generated {} animated previews in {} seconds.",
+            count,
+            start.elapsed().as_secs()
+        );
+
+        progress_monitor.emit(ProgressMonitorInput::Complete);
+
+        let _ = sender.output(AnimatedThumbnailTaskOutput::Completed(count));
+
+        Ok(())
+    }
+}
+
+impl Worker for AnimatedThumbnailTask {
+    type Init = (
+        Arc<AtomicBool>,
+        Arc<Pause>,
+        AnimatedThumbnailer,
+        Repository,
+        Arc<Reducer<ProgressMonitor>>,
+    );
+    type Input = AnimatedThumbnailTaskInput;
+    type Output = AnimatedThumbnailTaskOutput;
+
+    fn init(
+        (stop, pause, thumbnailer, repo, progress_monitor): Self::Init,
+        _sender: ComponentSender<Self>,
+    ) -> Self {
+        Self {
+            stop,
+            pause,
+            thumbnailer,
+            repo,
+            progress_monitor,
+        }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            AnimatedThumbnailTaskInput::Start => {
+                info!("Generating animated previews...");
+                let stop = self.stop.clone();
+                let pause = self.pause.clone();
+                let repo = self.repo.clone();
+                let thumbnailer = self.thumbnailer.clone();
+                let progress_monitor = self.progress_monitor.clone();
+
+                // Batches are handed to rayon from a thread of their own, so that waiting
+                // while paused doesn't park one of rayon's workers. Also avoids a runtime
+                // panic from calling block_on.
+                std::thread::spawn(move || {
+                    if let Err(e) = AnimatedThumbnailTask::enrich(
+                        stop,
+                        pause,
+                        repo,
+                        thumbnailer,
+                        progress_monitor,
+                        sender,
+                    ) {
+                        error!("Failed to update animated previews: {}", e);
+                    }
+                });
+            }
+        };
+    }
+}
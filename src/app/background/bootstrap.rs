@@ -8,31 +8,51 @@ use relm4::{
 use crate::app::Settings;
 use crate::config::APP_ID;
 use fotema_core::PictureId;
+use fotema_core::people::PersonId;
+use fotema_core::photo::{ExportFormat, ExportSummary};
+use fotema_core::import::ImportSummary;
+use fotema_core::burst::{self, BurstId};
 use fotema_core::database;
 use fotema_core::people;
 use fotema_core::photo;
-use fotema_core::thumbnailify::Thumbnailer;
+use fotema_core::tag;
+use fotema_core::thumbnailify::{ThumbnailLocation, Thumbnailer};
+use fotema_core::undo::{self, Undo};
+use fotema_core::user_album::{self, UserAlbumId};
 use fotema_core::video;
 use fotema_core::visual;
 use fotema_core::Scanner;
+use fotema_core::LibraryRoots;
 use fotema_core::FlatpakPathBuf;
 use fotema_core::people::migrate::Migrate;
+use fotema_core::machine_learning::face_extractor::FaceDetectionModel;
 
 use std::result::Result::Ok;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 
 use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
 use std::time::Instant;
 
 use tracing::{error, info, warn};
 
 use anyhow;
 
+use super::library_watcher::LibraryWatcher;
+use super::pause::Pause;
+#[cfg(feature = "power")]
+use super::power_monitor::{PowerMonitor, ThumbnailPowerMode};
+
 use super::{
+    animated_thumbnail_task::{AnimatedThumbnailTask, AnimatedThumbnailTaskInput, AnimatedThumbnailTaskOutput},
+    import_task::{ImportTask, ImportTaskInput, ImportTaskOutput},
     load_library_task::{LoadLibraryTask, LoadLibraryTaskInput, LoadLibraryTaskOutput},
     library_scan_task::{LibraryScanTask, LibraryScanTaskInput, LibraryScanTaskOutput},
+    metadata_export_task::{MetadataExportTask, MetadataExportTaskInput, MetadataExportTaskOutput},
+    person_export_task::{PersonExportTask, PersonExportTaskInput, PersonExportTaskOutput},
     person_thumbnail_task::{PersonThumbnailTask, PersonThumbnailTaskInput, PersonThumbnailTaskOutput},
+    photo_burst_task::{PhotoBurstTask, PhotoBurstTaskInput, PhotoBurstTaskOutput},
     photo_clean_task::{PhotoCleanTask, PhotoCleanTaskInput, PhotoCleanTaskOutput},
     photo_detect_faces_task::{
         PhotoDetectFacesTask, PhotoDetectFacesTaskInput, PhotoDetectFacesTaskOutput,
@@ -44,21 +64,30 @@ use super::{
     photo_recognize_faces_task::{
         PhotoRecognizeFacesTask, PhotoRecognizeFacesTaskInput, PhotoRecognizeFacesTaskOutput,
     },
+    photo_sidecar_task::{PhotoSidecarTask, PhotoSidecarTaskInput, PhotoSidecarTaskOutput},
     photo_thumbnail_task::{PhotoThumbnailTask, PhotoThumbnailTaskInput, PhotoThumbnailTaskOutput},
     video_clean_task::{VideoCleanTask, VideoCleanTaskInput, VideoCleanTaskOutput},
     video_enrich_task::{VideoEnrichTask, VideoEnrichTaskInput, VideoEnrichTaskOutput},
     video_thumbnail_task::{VideoThumbnailTask, VideoThumbnailTaskInput, VideoThumbnailTaskOutput},
     video_transcode_task::{VideoTranscodeTask, VideoTranscodeTaskInput, VideoTranscodeTaskOutput},
 
+    thumbnail_relocate_task::{
+        ThumbnailRelocateTask, ThumbnailRelocateTaskInput, ThumbnailRelocateTaskOutput,
+    },
     tidy_task::{TidyTask, TidyTaskInput, TidyTaskOutput},
     migrate_task::{MigrateTask, MigrateTaskInput, MigrateTaskOutput},
 };
 
 use crate::app::FaceDetectionMode;
+use crate::app::Privacy;
+use crate::app::PrivacyMode;
+use crate::app::PrivacyState;
 use crate::app::SettingsState;
 use crate::app::SharedState;
 
-use crate::app::components::progress_monitor::ProgressMonitor;
+use crate::app::components::progress_monitor::{
+    self, Interrupted, ProgressMonitor, ProgressMonitorInput,
+};
 
 /// FIXME copied from progress_monitor. Consolidate?
 #[derive(Debug)]
@@ -72,6 +101,7 @@ pub enum ThumbnailType {
     Photo,
     Video,
     Face,
+    Animated,
 }
 
 /// FIXME very similar (but different) to progress_monitor::TaskName.
@@ -82,6 +112,7 @@ pub enum TaskName {
     Scan,
     Enrich(MediaType),
     MotionPhoto,
+    Bursts,
     Thumbnail(ThumbnailType),
     Clean(MediaType),
     DetectFaces,
@@ -89,12 +120,18 @@ pub enum TaskName {
     Transcode,
     Tidy,
     Migrate,
+    Export,
+    ExportMetadata,
+    Import,
+    WriteSidecars,
+    ImportSidecars,
+    RelocateThumbnails,
 }
 
 #[derive(Debug)]
 pub enum BootstrapInput {
-    /// Configure the pictures library root and host path
-    Configure(FlatpakPathBuf),
+    /// Configure the pictures library roots and host paths
+    Configure(LibraryRoots),
 
     /// Settings updated
     SettingsUpdated(Settings),
@@ -105,6 +142,13 @@ pub enum BootstrapInput {
     // Stop all background tasks
     Stop,
 
+    /// Pause or resume background tasks that support pausing.
+    SetPaused(bool),
+
+    /// Hold back or allow photo thumbnail generation, depending on whether the
+    /// computer is on battery power or in use.
+    SetPowerPaused(bool),
+
     /// No more tasks running
     Stopped,
 
@@ -112,12 +156,139 @@ pub enum BootstrapInput {
     ScanPictureForFaces(PictureId),
     ScanPicturesForFaces,
 
+    /// Queue task for scanning pictures for faces again, if they were scanned
+    /// with another face detection model.
+    RedetectFaces,
+
+    /// Queue tasks for scanning the library for changed files and processing them.
+    Rescan,
+
+    /// Queue tasks for scanning every file in the library and processing them.
+    Rebuild,
+
+    /// Queue task for copying pictures of a person to a directory.
+    ExportPerson(PersonId, PathBuf),
+
+    /// Person export task has finished.
+    ExportCompleted(ExportSummary),
+
+    /// Queue task for writing the metadata of every picture to a file.
+    ExportMetadata(ExportFormat, PathBuf),
+
+    /// Metadata export task has finished. None if it failed.
+    MetadataExportCompleted(Option<usize>),
+
+    /// Queue task for writing favorites and tags to XMP sidecar files.
+    WriteSidecars,
+
+    /// Queue tasks for copying pictures and videos into the library and processing them.
+    Import(Vec<PathBuf>),
+
+    /// Import task has finished.
+    ImportCompleted(ImportSummary),
+
     /// Queue task for transcoding videos
     TranscodeAll,
 
     /// Queue task for processing motion photos
     ProcessMotionPhotos,
 
+    /// Queue task for generating looping previews of videos and GIFs.
+    GenerateAnimatedPreviews,
+
+    /// Queue task for deleting looping previews of videos and GIFs.
+    RemoveAnimatedPreviews,
+
+    /// Queue task for deleting thumbnails until the thumbnail cache is within its size limit.
+    LimitThumbnailCache,
+
+    /// Save thumbnails where the settings say, and queue tasks for moving existing
+    /// thumbnails there if true, or otherwise generating them again.
+    RelocateThumbnails(bool),
+
+    /// Star or unstar pictures as favorites.
+    SetFavorite(Vec<PictureId>, bool),
+
+    /// Mark a picture as private, or not.
+    SetPrivate(PictureId, bool),
+
+    /// Private pictures have been locked or unlocked, or are shown differently while locked.
+    PrivacyChanged,
+
+    /// Choose a picture as the cover of a folder.
+    SetFolderCover(PathBuf, PictureId),
+
+    /// Hide a folder, and the folders beneath it, from the folders album, or show it again.
+    SetFolderHidden(PathBuf, bool),
+
+    /// Delete and regenerate the thumbnail of a picture, without waiting for other tasks.
+    RegenerateThumbnail(PictureId),
+
+    /// Tag a picture. String is the tag name as typed.
+    AddTag(PictureId, String),
+
+    /// Remove a tag from a picture.
+    RemoveTag(PictureId, String),
+
+    /// Put a picture in a user album, making the album if there isn't one with the name.
+    /// String is the album name as typed.
+    AddToUserAlbum(PictureId, String),
+
+    /// Take a picture out of a user album. The picture stays in the library.
+    RemoveFromUserAlbum(PictureId, UserAlbumId),
+
+    /// Choose a picture as the cover of a user album.
+    SetUserAlbumCover(UserAlbumId, PictureId),
+
+    /// Arrange the pictures of a user album in an order.
+    SetUserAlbumOrder(UserAlbumId, Vec<PictureId>),
+
+    /// Show the frames of a burst as separate pictures.
+    UngroupBurst(BurstId),
+
+    /// Thumbnail of a picture has been regenerated.
+    ThumbnailRegenerated(PictureId),
+
+    /// Count the pictures that need thumbnails, without generating any.
+    CountPhotoThumbnails,
+
+    /// Pictures that need thumbnails have been counted.
+    PhotoThumbnailsCounted(usize),
+
+    /// Queue task for generating thumbnails of pictures that need them.
+    GeneratePhotoThumbnails,
+
+    /// Move pictures to, or restore pictures from, the trash.
+    SetTrashed(Vec<PictureId>, bool),
+
+    /// Move pictures to another folder of the library, along with their thumbnails.
+    MovePictures(Vec<PictureId>, FlatpakPathBuf),
+
+    /// Record that a missing picture was moved to a file, if the file has the same content.
+    LocateMissing(PictureId, FlatpakPathBuf),
+
+    /// Remove a missing picture, along with its thumbnails, from the library.
+    RemoveMissing(PictureId),
+
+    /// Find pictures that look like a picture.
+    FindSimilar(PictureId),
+
+    /// Find pictures that are identical copies of other pictures.
+    FindDuplicates,
+
+    /// Remove trashed pictures from the library. If true, then pictures the user trashed
+    /// are also deleted from disk.
+    EmptyTrash(bool),
+
+    /// Reverse the most recent trash, restore, or merge.
+    Undo,
+
+    /// Thumbnails can't be saved to the directory, so thumbnail generation was abandoned.
+    CacheUnwritable(PathBuf),
+
+    /// Library couldn't be loaded from the database.
+    LibraryLoadFailed,
+
     /// A background task has started.
     TaskStarted(TaskName),
 
@@ -136,6 +307,34 @@ pub enum BootstrapOutput {
 
     // Tasks are in the process of stopping
     Stopping,
+
+    // Pictures of a person have been exported.
+    Exported(ExportSummary),
+
+    // Metadata of pictures has been exported. None if the export failed.
+    MetadataExported(Option<usize>),
+
+    // Pictures and videos have been copied into the library.
+    Imported(ImportSummary),
+
+    // Thumbnails can't be saved to the directory.
+    CacheUnwritable(PathBuf),
+
+    // Library couldn't be loaded, so albums still show what was loaded before.
+    LibraryLoadFailed,
+
+    // Number of pictures that need thumbnails.
+    PhotoThumbnailsCounted(usize),
+
+    // A missing picture has been looked for. Some(true) if found, Some(false) if the
+    // file has different content, and None if locating failed.
+    MissingLocated(PictureId, Option<bool>),
+
+    // Pictures that look like a picture, most alike first.
+    SimilarFound(PictureId, Vec<PictureId>),
+
+    // Pictures that are identical copies of other pictures.
+    DuplicatesFound(Vec<PictureId>),
 }
 
 type Task = dyn Fn() + Send + Sync;
@@ -149,9 +348,36 @@ pub struct Controllers {
 
     settings_state: SettingsState,
 
+    photo_repo: photo::Repository,
+
+    /// Finds thumbnails wherever they are saved. Shared with albums.
+    thumbnailer: Thumbnailer,
+
+    /// Most recent actions that can be undone.
+    undo_repo: undo::Repository,
+
+    tag_repo: tag::Repository,
+
+    user_album_repo: user_album::Repository,
+
+    burst_repo: burst::Repository,
+
+    people_repo: people::Repository,
+
+    /// Face detection model the detect faces task was built with.
+    face_detection_model: FaceDetectionModel,
+
     // Stop background tasks.
     stop: Arc<AtomicBool>,
 
+    // Pause background tasks.
+    pause: Arc<Pause>,
+
+    // Pause photo thumbnail generation on battery power, or while the computer is in use.
+    power_pause: Arc<Pause>,
+
+    progress_monitor: Arc<Reducer<ProgressMonitor>>,
+
     /// Whether a background task has updated some library state and the library should be reloaded.
     library_stale: Arc<AtomicBool>,
 
@@ -166,17 +392,25 @@ pub struct Controllers {
 
     photo_thumbnail_task: Arc<WorkerController<PhotoThumbnailTask>>,
     video_thumbnail_task: Arc<WorkerController<VideoThumbnailTask>>,
+    animated_thumbnail_task: Arc<WorkerController<AnimatedThumbnailTask>>,
 
     photo_extract_motion_task: Arc<WorkerController<PhotoExtractMotionTask>>,
 
+    photo_burst_task: Arc<WorkerController<PhotoBurstTask>>,
+
     photo_detect_faces_task: Arc<WorkerController<PhotoDetectFacesTask>>,
     photo_recognize_faces_task: Arc<WorkerController<PhotoRecognizeFacesTask>>,
 
     video_transcode_task: Arc<WorkerController<VideoTranscodeTask>>,
 
     tidy_task: Arc<WorkerController<TidyTask>>,
+    thumbnail_relocate_task: Arc<WorkerController<ThumbnailRelocateTask>>,
     migrate_task: Arc<WorkerController<MigrateTask>>,
     person_thumbnail_task: Arc<WorkerController<PersonThumbnailTask>>,
+    person_export_task: Arc<WorkerController<PersonExportTask>>,
+    metadata_export_task: Arc<WorkerController<MetadataExportTask>>,
+    photo_sidecar_task: Arc<WorkerController<PhotoSidecarTask>>,
+    import_task: Arc<WorkerController<ImportTask>>,
 
     /// Pending ordered tasks to process
     /// Wow... figuring out a type signature that would compile was a nightmare.
@@ -217,6 +451,87 @@ impl Controllers {
                 self.add_task_photo_recognize_faces();
                 self.run_if_idle();
             }
+            BootstrapInput::RedetectFaces => {
+                // Settings may have changed since these controllers were built,
+                // in which case they are being replaced.
+                let detection_model = self.settings_state.read().face_detection_model;
+                info!(
+                    "Queueing task to scan pictures for faces with {:?} model",
+                    detection_model
+                );
+                match self.people_repo.reset_face_scans(detection_model) {
+                    Ok(count) => info!("Reset face scans of {} pictures", count),
+                    Err(e) => error!("Failed resetting face scans: {:?}", e),
+                }
+
+                self.add_task_photo_detect_faces();
+                self.add_task_photo_recognize_faces();
+                self.run_if_idle();
+            }
+            BootstrapInput::Rescan => {
+                info!("Queueing tasks to rescan library");
+                self.add_task_library_scan();
+                self.add_tasks_process_scanned(sender.input_sender().clone());
+                self.run_if_idle();
+            }
+            BootstrapInput::Rebuild => {
+                info!("Queueing tasks to rebuild library");
+                self.add_task_library_rebuild();
+                self.add_tasks_process_scanned(sender.input_sender().clone());
+                self.run_if_idle();
+            }
+            BootstrapInput::ExportPerson(person_id, destination) => {
+                info!("Queueing task to export person {} to {:?}", person_id, destination);
+                self.add_task_person_export(person_id, destination);
+                self.run_if_idle();
+            }
+            BootstrapInput::ExportCompleted(summary) => {
+                let _ = sender.output(BootstrapOutput::Exported(summary));
+                self.update(BootstrapInput::TaskCompleted(TaskName::Export, None), sender);
+            }
+            BootstrapInput::ExportMetadata(format, destination) => {
+                info!("Queueing task to export metadata to {:?}", destination);
+                self.add_task_metadata_export(format, destination);
+                self.run_if_idle();
+            }
+            BootstrapInput::MetadataExportCompleted(count) => {
+                let _ = sender.output(BootstrapOutput::MetadataExported(count));
+                self.update(
+                    BootstrapInput::TaskCompleted(TaskName::ExportMetadata, count),
+                    sender,
+                );
+            }
+            BootstrapInput::WriteSidecars => {
+                info!("Queueing task to write XMP sidecars");
+                self.add_task_photo_write_sidecars();
+                self.run_if_idle();
+            }
+            BootstrapInput::Import(sources) => {
+                info!("Queueing tasks to import {} files and folders", sources.len());
+                self.add_task_import(sources);
+                self.add_task_library_scan();
+                self.add_tasks_process_scanned(sender.input_sender().clone());
+                self.run_if_idle();
+            }
+            BootstrapInput::ImportCompleted(summary) => {
+                let imported = summary.imported;
+                let _ = sender.output(BootstrapOutput::Imported(summary));
+                self.update(BootstrapInput::TaskCompleted(TaskName::Import, Some(imported)), sender);
+            }
+            BootstrapInput::LibraryLoadFailed => {
+                let _ = sender.output(BootstrapOutput::LibraryLoadFailed);
+                self.update(
+                    BootstrapInput::TaskCompleted(TaskName::LoadLibrary, None),
+                    sender,
+                );
+            }
+            BootstrapInput::CacheUnwritable(path) => {
+                let _ = sender.output(BootstrapOutput::CacheUnwritable(path));
+                self.update(
+                    BootstrapInput::TaskCompleted(TaskName::Thumbnail(ThumbnailType::Photo), None),
+                    sender,
+                );
+            }
             BootstrapInput::TranscodeAll => {
                 info!("Queueing task to transcode all incompatible videos");
                 self.add_task_video_transcode();
@@ -227,6 +542,379 @@ impl Controllers {
                 self.add_task_photo_extract_motion();
                 self.run_if_idle();
             }
+            BootstrapInput::GenerateAnimatedPreviews => {
+                info!("Queueing task to generate animated previews");
+                self.add_task_animated_thumbnail();
+                self.run_if_idle();
+            }
+            BootstrapInput::RemoveAnimatedPreviews => {
+                // Tidying removes previews when they are disabled. Queued so that it
+                // runs after any preview generation that is already queued.
+                info!("Queueing task to remove animated previews");
+                self.add_task_tidy();
+                self.run_if_idle();
+            }
+            BootstrapInput::LimitThumbnailCache => {
+                // Tidying evicts thumbnails when the cache is too big.
+                info!("Queueing task to limit thumbnail cache");
+                self.add_task_tidy();
+                self.run_if_idle();
+            }
+            BootstrapInput::RelocateThumbnails(is_moved) => {
+                let from = self.thumbnailer.location();
+                let to = self.settings_state.read().thumbnail_location;
+                if from == to {
+                    return;
+                }
+
+                info!("Saving thumbnails in {:?} instead of {:?}", to, from);
+                self.thumbnailer.set_location(to);
+
+                if is_moved {
+                    self.add_task_relocate_thumbnails(from);
+                }
+
+                // Albums find where thumbnails are saved when the library loads, and
+                // thumbnails that weren't moved are generated again.
+                self.library_stale.store(true, Ordering::Relaxed);
+                self.add_task_load_library(sender.input_sender().clone());
+                self.add_task_photo_thumbnail();
+                self.add_task_video_thumbnail();
+                self.add_task_animated_thumbnail();
+                self.run_if_idle();
+            }
+            BootstrapInput::SetFavorite(picture_ids, is_favorite) => {
+                info!(
+                    "Setting {} pictures favorite to {}",
+                    picture_ids.len(),
+                    is_favorite
+                );
+                if let Err(e) = self.photo_repo.set_favorite(&picture_ids, is_favorite) {
+                    error!("Failed setting favorite for pictures: {:?}", e);
+                    return;
+                }
+
+                // Update loaded library in place, rather than reloading everything from database.
+                let mut library = self.shared_state.write();
+                for visual in library.iter_mut().filter(|visual| {
+                    visual
+                        .picture_id
+                        .is_some_and(|picture_id| picture_ids.contains(&picture_id))
+                }) {
+                    let mut updated = (**visual).clone();
+                    updated.is_favorite = is_favorite;
+                    *visual = Arc::new(updated);
+                }
+            }
+            BootstrapInput::SetPrivate(picture_id, is_private) => {
+                info!("Setting picture {} private to {}", picture_id, is_private);
+                if let Err(e) = self.photo_repo.set_private(&picture_id, is_private) {
+                    error!("Failed setting private for picture {}: {:?}", picture_id, e);
+                    return;
+                }
+
+                // Reload, because a private picture might now be hidden or blurred.
+                self.library_stale.store(true, Ordering::Relaxed);
+                self.add_task_load_library(sender.input_sender().clone());
+                self.run_if_idle();
+            }
+            BootstrapInput::PrivacyChanged => {
+                info!("Reloading library for privacy change");
+                self.library_stale.store(true, Ordering::Relaxed);
+                self.add_task_load_library(sender.input_sender().clone());
+                self.run_if_idle();
+            }
+            BootstrapInput::SetFolderCover(folder_path, picture_id) => {
+                info!("Setting picture {} as cover of {:?}", picture_id, folder_path);
+
+                // Picture might have been removed since the menu was shown.
+                match self.photo_repo.get(&picture_id) {
+                    Ok(Some(_)) => {}
+                    Ok(None) => {
+                        warn!("Not setting missing picture {} as a cover", picture_id);
+                        return;
+                    }
+                    Err(e) => {
+                        error!("Failed getting picture {}: {:?}", picture_id, e);
+                        return;
+                    }
+                }
+
+                if let Err(e) = self.photo_repo.set_folder_cover(&folder_path, &picture_id) {
+                    error!("Failed setting cover of folder {:?}: {:?}", folder_path, e);
+                    return;
+                }
+
+                // Update loaded library in place, rather than reloading everything from database.
+                let mut library = self.shared_state.write();
+                for visual in library
+                    .iter_mut()
+                    .filter(|visual| visual.parent_path == folder_path)
+                {
+                    let mut updated = (**visual).clone();
+                    updated.is_folder_cover = visual.picture_id == Some(picture_id);
+                    *visual = Arc::new(updated);
+                }
+            }
+            BootstrapInput::SetFolderHidden(folder_path, is_hidden) => {
+                info!("Setting folder {:?} hidden to {}", folder_path, is_hidden);
+                if let Err(e) = self.photo_repo.set_folder_hidden(&folder_path, is_hidden) {
+                    error!("Failed hiding folder {:?}: {:?}", folder_path, e);
+                    return;
+                }
+
+                // Showing a folder can show the folders containing it, so reload rather
+                // than working out which items changed.
+                self.library_stale.store(true, Ordering::Relaxed);
+                self.add_task_load_library(sender.input_sender().clone());
+                self.run_if_idle();
+            }
+            BootstrapInput::RegenerateThumbnail(picture_id) => {
+                info!("Regenerating thumbnail of picture {}", picture_id);
+                self.photo_thumbnail_task
+                    .emit(PhotoThumbnailTaskInput::Regenerate(picture_id));
+            }
+            BootstrapInput::AddTag(picture_id, name) => {
+                info!("Tagging picture {} with {:?}", picture_id, name);
+                if let Err(e) = self.tag_repo.add_tag(&picture_id, &name) {
+                    error!("Failed tagging picture {}: {:?}", picture_id, e);
+                    return;
+                }
+                self.update_tags(picture_id);
+            }
+            BootstrapInput::RemoveTag(picture_id, name) => {
+                info!("Removing tag {:?} from picture {}", name, picture_id);
+                if let Err(e) = self.tag_repo.remove_tag(&picture_id, &name) {
+                    error!("Failed removing tag from picture {}: {:?}", picture_id, e);
+                    return;
+                }
+                self.update_tags(picture_id);
+            }
+            BootstrapInput::AddToUserAlbum(picture_id, name) => {
+                info!("Adding picture {} to album {:?}", picture_id, name);
+                let added = self
+                    .user_album_repo
+                    .create_album(&name)
+                    .and_then(|album_id| {
+                        self.user_album_repo.add_to_album(&album_id, &[picture_id])
+                    });
+                if let Err(e) = added {
+                    error!("Failed adding picture {} to album: {:?}", picture_id, e);
+                    return;
+                }
+                self.update_user_albums();
+            }
+            BootstrapInput::RemoveFromUserAlbum(picture_id, album_id) => {
+                info!("Removing picture {} from album {}", picture_id, album_id);
+                if let Err(e) = self
+                    .user_album_repo
+                    .remove_from_album(&album_id, &[picture_id])
+                {
+                    error!("Failed removing picture {} from album: {:?}", picture_id, e);
+                    return;
+                }
+                self.update_user_albums();
+            }
+            BootstrapInput::SetUserAlbumCover(album_id, picture_id) => {
+                info!(
+                    "Choosing picture {} as cover of album {}",
+                    picture_id, album_id
+                );
+                if let Err(e) = self.user_album_repo.set_album_cover(&album_id, &picture_id) {
+                    error!("Failed setting cover of album {}: {:?}", album_id, e);
+                    return;
+                }
+                self.update_user_albums();
+            }
+            BootstrapInput::SetUserAlbumOrder(album_id, order) => {
+                info!("Arranging {} pictures of album {}", order.len(), album_id);
+                if let Err(e) = self.user_album_repo.set_album_order(&album_id, order) {
+                    error!("Failed arranging album {}: {:?}", album_id, e);
+                    return;
+                }
+                self.update_user_albums();
+            }
+            BootstrapInput::UngroupBurst(burst_id) => {
+                info!("Ungrouping burst {}", burst_id);
+                if let Err(e) = self.burst_repo.ungroup(&burst_id) {
+                    error!("Failed ungrouping burst {}: {:?}", burst_id, e);
+                    return;
+                }
+
+                // Update loaded library in place, rather than reloading everything from database.
+                let mut library = self.shared_state.write();
+                for visual in library
+                    .iter_mut()
+                    .filter(|visual| visual.burst.is_some_and(|frame| frame.burst_id == burst_id))
+                {
+                    let mut updated = (**visual).clone();
+                    updated.burst = None;
+                    *visual = Arc::new(updated);
+                }
+            }
+            BootstrapInput::CountPhotoThumbnails => {
+                // Only reads, so runs straight away rather than waiting for other tasks.
+                info!("Counting pictures that need thumbnails");
+                self.photo_thumbnail_task
+                    .emit(PhotoThumbnailTaskInput::Count);
+            }
+            BootstrapInput::PhotoThumbnailsCounted(count) => {
+                info!("{} pictures need thumbnails", count);
+                let _ = sender.output(BootstrapOutput::PhotoThumbnailsCounted(count));
+            }
+            BootstrapInput::GeneratePhotoThumbnails => {
+                info!("Queueing task to generate photo thumbnails");
+                self.add_task_photo_thumbnail();
+                self.run_if_idle();
+            }
+            BootstrapInput::ThumbnailRegenerated(picture_id) => {
+                info!("Regenerated thumbnail of picture {}", picture_id);
+                self.library_stale.store(true, Ordering::Relaxed);
+                self.add_task_load_library(sender.input_sender().clone());
+                self.run_if_idle();
+            }
+            BootstrapInput::SetTrashed(picture_ids, is_trashed) => {
+                info!("Setting {} pictures trashed to {}", picture_ids.len(), is_trashed);
+                let result = if is_trashed {
+                    self.photo_repo.trash(&picture_ids)
+                } else {
+                    self.photo_repo.restore(&picture_ids)
+                };
+                let changed = result.unwrap_or_else(|e| {
+                    error!("Failed setting trashed for pictures: {:?}", e);
+                    Vec::new()
+                });
+
+                // Only pictures that actually moved in or out of the trash are reversed.
+                if !changed.is_empty() {
+                    self.undo_repo.push(if is_trashed {
+                        Undo::Restore(changed)
+                    } else {
+                        Undo::Trash(changed)
+                    });
+                }
+
+                self.library_stale.store(true, Ordering::Relaxed);
+                self.add_task_load_library(sender.input_sender().clone());
+                self.run_if_idle();
+            }
+            BootstrapInput::MovePictures(picture_ids, dest_folder) => {
+                info!(
+                    "Moving {} pictures to {:?}",
+                    picture_ids.len(),
+                    dest_folder.host_path
+                );
+                for picture_id in picture_ids {
+                    if let Err(e) =
+                        self.photo_repo
+                            .move_picture(&picture_id, &dest_folder, &self.thumbnailer)
+                    {
+                        error!("Failed moving picture {}: {:?}", picture_id, e);
+                    }
+                }
+
+                self.library_stale.store(true, Ordering::Relaxed);
+                self.add_task_load_library(sender.input_sender().clone());
+                self.run_if_idle();
+            }
+            BootstrapInput::LocateMissing(picture_id, path) => {
+                info!(
+                    "Locating missing picture {} at {:?}",
+                    picture_id, path.host_path
+                );
+                let located = self
+                    .photo_repo
+                    .locate(&picture_id, &path, &self.thumbnailer)
+                    .inspect_err(|e| error!("Failed locating picture {}: {:?}", picture_id, e))
+                    .ok()
+                    .map(|new_path| new_path.is_some());
+
+                let _ = sender.output(BootstrapOutput::MissingLocated(picture_id, located));
+
+                if located == Some(true) {
+                    self.library_stale.store(true, Ordering::Relaxed);
+                    self.add_task_load_library(sender.input_sender().clone());
+                    self.run_if_idle();
+                }
+            }
+            BootstrapInput::FindSimilar(picture_id) => {
+                let max_distance = self.settings_state.read().similar_photos_distance;
+                info!(
+                    "Finding pictures within distance {} of picture {}",
+                    max_distance, picture_id
+                );
+
+                let similar = self
+                    .photo_repo
+                    .similar_to(&picture_id, max_distance)
+                    .inspect_err(|e| error!("Failed finding pictures like {}: {:?}", picture_id, e))
+                    .unwrap_or_default();
+
+                let similar = similar.into_iter().map(|pic| pic.picture_id).collect();
+                let _ = sender.output(BootstrapOutput::SimilarFound(picture_id, similar));
+            }
+            BootstrapInput::FindDuplicates => {
+                info!("Finding duplicate pictures");
+
+                let duplicates = self
+                    .photo_repo
+                    .duplicates()
+                    .inspect_err(|e| error!("Failed finding duplicate pictures: {:?}", e))
+                    .unwrap_or_default();
+
+                info!("Found {} groups of duplicate pictures", duplicates.len());
+
+                let picture_ids = duplicates
+                    .into_iter()
+                    .flatten()
+                    .map(|pic| pic.picture_id)
+                    .collect();
+                let _ = sender.output(BootstrapOutput::DuplicatesFound(picture_ids));
+            }
+            BootstrapInput::RemoveMissing(picture_id) => {
+                info!("Removing missing picture {} from library", picture_id);
+                if let Err(e) = self
+                    .photo_repo
+                    .remove_from_library(&picture_id, &self.thumbnailer)
+                {
+                    error!("Failed removing picture {}: {:?}", picture_id, e);
+                }
+
+                self.library_stale.store(true, Ordering::Relaxed);
+                self.add_task_load_library(sender.input_sender().clone());
+                self.run_if_idle();
+            }
+            BootstrapInput::EmptyTrash(delete_files) => {
+                info!("Emptying trash, deleting files: {}", delete_files);
+                match self.photo_repo.empty_trash(delete_files, &self.thumbnailer) {
+                    Ok(count) => info!("Removed {} trashed pictures", count),
+                    Err(e) => error!("Failed emptying trash: {:?}", e),
+                }
+
+                // Deleted pictures can't be restored, so earlier trashing can't be undone.
+                self.undo_repo.clear();
+
+                self.library_stale.store(true, Ordering::Relaxed);
+                self.add_task_load_library(sender.input_sender().clone());
+                self.run_if_idle();
+            }
+            BootstrapInput::Undo => {
+                match self.undo_repo.undo_last() {
+                    Ok(Some(undo)) => info!("Undid {:?}", undo),
+                    Ok(None) => {
+                        info!("Nothing to undo");
+                        return;
+                    }
+                    Err(e) => {
+                        error!("Failed undoing: {:?}", e);
+                        return;
+                    }
+                }
+
+                self.library_stale.store(true, Ordering::Relaxed);
+                self.add_task_load_library(sender.input_sender().clone());
+                self.run_if_idle();
+            }
             BootstrapInput::TaskStarted(task_name) => {
                 info!("Task started: {:?}", task_name);
                 let _ = sender.output(BootstrapOutput::TaskStarted(task_name));
@@ -257,8 +945,34 @@ impl Controllers {
                     }
                 }
             }
+            BootstrapInput::SetPaused(is_paused) => {
+                info!("Setting background tasks paused to {}", is_paused);
+                if is_paused {
+                    self.pause.pause();
+                    self.progress_monitor.emit(ProgressMonitorInput::Pause);
+                } else {
+                    self.pause.resume();
+                    self.progress_monitor.emit(ProgressMonitorInput::Resume);
+                }
+            }
+            BootstrapInput::SetPowerPaused(is_paused) => {
+                // Checked periodically, so only act on changes.
+                if is_paused != self.power_pause.is_paused() {
+                    info!("Setting photo thumbnails paused for power to {}", is_paused);
+                    if is_paused {
+                        self.power_pause.pause();
+                    } else {
+                        self.power_pause.resume();
+                    }
+                }
+            }
             BootstrapInput::Stop => {
                 info!("Stopping all background tasks");
+
+                // Paused tasks must run to notice they have been stopped.
+                self.pause.resume();
+                self.power_pause.resume();
+                self.progress_monitor.emit(ProgressMonitorInput::Resume);
                 if self.is_running {
                     let _ = sender.output(BootstrapOutput::Stopping);
                     if let Ok(mut tasks) = self.pending_tasks.lock() {
@@ -277,12 +991,66 @@ impl Controllers {
 
     fn add_task_library_scan(&mut self) {
         let sender = self.library_scan_task.sender().clone();
-        self.enqueue(Box::new(move || sender.emit(LibraryScanTaskInput::Start)));
+        let ignore_patterns = self.settings_state.read().ignore_patterns.clone();
+        let follow_symlinks = self.settings_state.read().follow_symlinks;
+        self.enqueue(Box::new(move || {
+            sender.emit(LibraryScanTaskInput::Start {
+                ignore_patterns: ignore_patterns.clone(),
+                follow_symlinks,
+            })
+        }));
+    }
+
+    fn add_task_library_rebuild(&mut self) {
+        let sender = self.library_scan_task.sender().clone();
+        let ignore_patterns = self.settings_state.read().ignore_patterns.clone();
+        let follow_symlinks = self.settings_state.read().follow_symlinks;
+        self.enqueue(Box::new(move || {
+            sender.emit(LibraryScanTaskInput::Rebuild {
+                ignore_patterns: ignore_patterns.clone(),
+                follow_symlinks,
+            })
+        }));
+    }
+
+    /// Tasks to bring newly scanned files into the library.
+    fn add_tasks_process_scanned(&mut self, bootstrap_sender: Sender<BootstrapInput>) {
+        self.add_task_photo_enrich();
+        self.add_task_video_enrich();
+        self.add_task_photo_import_sidecars();
+        self.add_task_photo_thumbnail();
+        self.add_task_video_thumbnail();
+        self.add_task_animated_thumbnail();
+        self.add_task_photo_clean();
+        self.add_task_video_clean();
+        self.add_task_photo_burst();
+        self.add_task_photo_extract_motion();
+        self.add_task_photo_detect_faces();
+        self.add_task_photo_recognize_faces();
+        self.add_task_tidy();
+        self.add_task_load_library(bootstrap_sender);
     }
 
     fn add_task_photo_enrich(&mut self) {
         let sender = self.photo_enrich_task.sender().clone();
-        self.enqueue(Box::new(move || sender.emit(PhotoEnrichTaskInput::Start)));
+        let time_zone = self.settings_state.read().naive_time_zone;
+        self.enqueue(Box::new(move || {
+            sender.emit(PhotoEnrichTaskInput::Start { time_zone })
+        }));
+    }
+
+    /// Favorites and tags from sidecars changed by other photo managers.
+    fn add_task_photo_import_sidecars(&mut self) {
+        let sender = self.photo_sidecar_task.sender().clone();
+        let policy = self.settings_state.read().sidecar_policy;
+        self.enqueue(Box::new(move || {
+            sender.emit(PhotoSidecarTaskInput::Import(policy))
+        }));
+    }
+
+    fn add_task_photo_write_sidecars(&mut self) {
+        let sender = self.photo_sidecar_task.sender().clone();
+        self.enqueue(Box::new(move || sender.emit(PhotoSidecarTaskInput::Write)));
     }
 
     fn add_task_video_enrich(&mut self) {
@@ -292,8 +1060,9 @@ impl Controllers {
 
     fn add_task_photo_thumbnail(&mut self) {
         let sender = self.photo_thumbnail_task.sender().clone();
+        let order = self.settings_state.read().thumbnail_order;
         self.enqueue(Box::new(move || {
-            sender.emit(PhotoThumbnailTaskInput::Start)
+            sender.emit(PhotoThumbnailTaskInput::Start { order })
         }));
     }
 
@@ -304,6 +1073,16 @@ impl Controllers {
         }));
     }
 
+    fn add_task_animated_thumbnail(&mut self) {
+        let sender = self.animated_thumbnail_task.sender().clone();
+        let enable = self.settings_state.read().animated_previews;
+        if enable {
+            self.enqueue(Box::new(move || {
+                sender.emit(AnimatedThumbnailTaskInput::Start)
+            }));
+        }
+    }
+
     fn add_task_photo_clean(&mut self) {
         let sender = self.photo_clean_task.sender().clone();
         self.enqueue(Box::new(move || sender.emit(PhotoCleanTaskInput::Start)));
@@ -314,6 +1093,13 @@ impl Controllers {
         self.enqueue(Box::new(move || sender.emit(VideoCleanTaskInput::Start)));
     }
 
+    /// Bursts are grouped after thumbnailing, because the sharpest thumbnail
+    /// is chosen as the cover.
+    fn add_task_photo_burst(&mut self) {
+        let sender = self.photo_burst_task.sender().clone();
+        self.enqueue(Box::new(move || sender.emit(PhotoBurstTaskInput::Start)));
+    }
+
     fn add_task_photo_extract_motion(&mut self) {
         let sender = self.photo_extract_motion_task.sender().clone();
         let enable = self.settings_state.read().process_motion_photos;
@@ -327,11 +1113,12 @@ impl Controllers {
     fn add_task_photo_detect_faces(&mut self) {
         let sender = self.photo_detect_faces_task.sender().clone();
         let mode = self.settings_state.read().face_detection_mode;
+        let head_margin = self.settings_state.read().face_thumbnail_margin;
         match mode {
             FaceDetectionMode::Off => {}
             FaceDetectionMode::On => {
                 self.enqueue(Box::new(move || {
-                    sender.emit(PhotoDetectFacesTaskInput::DetectForAllPictures)
+                    sender.emit(PhotoDetectFacesTaskInput::DetectForAllPictures { head_margin })
                 }));
             }
         };
@@ -340,11 +1127,15 @@ impl Controllers {
     fn add_task_photo_detect_faces_for_one(&mut self, picture_id: PictureId) {
         let sender = self.photo_detect_faces_task.sender().clone();
         let mode = self.settings_state.read().face_detection_mode;
+        let head_margin = self.settings_state.read().face_thumbnail_margin;
         match mode {
             FaceDetectionMode::Off => {}
             FaceDetectionMode::On => {
                 self.enqueue(Box::new(move || {
-                    sender.emit(PhotoDetectFacesTaskInput::DetectForOnePicture(picture_id))
+                    sender.emit(PhotoDetectFacesTaskInput::DetectForOnePicture {
+                        picture_id,
+                        head_margin,
+                    })
                 }));
             }
         };
@@ -366,11 +1157,12 @@ impl Controllers {
     fn add_task_person_thumbnails(&mut self) {
         let sender = self.person_thumbnail_task.sender().clone();
         let mode = self.settings_state.read().face_detection_mode;
+        let head_margin = self.settings_state.read().face_thumbnail_margin;
         match mode {
             FaceDetectionMode::Off => {}
             FaceDetectionMode::On => {
                 self.enqueue(Box::new(move || {
-                    sender.emit(PersonThumbnailTaskInput::Start)
+                    sender.emit(PersonThumbnailTaskInput::Start { head_margin })
                 }));
             }
         };
@@ -381,6 +1173,55 @@ impl Controllers {
         self.enqueue(Box::new(move || sender.emit(VideoTranscodeTaskInput::Start)));
     }
 
+    /// Update the tags of a picture in the loaded library in place, rather than
+    /// reloading everything from database.
+    fn update_tags(&self, picture_id: PictureId) {
+        let tags = match self.tag_repo.tags_for(&picture_id) {
+            Ok(tags) => tags,
+            Err(e) => {
+                error!("Failed reading tags of picture {}: {:?}", picture_id, e);
+                return;
+            }
+        };
+
+        let mut library = self.shared_state.write();
+        for visual in library
+            .iter_mut()
+            .filter(|visual| visual.picture_id == Some(picture_id))
+        {
+            let mut updated = (**visual).clone();
+            updated.tags = tags.clone();
+            *visual = Arc::new(updated);
+        }
+    }
+
+    /// Update user albums of the loaded library in place, rather than reloading
+    /// everything from database. Changing one picture's album can change the order
+    /// and cover of the others in the album, so every picture is checked.
+    fn update_user_albums(&self) {
+        let entries = match self.user_album_repo.all_entries() {
+            Ok(entries) => entries,
+            Err(e) => {
+                error!("Failed reading user albums: {:?}", e);
+                return;
+            }
+        };
+
+        let mut library = self.shared_state.write();
+        for visual in library.iter_mut() {
+            let user_albums = visual
+                .picture_id
+                .and_then(|picture_id| entries.get(&picture_id).cloned())
+                .unwrap_or_default();
+
+            if visual.user_albums != user_albums {
+                let mut updated = (**visual).clone();
+                updated.user_albums = user_albums;
+                *visual = Arc::new(updated);
+            }
+        }
+    }
+
     fn add_task_load_library(&mut self, bootstrap_sender: Sender<BootstrapInput>) {
         let sender = self.load_library_task.sender().clone();
         let stale = self.library_stale.clone();
@@ -395,9 +1236,67 @@ impl Controllers {
         }));
     }
 
+    fn add_task_person_export(&mut self, person_id: PersonId, destination: PathBuf) {
+        let sender = self.person_export_task.sender().clone();
+        let min_confidence = self.settings_state.read().face_confidence_threshold;
+        let privacy_state = self.privacy_state.clone();
+        self.enqueue(Box::new(move || {
+            // Private pictures could have been locked since the export was asked for.
+            let include_private = *privacy_state.read() == Privacy::Unlocked;
+            sender.emit(PersonExportTaskInput::Start(
+                person_id,
+                min_confidence,
+                include_private,
+                destination.clone(),
+            ))
+        }));
+    }
+
+    fn add_task_metadata_export(&mut self, format: ExportFormat, destination: PathBuf) {
+        let sender = self.metadata_export_task.sender().clone();
+        let min_confidence = self.settings_state.read().face_confidence_threshold;
+        let privacy_state = self.privacy_state.clone();
+        self.enqueue(Box::new(move || {
+            let include_private = *privacy_state.read() == Privacy::Unlocked;
+            sender.emit(MetadataExportTaskInput::Start(
+                format,
+                min_confidence,
+                include_private,
+                destination.clone(),
+            ))
+        }));
+    }
+
+    fn add_task_import(&mut self, sources: Vec<PathBuf>) {
+        let sender = self.import_task.sender().clone();
+        let library_root = self.settings_state.read().library_base_dir.sandbox_path.clone();
+        let layout = self.settings_state.read().import_layout;
+        self.enqueue(Box::new(move || {
+            sender.emit(ImportTaskInput::Start(
+                sources.clone(),
+                library_root.clone(),
+                layout,
+            ))
+        }));
+    }
+
     fn add_task_tidy(&mut self) {
         let sender = self.tidy_task.sender().clone();
-        self.enqueue(Box::new(move || sender.emit(TidyTaskInput::Start)));
+        let animated_previews = self.settings_state.read().animated_previews;
+        let thumbnail_cache_limit = self.settings_state.read().thumbnail_cache_limit_bytes();
+        self.enqueue(Box::new(move || {
+            sender.emit(TidyTaskInput::Start {
+                animated_previews,
+                thumbnail_cache_limit,
+            })
+        }));
+    }
+
+    fn add_task_relocate_thumbnails(&mut self, from: ThumbnailLocation) {
+        let sender = self.thumbnail_relocate_task.sender().clone();
+        self.enqueue(Box::new(move || {
+            sender.emit(ThumbnailRelocateTaskInput::Start(from))
+        }));
     }
 
     fn add_task_migrate(&mut self) {
@@ -431,6 +1330,18 @@ pub struct Bootstrap {
 
     shared_state: SharedState,
 
+    /// Visual items with trashed pictures.
+    trash_state: SharedState,
+
+    /// Whether private pictures are locked.
+    privacy_state: PrivacyState,
+
+    /// How private pictures are shown while locked, when the library was last loaded.
+    privacy_mode: PrivacyMode,
+
+    /// Thumbnailer used by albums, which blurs the thumbnails of private pictures.
+    album_thumbnailer: Thumbnailer,
+
     con: Arc<Mutex<database::Connection>>,
 
     progress_monitor: Arc<Reducer<ProgressMonitor>>,
@@ -438,14 +1349,75 @@ pub struct Bootstrap {
     /// Background task runners. Only present after library path is set.
     controllers: Option<Controllers>,
 
-    /// Current pictures base directory used by background tasks.
-    library_base_dir: Option<FlatpakPathBuf>,
+    /// Current pictures library roots used by background tasks.
+    library_roots: Option<LibraryRoots>,
+
+    /// Rescan the library when files change. One per library root.
+    /// Only present if enabled in settings.
+    library_watchers: Vec<LibraryWatcher>,
+
+    /// Holds back photo thumbnails on battery power, or while the computer is in use.
+    /// Only present if enabled in settings.
+    #[cfg(feature = "power")]
+    power_monitor: Option<PowerMonitor>,
 }
 
 impl Bootstrap {
+    /// Start or stop watching the library roots, depending on settings.
+    fn update_library_watchers(&mut self, sender: &ComponentSender<Self>) {
+        let watch_library = self.settings_state.read().watch_library;
+        let Some(library_roots) = self.library_roots.as_ref().filter(|_| watch_library)
+        else {
+            self.library_watchers.clear();
+            return;
+        };
+
+        let paths: Vec<&Path> = library_roots.iter().map(|root| root.sandbox_path.as_path()).collect();
+        let watched: Vec<&Path> = self.library_watchers.iter().map(|watcher| watcher.path()).collect();
+        if watched == paths {
+            return;
+        }
+
+        self.library_watchers.clear();
+
+        for path in paths {
+            info!("Watching {:?} for changes", path);
+            let sender = sender.input_sender().clone();
+            match LibraryWatcher::watch(path, move || sender.emit(BootstrapInput::Rescan)) {
+                Ok(watcher) => self.library_watchers.push(watcher),
+                Err(e) => error!("Failed watching {:?}: {:?}", path, e),
+            }
+        }
+    }
+
+    /// Start or stop checking power and idle state, depending on settings.
+    #[cfg(feature = "power")]
+    fn update_power_monitor(&mut self, sender: &ComponentSender<Self>) {
+        let mode = self.settings_state.read().thumbnail_power_mode;
+        if self
+            .power_monitor
+            .as_ref()
+            .is_some_and(|monitor| monitor.mode() == mode)
+        {
+            return;
+        }
+
+        self.power_monitor = None;
+        if mode == ThumbnailPowerMode::Always {
+            sender.input(BootstrapInput::SetPowerPaused(false));
+            return;
+        }
+
+        info!("Generating photo thumbnails {:?}", mode);
+        let sender = sender.input_sender().clone();
+        self.power_monitor = Some(PowerMonitor::watch(mode, move |is_allowed| {
+            sender.emit(BootstrapInput::SetPowerPaused(!is_allowed))
+        }));
+    }
+
     fn build_controllers(
         &mut self,
-        library_base_dir: &FlatpakPathBuf,
+        library_roots: &LibraryRoots,
         sender: &ComponentSender<Self>,
     ) -> anyhow::Result<Controllers> {
         let data_dir = glib::user_data_dir().join(APP_ID);
@@ -461,10 +1433,11 @@ impl Bootstrap {
 
         info!("Thumbnail directory is {:?}", thumbnail_dir);
 
-        let thumbnailer = Thumbnailer::build(&thumbnail_dir);
+        // Shared with albums, so that both find thumbnails saved beside pictures.
+        let thumbnailer = self.album_thumbnailer.clone();
 
         let photo_repo = photo::Repository::open(
-            library_base_dir,
+            library_roots,
             &cache_dir,
             &data_dir,
             self.con.clone(),
@@ -472,10 +1445,19 @@ impl Bootstrap {
 
         let photo_thumbnailer = photo::PhotoThumbnailer::build(thumbnailer.clone())?;
 
-        let scanner = Scanner::build(&library_base_dir.sandbox_path)?;
+        // Always scan the primary root, creating it if necessary, but skip any other
+        // root that is missing, such as one on a disconnected drive.
+        let mut scanners = vec![Scanner::build(&library_roots.primary().sandbox_path)?];
+        for root in library_roots.iter().skip(1) {
+            if root.exists() {
+                scanners.push(Scanner::build(&root.sandbox_path)?);
+            } else {
+                warn!("Not scanning missing library root {:?}", root);
+            }
+        }
 
         let video_repo = video::Repository::open(
-            library_base_dir,
+            library_roots,
             &cache_dir,
             &data_dir,
             self.con.clone(),
@@ -486,7 +1468,7 @@ impl Bootstrap {
         let motion_photo_extractor = photo::MotionPhotoExtractor::build(&cache_dir)?;
 
         let visual_repo = visual::Repository::open(
-            library_base_dir,
+            library_roots,
             &cache_dir,
             self.con.clone(),
         )?;
@@ -496,24 +1478,51 @@ impl Bootstrap {
             &data_dir,
             self.con.clone())?;
 
+        let face_detection_model = self.settings_state.read().face_detection_model;
+
+        let undo_repo = undo::Repository::open(self.con.clone())?;
+
+        let tag_repo = tag::Repository::open(self.con.clone())?;
+
+        let user_album_repo = user_album::Repository::open(self.con.clone())?;
+
+        let burst_repo = burst::Repository::open(self.con.clone())?;
+
         let stop = Arc::new(AtomicBool::new(false));
+        let pause = Arc::new(Pause::default());
+        let power_pause = Arc::new(Pause::default());
 
         let load_library_task = LoadLibraryTask::builder()
-            .detach_worker((visual_repo.clone(), self.shared_state.clone()))
+            .detach_worker((
+                visual_repo.clone(),
+                self.shared_state.clone(),
+                self.trash_state.clone(),
+                self.privacy_state.clone(),
+                self.settings_state.clone(),
+                self.album_thumbnailer.clone(),
+            ))
             .forward(sender.input_sender(), |msg| match msg {
                 LoadLibraryTaskOutput::Done => {
                     BootstrapInput::TaskCompleted(TaskName::LoadLibrary, None)
                 }
+                LoadLibraryTaskOutput::Failed => BootstrapInput::LibraryLoadFailed,
             });
 
         let library_scan_task = LibraryScanTask::builder()
-            .detach_worker((scanner, photo_repo.clone(), video_repo.clone()))
+            .detach_worker((
+                scanners,
+                photo_repo.clone(),
+                video_repo.clone(),
+                visual_repo.clone(),
+                thumbnailer.clone(),
+                self.progress_monitor.clone(),
+            ))
             .forward(sender.input_sender(), |msg| match msg {
                 LibraryScanTaskOutput::Started => {
                     BootstrapInput::TaskStarted(TaskName::Scan)
                 }
-                LibraryScanTaskOutput::Completed => {
-                    BootstrapInput::TaskCompleted(TaskName::Scan, None)
+                LibraryScanTaskOutput::Completed(count) => {
+                    BootstrapInput::TaskCompleted(TaskName::Scan, Some(count))
                 }
             });
 
@@ -562,6 +1571,8 @@ impl Bootstrap {
         let photo_thumbnail_task = PhotoThumbnailTask::builder()
             .detach_worker((
                 stop.clone(),
+                pause.clone(),
+                power_pause.clone(),
                 thumbnail_dir.clone(),
                 photo_thumbnailer.clone(),
                 photo_repo.clone(),
@@ -575,12 +1586,21 @@ impl Bootstrap {
                     TaskName::Thumbnail(ThumbnailType::Photo),
                     Some(count),
                 ),
+                PhotoThumbnailTaskOutput::CacheUnwritable(path) => {
+                    BootstrapInput::CacheUnwritable(path)
+                }
+                PhotoThumbnailTaskOutput::Regenerated(picture_id) => {
+                    BootstrapInput::ThumbnailRegenerated(picture_id)
+                }
+                PhotoThumbnailTaskOutput::Counted(count) => {
+                    BootstrapInput::PhotoThumbnailsCounted(count)
+                }
             });
 
         let video_thumbnail_task = VideoThumbnailTask::builder()
             .detach_worker((
                 stop.clone(),
-                thumbnail_dir.clone(),
+                pause.clone(),
                 video_thumbnailer.clone(),
                 video_repo.clone(),
                 self.progress_monitor.clone(),
@@ -595,6 +1615,26 @@ impl Bootstrap {
                 ),
             });
 
+        let animated_thumbnailer = visual::AnimatedThumbnailer::build(thumbnailer.clone())?;
+
+        let animated_thumbnail_task = AnimatedThumbnailTask::builder()
+            .detach_worker((
+                stop.clone(),
+                pause.clone(),
+                animated_thumbnailer,
+                visual_repo.clone(),
+                self.progress_monitor.clone(),
+            ))
+            .forward(sender.input_sender(), |msg| match msg {
+                AnimatedThumbnailTaskOutput::Started => {
+                    BootstrapInput::TaskStarted(TaskName::Thumbnail(ThumbnailType::Animated))
+                }
+                AnimatedThumbnailTaskOutput::Completed(count) => BootstrapInput::TaskCompleted(
+                    TaskName::Thumbnail(ThumbnailType::Animated),
+                    Some(count),
+                ),
+            });
+
         let transcoder = video::Transcoder::new(&cache_dir);
 
         let video_transcode_task = VideoTranscodeTask::builder()
@@ -639,6 +1679,7 @@ impl Bootstrap {
                 stop.clone(),
                 data_dir.clone(),
                 thumbnailer.clone(),
+                face_detection_model,
                 photo_repo.clone(),
                 people_repo.clone(),
                 self.progress_monitor.clone(),
@@ -668,8 +1709,22 @@ impl Bootstrap {
                 }
             });
 
+        let photo_burst_task = PhotoBurstTask::builder()
+            .detach_worker((
+                stop.clone(),
+                photo_repo.clone(),
+                burst_repo.clone(),
+                thumbnailer.clone(),
+            ))
+            .forward(sender.input_sender(), |msg| match msg {
+                PhotoBurstTaskOutput::Started => BootstrapInput::TaskStarted(TaskName::Bursts),
+                PhotoBurstTaskOutput::Completed(count) => {
+                    BootstrapInput::TaskCompleted(TaskName::Bursts, Some(count))
+                }
+            });
+
         let tidy_task = TidyTask::builder()
-            .detach_worker(stop.clone())
+            .detach_worker((stop.clone(), thumbnailer.clone()))
             .forward(sender.input_sender(), |msg| match msg {
                 TidyTaskOutput::Started => {
                     BootstrapInput::TaskStarted(TaskName::Tidy)
@@ -679,7 +1734,27 @@ impl Bootstrap {
                 }
             });
 
-        let migrate = Migrate::build(people_repo, &data_dir, library_base_dir.clone());
+        let thumbnail_relocate_task = ThumbnailRelocateTask::builder()
+            .detach_worker((
+                stop.clone(),
+                visual_repo.clone(),
+                thumbnailer.clone(),
+                self.progress_monitor.clone(),
+            ))
+            .forward(sender.input_sender(), |msg| match msg {
+                ThumbnailRelocateTaskOutput::Started => {
+                    BootstrapInput::TaskStarted(TaskName::RelocateThumbnails)
+                }
+                ThumbnailRelocateTaskOutput::Completed(count) => {
+                    BootstrapInput::TaskCompleted(TaskName::RelocateThumbnails, Some(count))
+                }
+            });
+
+        let migrate = Migrate::build(
+            people_repo.clone(),
+            &data_dir,
+            library_roots.primary().clone(),
+        );
 
         let migrate_task = MigrateTask::builder()
             .detach_worker((stop.clone(), migrate))
@@ -705,26 +1780,89 @@ impl Bootstrap {
                 }
             });
 
+        let person_export_task = PersonExportTask::builder()
+            .detach_worker((photo_repo.clone(), self.progress_monitor.clone()))
+            .forward(sender.input_sender(), |msg| match msg {
+                PersonExportTaskOutput::Started => BootstrapInput::TaskStarted(TaskName::Export),
+                PersonExportTaskOutput::Completed(summary) => {
+                    BootstrapInput::ExportCompleted(summary)
+                }
+            });
+
+        let metadata_export_task = MetadataExportTask::builder()
+            .detach_worker((photo_repo.clone(), self.progress_monitor.clone()))
+            .forward(sender.input_sender(), |msg| match msg {
+                MetadataExportTaskOutput::Started => {
+                    BootstrapInput::TaskStarted(TaskName::ExportMetadata)
+                }
+                MetadataExportTaskOutput::Completed(count) => {
+                    BootstrapInput::MetadataExportCompleted(count)
+                }
+            });
+
+        let photo_sidecar_task = PhotoSidecarTask::builder()
+            .detach_worker((photo_repo.clone(), self.progress_monitor.clone()))
+            .forward(sender.input_sender(), |msg| match msg {
+                PhotoSidecarTaskOutput::WriteStarted => {
+                    BootstrapInput::TaskStarted(TaskName::WriteSidecars)
+                }
+                // Sidecars are written from the library, so the library is unchanged.
+                PhotoSidecarTaskOutput::WriteCompleted => {
+                    BootstrapInput::TaskCompleted(TaskName::WriteSidecars, None)
+                }
+                PhotoSidecarTaskOutput::ImportStarted => {
+                    BootstrapInput::TaskStarted(TaskName::ImportSidecars)
+                }
+                PhotoSidecarTaskOutput::ImportCompleted(count) => {
+                    BootstrapInput::TaskCompleted(TaskName::ImportSidecars, Some(count))
+                }
+            });
+
+        let import_task = ImportTask::builder()
+            .detach_worker((photo_repo.clone(), self.progress_monitor.clone()))
+            .forward(sender.input_sender(), |msg| match msg {
+                ImportTaskOutput::Started => BootstrapInput::TaskStarted(TaskName::Import),
+                ImportTaskOutput::Completed(summary) => BootstrapInput::ImportCompleted(summary),
+            });
+
         let mut controllers = Controllers {
             stop,
+            pause,
+            power_pause,
+            progress_monitor: self.progress_monitor.clone(),
             started_at: None,
             shared_state: self.shared_state.clone(),
             settings_state: self.settings_state.clone(),
+            photo_repo: photo_repo.clone(),
+            thumbnailer,
+            undo_repo,
+            tag_repo,
+            user_album_repo,
+            burst_repo,
+            people_repo,
+            face_detection_model,
             load_library_task: Arc::new(load_library_task),
             library_scan_task: Arc::new(library_scan_task),
             photo_enrich_task: Arc::new(photo_enrich_task),
             video_enrich_task: Arc::new(video_enrich_task),
             photo_extract_motion_task: Arc::new(photo_extract_motion_task),
+            photo_burst_task: Arc::new(photo_burst_task),
             photo_clean_task: Arc::new(photo_clean_task),
             video_clean_task: Arc::new(video_clean_task),
             photo_thumbnail_task: Arc::new(photo_thumbnail_task),
             video_thumbnail_task: Arc::new(video_thumbnail_task),
+            animated_thumbnail_task: Arc::new(animated_thumbnail_task),
             photo_detect_faces_task: Arc::new(photo_detect_faces_task),
             photo_recognize_faces_task: Arc::new(photo_recognize_faces_task),
             video_transcode_task: Arc::new(video_transcode_task),
             tidy_task: Arc::new(tidy_task),
+            thumbnail_relocate_task: Arc::new(thumbnail_relocate_task),
             migrate_task: Arc::new(migrate_task),
             person_thumbnail_task: Arc::new(person_thumbnail_task),
+            person_export_task: Arc::new(person_export_task),
+            metadata_export_task: Arc::new(metadata_export_task),
+            photo_sidecar_task: Arc::new(photo_sidecar_task),
+            import_task: Arc::new(import_task),
             pending_tasks: Arc::new(Mutex::new(VecDeque::new())),
             is_running: false,
             library_stale: Arc::new(AtomicBool::new(true)),
@@ -748,18 +1886,30 @@ impl Bootstrap {
 
         controllers.add_task_photo_thumbnail();
         controllers.add_task_video_thumbnail();
+        controllers.add_task_animated_thumbnail();
         controllers.add_task_photo_clean();
         controllers.add_task_video_clean();
+        controllers.add_task_photo_burst();
         controllers.add_task_photo_extract_motion();
         controllers.add_task_photo_detect_faces();
         controllers.add_task_photo_recognize_faces();
 
-        controllers.add_task_tidy();
+        // Transcoding is only started by the user, so carry on with it if the app exited
+        // before it completed. Other tasks are already run on every start.
+        if Interrupted::load().is_some_and(|i| i.task_name == progress_monitor::TaskName::Transcode)
+        {
+            info!("Resuming interrupted video transcoding");
+            controllers.add_task_video_transcode();
+        }
 
         controllers.add_task_load_library(sender.input_sender().clone());
 
         controllers.add_task_person_thumbnails();
 
+        // Tidy last, because tidying can evict thumbnails that face detection and
+        // person thumbnails are made from.
+        controllers.add_task_tidy();
+
         Ok(controllers)
     }
 }
@@ -768,27 +1918,49 @@ impl Worker for Bootstrap {
     type Init = (
         Arc<Mutex<database::Connection>>,
         SharedState,
+        SharedState,
         SettingsState,
+        PrivacyState,
+        Thumbnailer,
         Arc<Reducer<ProgressMonitor>>,
     );
     type Input = BootstrapInput;
     type Output = BootstrapOutput;
 
     fn init(
-        (con, shared_state, settings_state, progress_monitor): Self::Init,
+        (
+            con,
+            shared_state,
+            trash_state,
+            settings_state,
+            privacy_state,
+            album_thumbnailer,
+            progress_monitor,
+        ): Self::Init,
         sender: ComponentSender<Self>,
     ) -> Self {
         settings_state.subscribe(sender.input_sender(), |settings| {
             BootstrapInput::SettingsUpdated(settings.clone())
         });
 
+        privacy_state.subscribe(sender.input_sender(), |_| BootstrapInput::PrivacyChanged);
+
+        let privacy_mode = settings_state.read().privacy_mode;
+
         Self {
             shared_state,
+            trash_state,
+            privacy_state,
+            privacy_mode,
+            album_thumbnailer,
             settings_state,
             progress_monitor,
             con,
             controllers: None,
-            library_base_dir: None,
+            library_roots: None,
+            library_watchers: Vec::new(),
+            #[cfg(feature = "power")]
+            power_monitor: None,
         }
     }
 
@@ -796,19 +1968,27 @@ impl Worker for Bootstrap {
         // This match block coordinates the background tasks launched immediately after
         // the app starts up.
         match msg {
-            BootstrapInput::Configure(library_base_dir) => {
+            BootstrapInput::Configure(library_roots) => {
                 info!(
-                    "Configuring with pictures base directory: {:?}",
-                    library_base_dir
+                    "Configuring with pictures library roots: {:?}",
+                    library_roots
                 );
 
                 match self.build_controllers(
-                    &library_base_dir,
+                    &library_roots,
                     &sender,
                 ) {
                     Ok(controllers) => {
-                        self.library_base_dir = Some(library_base_dir);
+                        self.library_roots = Some(library_roots);
                         self.controllers = Some(controllers);
+                        self.update_library_watchers(&sender);
+                        #[cfg(feature = "power")]
+                        {
+                            // Check again straight away, because new controllers
+                            // aren't held back.
+                            self.power_monitor = None;
+                            self.update_power_monitor(&sender);
+                        }
                         sender.input(BootstrapInput::Start);
                     }
                     Err(e) => {
@@ -818,12 +1998,19 @@ impl Worker for Bootstrap {
             }
             BootstrapInput::SettingsUpdated(settings) => {
                 info!("Settings updated.");
-                // Only stop, reconfigure, and restart tasks if pictures dir changes.
-                if self
-                    .library_base_dir
+                // Only stop, reconfigure, and restart tasks if library roots change,
+                // or if the face detection model the tasks were built with changes.
+                let library_roots = settings.library_roots();
+                let is_roots_changed = self
+                    .library_roots
                     .as_ref()
-                    .is_some_and(|dir| *dir != settings.library_base_dir)
-                {
+                    .is_some_and(|roots| *roots != library_roots);
+                let is_face_detection_model_changed =
+                    self.controllers.as_ref().is_some_and(|controllers| {
+                        controllers.face_detection_model != settings.face_detection_model
+                    });
+
+                if is_roots_changed || is_face_detection_model_changed {
                     // If running, then shutdown running and queued tasks, and then reconfigure.
                     // Otherwise simply reconfigure with new path.
                     if self
@@ -831,25 +2018,30 @@ impl Worker for Bootstrap {
                         .as_ref()
                         .is_some_and(|controllers| controllers.is_running)
                     {
-                        self.library_base_dir = None;
+                        self.library_roots = None;
                         sender.input(BootstrapInput::Stop);
                     } else {
                         self.controllers = None;
-                        sender.input(BootstrapInput::Configure(
-                            settings.library_base_dir.clone(),
-                        ));
+                        sender.input(BootstrapInput::Configure(library_roots));
                     }
                 }
+
+                self.update_library_watchers(&sender);
+                #[cfg(feature = "power")]
+                self.update_power_monitor(&sender);
+
+                if self.privacy_mode != settings.privacy_mode {
+                    self.privacy_mode = settings.privacy_mode;
+                    sender.input(BootstrapInput::PrivacyChanged);
+                }
             }
-            BootstrapInput::Stopped if self.library_base_dir.is_none() => {
-                // If stopped and no pictures base dir, then background tasks were
-                // shutdown in response to the user changing the pictures base directory.
+            BootstrapInput::Stopped if self.library_roots.is_none() => {
+                // If stopped and no library roots, then background tasks were
+                // shutdown in response to the user changing the library directories.
                 // Now that tasks are shutdown, it is safe to reconfigure with
-                // the new directory.
+                // the new directories.
                 let settings = self.settings_state.read();
-                sender.input(BootstrapInput::Configure(
-                    settings.library_base_dir.clone(),
-                ));
+                sender.input(BootstrapInput::Configure(settings.library_roots()));
             }
             BootstrapInput::TaskCompleted(TaskName::LoadLibrary, _)
                 if self.controllers.is_some() =>
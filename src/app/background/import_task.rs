@@ -0,0 +1,110 @@
+// SPDX-FileCopyrightText: © 2025 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use anyhow::*;
+use relm4::Reducer;
+use relm4::Worker;
+use relm4::prelude::*;
+use std::path::{Path, PathBuf};
+use std::result::Result::Ok;
+use std::sync::Arc;
+use tracing::{error, info, warn};
+
+use fotema_core::import::{self, ImportLayout, ImportSummary};
+use fotema_core::photo;
+
+use crate::app::components::progress_monitor::{ProgressMonitor, ProgressMonitorInput, TaskName};
+
+#[derive(Debug)]
+pub enum ImportTaskInput {
+    /// Copy pictures and videos from files and directories into a library root.
+    Start(Vec<PathBuf>, PathBuf, ImportLayout),
+}
+
+#[derive(Debug)]
+pub enum ImportTaskOutput {
+    // Import has started.
+    Started,
+
+    // Import has completed.
+    Completed(ImportSummary),
+}
+
+pub struct ImportTask {
+    repo: photo::Repository,
+    progress_monitor: Arc<Reducer<ProgressMonitor>>,
+}
+
+impl ImportTask {
+    fn import(
+        &self,
+        sources: &[PathBuf],
+        library_root: &Path,
+        layout: ImportLayout,
+        sender: &ComponentSender<Self>,
+    ) -> Result<ImportSummary> {
+        let start = std::time::Instant::now();
+
+        let files = import::find_importable(sources);
+        info!("Importing {} files to {:?}", files.len(), library_root);
+
+        // Only pictures have content hashes, so videos are only checked against
+        // other imported videos and files already at the destination.
+        let mut known_hashes = self.repo.content_hashes()?;
+
+        let _ = sender.output(ImportTaskOutput::Started);
+
+        self.progress_monitor
+            .emit(ProgressMonitorInput::Start(TaskName::Import, files.len()));
+
+        let summary = import::import(&files, library_root, layout, &mut known_hashes, || {
+            self.progress_monitor.emit(ProgressMonitorInput::Advance);
+        });
+
+        self.progress_monitor.emit(ProgressMonitorInput::Complete);
+
+        let summary = summary?;
+
+        for path in &summary.failed {
+            warn!("Not imported because copying failed: {:?}", path);
+        }
+
+        info!(
+            "Imported {} files, skipped {} duplicates, in {} seconds.",
+            summary.imported,
+            summary.duplicates,
+            start.elapsed().as_secs()
+        );
+
+        Ok(summary)
+    }
+}
+
+impl Worker for ImportTask {
+    type Init = (photo::Repository, Arc<Reducer<ProgressMonitor>>);
+    type Input = ImportTaskInput;
+    type Output = ImportTaskOutput;
+
+    fn init((repo, progress_monitor): Self::Init, _sender: ComponentSender<Self>) -> Self {
+        ImportTask {
+            repo,
+            progress_monitor,
+        }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            ImportTaskInput::Start(sources, library_root, layout) => {
+                let summary = self
+                    .import(&sources, &library_root, layout, &sender)
+                    .unwrap_or_else(|e| {
+                        error!("Failed importing to {:?}: {:?}", library_root, e);
+                        ImportSummary::default()
+                    });
+
+                let _ = sender.output(ImportTaskOutput::Completed(summary));
+            }
+        };
+    }
+}
@@ -2,82 +2,255 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use relm4::Reducer;
 use relm4::Worker;
 use relm4::prelude::*;
+use std::sync::Arc;
 use tracing::{error, info};
 use fotema_core::{Scanner, ScannedFile};
+use fotema_core::scanner::IgnorePatterns;
 use fotema_core::photo::Repository as PhotoRepository;
 use fotema_core::video::Repository as VideoRepository;
+use fotema_core::visual::Repository as VisualRepository;
+use fotema_core::thumbnailify::Thumbnailer;
 use itertools::{Itertools, Either};
+use chrono::Utc;
+
+use crate::app::components::progress_monitor::{ProgressMonitor, ProgressMonitorInput, TaskName};
 
 #[derive(Debug)]
 pub enum LibraryScanTaskInput {
-    Start,
+    /// Scan files changed since the last scan, and flag pictures whose files have gone.
+    /// Scans everything in a library root that has never been scanned.
+    /// Files and folders matching an ignore pattern are skipped, and pictures already
+    /// in the library that match one are trashed.
+    /// Symbolic links are skipped unless `follow_symlinks` is set.
+    Start {
+        ignore_patterns: Vec<String>,
+        follow_symlinks: bool,
+    },
+
+    /// Scan every file in the library.
+    Rebuild {
+        ignore_patterns: Vec<String>,
+        follow_symlinks: bool,
+    },
 }
 
 #[derive(Debug)]
 pub enum LibraryScanTaskOutput {
     Started,
-    Completed,
+
+    /// Scan has completed. usize is the count of pictures newly missing because their file
+    /// has gone, or trashed because their directory has been removed from the library or
+    /// they match an ignore pattern.
+    Completed(usize),
 }
 
 pub struct LibraryScanTask {
-    scan: Scanner,
+    /// One scanner per library root.
+    scanners: Vec<Scanner>,
     photo_repo: PhotoRepository,
     video_repo: VideoRepository,
+    visual_repo: VisualRepository,
+    thumbnailer: Thumbnailer,
+    progress_monitor: Arc<Reducer<ProgressMonitor>>,
 }
 
 impl Worker for LibraryScanTask {
-    type Init = (Scanner, PhotoRepository, VideoRepository);
+    type Init = (
+        Vec<Scanner>,
+        PhotoRepository,
+        VideoRepository,
+        VisualRepository,
+        Thumbnailer,
+        Arc<Reducer<ProgressMonitor>>,
+    );
     type Input = LibraryScanTaskInput;
     type Output = LibraryScanTaskOutput;
 
-    fn init((scan, photo_repo, video_repo): Self::Init, _sender: ComponentSender<Self>) -> Self {
-        Self { scan, photo_repo, video_repo }
+    fn init(
+        (scanners, photo_repo, video_repo, visual_repo, thumbnailer, progress_monitor): Self::Init,
+        _sender: ComponentSender<Self>,
+    ) -> Self {
+        Self { scanners, photo_repo, video_repo, visual_repo, thumbnailer, progress_monitor }
     }
 
     fn update(&mut self, msg: LibraryScanTaskInput, sender: ComponentSender<Self>) {
-        match msg {
-            LibraryScanTaskInput::Start => {
-                let result = self.scan_and_add(sender);
-                if let Err(e) = result {
-                    error!("Failed scan with: {}", e);
-                }
-            }
+        let (is_full_scan, ignore_patterns, follow_symlinks) = match msg {
+            LibraryScanTaskInput::Start {
+                ignore_patterns,
+                follow_symlinks,
+            } => (false, ignore_patterns, follow_symlinks),
+            LibraryScanTaskInput::Rebuild {
+                ignore_patterns,
+                follow_symlinks,
+            } => (true, ignore_patterns, follow_symlinks),
         };
+
+        for scanner in &mut self.scanners {
+            scanner.set_ignore_patterns(&ignore_patterns);
+            scanner.set_follow_symlinks(follow_symlinks);
+        }
+
+        let result = self.scan_and_add(is_full_scan, sender);
+        if let Err(e) = result {
+            error!("Failed scan with: {}", e);
+            self.progress_monitor.emit(ProgressMonitorInput::Complete);
+        }
     }
 }
 
 impl LibraryScanTask {
-    fn scan_and_add(&mut self, sender: ComponentSender<Self>) -> std::result::Result<(), String> {
-        let start = std::time::Instant::now();
-
+    fn scan_and_add(
+        &mut self,
+        is_full_scan: bool,
+        sender: ComponentSender<Self>,
+    ) -> std::result::Result<(), String> {
         sender
             .output(LibraryScanTaskOutput::Started)
             .map_err(|e| format!("{:?}", e))?;
 
-        info!("Scanning file system for pictures...");
-
-        let result = self.scan.scan_all().map_err(|e| e.to_string())?;
-
-        let (photos, videos) = result.into_iter().partition_map(|scanned_file|
-            match scanned_file {
-                f @ ScannedFile::Photo(_) => Either::Left(f),
-                f @ ScannedFile::Video(_) => Either::Right(f),
-            });
+        // Count of files isn't known until the scan completes.
+        self.progress_monitor
+            .emit(ProgressMonitorInput::StartIndeterminate(TaskName::Scan));
 
-        self.photo_repo.add_all(&photos).map_err(|e| e.to_string())?;
-        self.video_repo.add_all(&videos).map_err(|e| e.to_string())?;
+        let progress_monitor = self.progress_monitor.clone();
+        let changed = scan_library(
+            &self.scanners,
+            &mut self.photo_repo,
+            &mut self.video_repo,
+            &mut self.visual_repo,
+            &self.thumbnailer,
+            is_full_scan,
+            || progress_monitor.emit(ProgressMonitorInput::Advance),
+        )
+        .map_err(|e| e.to_string())?;
 
-        info!(
-            "Scanned {} photos and {} videos in {} seconds.",
-            photos.len(),
-            videos.len(),
-            start.elapsed().as_secs()
-        );
+        self.progress_monitor.emit(ProgressMonitorInput::Complete);
 
         sender
-            .output(LibraryScanTaskOutput::Completed)
+            .output(LibraryScanTaskOutput::Completed(changed))
             .map_err(|e| format!("{:?}", e))
     }
 }
+
+/// Scan files changed since the last scan, or every file for a full scan, and add them to
+/// the library. Pictures whose files have gone are flagged as missing, and those that match
+/// an ignore pattern are trashed.
+/// Calls `found` for each file found.
+/// Returns the count of pictures newly missing or trashed.
+pub fn scan_library(
+    scanners: &[Scanner],
+    photo_repo: &mut PhotoRepository,
+    video_repo: &mut VideoRepository,
+    visual_repo: &mut VisualRepository,
+    thumbnailer: &Thumbnailer,
+    is_full_scan: bool,
+    mut found: impl FnMut(),
+) -> anyhow::Result<usize> {
+    let start = std::time::Instant::now();
+
+    // Take the time before scanning so that files changed during the scan
+    // are picked up again by the next scan.
+    let scanned_at = Utc::now();
+
+    let mut result = Vec::new();
+
+    let mut visit = |scanned_file: ScannedFile| {
+        found();
+        result.push(scanned_file);
+    };
+
+    for scanner in scanners {
+        let root = scanner.scan_base();
+
+        let last_scan_time = if is_full_scan {
+            None
+        } else {
+            visual_repo.last_scan_time(root)?
+        };
+
+        if let Some(since) = last_scan_time {
+            info!(
+                "Scanning {:?} for pictures changed since {}...",
+                root, since
+            );
+            scanner.scan_changed_since_visit(since, &mut visit);
+        } else {
+            info!("Scanning {:?} for pictures...", root);
+            scanner.scan_all_visit(&mut visit);
+        }
+    }
+
+    let (photos, videos) = result
+        .into_iter()
+        .partition_map(|scanned_file| match scanned_file {
+            f @ ScannedFile::Photo(_) => Either::Left(f),
+            f @ ScannedFile::Video(_) => Either::Right(f),
+        });
+
+    photo_repo.add_all(&photos)?;
+
+    for duplicate in video_repo.add_all(&videos)? {
+        if let Err(e) = thumbnailer.remove_thumbnails(&duplicate) {
+            error!("Failed removing thumbnails of {:?}: {:?}", duplicate, e);
+        }
+    }
+
+    let missing = photo_repo.reconcile_missing()?;
+
+    let trashed = remove_ignored(scanners, photo_repo, video_repo)?;
+
+    for scanner in scanners {
+        visual_repo.set_last_scan_time(scanner.scan_base(), scanned_at)?;
+    }
+
+    info!(
+        "Scanned {} photos and {} videos in {} seconds. {} newly missing, {} trashed.",
+        photos.len(),
+        videos.len(),
+        start.elapsed().as_secs(),
+        missing,
+        trashed
+    );
+
+    Ok(missing + trashed)
+}
+
+/// Trash pictures, and remove videos, that were added to the library before an ignore
+/// pattern matching them was added. Videos can't be trashed, so a rebuild brings them
+/// back if the pattern is removed again.
+/// Returns the count of pictures trashed.
+fn remove_ignored(
+    scanners: &[Scanner],
+    photo_repo: &mut PhotoRepository,
+    video_repo: &mut VideoRepository,
+) -> anyhow::Result<usize> {
+    let ignores: Vec<IgnorePatterns> = scanners
+        .iter()
+        .map(|scanner| scanner.ignore())
+        .filter(|ignore| !ignore.is_empty())
+        .collect();
+
+    if ignores.is_empty() {
+        return Ok(0);
+    }
+
+    let is_ignored = |path: &std::path::Path| ignores.iter().any(|ignore| ignore.is_ignored(path));
+
+    let trashed = photo_repo.trash_ignored(is_ignored)?;
+
+    for video in video_repo.all()? {
+        if is_ignored(&video.path.sandbox_path) {
+            info!("Removing ignored video {:?}", video.path);
+            video_repo.remove(video.video_id)?;
+        }
+    }
+
+    if trashed > 0 {
+        info!("Trashed {} ignored photos", trashed);
+    }
+
+    Ok(trashed)
+}
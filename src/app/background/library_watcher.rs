@@ -0,0 +1,79 @@
+// SPDX-FileCopyrightText: © 2025 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::Duration;
+use tracing::{error, info};
+
+/// How long the library must be quiet after a change before calling back.
+/// Copying lots of pictures into the library should cause one rescan, not one per picture.
+const SETTLE_TIME: Duration = Duration::from_secs(3);
+
+/// Watches the library directory for files being added, changed, or deleted.
+/// Stops watching when dropped.
+pub struct LibraryWatcher {
+    path: PathBuf,
+    _watcher: RecommendedWatcher,
+}
+
+impl LibraryWatcher {
+    /// Watch a directory and everything beneath it.
+    /// `on_change` is called on a background thread once changes have settled down.
+    pub fn watch(path: &Path, on_change: impl Fn() + Send + 'static) -> notify::Result<Self> {
+        let (tx, rx) = mpsc::channel::<()>();
+        let root = path.to_path_buf();
+
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            match event {
+                Ok(event) if Self::is_library_change(&event, &root) => {
+                    let _ = tx.send(());
+                }
+                Ok(_) => {}
+                Err(e) => error!("Failed watching library: {:?}", e),
+            }
+        })?;
+
+        watcher.watch(path, RecursiveMode::Recursive)?;
+
+        // Channel disconnects when the watcher is dropped, which ends this thread.
+        std::thread::spawn(move || {
+            while rx.recv().is_ok() {
+                loop {
+                    match rx.recv_timeout(SETTLE_TIME) {
+                        Ok(()) => continue,
+                        Err(RecvTimeoutError::Timeout) => break,
+                        Err(RecvTimeoutError::Disconnected) => return,
+                    }
+                }
+                info!("Library changed");
+                on_change();
+            }
+        });
+
+        Ok(Self {
+            path: path.into(),
+            _watcher: watcher,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Ignore reads and changes to hidden files, such as the temporary files
+    /// written by some applications before renaming into place, and to anything
+    /// in hidden directories, such as thumbnails saved beside pictures.
+    fn is_library_change(event: &Event, root: &Path) -> bool {
+        !event.kind.is_access()
+            && event.paths.iter().any(|path| {
+                !path
+                    .strip_prefix(root)
+                    .unwrap_or(path)
+                    .components()
+                    .any(|component| component.as_os_str().to_string_lossy().starts_with('.'))
+            })
+    }
+}
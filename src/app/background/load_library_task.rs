@@ -2,12 +2,14 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use crate::app::SharedState;
+use crate::app::{Privacy, PrivacyMode, PrivacyState, SettingsState, SharedState};
 use anyhow::*;
 use fotema_core::Visual;
+use fotema_core::thumbnailify::{ThumbnailLocation, Thumbnailer};
 use fotema_core::visual::Repository;
 use relm4::Worker;
 use relm4::prelude::*;
+use std::collections::HashSet;
 use std::sync::Arc;
 use tracing::{error, info};
 
@@ -19,20 +21,51 @@ pub enum LoadLibraryTaskInput {
 #[derive(Debug)]
 pub enum LoadLibraryTaskOutput {
     Done,
+
+    // Library couldn't be loaded, so the previously loaded items are kept.
+    Failed,
 }
 
 pub struct LoadLibraryTask {
     repo: Repository,
     state: SharedState,
+
+    // Visual items with trashed pictures.
+    trash_state: SharedState,
+
+    privacy_state: PrivacyState,
+
+    settings_state: SettingsState,
+
+    // Thumbnailer used by albums. Blurs thumbnails of locked private pictures, and
+    // finds thumbnails saved beside pictures.
+    thumbnailer: Thumbnailer,
 }
 
 impl Worker for LoadLibraryTask {
-    type Init = (Repository, SharedState);
+    type Init = (
+        Repository,
+        SharedState,
+        SharedState,
+        PrivacyState,
+        SettingsState,
+        Thumbnailer,
+    );
     type Input = LoadLibraryTaskInput;
     type Output = LoadLibraryTaskOutput;
 
-    fn init((repo, state): Self::Init, _sender: ComponentSender<Self>) -> Self {
-        Self { repo, state }
+    fn init(
+        (repo, state, trash_state, privacy_state, settings_state, thumbnailer): Self::Init,
+        _sender: ComponentSender<Self>,
+    ) -> Self {
+        Self {
+            repo,
+            state,
+            trash_state,
+            privacy_state,
+            settings_state,
+            thumbnailer,
+        }
     }
 
     fn update(&mut self, msg: LoadLibraryTaskInput, sender: ComponentSender<Self>) {
@@ -42,28 +75,66 @@ impl Worker for LoadLibraryTask {
 
                 if let Err(e) = result {
                     error!("Failed load library with: {}", e);
+                    let _ = sender.output(LoadLibraryTaskOutput::Failed);
+                } else {
+                    let _ = sender.output(LoadLibraryTaskOutput::Done);
                 }
-
-                let _ = sender.output(LoadLibraryTaskOutput::Done);
             }
         };
     }
 }
 
 impl LoadLibraryTask {
+    /// Shared state is only replaced once everything has been read from the database.
     fn load(&self) -> Result<()> {
-        let mut all = self
-            .repo
-            .all()?
+        let all = self.repo.all()?;
+        let trashed = self.repo.trashed()?;
+
+        // While private pictures are locked, either leave them out or blur their thumbnails.
+        let is_locked = *self.privacy_state.read() == Privacy::Locked;
+        let is_hidden = is_locked && self.settings_state.read().privacy_mode == PrivacyMode::Hide;
+
+        // Blur before albums are told about the loaded items.
+        let redacted: HashSet<String> = all
+            .iter()
+            .chain(trashed.iter())
+            .filter(|visual| is_locked && visual.is_private)
+            .map(|visual| visual.thumbnail_hash())
+            .collect();
+        self.thumbnailer.set_redacted(redacted);
+
+        // Albums only know the hash of a thumbnail, so find where each is saved first.
+        if self.thumbnailer.location() == ThumbnailLocation::Sidecar {
+            for visual in all.iter().chain(trashed.iter()) {
+                self.thumbnailer.add_thumbnail(visual.path());
+            }
+        }
+
+        let mut all = all
             .into_iter()
+            .filter(|visual| !(is_hidden && visual.is_private))
             .map(Arc::new)
             .collect::<Vec<Arc<Visual>>>();
 
         info!("Loaded {} visual items", all.len());
 
-        let mut index = self.state.write();
-        index.clear();
-        index.append(&mut all);
+        {
+            let mut index = self.state.write();
+            index.clear();
+            index.append(&mut all);
+        }
+
+        let mut trashed = trashed
+            .into_iter()
+            .filter(|visual| !(is_hidden && visual.is_private))
+            .map(Arc::new)
+            .collect::<Vec<Arc<Visual>>>();
+
+        info!("Loaded {} trashed visual items", trashed.len());
+
+        let mut trash = self.trash_state.write();
+        trash.clear();
+        trash.append(&mut trashed);
         Ok(())
     }
 }
@@ -0,0 +1,120 @@
+// SPDX-FileCopyrightText: © 2025 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use anyhow::*;
+use relm4::Reducer;
+use relm4::Worker;
+use relm4::prelude::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::result::Result::Ok;
+use std::sync::Arc;
+use tracing::{error, info};
+
+use fotema_core::photo::{self, ExportFormat};
+
+use crate::app::components::progress_monitor::{ProgressMonitor, ProgressMonitorInput, TaskName};
+
+#[derive(Debug)]
+pub enum MetadataExportTaskInput {
+    /// Write the metadata of every picture to a file.
+    /// People are those recognized with at least the face confidence threshold.
+    /// Private pictures are left out unless they are included.
+    Start(ExportFormat, f32, bool, PathBuf),
+}
+
+#[derive(Debug)]
+pub enum MetadataExportTaskOutput {
+    // Export has started.
+    Started,
+
+    // Export has completed with the number of pictures written, or None if it failed.
+    Completed(Option<usize>),
+}
+
+pub struct MetadataExportTask {
+    repo: photo::Repository,
+    progress_monitor: Arc<Reducer<ProgressMonitor>>,
+}
+
+impl MetadataExportTask {
+    fn export(
+        &self,
+        format: ExportFormat,
+        min_confidence: f32,
+        include_private: bool,
+        destination: &Path,
+        sender: &ComponentSender<Self>,
+    ) -> Result<usize> {
+        let start = std::time::Instant::now();
+
+        info!("Exporting metadata as {:?} to {:?}", format, destination);
+
+        let _ = sender.output(MetadataExportTaskOutput::Started);
+
+        // The repository reads the pictures itself, so how many there are isn't known yet.
+        self.progress_monitor
+            .emit(ProgressMonitorInput::StartIndeterminate(
+                TaskName::ExportMetadata,
+            ));
+
+        let count = fs::File::create(destination)
+            .with_context(|| format!("Failed creating {:?}", destination))
+            .and_then(|file| {
+                self.repo
+                    .export_metadata(format, min_confidence, include_private, file, || {
+                        self.progress_monitor.emit(ProgressMonitorInput::Advance);
+                    })
+            });
+
+        self.progress_monitor.emit(ProgressMonitorInput::Complete);
+
+        let count = count?;
+
+        info!(
+            "Exported metadata of {} pictures in {} seconds.",
+            count,
+            start.elapsed().as_secs()
+        );
+
+        Ok(count)
+    }
+}
+
+impl Worker for MetadataExportTask {
+    type Init = (photo::Repository, Arc<Reducer<ProgressMonitor>>);
+    type Input = MetadataExportTaskInput;
+    type Output = MetadataExportTaskOutput;
+
+    fn init((repo, progress_monitor): Self::Init, _sender: ComponentSender<Self>) -> Self {
+        MetadataExportTask {
+            repo,
+            progress_monitor,
+        }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            MetadataExportTaskInput::Start(
+                format,
+                min_confidence,
+                include_private,
+                destination,
+            ) => {
+                let count = self
+                    .export(
+                        format,
+                        min_confidence,
+                        include_private,
+                        &destination,
+                        &sender,
+                    )
+                    .inspect_err(|e| error!("Failed exporting library metadata: {:?}", e))
+                    .ok();
+
+                let _ = sender.output(MetadataExportTaskOutput::Completed(count));
+            }
+        };
+    }
+}
@@ -3,17 +3,27 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 pub mod bootstrap;
+pub mod pause;
 
+pub mod animated_thumbnail_task;
 pub mod load_library_task;
 pub mod library_scan_task;
+pub mod library_watcher;
+pub mod power_monitor;
 
+pub mod import_task;
+pub mod metadata_export_task;
+
+pub mod person_export_task;
 pub mod person_thumbnail_task;
 
+pub mod photo_burst_task;
 pub mod photo_clean_task;
 pub mod photo_detect_faces_task;
 pub mod photo_enrich_task;
 pub mod photo_extract_motion_task;
 pub mod photo_recognize_faces_task;
+pub mod photo_sidecar_task;
 pub mod photo_thumbnail_task;
 
 pub mod video_clean_task;
@@ -21,5 +31,6 @@ pub mod video_enrich_task;
 pub mod video_thumbnail_task;
 pub mod video_transcode_task;
 
+pub mod thumbnail_relocate_task;
 pub mod tidy_task;
 pub mod migrate_task;
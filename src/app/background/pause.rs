@@ -0,0 +1,57 @@
+// SPDX-FileCopyrightText: © 2025 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::sync::{Condvar, Mutex, PoisonError};
+
+/// Lets a background task be paused and resumed without restarting it.
+/// Tasks call `wait_while_paused` before each batch of work, which blocks
+/// without using any CPU until the task is resumed.
+#[derive(Debug, Default)]
+pub struct Pause {
+    is_paused: Mutex<bool>,
+    resumed: Condvar,
+}
+
+impl Pause {
+    pub fn pause(&self) {
+        *self
+            .is_paused
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = true;
+    }
+
+    pub fn resume(&self) {
+        *self
+            .is_paused
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = false;
+        self.resumed.notify_all();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        *self
+            .is_paused
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn wait_while_paused(&self) {
+        let is_paused = self
+            .is_paused
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let _is_paused = self
+            .resumed
+            .wait_while(is_paused, |is_paused| *is_paused)
+            .unwrap_or_else(PoisonError::into_inner);
+    }
+}
+
+/// Count of items a task hands to rayon at a time. Tasks must wait while paused between
+/// batches on a thread of their own, such as from `std::thread::spawn`, and not from
+/// inside rayon's global thread pool, where waiting would park a worker that other
+/// tasks need.
+pub fn batch_size() -> usize {
+    rayon::current_num_threads() * 4
+}
@@ -0,0 +1,125 @@
+// SPDX-FileCopyrightText: © 2025 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use anyhow::*;
+use relm4::Reducer;
+use relm4::Worker;
+use relm4::prelude::*;
+use std::path::{Path, PathBuf};
+use std::result::Result::Ok;
+use std::sync::Arc;
+use tracing::{error, info, warn};
+
+use fotema_core::people::PersonId;
+use fotema_core::photo::{self, ExportSummary};
+
+use crate::app::components::progress_monitor::{ProgressMonitor, ProgressMonitorInput, TaskName};
+
+#[derive(Debug)]
+pub enum PersonExportTaskInput {
+    /// Copy pictures of a person to a directory.
+    /// Pictures below the face confidence threshold are excluded, as are private pictures
+    /// unless they are included.
+    Start(PersonId, f32, bool, PathBuf),
+}
+
+#[derive(Debug)]
+pub enum PersonExportTaskOutput {
+    // Export has started.
+    Started,
+
+    // Export has completed.
+    Completed(ExportSummary),
+}
+
+pub struct PersonExportTask {
+    repo: photo::Repository,
+    progress_monitor: Arc<Reducer<ProgressMonitor>>,
+}
+
+impl PersonExportTask {
+    fn export(
+        &self,
+        person_id: PersonId,
+        min_confidence: f32,
+        include_private: bool,
+        destination: &Path,
+        sender: &ComponentSender<Self>,
+    ) -> Result<ExportSummary> {
+        let start = std::time::Instant::now();
+
+        let pictures = self
+            .repo
+            .pictures_for_person(person_id, min_confidence, include_private)?;
+        info!("Exporting {} pictures of person {} to {:?}", pictures.len(), person_id, destination);
+
+        let _ = sender.output(PersonExportTaskOutput::Started);
+
+        self.progress_monitor
+            .emit(ProgressMonitorInput::Start(TaskName::Export, pictures.len()));
+
+        let summary = photo::export::export(&pictures, destination, || {
+            self.progress_monitor.emit(ProgressMonitorInput::Advance);
+        });
+
+        self.progress_monitor.emit(ProgressMonitorInput::Complete);
+
+        let summary = summary?;
+
+        for path in &summary.missing {
+            warn!("Not exported because file is missing: {:?}", path);
+        }
+
+        for path in &summary.failed {
+            warn!("Not exported because copying failed: {:?}", path);
+        }
+
+        info!(
+            "Exported {} pictures in {} seconds.",
+            summary.exported,
+            start.elapsed().as_secs()
+        );
+
+        Ok(summary)
+    }
+}
+
+impl Worker for PersonExportTask {
+    type Init = (photo::Repository, Arc<Reducer<ProgressMonitor>>);
+    type Input = PersonExportTaskInput;
+    type Output = PersonExportTaskOutput;
+
+    fn init((repo, progress_monitor): Self::Init, _sender: ComponentSender<Self>) -> Self {
+        PersonExportTask {
+            repo,
+            progress_monitor,
+        }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            PersonExportTaskInput::Start(
+                person_id,
+                min_confidence,
+                include_private,
+                destination,
+            ) => {
+                let summary = self
+                    .export(
+                        person_id,
+                        min_confidence,
+                        include_private,
+                        &destination,
+                        &sender,
+                    )
+                    .unwrap_or_else(|e| {
+                        error!("Failed exporting person {}: {:?}", person_id, e);
+                        ExportSummary::default()
+                    });
+
+                let _ = sender.output(PersonExportTaskOutput::Completed(summary));
+            }
+        };
+    }
+}
@@ -24,7 +24,8 @@ use crate::app::components::progress_monitor::{
 
 #[derive(Debug)]
 pub enum PersonThumbnailTaskInput {
-    Start,
+    /// Generate missing person thumbnails, expanded by the head margin.
+    Start { head_margin: f32 },
 }
 
 #[derive(Debug)]
@@ -53,6 +54,7 @@ impl PersonThumbnailTask {
         stop: Arc<AtomicBool>,
         repo: fotema_core::photo::Repository,
         thumbnailer: PersonThumbnailer,
+        head_margin: f32,
         progress_monitor: Arc<Reducer<ProgressMonitor>>,
         sender: ComponentSender<Self>,
     ) -> Result<()> {
@@ -94,7 +96,7 @@ impl PersonThumbnailTask {
             .par_iter()
             .take_any_while(|_| !stop.load(Ordering::Relaxed))
             .for_each(|(path, face)| {
-                let result = block_on(async { thumbnailer.thumbnail(path, face).await });
+                let result = block_on(async { thumbnailer.thumbnail(path, face, head_margin).await });
 
                 // If we got an err, then there was a panic.
                 // If we got Ok(Err(e)) there wasn't a panic, but we still failed.
@@ -146,7 +148,7 @@ impl Worker for PersonThumbnailTask {
 
     fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
         match msg {
-            PersonThumbnailTaskInput::Start => {
+            PersonThumbnailTaskInput::Start { head_margin } => {
                 info!("Generating person thumbnails...");
                 let stop = self.stop.clone();
                 let repo = self.repo.clone();
@@ -159,6 +161,7 @@ impl Worker for PersonThumbnailTask {
                         stop,
                         repo,
                         thumbnailer,
+                        head_margin,
                         progress_monitor,
                         sender,
                     ) {
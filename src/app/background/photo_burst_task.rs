@@ -0,0 +1,134 @@
+// SPDX-FileCopyrightText: © 2025 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use anyhow::*;
+use relm4::Worker;
+use relm4::prelude::*;
+use std::collections::HashMap;
+use std::result::Result::Ok;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::{error, info};
+
+use fotema_core::burst;
+use fotema_core::photo::model::Picture;
+use fotema_core::thumbnailify::Thumbnailer;
+
+#[derive(Debug)]
+pub enum PhotoBurstTaskInput {
+    Start,
+}
+
+#[derive(Debug)]
+pub enum PhotoBurstTaskOutput {
+    // Burst grouping has started.
+    Started,
+
+    // Burst grouping has completed.
+    // usize is count of new bursts.
+    Completed(usize),
+}
+
+pub struct PhotoBurstTask {
+    // Stop flag
+    stop: Arc<AtomicBool>,
+
+    photo_repo: fotema_core::photo::Repository,
+
+    burst_repo: burst::Repository,
+
+    thumbnailer: Thumbnailer,
+}
+
+impl PhotoBurstTask {
+    fn group(&mut self, sender: &ComponentSender<Self>) -> Result<()> {
+        // Pictures already in a burst are skipped, so that a burst the user has
+        // ungrouped isn't grouped again.
+        let grouped = self.burst_repo.pictures_in_bursts()?;
+
+        let pictures: HashMap<_, Picture> = self
+            .photo_repo
+            .all()?
+            .into_iter()
+            .filter(|pic| !grouped.contains(&pic.picture_id))
+            .map(|pic| (pic.picture_id, pic))
+            .collect();
+
+        let candidates: Vec<Picture> = pictures.values().cloned().collect();
+        let bursts = burst::find_bursts(&candidates);
+
+        info!("Found {} new bursts", bursts.len());
+
+        // Short-circuit before sending progress messages to stop
+        // banner from appearing and disappearing.
+        if bursts.is_empty() {
+            let _ = sender.output(PhotoBurstTaskOutput::Completed(0));
+            return Ok(());
+        }
+
+        let _ = sender.output(PhotoBurstTaskOutput::Started);
+
+        let mut count = 0;
+        for picture_ids in bursts {
+            if self.stop.load(Ordering::Relaxed) {
+                info!("Stopping burst grouping");
+                break;
+            }
+
+            let frames: Vec<&Picture> = picture_ids
+                .iter()
+                .filter_map(|picture_id| pictures.get(picture_id))
+                .collect();
+
+            // Fall back to the first frame if none have been thumbnailed.
+            let cover = burst::sharpest(&self.thumbnailer, &frames).unwrap_or(picture_ids[0]);
+
+            if self.burst_repo.add_burst(&picture_ids, &cover)?.is_some() {
+                count += 1;
+            }
+        }
+
+        info!("Grouped {} bursts", count);
+
+        let _ = sender.output(PhotoBurstTaskOutput::Completed(count));
+
+        Ok(())
+    }
+}
+
+impl Worker for PhotoBurstTask {
+    type Init = (
+        Arc<AtomicBool>,
+        fotema_core::photo::Repository,
+        burst::Repository,
+        Thumbnailer,
+    );
+    type Input = PhotoBurstTaskInput;
+    type Output = PhotoBurstTaskOutput;
+
+    fn init(
+        (stop, photo_repo, burst_repo, thumbnailer): Self::Init,
+        _sender: ComponentSender<Self>,
+    ) -> Self {
+        PhotoBurstTask {
+            stop,
+            photo_repo,
+            burst_repo,
+            thumbnailer,
+        }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            PhotoBurstTaskInput::Start => {
+                info!("Grouping bursts...");
+
+                if let Err(e) = self.group(&sender) {
+                    error!("Failed to group bursts: {}", e);
+                    let _ = sender.output(PhotoBurstTaskOutput::Completed(0));
+                }
+            }
+        };
+    }
+}
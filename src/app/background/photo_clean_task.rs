@@ -10,7 +10,9 @@ use relm4::prelude::*;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 
-use tracing::{debug, error, info};
+use tracing::{error, info};
+
+use fotema_core::Availability;
 
 #[derive(Debug)]
 pub enum PhotoCleanTaskInput {
@@ -38,12 +40,18 @@ impl PhotoCleanTask {
     fn cleanup(&mut self, sender: &ComponentSender<Self>) -> Result<()> {
         let start = std::time::Instant::now();
 
-        // Scrub pics from database if they no longer exist on the file system.
+        // Flag pics as missing if they no longer exist on the file system.
+        // Missing pics are kept, so the user can locate them without losing
+        // their favorite, tags, and faces.
         let pics: Vec<fotema_core::photo::model::Picture> = self.repo.all()?;
 
         info!("Found {} photos as candidates for cleaning", pics.len());
 
-        let count = pics.par_iter().filter(|p| !p.path.exists()).count();
+        let is_missing = |pic: &fotema_core::photo::model::Picture| {
+            pic.path.availability() == Availability::Missing
+        };
+
+        let count = pics.par_iter().filter(|pic| is_missing(pic)).count();
 
         // Short-circuit before sending progress messages to stop
         // banner from appearing and disappearing.
@@ -59,25 +67,12 @@ impl PhotoCleanTask {
         pics.par_iter()
             .take_any_while(|_| !self.stop.load(Ordering::Relaxed))
             .for_each(|pic| {
-                if !pic.path.exists() {
+                if is_missing(pic) {
                     let mut repo = self.repo.clone();
-                    if let Ok(paths) = repo.find_files_to_cleanup(pic.picture_id) {
-                        for path in paths {
-                            if !path.exists() {
-                                continue;
-                            }
-                            debug!("Deleting {:?}", path);
-                            if let Err(e) = std::fs::remove_file(&path) {
-                                error!("Failed deleting {:?} with {}", path, e);
-                            }
-                        }
-                    }
-
-                    let result = repo.remove(pic.picture_id);
-                    if let Err(e) = result {
-                        error!("Failed remove {}: {:?}", pic.picture_id, e);
+                    if let Err(e) = repo.mark_missing(&pic.picture_id) {
+                        error!("Failed marking {} missing: {:?}", pic.picture_id, e);
                     } else {
-                        info!("Removed {}", pic.picture_id);
+                        info!("Marked {} missing", pic.picture_id);
                     }
                 }
             });
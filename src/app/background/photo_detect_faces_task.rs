@@ -8,15 +8,17 @@ use relm4::Reducer;
 use relm4::Worker;
 use relm4::prelude::*;
 
+use std::panic::{self, AssertUnwindSafe};
 use std::path::PathBuf;
 use std::result::Result::Ok;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 
 use futures::executor::block_on;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
-use fotema_core::machine_learning::face_extractor::FaceExtractor;
+use fotema_core::Availability;
+use fotema_core::machine_learning::face_extractor::{FaceDetectionModel, FaceExtractor};
 use fotema_core::people;
 use fotema_core::people::FaceDetectionCandidate;
 use fotema_core::photo;
@@ -33,6 +35,7 @@ struct FaceDetectorPoolManager{
     /// Base directory for storing photo faces
     faces_base_dir: PathBuf,
     thumbnailer: Thumbnailer,
+    detection_model: FaceDetectionModel,
 }
 
 impl managed::Manager for FaceDetectorPoolManager {
@@ -40,7 +43,11 @@ impl managed::Manager for FaceDetectorPoolManager {
     type Error = Error;
 
     async fn create(&self) -> Result<FaceExtractor, Error> {
-        FaceExtractor::build(&self.faces_base_dir, self.thumbnailer.clone())
+        FaceExtractor::build(
+            &self.faces_base_dir,
+            self.thumbnailer.clone(),
+            self.detection_model,
+        )
     }
 
     async fn recycle(&self, _: &mut FaceExtractor, _: &managed::Metrics) -> managed::RecycleResult<Error> {
@@ -53,8 +60,10 @@ type FaceDetectorPool = managed::Pool<FaceDetectorPoolManager>;
 
 #[derive(Debug)]
 pub enum PhotoDetectFacesTaskInput {
-    DetectForAllPictures,
-    DetectForOnePicture(PictureId),
+    /// Detect faces in pictures not yet scanned.
+    /// Face thumbnails are expanded by the head margin.
+    DetectForAllPictures { head_margin: f32 },
+    DetectForOnePicture { picture_id: PictureId, head_margin: f32 },
 }
 
 #[derive(Debug)]
@@ -75,6 +84,9 @@ pub struct PhotoDetectFacesTask {
     faces_base_dir: PathBuf,
     thumbnailer: Thumbnailer,
 
+    /// Face detectors to run.
+    detection_model: FaceDetectionModel,
+
     photo_repo: photo::Repository,
     people_repo: people::Repository,
 
@@ -82,18 +94,23 @@ pub struct PhotoDetectFacesTask {
 }
 
 impl PhotoDetectFacesTask {
-    fn detect_for_one(&self, sender: ComponentSender<Self>, picture_id: PictureId) -> Result<()> {
+    fn detect_for_one(
+        &self,
+        sender: ComponentSender<Self>,
+        picture_id: PictureId,
+        head_margin: f32,
+    ) -> Result<()> {
         self.people_repo.delete_faces(picture_id)?;
         let result = self.photo_repo.get_face_detection_candidate(&picture_id)?;
         if let Some(candidate) = result {
             let unprocessed = vec![candidate];
-            self.detect(sender, unprocessed)
+            self.detect(sender, unprocessed, head_margin)
         } else {
             Err(anyhow!("No file to scan"))
         }
     }
 
-    fn detect_for_all(&self, sender: ComponentSender<Self>) -> Result<()> {
+    fn detect_for_all(&self, sender: ComponentSender<Self>, head_margin: f32) -> Result<()> {
         let unprocessed: Vec<FaceDetectionCandidate> = self
             .photo_repo
             .find_face_detection_candidates()?
@@ -101,13 +118,14 @@ impl PhotoDetectFacesTask {
             .filter(|candidate| candidate.sandbox_path.exists())
             .collect();
 
-        self.detect(sender, unprocessed)
+        self.detect(sender, unprocessed, head_margin)
     }
 
     fn detect(
         &self,
         sender: ComponentSender<Self>,
         unprocessed: Vec<FaceDetectionCandidate>,
+        head_margin: f32,
     ) -> Result<()> {
         let start = std::time::Instant::now();
 
@@ -137,11 +155,16 @@ impl PhotoDetectFacesTask {
         // We must do this before using the object pool and parallel processing, otherwise
         // multiple threads will try to download the same model.
         // FIXME add a method to the face detection library to download models.
-        let _ = FaceExtractor::build(&self.faces_base_dir, self.thumbnailer.clone());
+        let _ = FaceExtractor::build(
+            &self.faces_base_dir,
+            self.thumbnailer.clone(),
+            self.detection_model,
+        );
 
         let detector_pool_manager = FaceDetectorPoolManager {
             faces_base_dir: self.faces_base_dir.clone(),
             thumbnailer: self.thumbnailer.clone(),
+            detection_model: self.detection_model,
         };
         let detector_pool = FaceDetectorPool::builder(detector_pool_manager).build()?;
 
@@ -153,19 +176,55 @@ impl PhotoDetectFacesTask {
 
                 // Careful! panic::catch_unwind returns Ok(Err) if the evaluated expression returns
                 // an error but doesn't panic.
-                let result = block_on(async {
-                    // FIXME unwrap
-                    let mut detector = detector_pool.get().await.unwrap();
-                    detector.extract_faces(&candidate).await
+                // The pool and candidate aren't used again after a panic, so asserting unwind
+                // safety is fine. A detector that panicked is recycled, but is stateless
+                // between pictures.
+                let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                    block_on(async {
+                        let mut detector = detector_pool
+                            .get()
+                            .await
+                            .map_err(|e| anyhow!("Failed getting face detector: {}", e))?;
+                        detector.extract_faces(candidate, head_margin).await
                     })
-                    .and_then(|faces| repo.clone().add_face_scans(&candidate.picture_id, &faces));
-
-                if result.is_err() {
-                    error!(
-                        "Failed detecting faces: Photo path: {:?}. Error: {:?}",
-                        candidate.sandbox_path, result
-                    );
-                    let _ = repo.mark_face_scan_broken(&candidate.picture_id);
+                }));
+
+                // If we got an err, then there was a panic.
+                // If we got Ok(Err(e)) there wasn't a panic, but we still failed.
+                match result {
+                    Ok(Ok(faces)) => {
+                        if let Err(e) =
+                            repo.add_face_scans(&candidate.picture_id, self.detection_model, &faces)
+                        {
+                            error!(
+                                "Failed adding faces: Photo path: {:?}. Error: {:?}",
+                                candidate.sandbox_path, e
+                            );
+                        }
+                    }
+                    Ok(Err(e)) if candidate.availability() == Availability::Unavailable => {
+                        // Probably a network share hiccup, so try again on the next refresh.
+                        warn!(
+                            "Failed detecting faces: Photo unavailable: {:?}. Error: {:?}",
+                            candidate.sandbox_path, e
+                        );
+                    }
+                    Ok(Err(e)) => {
+                        error!(
+                            "Failed detecting faces: Photo path: {:?}. Error: {:?}",
+                            candidate.sandbox_path, e
+                        );
+                        let _ =
+                            repo.mark_face_scan_broken(&candidate.picture_id, self.detection_model);
+                    }
+                    Err(_) => {
+                        error!(
+                            "Panicked detecting faces: Photo path: {:?}",
+                            candidate.sandbox_path
+                        );
+                        let _ =
+                            repo.mark_face_scan_broken(&candidate.picture_id, self.detection_model);
+                    }
                 }
 
                 self.progress_monitor.emit(ProgressMonitorInput::Advance);
@@ -190,6 +249,7 @@ impl Worker for PhotoDetectFacesTask {
         Arc<AtomicBool>,
         PathBuf,
         Thumbnailer,
+        FaceDetectionModel,
         photo::Repository,
         people::Repository,
         Arc<Reducer<ProgressMonitor>>,
@@ -198,13 +258,22 @@ impl Worker for PhotoDetectFacesTask {
     type Output = PhotoDetectFacesTaskOutput;
 
     fn init(
-        (stop, faces_base_dir, thumbnailer, photo_repo, people_repo, progress_monitor): Self::Init,
+        (
+            stop,
+            faces_base_dir,
+            thumbnailer,
+            detection_model,
+            photo_repo,
+            people_repo,
+            progress_monitor,
+        ): Self::Init,
         _sender: ComponentSender<Self>,
     ) -> Self {
         PhotoDetectFacesTask {
             stop,
             faces_base_dir,
             thumbnailer,
+            detection_model,
             photo_repo,
             people_repo,
             progress_monitor,
@@ -213,25 +282,28 @@ impl Worker for PhotoDetectFacesTask {
 
     fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
         match msg {
-            PhotoDetectFacesTaskInput::DetectForAllPictures => {
+            PhotoDetectFacesTaskInput::DetectForAllPictures { head_margin } => {
                 info!("Extracting faces for all pictures...");
                 let this = self.clone();
 
                 // Avoid runtime panic from calling block_on
                 rayon::spawn(move || {
-                    if let Err(e) = this.detect_for_all(sender) {
+                    if let Err(e) = this.detect_for_all(sender, head_margin) {
                         error!("Failed to extract photo faces: {}", e);
                     }
                 });
             }
 
-            PhotoDetectFacesTaskInput::DetectForOnePicture(picture_id) => {
+            PhotoDetectFacesTaskInput::DetectForOnePicture {
+                picture_id,
+                head_margin,
+            } => {
                 info!("Extracting faces for one picture...");
                 let this = self.clone();
 
                 // Avoid runtime panic from calling block_on
                 rayon::spawn(move || {
-                    if let Err(e) = this.detect_for_one(sender.clone(), picture_id) {
+                    if let Err(e) = this.detect_for_one(sender.clone(), picture_id, head_margin) {
                         error!("Failed to extract photo faces: {}", e);
                         let _ = sender.output(PhotoDetectFacesTaskOutput::Completed);
                     }
@@ -3,7 +3,9 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use anyhow::*;
-use fotema_core::photo::metadata;
+use fotema_core::Availability;
+use fotema_core::photo::content_hash::{self, FileStamp};
+use fotema_core::photo::metadata::{self, NaiveTimeZone};
 use rayon::prelude::*;
 use relm4::Worker;
 use relm4::prelude::*;
@@ -15,7 +17,9 @@ use tracing::{error, info};
 
 #[derive(Debug)]
 pub enum PhotoEnrichTaskInput {
-    Start,
+    /// Extract metadata from pictures not yet enriched.
+    /// Timestamps without a UTC offset are taken to be in the time zone.
+    Start { time_zone: NaiveTimeZone },
 }
 
 #[derive(Debug)]
@@ -39,10 +43,13 @@ impl PhotoEnrichTask {
     fn enrich(
         stop: Arc<AtomicBool>,
         mut repo: fotema_core::photo::Repository,
+        time_zone: NaiveTimeZone,
         sender: &ComponentSender<PhotoEnrichTask>,
     ) -> Result<()> {
         let start = std::time::Instant::now();
 
+        Self::hash(stop.clone(), repo.clone())?;
+
         let unprocessed = repo.find_need_metadata_update()?;
 
         let count = unprocessed.len();
@@ -57,11 +64,21 @@ impl PhotoEnrichTask {
 
         let _ = sender.output(PhotoEnrichTaskOutput::Started);
 
+        // Pictures on a network share that can't be reached are left for the next
+        // refresh, rather than blocking on each one in turn.
+        let (unprocessed, unavailable): (Vec<_>, Vec<_>) = unprocessed
+            .into_par_iter()
+            .partition(|pic| pic.path.availability() != Availability::Unavailable);
+
+        for pic in &unavailable {
+            repo.set_availability(&pic.picture_id, Availability::Unavailable)?;
+        }
+
         let metadatas = unprocessed
             .par_iter()
             .take_any_while(|_| !stop.load(Ordering::Relaxed))
             .flat_map(|pic| {
-                let result = metadata::from_path(&pic.sandbox_path());
+                let result = metadata::from_path(pic.sandbox_path(), time_zone);
                 result.map(|m| (pic.picture_id, m))
             })
             .collect();
@@ -82,6 +99,38 @@ impl PhotoEnrichTask {
     }
 }
 
+impl PhotoEnrichTask {
+    /// Hash file contents for finding duplicates. Contents are only hashed again
+    /// if the file size or modification timestamp changed since the last hash.
+    fn hash(stop: Arc<AtomicBool>, mut repo: fotema_core::photo::Repository) -> Result<()> {
+        let start = std::time::Instant::now();
+
+        let candidates = repo.find_content_hash_stamps()?;
+
+        let hashes: Vec<_> = candidates
+            .par_iter()
+            .take_any_while(|_| !stop.load(Ordering::Relaxed))
+            .filter(|(pic, stamp)| {
+                stamp.is_none() || FileStamp::from_path(pic.sandbox_path()).ok() != *stamp
+            })
+            .flat_map(|(pic, _)| {
+                content_hash::from_path(pic.sandbox_path()).map(|hash| (pic.picture_id, hash))
+            })
+            .collect();
+
+        let count = hashes.len();
+        repo.set_content_hashes(hashes)?;
+
+        info!(
+            "Hashed {} photos in {} seconds.",
+            count,
+            start.elapsed().as_secs()
+        );
+
+        Ok(())
+    }
+}
+
 impl Worker for PhotoEnrichTask {
     type Init = (Arc<AtomicBool>, fotema_core::photo::Repository);
     type Input = PhotoEnrichTaskInput;
@@ -93,14 +142,14 @@ impl Worker for PhotoEnrichTask {
 
     fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
         match msg {
-            PhotoEnrichTaskInput::Start => {
+            PhotoEnrichTaskInput::Start { time_zone } => {
                 info!("Enriching photos...");
                 let repo = self.repo.clone();
                 let stop = self.stop.clone();
 
                 // Avoid runtime panic from calling block_on
                 rayon::spawn(move || {
-                    if let Err(e) = PhotoEnrichTask::enrich(stop, repo, &sender) {
+                    if let Err(e) = PhotoEnrichTask::enrich(stop, repo, time_zone, &sender) {
                         error!("Failed to update previews: {}", e);
                     }
                 });
@@ -91,7 +91,8 @@ impl PhotoExtractMotionTask {
                             "Failed extracting motion photo: {:?}: Photo path: {:?}",
                             e, photo.path
                         );
-                        repo.clone().mark_broken(&photo.picture_id)
+                        repo.clone()
+                            .mark_broken(&photo.picture_id, &e.root_cause().to_string())
                     }
                 };
 
@@ -47,9 +47,54 @@ pub struct PhotoRecognizeFacesTask {
 }
 
 impl PhotoRecognizeFacesTask {
+    /// Computes embeddings for unknown faces so that they can be clustered
+    /// into suggested people.
+    fn embed(&self, sender: &ComponentSender<Self>) -> Result<()> {
+        let unprocessed: Vec<DetectedFace> = self.repo.find_faces_without_embedding()?;
+
+        if unprocessed.is_empty() {
+            return Ok(());
+        }
+
+        info!("Computing embeddings for {} unknown faces", unprocessed.len());
+
+        let _ = sender.output(PhotoRecognizeFacesTaskOutput::Started);
+        self.progress_monitor.emit(ProgressMonitorInput::Start(
+            TaskName::RecognizeFaces,
+            unprocessed.len(),
+        ));
+
+        let recognizer = FaceRecognizer::build(&self.cache_dir, vec![])?;
+
+        unprocessed
+            .into_par_iter()
+            .take_any_while(|_| !self.stop.load(Ordering::Relaxed))
+            .for_each(|face| {
+                match recognizer.embedding(&face) {
+                    Ok(embedding) => {
+                        let mut repo = self.repo.clone();
+                        if let Err(e) = repo.set_face_embedding(face.face_id, &embedding) {
+                            error!("Failed saving embedding for face {}: {:?}", face.face_id, e);
+                        }
+                    }
+                    Err(e) => {
+                        error!("Failed computing embedding for face {}: {:?}", face.face_id, e);
+                    }
+                }
+
+                self.progress_monitor.emit(ProgressMonitorInput::Advance);
+            });
+
+        self.progress_monitor.emit(ProgressMonitorInput::Complete);
+
+        Ok(())
+    }
+
     fn recognize(&self, sender: ComponentSender<Self>) -> Result<()> {
         let start = std::time::Instant::now();
 
+        self.embed(&sender)?;
+
         let people: Vec<PersonForRecognition> = self
             .repo
             .find_people_for_recognition()?
@@ -0,0 +1,104 @@
+// SPDX-FileCopyrightText: © 2025 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use relm4::Reducer;
+use relm4::Worker;
+use relm4::prelude::*;
+use std::sync::Arc;
+use tracing::{error, info};
+
+use fotema_core::photo;
+use fotema_core::photo::xmp::SidecarPolicy;
+
+use crate::app::components::progress_monitor::{ProgressMonitor, ProgressMonitorInput, TaskName};
+
+#[derive(Debug)]
+pub enum PhotoSidecarTaskInput {
+    /// Write favorites and tags to XMP sidecar files.
+    Write,
+
+    /// Import favorites and tags from XMP sidecar files changed by other photo managers.
+    Import(SidecarPolicy),
+}
+
+#[derive(Debug)]
+pub enum PhotoSidecarTaskOutput {
+    // Writing sidecars has started.
+    WriteStarted,
+
+    // Writing sidecars has completed.
+    WriteCompleted,
+
+    // Importing sidecars has started.
+    ImportStarted,
+
+    // Importing sidecars has completed.
+    // usize is count of pictures updated.
+    ImportCompleted(usize),
+}
+
+pub struct PhotoSidecarTask {
+    repo: photo::Repository,
+    progress_monitor: Arc<Reducer<ProgressMonitor>>,
+}
+
+impl Worker for PhotoSidecarTask {
+    type Init = (photo::Repository, Arc<Reducer<ProgressMonitor>>);
+    type Input = PhotoSidecarTaskInput;
+    type Output = PhotoSidecarTaskOutput;
+
+    fn init((repo, progress_monitor): Self::Init, _sender: ComponentSender<Self>) -> Self {
+        PhotoSidecarTask {
+            repo,
+            progress_monitor,
+        }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        // The repository reads the pictures itself, so how many there are isn't known up front.
+        match msg {
+            PhotoSidecarTaskInput::Write => {
+                info!("Writing XMP sidecars...");
+                let _ = sender.output(PhotoSidecarTaskOutput::WriteStarted);
+
+                self.progress_monitor
+                    .emit(ProgressMonitorInput::StartIndeterminate(
+                        TaskName::WriteSidecars,
+                    ));
+
+                let _ = self
+                    .repo
+                    .write_sidecars(|| self.progress_monitor.emit(ProgressMonitorInput::Advance))
+                    .inspect(|count| info!("Wrote {} XMP sidecars", count))
+                    .inspect_err(|e| error!("Failed writing XMP sidecars: {:?}", e));
+
+                self.progress_monitor.emit(ProgressMonitorInput::Complete);
+
+                let _ = sender.output(PhotoSidecarTaskOutput::WriteCompleted);
+            }
+            PhotoSidecarTaskInput::Import(policy) => {
+                info!("Importing XMP sidecars with policy {:?}...", policy);
+                let _ = sender.output(PhotoSidecarTaskOutput::ImportStarted);
+
+                self.progress_monitor
+                    .emit(ProgressMonitorInput::StartIndeterminate(
+                        TaskName::ImportSidecars,
+                    ));
+
+                let count = self
+                    .repo
+                    .import_sidecars(policy, || {
+                        self.progress_monitor.emit(ProgressMonitorInput::Advance)
+                    })
+                    .inspect(|count| info!("Imported {} XMP sidecars", count))
+                    .inspect_err(|e| error!("Failed importing XMP sidecars: {:?}", e))
+                    .unwrap_or(0);
+
+                self.progress_monitor.emit(ProgressMonitorInput::Complete);
+
+                let _ = sender.output(PhotoSidecarTaskOutput::ImportCompleted(count));
+            }
+        };
+    }
+}
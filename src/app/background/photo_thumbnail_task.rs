@@ -12,21 +12,37 @@ use std::path::{Path, PathBuf};
 use std::result::Result::Ok;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
-use tracing::{error, info};
+use std::sync::mpsc;
+use tracing::{error, info, warn};
 
 use std::panic;
 
-use fotema_core::thumbnailify;
-use fotema_core::thumbnailify::ThumbnailSize;
-use fotema_core::photo::thumbnailer::PhotoThumbnailer;
+use fotema_core::Availability;
+use fotema_core::photo::PictureId;
+use fotema_core::photo::thumbnailer::{PhotoThumbnailer, ThumbnailOrder};
+use fotema_core::thumbnailify::ThumbnailError;
 
+use super::pause::{self, Pause};
 use crate::app::components::progress_monitor::{
     ThumbnailType, ProgressMonitor, ProgressMonitorInput, TaskName,
 };
 
+/// Number of blurhashes to write to the database in one transaction.
+const BATCH_SIZE: usize = 100;
+
 #[derive(Debug)]
 pub enum PhotoThumbnailTaskInput {
-    Start,
+    /// Generate thumbnails for pictures that need them, in the given order.
+    Start { order: ThumbnailOrder },
+
+    /// Delete and regenerate the thumbnail of one picture, such as one that looks wrong.
+    /// Runs straight away, rather than waiting for other background tasks.
+    Regenerate(PictureId),
+
+    /// Count the pictures that need thumbnails, without generating any, so the user
+    /// knows how long thumbnailing might take before starting it.
+    /// Truncated or corrupt thumbnails are deleted first.
+    Count,
 }
 
 #[derive(Debug)]
@@ -36,12 +52,28 @@ pub enum PhotoThumbnailTaskOutput {
 
     // Thumbnail generation has completed
     Completed(usize),
+
+    // Thumbnail generation was abandoned because thumbnails can't be saved to the
+    // directory. Generating them would fail and mark every photo as broken.
+    CacheUnwritable(PathBuf),
+
+    // Thumbnail of a single picture has been regenerated, or failed to regenerate.
+    Regenerated(PictureId),
+
+    // Number of pictures that need thumbnails.
+    Counted(usize),
 }
 
 pub struct PhotoThumbnailTask {
     // Stop flag
     stop: Arc<AtomicBool>,
 
+    // Pause flag
+    pause: Arc<Pause>,
+
+    // Pause flag for when on battery power, or when the computer is in use.
+    power_pause: Arc<Pause>,
+
     thumbnails_path: PathBuf,
     thumbnailer: fotema_core::photo::PhotoThumbnailer,
 
@@ -54,81 +86,86 @@ pub struct PhotoThumbnailTask {
 impl PhotoThumbnailTask {
     fn enrich(
         stop: Arc<AtomicBool>,
-        repo: fotema_core::photo::Repository,
+        pause: Arc<Pause>,
+        power_pause: Arc<Pause>,
+        mut repo: fotema_core::photo::Repository,
         thumbnails_path: &Path,
         thumbnailer: PhotoThumbnailer,
+        order: ThumbnailOrder,
         progress_monitor: Arc<Reducer<ProgressMonitor>>,
         sender: ComponentSender<Self>,
     ) -> Result<()> {
         let start = std::time::Instant::now();
 
-        let mut unprocessed: Vec<fotema_core::photo::model::Picture> = repo
-            .all()?
-            .into_iter()
-            .filter(|pic| pic.path.exists())
-            .filter(|pic| {
-                let thumb_hash = pic.thumbnail_hash();
-                let large_path = thumbnailify::get_thumbnail_hash_output(
-                    thumbnails_path,
-                    &thumb_hash,
-                    ThumbnailSize::XLarge,
-                );
-                !large_path.exists()
-            })
-            .collect();
+        // Pictures thumbnailed before perceptual hashes existed, or since the last run.
+        PhotoThumbnailTask::hash_thumbnails(&stop, &mut repo, &thumbnailer)?;
 
-        // should be ascending time order from database, so reverse to process newest items first
-        unprocessed.reverse();
-
-        let count = unprocessed.len();
-        info!("Found {} photos to generate thumbnails for", count);
+        let (mut unprocessed, mut is_resumed) = PhotoThumbnailTask::pending(&repo, &thumbnailer)?;
+        if is_resumed {
+            info!(
+                "Resuming thumbnail generation for {} queued photos",
+                unprocessed.len()
+            );
+        } else {
+            PhotoThumbnailTask::queue(&mut repo, &unprocessed)?;
+        }
 
         // Short-circuit before sending progress messages to stop
         // banner from appearing and disappearing.
-        if count == 0 {
-            let _ = sender.output(PhotoThumbnailTaskOutput::Completed(count));
+        if unprocessed.is_empty() {
+            let _ = sender.output(PhotoThumbnailTaskOutput::Completed(0));
+            return Ok(());
+        }
+
+        if let Err(e) = thumbnailer.check_writable() {
+            error!("Not generating photo thumbnails: {}", e);
+            let path = match e {
+                ThumbnailError::Unwritable { path, .. } => path,
+                _ => thumbnails_path.to_path_buf(),
+            };
+            let _ = sender.output(PhotoThumbnailTaskOutput::CacheUnwritable(path));
             return Ok(());
         }
 
         let _ = sender.output(PhotoThumbnailTaskOutput::Started);
 
-        progress_monitor.emit(ProgressMonitorInput::Start(
-            TaskName::Thumbnail(ThumbnailType::Photo),
-            count,
-        ));
+        let mut count = 0;
+        loop {
+            count += unprocessed.len();
 
-        // One thread per CPU core... makes my laptop sluggish and hot... also likes memory.
-        // Might need to consider constraining number of CPUs to use less memory or to
-        // keep the computer more response while thumbnail generation is going on.
-        unprocessed
-            .par_iter()
-            .take_any_while(|_| !stop.load(Ordering::Relaxed))
-            .for_each(|pic| {
-                // Careful! panic::catch_unwind returns Ok(Err) if the evaluated expression returns
-                // an error but doesn't panic.
-                let result = panic::catch_unwind(|| {
-                    block_on(async { thumbnailer.thumbnail(&pic.path).await })
+            progress_monitor.emit(ProgressMonitorInput::Start(
+                TaskName::Thumbnail(ThumbnailType::Photo),
+                unprocessed.len(),
+            ));
+
+            PhotoThumbnailTask::thumbnail_all(
+                unprocessed,
+                order,
+                &stop,
+                &pause,
+                &power_pause,
+                &repo,
+                &thumbnailer,
+                &progress_monitor,
+            );
+
+            // Photos added since the queue was saved still need thumbnails, so walk
+            // the library once the queue is done.
+            if !is_resumed || stop.load(Ordering::Relaxed) {
+                break;
+            }
+            is_resumed = false;
+
+            unprocessed = PhotoThumbnailTask::find_unprocessed(&mut repo, &thumbnailer)
+                .unwrap_or_else(|e| {
+                    error!("Failed to find photos to generate thumbnails for: {:?}", e);
+                    Vec::new()
                 });
 
-                // If we got an err, then there was a panic.
-                // If we got Ok(Err(e)) there wasn't a panic, but we still failed.
-                if let Ok(Err(e)) = result {
-                    error!(
-                        "Failed generate or add thumbnail: {:?}: Photo path: {:?}",
-                        e.root_cause(),
-                        pic.path
-                    );
-                    let _ = repo.clone().mark_broken(&pic.picture_id);
-                } else if result.is_err() {
-                    error!(
-                        "Panicked generate or add thumbnail: Photo path: {:?}",
-                        pic.path
-                    );
-                    let _ = repo.clone().mark_broken(&pic.picture_id);
-                }
-
-                progress_monitor.emit(ProgressMonitorInput::Advance);
-            });
+            if unprocessed.is_empty() {
+                break;
+            }
+        }
 
         info!(
             "Generated {} photo thumbnails in {} seconds.",
@@ -136,17 +173,273 @@ impl PhotoThumbnailTask {
             start.elapsed().as_secs()
         );
 
+        PhotoThumbnailTask::hash_thumbnails(&stop, &mut repo, &thumbnailer)?;
+
         progress_monitor.emit(ProgressMonitorInput::Complete);
 
         let _ = sender.output(PhotoThumbnailTaskOutput::Completed(count));
 
         Ok(())
     }
+
+    /// Photos that need thumbnails, and whether they were left over from an earlier run.
+    /// Photos left over from a run cut short, such as by closing Fotema, are resumed
+    /// so we know how many remain without walking the library. Otherwise the library
+    /// is walked for photos whose thumbnails are missing or out of date.
+    fn pending(
+        repo: &fotema_core::photo::Repository,
+        thumbnailer: &PhotoThumbnailer,
+    ) -> Result<(Vec<fotema_core::photo::model::Picture>, bool)> {
+        let queued = repo.queued_thumbnails()?;
+        if !queued.is_empty() {
+            return Ok((queued, true));
+        }
+
+        let unprocessed = repo.needs_rethumbnail(thumbnailer.thumbnailer())?;
+        info!(
+            "Found {} photos to generate thumbnails for",
+            unprocessed.len()
+        );
+        Ok((unprocessed, false))
+    }
+
+    /// Walks the library for photos that need thumbnails, and saves them as the
+    /// thumbnail queue so that a later run can resume without walking it again.
+    fn find_unprocessed(
+        repo: &mut fotema_core::photo::Repository,
+        thumbnailer: &PhotoThumbnailer,
+    ) -> Result<Vec<fotema_core::photo::model::Picture>> {
+        let unprocessed = repo.needs_rethumbnail(thumbnailer.thumbnailer())?;
+        info!(
+            "Found {} photos to generate thumbnails for",
+            unprocessed.len()
+        );
+
+        PhotoThumbnailTask::queue(repo, &unprocessed)?;
+
+        Ok(unprocessed)
+    }
+
+    /// Saves photos as the thumbnail queue, so that a later run can resume with them.
+    fn queue(
+        repo: &mut fotema_core::photo::Repository,
+        unprocessed: &[fotema_core::photo::model::Picture],
+    ) -> Result<()> {
+        let picture_ids: Vec<PictureId> = unprocessed.iter().map(|pic| pic.picture_id).collect();
+        repo.queue_thumbnails(&picture_ids)
+    }
+
+    fn thumbnail_all(
+        mut unprocessed: Vec<fotema_core::photo::model::Picture>,
+        order: ThumbnailOrder,
+        stop: &AtomicBool,
+        pause: &Pause,
+        power_pause: &Pause,
+        repo: &fotema_core::photo::Repository,
+        thumbnailer: &PhotoThumbnailer,
+        progress_monitor: &Reducer<ProgressMonitor>,
+    ) {
+        // Only changes which pictures get thumbnails first. Rayon splits the work into
+        // chunks, so the order is approximate.
+        order.sort(&mut unprocessed);
+
+        // Blurhashes are written by a single consumer in batches to avoid a tiny write
+        // transaction per thumbnail contending on the database.
+        let (blurhash_tx, blurhash_rx) = mpsc::channel::<(PictureId, String)>();
+        let writer = {
+            let repo = repo.clone();
+            std::thread::spawn(move || PhotoThumbnailTask::write_blurhashes(repo, blurhash_rx))
+        };
+
+        // One thread per CPU core... makes my laptop sluggish and hot... also likes memory.
+        // Memory is kept in check by the thumbnailer's decode budget, which makes threads
+        // decoding large pictures wait for each other.
+        for batch in unprocessed.chunks(pause::batch_size()) {
+            pause.wait_while_paused();
+            power_pause.wait_while_paused();
+            if stop.load(Ordering::Relaxed) {
+                break;
+            }
+
+            batch
+                .par_iter()
+                .take_any_while(|_| !stop.load(Ordering::Relaxed))
+                .for_each(|pic| {
+                    // Careful! panic::catch_unwind returns Ok(Err) if the evaluated expression returns
+                    // an error but doesn't panic.
+                    // Stored when the picture was hashed, rather than hashing it again.
+                    let content_hash = repo.current_content_hash(&pic.picture_id).ok().flatten();
+
+                    let result = panic::catch_unwind(|| {
+                        block_on(async {
+                            thumbnailer
+                                .thumbnail(&pic.path, content_hash.as_deref())
+                                .await
+                        })
+                    });
+
+                    // If we got an err, then there was a panic.
+                    // If we got Ok(Err(e)) there wasn't a panic, but we still failed.
+                    match result {
+                        Ok(Ok(blurhash)) => {
+                            let _ = blurhash_tx.send((pic.picture_id, blurhash));
+                        }
+                        Ok(Err(e)) if pic.path.availability() == Availability::Unavailable => {
+                            // Probably a network share hiccup, so try again on the next refresh.
+                            warn!(
+                                "Failed generate or add thumbnail: {:?}: Photo unavailable: {:?}",
+                                e.root_cause(),
+                                pic.path
+                            );
+                            let mut repo = repo.clone();
+                            let _ =
+                                repo.set_availability(&pic.picture_id, Availability::Unavailable);
+                            let _ = repo.dequeue_thumbnail(&pic.picture_id);
+                        }
+                        Ok(Err(e)) => {
+                            error!(
+                                "Failed generate or add thumbnail: {:?}: Photo path: {:?}",
+                                e.root_cause(),
+                                pic.path
+                            );
+                            let reason = e.root_cause().to_string();
+                            let _ = repo.clone().mark_broken(&pic.picture_id, &reason);
+                        }
+                        Err(_) => {
+                            error!(
+                                "Panicked generate or add thumbnail: Photo path: {:?}",
+                                pic.path
+                            );
+                            let _ = repo
+                                .clone()
+                                .mark_broken(&pic.picture_id, "Panicked generating thumbnail");
+                        }
+                    }
+
+                    progress_monitor.emit(ProgressMonitorInput::Advance);
+                });
+        }
+
+        // Closing the channel flushes whatever is left, including work completed
+        // before a stop.
+        drop(blurhash_tx);
+        if writer.join().is_err() {
+            error!("Panicked writing photo blurhashes");
+        }
+    }
+
+    fn regenerate(
+        mut repo: fotema_core::photo::Repository,
+        thumbnailer: PhotoThumbnailer,
+        picture_id: PictureId,
+    ) -> Result<()> {
+        let pic = repo
+            .get(&picture_id)?
+            .ok_or_else(|| anyhow!("No picture with ID {}", picture_id))?;
+
+        let content_hash = repo.current_content_hash(&picture_id)?;
+
+        thumbnailer.remove_thumbnails(&pic.path, content_hash.as_deref())?;
+        repo.reset_thumbnail(&picture_id)?;
+
+        let result = panic::catch_unwind(|| {
+            block_on(async {
+                thumbnailer
+                    .thumbnail(&pic.path, content_hash.as_deref())
+                    .await
+            })
+        });
+
+        match result {
+            Ok(Ok(blurhash)) => {
+                repo.set_blurhashes(&[(picture_id, blurhash)])?;
+            }
+            Ok(Err(e)) if pic.path.availability() == Availability::Unavailable => {
+                repo.set_availability(&picture_id, Availability::Unavailable)?;
+                return Err(e.context(format!("Photo unavailable: {:?}", pic.path)));
+            }
+            Ok(Err(e)) => {
+                repo.mark_broken(&picture_id, &e.root_cause().to_string())?;
+                return Err(e);
+            }
+            Err(_) => {
+                repo.mark_broken(&picture_id, "Panicked generating thumbnail")?;
+                bail!("Panicked generating thumbnail: Photo path: {:?}", pic.path);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Compute perceptual hashes, for finding similar photos, from the thumbnails of
+    /// photos that don't have one yet. Hashing a thumbnail is much quicker than
+    /// decoding the photo, so this doesn't wait while thumbnailing is paused.
+    fn hash_thumbnails(
+        stop: &AtomicBool,
+        repo: &mut fotema_core::photo::Repository,
+        thumbnailer: &PhotoThumbnailer,
+    ) -> Result<()> {
+        let start = std::time::Instant::now();
+
+        let unhashed = repo.find_need_perceptual_hash()?;
+        if unhashed.is_empty() {
+            return Ok(());
+        }
+
+        // Thumbnails evicted from the cache are hashed once they are generated again.
+        let hashes: Vec<_> = unhashed
+            .par_iter()
+            .take_any_while(|_| !stop.load(Ordering::Relaxed))
+            .filter(|pic| !thumbnailer.thumbnailer().is_evicted(&pic.thumbnail_hash()))
+            .flat_map(|pic| {
+                thumbnailer
+                    .perceptual_hash(&pic.path)
+                    .inspect_err(|e| warn!("Failed hashing thumbnail of {:?}: {:?}", pic.path, e))
+                    .map(|hash| (pic.picture_id, hash))
+            })
+            .collect();
+
+        repo.set_perceptual_hashes(&hashes)?;
+
+        info!(
+            "Computed {} perceptual hashes in {} seconds.",
+            hashes.len(),
+            start.elapsed().as_secs()
+        );
+
+        Ok(())
+    }
+
+    fn write_blurhashes(
+        mut repo: fotema_core::photo::Repository,
+        rx: mpsc::Receiver<(PictureId, String)>,
+    ) {
+        let mut batch = Vec::with_capacity(BATCH_SIZE);
+        let mut flush = |batch: &mut Vec<(PictureId, String)>| {
+            if let Err(e) = repo.set_blurhashes(batch) {
+                error!("Failed to write {} photo blurhashes: {:?}", batch.len(), e);
+            }
+            batch.clear();
+        };
+
+        for blurhash in rx {
+            batch.push(blurhash);
+            if batch.len() >= BATCH_SIZE {
+                flush(&mut batch);
+            }
+        }
+
+        if !batch.is_empty() {
+            flush(&mut batch);
+        }
+    }
 }
 
 impl Worker for PhotoThumbnailTask {
     type Init = (
         Arc<AtomicBool>,
+        Arc<Pause>,
+        Arc<Pause>,
         PathBuf,
         PhotoThumbnailer,
         fotema_core::photo::Repository,
@@ -156,11 +449,13 @@ impl Worker for PhotoThumbnailTask {
     type Output = PhotoThumbnailTaskOutput;
 
     fn init(
-        (stop, thumbnails_path, thumbnailer, repo, progress_monitor): Self::Init,
+        (stop, pause, power_pause, thumbnails_path, thumbnailer, repo, progress_monitor): Self::Init,
         _sender: ComponentSender<Self>,
     ) -> Self {
         PhotoThumbnailTask {
             stop,
+            pause,
+            power_pause,
             thumbnails_path: thumbnails_path.into(),
             thumbnailer,
             repo,
@@ -170,21 +465,28 @@ impl Worker for PhotoThumbnailTask {
 
     fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
         match msg {
-            PhotoThumbnailTaskInput::Start => {
+            PhotoThumbnailTaskInput::Start { order } => {
                 info!("Generating photo thumbnails...");
                 let stop = self.stop.clone();
+                let pause = self.pause.clone();
+                let power_pause = self.power_pause.clone();
                 let repo = self.repo.clone();
                 let thumbnails_path = self.thumbnails_path.clone();
                 let thumbnailer = self.thumbnailer.clone();
                 let progress_monitor = self.progress_monitor.clone();
 
-                // Avoid runtime panic from calling block_on
-                rayon::spawn(move || {
+                // Batches are handed to rayon from a thread of their own, so that waiting
+                // while paused doesn't park one of rayon's workers. Also avoids a runtime
+                // panic from calling block_on.
+                std::thread::spawn(move || {
                     if let Err(e) = PhotoThumbnailTask::enrich(
                         stop,
+                        pause,
+                        power_pause,
                         repo,
                         &thumbnails_path,
                         thumbnailer,
+                        order,
                         progress_monitor,
                         sender,
                     ) {
@@ -192,6 +494,45 @@ impl Worker for PhotoThumbnailTask {
                     }
                 });
             }
+            PhotoThumbnailTaskInput::Regenerate(picture_id) => {
+                info!("Regenerating thumbnail for picture {}", picture_id);
+                let repo = self.repo.clone();
+                let thumbnailer = self.thumbnailer.clone();
+
+                // Avoid runtime panic from calling block_on
+                rayon::spawn(move || {
+                    if let Err(e) = PhotoThumbnailTask::regenerate(repo, thumbnailer, picture_id) {
+                        error!(
+                            "Failed to regenerate thumbnail for picture {}: {:?}",
+                            picture_id, e
+                        );
+                    }
+                    let _ = sender.output(PhotoThumbnailTaskOutput::Regenerated(picture_id));
+                });
+            }
+            PhotoThumbnailTaskInput::Count => {
+                info!("Counting photos that need thumbnails");
+                let repo = self.repo.clone();
+                let thumbnailer = self.thumbnailer.clone();
+
+                // Walking the library can take a while, so don't block other messages.
+                rayon::spawn(move || {
+                    // Corrupt thumbnails are deleted first, so they are counted and
+                    // then generated again.
+                    if let Err(e) = thumbnailer.verify_cache() {
+                        error!("Failed to verify thumbnail cache: {:?}", e);
+                    }
+
+                    let count = match PhotoThumbnailTask::pending(&repo, &thumbnailer) {
+                        Ok((pending, _)) => pending.len(),
+                        Err(e) => {
+                            error!("Failed to count photos that need thumbnails: {:?}", e);
+                            0
+                        }
+                    };
+                    let _ = sender.output(PhotoThumbnailTaskOutput::Counted(count));
+                });
+            }
         };
     }
 }
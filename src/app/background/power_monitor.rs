@@ -0,0 +1,142 @@
+// SPDX-FileCopyrightText: © 2025 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Holds back thumbnail generation while on battery power, or while the computer
+//! is in use, so that thumbnailing a large library doesn't drain a laptop's battery.
+
+use strum::{AsRefStr, EnumString, FromRepr};
+
+/// When photo thumbnails are generated.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, EnumString, AsRefStr, FromRepr)]
+#[repr(u32)]
+pub enum ThumbnailPowerMode {
+    /// Whenever there are pictures without thumbnails.
+    #[default]
+    Always,
+
+    /// Only while on AC power.
+    AcPower,
+
+    /// Only while on AC power and the computer hasn't been used for a while.
+    AcPowerIdle,
+}
+
+#[cfg(feature = "power")]
+pub use monitor::PowerMonitor;
+
+#[cfg(feature = "power")]
+mod monitor {
+    use relm4::gtk::gio;
+    use relm4::gtk::prelude::*;
+    use std::sync::mpsc::{self, RecvTimeoutError};
+    use std::time::Duration;
+    use tracing::debug;
+
+    use super::ThumbnailPowerMode;
+
+    /// How often power and idle state is checked.
+    const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+    /// How long the computer must go unused before it counts as idle.
+    const IDLE_TIME: Duration = Duration::from_secs(5 * 60);
+
+    // Bus name and object path of UPower, which knows if we are on battery.
+    // See https://upower.freedesktop.org/docs/UPower.html
+    const UPOWER_NAME: &str = "org.freedesktop.UPower";
+    const UPOWER_PATH: &str = "/org/freedesktop/UPower";
+
+    // Bus name and object path of the GNOME idle monitor, which knows how long since
+    // the user last used the keyboard or mouse.
+    const IDLE_MONITOR_NAME: &str = "org.gnome.Mutter.IdleMonitor";
+    const IDLE_MONITOR_PATH: &str = "/org/gnome/Mutter/IdleMonitor/Core";
+
+    /// Checks whether thumbnails may be generated for a power mode.
+    /// Stops checking when dropped.
+    pub struct PowerMonitor {
+        mode: ThumbnailPowerMode,
+        _stop: mpsc::Sender<()>,
+    }
+
+    impl PowerMonitor {
+        /// `on_check` is called on a background thread straight away, and then
+        /// periodically, with whether thumbnails may be generated.
+        /// Power or idle state that can't be found out, such as on a desktop without
+        /// UPower, doesn't hold thumbnails back.
+        pub fn watch(mode: ThumbnailPowerMode, on_check: impl Fn(bool) + Send + 'static) -> Self {
+            let (stop, rx) = mpsc::channel::<()>();
+
+            // Channel disconnects when the monitor is dropped, which ends this thread.
+            std::thread::spawn(move || {
+                on_check(is_allowed(mode));
+                while let Err(RecvTimeoutError::Timeout) = rx.recv_timeout(POLL_INTERVAL) {
+                    on_check(is_allowed(mode));
+                }
+            });
+
+            Self { mode, _stop: stop }
+        }
+
+        pub fn mode(&self) -> ThumbnailPowerMode {
+            self.mode
+        }
+    }
+
+    /// May thumbnails be generated right now?
+    fn is_allowed(mode: ThumbnailPowerMode) -> bool {
+        let is_on_ac_power = || !on_battery().unwrap_or(false);
+        let is_idle = || idle_time().is_none_or(|idle_time| idle_time >= IDLE_TIME);
+
+        match mode {
+            ThumbnailPowerMode::Always => true,
+            ThumbnailPowerMode::AcPower => is_on_ac_power(),
+            ThumbnailPowerMode::AcPowerIdle => is_on_ac_power() && is_idle(),
+        }
+    }
+
+    /// Is the computer running on battery power? `None` if UPower can't be asked.
+    fn on_battery() -> Option<bool> {
+        let bus = gio::bus_get_sync(gio::BusType::System, gio::Cancellable::NONE).ok()?;
+        let reply = bus
+            .call_sync(
+                Some(UPOWER_NAME),
+                UPOWER_PATH,
+                "org.freedesktop.DBus.Properties",
+                "Get",
+                Some(&(UPOWER_NAME, "OnBattery").to_variant()),
+                None,
+                gio::DBusCallFlags::NONE,
+                -1,
+                gio::Cancellable::NONE,
+            )
+            .inspect_err(|e| debug!("Failed asking UPower for power state: {:?}", e))
+            .ok()?;
+
+        let on_battery = reply.child_value(0).as_variant()?.get::<bool>()?;
+        debug!("On battery: {}", on_battery);
+        Some(on_battery)
+    }
+
+    /// Time since the user last used the computer. `None` if it can't be found out,
+    /// such as on desktops other than GNOME.
+    fn idle_time() -> Option<Duration> {
+        let bus = gio::bus_get_sync(gio::BusType::Session, gio::Cancellable::NONE).ok()?;
+        let reply = bus
+            .call_sync(
+                Some(IDLE_MONITOR_NAME),
+                IDLE_MONITOR_PATH,
+                IDLE_MONITOR_NAME,
+                "GetIdletime",
+                None,
+                None,
+                gio::DBusCallFlags::NONE,
+                -1,
+                gio::Cancellable::NONE,
+            )
+            .inspect_err(|e| debug!("Failed asking for idle time: {:?}", e))
+            .ok()?;
+
+        let (idle_time_ms,) = reply.get::<(u64,)>()?;
+        Some(Duration::from_millis(idle_time_ms))
+    }
+}
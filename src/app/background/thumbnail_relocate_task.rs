@@ -0,0 +1,147 @@
+// SPDX-FileCopyrightText: © 2025 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use anyhow::Result;
+use relm4::Reducer;
+use relm4::Worker;
+use relm4::prelude::*;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use tracing::{error, info};
+
+use fotema_core::thumbnailify::{ThumbnailLocation, Thumbnailer};
+use fotema_core::visual::Repository;
+
+use crate::app::components::progress_monitor::{ProgressMonitor, ProgressMonitorInput, TaskName};
+
+#[derive(Debug)]
+pub enum ThumbnailRelocateTaskInput {
+    /// Move thumbnails from where they were saved for a location to where they are
+    /// saved now.
+    Start(ThumbnailLocation),
+}
+
+#[derive(Debug)]
+pub enum ThumbnailRelocateTaskOutput {
+    Started,
+
+    /// Count of pictures and videos whose thumbnails were moved.
+    Completed(usize),
+}
+
+pub struct ThumbnailRelocateTask {
+    // Stop flag
+    stop: Arc<AtomicBool>,
+
+    repo: Repository,
+
+    thumbnailer: Thumbnailer,
+
+    progress_monitor: Arc<Reducer<ProgressMonitor>>,
+}
+
+impl ThumbnailRelocateTask {
+    fn relocate(
+        &self,
+        from: ThumbnailLocation,
+        sender: &ComponentSender<ThumbnailRelocateTask>,
+    ) -> Result<()> {
+        let start = std::time::Instant::now();
+
+        // Pictures on drives that aren't mounted are skipped, and have their thumbnails
+        // generated again when the drive is next mounted.
+        let visuals: Vec<_> = self
+            .repo
+            .all()?
+            .into_iter()
+            .chain(self.repo.trashed()?)
+            .filter(|visual| visual.path().exists())
+            .collect();
+
+        info!(
+            "Moving thumbnails of {} items from {:?} to {:?}",
+            visuals.len(),
+            from,
+            self.thumbnailer.location()
+        );
+
+        let _ = sender.output(ThumbnailRelocateTaskOutput::Started);
+
+        self.progress_monitor.emit(ProgressMonitorInput::Start(
+            TaskName::RelocateThumbnails,
+            visuals.len(),
+        ));
+
+        let mut count = 0;
+        for visual in visuals {
+            if self.stop.load(Ordering::Relaxed) {
+                break;
+            }
+
+            match self.thumbnailer.relocate_thumbnails(visual.path(), from) {
+                Ok(()) => count += 1,
+                Err(e) => error!("Failed moving thumbnails of {:?}: {:?}", visual.path(), e),
+            }
+
+            self.progress_monitor.emit(ProgressMonitorInput::Advance);
+        }
+
+        if from == ThumbnailLocation::Sidecar {
+            self.thumbnailer.remove_empty_sidecars();
+        }
+
+        info!(
+            "Moved thumbnails of {} items in {} seconds.",
+            count,
+            start.elapsed().as_secs()
+        );
+
+        self.progress_monitor.emit(ProgressMonitorInput::Complete);
+
+        let _ = sender.output(ThumbnailRelocateTaskOutput::Completed(count));
+
+        Ok(())
+    }
+}
+
+impl Worker for ThumbnailRelocateTask {
+    type Init = (
+        Arc<AtomicBool>,
+        Repository,
+        Thumbnailer,
+        Arc<Reducer<ProgressMonitor>>,
+    );
+    type Input = ThumbnailRelocateTaskInput;
+    type Output = ThumbnailRelocateTaskOutput;
+
+    fn init(
+        (stop, repo, thumbnailer, progress_monitor): Self::Init,
+        _sender: ComponentSender<Self>,
+    ) -> Self {
+        Self {
+            stop,
+            repo,
+            thumbnailer,
+            progress_monitor,
+        }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        if self.stop.load(Ordering::Relaxed) {
+            let _ = sender.output(ThumbnailRelocateTaskOutput::Completed(0));
+            return;
+        }
+
+        match msg {
+            ThumbnailRelocateTaskInput::Start(from) => {
+                if let Err(e) = self.relocate(from, &sender) {
+                    error!("Failed to move thumbnails: {}", e);
+                    let _ = sender.output(ThumbnailRelocateTaskOutput::Completed(0));
+                }
+            }
+        };
+    }
+}
@@ -12,11 +12,19 @@ use std::sync::atomic::{AtomicBool, Ordering};
 
 use tracing::{error, info};
 
+use fotema_core::thumbnailify::Thumbnailer;
+
 use crate::APP_ID;
 
 #[derive(Debug)]
 pub enum TidyTaskInput {
-    Start,
+    Start {
+        /// Are animated previews enabled? If not, any previously generated are deleted.
+        animated_previews: bool,
+
+        /// Maximum size of thumbnail cache in bytes, if limited.
+        thumbnail_cache_limit: Option<u64>,
+    },
 }
 
 #[derive(Debug)]
@@ -28,13 +36,30 @@ pub enum TidyTaskOutput {
 pub struct TidyTask {
     // Stop flag
     stop: Arc<AtomicBool>,
+
+    thumbnailer: Thumbnailer,
 }
 
 impl TidyTask {
-    fn tidy(&self, sender: &ComponentSender<TidyTask>) -> Result<()> {
+    fn tidy(
+        &self,
+        animated_previews: bool,
+        thumbnail_cache_limit: Option<u64>,
+        sender: &ComponentSender<TidyTask>,
+    ) -> Result<()> {
 
         let _= sender.output(TidyTaskOutput::Started);
 
+        // Animated previews take up a lot of space, so don't keep them if disabled.
+        if !animated_previews {
+            self.thumbnailer.remove_animated_thumbnails()?;
+        }
+
+        // Evicted thumbnails are regenerated when next shown.
+        if let Some(max_bytes) = thumbnail_cache_limit {
+            self.thumbnailer.enforce_cache_limit(max_bytes)?;
+        }
+
         // TODO remove me after 2026-01-01
         // Delete legacy thumbnail directory
         let legacy_dir = glib::user_cache_dir()
@@ -72,12 +97,12 @@ impl TidyTask {
 }
 
 impl Worker for TidyTask {
-    type Init = Arc<AtomicBool>;
+    type Init = (Arc<AtomicBool>, Thumbnailer);
     type Input = TidyTaskInput;
     type Output = TidyTaskOutput;
 
-    fn init(stop: Self::Init, _sender: ComponentSender<Self>) -> Self {
-        Self { stop }
+    fn init((stop, thumbnailer): Self::Init, _sender: ComponentSender<Self>) -> Self {
+        Self { stop, thumbnailer }
     }
 
     fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
@@ -87,11 +112,15 @@ impl Worker for TidyTask {
         }
 
         match msg {
-            TidyTaskInput::Start => {
+            TidyTaskInput::Start {
+                animated_previews,
+                thumbnail_cache_limit,
+            } => {
                 info!("Tidying up...");
 
-                if let Err(e) = self.tidy(&sender) {
+                if let Err(e) = self.tidy(animated_previews, thumbnail_cache_limit, &sender) {
                     error!("Failed to tidy: {}", e);
+                    let _= sender.output(TidyTaskOutput::Completed);
                 }
             }
         };
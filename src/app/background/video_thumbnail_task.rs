@@ -8,7 +8,6 @@ use relm4::Reducer;
 use relm4::Worker;
 use relm4::prelude::*;
 use std::panic;
-use std::path::{Path, PathBuf};
 use std::result::Result::Ok;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -18,6 +17,7 @@ use fotema_core::thumbnailify;
 use fotema_core::thumbnailify::ThumbnailSize;
 use fotema_core::video::{Repository, VideoThumbnailer, Video};
 
+use super::pause::{self, Pause};
 use crate::app::components::progress_monitor::{
     ThumbnailType, ProgressMonitor, ProgressMonitorInput, TaskName,
 };
@@ -40,7 +40,9 @@ pub struct VideoThumbnailTask {
     // Stop flag
     stop: Arc<AtomicBool>,
 
-    thumbnails_path: PathBuf,
+    // Pause flag
+    pause: Arc<Pause>,
+
     thumbnailer: VideoThumbnailer,
 
     // Danger! Don't hold the repo mutex for too long as it blocks viewing images.
@@ -52,8 +54,8 @@ pub struct VideoThumbnailTask {
 impl VideoThumbnailTask {
     fn enrich(
         stop: Arc<AtomicBool>,
+        pause: Arc<Pause>,
         repo: Repository,
-        thumbnails_path: &Path,
         thumbnailer: VideoThumbnailer,
         progress_monitor: Arc<Reducer<ProgressMonitor>>,
         sender: ComponentSender<VideoThumbnailTask>,
@@ -65,9 +67,16 @@ impl VideoThumbnailTask {
             .into_iter()
             .filter(|vid| vid.path.exists())
             .filter(|vid| {
+                // Thumbnails may be saved beside the video rather than centrally.
+                let thumbnails_path = thumbnailer.thumbnailer().add_thumbnail(&vid.path);
+
+                // Evicted thumbnails are regenerated when next shown.
                 let thumb_hash = vid.thumbnail_hash();
+                if thumbnailify::is_evicted(&thumbnails_path, &thumb_hash) {
+                    return false;
+                }
                 let large_path = thumbnailify::get_thumbnail_hash_output(
-                    thumbnails_path,
+                    &thumbnails_path,
                     &thumb_hash,
                     ThumbnailSize::Large,
                 );
@@ -95,34 +104,42 @@ impl VideoThumbnailTask {
             count,
         ));
 
-        unprocessed
-            .par_iter()
-            .take_any_while(|_| !stop.load(Ordering::Relaxed))
-            .for_each(|vid| {
-                // Careful! panic::catch_unwind returns Ok(Err) if the evaluated expression returns
-                // an error but doesn't panic.
-                let result =
-                    panic::catch_unwind(|| thumbnailer.thumbnail(&vid.path));
-
-                // If we got an err, then there was a panic.
-                // If we got Ok(Err(e)) there wasn't a panic, but we still failed.
-                if let Ok(Err(e)) = result {
-                    error!(
-                        "Failed generate or add thumbnail: {:?}: Video path: {:?}",
-                        e.root_cause(),
-                        vid.path
-                    );
-                    let _ = repo.clone().mark_broken(&vid.video_id);
-                } else if result.is_err() {
-                    error!(
-                        "Panicked generate or add thumbnail: Video path: {:?}",
-                        vid.path
-                    );
-                    let _ = repo.clone().mark_broken(&vid.video_id);
-                }
+        for batch in unprocessed.chunks(pause::batch_size()) {
+            pause.wait_while_paused();
+            if stop.load(Ordering::Relaxed) {
+                break;
+            }
 
-                progress_monitor.emit(ProgressMonitorInput::Advance);
-            });
+            batch
+                .par_iter()
+                .take_any_while(|_| !stop.load(Ordering::Relaxed))
+                .for_each(|vid| {
+                    // Careful! panic::catch_unwind returns Ok(Err) if the evaluated expression returns
+                    // an error but doesn't panic.
+                    let result = panic::catch_unwind(|| {
+                        thumbnailer.thumbnail(&vid.path, vid.stream_duration)
+                    });
+
+                    // If we got an err, then there was a panic.
+                    // If we got Ok(Err(e)) there wasn't a panic, but we still failed.
+                    if let Ok(Err(e)) = result {
+                        error!(
+                            "Failed generate or add thumbnail: {:?}: Video path: {:?}",
+                            e.root_cause(),
+                            vid.path
+                        );
+                        let _ = repo.clone().mark_broken(&vid.video_id);
+                    } else if result.is_err() {
+                        error!(
+                            "Panicked generate or add thumbnail: Video path: {:?}",
+                            vid.path
+                        );
+                        let _ = repo.clone().mark_broken(&vid.video_id);
+                    }
+
+                    progress_monitor.emit(ProgressMonitorInput::Advance);
+                });
+        }
 
         info!(
             "Generated {} video thumbnails in {} seconds.",
@@ -141,7 +158,7 @@ impl VideoThumbnailTask {
 impl Worker for VideoThumbnailTask {
     type Init = (
         Arc<AtomicBool>,
-        PathBuf,
+        Arc<Pause>,
         VideoThumbnailer,
         Repository,
         Arc<Reducer<ProgressMonitor>>,
@@ -150,12 +167,12 @@ impl Worker for VideoThumbnailTask {
     type Output = VideoThumbnailTaskOutput;
 
     fn init(
-        (stop, thumbnails_path, thumbnailer, repo, progress_monitor): Self::Init,
+        (stop, pause, thumbnailer, repo, progress_monitor): Self::Init,
         _sender: ComponentSender<Self>,
     ) -> Self {
         Self {
             stop,
-            thumbnails_path: thumbnails_path.into(),
+            pause,
             thumbnailer,
             repo,
             progress_monitor,
@@ -167,17 +184,19 @@ impl Worker for VideoThumbnailTask {
             VideoThumbnailTaskInput::Start => {
                 info!("Generating video thumbnails...");
                 let stop = self.stop.clone();
+                let pause = self.pause.clone();
                 let repo = self.repo.clone();
-                let thumbnails_path = self.thumbnails_path.clone();
                 let thumbnailer = self.thumbnailer.clone();
                 let progress_monitor = self.progress_monitor.clone();
 
-                // Avoid runtime panic from calling block_on
-                rayon::spawn(move || {
+                // Batches are handed to rayon from a thread of their own, so that waiting
+                // while paused doesn't park one of rayon's workers. Also avoids a runtime
+                // panic from calling block_on.
+                std::thread::spawn(move || {
                     if let Err(e) = VideoThumbnailTask::enrich(
                         stop,
+                        pause,
                         repo,
-                        &thumbnails_path,
                         thumbnailer,
                         progress_monitor,
                         sender,
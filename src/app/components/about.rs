@@ -47,6 +47,7 @@ impl SimpleComponent for AboutDialog {
                 "libde265 https://github.com/strukturag/libde265",
                 "OpenStreetMap https://www.openstreetmap.org",
                 "Shumate https://gitlab.gnome.org/GNOME/libshumate",
+                "GeoNames https://www.geonames.org/",
             ],
         );
 
@@ -68,6 +69,12 @@ impl SimpleComponent for AboutDialog {
             gtk::License::Lgpl30,
             None,
         );
+        about.add_legal_section(
+            "GeoNames",
+            Some("Place names from GeoNames https://www.geonames.org/"),
+            gtk::License::Custom,
+            Some("Licensed under the <a href=\"https://creativecommons.org/licenses/by/4.0/\">Creative Commons Attribution 4.0 License</a>."),
+        );
 
         about
     }
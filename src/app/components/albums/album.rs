@@ -2,34 +2,96 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use fotema_core::PictureId;
 use fotema_core::VisualId;
 use fotema_core::YearMonth;
-use fotema_core::thumbnailify::{Thumbnailer, ThumbnailSize};
+use fotema_core::burst::BurstId;
+use fotema_core::thumbnailify::{MemoryCache, Thumbnailer, blurhash};
+use fotema_core::user_album::UserAlbumId;
+use fotema_core::visual::VisualThumbnailer;
 
 use gtk::prelude::OrientableExt;
 use relm4::binding::*;
 use relm4::gtk;
 use relm4::gtk::gdk;
 use relm4::gtk::gdk_pixbuf;
+use relm4::gtk::gio;
+use relm4::gtk::glib;
 use relm4::gtk::prelude::AdjustmentExt;
 use relm4::gtk::prelude::*;
+use relm4::actions::{RelmAction, RelmActionGroup};
+use relm4::typed_view::TypedListItem;
 use relm4::typed_view::grid::{RelmGridItem, TypedGridView};
 use relm4::*;
-use std::path::Path;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::rc::Rc;
+use std::time::Duration;
+
+use chrono::{DateTime, Datelike, Local, NaiveDate, Utc};
+use humansize::{DECIMAL, format_size};
 
 use super::album_filter::AlbumFilter;
-use super::album_sort::AlbumSort;
+use super::album_sort::{AlbumSort, SortKey};
+use super::file_manager;
+use super::grid_zoom::{self, GridZoom};
+use super::slideshow::{Slideshow, SlideshowInput};
 use crate::app::ActiveView;
 use crate::app::SharedState;
 use crate::app::ViewName;
 use crate::app::adaptive;
+use crate::app::components::progress_monitor::MediaType;
+use crate::fl;
+
+use tracing::{debug, error, info};
+
+// Edge length of grid items until the layout and zoom are known.
+const DEFAULT_EDGE_LENGTH: i32 = GridZoom::Medium.edge_length(adaptive::Layout::Narrow);
+
+// Blurhash placeholders are decoded at this size then scaled up.
+const PLACEHOLDER_EDGE_LENGTH: u32 = 32;
+
+// Frame delay for animated previews that don't specify one.
+const DEFAULT_FRAME_DELAY: Duration = Duration::from_millis(100);
+
+// Memory for decoded thumbnails. Enough for a few screens of the largest grid thumbnails.
+const TEXTURE_CACHE_BYTES: usize = 128 * 1024 * 1024;
+
+// Items added to the photo grid at a time. The first chunk is shown straight away and
+// the rest are added while idle, so albums with tens of thousands of items appear quickly.
+const CHUNK_SIZE: usize = 1000;
+
+// Actions in the context menu of a grid item.
+relm4::new_action_group!(ItemActionGroup, "item");
 
-use tracing::{debug, info};
+relm4::new_stateless_action!(OpenAction, ItemActionGroup, "open");
+relm4::new_stateless_action!(InfoAction, ItemActionGroup, "info");
 
-const NARROW_EDGE_LENGTH: i32 = 112;
-const WIDE_EDGE_LENGTH: i32 = 200;
+// Choose a picture as the cover of its folder.
+relm4::new_stateless_action!(FolderCoverAction, ItemActionGroup, "folder_cover");
+
+relm4::new_stateless_action!(FavoriteAction, ItemActionGroup, "favorite");
+relm4::new_stateless_action!(RegenerateThumbnailAction, ItemActionGroup, "regenerate_thumbnail");
+relm4::new_stateless_action!(RevealAction, ItemActionGroup, "reveal");
+relm4::new_stateless_action!(OpenDefaultAction, ItemActionGroup, "open_default");
+relm4::new_stateless_action!(OpenWithAction, ItemActionGroup, "open_with");
+relm4::new_stateless_action!(CopyPathAction, ItemActionGroup, "copy_path");
+relm4::new_stateless_action!(TrashAction, ItemActionGroup, "trash");
+
+// Show the frames of an expanded burst as one stacked item again.
+relm4::new_stateless_action!(CollapseBurstAction, ItemActionGroup, "collapse_burst");
+
+// Show the frames of a burst as separate pictures for good.
+relm4::new_stateless_action!(UngroupBurstAction, ItemActionGroup, "ungroup_burst");
+
+thread_local! {
+    // Decoded thumbnails shared by every album, so that scrolling back over thumbnails
+    // doesn't decode them from disk again. Textures can only be used on the main thread.
+    static TEXTURE_CACHE: RefCell<MemoryCache<gdk::Texture>> =
+        RefCell::new(MemoryCache::new(TEXTURE_CACHE_BYTES));
+}
 
 #[derive(Debug)]
 pub enum AlbumInput {
@@ -39,9 +101,16 @@ pub enum AlbumInput {
     // State has been updated
     Refresh,
 
+    /// Add the next chunk of items to the photo grid.
+    /// Ignored if the album has been reloaded since, which bumps the generation.
+    LoadMore(u64),
+
     /// User has selected photo in grid view
     Selected(u32), // Index into a Vec
 
+    /// Open an item from its context menu. Opens even in selection mode.
+    Open(VisualId),
+
     // Scroll to first photo of year/month.
     GoToMonth(YearMonth),
 
@@ -51,17 +120,123 @@ pub enum AlbumInput {
     // Show no photos
     Filter(AlbumFilter),
 
-    // Sort
-    Sort(AlbumSort),
+    /// Narrow the album with another filter. Replaces any added filter of the same kind.
+    AddFilter(AlbumFilter),
+
+    /// Remove a filter previously added with `AddFilter`.
+    RemoveFilter(AlbumFilter),
+
+    /// Remove all filters added with `AddFilter`.
+    ClearFilters,
+
+    /// Only show items with a file name containing the text.
+    /// Empty text shows all items again.
+    Search(String),
+
+    // Change the order of items
+    SortBy(SortKey),
 
     // Adapt to layout
     Adapt(adaptive::Layout),
 
+    // Change the size of items
+    Zoom(GridZoom),
+
     // Scroll offset, in pixels.
     ScrollOffset(f64),
 
     // Scroll to top of photo grid, regardless of sort order
     ScrollToTop,
+
+    /// Scroll to an offset, in pixels, once the photo grid has been laid out.
+    RestoreScrollOffset(f64),
+
+    /// User has starred or unstarred pictures as favorites.
+    SetFavorite(Vec<PictureId>, bool),
+
+    /// User has chosen a picture as the cover of a folder.
+    SetFolderCover(PathBuf, PictureId),
+
+    /// User has dragged a picture onto another in a user album, to move the first
+    /// picture to the place of the second.
+    MoveInUserAlbum(PictureId, PictureId),
+
+    /// Delete and regenerate the thumbnail of a picture from its context menu.
+    RegenerateThumbnail(PictureId),
+
+    /// Show a file in the file manager. Host path of file.
+    RevealInFileManager(PathBuf),
+
+    /// Open a file in the default app for its type. Sandbox path of file.
+    OpenWithDefault(PathBuf),
+
+    /// Open a file in an app chosen by the user. Sandbox path of file.
+    OpenWithChosen(PathBuf),
+
+    /// Copy the path of a file to the clipboard.
+    CopyPath(PathBuf),
+
+    /// Move a picture to the trash from its context menu.
+    Trash(PictureId),
+
+    /// Show every frame of a burst in place of its stacked item.
+    ExpandBurst(BurstId),
+
+    /// Show the frames of a burst as one stacked item again.
+    CollapseBurst(BurstId),
+
+    /// Show the frames of a burst as separate pictures for good.
+    UngroupBurst(BurstId),
+
+    /// Activating an item toggles its selection instead of opening it.
+    EnterSelectionMode,
+
+    /// Activating an item opens it again. Clears selection.
+    ExitSelectionMode,
+
+    /// Items have been selected or deselected.
+    SelectionChanged,
+
+    /// Select every visible item.
+    SelectAll,
+
+    /// Deselect every item.
+    SelectNone,
+
+    /// Star all selected pictures as favorites.
+    FavoriteSelected,
+
+    /// Move all selected pictures to the trash, or restore them from the trash.
+    TrashSelected(bool),
+
+    /// Choose a folder to move all selected pictures to.
+    MoveSelectedDialog,
+
+    /// Move all selected pictures to a folder of the library.
+    MoveSelected(PathBuf),
+
+    /// Choose a person to name in all selected pictures.
+    AssignSelectedToPerson,
+
+    /// Keyboard shortcut to star or unstar the focused item.
+    /// In selection mode, selects or deselects the focused item instead.
+    ToggleFocused,
+
+    /// Keyboard shortcut to move the focused item to the trash.
+    TrashFocused,
+
+    /// Show basic facts about an item's file over the album.
+    ShowInfo(Arc<fotema_core::visual::Visual>),
+
+    /// Keyboard shortcut to show or hide facts about the focused item.
+    ToggleInfoFocused,
+
+    /// Hide facts about an item.
+    HideInfo,
+
+    /// Show visible items fullscreen, one after another, starting from the focused item.
+    /// Pictures are shown for the given interval.
+    Slideshow(Duration),
 }
 
 #[derive(Debug)]
@@ -71,15 +246,57 @@ pub enum AlbumOutput {
 
     // Scroll offset, in pixels.
     ScrollOffset(f64),
+
+    /// User has starred or unstarred pictures as favorites.
+    SetFavorite(Vec<PictureId>, bool),
+
+    /// User has moved pictures to, or restored pictures from, the trash.
+    SetTrashed(Vec<PictureId>, bool),
+
+    /// User has chosen a picture as the cover of a folder.
+    SetFolderCover(PathBuf, PictureId),
+
+    /// User wants the thumbnail of a picture regenerated.
+    RegenerateThumbnail(PictureId),
+
+    /// User wants the frames of a burst shown as separate pictures.
+    UngroupBurst(BurstId),
+
+    /// User has moved pictures to another folder.
+    MovePictures(Vec<PictureId>, PathBuf),
+
+    /// User wants the one unknown face of each picture named as a person.
+    AssignToPerson(Vec<PictureId>),
+
+    /// User has arranged the pictures of a user album in a new order.
+    SetUserAlbumOrder(UserAlbumId, Vec<PictureId>),
 }
 
 #[derive(Debug)]
 struct PhotoGridItem {
     visual: Arc<fotema_core::visual::Visual>,
 
+    // For sending favorite toggles back to album.
+    sender: relm4::Sender<AlbumInput>,
+
     // Length of thumbnail edge to allow for resizing when layout changes.
     edge_length: I32Binding,
 
+    // Is album in selection mode? Shared by all items.
+    selection_mode: BoolBinding,
+
+    // Can the item be moved to the trash? Not if it is already there.
+    is_trashable: bool,
+
+    // Number of frames if the item stands for a collapsed burst.
+    burst_size: Option<usize>,
+
+    // Is the item a frame of a burst that has been expanded?
+    is_burst_expanded: bool,
+
+    // Can the item be dragged to another place? Only in user albums.
+    is_reorderable: bool,
+
     thumbnailer: Rc<Thumbnailer>,
 }
 
@@ -87,18 +304,59 @@ struct PhotoGridItemWidgets {
     picture: gtk::Picture,
     status_overlay: gtk::Frame,
     motion_type_icon: gtk::Image,
+    burst_overlay: gtk::Frame,
+    burst_label: gtk::Label,
     duration_overlay: gtk::Frame,
     duration_label: gtk::Label,
+    favorite_button: gtk::ToggleButton,
+    select_button: gtk::CheckButton,
+
+    // Handler for favorite_button toggles. Must be disconnected on unbind.
+    favorite_handler: Option<glib::SignalHandlerId>,
+
+    // List item shown by the widgets. Weak, because the list item owns the widgets.
+    list_item: glib::WeakRef<gtk::ListItem>,
+
+    // Binding of the list item's selectable state to selection mode. Must be unbound on unbind.
+    selectable_binding: Option<glib::Binding>,
+
+    // Loads thumbnail while the blurhash placeholder is drawn. Must be aborted on unbind.
+    thumbnail_loader: Option<glib::JoinHandle<()>>,
+
+    // Pointer entering and leaving the item, to play animated previews.
+    hover: gtk::EventControllerMotion,
+
+    // Handlers for hover enter and leave. Must be disconnected on unbind.
+    hover_handlers: Vec<glib::SignalHandlerId>,
+
+    // Dragging the item onto another item, to arrange a user album.
+    drag_source: gtk::DragSource,
+    drop_target: gtk::DropTarget,
+
+    // Handlers for dragging and dropping. Must be disconnected on unbind.
+    drag_handler: Option<glib::SignalHandlerId>,
+    drop_handler: Option<glib::SignalHandlerId>,
+
+    // Playing animated preview. Must be stopped on unbind.
+    animation: Rc<RefCell<Option<Animation>>>,
 
     // If the gtk::Picture has been bound to edge_length.
     is_bound: bool,
 }
 
+/// Animated preview playing while the pointer is over a grid item.
+struct Animation {
+    player: glib::JoinHandle<()>,
+
+    // Thumbnail to restore when the animation stops.
+    still: Option<gdk::Paintable>,
+}
+
 impl RelmGridItem for PhotoGridItem {
     type Root = gtk::Frame;
     type Widgets = PhotoGridItemWidgets;
 
-    fn setup(_item: &gtk::ListItem) -> (Self::Root, Self::Widgets) {
+    fn setup(item: &gtk::ListItem) -> (Self::Root, Self::Widgets) {
         relm4::view! {
             root = gtk::Frame {
                 gtk::Overlay {
@@ -118,6 +376,33 @@ impl RelmGridItem for PhotoGridItem {
                         },
                     },
 
+                    #[name(burst_overlay)]
+                    add_overlay =  &gtk::Frame {
+                        set_halign: gtk::Align::Start,
+                        set_valign: gtk::Align::End,
+                        set_margin_all: 8,
+                        set_visible: false,
+                        add_css_class: "photo-grid-photo-status-frame",
+
+                        #[wrap(Some)]
+                        set_child = &gtk::Box {
+                            set_orientation: gtk::Orientation::Horizontal,
+                            set_spacing: 4,
+
+                            gtk::Image {
+                                set_icon_name: Some("edit-copy-symbolic"),
+                                set_width_request: 16,
+                                set_height_request: 16,
+                                add_css_class: "photo-grid-photo-status-label",
+                            },
+
+                            #[name(burst_label)]
+                            gtk::Label {
+                                add_css_class: "photo-grid-photo-status-label",
+                            },
+                        },
+                    },
+
                     #[name(duration_overlay)]
                     add_overlay =  &gtk::Frame {
                         set_halign: gtk::Align::End,
@@ -126,36 +411,182 @@ impl RelmGridItem for PhotoGridItem {
                         add_css_class: "photo-grid-photo-status-frame",
 
                         #[wrap(Some)]
-                        #[name(duration_label)]
-                        set_child = &gtk::Label{
-                            add_css_class: "photo-grid-photo-status-label",
+                        set_child = &gtk::Box {
+                            set_orientation: gtk::Orientation::Horizontal,
+                            set_spacing: 4,
+
+                            gtk::Image {
+                                set_icon_name: Some("play-symbolic"),
+                                set_width_request: 16,
+                                set_height_request: 16,
+                                add_css_class: "photo-grid-photo-status-label",
+                            },
+
+                            #[name(duration_label)]
+                            gtk::Label {
+                                add_css_class: "photo-grid-photo-status-label",
+                            },
                         },
                     },
 
+                    #[name(favorite_button)]
+                    add_overlay = &gtk::ToggleButton {
+                        set_halign: gtk::Align::End,
+                        set_valign: gtk::Align::Start,
+                        set_margin_all: 8,
+                        set_icon_name: "non-starred-symbolic",
+                        add_css_class: "osd",
+                        add_css_class: "circular",
+                    },
+
+                    #[name(select_button)]
+                    add_overlay = &gtk::CheckButton {
+                        set_halign: gtk::Align::Start,
+                        set_valign: gtk::Align::Start,
+                        set_margin_all: 8,
+                        set_visible: false,
+                        // Clicks go through to the item, which toggles its selection.
+                        set_can_target: false,
+                        add_css_class: "selection-mode",
+                    },
+
                     #[wrap(Some)]
                     #[name(picture)]
                     set_child = &gtk::Picture {
                         set_content_fit: gtk::ContentFit::Cover,
-                        set_width_request: NARROW_EDGE_LENGTH,
-                        set_height_request: NARROW_EDGE_LENGTH,
+                        set_width_request: DEFAULT_EDGE_LENGTH,
+                        set_height_request: DEFAULT_EDGE_LENGTH,
                     }
                 }
             }
         }
 
+        let hover = gtk::EventControllerMotion::new();
+        root.add_controller(hover.clone());
+
+        // Dragging an item onto another arranges a user album. Items can only be dragged,
+        // and only accept drops, once bound to a picture that can be moved.
+        let drag_source = gtk::DragSource::builder()
+            .actions(gdk::DragAction::MOVE)
+            .build();
+        let icon = picture.clone();
+        drag_source.connect_drag_begin(move |source, _| {
+            source.set_icon(Some(&gtk::WidgetPaintable::new(Some(&icon))), 0, 0);
+        });
+        root.add_controller(drag_source.clone());
+
+        let drop_target = gtk::DropTarget::new(glib::Type::STRING, gdk::DragAction::empty());
+        root.add_controller(drop_target.clone());
+
+        // Context menu on right-click or long press. Actions are registered on bind, and
+        // are disabled if they don't apply to the item.
+        let open_section = gio::Menu::new();
+        open_section.append(Some(&fl!("album-item-menu", "open")), Some("item.open"));
+        open_section.append(Some(&fl!("album-item-menu", "info")), Some("item.info"));
+
+        let picture_section = gio::Menu::new();
+        picture_section.append(
+            Some(&fl!("album-item-menu", "folder-cover")),
+            Some("item.folder_cover"),
+        );
+        picture_section.append(
+            Some(&fl!("album-item-menu", "favorite")),
+            Some("item.favorite"),
+        );
+        picture_section.append(
+            Some(&fl!("album-item-menu", "regenerate-thumbnail")),
+            Some("item.regenerate_thumbnail"),
+        );
+
+        let burst_section = gio::Menu::new();
+        burst_section.append(
+            Some(&fl!("album-item-menu", "collapse-burst")),
+            Some("item.collapse_burst"),
+        );
+        burst_section.append(
+            Some(&fl!("album-item-menu", "ungroup-burst")),
+            Some("item.ungroup_burst"),
+        );
+
+        let file_section = gio::Menu::new();
+        file_section.append(
+            Some(&fl!("album-item-menu", "open-default")),
+            Some("item.open_default"),
+        );
+        file_section.append(
+            Some(&fl!("album-item-menu", "open-with")),
+            Some("item.open_with"),
+        );
+        file_section.append(Some(&fl!("album-item-menu", "reveal")), Some("item.reveal"));
+        file_section.append(
+            Some(&fl!("album-item-menu", "copy-path")),
+            Some("item.copy_path"),
+        );
+
+        let trash_section = gio::Menu::new();
+        trash_section.append(Some(&fl!("album-item-menu", "trash")), Some("item.trash"));
+
+        let menu = gio::Menu::new();
+        menu.append_section(None, &open_section);
+        menu.append_section(None, &picture_section);
+        menu.append_section(None, &burst_section);
+        menu.append_section(None, &file_section);
+        menu.append_section(None, &trash_section);
+
+        let context_menu = gtk::PopoverMenu::from_model(Some(&menu));
+        context_menu.set_parent(&root);
+        context_menu.set_has_arrow(false);
+
+        let right_click = gtk::GestureClick::builder()
+            .button(gdk::BUTTON_SECONDARY)
+            .build();
+        let menu = context_menu.clone();
+        right_click.connect_pressed(move |_, _, x, y| {
+            menu.set_pointing_to(Some(&gdk::Rectangle::new(x as i32, y as i32, 1, 1)));
+            menu.popup();
+        });
+        root.add_controller(right_click);
+
+        let long_press = gtk::GestureLongPress::new();
+        let menu = context_menu.clone();
+        long_press.connect_pressed(move |_, x, y| {
+            menu.set_pointing_to(Some(&gdk::Rectangle::new(x as i32, y as i32, 1, 1)));
+            menu.popup();
+        });
+        root.add_controller(long_press);
+
+        item.bind_property("selected", &select_button, "active")
+            .sync_create()
+            .build();
+
         let widgets = PhotoGridItemWidgets {
             picture,
             status_overlay,
             motion_type_icon,
+            burst_overlay,
+            burst_label,
             duration_overlay,
             duration_label,
+            favorite_button,
+            select_button,
+            favorite_handler: None,
+            list_item: item.downgrade(),
+            selectable_binding: None,
+            thumbnail_loader: None,
+            hover,
+            hover_handlers: Vec::new(),
+            drag_source,
+            drop_target,
+            drag_handler: None,
+            drop_handler: None,
+            animation: Rc::new(RefCell::new(None)),
             is_bound: false,
         };
 
         (root, widgets)
     }
 
-    fn bind(&mut self, widgets: &mut Self::Widgets, _root: &mut Self::Root) {
+    fn bind(&mut self, widgets: &mut Self::Widgets, root: &mut Self::Root) {
         // Bindings to allow dynamic update of thumbnail width and height
         // when layout changes between wide and narrow
 
@@ -171,23 +602,111 @@ impl RelmGridItem for PhotoGridItem {
             widgets
                 .picture
                 .add_write_only_binding(&self.edge_length, "height-request");
+            widgets
+                .select_button
+                .add_write_only_binding(&self.selection_mode, "visible");
             widgets.is_bound = true;
         }
 
-        let thumbnail_size = if self.edge_length.value() == NARROW_EDGE_LENGTH {
-            ThumbnailSize::Normal
-        } else {
-            ThumbnailSize::Large
-        };
+        // In selection mode, activating an item toggles its selection, so hovering or
+        // clicking mustn't select the item alone.
+        if let Some(list_item) = widgets.list_item.upgrade() {
+            let selectable_binding = self
+                .selection_mode
+                .bind_property("value", &list_item, "selectable")
+                .invert_boolean()
+                .sync_create()
+                .build();
+            widgets.selectable_binding = Some(selectable_binding);
+        }
+
+        let thumbnail_size =
+            grid_zoom::thumbnail_size(self.edge_length.value(), root.scale_factor());
 
+        let thumbnail_hash = self.visual.thumbnail_hash();
         let thumbnail_path = self.thumbnailer
-            .nearest_thumbnail(&self.visual.thumbnail_hash(), thumbnail_size);
+            .nearest_thumbnail(&thumbnail_hash, thumbnail_size);
 
-        if thumbnail_path.is_some() {
-            widgets
-                .picture
-                .set_filename(thumbnail_path);
+        // Least recently shown thumbnails are the first evicted when the cache is full.
+        if let Some(ref thumbnail_path) = thumbnail_path {
+            let _ = self.thumbnailer.touch(thumbnail_path);
+        }
+
+        let placeholder = self
+            .visual
+            .blurhash
+            .as_deref()
+            .and_then(placeholder_texture);
+
+        let cached = thumbnail_path.as_deref().and_then(cached_texture);
+
+        if let Some(texture) = cached {
+            widgets.picture.set_paintable(Some(&texture));
+            widgets.picture.set_content_fit(gtk::ContentFit::Cover);
+        } else if let (Some(thumbnail_path), Some(placeholder)) = (&thumbnail_path, &placeholder) {
+            // Draw the placeholder straight away and swap in the thumbnail once loaded.
+            widgets.picture.set_paintable(Some(placeholder));
+            widgets.picture.set_content_fit(gtk::ContentFit::Cover);
+
+            let picture = widgets.picture.clone();
+            let thumbnail_path = thumbnail_path.clone();
+            let file = gio::File::for_path(&thumbnail_path);
+            let loader = glib::spawn_future_local(async move {
+                let texture = file
+                    .load_bytes_future()
+                    .await
+                    .and_then(|(bytes, _)| gdk::Texture::from_bytes(&bytes));
+
+                match texture {
+                    Ok(texture) => {
+                        picture.set_paintable(Some(&texture));
+                        cache_texture(thumbnail_path, &texture);
+                    }
+                    Err(e) => debug!("Failed loading thumbnail {:?}: {}", file.path(), e),
+                }
+            });
+            widgets.thumbnail_loader = Some(loader);
+        } else if let Some(thumbnail_path) = thumbnail_path {
+            match gdk::Texture::from_filename(&thumbnail_path) {
+                Ok(texture) => {
+                    widgets.picture.set_paintable(Some(&texture));
+                    cache_texture(thumbnail_path, &texture);
+                }
+                Err(e) => {
+                    debug!("Failed decoding thumbnail {:?}: {}", thumbnail_path, e);
+                    widgets.picture.set_filename(Some(&thumbnail_path));
+                }
+            }
 
+            widgets.picture.set_content_fit(gtk::ContentFit::Cover);
+        } else if self.thumbnailer.is_evicted(&thumbnail_hash) {
+            // Thumbnail was evicted to keep the cache small, so make it again now it is needed.
+            if let Some(placeholder) = placeholder {
+                widgets.picture.set_paintable(Some(&placeholder));
+                widgets.picture.set_content_fit(gtk::ContentFit::Cover);
+            }
+
+            let picture = widgets.picture.clone();
+            let thumbnailer = (*self.thumbnailer).clone();
+            let visual = self.visual.clone();
+            let loader = glib::spawn_future_local(async move {
+                let regenerated = gio::spawn_blocking(move || {
+                    VisualThumbnailer::build(thumbnailer)?.regenerate(&visual, thumbnail_size)
+                })
+                .await;
+
+                match regenerated {
+                    Ok(Ok(thumbnail_path)) => {
+                        picture.set_filename(Some(&thumbnail_path));
+                        picture.set_content_fit(gtk::ContentFit::Cover);
+                    }
+                    Ok(Err(e)) => error!("Failed regenerating evicted thumbnail: {:?}", e),
+                    Err(_) => error!("Panicked regenerating evicted thumbnail"),
+                }
+            });
+            widgets.thumbnail_loader = Some(loader);
+        } else if let Some(placeholder) = placeholder {
+            widgets.picture.set_paintable(Some(&placeholder));
             widgets.picture.set_content_fit(gtk::ContentFit::Cover);
         } else {
             let pb = gdk_pixbuf::Pixbuf::from_resource_at_scale(
@@ -231,14 +750,335 @@ impl RelmGridItem for PhotoGridItem {
             widgets.duration_overlay.set_visible(false);
             widgets.duration_label.set_label("");
         }
+
+        if let Some(burst_size) = self.burst_size {
+            widgets.burst_overlay.set_visible(true);
+            widgets.burst_label.set_label(&burst_size.to_string());
+            widgets
+                .burst_overlay
+                .set_tooltip_text(Some(&fl!("album-item-burst", count = burst_size)));
+        }
+
+        // Animated previews only exist if enabled in preferences.
+        let animated_thumbnail_path = Some(&self.visual)
+            .filter(|visual| visual.is_animated())
+            .and_then(|visual| self.thumbnailer.animated_thumbnail(&visual.thumbnail_hash()));
+
+        if let Some(animated_thumbnail_path) = animated_thumbnail_path {
+            let picture = widgets.picture.clone();
+            let animation = widgets.animation.clone();
+            let enter_handler = widgets.hover.connect_enter(move |_, _, _| {
+                let still = picture.paintable();
+                let file = gio::File::for_path(&animated_thumbnail_path);
+                let player = glib::spawn_future_local(play_animation(picture.clone(), file));
+                if let Some(previous) = animation.replace(Some(Animation { player, still })) {
+                    previous.player.abort();
+                }
+            });
+
+            let picture = widgets.picture.clone();
+            let animation = widgets.animation.clone();
+            let leave_handler = widgets.hover.connect_leave(move |_| {
+                stop_animation(&picture, &animation);
+            });
+
+            widgets.hover_handlers = vec![enter_handler, leave_handler];
+        }
+
+        // Only pictures can be favorites.
+        if let Some(picture_id) = self.visual.picture_id {
+            widgets.favorite_button.set_visible(true);
+
+            // Set state before connecting so binding doesn't emit a toggle.
+            set_favorite_icon(&widgets.favorite_button, self.visual.is_favorite());
+
+            let sender = self.sender.clone();
+            let handler = widgets.favorite_button.connect_toggled(move |button| {
+                set_favorite_icon(button, button.is_active());
+                sender.emit(AlbumInput::SetFavorite(
+                    vec![picture_id],
+                    button.is_active(),
+                ));
+            });
+            widgets.favorite_handler = Some(handler);
+        } else {
+            widgets.favorite_button.set_visible(false);
+        }
+
+        // Pictures of a user album are dragged as their picture ID.
+        if let Some(picture_id) = self.visual.picture_id.filter(|_| self.is_reorderable) {
+            let drag_handler = widgets.drag_source.connect_prepare(move |_, _, _| {
+                let value = picture_id.id().to_string().to_value();
+                Some(gdk::ContentProvider::for_value(&value))
+            });
+
+            let sender = self.sender.clone();
+            let drop_handler = widgets.drop_target.connect_drop(move |_, value, _, _| {
+                let moved = value
+                    .get::<String>()
+                    .ok()
+                    .and_then(|id| id.parse().ok())
+                    .map(PictureId::new);
+
+                match moved {
+                    Some(moved) if moved != picture_id => {
+                        sender.emit(AlbumInput::MoveInUserAlbum(moved, picture_id));
+                        true
+                    }
+                    _ => false,
+                }
+            });
+
+            widgets.drop_target.set_actions(gdk::DragAction::MOVE);
+            widgets.drag_handler = Some(drag_handler);
+            widgets.drop_handler = Some(drop_handler);
+        }
+
+        // Only pictures can be folder covers.
+        let folder_cover: RelmAction<FolderCoverAction> = {
+            let sender = self.sender.clone();
+            let folder_path = self.visual.parent_path.clone();
+            let picture_id = self.visual.picture_id;
+            RelmAction::new_stateless(move |_| {
+                if let Some(picture_id) = picture_id {
+                    sender.emit(AlbumInput::SetFolderCover(folder_path.clone(), picture_id));
+                }
+            })
+        };
+        folder_cover
+            .gio_action()
+            .set_enabled(self.visual.picture_id.is_some());
+
+        let open: RelmAction<OpenAction> = {
+            let sender = self.sender.clone();
+            let visual_id = self.visual.visual_id.clone();
+            RelmAction::new_stateless(move |_| sender.emit(AlbumInput::Open(visual_id.clone())))
+        };
+
+        let info: RelmAction<InfoAction> = {
+            let sender = self.sender.clone();
+            let visual = self.visual.clone();
+            RelmAction::new_stateless(move |_| sender.emit(AlbumInput::ShowInfo(visual.clone())))
+        };
+
+        // Toggling the star button sends the change, and keeps the star in step.
+        let favorite: RelmAction<FavoriteAction> = {
+            let button = widgets.favorite_button.clone();
+            RelmAction::new_stateless(move |_| button.set_active(!button.is_active()))
+        };
+        favorite
+            .gio_action()
+            .set_enabled(self.visual.picture_id.is_some());
+
+        // Only thumbnails of pictures can be regenerated one at a time.
+        let regenerate_thumbnail: RelmAction<RegenerateThumbnailAction> = {
+            let sender = self.sender.clone();
+            let picture_id = self.visual.picture_id;
+            RelmAction::new_stateless(move |_| {
+                if let Some(picture_id) = picture_id {
+                    sender.emit(AlbumInput::RegenerateThumbnail(picture_id));
+                }
+            })
+        };
+        regenerate_thumbnail
+            .gio_action()
+            .set_enabled(self.visual.picture_id.is_some());
+
+        // The file manager runs outside of the sandbox, so needs the host path.
+        let reveal: RelmAction<RevealAction> = {
+            let sender = self.sender.clone();
+            let path = self.visual.path().host_path.clone();
+            RelmAction::new_stateless(move |_| {
+                sender.emit(AlbumInput::RevealInFileManager(path.clone()))
+            })
+        };
+
+        // Apps are launched from within the sandbox, so need the sandbox path.
+        let open_default: RelmAction<OpenDefaultAction> = {
+            let sender = self.sender.clone();
+            let path = self.visual.sandbox_path().clone();
+            RelmAction::new_stateless(move |_| {
+                sender.emit(AlbumInput::OpenWithDefault(path.clone()))
+            })
+        };
+
+        let open_with: RelmAction<OpenWithAction> = {
+            let sender = self.sender.clone();
+            let path = self.visual.sandbox_path().clone();
+            RelmAction::new_stateless(move |_| {
+                sender.emit(AlbumInput::OpenWithChosen(path.clone()))
+            })
+        };
+
+        let copy_path: RelmAction<CopyPathAction> = {
+            let sender = self.sender.clone();
+            let path = self.visual.path().host_path.clone();
+            RelmAction::new_stateless(move |_| sender.emit(AlbumInput::CopyPath(path.clone())))
+        };
+
+        // Only pictures can be trashed.
+        let trash: RelmAction<TrashAction> = {
+            let sender = self.sender.clone();
+            let picture_id = self.visual.picture_id;
+            RelmAction::new_stateless(move |_| {
+                if let Some(picture_id) = picture_id {
+                    sender.emit(AlbumInput::Trash(picture_id));
+                }
+            })
+        };
+        trash
+            .gio_action()
+            .set_enabled(self.is_trashable && self.visual.picture_id.is_some());
+
+        let burst_id = self.visual.burst.map(|frame| frame.burst_id);
+
+        let collapse_burst: RelmAction<CollapseBurstAction> = {
+            let sender = self.sender.clone();
+            RelmAction::new_stateless(move |_| {
+                if let Some(burst_id) = burst_id {
+                    sender.emit(AlbumInput::CollapseBurst(burst_id));
+                }
+            })
+        };
+        collapse_burst
+            .gio_action()
+            .set_enabled(burst_id.is_some() && self.is_burst_expanded);
+
+        let ungroup_burst: RelmAction<UngroupBurstAction> = {
+            let sender = self.sender.clone();
+            RelmAction::new_stateless(move |_| {
+                if let Some(burst_id) = burst_id {
+                    sender.emit(AlbumInput::UngroupBurst(burst_id));
+                }
+            })
+        };
+        ungroup_burst.gio_action().set_enabled(burst_id.is_some());
+
+        let mut actions = RelmActionGroup::<ItemActionGroup>::new();
+        actions.add_action(open);
+        actions.add_action(info);
+        actions.add_action(folder_cover);
+        actions.add_action(favorite);
+        actions.add_action(regenerate_thumbnail);
+        actions.add_action(collapse_burst);
+        actions.add_action(ungroup_burst);
+        actions.add_action(open_default);
+        actions.add_action(open_with);
+        actions.add_action(reveal);
+        actions.add_action(copy_path);
+        actions.add_action(trash);
+        actions.register_for_widget(&*root);
     }
 
-    fn unbind(&mut self, widgets: &mut Self::Widgets, _root: &mut Self::Root) {
+    fn unbind(&mut self, widgets: &mut Self::Widgets, root: &mut Self::Root) {
+        if let Some(loader) = widgets.thumbnail_loader.take() {
+            loader.abort();
+        }
+        for handler in widgets.hover_handlers.drain(..) {
+            widgets.hover.disconnect(handler);
+        }
+        if let Some(handler) = widgets.drag_handler.take() {
+            widgets.drag_source.disconnect(handler);
+        }
+        if let Some(handler) = widgets.drop_handler.take() {
+            widgets.drop_target.disconnect(handler);
+        }
+        widgets.drop_target.set_actions(gdk::DragAction::empty());
+        stop_animation(&widgets.picture, &widgets.animation);
         widgets.picture.set_filename(None::<&Path>);
         widgets.motion_type_icon.set_icon_name(None);
         widgets.status_overlay.set_visible(false);
+        widgets.burst_overlay.set_visible(false);
+        widgets.burst_label.set_label("");
         widgets.duration_overlay.set_visible(false);
         widgets.duration_label.set_label("");
+        if let Some(handler) = widgets.favorite_handler.take() {
+            widgets.favorite_button.disconnect(handler);
+        }
+        if let Some(binding) = widgets.selectable_binding.take() {
+            binding.unbind();
+        }
+        root.insert_action_group("item", None::<&gio::ActionGroup>);
+    }
+}
+
+/// Decoded thumbnail, if it has been shown recently.
+fn cached_texture(thumbnail_path: &Path) -> Option<gdk::Texture> {
+    TEXTURE_CACHE.with_borrow_mut(|cache| cache.get(thumbnail_path))
+}
+
+/// Keeps a decoded thumbnail in memory for when it is next shown.
+fn cache_texture(thumbnail_path: PathBuf, texture: &gdk::Texture) {
+    let bytes = texture.width() as usize * texture.height() as usize * 4;
+    TEXTURE_CACHE.with_borrow_mut(|cache| cache.insert(thumbnail_path, texture.clone(), bytes));
+}
+
+/// Decodes a blurhash into a small texture. Scaling the texture up to
+/// the grid item size smooths it out.
+fn placeholder_texture(blurhash: &str) -> Option<gdk::Texture> {
+    let pixels = blurhash::decode(blurhash, PLACEHOLDER_EDGE_LENGTH, PLACEHOLDER_EDGE_LENGTH)?;
+    let texture = gdk::MemoryTexture::new(
+        PLACEHOLDER_EDGE_LENGTH as i32,
+        PLACEHOLDER_EDGE_LENGTH as i32,
+        gdk::MemoryFormat::R8g8b8a8,
+        &glib::Bytes::from_owned(pixels),
+        (PLACEHOLDER_EDGE_LENGTH * 4) as usize,
+    );
+    Some(texture.upcast())
+}
+
+/// Plays an animated preview on a loop until aborted.
+async fn play_animation(picture: gtk::Picture, file: gio::File) {
+    let image = match glycin::Loader::new(file).load().await {
+        Ok(image) => image,
+        Err(e) => {
+            debug!("Failed loading animated preview: {:?}", e);
+            return;
+        }
+    };
+
+    loop {
+        let frame = match image.next_frame().await {
+            Ok(frame) => frame,
+            Err(e) => {
+                debug!("Failed loading animated preview frame: {:?}", e);
+                return;
+            }
+        };
+
+        picture.set_paintable(Some(&frame.texture()));
+        glib::timeout_future(frame.delay().unwrap_or(DEFAULT_FRAME_DELAY)).await;
+    }
+}
+
+/// Stops a playing animated preview and restores the still thumbnail.
+fn stop_animation(picture: &gtk::Picture, animation: &RefCell<Option<Animation>>) {
+    if let Some(animation) = animation.take() {
+        animation.player.abort();
+        picture.set_paintable(animation.still.as_ref());
+    }
+}
+
+/// Keyboard shortcut sending an input message to the album.
+fn shortcut(
+    trigger: &str,
+    sender: &ComponentSender<Album>,
+    msg: fn() -> AlbumInput,
+) -> gtk::Shortcut {
+    let sender = sender.input_sender().clone();
+    let action = gtk::CallbackAction::new(move |_, _| {
+        sender.emit(msg());
+        glib::Propagation::Stop
+    });
+    gtk::Shortcut::new(gtk::ShortcutTrigger::parse_string(trigger), Some(action))
+}
+
+fn set_favorite_icon(button: &gtk::ToggleButton, is_favorite: bool) {
+    button.set_active(is_favorite);
+    if is_favorite {
+        button.set_icon_name("starred-symbolic");
+    } else {
+        button.set_icon_name("non-starred-symbolic");
     }
 }
 
@@ -247,10 +1087,58 @@ pub struct Album {
     active_view: ActiveView,
     view_name: ViewName,
     photo_grid: TypedGridView<PhotoGridItem, gtk::SingleSelection>,
+
+    /// Filter defining the album.
     filter: AlbumFilter,
-    sort: AlbumSort,
+
+    /// Filters added by the user to narrow the album. Applied together with `filter`
+    /// and shown as removable chips.
+    added_filters: Vec<AlbumFilter>,
+
+    /// File name search text.
+    search: Option<String>,
+
+    /// Items matching the filters that are yet to be added to the photo grid.
+    pending: VecDeque<Arc<fotema_core::visual::Visual>>,
+
+    /// Incremented whenever the photo grid is reloaded, so that chunks of an
+    /// earlier load aren't added.
+    load_generation: u64,
+
+    /// Bursts the user has expanded to show every frame.
+    expanded_bursts: HashSet<BurstId>,
+
+    /// Number of frames matching the filters in each collapsed burst.
+    burst_sizes: HashMap<BurstId, usize>,
+
+    chips: gtk::Box,
+
+    // For telling the user when a file couldn't be shown or opened outside of Fotema.
+    toasts: adw::ToastOverlay,
+
+    // Item whose file facts are shown over the album.
+    info: Option<Arc<fotema_core::visual::Visual>>,
+
+    sort: SortKey,
+    zoom: GridZoom,
+    layout: adaptive::Layout,
     edge_length: I32Binding,
+
+    // Activating items selects them, rather than opening them.
+    selection_mode: BoolBinding,
+
+    // Selection of the items matching the filters, in selection mode. It is the grid
+    // view's model in place of the photo grid's single selection.
+    multi_selection: Option<gtk::MultiSelection>,
+
+    // Number of selected items.
+    selected_count: usize,
+
     thumbnailer: Rc<Thumbnailer>,
+    input_sender: relm4::Sender<AlbumInput>,
+
+    // Fullscreen slideshow window. Built when first needed.
+    slideshow: Option<Controller<Slideshow>>,
 }
 
 #[relm4::component(pub)]
@@ -260,27 +1148,144 @@ impl SimpleComponent for Album {
     type Output = AlbumOutput;
 
     view! {
-        gtk::ScrolledWindow {
-            set_vexpand: true,
+        gtk::Box {
+            set_orientation: gtk::Orientation::Vertical,
+
+            #[local_ref]
+            chips -> gtk::Box {
+                set_orientation: gtk::Orientation::Horizontal,
+                set_spacing: 6,
+                set_margin_all: 6,
+                set_visible: false,
+            },
 
             #[local_ref]
-            grid_view -> gtk::GridView {
-                set_orientation: gtk::Orientation::Vertical,
-                set_single_click_activate: true,
+            toasts -> adw::ToastOverlay {
+                set_vexpand: true,
+
+                #[wrap(Some)]
+                set_child = &gtk::Overlay {
+                    #[wrap(Some)]
+                    set_child = &gtk::ScrolledWindow {
+                        set_vexpand: true,
+
+                        #[local_ref]
+                        grid_view -> gtk::GridView {
+                            set_orientation: gtk::Orientation::Vertical,
+                            set_single_click_activate: true,
+
+                            connect_activate[sender] => move |_, idx| {
+                                sender.input(AlbumInput::Selected(idx))
+                            },
+                        },
+
+                        #[wrap(Some)]
+                        set_vadjustment = &gtk::Adjustment {
+                            // Emit scroll events so PersonAlbum can determine when to hide avatar.
+                            // FIXME maybe just emit one event at a boundary, instead of emitting an
+                            // event for every scroll?
+                            connect_value_changed[sender] => move |v| sender.input(AlbumInput::ScrollOffset(v.value())),
+                        },
+                    },
+
+                    add_overlay = &gtk::Box {
+                        set_orientation: gtk::Orientation::Vertical,
+                        set_halign: gtk::Align::End,
+                        set_valign: gtk::Align::End,
+                        set_margin_all: 12,
+                        set_spacing: 6,
+                        add_css_class: "osd",
+                        add_css_class: "toolbar",
+                        #[watch]
+                        set_visible: model.info.is_some(),
+
+                        gtk::Button {
+                            set_halign: gtk::Align::End,
+                            set_icon_name: "window-close-symbolic",
+                            set_tooltip_text: Some(&fl!("album-info", "close")),
+                            add_css_class: "flat",
+                            add_css_class: "circular",
+                            connect_clicked => AlbumInput::HideInfo,
+                        },
 
-                connect_activate[sender] => move |_, idx| {
-                    sender.input(AlbumInput::Selected(idx))
+                        gtk::Label {
+                            set_xalign: 0.0,
+                            set_selectable: true,
+                            set_wrap: true,
+                            set_wrap_mode: gtk::pango::WrapMode::WordChar,
+                            set_max_width_chars: 50,
+                            #[watch]
+                            set_label: &model.info_text(),
+                        },
+                    },
                 },
             },
 
-            #[wrap(Some)]
-            set_vadjustment = &gtk::Adjustment {
-                // Emit scroll events so PersonAlbum can determine when to hide avatar.
-                // FIXME maybe just emit one event at a boundary, instead of emitting an
-                // event for every scroll?
-                connect_value_changed[sender] => move |v| sender.input(AlbumInput::ScrollOffset(v.value())),
-            },
+            gtk::ActionBar {
+                #[watch]
+                set_revealed: model.selection_mode.value(),
+
+                pack_start = &gtk::Button {
+                    set_label: &fl!("album-selection", "select-all"),
+                    connect_clicked => AlbumInput::SelectAll,
+                },
+
+                pack_start = &gtk::Button {
+                    set_label: &fl!("album-selection", "select-none"),
+                    connect_clicked => AlbumInput::SelectNone,
+                },
+
+                #[wrap(Some)]
+                set_center_widget = &gtk::Label {
+                    #[watch]
+                    set_label: &fl!("album-selection-count", count = model.selected_count),
+                },
 
+                pack_end = &gtk::Button {
+                    set_icon_name: "user-trash-symbolic",
+                    set_tooltip_text: Some(&fl!("album-selection", "trash")),
+                    set_visible: !is_trash,
+                    #[watch]
+                    set_sensitive: model.selected_count > 0,
+                    connect_clicked => AlbumInput::TrashSelected(true),
+                },
+
+                pack_end = &gtk::Button {
+                    set_icon_name: "starred-symbolic",
+                    set_tooltip_text: Some(&fl!("album-selection", "favorite")),
+                    set_visible: !is_trash,
+                    #[watch]
+                    set_sensitive: model.selected_count > 0,
+                    connect_clicked => AlbumInput::FavoriteSelected,
+                },
+
+                pack_end = &gtk::Button {
+                    set_icon_name: "avatar-default-symbolic",
+                    set_tooltip_text: Some(&fl!("album-selection", "person")),
+                    set_visible: !is_trash,
+                    #[watch]
+                    set_sensitive: model.selected_count > 0,
+                    connect_clicked => AlbumInput::AssignSelectedToPerson,
+                },
+
+                pack_end = &gtk::Button {
+                    set_icon_name: "folder-symbolic",
+                    set_tooltip_text: Some(&fl!("album-selection", "move")),
+                    set_visible: !is_trash,
+                    #[watch]
+                    set_sensitive: model.selected_count > 0,
+                    connect_clicked => AlbumInput::MoveSelectedDialog,
+                },
+
+                pack_end = &gtk::Button {
+                    set_label: &fl!("album-selection", "restore"),
+                    add_css_class: "suggested-action",
+                    set_visible: is_trash,
+                    #[watch]
+                    set_sensitive: model.selected_count > 0,
+                    connect_clicked => AlbumInput::TrashSelected(false),
+                },
+            },
         }
     }
 
@@ -292,20 +1297,80 @@ impl SimpleComponent for Album {
         let photo_grid = TypedGridView::new();
         let grid_view = &photo_grid.view.clone();
 
+        let chips = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+        let toasts = adw::ToastOverlay::new();
+
+        let is_trash = view_name == ViewName::Trash;
+
         let mut model = Album {
             state,
             active_view,
             view_name,
             photo_grid,
             filter,
-            sort: AlbumSort::default(),
-            edge_length: I32Binding::new(NARROW_EDGE_LENGTH),
+            added_filters: Vec::new(),
+            search: None,
+            pending: VecDeque::new(),
+            load_generation: 0,
+            expanded_bursts: HashSet::new(),
+            burst_sizes: HashMap::new(),
+            chips: chips.clone(),
+            toasts: toasts.clone(),
+            info: None,
+            sort: SortKey::default(),
+            zoom: GridZoom::default(),
+            layout: adaptive::Layout::default(),
+            edge_length: I32Binding::new(DEFAULT_EDGE_LENGTH),
+            selection_mode: BoolBinding::new(false),
+            multi_selection: None,
+            selected_count: 0,
             thumbnailer,
+            input_sender: sender.input_sender().clone(),
+            slideshow: None,
         };
 
         model.update_filter();
 
         let widgets = view_output!();
+
+        // Arrow keys and Enter are handled by the grid view itself. Keyboard focus
+        // moves the selection, so the focused item is the selected item.
+        // Capture phase, otherwise grid items consume space to select themselves.
+        let shortcuts = gtk::ShortcutController::new();
+        shortcuts.set_propagation_phase(gtk::PropagationPhase::Capture);
+        shortcuts.add_shortcut(shortcut("space", &sender, || AlbumInput::ToggleFocused));
+        shortcuts.add_shortcut(shortcut("Delete|KP_Delete", &sender, || {
+            AlbumInput::TrashFocused
+        }));
+        shortcuts.add_shortcut(shortcut("i", &sender, || AlbumInput::ToggleInfoFocused));
+        shortcuts.add_shortcut(shortcut("Escape", &sender, || AlbumInput::HideInfo));
+        grid_view.add_controller(shortcuts);
+
+        // Ctrl+scroll zooms the grid. Zoom is a setting shared by every album, so it
+        // goes through the window's zoom actions.
+        let zoom_scroll =
+            gtk::EventControllerScroll::new(gtk::EventControllerScrollFlags::VERTICAL);
+        zoom_scroll.set_propagation_phase(gtk::PropagationPhase::Capture);
+        let grid = grid_view.downgrade();
+        zoom_scroll.connect_scroll(move |controller, _dx, dy| {
+            if !controller
+                .current_event_state()
+                .contains(gdk::ModifierType::CONTROL_MASK)
+            {
+                return glib::Propagation::Proceed;
+            }
+            let action = if dy < 0.0 {
+                "win.zoom-in"
+            } else {
+                "win.zoom-out"
+            };
+            if let Some(grid) = grid.upgrade() {
+                let _ = grid.activate_action(action, None);
+            }
+            glib::Propagation::Stop
+        });
+        grid_view.add_controller(zoom_scroll);
+
         ComponentParts { model, widgets }
     }
 
@@ -313,25 +1378,67 @@ impl SimpleComponent for Album {
         match msg {
             AlbumInput::Activate => {
                 *self.active_view.write() = self.view_name;
-                if self.photo_grid.is_empty() {
+
+                // The day might have changed since the album was last shown.
+                let today = AlbumFilter::on_this_day();
+                if matches!(self.filter, AlbumFilter::OnThisDay { .. }) && self.filter != today {
+                    info!("Showing {:?}", today);
+                    self.filter = today;
+                    self.update_filter();
+                } else if self.photo_grid.is_empty() {
                     self.refresh();
                 }
             }
             AlbumInput::Refresh => {
+                // Thumbnails might have been remade since they were decoded.
+                TEXTURE_CACHE.with_borrow_mut(|cache| cache.clear());
+
                 if *self.active_view.read() == self.view_name {
                     info!("{:?} view is active so refreshing", self.view_name);
                     self.refresh();
                 } else {
                     info!("{:?} view is inactive so clearing", self.view_name);
-                    self.photo_grid.clear();
+                    self.unload();
+                }
+            }
+            AlbumInput::LoadMore(generation) => {
+                if generation == self.load_generation {
+                    self.load_chunk();
+                    self.load_more_later();
                 }
             }
             AlbumInput::Filter(filter) => {
                 self.filter = filter;
+                self.added_filters.clear();
                 self.update_filter();
                 //self.scroll();
             }
-            AlbumInput::Sort(sort) => {
+            AlbumInput::AddFilter(filter) => {
+                info!("Adding filter {:?}", filter);
+                self.added_filters
+                    .retain(|f| std::mem::discriminant(f) != std::mem::discriminant(&filter));
+                self.added_filters.push(filter);
+                self.update_filter();
+            }
+            AlbumInput::RemoveFilter(filter) => {
+                info!("Removing filter {:?}", filter);
+                self.added_filters.retain(|f| *f != filter);
+                self.update_filter();
+            }
+            AlbumInput::ClearFilters => {
+                self.added_filters.clear();
+                self.update_filter();
+            }
+            AlbumInput::Search(query) => {
+                let query = query.trim();
+                let search = (!query.is_empty()).then(|| query.to_string());
+                if self.search != search {
+                    info!("Searching for {:?}", search);
+                    self.search = search;
+                    self.update_filter();
+                }
+            }
+            AlbumInput::SortBy(sort) => {
                 if self.sort != sort {
                     info!("Sort order is now {:?}", sort);
                     self.sort = sort;
@@ -339,19 +1446,51 @@ impl SimpleComponent for Album {
                 }
             }
             AlbumInput::Selected(index) => {
+                if let Some(selection) = &self.multi_selection {
+                    if selection.is_selected(index) {
+                        selection.unselect_item(index);
+                    } else {
+                        selection.select_item(index, false);
+                    }
+                    return;
+                }
+
                 // Albums are filters so must use get_visible(...) over get(...), otherwise
                 // wrong photo is displayed.
                 if let Some(item) = self.photo_grid.get_visible(index) {
+                    // A collapsed burst expands rather than opening.
+                    if item.borrow().burst_size.is_some() {
+                        let burst_id = item.borrow().visual.burst.map(|frame| frame.burst_id);
+                        if let Some(burst_id) = burst_id {
+                            sender.input(AlbumInput::ExpandBurst(burst_id));
+                            return;
+                        }
+                    }
+
+                    // A blurred private picture asks for the PIN rather than opening.
+                    let thumbnail_hash = item.borrow().visual.thumbnail_hash();
+                    if self.thumbnailer.is_redacted(&thumbnail_hash) {
+                        let _ = self
+                            .photo_grid
+                            .view
+                            .activate_action("win.unlock-private", None);
+                        return;
+                    }
+
                     let visual_id = item.borrow().visual.visual_id.clone();
                     debug!("index {} has visual_id {}", index, visual_id);
-                    let _ = sender.output(AlbumOutput::Selected(visual_id, self.filter.clone()));
+                    let _ = sender.output(AlbumOutput::Selected(visual_id, self.combined_filter()));
                 }
             }
+            AlbumInput::Open(visual_id) => {
+                let _ = sender.output(AlbumOutput::Selected(visual_id, self.combined_filter()));
+            }
             AlbumInput::GoToMonth(ym) => {
                 info!("Showing for month: {}", ym);
                 let index_opt = self.photo_grid.find(|p| p.visual.year_month() == ym);
                 if let Some(index) = index_opt {
-                    let flags = gtk::ListScrollFlags::SELECT;
+                    // Move focus too, so screen readers announce the month's first item.
+                    let flags = gtk::ListScrollFlags::SELECT | gtk::ListScrollFlags::FOCUS;
                     debug!("Scrolling to {}", index);
                     self.photo_grid.view.scroll_to(index, flags, None);
                 }
@@ -364,51 +1503,760 @@ impl SimpleComponent for Album {
                         .scroll_to(0, gtk::ListScrollFlags::SELECT, None);
                 }
             }
-            AlbumInput::Adapt(adaptive::Layout::Narrow) => {
-                self.edge_length.set_value(NARROW_EDGE_LENGTH);
+            AlbumInput::RestoreScrollOffset(offset) => {
+                let Some(adjustment) = self.photo_grid.view.vadjustment() else {
+                    return;
+                };
+
+                // The grid's height isn't known until it has been laid out, so wait for
+                // the adjustment to change before scrolling. Only the first change comes
+                // from laying out the new model, so always disconnect after it.
+                let handler_id: Rc<Cell<Option<glib::SignalHandlerId>>> = Rc::default();
+                let handler_id_ref = handler_id.clone();
+                let id = adjustment.connect_changed(move |adjustment| {
+                    if let Some(id) = handler_id_ref.take() {
+                        adjustment.disconnect(id);
+                    }
+                    // Clamped to the scrollable range by the adjustment.
+                    adjustment.set_value(offset);
+                });
+                handler_id.set(Some(id));
+            }
+            AlbumInput::Adapt(layout) => {
+                self.layout = layout;
+                self.edge_length.set_value(self.zoom.edge_length(layout));
             }
-            AlbumInput::Adapt(adaptive::Layout::Wide) => {
-                self.edge_length.set_value(WIDE_EDGE_LENGTH);
+            AlbumInput::Zoom(zoom) => {
+                if self.zoom != zoom {
+                    info!("Grid zoom is now {:?}", zoom);
+                    self.zoom = zoom;
+                    // Items are bound to the edge length, so the grid is laid out again
+                    // without reloading.
+                    self.edge_length.set_value(zoom.edge_length(self.layout));
+                }
             }
             AlbumInput::ScrollOffset(offset) => {
                 let _ = sender.output(AlbumOutput::ScrollOffset(offset));
             }
+            AlbumInput::SetFavorite(picture_ids, is_favorite) => {
+                let _ = sender.output(AlbumOutput::SetFavorite(picture_ids, is_favorite));
+            }
+            AlbumInput::SetFolderCover(folder_path, picture_id) => {
+                info!("Choosing picture {} as cover of {:?}", picture_id, folder_path);
+                let _ = sender.output(AlbumOutput::SetFolderCover(folder_path, picture_id));
+            }
+            AlbumInput::MoveInUserAlbum(moved, target) => {
+                let Some(album_id) = self.user_album() else {
+                    return;
+                };
+
+                // Arrange every picture of the album, not just those matching added filters.
+                let mut visuals: Vec<_> = self
+                    .state
+                    .read()
+                    .iter()
+                    .filter(|visual| AlbumFilter::UserAlbum(album_id).filter(visual))
+                    .cloned()
+                    .collect();
+                sort_by_album_position(&mut visuals, album_id);
+
+                let mut order: Vec<PictureId> = visuals
+                    .iter()
+                    .filter_map(|visual| visual.picture_id)
+                    .collect();
+                let from = order.iter().position(|id| *id == moved);
+                let to = order.iter().position(|id| *id == target);
+                let (Some(from), Some(to)) = (from, to) else {
+                    return;
+                };
+
+                info!(
+                    "Moving picture {} to place of {} in album {}",
+                    moved, target, album_id
+                );
+                let picture_id = order.remove(from);
+                order.insert(to, picture_id);
+                let _ = sender.output(AlbumOutput::SetUserAlbumOrder(album_id, order));
+            }
+            AlbumInput::RegenerateThumbnail(picture_id) => {
+                info!("Regenerating thumbnail of picture {}", picture_id);
+                let _ = sender.output(AlbumOutput::RegenerateThumbnail(picture_id));
+            }
+            AlbumInput::RevealInFileManager(path) => {
+                let toasts = self.toasts.clone();
+                glib::spawn_future_local(async move {
+                    if let Err(e) = file_manager::reveal(&path).await {
+                        error!("Failed revealing {:?} in file manager: {}", path, e);
+                        toasts.add_toast(adw::Toast::new(&fl!("album-item-error", "reveal")));
+                    }
+                });
+            }
+            AlbumInput::OpenWithDefault(path) => {
+                if let Err(e) = file_manager::open_with_default(&self.photo_grid.view, &path) {
+                    error!("Failed opening {:?} with default app: {}", path, e);
+                    self.toasts
+                        .add_toast(adw::Toast::new(&fl!("album-item-error", "open")));
+                }
+            }
+            AlbumInput::OpenWithChosen(path) => {
+                let toasts = self.toasts.clone();
+                let view = self.photo_grid.view.clone();
+                glib::spawn_future_local(async move {
+                    if let Err(e) = file_manager::open_with_chosen(&view, &path).await {
+                        error!("Failed opening {:?} with chosen app: {}", path, e);
+                        toasts.add_toast(adw::Toast::new(&fl!("album-item-error", "open")));
+                    }
+                });
+            }
+            AlbumInput::CopyPath(path) => {
+                file_manager::copy_path(&self.photo_grid.view, &path);
+            }
+            AlbumInput::Trash(picture_id) => {
+                info!("Trashing picture {}", picture_id);
+                let _ = sender.output(AlbumOutput::SetTrashed(vec![picture_id], true));
+            }
+            AlbumInput::ExpandBurst(burst_id) => {
+                info!("Expanding burst {}", burst_id);
+                self.expanded_bursts.insert(burst_id);
+
+                let Some(position) = self.photo_grid.find(|item| is_frame(item, burst_id)) else {
+                    return;
+                };
+
+                // Swap the stacked item for every frame matching the filters, in place,
+                // so that the grid doesn't scroll away from the burst.
+                let filter = self.combined_filter();
+                let mut frames: Vec<_> = self
+                    .state
+                    .read()
+                    .iter()
+                    .filter(|visual| visual.burst.is_some_and(|frame| frame.burst_id == burst_id))
+                    .filter(|visual| filter.clone().filter(visual))
+                    .cloned()
+                    .collect();
+                self.sort.sort(&mut frames);
+
+                self.photo_grid.remove(position);
+                for (offset, visual) in frames.into_iter().enumerate() {
+                    let item = self.grid_item(visual);
+                    self.photo_grid.insert(position + offset as u32, item);
+                }
+
+                self.photo_grid
+                    .view
+                    .scroll_to(position, gtk::ListScrollFlags::FOCUS, None);
+            }
+            AlbumInput::CollapseBurst(burst_id) => {
+                info!("Collapsing burst {}", burst_id);
+                self.expanded_bursts.remove(&burst_id);
+
+                let Some(position) = self.photo_grid.find(|item| is_frame(item, burst_id)) else {
+                    return;
+                };
+
+                let mut frames = Vec::new();
+                while let Some(index) = self.photo_grid.find(|item| is_frame(item, burst_id)) {
+                    if let Some(item) = self.photo_grid.get(index) {
+                        frames.push(item.borrow().visual.clone());
+                    }
+                    self.photo_grid.remove(index);
+                }
+
+                let sizes = collapse_bursts(&mut frames, &self.expanded_bursts);
+                self.burst_sizes.extend(sizes);
+
+                for visual in frames {
+                    let item = self.grid_item(visual);
+                    self.photo_grid.insert(position, item);
+                }
+
+                self.photo_grid
+                    .view
+                    .scroll_to(position, gtk::ListScrollFlags::FOCUS, None);
+            }
+            AlbumInput::UngroupBurst(burst_id) => {
+                info!("Ungrouping burst {}", burst_id);
+                self.expanded_bursts.remove(&burst_id);
+                let _ = sender.output(AlbumOutput::UngroupBurst(burst_id));
+            }
+            AlbumInput::EnterSelectionMode => {
+                self.set_selection_mode(true);
+            }
+            AlbumInput::ExitSelectionMode => {
+                self.set_selection_mode(false);
+            }
+            AlbumInput::SelectionChanged => {
+                self.selected_count = self
+                    .multi_selection
+                    .as_ref()
+                    .map_or(0, |selection| selection.selection().size() as usize);
+            }
+            AlbumInput::SelectAll => {
+                if let Some(selection) = &self.multi_selection {
+                    selection.select_all();
+                }
+            }
+            AlbumInput::SelectNone => {
+                self.select_none();
+            }
+            AlbumInput::FavoriteSelected => {
+                let picture_ids = self.selected_picture_ids();
+                info!("Adding {} pictures to favorites", picture_ids.len());
+                let _ = sender.output(AlbumOutput::SetFavorite(picture_ids, true));
+            }
+            AlbumInput::TrashSelected(is_trashed) => {
+                let picture_ids = self.selected_picture_ids();
+                info!("Setting {} pictures trashed to {}", picture_ids.len(), is_trashed);
+                self.select_none();
+                let _ = sender.output(AlbumOutput::SetTrashed(picture_ids, is_trashed));
+            }
+            AlbumInput::MoveSelectedDialog => {
+                let dialog = gtk::FileDialog::builder()
+                    .title(fl!("album-move-folder-dialog"))
+                    .modal(true)
+                    .build();
+
+                let window = self.photo_grid.view.root().and_downcast::<gtk::Window>();
+                let sender = self.input_sender.clone();
+
+                dialog.select_folder(window.as_ref(), gio::Cancellable::NONE, move |result| {
+                    match result.map(|folder| folder.path()) {
+                        Ok(Some(path)) => sender.emit(AlbumInput::MoveSelected(path)),
+                        Ok(None) => error!("Chosen folder has no path"),
+                        Err(e) => info!("No folder chosen: {}", e),
+                    }
+                });
+            }
+            AlbumInput::MoveSelected(dest_folder) => {
+                let picture_ids = self.selected_picture_ids();
+                info!("Moving {} pictures to {:?}", picture_ids.len(), dest_folder);
+                self.select_none();
+                let _ = sender.output(AlbumOutput::MovePictures(picture_ids, dest_folder));
+            }
+            AlbumInput::AssignSelectedToPerson => {
+                let picture_ids = self.selected_picture_ids();
+                info!("Assigning {} pictures to a person", picture_ids.len());
+                self.select_none();
+                let _ = sender.output(AlbumOutput::AssignToPerson(picture_ids));
+            }
+            AlbumInput::ToggleFocused => {
+                // Focus doesn't move the selection in selection mode, so activate the
+                // focused item to toggle its selection, as clicking it would.
+                if self.selection_mode.value() {
+                    let grid_view = self.photo_grid.view.upcast_ref::<gtk::Widget>();
+                    let focus = grid_view.root().and_then(|root| root.focus());
+                    let item_widget = std::iter::successors(focus, |widget| widget.parent())
+                        .find(|widget| widget.parent().as_ref() == Some(grid_view));
+                    if let Some(item_widget) = item_widget {
+                        item_widget.activate();
+                    }
+                    return;
+                }
+
+                let Some(item) = self.focused_item() else {
+                    return;
+                };
+                let item = item.borrow();
+
+                if let Some(picture_id) = item.visual.picture_id {
+                    let is_favorite = !item.visual.is_favorite();
+                    let _ = sender.output(AlbumOutput::SetFavorite(vec![picture_id], is_favorite));
+                }
+            }
+            AlbumInput::ShowInfo(visual) => {
+                self.info = Some(visual);
+            }
+            AlbumInput::ToggleInfoFocused => {
+                if self.info.is_some() {
+                    self.info = None;
+                } else {
+                    self.info = self.focused_item().map(|item| item.borrow().visual.clone());
+                }
+            }
+            AlbumInput::HideInfo => {
+                self.info = None;
+            }
+            AlbumInput::TrashFocused => {
+                // Items leave the trash by being restored or by emptying the trash.
+                if self.view_name == ViewName::Trash {
+                    return;
+                }
+
+                let picture_id = self
+                    .focused_item()
+                    .and_then(|item| item.borrow().visual.picture_id);
+
+                if let Some(picture_id) = picture_id {
+                    info!("Trashing focused picture {}", picture_id);
+                    let _ = sender.output(AlbumOutput::SetTrashed(vec![picture_id], true));
+                }
+            }
+            AlbumInput::Slideshow(interval) => {
+                let n_items = self.photo_grid.selection_model.n_items();
+                let visuals: Vec<Arc<fotema_core::visual::Visual>> = (0..n_items)
+                    .filter_map(|index| self.photo_grid.get_visible(index))
+                    .map(|item| item.borrow().visual.clone())
+                    .collect();
+
+                if visuals.is_empty() {
+                    info!("No items for slideshow");
+                    return;
+                }
+
+                // The slideshow is a separate window, so the grid stays where it was
+                // and is there to return to when the slideshow exits.
+                let index = self.photo_grid.selection_model.selected() as usize;
+                let index = if index < visuals.len() { index } else { 0 };
+
+                let slideshow = self.slideshow.get_or_insert_with(|| {
+                    Slideshow::builder()
+                        .launch(self.thumbnailer.clone())
+                        .detach()
+                });
+
+                let window = self.photo_grid.view.root().and_downcast::<gtk::Window>();
+                slideshow.widget().set_transient_for(window.as_ref());
+
+                slideshow.emit(SlideshowInput::Start {
+                    visuals,
+                    index,
+                    interval,
+                });
+            }
         }
     }
 }
 
 impl Album {
     fn refresh(&mut self) {
-        let mut all = {
-            let data = self.state.read();
-            data.iter()
-                .map(|visual| PhotoGridItem {
-                    visual: visual.clone(),
-                    edge_length: self.edge_length.clone(),
-                    thumbnailer: self.thumbnailer.clone(),
-                })
-                .collect::<Vec<PhotoGridItem>>()
-        };
+        let mut visuals = self.state.read().clone();
 
         // State is always in ascending time order
-        self.sort.sort(&mut all);
+        match self.filter {
+            AlbumFilter::UserAlbum(album_id) => sort_by_album_position(&mut visuals, album_id),
+            AlbumFilter::OnThisDay { .. } => sort_by_year_descending(&mut visuals),
+            _ => self.sort.sort(&mut visuals),
+        }
 
-        self.photo_grid.clear();
+        // Keep the info panel up to date, such as after starring the item it shows.
+        if let Some(info) = &self.info {
+            self.info = visuals
+                .iter()
+                .find(|visual| visual.visual_id == info.visual_id)
+                .cloned();
+        }
+
+        // Only items matching the filters are loaded, so the first chunk fills the
+        // screen however narrow the filters are.
+        let filter = self.combined_filter();
+        visuals.retain(|visual| filter.clone().filter(visual));
+
+        self.burst_sizes = collapse_bursts(&mut visuals, &self.expanded_bursts);
+
+        self.unload();
+        self.pending = visuals.into();
 
-        //self.photo_grid.add_filter(move |item| (self.photo_grid_filter)(&item.picture));
-        self.photo_grid.extend_from_iter(all);
+        info!("{} items to add to album", self.pending.len());
 
-        info!("{} items added to album", self.photo_grid.len());
+        self.load_chunk();
 
         // NOTE person album will in effect overide scrolling to the end
         // by sending a ScrollToTop command.
-        self.sort.scroll_to_end(&mut self.photo_grid);
+        self.direction().scroll_to_end(&mut self.photo_grid);
+
+        // Scrolling to the end selects the end item, and toggling the filters
+        // replaced the model of the items matching them.
+        self.reset_selection();
+
+        self.load_more_later();
+    }
+
+    /// Removes all items from the photo grid, and stops adding pending items.
+    fn unload(&mut self) {
+        self.photo_grid.clear();
+        self.pending.clear();
+        self.load_generation += 1;
+    }
+
+    /// Adds the next chunk of pending items to the photo grid.
+    /// Items are added outwards from the end of the album that is scrolled to first,
+    /// so a chunk always lands off screen. Items inserted before the visible items
+    /// don't move them, because the grid view keeps its scroll anchor on an item.
+    fn load_chunk(&mut self) {
+        let count = CHUNK_SIZE.min(self.pending.len());
+        if count == 0 {
+            return;
+        }
+
+        let visuals: Vec<_> = match self.direction() {
+            AlbumSort::Ascending => self.pending.drain(self.pending.len() - count..).collect(),
+            AlbumSort::Descending => self.pending.drain(..count).collect(),
+        };
+
+        let items: Vec<PhotoGridItem> = visuals
+            .into_iter()
+            .map(|visual| self.grid_item(visual))
+            .collect();
+
+        if self.direction() == AlbumSort::Ascending && !self.photo_grid.is_empty() {
+            for item in items.into_iter().rev() {
+                self.photo_grid.insert(0, item);
+            }
+        } else {
+            self.photo_grid.extend_from_iter(items);
+        }
+
+        if self.pending.is_empty() {
+            info!("{} items added to album", self.photo_grid.len());
+        }
+    }
+
+    /// Adds the next chunk once the grid has drawn the items already added.
+    fn load_more_later(&self) {
+        if self.pending.is_empty() {
+            return;
+        }
+
+        let sender = self.input_sender.clone();
+        let generation = self.load_generation;
+        glib::idle_add_local_once(move || sender.emit(AlbumInput::LoadMore(generation)));
+    }
+
+    fn grid_item(&self, visual: Arc<fotema_core::visual::Visual>) -> PhotoGridItem {
+        let burst_id = visual.burst.map(|frame| frame.burst_id);
+        let is_burst_expanded = burst_id.is_some_and(|id| self.expanded_bursts.contains(&id));
+
+        // A burst with only one frame matching the filters isn't shown as a stack.
+        let burst_size = burst_id
+            .filter(|_| !is_burst_expanded)
+            .and_then(|id| self.burst_sizes.get(&id).copied())
+            .filter(|size| *size > 1);
+
+        PhotoGridItem {
+            visual,
+            sender: self.input_sender.clone(),
+            edge_length: self.edge_length.clone(),
+            selection_mode: self.selection_mode.clone(),
+            is_trashable: self.view_name != ViewName::Trash,
+            burst_size,
+            is_burst_expanded,
+            is_reorderable: self.user_album().is_some(),
+            thumbnailer: self.thumbnailer.clone(),
+        }
+    }
+
+    /// User album shown, if the album is one.
+    fn user_album(&self) -> Option<UserAlbumId> {
+        match self.filter {
+            AlbumFilter::UserAlbum(album_id) => Some(album_id),
+            _ => None,
+        }
+    }
+
+    /// End of the album that is loaded and scrolled to first. User albums start from
+    /// the top, as the user arranged them, whatever the sort order. So does On This Day,
+    /// with the most recent year at the top.
+    fn direction(&self) -> AlbumSort {
+        if matches!(
+            self.filter,
+            AlbumFilter::UserAlbum(_) | AlbumFilter::OnThisDay { .. }
+        ) {
+            AlbumSort::Descending
+        } else {
+            AlbumSort::from(self.sort)
+        }
+    }
+
+    /// Picture IDs of visible items that are selected, for bulk operations.
+    fn selected_picture_ids(&self) -> Vec<PictureId> {
+        let Some(selection) = &self.multi_selection else {
+            return Vec::new();
+        };
+
+        let selected = selection.selection();
+        (0..selected.size())
+            .filter_map(|n| self.photo_grid.get_visible(selected.nth(n as u32)))
+            .filter_map(|item| item.borrow().visual.picture_id)
+            .collect()
+    }
+
+    /// Facts about the file of the item in the info panel, one per line.
+    fn info_text(&self) -> String {
+        let Some(visual) = &self.info else {
+            return String::new();
+        };
+
+        let path = &visual.path().host_path;
+
+        let name = path
+            .file_name()
+            .map(|x| x.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let dimensions = match visual.picture_dimensions {
+            Some((width, height)) => {
+                fl!("album-info", "dimensions", width = width, height = height)
+            }
+            None => fl!("album-info", "dimensions-unknown"),
+        };
+
+        let file_size = match visual.byte_size() {
+            Some(byte_size) => fl!(
+                "album-info",
+                "file-size",
+                size = format_size(byte_size, DECIMAL)
+            ),
+            None => fl!("album-info", "file-size-unknown"),
+        };
+
+        let yes_no = |value: bool| if value { "yes" } else { "no" };
+
+        [
+            fl!("album-info", "file-name", name = name),
+            dimensions,
+            file_size,
+            fl!("album-info", "path", path = path.to_string_lossy()),
+            fl!(
+                "album-info",
+                "favorite",
+                is_favorite = yes_no(visual.is_favorite())
+            ),
+            fl!(
+                "album-info",
+                "broken",
+                is_broken = yes_no(self.thumbnailer.is_failed(path))
+            ),
+        ]
+        .join("\n")
+    }
+
+    /// Item with keyboard focus. Focus moves the selection, so this is the selected item.
+    /// Not known in selection mode, where focus doesn't move the selection.
+    fn focused_item(&self) -> Option<TypedListItem<PhotoGridItem>> {
+        if self.multi_selection.is_some() {
+            return None;
+        }
+        let index = self.photo_grid.selection_model.selected();
+        self.photo_grid.get_visible(index)
+    }
+
+    fn select_none(&self) {
+        if let Some(selection) = &self.multi_selection {
+            selection.unselect_all();
+        }
+    }
+
+    /// Enter or leave selection mode, with nothing selected.
+    fn set_selection_mode(&mut self, is_selection_mode: bool) {
+        self.selection_mode.set_value(is_selection_mode);
+
+        // Changing the grid view's model loses the scroll offset.
+        let offset = self
+            .photo_grid
+            .view
+            .vadjustment()
+            .map(|adjustment| adjustment.value());
+        self.reset_selection();
+        if let Some(offset) = offset {
+            self.input_sender
+                .emit(AlbumInput::RestoreScrollOffset(offset));
+        }
+    }
+
+    /// Select nothing. In selection mode, the grid view gets a new multi selection of
+    /// the items matching the filters, because the photo grid replaces the model of
+    /// those items whenever the filters change.
+    fn reset_selection(&mut self) {
+        self.selected_count = 0;
+
+        if !self.selection_mode.value() {
+            self.multi_selection = None;
+            self.photo_grid
+                .view
+                .set_model(Some(&self.photo_grid.selection_model));
+            return;
+        }
+
+        let selection = gtk::MultiSelection::new(self.photo_grid.selection_model.model());
+
+        let sender = self.input_sender.clone();
+        selection.connect_selection_changed(move |_, _, _| {
+            sender.emit(AlbumInput::SelectionChanged);
+        });
+
+        // Removed items leave the selection without a selection change.
+        let sender = self.input_sender.clone();
+        selection.connect_items_changed(move |_, _, _, _| {
+            sender.emit(AlbumInput::SelectionChanged);
+        });
+
+        self.photo_grid.view.set_model(Some(&selection));
+        self.multi_selection = Some(selection);
+    }
+
+    /// Album filter together with any filters added by the user.
+    fn combined_filter(&self) -> AlbumFilter {
+        let mut filters = vec![self.filter.clone()];
+        filters.extend(self.added_filters.iter().cloned());
+        filters.extend(self.search.clone().map(AlbumFilter::Search));
+        AlbumFilter::and(filters)
     }
 
     fn update_filter(&mut self) {
         self.photo_grid.clear_filters();
-        let filter = self.filter.clone();
+        let filter = self.combined_filter();
         self.photo_grid
             .add_filter(move |item| filter.clone().filter(&item.visual));
+        self.reset_selection();
+        self.update_chips();
+
+        // Items that didn't match the old filters were never loaded, so start loading
+        // again from the first chunk. An inactive album loads when next activated.
+        if *self.active_view.read() == self.view_name {
+            self.refresh();
+        } else {
+            self.unload();
+        }
+    }
+
+    /// Show a removable chip for each added filter.
+    fn update_chips(&self) {
+        while let Some(child) = self.chips.first_child() {
+            self.chips.remove(&child);
+        }
+
+        for filter in &self.added_filters {
+            let Some(label) = chip_label(filter) else {
+                continue;
+            };
+
+            let content = adw::ButtonContent::builder()
+                .label(label)
+                .icon_name("window-close-symbolic")
+                .build();
+
+            let chip = gtk::Button::builder()
+                .child(&content)
+                .tooltip_text(fl!("album-filter-chip", "remove"))
+                .css_classes(["pill", "small"])
+                .build();
+
+            {
+                let sender = self.input_sender.clone();
+                let filter = filter.clone();
+                chip.connect_clicked(move |_| {
+                    sender.emit(AlbumInput::RemoveFilter(filter.clone()));
+                });
+            }
+
+            self.chips.append(&chip);
+        }
+
+        if self.added_filters.len() > 1 {
+            let clear = gtk::Button::builder()
+                .label(fl!("album-filter-chip", "clear"))
+                .css_classes(["flat"])
+                .build();
+
+            let sender = self.input_sender.clone();
+            clear.connect_clicked(move |_| sender.emit(AlbumInput::ClearFilters));
+
+            self.chips.append(&clear);
+        }
+
+        self.chips.set_visible(!self.added_filters.is_empty());
+    }
+}
+
+/// Is the grid item a frame of the burst?
+fn is_frame(item: &PhotoGridItem, burst_id: BurstId) -> bool {
+    item.visual
+        .burst
+        .is_some_and(|frame| frame.burst_id == burst_id)
+}
+
+/// Keeps one frame of each collapsed burst, preferring the cover, and drops the others.
+/// The cover might not match the filters, in which case the first frame is kept.
+/// Returns the number of frames in each collapsed burst.
+fn collapse_bursts(
+    visuals: &mut Vec<Arc<fotema_core::visual::Visual>>,
+    expanded_bursts: &HashSet<BurstId>,
+) -> HashMap<BurstId, usize> {
+    let collapsed = |visual: &fotema_core::visual::Visual| {
+        visual
+            .burst
+            .filter(|frame| !expanded_bursts.contains(&frame.burst_id))
+    };
+
+    let mut sizes: HashMap<BurstId, usize> = HashMap::new();
+    let mut shown: HashMap<BurstId, VisualId> = HashMap::new();
+
+    for visual in visuals.iter() {
+        let Some(frame) = collapsed(visual) else {
+            continue;
+        };
+
+        *sizes.entry(frame.burst_id).or_default() += 1;
+
+        if frame.is_cover {
+            shown.insert(frame.burst_id, visual.visual_id.clone());
+        } else {
+            shown
+                .entry(frame.burst_id)
+                .or_insert_with(|| visual.visual_id.clone());
+        }
+    }
+
+    visuals.retain(|visual| match collapsed(visual) {
+        Some(frame) => shown.get(&frame.burst_id) == Some(&visual.visual_id),
+        None => true,
+    });
+
+    sizes
+}
+
+/// Puts the pictures of a user album in the order the user arranged.
+fn sort_by_album_position(visuals: &mut [Arc<fotema_core::visual::Visual>], album_id: UserAlbumId) {
+    visuals.sort_by_cached_key(|visual| {
+        visual
+            .user_albums
+            .iter()
+            .find(|entry| entry.album_id == album_id)
+            .map(|entry| entry.position)
+    });
+}
+
+/// Puts the newest year first, keeping items in ascending time order within a year.
+/// Items must already be in ascending time order.
+fn sort_by_year_descending(visuals: &mut [Arc<fotema_core::visual::Visual>]) {
+    // Stable sort, so items in the same year stay in ascending order.
+    visuals.sort_by_key(|visual| std::cmp::Reverse(visual.ordering_ts.year()));
+}
+
+/// Text for a chip describing a filter the user has added.
+fn chip_label(filter: &AlbumFilter) -> Option<String> {
+    match filter {
+        AlbumFilter::MediaType(MediaType::Photo) => Some(fl!("album-filter-chip", "photos")),
+        AlbumFilter::MediaType(MediaType::Video) => Some(fl!("album-filter-chip", "videos")),
+        AlbumFilter::Favorites => Some(fl!("album-filter-chip", "favorites")),
+        AlbumFilter::Private => Some(fl!("album-filter-chip", "private")),
+        AlbumFilter::Motion => Some(fl!("album-filter-chip", "motion")),
+        AlbumFilter::Selfies => Some(fl!("album-filter-chip", "selfies")),
+        AlbumFilter::Folder(path) => path.file_name().map(|x| x.to_string_lossy().to_string()),
+        AlbumFilter::Place(place_name) => Some(place_name.clone()),
+        AlbumFilter::Tag(name) => Some(fl!("album-filter-chip", "tag", name = name.clone())),
+        AlbumFilter::DateRange { from, to } => {
+            let format = |ts: &Option<DateTime<Utc>>| {
+                ts.map(|ts| ts.with_timezone(&Local).format("%x").to_string())
+                    .unwrap_or(String::from("…"))
+            };
+            Some(format!("{} – {}", format(from), format(to)))
+        }
+        AlbumFilter::Year(year) => Some(year.to_string()),
+        AlbumFilter::YearMonth(year, month) => i32::try_from(*year)
+            .ok()
+            .and_then(|year| NaiveDate::from_ymd_opt(year, *month, 1))
+            .map(|date| date.format("%B %Y").to_string()),
+        _ => None,
     }
 }
@@ -4,11 +4,16 @@
 
 use std::path::PathBuf;
 
+use chrono::{DateTime, Datelike, Local, NaiveDate, Utc};
 use fotema_core::PictureId;
 use fotema_core::Visual;
 use fotema_core::VisualId;
+use fotema_core::photo::model::DatePrecision;
+use fotema_core::user_album::UserAlbumId;
 use h3o::CellIndex;
 
+use crate::app::components::progress_monitor::MediaType;
+
 // An album is a view applied over the whole collection of messages.
 // An AlbumFilter defines the filter to apply to produce an album.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -19,25 +24,69 @@ pub enum AlbumFilter {
     // Show a single photo
     One(VisualId),
 
-    // Show all photos
+    /// Show all photos. Identity for combining filters with `And`.
     All,
 
     // Show only selfies
     Selfies,
 
-    // Show only videos
-    Videos,
+    /// Show only photos or only videos.
+    /// Motion photos are photos.
+    MediaType(MediaType),
 
     // Show only motion photos (live photos)
     Motion,
 
+    // Show only photos starred as favorites
+    Favorites,
+
+    /// Show only photos marked private.
+    Private,
+
     // Show photos only for folder
     Folder(PathBuf),
 
     // Show photos in a geographic area
     GeographicArea(CellIndex),
 
-    /// Show photos who's picture_id is in a set. Used for person filtering.
+    /// Show photos taken in a named place, such as "Paris, France".
+    /// Photos without a known place are never shown.
+    Place(String),
+
+    /// Show photos taken between two timestamps. Both bounds are inclusive.
+    /// A missing bound leaves that end of the range open.
+    /// Photos with an imprecise date are shown only if the range covers all of
+    /// the year or month they might have been taken in.
+    DateRange {
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    },
+
+    /// Show photos taken on a month and day in any year.
+    /// Photos dated to only a year or month are never shown.
+    OnThisDay { month: u32, day: u32 },
+
+    /// Show photos taken in a year, including photos dated to only the year.
+    Year(u32),
+
+    /// Show photos taken in a month of a year, such as (1985, 6) for June 1985.
+    /// Photos dated to only the year are never shown.
+    YearMonth(u32, u32),
+
+    /// Show photos with a file name containing the text, ignoring case.
+    Search(String),
+
+    /// Show photos tagged with a tag. Matches tag names ignoring case.
+    Tag(String),
+
+    /// Show photos the user has put in an album, in the order the user has arranged.
+    UserAlbum(UserAlbumId),
+
+    /// Show photos matching every filter.
+    And(Vec<AlbumFilter>),
+
+    /// Show photos who's picture_id is in a set. Used for person filtering, and for
+    /// photos that look alike.
     /// FIXME should probably be a Set of some kind... but that mucks up PartialEq and Eq.
     Any(Vec<PictureId>),
 }
@@ -51,7 +100,10 @@ impl AlbumFilter {
             AlbumFilter::Folder(path) => v.parent_path == path,
             AlbumFilter::Motion => v.is_motion_photo(),
             AlbumFilter::Selfies => v.is_selfie(),
-            AlbumFilter::Videos => v.is_video_only() && !v.is_motion_photo(),
+            AlbumFilter::Favorites => v.is_favorite(),
+            AlbumFilter::Private => v.is_private,
+            AlbumFilter::MediaType(MediaType::Photo) => v.picture_id.is_some(),
+            AlbumFilter::MediaType(MediaType::Video) => v.is_video_only() && !v.is_motion_photo(),
             AlbumFilter::GeographicArea(cell_index) => {
                 if let Some(location) = v.location {
                     let cell = location.to_cell(cell_index.resolution());
@@ -60,9 +112,81 @@ impl AlbumFilter {
                     false
                 }
             }
+            AlbumFilter::Place(place_name) => v.place_name.as_ref() == Some(&place_name),
+            AlbumFilter::DateRange { from, to } => is_taken_between(v, from, to),
+            AlbumFilter::OnThisDay { month, day } => {
+                !v.is_taken_at_imprecise()
+                    && fotema_core::time::is_on_this_day(v.ordering_ts.date_naive(), month, day)
+            }
+            AlbumFilter::Year(year) => period(year, 1, DatePrecision::Year)
+                .is_some_and(|(from, to)| is_taken_between(v, Some(from), Some(to))),
+            AlbumFilter::YearMonth(year, month) => period(year, month, DatePrecision::Month)
+                .is_some_and(|(from, to)| is_taken_between(v, Some(from), Some(to))),
+            AlbumFilter::Search(query) => v.path().file_name_contains(&query),
+            AlbumFilter::Tag(name) => {
+                let name = fotema_core::tag::fold(&name);
+                v.tags.iter().any(|tag| fotema_core::tag::fold(tag) == name)
+            }
+            AlbumFilter::UserAlbum(album_id) => {
+                v.user_albums.iter().any(|entry| entry.album_id == album_id)
+            }
+            AlbumFilter::And(filters) => filters.into_iter().all(|filter| filter.filter(v)),
             AlbumFilter::Any(picture_ids) => {
                 v.picture_id.is_some_and(|id| picture_ids.contains(&id))
             }
         }
     }
+
+    /// Photos taken on today's month and day in any year.
+    pub fn on_this_day() -> AlbumFilter {
+        let today = Local::now().date_naive();
+        AlbumFilter::OnThisDay {
+            month: today.month(),
+            day: today.day(),
+        }
+    }
+
+    /// Photos taken in the same year and month as a photo, or the same year if
+    /// the photo is dated to only the year.
+    pub fn taken_in_same_period(v: &Visual) -> AlbumFilter {
+        if v.taken_at_precision() == DatePrecision::Year {
+            AlbumFilter::Year(v.year())
+        } else {
+            let date = v.ordering_ts.date_naive();
+            AlbumFilter::YearMonth(v.year(), date.month())
+        }
+    }
+
+    /// Combines filters so that photos must match all of them.
+    /// `All` filters are dropped and a single remaining filter is returned as is.
+    pub fn and(filters: Vec<AlbumFilter>) -> AlbumFilter {
+        let mut filters: Vec<AlbumFilter> = filters
+            .into_iter()
+            .filter(|filter| *filter != AlbumFilter::All)
+            .collect();
+
+        match filters.len() {
+            0 => AlbumFilter::All,
+            1 => filters.remove(0),
+            _ => AlbumFilter::And(filters),
+        }
+    }
+}
+
+/// Is every moment the photo might have been taken between the bounds?
+fn is_taken_between(v: &Visual, from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> bool {
+    let (start, end) = v.taken_at_span();
+    from.is_none_or(|from| from <= start) && to.is_none_or(|to| end <= to)
+}
+
+/// First and last moments of a year or month. In UTC, like `Visual::year_month`.
+fn period(
+    year: u32,
+    month: u32,
+    precision: DatePrecision,
+) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let start = NaiveDate::from_ymd_opt(i32::try_from(year).ok()?, month, 1)?
+        .and_hms_opt(0, 0, 0)?
+        .and_utc();
+    Some((start, precision.period_end(start)))
 }
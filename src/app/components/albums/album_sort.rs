@@ -2,8 +2,10 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use fotema_core::Visual;
 use relm4::gtk;
 use relm4::typed_view::grid::{RelmGridItem, TypedGridView};
+use std::sync::Arc;
 use strum::AsRefStr;
 use strum::EnumString;
 use strum::FromRepr;
@@ -56,3 +58,60 @@ impl AlbumSort {
         }
     }
 }
+
+// Order of items in an album
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, EnumString, AsRefStr, FromRepr)]
+#[repr(u32)]
+pub enum SortKey {
+    // Oldest capture date first
+    #[strum(to_string = "CaptureDateAsc", serialize = "Ascending")]
+    CaptureDateAsc,
+
+    // Newest capture date first
+    #[default]
+    #[strum(to_string = "CaptureDateDesc", serialize = "Descending")]
+    CaptureDateDesc,
+
+    // File name in alphabetical order
+    FileName,
+
+    // Largest file first
+    FileSize,
+}
+
+impl SortKey {
+    /// Sort visual items, which must already be in ascending capture date order.
+    pub fn sort(&self, visuals: &mut [Arc<Visual>]) {
+        match self {
+            SortKey::CaptureDateAsc => {}
+            SortKey::CaptureDateDesc => visuals.reverse(),
+            SortKey::FileName => visuals.sort_by_cached_key(|visual| {
+                visual
+                    .sandbox_path()
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_lowercase())
+            }),
+            SortKey::FileSize => {
+                visuals.sort_by_key(|visual| std::cmp::Reverse(visual.byte_size().unwrap_or(0)))
+            }
+        }
+    }
+
+    pub fn scroll_to_end<T: RelmGridItem>(
+        &self,
+        grid: &mut TypedGridView<T, gtk::SingleSelection>,
+    ) {
+        AlbumSort::from(*self).scroll_to_end(grid);
+    }
+}
+
+/// Chronological direction for albums grouped by date.
+/// Only oldest first is ascending, otherwise the newest items lead.
+impl From<SortKey> for AlbumSort {
+    fn from(key: SortKey) -> Self {
+        match key {
+            SortKey::CaptureDateAsc => AlbumSort::Ascending,
+            _ => AlbumSort::Descending,
+        }
+    }
+}
@@ -0,0 +1,87 @@
+// SPDX-FileCopyrightText: © 2025 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Showing and opening files of grid items outside of Fotema.
+
+use relm4::gtk;
+use relm4::gtk::prelude::*;
+use relm4::gtk::{gio, glib};
+
+use std::path::Path;
+
+use tracing::debug;
+
+// Bus name, object path, and interface of the freedesktop file manager interface.
+// See https://www.freedesktop.org/wiki/Specifications/file-manager-interface/
+const FILE_MANAGER_NAME: &str = "org.freedesktop.FileManager1";
+const FILE_MANAGER_PATH: &str = "/org/freedesktop/FileManager1";
+
+/// Opens the file manager at the folder containing `path`, with `path` highlighted.
+/// Must be the host path, because the file manager runs outside of the Flatpak sandbox.
+/// Fails if no file manager implements the freedesktop file manager interface.
+pub async fn reveal(path: &Path) -> Result<(), glib::Error> {
+    debug!("Revealing {:?} in file manager", path);
+
+    let uri = gio::File::for_path(path).uri().to_string();
+    let startup_id = String::new();
+
+    let bus = gio::bus_get_future(gio::BusType::Session).await?;
+    bus.call_future(
+        Some(FILE_MANAGER_NAME),
+        FILE_MANAGER_PATH,
+        FILE_MANAGER_NAME,
+        "ShowItems",
+        Some(&(vec![uri], startup_id).to_variant()),
+        None,
+        gio::DBusCallFlags::NONE,
+        -1,
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Opens a file with the default app for its content type.
+/// Must be the sandbox path, because the app is launched from within the sandbox.
+/// Fails if no app is registered for the content type.
+pub fn open_with_default(widget: &impl IsA<gtk::Widget>, path: &Path) -> Result<(), glib::Error> {
+    debug!("Opening {:?} with default app", path);
+
+    let (content_type, _) = gio::content_type_guess(Some(path), &[]);
+    let app = gio::AppInfo::default_for_type(&content_type, false).ok_or_else(|| {
+        glib::Error::new(
+            gio::IOErrorEnum::NotFound,
+            &format!("No app registered for {}", content_type),
+        )
+    })?;
+
+    let file = gio::File::for_path(path);
+    let context = widget.display().app_launch_context();
+    app.launch(&[file], Some(&context))
+}
+
+/// Asks the user to choose an app, then opens a file with it.
+/// Must be the sandbox path, because the file is passed on from within the sandbox.
+/// Dismissing the chooser isn't an error.
+pub async fn open_with_chosen(
+    widget: &impl IsA<gtk::Widget>,
+    path: &Path,
+) -> Result<(), glib::Error> {
+    debug!("Opening {:?} with chosen app", path);
+
+    let window = widget.root().and_downcast::<gtk::Window>();
+    let file = gio::File::for_path(path);
+    let launcher = gtk::FileLauncher::new(Some(&file));
+    launcher.set_always_ask(true);
+
+    match launcher.launch_future(window.as_ref()).await {
+        Err(e) if e.matches(gtk::DialogError::Dismissed) => Ok(()),
+        result => result,
+    }
+}
+
+/// Copies a path to the clipboard as text.
+pub fn copy_path(widget: &impl IsA<gtk::Widget>, path: &Path) {
+    widget.clipboard().set_text(&path.to_string_lossy());
+}
@@ -4,36 +4,81 @@
 
 use gtk::prelude::OrientableExt;
 
+use fotema_core::PictureId;
+use fotema_core::number;
+use fotema_core::people;
 use fotema_core::thumbnailify::{Thumbnailer, ThumbnailSize};
+use fotema_core::visual::folder;
 
-use itertools::Itertools;
-
+use relm4::actions::{RelmAction, RelmActionGroup};
 use relm4::binding::*;
 use relm4::gtk;
 use relm4::gtk::gdk;
 use relm4::gtk::gdk_pixbuf;
-use relm4::gtk::prelude::WidgetExt;
+use relm4::gtk::{gio, glib};
+use relm4::gtk::prelude::{PopoverExt, WidgetExt};
 use relm4::typed_view::grid::{RelmGridItem, TypedGridView};
 use relm4::*;
 
+use std::cell::RefCell;
 use std::path;
 use std::sync::Arc;
 use std::rc::Rc;
 
+use strum::AsRefStr;
+use strum::EnumString;
+use strum::FromRepr;
+
+use super::file_manager;
 use crate::adaptive;
 use crate::app::ActiveView;
+use crate::app::SettingsState;
 use crate::app::SharedState;
 use crate::app::ViewName;
+use crate::fl;
 
-use tracing::{Level, event, info};
+use tracing::{debug, error, info};
 
 const NARROW_EDGE_LENGTH: i32 = 170;
 const WIDE_EDGE_LENGTH: i32 = 200;
 
+// Actions in the context menu of a folder.
+relm4::new_action_group!(FolderActionGroup, "folder");
+
+relm4::new_stateless_action!(OpenAction, FolderActionGroup, "open");
+relm4::new_stateless_action!(RevealAction, FolderActionGroup, "reveal");
+relm4::new_stateless_action!(CopyPathAction, FolderActionGroup, "copy_path");
+relm4::new_stateful_action!(HideAction, FolderActionGroup, "hide", (), bool);
+
+/// How folders are laid out in the folders album.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, EnumString, AsRefStr, FromRepr)]
+#[repr(u32)]
+pub enum FoldersViewMode {
+    /// Folders nested as they are on disk. Folders with subfolders are opened level by level.
+    #[default]
+    Tree,
+
+    /// Every folder containing photos or videos at one level, ordered by name.
+    Flat,
+}
+
 #[derive(Debug)]
 struct PhotoGridItem {
     folder_name: String,
 
+    // Path to folder, and whether it has subfolders, for the context menu.
+    path: path::PathBuf,
+    has_subfolders: bool,
+
+    // Has the user hidden the folder, or a folder containing it?
+    is_hidden: bool,
+
+    // For sending context menu actions back to album.
+    sender: relm4::Sender<FoldersAlbumInput>,
+
+    // Number of photos and videos in folder
+    count: usize,
+
     // Folder album cover
     visual: Arc<fotema_core::visual::Visual>,
 
@@ -41,14 +86,24 @@ struct PhotoGridItem {
     edge_length: I32Binding,
 
     thumbnailer: Rc<Thumbnailer>,
+
+    // Faces in the cover, for cropping the cover around them.
+    people_repo: people::Repository,
+
+    // Faces detected with a lower confidence aren't cropped around.
+    min_face_confidence: f32,
 }
 
 struct Widgets {
     picture: gtk::Picture,
     label: gtk::Label,
+    count_label: gtk::Label,
 
     // If the gtk::Picture has been bound to edge_length.
     is_bound: bool,
+
+    // Crops the cover around faces. Must be aborted when unbinding.
+    crop_loader: Option<glib::JoinHandle<()>>,
 }
 #[derive(Debug)]
 pub enum FoldersAlbumInput {
@@ -57,11 +112,27 @@ pub enum FoldersAlbumInput {
     // Reload photos from database
     Refresh,
 
-    FolderSelected(u32), // Index into photo grid vector
+    // Folder activated in the photo grid. Carries the folder, not its grid index, because
+    // a refresh could reorder the grid before this message is handled.
+    FolderSelected {
+        path: path::PathBuf,
+        has_subfolders: bool,
+    },
+
+    /// Show a folder in the file manager.
+    RevealInFileManager(path::PathBuf),
+
+    /// Copy the path of a folder to the clipboard.
+    CopyPath(path::PathBuf),
+
+    /// Hide a folder, and the folders beneath it, or show it again.
+    SetHidden(path::PathBuf, bool),
 
     // Adapt to layout
     Adapt(adaptive::Layout),
 
+    SettingsChanged,
+
     /// No-op. After refreshing the thumbnail grid, the screen would be blank and thumbnails
     /// would not appear until clicking to another view and back. I don't know why this happens,
     /// and have only observed this behaviour on the folders album view. As a work around, send
@@ -71,7 +142,14 @@ pub enum FoldersAlbumInput {
 
 #[derive(Debug)]
 pub enum FoldersAlbumOutput {
+    /// Show photos and videos in folder.
     FolderSelected(path::PathBuf),
+
+    /// Show next level of the folder tree beneath folder.
+    SubfoldersSelected(path::PathBuf),
+
+    /// User has hidden a folder, or shown it again.
+    SetHidden(path::PathBuf, bool),
 }
 
 impl RelmGridItem for PhotoGridItem {
@@ -95,22 +173,73 @@ impl RelmGridItem for PhotoGridItem {
                 gtk::Label {
                     add_css_class: "caption-heading",
                     set_margin_top: 4,
+                },
+
+                #[name(count_label)]
+                gtk::Label {
+                    add_css_class: "caption",
+                    add_css_class: "dim-label",
                     set_margin_bottom: 12,
                 },
             }
         }
 
+        // Context menu on right-click or long press. Actions are registered on bind.
+        // Folders can't be favorites, covers, or trashed, so only have file actions
+        // and hiding.
+        let menu = gio::Menu::new();
+        menu.append(Some(&fl!("folder-item-menu", "open")), Some("folder.open"));
+        menu.append(Some(&fl!("folder-item-menu", "reveal")), Some("folder.reveal"));
+        menu.append(
+            Some(&fl!("folder-item-menu", "copy-path")),
+            Some("folder.copy_path"),
+        );
+        menu.append(Some(&fl!("folder-item-menu", "hide")), Some("folder.hide"));
+        let context_menu = gtk::PopoverMenu::from_model(Some(&menu));
+        context_menu.set_parent(&my_box);
+        context_menu.set_has_arrow(false);
+
+        let right_click = gtk::GestureClick::builder()
+            .button(gdk::BUTTON_SECONDARY)
+            .build();
+        let menu = context_menu.clone();
+        right_click.connect_pressed(move |_, _, x, y| {
+            menu.set_pointing_to(Some(&gdk::Rectangle::new(x as i32, y as i32, 1, 1)));
+            menu.popup();
+        });
+        my_box.add_controller(right_click);
+
+        let long_press = gtk::GestureLongPress::new();
+        let menu = context_menu.clone();
+        long_press.connect_pressed(move |_, x, y| {
+            menu.set_pointing_to(Some(&gdk::Rectangle::new(x as i32, y as i32, 1, 1)));
+            menu.popup();
+        });
+        my_box.add_controller(long_press);
+
         let widgets = Widgets {
             picture,
             label,
+            count_label,
             is_bound: false,
+            crop_loader: None,
         };
 
         (my_box, widgets)
     }
 
-    fn bind(&mut self, widgets: &mut Self::Widgets, _root: &mut Self::Root) {
+    fn bind(&mut self, widgets: &mut Self::Widgets, root: &mut Self::Root) {
+        self.register_actions(root);
+
+        // Hidden folders are only shown when the user asks, and are dimmed.
+        root.set_opacity(if self.is_hidden { 0.5 } else { 1.0 });
+
         widgets.label.set_text(&self.folder_name.to_string());
+        widgets.count_label.set_text(&fl!(
+            "folder-item-count",
+            count = self.count,
+            formatted_count = number::format_count(self.count)
+        ));
 
         // If we repeatedly bind, then Fotema will die with the following error:
         // (fotema:2): GLib-GObject-CRITICAL **: 13:26:14.297: Too many GWeakRef registered
@@ -135,10 +264,14 @@ impl RelmGridItem for PhotoGridItem {
         let thumbnail_path = self.thumbnailer
             .nearest_thumbnail(&self.visual.thumbnail_hash(), thumbnail_size);
 
+        if let Some(ref thumbnail_path) = thumbnail_path {
+            let _ = self.thumbnailer.touch(thumbnail_path);
+        }
+
         if thumbnail_path.is_some() {
             widgets
                 .picture
-                .set_filename(thumbnail_path);
+                .set_filename(thumbnail_path.as_ref());
             widgets.picture.set_content_fit(gtk::ContentFit::Cover);
         } else {
             let pb = gdk_pixbuf::Pixbuf::from_resource_at_scale(
@@ -152,24 +285,167 @@ impl RelmGridItem for PhotoGridItem {
             widgets.picture.set_paintable(Some(&img));
             widgets.picture.set_content_fit(gtk::ContentFit::Contain);
         }
+
+        // Cropping around the centre can cut faces out of a group photo.
+        if let Some((picture_id, thumbnail_path)) = self.visual.picture_id.zip(thumbnail_path) {
+            let loader = self.crop_to_faces(picture_id, thumbnail_path, &widgets.picture);
+            widgets.crop_loader = Some(loader);
+        }
     }
 
-    fn unbind(&mut self, widgets: &mut Self::Widgets, _root: &mut Self::Root) {
+    fn unbind(&mut self, widgets: &mut Self::Widgets, root: &mut Self::Root) {
+        if let Some(loader) = widgets.crop_loader.take() {
+            loader.abort();
+        }
         widgets.picture.set_filename(None::<&path::Path>);
+        root.insert_action_group("folder", None::<&gio::ActionGroup>);
+    }
+}
+
+impl PhotoGridItem {
+    /// Actions for the context menu of this folder.
+    fn register_actions(&self, root: &gtk::Box) {
+        let open: RelmAction<OpenAction> = {
+            let sender = self.sender.clone();
+            let path = self.path.clone();
+            let has_subfolders = self.has_subfolders;
+            RelmAction::new_stateless(move |_| {
+                sender.emit(FoldersAlbumInput::FolderSelected {
+                    path: path.clone(),
+                    has_subfolders,
+                })
+            })
+        };
+
+        let reveal: RelmAction<RevealAction> = {
+            let sender = self.sender.clone();
+            let path = self.path.clone();
+            RelmAction::new_stateless(move |_| {
+                sender.emit(FoldersAlbumInput::RevealInFileManager(path.clone()))
+            })
+        };
+
+        let copy_path: RelmAction<CopyPathAction> = {
+            let sender = self.sender.clone();
+            let path = self.path.clone();
+            RelmAction::new_stateless(move |_| {
+                sender.emit(FoldersAlbumInput::CopyPath(path.clone()))
+            })
+        };
+
+        let hide: RelmAction<HideAction> = {
+            let sender = self.sender.clone();
+            let path = self.path.clone();
+            RelmAction::new_stateful(&self.is_hidden, move |_, is_hidden: &mut bool| {
+                *is_hidden = !*is_hidden;
+                sender.emit(FoldersAlbumInput::SetHidden(path.clone(), *is_hidden))
+            })
+        };
+
+        let mut actions = RelmActionGroup::<FolderActionGroup>::new();
+        actions.add_action(open);
+        actions.add_action(reveal);
+        actions.add_action(copy_path);
+        actions.add_action(hide);
+        actions.register_for_widget(root);
+    }
+
+    /// Replace the cover with a square crop around the largest face in it.
+    /// A cover without faces is left cropped around the centre.
+    fn crop_to_faces(
+        &self,
+        picture_id: PictureId,
+        thumbnail_path: path::PathBuf,
+        picture: &gtk::Picture,
+    ) -> glib::JoinHandle<()> {
+        let repo = self.people_repo.clone();
+        let thumbnailer = (*self.thumbnailer).clone();
+        let hash = self.visual.thumbnail_hash();
+        let min_confidence = self.min_face_confidence;
+        let picture = picture.clone();
+
+        glib::spawn_future_local(async move {
+            let path = thumbnail_path.clone();
+            let crop = gio::spawn_blocking(move || {
+                let faces: Vec<_> = repo
+                    .find_faces(&picture_id, min_confidence)?
+                    .into_iter()
+                    .map(|(face, _)| face.bounds)
+                    .collect();
+
+                if faces.is_empty() {
+                    return anyhow::Ok(None);
+                }
+
+                Ok(Some(thumbnailer.smart_crop(&path, &hash, &faces)?))
+            })
+            .await;
+
+            let crop = match crop {
+                Ok(Ok(Some(crop))) => crop,
+                Ok(Ok(None)) => return,
+                Ok(Err(e)) => {
+                    debug!("Failed cropping folder cover to faces: {:?}", e);
+                    return;
+                }
+                Err(_) => {
+                    error!("Panicked cropping folder cover to faces");
+                    return;
+                }
+            };
+
+            match gdk_pixbuf::Pixbuf::from_file(&thumbnail_path) {
+                Ok(pb) => {
+                    let cropped = pb.new_subpixbuf(
+                        crop.x as i32,
+                        crop.y as i32,
+                        crop.edge_length as i32,
+                        crop.edge_length as i32,
+                    );
+                    let img = gdk::Texture::for_pixbuf(&cropped);
+                    picture.set_paintable(Some(&img));
+                }
+                Err(e) => debug!("Failed loading folder cover {:?}: {}", thumbnail_path, e),
+            }
+        })
     }
 }
 
 pub struct FoldersAlbum {
     state: SharedState,
+    settings_state: SettingsState,
     active_view: ActiveView,
     photo_grid: TypedGridView<PhotoGridItem, gtk::SingleSelection>,
     edge_length: I32Binding,
     thumbnailer: Rc<Thumbnailer>,
+    people_repo: people::Repository,
+    input_sender: relm4::Sender<FoldersAlbumInput>,
+
+    // Folder whose subfolders are shown. None for the top of the tree.
+    parent: Option<path::PathBuf>,
+
+    // Folder at each position of the grid, looked up by the activate handler.
+    grid_folders: Rc<RefCell<folder::FolderPositions>>,
+
+    view_mode: FoldersViewMode,
+
+    // Show folders the user has hidden, rather than leaving them out.
+    show_hidden: bool,
+
+    // Host paths of library roots. Shown as the top of the tree if there is more than one.
+    library_roots: Vec<path::PathBuf>,
 }
 
 #[relm4::component(pub)]
 impl SimpleComponent for FoldersAlbum {
-    type Init = (SharedState, ActiveView, Rc<Thumbnailer>);
+    type Init = (
+        SharedState,
+        SettingsState,
+        ActiveView,
+        Rc<Thumbnailer>,
+        people::Repository,
+        Option<path::PathBuf>,
+    );
     type Input = FoldersAlbumInput;
     type Output = FoldersAlbumOutput;
 
@@ -182,26 +458,48 @@ impl SimpleComponent for FoldersAlbum {
                 set_orientation: gtk::Orientation::Vertical,
                 set_single_click_activate: true,
 
-                connect_activate[sender] => move |_, idx| {
-                    sender.input(FoldersAlbumInput::FolderSelected(idx))
+                connect_activate[sender, grid_folders = model.grid_folders.clone()] => move |_, idx| {
+                    let folder = grid_folders.borrow().get(idx);
+                    debug!(
+                        index = idx,
+                        folder_path = ?folder.as_ref().map(|(path, _)| path),
+                        "Folder activated"
+                    );
+                    if let Some((path, has_subfolders)) = folder {
+                        sender.input(FoldersAlbumInput::FolderSelected { path, has_subfolders });
+                    }
                 }
             }
         }
     }
 
     fn init(
-        (state, active_view, thumbnailer): Self::Init,
+        (state, settings_state, active_view, thumbnailer, people_repo, parent): Self::Init,
         _root: Self::Root,
         sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
+        settings_state.subscribe(sender.input_sender(), |_| FoldersAlbumInput::SettingsChanged);
+
         let photo_grid = TypedGridView::new();
 
+        let view_mode = settings_state.read().folders_view_mode;
+        let show_hidden = settings_state.read().show_hidden_folders;
+        let library_roots = Self::library_roots(&settings_state);
+
         let model = FoldersAlbum {
             state,
+            settings_state,
             active_view,
             photo_grid,
             edge_length: I32Binding::new(NARROW_EDGE_LENGTH),
             thumbnailer,
+            people_repo,
+            input_sender: sender.input_sender().clone(),
+            parent,
+            grid_folders: Rc::default(),
+            view_mode,
+            show_hidden,
+            library_roots,
         };
 
         let pictures_box = &model.photo_grid.view;
@@ -214,7 +512,7 @@ impl SimpleComponent for FoldersAlbum {
     fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
         match msg {
             FoldersAlbumInput::Noop => {
-                info!("No-op received... so doing nothing. As expected :-/");
+                debug!("No-op received... so doing nothing. As expected :-/");
             }
             FoldersAlbumInput::Activate => {
                 *self.active_view.write() = ViewName::Folders;
@@ -224,25 +522,57 @@ impl SimpleComponent for FoldersAlbum {
             }
             FoldersAlbumInput::Refresh => {
                 if *self.active_view.read() == ViewName::Folders {
-                    info!("Folders view is active so refreshing");
+                    debug!("Folders view is active so refreshing");
                     self.refresh();
 
                     // Work-around to make grid appear.
                     sender.input(FoldersAlbumInput::Noop);
                 } else {
-                    info!("Folders view is inactive so clearing");
+                    debug!("Folders view is inactive so clearing");
                     self.photo_grid.clear();
+                    *self.grid_folders.borrow_mut() = folder::FolderPositions::default();
+                }
+            }
+            FoldersAlbumInput::FolderSelected { path, has_subfolders } => {
+                debug!(
+                    folder_name = ?folder::name(&path),
+                    folder_path = ?path,
+                    has_subfolders,
+                    "Folder selected"
+                );
+                if has_subfolders {
+                    let _ = sender.output(FoldersAlbumOutput::SubfoldersSelected(path));
+                } else {
+                    let _ = sender.output(FoldersAlbumOutput::FolderSelected(path));
                 }
             }
-            FoldersAlbumInput::FolderSelected(index) => {
-                event!(Level::DEBUG, "Folder selected index: {}", index);
-                if let Some(item) = self.photo_grid.get_visible(index) {
-                    let item = item.borrow();
-                    event!(Level::DEBUG, "Folder selected item: {}", item.folder_name);
-
-                    let _ = sender.output(FoldersAlbumOutput::FolderSelected(
-                        item.visual.parent_path.clone(),
-                    ));
+            FoldersAlbumInput::RevealInFileManager(path) => {
+                glib::spawn_future_local(async move {
+                    if let Err(e) = file_manager::reveal(&path).await {
+                        error!("Failed revealing {:?} in file manager: {}", path, e);
+                    }
+                });
+            }
+            FoldersAlbumInput::CopyPath(path) => {
+                file_manager::copy_path(&self.photo_grid.view, &path);
+            }
+            FoldersAlbumInput::SetHidden(path, is_hidden) => {
+                info!(folder_path = ?path, is_hidden, "Setting folder hidden");
+                let _ = sender.output(FoldersAlbumOutput::SetHidden(path, is_hidden));
+            }
+            FoldersAlbumInput::SettingsChanged => {
+                let view_mode = self.settings_state.read().folders_view_mode;
+                let show_hidden = self.settings_state.read().show_hidden_folders;
+                let library_roots = Self::library_roots(&self.settings_state);
+                if self.view_mode != view_mode
+                    || self.show_hidden != show_hidden
+                    || self.library_roots != library_roots
+                {
+                    info!(?view_mode, show_hidden, "Folders view changed");
+                    self.view_mode = view_mode;
+                    self.show_hidden = show_hidden;
+                    self.library_roots = library_roots;
+                    sender.input(FoldersAlbumInput::Refresh);
                 }
             }
             FoldersAlbumInput::Adapt(adaptive::Layout::Narrow) => {
@@ -256,29 +586,68 @@ impl SimpleComponent for FoldersAlbum {
 }
 
 impl FoldersAlbum {
+    fn library_roots(settings_state: &SettingsState) -> Vec<path::PathBuf> {
+        settings_state
+            .read()
+            .library_roots()
+            .iter()
+            .map(|root| root.host_path.clone())
+            .collect()
+    }
+
     fn refresh(&mut self) {
-        let all = {
-            let data = self.state.read();
-            data.clone()
-                .into_iter()
-                .sorted_by_key(|visual| visual.parent_path.clone())
-                .chunk_by(|visual| visual.parent_path.clone())
+        // Top of the tree for a library with one root. Items directly in it are
+        // shown as the library itself, whatever the directory happens to be called.
+        let mut library_root = None;
+
+        let folders = {
+            // Items in hidden folders are left out here, but still shown by other albums.
+            let data: Vec<_> = self
+                .state
+                .read()
+                .iter()
+                .filter(|visual| self.show_hidden || visual.hidden_folder.is_none())
+                .cloned()
+                .collect();
+
+            match (&self.parent, self.view_mode) {
+                // Levels beneath the top of the tree are always shown as a tree.
+                (Some(parent), _) => folder::tree_level(&data, parent),
+                (None, FoldersViewMode::Tree) if self.library_roots.len() > 1 => {
+                    folder::roots(&data, &self.library_roots)
+                }
+                (None, FoldersViewMode::Tree) => {
+                    library_root = folder::root(&data);
+                    library_root
+                        .as_ref()
+                        .map(|root| folder::tree_level(&data, root))
+                        .unwrap_or_default()
+                }
+                (None, FoldersViewMode::Flat) => folder::flat(&data),
+            }
         };
 
-        let mut pictures = Vec::new();
-
-        for (_key, mut group) in &all {
-            let first = group.nth(0).expect("Groups can't be empty");
-            let album = PhotoGridItem {
-                folder_name: first.folder_name().unwrap_or("-".to_string()),
-                visual: first.clone(),
-                edge_length: self.edge_length.clone(),
-                thumbnailer: self.thumbnailer.clone(),
-            };
-            pictures.push(album);
-        }
-
-        pictures.sort_by_key(|pic| pic.folder_name.clone());
+        *self.grid_folders.borrow_mut() = folder::FolderPositions::new(&folders);
+
+        let min_face_confidence = self.settings_state.read().face_confidence_threshold;
+
+        let pictures = folders.into_iter().map(|folder| PhotoGridItem {
+            folder_name: if library_root.as_ref() == Some(&folder.path) {
+                fl!("folder-library-root")
+            } else {
+                folder.name().unwrap_or_else(|| fl!("folder-library-root"))
+            },
+            is_hidden: folder.is_hidden(),
+            path: folder.path,
+            has_subfolders: folder.has_subfolders,
+            sender: self.input_sender.clone(),
+            count: folder.count,
+            visual: folder.cover,
+            edge_length: self.edge_length.clone(),
+            thumbnailer: self.thumbnailer.clone(),
+            people_repo: self.people_repo.clone(),
+            min_face_confidence,
+        });
 
         self.photo_grid.clear();
         self.photo_grid.extend_from_iter(pictures);
@@ -286,3 +655,4 @@ impl FoldersAlbum {
         // NOTE folder view is not sorted by a timestamp, so don't scroll to end.
     }
 }
+
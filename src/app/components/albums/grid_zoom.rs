@@ -0,0 +1,66 @@
+// SPDX-FileCopyrightText: © 2025 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use fotema_core::thumbnailify::ThumbnailSize;
+use strum::AsRefStr;
+use strum::EnumString;
+use strum::FromRepr;
+
+use crate::app::adaptive;
+
+// Size of items in album grids
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, EnumString, AsRefStr, FromRepr)]
+#[repr(u32)]
+pub enum GridZoom {
+    // Fit more items on screen
+    Small,
+
+    #[default]
+    Medium,
+
+    // Show more detail of each item
+    Large,
+}
+
+impl GridZoom {
+    /// Length in logical pixels of the edge of a grid item.
+    pub const fn edge_length(&self, layout: adaptive::Layout) -> i32 {
+        match (layout, self) {
+            (adaptive::Layout::Narrow, GridZoom::Small) => 80,
+            (adaptive::Layout::Narrow, GridZoom::Medium) => 112,
+            (adaptive::Layout::Narrow, GridZoom::Large) => 168,
+            (adaptive::Layout::Wide, GridZoom::Small) => 140,
+            (adaptive::Layout::Wide, GridZoom::Medium) => 200,
+            (adaptive::Layout::Wide, GridZoom::Large) => 300,
+        }
+    }
+
+    pub fn zoom_in(&self) -> Self {
+        match self {
+            GridZoom::Small => GridZoom::Medium,
+            _ => GridZoom::Large,
+        }
+    }
+
+    pub fn zoom_out(&self) -> Self {
+        match self {
+            GridZoom::Large => GridZoom::Medium,
+            _ => GridZoom::Small,
+        }
+    }
+}
+
+/// Smallest thumbnail that fills a grid item without being scaled up,
+/// allowing for the display scale factor on high-DPI screens.
+pub fn thumbnail_size(edge_length: i32, scale_factor: i32) -> ThumbnailSize {
+    let pixels = (edge_length * scale_factor.max(1)) as u32;
+    [
+        ThumbnailSize::Normal,
+        ThumbnailSize::Large,
+        ThumbnailSize::XLarge,
+    ]
+    .into_iter()
+    .find(|size| size.to_dimension() >= pixels)
+    .unwrap_or(ThumbnailSize::XXLarge)
+}
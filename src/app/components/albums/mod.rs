@@ -5,9 +5,14 @@
 pub mod album;
 pub mod album_filter;
 pub mod album_sort;
+pub mod file_manager;
 pub mod folders_album;
+pub mod grid_zoom;
 pub mod months_album;
 pub mod people_album;
+pub mod person_faces;
 pub mod person_album;
+#[cfg(feature = "map")]
 pub mod places_album;
+pub mod slideshow;
 pub mod years_album;
@@ -4,6 +4,7 @@
 
 use gtk::prelude::OrientableExt;
 
+use fotema_core::FaceId;
 use fotema_core::people;
 
 use relm4::binding::*;
@@ -11,6 +12,7 @@ use relm4::gtk;
 use relm4::gtk::gdk;
 use relm4::gtk::prelude::WidgetExt;
 use relm4::gtk::prelude::*;
+use relm4::prelude::*;
 use relm4::typed_view::grid::{RelmGridItem, TypedGridView};
 use relm4::*;
 
@@ -19,17 +21,30 @@ use crate::app::ActiveView;
 use crate::app::FaceDetectionMode;
 use crate::app::SettingsState;
 use crate::app::ViewName;
+use crate::app::components::viewer::person_select::{
+    PersonSelect, PersonSelectInput, PersonSelectOutput,
+};
 use crate::fl;
 
-use tracing::{debug, info};
+use tracing::{debug, error, info};
 
 const NARROW_EDGE_LENGTH: i32 = 170;
 const WIDE_EDGE_LENGTH: i32 = 200;
 
+#[derive(Debug)]
+pub enum PeopleItem {
+    /// A named person.
+    Person(people::Person),
+
+    /// A suggested person made from a cluster of similar unknown faces.
+    /// Face is the first face of the cluster and is used for the avatar.
+    Suggestion(Vec<FaceId>, people::Face),
+}
+
 #[derive(Debug)]
 struct PhotoGridItem {
-    /// Person for avatar
-    person: people::Person,
+    /// Person or suggested person for avatar
+    item: PeopleItem,
 
     // Length of thumbnail edge to allow for resizing when layout changes.
     edge_length: I32Binding,
@@ -50,14 +65,27 @@ pub enum PeopleAlbumInput {
     // Reload photos from database
     Refresh,
 
+    /// People and suggested people have been loaded from the database.
+    /// Loads are numbered so that a load finishing after a later load is ignored.
+    Loaded(u64, Vec<PeopleItem>),
+
     Selected(u32), // Index into photo grid vector
 
+    /// Index into suggestion grid vector
+    SuggestionSelected(u32),
+
     // Adapt to layout
     Adapt(adaptive::Layout),
 
     SettingsChanged,
 
     EnableFaceDetection,
+
+    /// Include or exclude hidden people.
+    ShowHidden(bool),
+
+    /// A suggested person has been named.
+    SuggestionNamed,
 }
 
 #[derive(Debug)]
@@ -100,8 +128,27 @@ impl RelmGridItem for PhotoGridItem {
         (my_box, widgets)
     }
 
-    fn bind(&mut self, widgets: &mut Self::Widgets, _root: &mut Self::Root) {
-        widgets.label.set_text(&self.person.name);
+    fn bind(&mut self, widgets: &mut Self::Widgets, root: &mut Self::Root) {
+        let (name, thumbnail_path) = match self.item {
+            PeopleItem::Person(ref person) => {
+                if person.is_ignored {
+                    root.add_css_class("dim-label");
+                }
+                (Some(person.name.clone()), person.thumbnail_path().cloned())
+            }
+            PeopleItem::Suggestion(ref face_ids, ref face) => {
+                root.add_css_class("dim-label");
+                widgets.label.set_text(&fl!(
+                    "people-page-suggested-person",
+                    count = face_ids.len()
+                ));
+                (None, Some(face.thumbnail_path.clone()))
+            }
+        };
+
+        if let Some(ref name) = name {
+            widgets.label.set_text(name);
+        }
 
         // If we repeatedly bind, then Fotema will die with the following error:
         // (fotema:2): GLib-GObject-CRITICAL **: 13:26:14.297: Too many GWeakRef registered
@@ -114,9 +161,9 @@ impl RelmGridItem for PhotoGridItem {
             widgets.is_bound = true;
         }
 
-        widgets.avatar.set_text(Some(&self.person.name));
+        widgets.avatar.set_text(name.as_deref());
 
-        if let Some(ref thumbnail_path) = self.person.thumbnail_path() {
+        if let Some(ref thumbnail_path) = thumbnail_path {
             if thumbnail_path.exists() {
                 let img = gdk::Texture::from_filename(thumbnail_path).ok();
                 widgets.avatar.set_custom_image(img.as_ref());
@@ -124,7 +171,8 @@ impl RelmGridItem for PhotoGridItem {
         }
     }
 
-    fn unbind(&mut self, widgets: &mut Self::Widgets, _root: &mut Self::Root) {
+    fn unbind(&mut self, widgets: &mut Self::Widgets, root: &mut Self::Root) {
+        root.remove_css_class("dim-label");
         widgets.avatar.set_custom_image(None::<&gdk::Paintable>);
         widgets.avatar.set_text(None);
     }
@@ -135,9 +183,23 @@ pub struct PeopleAlbum {
     active_view: ActiveView,
     settings_state: SettingsState,
     photo_grid: TypedGridView<PhotoGridItem, gtk::SingleSelection>,
+
+    /// Suggested people, in a section after named people.
+    suggestion_grid: TypedGridView<PhotoGridItem, gtk::SingleSelection>,
+    suggestions_heading: gtk::Label,
+
     avatars: gtk::ScrolledWindow,
     status: adw::StatusPage,
     edge_length: I32Binding,
+    show_hidden: bool,
+    person_dialog: adw::Dialog,
+    person_select: AsyncController<PersonSelect>,
+
+    /// Faces of suggested person being named.
+    suggestion: Vec<FaceId>,
+
+    /// Number of most recent load of people.
+    load_generation: u64,
 }
 
 #[relm4::component(pub)]
@@ -154,15 +216,50 @@ impl SimpleComponent for PeopleAlbum {
             avatars -> gtk::ScrolledWindow {
                 set_vexpand: true,
 
-                #[local_ref]
-                pictures_box -> gtk::GridView {
+                gtk::Box {
                     set_orientation: gtk::Orientation::Vertical,
-                    set_single_click_activate: true,
 
-                    connect_activate[sender] => move |_, idx| {
-                        sender.input(PeopleAlbumInput::Selected(idx))
-                    }
-                }
+                    #[local_ref]
+                    pictures_box -> gtk::GridView {
+                        set_orientation: gtk::Orientation::Vertical,
+                        set_single_click_activate: true,
+
+                        connect_activate[sender] => move |_, idx| {
+                            sender.input(PeopleAlbumInput::Selected(idx))
+                        }
+                    },
+
+                    #[local_ref]
+                    suggestions_heading -> gtk::Label {
+                        set_label: &fl!("people-page-suggestions"),
+                        set_halign: gtk::Align::Start,
+                        set_margin_all: 12,
+                        add_css_class: "heading",
+                    },
+
+                    #[local_ref]
+                    suggestions_box -> gtk::GridView {
+                        set_orientation: gtk::Orientation::Vertical,
+                        set_single_click_activate: true,
+
+                        connect_activate[sender] => move |_, idx| {
+                            sender.input(PeopleAlbumInput::SuggestionSelected(idx))
+                        }
+                    },
+                },
+            },
+
+            gtk::ActionBar {
+                #[watch]
+                set_revealed: model.settings_state.read().face_detection_mode == FaceDetectionMode::On,
+
+                pack_end = &gtk::CheckButton {
+                    set_label: Some(&fl!("people-page-show-hidden")),
+
+                    connect_toggled[sender] => move |check| {
+                        sender.input(PeopleAlbumInput::ShowHidden(check.is_active()));
+                    },
+                },
             },
 
             #[local_ref]
@@ -202,22 +299,44 @@ impl SimpleComponent for PeopleAlbum {
         settings_state.subscribe(sender.input_sender(), |_| PeopleAlbumInput::SettingsChanged);
 
         let photo_grid = TypedGridView::new();
+        let suggestion_grid = TypedGridView::new();
+        let suggestions_heading = gtk::Label::new(None);
 
         let status = adw::StatusPage::new();
 
         let avatars = gtk::ScrolledWindow::builder().build();
 
+        let person_select = PersonSelect::builder()
+            .launch((repo.clone(), settings_state.clone()))
+            .forward(sender.input_sender(), |msg| match msg {
+                PersonSelectOutput::Done => PeopleAlbumInput::SuggestionNamed,
+            });
+
+        let person_dialog = adw::Dialog::builder()
+            .child(person_select.widget())
+            .presentation_mode(adw::DialogPresentationMode::BottomSheet)
+            .height_request(400)
+            .build();
+
         let model = PeopleAlbum {
             repo,
             active_view,
             settings_state,
             photo_grid,
+            suggestion_grid,
+            suggestions_heading: suggestions_heading.clone(),
             avatars: avatars.clone(),
             status: status.clone(),
             edge_length: I32Binding::new(NARROW_EDGE_LENGTH),
+            show_hidden: false,
+            person_dialog,
+            person_select,
+            suggestion: vec![],
+            load_generation: 0,
         };
 
         let pictures_box = &model.photo_grid.view;
+        let suggestions_box = &model.suggestion_grid.view;
 
         let widgets = view_output!();
 
@@ -229,16 +348,35 @@ impl SimpleComponent for PeopleAlbum {
             PeopleAlbumInput::Activate => {
                 info!("Activating people view");
                 *self.active_view.write() = ViewName::People;
-                self.refresh();
+                self.refresh(&sender);
             }
             PeopleAlbumInput::Selected(index) => {
                 debug!("Person selected index: {}", index);
                 if let Some(item) = self.photo_grid.get_visible(index) {
                     let item = item.borrow();
-                    debug!("Person selected item: {}", item.person.person_id);
-                    //let picture_ids = self.repo.find_pictures_for_person(item.person.person_id).unwrap_or(vec![]);
-
-                    let _ = sender.output(PeopleAlbumOutput::Selected(item.person.clone()));
+                    if let PeopleItem::Person(ref person) = item.item {
+                        debug!("Person selected item: {}", person.person_id);
+                        let _ = sender.output(PeopleAlbumOutput::Selected(person.clone()));
+                    }
+                }
+            }
+            PeopleAlbumInput::SuggestionSelected(index) => {
+                debug!("Suggested person selected index: {}", index);
+                if let Some(item) = self.suggestion_grid.get_visible(index) {
+                    let item = item.borrow();
+                    if let PeopleItem::Suggestion(ref face_ids, ref face) = item.item {
+                        debug!("Suggested person selected with {} faces", face_ids.len());
+                        self.suggestion = face_ids.clone();
+                        self.person_select.emit(PersonSelectInput::Activate(
+                            face.face_id,
+                            face.thumbnail_path.clone(),
+                        ));
+                        if let Some(root) = gtk::Widget::root(self.avatars.widget_ref()) {
+                            self.person_dialog.present(Some(&root));
+                        } else {
+                            error!("Couldn't get root widget!");
+                        }
+                    }
                 }
             }
             PeopleAlbumInput::Adapt(adaptive::Layout::Narrow) => {
@@ -248,16 +386,51 @@ impl SimpleComponent for PeopleAlbum {
                 self.edge_length.set_value(WIDE_EDGE_LENGTH);
             }
             PeopleAlbumInput::Refresh => {
-                self.refresh();
+                self.refresh(&sender);
+            }
+            PeopleAlbumInput::Loaded(generation, items) => {
+                if generation == self.load_generation {
+                    self.show(items);
+                } else {
+                    debug!("Ignoring stale load of people");
+                }
             }
             PeopleAlbumInput::SettingsChanged => {
-                self.refresh();
+                self.refresh(&sender);
+            }
+            PeopleAlbumInput::SuggestionNamed => {
+                self.person_dialog.close();
+
+                // The person selector names the first face, so name the rest of the
+                // faces as the same person.
+                let face_ids = std::mem::take(&mut self.suggestion);
+                if let Some((first, rest)) = face_ids.split_first() {
+                    match self.repo.person_for_face(*first) {
+                        Ok(Some(person)) => {
+                            info!("Naming {} suggested faces as {}", face_ids.len(), person.person_id);
+                            if let Err(e) = self.repo.confirm_cluster(rest, person.person_id) {
+                                error!("Failed naming suggested person: {}", e);
+                            }
+                        }
+                        Ok(None) => {
+                            debug!("Suggested person was not named");
+                        }
+                        Err(e) => {
+                            error!("Failed getting person for face: {}", e);
+                        }
+                    }
+                }
+                self.refresh(&sender);
+            }
+            PeopleAlbumInput::ShowHidden(show_hidden) => {
+                self.show_hidden = show_hidden;
+                self.refresh(&sender);
             }
             PeopleAlbumInput::EnableFaceDetection => {
                 let mut settings = self.settings_state.read().clone();
                 settings.face_detection_mode = FaceDetectionMode::On;
                 *self.settings_state.write() = settings;
-                self.refresh();
+                self.refresh(&sender);
                 let _ = sender.output(PeopleAlbumOutput::EnableFaceDetection);
             }
         }
@@ -265,7 +438,7 @@ impl SimpleComponent for PeopleAlbum {
 }
 
 impl PeopleAlbum {
-    fn refresh(&mut self) {
+    fn refresh(&mut self, sender: &ComponentSender<Self>) {
         if self.settings_state.read().face_detection_mode == FaceDetectionMode::Off {
             self.avatars.set_visible(false);
             self.status.set_visible(true);
@@ -280,21 +453,34 @@ impl PeopleAlbum {
             return;
         }
 
-        let mut people = self.repo.all_people().unwrap_or_default();
-        people.sort_by_key(|p| p.name.clone());
+        self.load_generation += 1;
+        let generation = self.load_generation;
 
-        self.photo_grid.clear();
+        let repo = self.repo.clone();
+        let show_hidden = self.show_hidden;
+        let (max_distance, min_confidence) = {
+            let settings = self.settings_state.read();
+            (settings.face_cluster_distance, settings.face_confidence_threshold)
+        };
 
-        let mut items = vec![];
-        for person in people {
-            let item = PhotoGridItem {
-                person,
-                edge_length: self.edge_length.clone(),
-            };
+        // Clustering faces is slow and the database is shared with the face detection
+        // task, so load off the main thread to keep the UI responsive.
+        let sender = sender.input_sender().clone();
+        relm4::spawn_blocking(move || {
+            let items = load(&repo, show_hidden, max_distance, min_confidence);
+            sender.emit(PeopleAlbumInput::Loaded(generation, items));
+        });
+    }
 
-            items.push(item);
+    fn show(&mut self, items: Vec<PeopleItem>) {
+        // Face detection might have been turned off while loading.
+        if self.settings_state.read().face_detection_mode == FaceDetectionMode::Off {
+            return;
         }
 
+        self.photo_grid.clear();
+        self.suggestion_grid.clear();
+
         self.status.set_visible(items.is_empty());
         self.avatars.set_visible(!items.is_empty());
 
@@ -308,6 +494,61 @@ impl PeopleAlbum {
                 .set_description(Some(&fl!("people-page-status-no-people", "description")));
         }
 
-        self.photo_grid.extend_from_iter(items);
+        let (people, suggestions): (Vec<_>, Vec<_>) = items
+            .into_iter()
+            .map(|item| PhotoGridItem {
+                item,
+                edge_length: self.edge_length.clone(),
+            })
+            .partition(|item| matches!(item.item, PeopleItem::Person(_)));
+
+        self.suggestions_heading
+            .set_visible(!suggestions.is_empty());
+
+        self.photo_grid.extend_from_iter(people);
+        self.suggestion_grid.extend_from_iter(suggestions);
     }
 }
+
+/// Named people with the most faces first, followed by suggested people.
+fn load(
+    repo: &people::Repository,
+    show_hidden: bool,
+    max_distance: f32,
+    min_confidence: f32,
+) -> Vec<PeopleItem> {
+    let face_counts = repo.face_counts(min_confidence).unwrap_or_default();
+    let face_count = |person: &people::Person| {
+        face_counts
+            .get(&person.person_id)
+            .copied()
+            .unwrap_or_default()
+    };
+
+    // People with the same number of faces are sorted by name.
+    let mut people = repo.all_people(show_hidden).unwrap_or_default();
+    people.sort_by(|a, b| {
+        face_count(b)
+            .cmp(&face_count(a))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+
+    let mut items: Vec<PeopleItem> = people.into_iter().map(PeopleItem::Person).collect();
+
+    let clusters = repo
+        .unconfirmed_clusters(max_distance, min_confidence)
+        .unwrap_or_default();
+
+    for face_ids in clusters {
+        let Some(face) = face_ids
+            .first()
+            .and_then(|face_id| repo.get_face(*face_id).ok().flatten())
+        else {
+            continue;
+        };
+
+        items.push(PeopleItem::Suggestion(face_ids, face));
+    }
+
+    items
+}
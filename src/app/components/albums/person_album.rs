@@ -10,27 +10,33 @@ use relm4::adw::prelude::*;
 use relm4::binding::*;
 use relm4::gtk;
 use relm4::gtk::gdk;
+use relm4::gtk::gio;
 use relm4::*;
 
 use crate::app::ActiveView;
+use crate::app::SettingsState;
 use crate::app::SharedState;
 use crate::app::ViewName;
 use crate::app::adaptive;
 use crate::app::components::albums::{
     album::{Album, AlbumInput, AlbumOutput},
     album_filter::AlbumFilter,
-    album_sort::AlbumSort,
+    album_sort::SortKey,
+    grid_zoom::GridZoom,
+    person_faces::{PersonFacesDialog, PersonFacesInput, PersonFacesOutput},
 };
 
 use crate::fl;
 
 use fotema_core::VisualId;
 use fotema_core::PictureId;
+use fotema_core::burst::BurstId;
 use fotema_core::people;
 use fotema_core::thumbnailify::Thumbnailer;
 
 use tracing::{error, info};
 
+use std::path::PathBuf;
 use std::rc::Rc;
 
 const NARROW_EDGE_LENGTH: i32 = 50;
@@ -44,8 +50,20 @@ relm4::new_stateless_action!(RenameAction, PersonActionGroup, "rename");
 // Delete a person
 relm4::new_stateless_action!(DeleteAction, PersonActionGroup, "delete");
 
+// Hide or show a person in the people album
+relm4::new_stateful_action!(HideAction, PersonActionGroup, "hide", (), bool);
+
+// Copy a person's photos to a folder
+relm4::new_stateless_action!(ExportAction, PersonActionGroup, "export");
+
+// Review the faces assigned to a person
+relm4::new_stateless_action!(FacesAction, PersonActionGroup, "faces");
+
 #[derive(Debug)]
 pub enum PersonAlbumInput {
+    /// Ignore an event
+    Ignore,
+
     /// Album is visible
     Activate,
 
@@ -55,6 +73,9 @@ pub enum PersonAlbumInput {
     /// View album for a person
     View(people::Person),
 
+    /// Settings have changed, such as the face confidence threshold.
+    SettingsChanged,
+
     /// Adapt to layout
     Adapt(adaptive::Layout),
 
@@ -64,6 +85,27 @@ pub enum PersonAlbumInput {
     /// Picture selected in underlying album
     Selected(VisualId),
 
+    /// Pictures starred or unstarred in underlying album
+    SetFavorite(Vec<PictureId>, bool),
+
+    /// Pictures trashed or restored in underlying album
+    SetTrashed(Vec<PictureId>, bool),
+
+    /// Picture chosen as a folder cover in underlying album
+    SetFolderCover(PathBuf, PictureId),
+
+    /// Thumbnail regeneration requested in underlying album
+    RegenerateThumbnail(PictureId),
+
+    /// Burst ungrouped in underlying album
+    UngroupBurst(BurstId),
+
+    /// Pictures moved to another folder in underlying album
+    MovePictures(Vec<PictureId>, PathBuf),
+
+    /// Pictures to name as a person in underlying album
+    AssignToPerson(Vec<PictureId>),
+
     /// Start rename person flow
     RenameDialog,
 
@@ -76,7 +118,24 @@ pub enum PersonAlbumInput {
     /// Actually delete person.
     Delete,
 
-    Sort(AlbumSort),
+    /// Hide or show person in people album.
+    SetHidden(bool),
+
+    /// Start export flow by choosing a folder.
+    ExportDialog,
+
+    /// Export person's photos to a folder.
+    Export(PathBuf),
+
+    /// Review the faces assigned to the person.
+    FacesDialog,
+
+    /// Faces or cover of the person changed in the faces dialog.
+    FacesChanged,
+
+    Sort(SortKey),
+
+    Zoom(GridZoom),
 }
 
 #[derive(Debug)]
@@ -89,6 +148,36 @@ pub enum PersonAlbumOutput {
 
     /// Person renamed.
     Renamed,
+
+    /// Person hidden or shown in people album.
+    Hidden,
+
+    /// Faces or cover of the person changed.
+    FacesChanged,
+
+    /// Copy a person's photos to a folder.
+    Export(people::PersonId, PathBuf),
+
+    /// User has starred or unstarred pictures as favorites.
+    SetFavorite(Vec<PictureId>, bool),
+
+    /// User has moved pictures to, or restored pictures from, the trash.
+    SetTrashed(Vec<PictureId>, bool),
+
+    /// User has chosen a picture as the cover of a folder.
+    SetFolderCover(PathBuf, PictureId),
+
+    /// User wants the thumbnail of a picture regenerated.
+    RegenerateThumbnail(PictureId),
+
+    /// User wants the frames of a burst shown as separate pictures.
+    UngroupBurst(BurstId),
+
+    /// User has moved pictures to another folder.
+    MovePictures(Vec<PictureId>, PathBuf),
+
+    /// User wants the one unknown face of each picture named as a person.
+    AssignToPerson(Vec<PictureId>),
 }
 
 pub struct PersonAlbum {
@@ -99,12 +188,21 @@ pub struct PersonAlbum {
     avatar: adw::Avatar,
     title: gtk::Label,
     active_view: ActiveView,
+    settings_state: SettingsState,
     edge_length: I32Binding,
+    hide_action: gio::SimpleAction,
+    faces_dialog: Controller<PersonFacesDialog>,
 }
 
 #[relm4::component(pub)]
 impl SimpleComponent for PersonAlbum {
-    type Init = (SharedState, people::Repository, ActiveView, Rc<Thumbnailer>);
+    type Init = (
+        SharedState,
+        people::Repository,
+        ActiveView,
+        SettingsState,
+        Rc<Thumbnailer>,
+    );
     type Input = PersonAlbumInput;
     type Output = PersonAlbumOutput;
 
@@ -114,6 +212,13 @@ impl SimpleComponent for PersonAlbum {
                 // FIXME I would like to have the person's name in these menu items.
                 &fl!("person-menu-rename") => RenameAction,
                 &fl!("person-menu-delete") => DeleteAction,
+            },
+            section! {
+                &fl!("person-menu-faces") => FacesAction,
+                &fl!("person-menu-hide") => HideAction,
+            },
+            section! {
+                &fl!("person-menu-export") => ExportAction,
             }
         }
     }
@@ -148,10 +253,12 @@ impl SimpleComponent for PersonAlbum {
     }
 
     fn init(
-        (state, repo, active_view, thumbnailer): Self::Init,
+        (state, repo, active_view, settings_state, thumbnailer): Self::Init,
         root: Self::Root,
         sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
+        settings_state.subscribe(sender.input_sender(), |_| PersonAlbumInput::SettingsChanged);
+
         let avatar = adw::Avatar::builder()
             .size(NARROW_EDGE_LENGTH)
             .show_initials(true)
@@ -168,10 +275,46 @@ impl SimpleComponent for PersonAlbum {
             .forward(sender.input_sender(), |msg| match msg {
                 AlbumOutput::Selected(id, _) => PersonAlbumInput::Selected(id),
                 AlbumOutput::ScrollOffset(offset) => PersonAlbumInput::ScrollOffset(offset),
+                AlbumOutput::SetFavorite(picture_ids, is_favorite) => {
+                    PersonAlbumInput::SetFavorite(picture_ids, is_favorite)
+                }
+                AlbumOutput::SetTrashed(picture_ids, is_trashed) => {
+                    PersonAlbumInput::SetTrashed(picture_ids, is_trashed)
+                }
+                AlbumOutput::SetFolderCover(folder_path, picture_id) => {
+                    PersonAlbumInput::SetFolderCover(folder_path, picture_id)
+                }
+                AlbumOutput::RegenerateThumbnail(picture_id) => {
+                    PersonAlbumInput::RegenerateThumbnail(picture_id)
+                }
+                AlbumOutput::UngroupBurst(burst_id) => PersonAlbumInput::UngroupBurst(burst_id),
+                AlbumOutput::MovePictures(picture_ids, dest_folder) => {
+                    PersonAlbumInput::MovePictures(picture_ids, dest_folder)
+                }
+                AlbumOutput::AssignToPerson(picture_ids) => {
+                    PersonAlbumInput::AssignToPerson(picture_ids)
+                }
+                // A person album never shows a user album, so is never arranged.
+                AlbumOutput::SetUserAlbumOrder(_, _) => PersonAlbumInput::Ignore,
             });
 
         let title = gtk::Label::builder().build();
 
+        let hide_action = {
+            let sender = sender.clone();
+            RelmAction::<HideAction>::new_stateful(&false, move |_, is_hidden| {
+                *is_hidden = !*is_hidden;
+                sender.input(PersonAlbumInput::SetHidden(*is_hidden));
+            })
+        };
+
+        let faces_dialog = PersonFacesDialog::builder().launch(repo.clone()).forward(
+            sender.input_sender(),
+            |msg| match msg {
+                PersonFacesOutput::Changed => PersonAlbumInput::FacesChanged,
+            },
+        );
+
         let model = PersonAlbum {
             repo,
             person: None,
@@ -179,8 +322,11 @@ impl SimpleComponent for PersonAlbum {
             title: title.clone(),
             album,
             active_view,
+            settings_state,
             picture_ids: vec![],
             edge_length: I32Binding::new(NARROW_EDGE_LENGTH),
+            hide_action: hide_action.gio_action().clone(),
+            faces_dialog,
         };
 
         model
@@ -204,8 +350,25 @@ impl SimpleComponent for PersonAlbum {
             })
         };
 
+        let export_action = {
+            let sender = sender.clone();
+            RelmAction::<ExportAction>::new_stateless(move |_| {
+                sender.input(PersonAlbumInput::ExportDialog);
+            })
+        };
+
+        let faces_action = {
+            let sender = sender.clone();
+            RelmAction::<FacesAction>::new_stateless(move |_| {
+                sender.input(PersonAlbumInput::FacesDialog);
+            })
+        };
+
         actions.add_action(rename_action);
         actions.add_action(delete_action);
+        actions.add_action(export_action);
+        actions.add_action(faces_action);
+        actions.add_action(hide_action);
         actions.register_for_widget(&root);
 
         ComponentParts { model, widgets }
@@ -213,6 +376,7 @@ impl SimpleComponent for PersonAlbum {
 
     fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
         match msg {
+            PersonAlbumInput::Ignore => {}
             PersonAlbumInput::Activate => {
                 *self.active_view.write() = ViewName::Person;
                 self.album.sender().emit(AlbumInput::Activate);
@@ -221,10 +385,13 @@ impl SimpleComponent for PersonAlbum {
                 self.album.sender().emit(AlbumInput::Refresh);
             }
             PersonAlbumInput::Sort(sort) => {
-                self.album.sender().emit(AlbumInput::Sort(sort));
+                self.album.sender().emit(AlbumInput::SortBy(sort));
                 self.album.sender().emit(AlbumInput::ScrollToTop)
                 //self.album.sender().emit(AlbumInput::ScrollOffset(0.0));
             }
+            PersonAlbumInput::Zoom(zoom) => {
+                self.album.sender().emit(AlbumInput::Zoom(zoom));
+            }
             PersonAlbumInput::View(person) => {
                 info!("Viewing album for person: {}", person.person_id);
 
@@ -233,10 +400,9 @@ impl SimpleComponent for PersonAlbum {
                     .filter(|p| p.exists())
                     .or(person.small_thumbnail_path.as_ref());
 
-                if let Some(ref thumbnail_path) = thumbnail_path {
-                    let img = gdk::Texture::from_filename(&thumbnail_path).ok();
-                    self.avatar.set_custom_image(img.as_ref());
-                }
+                // Cleared if the cover face has been unassigned, so initials are shown.
+                let img = thumbnail_path.and_then(|path| gdk::Texture::from_filename(path).ok());
+                self.avatar.set_custom_image(img.as_ref());
 
                 self.avatar.set_text(Some(&person.name));
 
@@ -244,9 +410,10 @@ impl SimpleComponent for PersonAlbum {
                     self.avatar.set_visible(true);
                 }
 
+                let min_confidence = self.settings_state.read().face_confidence_threshold;
                 self.picture_ids = self
                     .repo
-                    .find_pictures_for_person(person.person_id)
+                    .find_pictures_for_person(person.person_id, min_confidence)
                     .unwrap_or_default();
                 info!(
                     "Person {} has {} items to view.",
@@ -261,9 +428,37 @@ impl SimpleComponent for PersonAlbum {
                     )));
                 self.album.sender().emit(AlbumInput::ScrollToTop);
 
+                self.hide_action.set_state(&person.is_ignored.to_variant());
+
                 self.title.set_label(&person.name);
                 self.person = Some(person);
             }
+            PersonAlbumInput::SettingsChanged => {
+                if let Some(person) = self.person.clone() {
+                    sender.input(PersonAlbumInput::View(person));
+                }
+            }
+            PersonAlbumInput::SetFavorite(picture_ids, is_favorite) => {
+                let _ = sender.output(PersonAlbumOutput::SetFavorite(picture_ids, is_favorite));
+            }
+            PersonAlbumInput::SetTrashed(picture_ids, is_trashed) => {
+                let _ = sender.output(PersonAlbumOutput::SetTrashed(picture_ids, is_trashed));
+            }
+            PersonAlbumInput::SetFolderCover(folder_path, picture_id) => {
+                let _ = sender.output(PersonAlbumOutput::SetFolderCover(folder_path, picture_id));
+            }
+            PersonAlbumInput::RegenerateThumbnail(picture_id) => {
+                let _ = sender.output(PersonAlbumOutput::RegenerateThumbnail(picture_id));
+            }
+            PersonAlbumInput::UngroupBurst(burst_id) => {
+                let _ = sender.output(PersonAlbumOutput::UngroupBurst(burst_id));
+            }
+            PersonAlbumInput::MovePictures(picture_ids, dest_folder) => {
+                let _ = sender.output(PersonAlbumOutput::MovePictures(picture_ids, dest_folder));
+            }
+            PersonAlbumInput::AssignToPerson(picture_ids) => {
+                let _ = sender.output(PersonAlbumOutput::AssignToPerson(picture_ids));
+            }
             PersonAlbumInput::Selected(visual_id) => {
                 let _ = sender.output(PersonAlbumOutput::Selected(
                     visual_id,
@@ -348,6 +543,7 @@ impl SimpleComponent for PersonAlbum {
                     return;
                 };
 
+                let name = name.trim().to_string();
                 info!("Renaming {} to {}", person.name, name);
 
                 if let Err(e) = self.repo.rename_person(person.person_id, &name) {
@@ -358,6 +554,80 @@ impl SimpleComponent for PersonAlbum {
                 person.name = name;
                 let _ = sender.output(PersonAlbumOutput::Renamed);
             }
+            PersonAlbumInput::SetHidden(is_hidden) => {
+                let Some(ref mut person) = self.person else {
+                    info!("Asked to hide person, but no person for album");
+                    return;
+                };
+
+                info!("Setting person {} hidden: {}", person.person_id, is_hidden);
+
+                if let Err(e) = self.repo.set_person_ignored(person.person_id, is_hidden) {
+                    error!("Failed to hide person: {}", e);
+                    self.hide_action.set_state(&person.is_ignored.to_variant());
+                    return;
+                }
+                person.is_ignored = is_hidden;
+                let _ = sender.output(PersonAlbumOutput::Hidden);
+            }
+            PersonAlbumInput::ExportDialog => {
+                let Some(ref person) = self.person else {
+                    info!("Asked to export person, but no person for album");
+                    return;
+                };
+                info!("Starting export flow for person: {}", person.person_id);
+
+                let dialog = gtk::FileDialog::builder()
+                    .title(fl!("person-export-folder-dialog"))
+                    .modal(true)
+                    .build();
+
+                let window = gtk::Widget::root(self.avatar.widget_ref())
+                    .and_then(|root| root.downcast::<gtk::Window>().ok());
+
+                dialog.select_folder(window.as_ref(), gio::Cancellable::NONE, move |result| {
+                    match result.map(|folder| folder.path()) {
+                        Ok(Some(path)) => sender.input(PersonAlbumInput::Export(path)),
+                        Ok(None) => error!("Chosen export folder has no path"),
+                        Err(e) => info!("No export folder chosen: {}", e),
+                    }
+                });
+            }
+            PersonAlbumInput::Export(destination) => {
+                let Some(ref person) = self.person else {
+                    info!("Asked to export person, but no person for album");
+                    return;
+                };
+                info!("Exporting person {} to {:?}", person.person_id, destination);
+                let _ = sender.output(PersonAlbumOutput::Export(person.person_id, destination));
+            }
+            PersonAlbumInput::FacesDialog => {
+                let Some(ref person) = self.person else {
+                    info!("Asked to review faces, but no person for album");
+                    return;
+                };
+                info!("Reviewing faces for person: {}", person.person_id);
+
+                self.faces_dialog
+                    .emit(PersonFacesInput::Present(person.clone()));
+
+                if let Some(root) = gtk::Widget::root(self.avatar.widget_ref()) {
+                    self.faces_dialog.widget().present(Some(&root));
+                } else {
+                    error!("Couldn't get root widget!");
+                }
+            }
+            PersonAlbumInput::FacesChanged => {
+                let Some(ref person) = self.person else {
+                    return;
+                };
+                match self.repo.get_person(person.person_id) {
+                    Ok(Some(person)) => sender.input(PersonAlbumInput::View(person)),
+                    Ok(None) => error!("Person {} no longer exists", person.person_id),
+                    Err(e) => error!("Failed reloading person: {}", e),
+                }
+                let _ = sender.output(PersonAlbumOutput::FacesChanged);
+            }
             PersonAlbumInput::DeleteDialog => {
                 let Some(ref person) = self.person else {
                     info!("Asked to delete person, but no person for album");
@@ -0,0 +1,444 @@
+// SPDX-FileCopyrightText: © 2025 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use relm4::adw::prelude::*;
+use relm4::binding::*;
+use relm4::gtk;
+use relm4::gtk::gdk;
+use relm4::gtk::gdk_pixbuf;
+use relm4::gtk::glib;
+use relm4::prelude::*;
+use relm4::typed_view::grid::{RelmGridItem, TypedGridView};
+
+use tracing::{error, info};
+
+use std::path::Path;
+
+use crate::fl;
+use fotema_core::FaceId;
+use fotema_core::PersonId;
+use fotema_core::people;
+
+const FACE_EDGE_LENGTH: i32 = 96;
+
+#[derive(Debug)]
+struct FaceGridItem {
+    face: people::Face,
+
+    // Is the face shown as the person's avatar?
+    is_cover: bool,
+
+    // Is the face of the person? Deselected faces are misattributed.
+    selected: BoolBinding,
+
+    // For sending cover choices back to the dialog.
+    sender: relm4::Sender<PersonFacesInput>,
+}
+
+struct FaceGridItemWidgets {
+    picture: gtk::Picture,
+    select_button: gtk::CheckButton,
+    cover_button: gtk::ToggleButton,
+
+    // Handler for cover_button clicks. Must be disconnected on unbind.
+    cover_handler: Option<glib::SignalHandlerId>,
+
+    // Binding of select_button to the item's selected state. Must be unbound on unbind.
+    selected_binding: Option<glib::Binding>,
+}
+
+impl RelmGridItem for FaceGridItem {
+    type Root = gtk::Frame;
+    type Widgets = FaceGridItemWidgets;
+
+    fn setup(_item: &gtk::ListItem) -> (Self::Root, Self::Widgets) {
+        relm4::view! {
+            root = gtk::Frame {
+                gtk::Overlay {
+                    #[name(cover_button)]
+                    add_overlay = &gtk::ToggleButton {
+                        set_halign: gtk::Align::End,
+                        set_valign: gtk::Align::Start,
+                        set_margin_all: 4,
+                        set_icon_name: "avatar-default-symbolic",
+                        set_tooltip_text: Some(&fl!("person-faces-item", "cover-tooltip")),
+                        add_css_class: "osd",
+                        add_css_class: "circular",
+                    },
+
+                    #[name(select_button)]
+                    add_overlay = &gtk::CheckButton {
+                        set_halign: gtk::Align::Start,
+                        set_valign: gtk::Align::Start,
+                        set_margin_all: 4,
+                        add_css_class: "selection-mode",
+                    },
+
+                    #[wrap(Some)]
+                    #[name(picture)]
+                    set_child = &gtk::Picture {
+                        set_content_fit: gtk::ContentFit::Cover,
+                        set_width_request: FACE_EDGE_LENGTH,
+                        set_height_request: FACE_EDGE_LENGTH,
+                    }
+                }
+            }
+        }
+
+        let widgets = FaceGridItemWidgets {
+            picture,
+            select_button,
+            cover_button,
+            cover_handler: None,
+            selected_binding: None,
+        };
+
+        (root, widgets)
+    }
+
+    fn bind(&mut self, widgets: &mut Self::Widgets, _root: &mut Self::Root) {
+        let selected_binding = self
+            .selected
+            .bind_property("value", &widgets.select_button, "active")
+            .bidirectional()
+            .sync_create()
+            .build();
+        widgets.selected_binding = Some(selected_binding);
+
+        if self.face.thumbnail_path.exists() {
+            widgets
+                .picture
+                .set_filename(Some(&self.face.thumbnail_path));
+            widgets.picture.set_content_fit(gtk::ContentFit::Cover);
+        } else {
+            let pb = gdk_pixbuf::Pixbuf::from_resource_at_scale(
+                "/app/fotema/Fotema/icons/scalable/actions/image-missing-symbolic.svg",
+                200,
+                200,
+                true,
+            )
+            .unwrap();
+            let img = gdk::Texture::for_pixbuf(&pb);
+            widgets.picture.set_paintable(Some(&img));
+            widgets.picture.set_content_fit(gtk::ContentFit::Contain);
+        }
+
+        // Choosing another cover rebuilds the grid, so the button only needs to
+        // pick a cover, not clear one.
+        widgets.cover_button.set_active(self.is_cover);
+        widgets.cover_button.set_sensitive(!self.is_cover);
+
+        let sender = self.sender.clone();
+        let face_id = self.face.face_id;
+        let handler = widgets.cover_button.connect_clicked(move |_| {
+            sender.emit(PersonFacesInput::SetCover(face_id));
+        });
+        widgets.cover_handler = Some(handler);
+    }
+
+    fn unbind(&mut self, widgets: &mut Self::Widgets, _root: &mut Self::Root) {
+        widgets.picture.set_filename(None::<&Path>);
+        if let Some(handler) = widgets.cover_handler.take() {
+            widgets.cover_button.disconnect(handler);
+        }
+        if let Some(binding) = widgets.selected_binding.take() {
+            binding.unbind();
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum PersonFacesInput {
+    /// Load the faces of a person. The caller presents the dialog.
+    Present(people::Person),
+
+    /// A face has been selected or deselected.
+    SelectionToggled,
+
+    /// Show a face as the person's avatar.
+    SetCover(FaceId),
+
+    /// Deselected faces aren't of any known person.
+    Unassign,
+
+    /// Deselected faces are of another person.
+    Reassign(PersonId),
+}
+
+#[derive(Debug)]
+pub enum PersonFacesOutput {
+    /// Faces or cover of the person have changed.
+    Changed,
+}
+
+pub struct PersonFacesDialog {
+    repo: people::Repository,
+    person: Option<people::Person>,
+    face_grid: TypedGridView<FaceGridItem, gtk::NoSelection>,
+
+    /// Other people that deselected faces can be moved to.
+    people_list: gtk::ListBox,
+    people_popover: gtk::Popover,
+
+    /// Number of faces deselected as not being the person.
+    deselected_count: usize,
+}
+
+impl PersonFacesDialog {
+    /// Fill the grid with the person's faces, all selected.
+    fn load_faces(&mut self, sender: &ComponentSender<Self>) {
+        self.face_grid.clear();
+        self.deselected_count = 0;
+
+        let Some(ref person) = self.person else {
+            return;
+        };
+
+        let faces = self
+            .repo
+            .faces_for_person(person.person_id)
+            .unwrap_or_else(|e| {
+                error!("Failed loading faces for person: {:?}", e);
+                vec![]
+            });
+
+        info!("Person {} has {} faces", person.person_id, faces.len());
+
+        let items = faces.into_iter().map(|face| {
+            let selected = BoolBinding::new(true);
+            let input = sender.input_sender().clone();
+            selected.connect_notify_local(Some("value"), move |_, _| {
+                input.emit(PersonFacesInput::SelectionToggled);
+            });
+
+            FaceGridItem {
+                is_cover: person.cover_face_id == Some(face.face_id),
+                face,
+                selected,
+                sender: sender.input_sender().clone(),
+            }
+        });
+
+        self.face_grid.extend_from_iter(items);
+    }
+
+    /// Show other people that deselected faces can be moved to.
+    fn load_people(&mut self, sender: &ComponentSender<Self>) {
+        self.people_list.remove_all();
+
+        let Some(ref person) = self.person else {
+            return;
+        };
+
+        let people = self.repo.all_people(true).unwrap_or_else(|e| {
+            error!("Failed loading people: {:?}", e);
+            vec![]
+        });
+
+        for other in people
+            .into_iter()
+            .filter(|other| other.person_id != person.person_id)
+        {
+            let avatar = adw::Avatar::builder().size(32).name(&other.name).build();
+
+            if let Some(thumbnail_path) = other.thumbnail_path() {
+                let img = gdk::Texture::from_filename(thumbnail_path).ok();
+                avatar.set_custom_image(img.as_ref());
+            }
+
+            let row = adw::ActionRow::builder()
+                .title(&other.name)
+                .activatable(true)
+                .build();
+            row.add_prefix(&avatar);
+
+            {
+                let sender = sender.clone();
+                let popover = self.people_popover.clone();
+                let person_id = other.person_id;
+                row.connect_activated(move |_| {
+                    popover.popdown();
+                    sender.input(PersonFacesInput::Reassign(person_id));
+                });
+            }
+
+            self.people_list.append(&row);
+        }
+    }
+
+    /// Faces the user has deselected as not being the person.
+    fn deselected_face_ids(&self) -> Vec<FaceId> {
+        (0..self.face_grid.len())
+            .filter_map(|index| self.face_grid.get(index))
+            .filter_map(|item| {
+                let item = item.borrow();
+                (!item.selected.value()).then_some(item.face.face_id)
+            })
+            .collect()
+    }
+
+    fn refresh_person(&mut self, sender: &ComponentSender<Self>) {
+        let Some(person_id) = self.person.as_ref().map(|p| p.person_id) else {
+            return;
+        };
+
+        match self.repo.get_person(person_id) {
+            Ok(person) => self.person = person,
+            Err(e) => error!("Failed reloading person: {:?}", e),
+        }
+
+        self.load_faces(sender);
+        let _ = sender.output(PersonFacesOutput::Changed);
+    }
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for PersonFacesDialog {
+    type Init = people::Repository;
+    type Input = PersonFacesInput;
+    type Output = PersonFacesOutput;
+
+    view! {
+        adw::Dialog {
+            #[watch]
+            set_title: &model
+                .person
+                .as_ref()
+                .map(|person| person.name.clone())
+                .unwrap_or_default(),
+            set_content_width: 560,
+            set_content_height: 560,
+
+            #[wrap(Some)]
+            set_child = &adw::ToolbarView {
+                add_top_bar = &adw::HeaderBar,
+
+                #[wrap(Some)]
+                set_content = &gtk::Box {
+                    set_orientation: gtk::Orientation::Vertical,
+
+                    gtk::Label {
+                        set_margin_all: 12,
+                        set_wrap: true,
+                        set_label: &fl!("person-faces", "description"),
+                    },
+
+                    gtk::ScrolledWindow {
+                        set_vexpand: true,
+
+                        #[local_ref]
+                        face_grid_view -> gtk::GridView {
+                            set_orientation: gtk::Orientation::Vertical,
+                            set_single_click_activate: false,
+                        },
+                    },
+                },
+
+                add_bottom_bar = &gtk::ActionBar {
+                    #[watch]
+                    set_revealed: model.deselected_count > 0,
+
+                    pack_start = &gtk::Button {
+                        set_label: &fl!("person-faces", "unassign-button"),
+                        add_css_class: "destructive-action",
+                        connect_clicked => PersonFacesInput::Unassign,
+                    },
+
+                    pack_end = &gtk::MenuButton {
+                        set_label: &fl!("person-faces", "reassign-button"),
+                        set_direction: gtk::ArrowType::Up,
+
+                        #[wrap(Some)]
+                        #[local_ref]
+                        set_popover = &people_popover -> gtk::Popover {
+                            #[wrap(Some)]
+                            set_child = &gtk::ScrolledWindow {
+                                set_propagate_natural_height: true,
+                                set_max_content_height: 320,
+                                set_min_content_width: 240,
+
+                                #[local_ref]
+                                people_list -> gtk::ListBox {
+                                    add_css_class: "navigation-sidebar",
+                                },
+                            },
+                        },
+                    },
+                },
+            },
+        }
+    }
+
+    fn init(
+        repo: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let face_grid = TypedGridView::new();
+        let people_list = gtk::ListBox::new();
+        let people_popover = gtk::Popover::new();
+
+        let model = Self {
+            repo,
+            person: None,
+            face_grid,
+            people_list: people_list.clone(),
+            people_popover: people_popover.clone(),
+            deselected_count: 0,
+        };
+
+        let face_grid_view = &model.face_grid.view.clone();
+
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            PersonFacesInput::Present(person) => {
+                info!("Reviewing faces of person {}", person.person_id);
+                self.person = Some(person);
+                self.load_faces(&sender);
+                self.load_people(&sender);
+            }
+            PersonFacesInput::SelectionToggled => {
+                self.deselected_count = self.deselected_face_ids().len();
+            }
+            PersonFacesInput::SetCover(face_id) => {
+                let Some(ref person) = self.person else {
+                    return;
+                };
+                info!(
+                    "Face {} is now cover of person {}",
+                    face_id, person.person_id
+                );
+
+                if let Err(e) = self.repo.set_person_thumbnail(person.person_id, face_id) {
+                    error!("Failed setting person cover: {:?}", e);
+                    return;
+                }
+                self.refresh_person(&sender);
+            }
+            PersonFacesInput::Unassign => {
+                for face_id in self.deselected_face_ids() {
+                    info!("Unassigning face {}", face_id);
+                    if let Err(e) = self.repo.unassign_face(face_id) {
+                        error!("Failed unassigning face: {:?}", e);
+                    }
+                }
+                self.refresh_person(&sender);
+            }
+            PersonFacesInput::Reassign(person_id) => {
+                for face_id in self.deselected_face_ids() {
+                    info!("Reassigning face {} to person {}", face_id, person_id);
+                    if let Err(e) = self.repo.mark_as_person(face_id, person_id) {
+                        error!("Failed reassigning face: {:?}", e);
+                    }
+                }
+                self.refresh_person(&sender);
+            }
+        }
+    }
+}
@@ -415,6 +415,7 @@ impl PlacesAlbum {
             .nearest_thumbnail(&visual.thumbnail_hash(), ThumbnailSize::Normal);
 
         let picture = if let Some(thumbnail_path) = thumbnail_path {
+            let _ = self.thumbnailer.touch(&thumbnail_path);
             let picture = gtk::Picture::for_filename(thumbnail_path);
             picture.set_content_fit(gtk::ContentFit::Cover);
             picture
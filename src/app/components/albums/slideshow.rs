@@ -0,0 +1,405 @@
+// SPDX-FileCopyrightText: © 2025 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Fullscreen slideshow of the pictures and videos in an album.
+
+use fotema_core::Visual;
+use fotema_core::thumbnailify::{ThumbnailSize, Thumbnailer};
+
+use relm4::adw::prelude::*;
+use relm4::gtk;
+use relm4::gtk::{gdk, gio, glib};
+use relm4::*;
+
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::fl;
+
+use tracing::{debug, error, info};
+
+#[derive(Debug)]
+pub enum SlideshowInput {
+    /// Show items fullscreen, starting with the item at `index`.
+    Start {
+        visuals: Vec<Arc<Visual>>,
+        index: usize,
+        interval: Duration,
+    },
+
+    /// Show the next item. Wraps around to the first item.
+    Next,
+
+    /// Show the previous item. Wraps around to the last item.
+    Previous,
+
+    /// Pause or resume moving from one item to the next.
+    PlayToggle,
+
+    /// Interval has elapsed or a video has played through.
+    Advance,
+
+    /// Leave fullscreen and close the slideshow.
+    Exit,
+}
+
+pub struct Slideshow {
+    window: adw::Window,
+    picture: gtk::Picture,
+    thumbnailer: Rc<Thumbnailer>,
+
+    /// Items in the order they are shown.
+    visuals: Vec<Arc<Visual>>,
+
+    /// Index of item being shown.
+    index: usize,
+
+    /// How long each picture is shown for.
+    interval: Duration,
+
+    /// Is the slideshow moving from one item to the next?
+    is_playing: bool,
+
+    // Video being shown. Must be paused before showing another item.
+    video: Option<gtk::MediaFile>,
+
+    // Loads the picture being shown. Must be aborted before showing another item.
+    loader: Option<glib::JoinHandle<()>>,
+
+    // Waits for the interval to elapse. Must be aborted before showing another item.
+    timer: Option<glib::JoinHandle<()>>,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for Slideshow {
+    type Init = Rc<Thumbnailer>;
+    type Input = SlideshowInput;
+    type Output = ();
+
+    view! {
+        adw::Window {
+            set_modal: true,
+            set_hide_on_close: true,
+            set_default_width: 800,
+            set_default_height: 600,
+
+            connect_close_request[sender] => move |_| {
+                sender.input(SlideshowInput::Exit);
+                glib::Propagation::Stop
+            },
+
+            #[wrap(Some)]
+            set_content = &gtk::Overlay {
+                #[local_ref]
+                picture -> gtk::Picture {
+                    set_content_fit: gtk::ContentFit::Contain,
+                    set_hexpand: true,
+                    set_vexpand: true,
+                },
+
+                add_overlay = &gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_halign: gtk::Align::Center,
+                    set_valign: gtk::Align::End,
+                    set_margin_bottom: 18,
+                    set_spacing: 12,
+
+                    gtk::Button {
+                        set_icon_name: "left-symbolic",
+                        add_css_class: "circular",
+                        add_css_class: "osd",
+                        set_tooltip_text: Some(&fl!("viewer-previous", "tooltip")),
+                        connect_clicked => SlideshowInput::Previous,
+                    },
+
+                    gtk::Button {
+                        #[watch]
+                        set_icon_name: if model.is_playing { "pause-symbolic" } else { "play-symbolic" },
+                        add_css_class: "circular",
+                        add_css_class: "osd",
+                        set_tooltip_text: Some(&fl!("viewer-play", "tooltip")),
+                        connect_clicked => SlideshowInput::PlayToggle,
+                    },
+
+                    gtk::Button {
+                        set_icon_name: "right-symbolic",
+                        add_css_class: "circular",
+                        add_css_class: "osd",
+                        set_tooltip_text: Some(&fl!("viewer-next", "tooltip")),
+                        connect_clicked => SlideshowInput::Next,
+                    },
+
+                    gtk::Button {
+                        set_icon_name: "window-close-symbolic",
+                        add_css_class: "circular",
+                        add_css_class: "osd",
+                        set_tooltip_text: Some(&fl!("album-slideshow", "exit")),
+                        connect_clicked => SlideshowInput::Exit,
+                    },
+                },
+            },
+        }
+    }
+
+    fn init(
+        thumbnailer: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let picture = gtk::Picture::new();
+
+        let model = Slideshow {
+            window: root.clone(),
+            picture: picture.clone(),
+            thumbnailer,
+            visuals: Vec::new(),
+            index: 0,
+            interval: Duration::from_secs(5),
+            is_playing: true,
+            video: None,
+            loader: None,
+            timer: None,
+        };
+
+        let widgets = view_output!();
+
+        // Capture phase, otherwise the focused button consumes space.
+        let shortcuts = gtk::ShortcutController::new();
+        shortcuts.set_propagation_phase(gtk::PropagationPhase::Capture);
+        shortcuts.add_shortcut(shortcut("Escape", &sender, || SlideshowInput::Exit));
+        shortcuts.add_shortcut(shortcut("Left", &sender, || SlideshowInput::Previous));
+        shortcuts.add_shortcut(shortcut("Right", &sender, || SlideshowInput::Next));
+        shortcuts.add_shortcut(shortcut("space", &sender, || SlideshowInput::PlayToggle));
+        root.add_controller(shortcuts);
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            SlideshowInput::Start {
+                visuals,
+                index,
+                interval,
+            } => {
+                if visuals.is_empty() {
+                    return;
+                }
+                info!(
+                    "Starting slideshow of {} items every {} seconds",
+                    visuals.len(),
+                    interval.as_secs()
+                );
+                self.index = index.min(visuals.len() - 1);
+                self.visuals = visuals;
+                self.interval = interval;
+                self.is_playing = true;
+                self.window.fullscreen();
+                self.window.present();
+                self.show(&sender);
+            }
+            SlideshowInput::Next => {
+                if self.visuals.is_empty() {
+                    return;
+                }
+                self.index = (self.index + 1) % self.visuals.len();
+                self.show(&sender);
+            }
+            SlideshowInput::Previous => {
+                if self.visuals.is_empty() {
+                    return;
+                }
+                self.index = (self.index + self.visuals.len() - 1) % self.visuals.len();
+                self.show(&sender);
+            }
+            SlideshowInput::PlayToggle => {
+                self.is_playing = !self.is_playing;
+                if let Some(ref video) = self.video {
+                    if self.is_playing {
+                        video.play();
+                    } else {
+                        video.pause();
+                    }
+                } else if self.is_playing {
+                    self.start_timer(&sender);
+                } else if let Some(timer) = self.timer.take() {
+                    timer.abort();
+                }
+            }
+            SlideshowInput::Advance => {
+                if self.is_playing {
+                    sender.input(SlideshowInput::Next);
+                }
+            }
+            SlideshowInput::Exit => {
+                info!("Stopping slideshow");
+                self.stop();
+                self.picture.set_paintable(None::<&gdk::Paintable>);
+                self.visuals.clear();
+                self.window.unfullscreen();
+                self.window.set_visible(false);
+            }
+        }
+    }
+}
+
+impl Slideshow {
+    /// Show the item at the current index. Pictures are shown for the interval, but
+    /// videos are played through.
+    fn show(&mut self, sender: &ComponentSender<Self>) {
+        self.stop();
+
+        let Some(visual) = self.visuals.get(self.index).cloned() else {
+            return;
+        };
+        debug!("Slideshow showing {}", visual.visual_id);
+
+        if let Some(video_path) = playable_video_path(&visual) {
+            let video = gtk::MediaFile::for_filename(video_path);
+
+            let sender1 = sender.input_sender().clone();
+            video.connect_ended_notify(move |video| {
+                if video.is_ended() {
+                    sender1.emit(SlideshowInput::Advance);
+                }
+            });
+
+            // Move on from videos that can't be played, rather than getting stuck.
+            let sender2 = sender.input_sender().clone();
+            video.connect_error_notify(move |video| {
+                if video.error().is_some() {
+                    sender2.emit(SlideshowInput::Advance);
+                }
+            });
+
+            self.picture.set_paintable(Some(&video));
+            if self.is_playing {
+                video.play();
+            }
+            self.video = Some(video);
+            return;
+        }
+
+        self.picture.set_paintable(None::<&gdk::Paintable>);
+
+        // Grid thumbnails are too small to show fullscreen.
+        let thumbnail_path = self
+            .thumbnailer
+            .nearest_thumbnail(&visual.thumbnail_hash(), ThumbnailSize::XXLarge)
+            .filter(|path| path.exists());
+
+        let picture = self.picture.clone();
+        let loader = glib::spawn_future_local(async move {
+            if let Some(texture) = load_texture(&visual, thumbnail_path).await {
+                picture.set_paintable(Some(&texture));
+            }
+        });
+        self.loader = Some(loader);
+
+        if self.is_playing {
+            self.start_timer(sender);
+        }
+    }
+
+    fn start_timer(&mut self, sender: &ComponentSender<Self>) {
+        if let Some(timer) = self.timer.take() {
+            timer.abort();
+        }
+
+        let sender = sender.input_sender().clone();
+        let interval = self.interval;
+        let timer = glib::spawn_future_local(async move {
+            glib::timeout_future(interval).await;
+            sender.emit(SlideshowInput::Advance);
+        });
+        self.timer = Some(timer);
+    }
+
+    /// Stop loading, waiting on, or playing the item being shown.
+    fn stop(&mut self) {
+        if let Some(loader) = self.loader.take() {
+            loader.abort();
+        }
+        if let Some(timer) = self.timer.take() {
+            timer.abort();
+        }
+        if let Some(video) = self.video.take() {
+            video.pause();
+        }
+    }
+}
+
+/// Path of a video that can be played without transcoding.
+/// Motion photos are shown as pictures.
+fn playable_video_path(visual: &Visual) -> Option<PathBuf> {
+    if !visual.is_video_only() {
+        return None;
+    }
+
+    let is_transcode_required = visual.is_transcode_required.is_some_and(|x| x);
+
+    visual
+        .video_transcoded_path
+        .clone()
+        .filter(|path| path.exists())
+        .or_else(|| {
+            visual
+                .video_path
+                .clone()
+                .map(|path| path.sandbox_path)
+                .filter(|path| !is_transcode_required && path.exists())
+        })
+}
+
+/// Loads a large thumbnail, or the original picture if there is no thumbnail.
+async fn load_texture(visual: &Visual, thumbnail_path: Option<PathBuf>) -> Option<gdk::Texture> {
+    if let Some(thumbnail_path) = thumbnail_path {
+        let file = gio::File::for_path(&thumbnail_path);
+        let texture = file
+            .load_bytes_future()
+            .await
+            .and_then(|(bytes, _)| gdk::Texture::from_bytes(&bytes));
+
+        match texture {
+            Ok(texture) => return Some(texture),
+            Err(e) => debug!("Failed loading thumbnail {:?}: {}", thumbnail_path, e),
+        }
+    }
+
+    // Only pictures can be loaded without a thumbnail.
+    let picture_path = visual.picture_path.as_ref()?;
+    let file = gio::File::for_path(&picture_path.sandbox_path);
+
+    let image = match glycin::Loader::new(file).load().await {
+        Ok(image) => image,
+        Err(e) => {
+            error!("Failed loading picture {:?}: {:?}", picture_path, e);
+            return None;
+        }
+    };
+
+    match image.next_frame().await {
+        Ok(frame) => Some(frame.texture()),
+        Err(e) => {
+            error!("Failed loading picture frame {:?}: {:?}", picture_path, e);
+            None
+        }
+    }
+}
+
+/// Keyboard shortcut sending an input message to the slideshow.
+fn shortcut(
+    trigger: &str,
+    sender: &ComponentSender<Slideshow>,
+    msg: fn() -> SlideshowInput,
+) -> gtk::Shortcut {
+    let sender = sender.input_sender().clone();
+    let action = gtk::CallbackAction::new(move |_, _| {
+        sender.emit(msg());
+        glib::Propagation::Stop
+    });
+    gtk::Shortcut::new(gtk::ShortcutTrigger::parse_string(trigger), Some(action))
+}
@@ -27,7 +27,7 @@ use tracing::info;
 
 use crate::adaptive;
 use crate::app::ActiveView;
-use crate::app::AlbumSort;
+use super::album_sort::AlbumSort;
 use crate::app::SharedState;
 use crate::app::ViewName;
 
@@ -140,6 +140,10 @@ impl RelmGridItem for PhotoGridItem {
 
         let thumbnail_path = self.thumbnailer
             .nearest_thumbnail(&self.visual.thumbnail_hash(), thumbnail_size);
+
+        if let Some(ref thumbnail_path) = thumbnail_path {
+            let _ = self.thumbnailer.touch(thumbnail_path);
+        }
         if thumbnail_path.is_some() {
             widgets
                 .picture
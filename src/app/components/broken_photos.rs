@@ -0,0 +1,291 @@
+// SPDX-FileCopyrightText: © 2025 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use relm4::adw::prelude::*;
+use relm4::gtk;
+use relm4::gtk::glib;
+use relm4::prelude::*;
+
+use chrono::Local;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tracing::{error, info};
+
+use crate::app::SettingsState;
+use crate::app::components::albums::file_manager;
+use crate::fl;
+use fotema_core::database;
+use fotema_core::photo;
+use fotema_core::photo::model::{BrokenPicture, PictureId};
+
+pub struct BrokenPhotosDialog {
+    parent: adw::ApplicationWindow,
+    dialog: adw::Dialog,
+    toasts: adw::ToastOverlay,
+
+    con: Arc<Mutex<database::Connection>>,
+    settings_state: SettingsState,
+    cache_dir: PathBuf,
+    data_dir: PathBuf,
+
+    /// Broken pictures, most recently broken first.
+    broken: Vec<BrokenPicture>,
+
+    broken_group: adw::PreferencesGroup,
+    broken_rows: Vec<adw::ActionRow>,
+}
+
+impl BrokenPhotosDialog {
+    /// Show a row for each broken picture, with buttons to retry, reveal, or trash it.
+    fn update_broken_rows(&mut self, sender: &AsyncComponentSender<Self>) {
+        for row in self.broken_rows.drain(..) {
+            self.broken_group.remove(&row);
+        }
+
+        for pic in &self.broken {
+            let file_name = pic
+                .path
+                .host_path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+
+            let reason = pic
+                .reason
+                .clone()
+                .unwrap_or_else(|| fl!("broken-photos-item", "unknown-reason"));
+
+            let reason = if let Some(broken_at) = pic.broken_at {
+                fl!(
+                    "broken-photos-item-reason",
+                    reason = reason,
+                    date = broken_at.with_timezone(&Local).format("%x %X").to_string()
+                )
+            } else {
+                reason
+            };
+
+            let row = adw::ActionRow::builder()
+                .use_markup(false)
+                .title(file_name)
+                .subtitle(format!(
+                    "{}\n{}",
+                    pic.path.host_path.to_string_lossy(),
+                    reason
+                ))
+                .build();
+
+            let retry_button = gtk::Button::builder()
+                .valign(gtk::Align::Center)
+                .icon_name("view-refresh-symbolic")
+                .tooltip_text(fl!("broken-photos-item", "retry-tooltip"))
+                .css_classes(["flat"])
+                .build();
+
+            let reveal_button = gtk::Button::builder()
+                .valign(gtk::Align::Center)
+                .icon_name("folder-open-symbolic")
+                .tooltip_text(fl!("broken-photos-item", "reveal-tooltip"))
+                .css_classes(["flat"])
+                .build();
+
+            let trash_button = gtk::Button::builder()
+                .valign(gtk::Align::Center)
+                .icon_name("user-trash-symbolic")
+                .tooltip_text(fl!("broken-photos-item", "trash-tooltip"))
+                .css_classes(["flat"])
+                .build();
+
+            {
+                let sender = sender.clone();
+                let picture_id = pic.picture_id;
+                retry_button.connect_clicked(move |_| {
+                    sender.input(BrokenPhotosInput::Retry(picture_id));
+                });
+            }
+
+            {
+                let sender = sender.clone();
+                let path = pic.path.host_path.clone();
+                reveal_button.connect_clicked(move |_| {
+                    sender.input(BrokenPhotosInput::RevealInFileManager(path.clone()));
+                });
+            }
+
+            {
+                let sender = sender.clone();
+                let picture_id = pic.picture_id;
+                trash_button.connect_clicked(move |_| {
+                    sender.input(BrokenPhotosInput::Trash(picture_id));
+                });
+            }
+
+            row.add_suffix(&retry_button);
+            row.add_suffix(&reveal_button);
+            row.add_suffix(&trash_button);
+            self.broken_group.add(&row);
+            self.broken_rows.push(row);
+        }
+    }
+
+    /// Stop showing a picture that is no longer broken, or has been trashed.
+    fn forget(&mut self, picture_id: PictureId, sender: &AsyncComponentSender<Self>) {
+        self.broken.retain(|pic| pic.picture_id != picture_id);
+        self.update_broken_rows(sender);
+    }
+}
+
+#[derive(Debug)]
+pub enum BrokenPhotosInput {
+    /// Load broken pictures and show the dialog.
+    Present,
+
+    /// Try to thumbnail a broken picture again.
+    Retry(PictureId),
+
+    /// Show a broken picture in the file manager. Must be the host path.
+    RevealInFileManager(PathBuf),
+
+    /// Move a broken picture to the trash.
+    Trash(PictureId),
+}
+
+#[derive(Debug)]
+pub enum BrokenPhotosOutput {
+    /// Regenerate the thumbnail of a broken picture.
+    RegenerateThumbnail(PictureId),
+
+    /// Move pictures to, or restore pictures from, the trash.
+    SetTrashed(Vec<PictureId>, bool),
+}
+
+#[relm4::component(pub async)]
+impl SimpleAsyncComponent for BrokenPhotosDialog {
+    type Init = (
+        Arc<Mutex<database::Connection>>,
+        SettingsState,
+        PathBuf,
+        PathBuf,
+        adw::ApplicationWindow,
+    );
+    type Input = BrokenPhotosInput;
+    type Output = BrokenPhotosOutput;
+
+    view! {
+        adw::Dialog {
+            set_title: &fl!("broken-photos"),
+            set_content_width: 560,
+            set_content_height: 480,
+
+            #[wrap(Some)]
+            set_child = &adw::ToolbarView {
+                add_top_bar = &adw::HeaderBar,
+
+                #[wrap(Some)]
+                #[local_ref]
+                set_content = &toasts -> adw::ToastOverlay {
+                    #[wrap(Some)]
+                    set_child = &gtk::Box {
+                        set_orientation: gtk::Orientation::Vertical,
+
+                        adw::StatusPage {
+                            set_vexpand: true,
+                            set_icon_name: Some("emblem-ok-symbolic"),
+                            set_title: &fl!("broken-photos-empty", "title"),
+                            set_description: Some(&fl!("broken-photos-empty", "description")),
+
+                            #[watch]
+                            set_visible: model.broken.is_empty(),
+                        },
+
+                        adw::PreferencesPage {
+                            set_vexpand: true,
+
+                            #[watch]
+                            set_visible: !model.broken.is_empty(),
+
+                            #[local_ref]
+                            add = &broken_group -> adw::PreferencesGroup {
+                                set_description: Some(&fl!("broken-photos", "description")),
+                            },
+                        },
+                    },
+                },
+            },
+        }
+    }
+
+    async fn init(
+        (con, settings_state, cache_dir, data_dir, parent): Self::Init,
+        dialog: Self::Root,
+        _sender: AsyncComponentSender<Self>,
+    ) -> AsyncComponentParts<Self> {
+        let toasts = adw::ToastOverlay::new();
+        let broken_group = adw::PreferencesGroup::new();
+
+        let model = Self {
+            parent,
+            dialog: dialog.clone(),
+            toasts: toasts.clone(),
+            con,
+            settings_state,
+            cache_dir,
+            data_dir,
+            broken: Vec::new(),
+            broken_group: broken_group.clone(),
+            broken_rows: Vec::new(),
+        };
+
+        let widgets = view_output!();
+
+        AsyncComponentParts { model, widgets }
+    }
+
+    async fn update(&mut self, msg: Self::Input, sender: AsyncComponentSender<Self>) {
+        match msg {
+            BrokenPhotosInput::Present => {
+                info!("Loading broken photos");
+                let library_roots = self.settings_state.read().library_roots();
+                let cache_dir = self.cache_dir.clone();
+                let data_dir = self.data_dir.clone();
+                let con = self.con.clone();
+
+                let result = relm4::spawn_blocking(move || {
+                    photo::Repository::open(&library_roots, &cache_dir, &data_dir, con)
+                        .and_then(|repo| repo.broken())
+                })
+                .await;
+
+                match result {
+                    Ok(Ok(broken)) => self.broken = broken,
+                    Ok(Err(e)) => error!("Failed loading broken photos: {:?}", e),
+                    Err(e) => error!("Failed loading broken photos: {:?}", e),
+                }
+
+                self.update_broken_rows(&sender);
+                self.dialog.present(Some(&self.parent));
+            }
+            BrokenPhotosInput::Retry(picture_id) => {
+                info!("Retrying broken picture {}", picture_id);
+                // If it fails again, it will be listed the next time the dialog is shown.
+                let _ = sender.output(BrokenPhotosOutput::RegenerateThumbnail(picture_id));
+                self.forget(picture_id, &sender);
+            }
+            BrokenPhotosInput::RevealInFileManager(path) => {
+                let toasts = self.toasts.clone();
+                glib::spawn_future_local(async move {
+                    if let Err(e) = file_manager::reveal(&path).await {
+                        error!("Failed revealing {:?} in file manager: {}", path, e);
+                        toasts.add_toast(adw::Toast::new(&fl!("album-item-error", "reveal")));
+                    }
+                });
+            }
+            BrokenPhotosInput::Trash(picture_id) => {
+                info!("Trashing broken picture {}", picture_id);
+                let _ = sender.output(BrokenPhotosOutput::SetTrashed(vec![picture_id], true));
+                self.forget(picture_id, &sender);
+            }
+        }
+    }
+}
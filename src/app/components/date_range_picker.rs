@@ -0,0 +1,153 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use chrono::prelude::*;
+
+use relm4::gtk;
+use relm4::gtk::glib;
+use relm4::gtk::prelude::*;
+use relm4::*;
+
+use tracing::info;
+
+use crate::fl;
+
+#[derive(Debug)]
+pub enum DateRangePickerInput {
+    /// Apply chosen date range.
+    Apply,
+}
+
+#[derive(Debug)]
+pub enum DateRangePickerOutput {
+    /// Show items captured between two dates. Both bounds are inclusive and either
+    /// bound may be absent.
+    Selected(Option<DateTime<Utc>>, Option<DateTime<Utc>>),
+}
+
+/// Menu button with a popover for choosing a range of dates.
+pub struct DateRangePicker {
+    popover: gtk::Popover,
+    from_check: gtk::CheckButton,
+    from_calendar: gtk::Calendar,
+    to_check: gtk::CheckButton,
+    to_calendar: gtk::Calendar,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for DateRangePicker {
+    type Init = ();
+    type Input = DateRangePickerInput;
+    type Output = DateRangePickerOutput;
+
+    view! {
+        gtk::MenuButton {
+            set_icon_name: "today-symbolic",
+            set_tooltip_text: Some(&fl!("date-range-picker", "tooltip")),
+
+            #[local_ref]
+            #[wrap(Some)]
+            set_popover = &popover -> gtk::Popover {
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Vertical,
+                    set_spacing: 8,
+
+                    #[local_ref]
+                    from_check -> gtk::CheckButton {
+                        set_label: Some(&fl!("date-range-picker", "from")),
+                    },
+
+                    #[local_ref]
+                    from_calendar -> gtk::Calendar,
+
+                    #[local_ref]
+                    to_check -> gtk::CheckButton {
+                        set_label: Some(&fl!("date-range-picker", "to")),
+                    },
+
+                    #[local_ref]
+                    to_calendar -> gtk::Calendar,
+
+                    gtk::Button {
+                        set_label: &fl!("date-range-picker", "apply"),
+                        add_css_class: "suggested-action",
+                        connect_clicked => DateRangePickerInput::Apply,
+                    },
+                },
+            },
+        }
+    }
+
+    fn init(
+        _: Self::Init,
+        _root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let popover = gtk::Popover::new();
+
+        let from_check = gtk::CheckButton::builder().active(true).build();
+        let from_calendar = gtk::Calendar::new();
+        let to_check = gtk::CheckButton::builder().active(true).build();
+        let to_calendar = gtk::Calendar::new();
+
+        {
+            let from_calendar = from_calendar.clone();
+            from_check.connect_toggled(move |check| from_calendar.set_sensitive(check.is_active()));
+        }
+        {
+            let to_calendar = to_calendar.clone();
+            to_check.connect_toggled(move |check| to_calendar.set_sensitive(check.is_active()));
+        }
+
+        let model = DateRangePicker {
+            popover: popover.clone(),
+            from_check: from_check.clone(),
+            from_calendar: from_calendar.clone(),
+            to_check: to_check.clone(),
+            to_calendar: to_calendar.clone(),
+        };
+
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            DateRangePickerInput::Apply => {
+                let from = self
+                    .from_check
+                    .is_active()
+                    .then(|| to_naive_date(&self.from_calendar.date()))
+                    .flatten()
+                    .and_then(|date| date.and_hms_opt(0, 0, 0))
+                    .and_then(to_utc);
+
+                let to = self
+                    .to_check
+                    .is_active()
+                    .then(|| to_naive_date(&self.to_calendar.date()))
+                    .flatten()
+                    .and_then(|date| date.and_hms_milli_opt(23, 59, 59, 999))
+                    .and_then(to_utc);
+
+                info!("Date range selected from {:?} to {:?}", from, to);
+
+                self.popover.popdown();
+                let _ = sender.output(DateRangePickerOutput::Selected(from, to));
+            }
+        }
+    }
+}
+
+fn to_naive_date(date: &glib::DateTime) -> Option<NaiveDate> {
+    let month = u32::try_from(date.month()).ok()?;
+    let day = u32::try_from(date.day_of_month()).ok()?;
+    NaiveDate::from_ymd_opt(date.year(), month, day)
+}
+
+/// Calendar dates are local dates, but visual items are ordered by UTC timestamps.
+fn to_utc(date_time: NaiveDateTime) -> Option<DateTime<Utc>> {
+    fotema_core::time::local_to_utc(date_time, &Local)
+}
@@ -2,13 +2,16 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use fotema_core::{VisualId, YearMonth};
+use fotema_core::burst::BurstId;
+use fotema_core::{PictureId, VisualId, YearMonth};
 
 use relm4::adw;
 use relm4::*;
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
 use std::rc::Rc;
+use std::time::Duration;
 use strum::EnumString;
 use strum::IntoStaticStr;
 
@@ -20,7 +23,8 @@ use crate::fl;
 
 use super::albums::album::{Album, AlbumInput, AlbumOutput};
 use super::albums::album_filter::AlbumFilter;
-use super::albums::album_sort::AlbumSort;
+use super::albums::album_sort::SortKey;
+use super::albums::grid_zoom::GridZoom;
 use super::albums::months_album::{MonthsAlbum, MonthsAlbumInput, MonthsAlbumOutput};
 use super::albums::years_album::{YearsAlbum, YearsAlbumInput, YearsAlbumOutput};
 
@@ -47,12 +51,45 @@ pub enum LibraryInput {
 
     View(VisualId),
 
-    Sort(AlbumSort),
+    Sort(SortKey),
+
+    Zoom(GridZoom),
+
+    SetFavorite(Vec<PictureId>, bool),
+
+    SetTrashed(Vec<PictureId>, bool),
+
+    SetFolderCover(PathBuf, PictureId),
+
+    RegenerateThumbnail(PictureId),
+
+    UngroupBurst(BurstId),
+
+    MovePictures(Vec<PictureId>, PathBuf),
+
+    AssignToPerson(Vec<PictureId>),
+
+    // Show a slideshow of all photos and videos.
+    Slideshow(Duration),
 }
 
 #[derive(Debug)]
 pub enum LibraryOutput {
     View(VisualId),
+
+    SetFavorite(Vec<PictureId>, bool),
+
+    SetTrashed(Vec<PictureId>, bool),
+
+    SetFolderCover(PathBuf, PictureId),
+
+    RegenerateThumbnail(PictureId),
+
+    UngroupBurst(BurstId),
+
+    MovePictures(Vec<PictureId>, PathBuf),
+
+    AssignToPerson(Vec<PictureId>),
 }
 
 pub struct Library {
@@ -104,6 +141,27 @@ impl SimpleComponent for Library {
             .forward(sender.input_sender(), |msg| match msg {
                 AlbumOutput::Selected(id, _) => LibraryInput::View(id),
                 AlbumOutput::ScrollOffset(_) => LibraryInput::Ignore,
+                AlbumOutput::SetFavorite(picture_ids, is_favorite) => {
+                    LibraryInput::SetFavorite(picture_ids, is_favorite)
+                }
+                AlbumOutput::SetTrashed(picture_ids, is_trashed) => {
+                    LibraryInput::SetTrashed(picture_ids, is_trashed)
+                }
+                AlbumOutput::SetFolderCover(folder_path, picture_id) => {
+                    LibraryInput::SetFolderCover(folder_path, picture_id)
+                }
+                AlbumOutput::RegenerateThumbnail(picture_id) => {
+                    LibraryInput::RegenerateThumbnail(picture_id)
+                }
+                AlbumOutput::UngroupBurst(burst_id) => LibraryInput::UngroupBurst(burst_id),
+                AlbumOutput::MovePictures(picture_ids, dest_folder) => {
+                    LibraryInput::MovePictures(picture_ids, dest_folder)
+                }
+                AlbumOutput::AssignToPerson(picture_ids) => {
+                    LibraryInput::AssignToPerson(picture_ids)
+                }
+                // The library never shows a user album, so is never arranged.
+                AlbumOutput::SetUserAlbumOrder(_, _) => LibraryInput::Ignore,
             });
 
         state.subscribe(all_album.sender(), |_| AlbumInput::Refresh);
@@ -179,10 +237,37 @@ impl SimpleComponent for Library {
             LibraryInput::View(id) => {
                 let _ = sender.output(LibraryOutput::View(id));
             }
+            LibraryInput::SetFavorite(picture_ids, is_favorite) => {
+                let _ = sender.output(LibraryOutput::SetFavorite(picture_ids, is_favorite));
+            }
+            LibraryInput::SetTrashed(picture_ids, is_trashed) => {
+                let _ = sender.output(LibraryOutput::SetTrashed(picture_ids, is_trashed));
+            }
+            LibraryInput::SetFolderCover(folder_path, picture_id) => {
+                let _ = sender.output(LibraryOutput::SetFolderCover(folder_path, picture_id));
+            }
+            LibraryInput::RegenerateThumbnail(picture_id) => {
+                let _ = sender.output(LibraryOutput::RegenerateThumbnail(picture_id));
+            }
+            LibraryInput::UngroupBurst(burst_id) => {
+                let _ = sender.output(LibraryOutput::UngroupBurst(burst_id));
+            }
+            LibraryInput::MovePictures(picture_ids, dest_folder) => {
+                let _ = sender.output(LibraryOutput::MovePictures(picture_ids, dest_folder));
+            }
+            LibraryInput::AssignToPerson(picture_ids) => {
+                let _ = sender.output(LibraryOutput::AssignToPerson(picture_ids));
+            }
             LibraryInput::Sort(sort) => {
-                self.all_album.emit(AlbumInput::Sort(sort));
-                self.months_album.emit(MonthsAlbumInput::Sort(sort));
-                self.years_album.emit(YearsAlbumInput::Sort(sort));
+                self.all_album.emit(AlbumInput::SortBy(sort));
+                self.months_album.emit(MonthsAlbumInput::Sort(sort.into()));
+                self.years_album.emit(YearsAlbumInput::Sort(sort.into()));
+            }
+            LibraryInput::Zoom(zoom) => {
+                self.all_album.emit(AlbumInput::Zoom(zoom));
+            }
+            LibraryInput::Slideshow(interval) => {
+                self.all_album.emit(AlbumInput::Slideshow(interval));
             }
         }
     }
@@ -0,0 +1,289 @@
+// SPDX-FileCopyrightText: © 2025 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use relm4::adw::prelude::*;
+use relm4::gtk;
+use relm4::gtk::gio;
+use relm4::prelude::*;
+
+use chrono::Local;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tracing::{error, info};
+
+use crate::app::SettingsState;
+use crate::fl;
+use fotema_core::database;
+use fotema_core::photo;
+use fotema_core::photo::model::{MissingPicture, PictureId};
+
+pub struct MissingPhotosDialog {
+    parent: adw::ApplicationWindow,
+    dialog: adw::Dialog,
+    toasts: adw::ToastOverlay,
+
+    con: Arc<Mutex<database::Connection>>,
+    settings_state: SettingsState,
+    cache_dir: PathBuf,
+    data_dir: PathBuf,
+
+    /// Missing pictures, most recently seen first.
+    missing: Vec<MissingPicture>,
+
+    missing_group: adw::PreferencesGroup,
+    missing_rows: Vec<adw::ActionRow>,
+}
+
+impl MissingPhotosDialog {
+    /// Show a row for each missing picture, with buttons to locate it or remove it from the library.
+    fn update_missing_rows(&mut self, sender: &AsyncComponentSender<Self>) {
+        for row in self.missing_rows.drain(..) {
+            self.missing_group.remove(&row);
+        }
+
+        for pic in &self.missing {
+            let file_name = pic
+                .path
+                .host_path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+
+            let last_seen = pic.last_seen_at.map_or_else(
+                || fl!("missing-photos-item", "never-seen"),
+                |ts| {
+                    fl!(
+                        "missing-photos-item-last-seen",
+                        date = ts.with_timezone(&Local).format("%x %X").to_string()
+                    )
+                },
+            );
+
+            let row = adw::ActionRow::builder()
+                .use_markup(false)
+                .title(file_name)
+                .subtitle(format!(
+                    "{}\n{}",
+                    pic.path.host_path.to_string_lossy(),
+                    last_seen
+                ))
+                .build();
+
+            let locate_button = gtk::Button::builder()
+                .valign(gtk::Align::Center)
+                .icon_name("edit-find-symbolic")
+                .tooltip_text(fl!("missing-photos-item", "locate-tooltip"))
+                .css_classes(["flat"])
+                .build();
+
+            let remove_button = gtk::Button::builder()
+                .valign(gtk::Align::Center)
+                .icon_name("list-remove-symbolic")
+                .tooltip_text(fl!("missing-photos-item", "remove-tooltip"))
+                .css_classes(["flat"])
+                .build();
+
+            {
+                let sender = sender.clone();
+                let picture_id = pic.picture_id;
+                locate_button.connect_clicked(move |_| {
+                    sender.input(MissingPhotosInput::Locate(picture_id));
+                });
+            }
+
+            {
+                let sender = sender.clone();
+                let picture_id = pic.picture_id;
+                remove_button.connect_clicked(move |_| {
+                    sender.input(MissingPhotosInput::Remove(picture_id));
+                });
+            }
+
+            row.add_suffix(&locate_button);
+            row.add_suffix(&remove_button);
+            self.missing_group.add(&row);
+            self.missing_rows.push(row);
+        }
+    }
+
+    /// Stop showing a picture that has been located, or removed from the library.
+    fn forget(&mut self, picture_id: PictureId, sender: &AsyncComponentSender<Self>) {
+        self.missing.retain(|pic| pic.picture_id != picture_id);
+        self.update_missing_rows(sender);
+    }
+}
+
+#[derive(Debug)]
+pub enum MissingPhotosInput {
+    /// Load missing pictures and show the dialog.
+    Present,
+
+    /// Choose the file a missing picture was moved to.
+    Locate(PictureId),
+
+    /// Remove a missing picture, and everything known about it, from the library.
+    Remove(PictureId),
+
+    /// Result of locating a missing picture.
+    /// Some(true) if found, Some(false) if the chosen file has different content,
+    /// and None if locating failed.
+    Located(PictureId, Option<bool>),
+}
+
+#[derive(Debug)]
+pub enum MissingPhotosOutput {
+    /// Check that a file is the missing picture and, if so, record its new path.
+    Locate(PictureId, PathBuf),
+
+    /// Remove a missing picture from the library.
+    RemoveFromLibrary(PictureId),
+}
+
+#[relm4::component(pub async)]
+impl SimpleAsyncComponent for MissingPhotosDialog {
+    type Init = (
+        Arc<Mutex<database::Connection>>,
+        SettingsState,
+        PathBuf,
+        PathBuf,
+        adw::ApplicationWindow,
+    );
+    type Input = MissingPhotosInput;
+    type Output = MissingPhotosOutput;
+
+    view! {
+        adw::Dialog {
+            set_title: &fl!("missing-photos"),
+            set_content_width: 560,
+            set_content_height: 480,
+
+            #[wrap(Some)]
+            set_child = &adw::ToolbarView {
+                add_top_bar = &adw::HeaderBar,
+
+                #[wrap(Some)]
+                #[local_ref]
+                set_content = &toasts -> adw::ToastOverlay {
+                    #[wrap(Some)]
+                    set_child = &gtk::Box {
+                        set_orientation: gtk::Orientation::Vertical,
+
+                        adw::StatusPage {
+                            set_vexpand: true,
+                            set_icon_name: Some("emblem-ok-symbolic"),
+                            set_title: &fl!("missing-photos-empty", "title"),
+                            set_description: Some(&fl!("missing-photos-empty", "description")),
+
+                            #[watch]
+                            set_visible: model.missing.is_empty(),
+                        },
+
+                        adw::PreferencesPage {
+                            set_vexpand: true,
+
+                            #[watch]
+                            set_visible: !model.missing.is_empty(),
+
+                            #[local_ref]
+                            add = &missing_group -> adw::PreferencesGroup {
+                                set_description: Some(&fl!("missing-photos", "description")),
+                            },
+                        },
+                    },
+                },
+            },
+        }
+    }
+
+    async fn init(
+        (con, settings_state, cache_dir, data_dir, parent): Self::Init,
+        dialog: Self::Root,
+        _sender: AsyncComponentSender<Self>,
+    ) -> AsyncComponentParts<Self> {
+        let toasts = adw::ToastOverlay::new();
+        let missing_group = adw::PreferencesGroup::new();
+
+        let model = Self {
+            parent,
+            dialog: dialog.clone(),
+            toasts: toasts.clone(),
+            con,
+            settings_state,
+            cache_dir,
+            data_dir,
+            missing: Vec::new(),
+            missing_group: missing_group.clone(),
+            missing_rows: Vec::new(),
+        };
+
+        let widgets = view_output!();
+
+        AsyncComponentParts { model, widgets }
+    }
+
+    async fn update(&mut self, msg: Self::Input, sender: AsyncComponentSender<Self>) {
+        match msg {
+            MissingPhotosInput::Present => {
+                info!("Loading missing photos");
+                let library_roots = self.settings_state.read().library_roots();
+                let cache_dir = self.cache_dir.clone();
+                let data_dir = self.data_dir.clone();
+                let con = self.con.clone();
+
+                let result = relm4::spawn_blocking(move || {
+                    photo::Repository::open(&library_roots, &cache_dir, &data_dir, con)
+                        .and_then(|repo| repo.missing())
+                })
+                .await;
+
+                match result {
+                    Ok(Ok(missing)) => self.missing = missing,
+                    Ok(Err(e)) => error!("Failed loading missing photos: {:?}", e),
+                    Err(e) => error!("Failed loading missing photos: {:?}", e),
+                }
+
+                self.update_missing_rows(&sender);
+                self.dialog.present(Some(&self.parent));
+            }
+            MissingPhotosInput::Locate(picture_id) => {
+                let file_dialog = gtk::FileDialog::builder()
+                    .title(fl!("missing-photos", "locate-title"))
+                    .modal(true)
+                    .build();
+
+                let sender = sender.clone();
+                file_dialog.open(Some(&self.parent), gio::Cancellable::NONE, move |result| {
+                    match result.map(|file| file.path()) {
+                        Ok(Some(path)) => {
+                            info!("Locating missing picture {} at {:?}", picture_id, path);
+                            let _ = sender.output(MissingPhotosOutput::Locate(picture_id, path));
+                        }
+                        Ok(None) => info!("Chosen file has no local path"),
+                        Err(e) => info!("No file chosen to locate: {}", e),
+                    }
+                });
+            }
+            MissingPhotosInput::Remove(picture_id) => {
+                info!("Removing missing picture {} from library", picture_id);
+                let _ = sender.output(MissingPhotosOutput::RemoveFromLibrary(picture_id));
+                self.forget(picture_id, &sender);
+            }
+            MissingPhotosInput::Located(picture_id, result) => match result {
+                Some(true) => {
+                    self.toasts
+                        .add_toast(adw::Toast::new(&fl!("missing-photos-toast", "located")));
+                    self.forget(picture_id, &sender);
+                }
+                Some(false) => {
+                    self.toasts
+                        .add_toast(adw::Toast::new(&fl!("missing-photos-toast", "mismatch")));
+                }
+                None => {
+                    self.toasts
+                        .add_toast(adw::Toast::new(&fl!("missing-photos-toast", "failed")));
+                }
+            },
+        }
+    }
+}
@@ -4,9 +4,13 @@
 
 pub mod about;
 pub mod albums;
+pub mod broken_photos;
+pub mod date_range_picker;
 pub mod library;
+pub mod missing_photos;
 pub mod onboard;
 pub mod preferences;
 pub mod progress_monitor;
 pub mod progress_panel;
+pub mod stats;
 pub mod viewer;
@@ -8,27 +8,119 @@ use relm4::adw::prelude::*;
 use relm4::gtk;
 use relm4::prelude::*;
 
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
-use crate::app::AlbumSort;
+use std::path::PathBuf;
+
+use crate::app::SortKey;
 use crate::app::FaceDetectionMode;
+use crate::app::FoldersViewMode;
+use crate::app::GridZoom;
+use crate::app::{Privacy, PrivacyMode, PrivacyState};
+use crate::app::ThumbnailPowerMode;
 use crate::app::{Settings, SettingsState};
 use crate::fl;
 use crate::host_path;
 use fotema_core::FlatpakPathBuf;
+use fotema_core::import::ImportLayout;
+use fotema_core::machine_learning::face_extractor::FaceDetectionModel;
+use fotema_core::photo::thumbnailer::ThumbnailOrder;
+use fotema_core::thumbnailify::ThumbnailLocation;
+use fotema_core::photo::xmp::SidecarPolicy;
+use fotema_core::LibraryRoots;
 
 pub struct PreferencesDialog {
     parent: adw::ApplicationWindow,
     dialog: adw::PreferencesDialog,
     album_sort: adw::ComboRow,
+    album_zoom: adw::ComboRow,
+    folders_view_mode: adw::ComboRow,
+    face_detection_model: adw::ComboRow,
+    face_confidence_threshold: adw::SpinRow,
+    face_cluster_distance: adw::SpinRow,
+    face_thumbnail_margin: adw::SpinRow,
+    thumbnail_cache_limit: adw::SpinRow,
+    thumbnail_order: adw::ComboRow,
+    thumbnail_location: adw::ComboRow,
+    thumbnail_power_mode: adw::ComboRow,
+    import_layout: adw::ComboRow,
+    sidecar_policy: adw::ComboRow,
+    slideshow_interval: adw::SpinRow,
+    similar_photos_distance: adw::SpinRow,
+    ignore_patterns: adw::EntryRow,
+    privacy_mode: adw::ComboRow,
+
+    // Group listing directories scanned alongside the pictures directory, and its rows.
+    library_dirs_group: adw::PreferencesGroup,
+    library_dir_rows: Vec<adw::ActionRow>,
 
     settings_state: SettingsState,
 
+    // Whether private pictures are unlocked, so their preferences can be changed.
+    privacy_state: PrivacyState,
+    privacy: Privacy,
+
     // Preference values
     settings: Settings,
 }
 
 impl PreferencesDialog {
+    /// Ask the user to choose a directory.
+    async fn choose_dir(&self) -> Option<PathBuf> {
+        let root = gtk::Widget::root(self.parent.widget_ref())?;
+        let identifier = WindowIdentifier::from_native(&root).await;
+        let request = OpenFileRequest::default()
+            .directory(true)
+            .identifier(identifier)
+            .modal(true) // can't be modal without identifier.
+            .multiple(false);
+
+        match request.send().await.and_then(|r| r.response()) {
+            Ok(files) => {
+                info!("Open: {:?}", files);
+                files.uris().first().and_then(|uri| uri.to_file_path().ok())
+            }
+            Err(err) => {
+                error!("Failed to open a file: {err}");
+                None
+            }
+        }
+    }
+
+    /// Show a removable row for each directory scanned alongside the pictures directory.
+    fn update_library_dir_rows(&mut self, sender: &AsyncComponentSender<Self>) {
+        for row in self.library_dir_rows.drain(..) {
+            self.library_dirs_group.remove(&row);
+        }
+
+        for dir in &self.settings.library_dirs {
+            let row = adw::ActionRow::builder()
+                .title(dir.host_path.to_string_lossy().to_string())
+                .build();
+
+            let button = gtk::Button::builder()
+                .valign(gtk::Align::Center)
+                .icon_name("list-remove-symbolic")
+                .tooltip_text(fl!("prefs-library-dirs-section", "remove-tooltip"))
+                .css_classes(["flat"])
+                .build();
+
+            let sender = sender.clone();
+            let path = dir.sandbox_path.clone();
+            button.connect_clicked(move |_| {
+                sender.input(PreferencesInput::RemoveLibraryDir(path.clone()));
+            });
+
+            row.add_suffix(&button);
+            self.library_dirs_group.add(&row);
+            self.library_dir_rows.push(row);
+        }
+    }
+
+    pub fn is_unlocked(&self) -> bool {
+        self.privacy == Privacy::Unlocked
+    }
+
     pub fn is_face_detection_active(&self) -> bool {
         self.settings.face_detection_mode == FaceDetectionMode::On
     }
@@ -50,16 +142,81 @@ pub enum PreferencesInput {
     /// Changed settings received.
     SettingsChanged(Settings),
 
+    /// Private pictures were locked or unlocked.
+    Privacy(Privacy),
+
     /// Send updated settings
     UpdateShowSelfies(bool),
 
     UpdateFaceDetectionMode(FaceDetectionMode),
 
+    /// Face detectors to run, trading speed for accuracy.
+    UpdateFaceDetectionModel(FaceDetectionModel),
+
+    UpdateFaceConfidenceThreshold(f32),
+
+    UpdateFaceClusterDistance(f32),
+
+    /// Fraction that face bounds are expanded by for new face thumbnails.
+    UpdateFaceThumbnailMargin(f32),
+
     UpdateProcessMotionPhotos(bool),
 
-    Sort(AlbumSort),
+    UpdateWatchLibrary(bool),
+
+    UpdateFollowSymlinks(bool),
+
+    UpdateAnimatedPreviews(bool),
+
+    /// Seconds each picture is shown for in a slideshow.
+    UpdateSlideshowInterval(u32),
+
+    /// Most bits by which the perceptual hashes of similar photos may differ.
+    UpdateSimilarPhotosDistance(u32),
+
+    /// Maximum size of the thumbnail cache in gigabytes.
+    UpdateThumbnailCacheLimit(f64),
+
+    /// Order that pictures are thumbnailed in.
+    UpdateThumbnailOrder(ThumbnailOrder),
+
+    /// Where thumbnails are saved.
+    UpdateThumbnailLocation(ThumbnailLocation),
+
+    /// Whether photo thumbnails are only generated on AC power, or while idle.
+    UpdateThumbnailPowerMode(ThumbnailPowerMode),
+
+    Sort(SortKey),
+
+    /// Size of items in album grids.
+    UpdateAlbumZoom(GridZoom),
+
+    UpdateFoldersViewMode(FoldersViewMode),
+
+    UpdateShowHiddenFolders(bool),
+
+    /// Folders that imported pictures and videos are copied into.
+    UpdateImportLayout(ImportLayout),
+
+    /// Which of a changed XMP sidecar and the library is kept.
+    UpdateSidecarPolicy(SidecarPolicy),
+
+    /// Comma separated glob patterns of files and folders to leave out of the library.
+    UpdateIgnorePatterns(String),
+
+    /// How private pictures are shown while locked. Ignored while locked.
+    UpdatePrivacyMode(PrivacyMode),
+
+    /// PIN that unlocks private pictures. Empty to unlock without a PIN. Ignored while locked.
+    UpdatePrivacyPin(String),
 
     ChoosePicturesDir,
+
+    /// Choose another directory to scan alongside the pictures directory.
+    AddLibraryDir,
+
+    /// Stop scanning a directory. Its pictures are moved to the trash by the next scan.
+    RemoveLibraryDir(PathBuf),
 }
 
 // Note that some settings update through the shared state, and others through output messages.
@@ -70,8 +227,29 @@ pub enum PreferencesOutput {
     /// Start face detection right now.
     EnableFaceDetection,
 
+    /// Offer to detect faces again with the new model.
+    FaceDetectionModelChanged,
+
     /// Process motion photos right now.
     ProcessMotionPhotos,
+
+    /// Generate animated previews right now.
+    GenerateAnimatedPreviews,
+
+    /// Delete animated previews to free up space.
+    RemoveAnimatedPreviews,
+
+    /// Delete thumbnails to bring the thumbnail cache within a lowered size limit.
+    LimitThumbnailCache,
+
+    /// Offer to move existing thumbnails to where thumbnails are now saved.
+    ThumbnailLocationChanged,
+
+    /// Scan the library for changes, such as to trash pictures matching a new ignore pattern.
+    Rescan,
+
+    /// Scan every file in the library, such as to add files no longer ignored.
+    Rebuild,
 }
 
 
@@ -79,7 +257,7 @@ pub enum PreferencesOutput {
 
 #[relm4::component(pub async)]
 impl SimpleAsyncComponent for PreferencesDialog {
-    type Init = (SettingsState, adw::ApplicationWindow);
+    type Init = (SettingsState, PrivacyState, adw::ApplicationWindow);
     type Input = PreferencesInput;
     type Output = PreferencesOutput;
 
@@ -104,7 +282,126 @@ impl SimpleAsyncComponent for PreferencesDialog {
                             set_tooltip_text: Some(&fl!("prefs-library-section-pictures-dir", "tooltip")),
                             connect_clicked => PreferencesInput::ChoosePicturesDir,
                         }
-                    }
+                    },
+
+                    adw::SwitchRow {
+                        set_title: &fl!("prefs-library-section-watch"),
+                        set_subtitle: &fl!("prefs-library-section-watch", "subtitle"),
+
+                        #[watch]
+                        set_active: model.settings.watch_library,
+
+                        connect_active_notify[sender] => move |switch| {
+                            let _ = sender.input_sender().send(PreferencesInput::UpdateWatchLibrary(switch.is_active()));
+                        },
+                    },
+
+                    adw::SwitchRow {
+                        set_title: &fl!("prefs-library-section-follow-symlinks"),
+                        set_subtitle: &fl!("prefs-library-section-follow-symlinks", "subtitle"),
+
+                        #[watch]
+                        set_active: model.settings.follow_symlinks,
+
+                        connect_active_notify[sender] => move |switch| {
+                            let _ = sender.input_sender().send(PreferencesInput::UpdateFollowSymlinks(switch.is_active()));
+                        },
+                    },
+
+                    #[local_ref]
+                    thumbnail_cache_limit_row -> adw::SpinRow {
+                        set_title: &fl!("prefs-library-section-thumbnail-cache"),
+                        set_subtitle: &fl!("prefs-library-section-thumbnail-cache", "subtitle"),
+                        set_digits: 1,
+
+                        connect_value_notify[sender] => move |row| {
+                            let _ = sender.input_sender().send(PreferencesInput::UpdateThumbnailCacheLimit(row.value()));
+                        },
+                    },
+
+                    #[local_ref]
+                    thumbnail_order_row -> adw::ComboRow {
+                        set_title: &fl!("prefs-library-section-thumbnail-order"),
+                        set_subtitle: &fl!("prefs-library-section-thumbnail-order", "subtitle"),
+
+                        connect_selected_item_notify[sender] => move |row| {
+                            let order = ThumbnailOrder::from_repr(row.selected()).unwrap_or_default();
+                            let _ = sender.input_sender().send(PreferencesInput::UpdateThumbnailOrder(order));
+                        }
+                    },
+
+                    #[local_ref]
+                    thumbnail_location_row -> adw::ComboRow {
+                        set_title: &fl!("prefs-library-section-thumbnail-location"),
+                        set_subtitle: &fl!("prefs-library-section-thumbnail-location", "subtitle"),
+
+                        connect_selected_item_notify[sender] => move |row| {
+                            let location = ThumbnailLocation::from_repr(row.selected()).unwrap_or_default();
+                            let _ = sender.input_sender().send(PreferencesInput::UpdateThumbnailLocation(location));
+                        }
+                    },
+
+                    #[local_ref]
+                    thumbnail_power_mode_row -> adw::ComboRow {
+                        set_title: &fl!("prefs-library-section-thumbnail-power"),
+                        set_subtitle: &fl!("prefs-library-section-thumbnail-power", "subtitle"),
+
+                        // Power and idle state can only be checked if built in.
+                        set_visible: cfg!(feature = "power"),
+
+                        connect_selected_item_notify[sender] => move |row| {
+                            let mode = ThumbnailPowerMode::from_repr(row.selected()).unwrap_or_default();
+                            let _ = sender.input_sender().send(PreferencesInput::UpdateThumbnailPowerMode(mode));
+                        }
+                    },
+
+                    #[local_ref]
+                    import_layout_row -> adw::ComboRow {
+                        set_title: &fl!("prefs-library-section-import-layout"),
+                        set_subtitle: &fl!("prefs-library-section-import-layout", "subtitle"),
+
+                        connect_selected_item_notify[sender] => move |row| {
+                            let layout = ImportLayout::from_repr(row.selected()).unwrap_or_default();
+                            let _ = sender.input_sender().send(PreferencesInput::UpdateImportLayout(layout));
+                        }
+                    },
+
+                    #[local_ref]
+                    sidecar_policy_row -> adw::ComboRow {
+                        set_title: &fl!("prefs-library-section-sidecar-policy"),
+                        set_subtitle: &fl!("prefs-library-section-sidecar-policy", "subtitle"),
+
+                        connect_selected_item_notify[sender] => move |row| {
+                            let policy = SidecarPolicy::from_repr(row.selected()).unwrap_or_default();
+                            let _ = sender.input_sender().send(PreferencesInput::UpdateSidecarPolicy(policy));
+                        }
+                    },
+
+                    #[local_ref]
+                    ignore_patterns_row -> adw::EntryRow {
+                        set_title: &fl!("prefs-library-section-ignore"),
+                        set_tooltip_text: Some(&fl!("prefs-library-section-ignore", "tooltip")),
+                        set_show_apply_button: true,
+
+                        connect_apply[sender] => move |row| {
+                            let _ = sender.input_sender().send(PreferencesInput::UpdateIgnorePatterns(row.text().into()));
+                        },
+                    },
+                },
+
+                #[local_ref]
+                add = &library_dirs_group -> adw::PreferencesGroup {
+                    set_title: &fl!("prefs-library-dirs-section", "title"),
+                    set_description: Some(&fl!("prefs-library-dirs-section", "description")),
+
+                    #[wrap(Some)]
+                    set_header_suffix = &gtk::Button {
+                        set_valign: gtk::Align::Center,
+                        set_icon_name: "list-add-symbolic",
+                        add_css_class: "flat",
+                        set_tooltip_text: Some(&fl!("prefs-library-dirs-section", "add-tooltip")),
+                        connect_clicked => PreferencesInput::AddLibraryDir,
+                    },
                 },
 
                 add = &adw::PreferencesGroup {
@@ -123,16 +420,84 @@ impl SimpleAsyncComponent for PreferencesDialog {
                         },
                     },
 
+                    adw::SwitchRow {
+                        set_title: &fl!("prefs-albums-animated-previews"),
+                        set_subtitle: &fl!("prefs-albums-animated-previews", "subtitle"),
+
+                        #[watch]
+                        set_active: model.settings.animated_previews,
+
+                        connect_active_notify[sender] => move |switch| {
+                            let _ = sender.input_sender().send(PreferencesInput::UpdateAnimatedPreviews(switch.is_active()));
+                        },
+                    },
+
+                    #[local_ref]
+                    slideshow_interval_row -> adw::SpinRow {
+                        set_title: &fl!("prefs-albums-slideshow-interval"),
+                        set_subtitle: &fl!("prefs-albums-slideshow-interval", "subtitle"),
+
+                        connect_value_notify[sender] => move |row| {
+                            let secs = row.value() as u32;
+                            let _ = sender.input_sender().send(PreferencesInput::UpdateSlideshowInterval(secs));
+                        },
+                    },
+
+                    #[local_ref]
+                    similar_photos_distance_row -> adw::SpinRow {
+                        set_title: &fl!("prefs-albums-similar-distance"),
+                        set_subtitle: &fl!("prefs-albums-similar-distance", "subtitle"),
+
+                        connect_value_notify[sender] => move |row| {
+                            let distance = row.value() as u32;
+                            let _ = sender.input_sender().send(PreferencesInput::UpdateSimilarPhotosDistance(distance));
+                        },
+                    },
+
                     #[local_ref]
                     album_sort_row -> adw::ComboRow {
-                        set_title: &fl!("prefs-albums-chronological-sort"),
-                        set_subtitle: &fl!("prefs-albums-chronological-sort", "subtitle"),
+                        set_title: &fl!("prefs-albums-sort"),
+                        set_subtitle: &fl!("prefs-albums-sort", "subtitle"),
 
                         connect_selected_item_notify[sender] => move |row| {
-                            let mode = AlbumSort::from_repr(row.selected()).unwrap_or_default();
+                            let mode = SortKey::from_repr(row.selected()).unwrap_or_default();
                             let _ = sender.input_sender().send(PreferencesInput::Sort(mode));
                         }
-                    }
+                    },
+
+                    #[local_ref]
+                    album_zoom_row -> adw::ComboRow {
+                        set_title: &fl!("prefs-albums-zoom"),
+                        set_subtitle: &fl!("prefs-albums-zoom", "subtitle"),
+
+                        connect_selected_item_notify[sender] => move |row| {
+                            let zoom = GridZoom::from_repr(row.selected()).unwrap_or_default();
+                            let _ = sender.input_sender().send(PreferencesInput::UpdateAlbumZoom(zoom));
+                        }
+                    },
+
+                    #[local_ref]
+                    folders_view_mode_row -> adw::ComboRow {
+                        set_title: &fl!("prefs-albums-folders-view"),
+                        set_subtitle: &fl!("prefs-albums-folders-view", "subtitle"),
+
+                        connect_selected_item_notify[sender] => move |row| {
+                            let mode = FoldersViewMode::from_repr(row.selected()).unwrap_or_default();
+                            let _ = sender.input_sender().send(PreferencesInput::UpdateFoldersViewMode(mode));
+                        }
+                    },
+
+                    adw::SwitchRow {
+                        set_title: &fl!("prefs-albums-show-hidden-folders"),
+                        set_subtitle: &fl!("prefs-albums-show-hidden-folders", "subtitle"),
+
+                        #[watch]
+                        set_active: model.settings.show_hidden_folders,
+
+                        connect_active_notify[sender] => move |switch| {
+                            let _ = sender.input_sender().send(PreferencesInput::UpdateShowHiddenFolders(switch.is_active()));
+                        },
+                    },
                 },
                 add = &adw::PreferencesGroup {
                     set_title: &fl!("prefs-processing-section"),
@@ -157,6 +522,65 @@ impl SimpleAsyncComponent for PreferencesDialog {
                         },
                     },
 
+                    #[local_ref]
+                    face_detection_model_row -> adw::ComboRow {
+                        set_title: &fl!("prefs-processing-face-detection-model"),
+                        set_subtitle: &fl!("prefs-processing-face-detection-model", "subtitle"),
+
+                        #[watch]
+                        set_sensitive: model.is_face_detection_active(),
+
+                        connect_selected_item_notify[sender] => move |row| {
+                            let detection_model = FaceDetectionModel::from_repr(row.selected()).unwrap_or_default();
+                            let _ = sender.input_sender().send(PreferencesInput::UpdateFaceDetectionModel(detection_model));
+                        }
+                    },
+
+                    #[local_ref]
+                    face_confidence_threshold_row -> adw::SpinRow {
+                        set_title: &fl!("prefs-processing-face-confidence"),
+                        set_subtitle: &fl!("prefs-processing-face-confidence", "subtitle"),
+                        set_digits: 2,
+
+                        #[watch]
+                        set_sensitive: model.is_face_detection_active(),
+
+                        connect_value_notify[sender] => move |row| {
+                            let threshold = row.value() as f32;
+                            let _ = sender.input_sender().send(PreferencesInput::UpdateFaceConfidenceThreshold(threshold));
+                        },
+                    },
+
+                    #[local_ref]
+                    face_cluster_distance_row -> adw::SpinRow {
+                        set_title: &fl!("prefs-processing-face-grouping"),
+                        set_subtitle: &fl!("prefs-processing-face-grouping", "subtitle"),
+                        set_digits: 2,
+
+                        #[watch]
+                        set_sensitive: model.is_face_detection_active(),
+
+                        connect_value_notify[sender] => move |row| {
+                            let distance = row.value() as f32;
+                            let _ = sender.input_sender().send(PreferencesInput::UpdateFaceClusterDistance(distance));
+                        },
+                    },
+
+                    #[local_ref]
+                    face_thumbnail_margin_row -> adw::SpinRow {
+                        set_title: &fl!("prefs-processing-face-thumbnail-margin"),
+                        set_subtitle: &fl!("prefs-processing-face-thumbnail-margin", "subtitle"),
+                        set_digits: 1,
+
+                        #[watch]
+                        set_sensitive: model.is_face_detection_active(),
+
+                        connect_value_notify[sender] => move |row| {
+                            let margin = row.value() as f32;
+                            let _ = sender.input_sender().send(PreferencesInput::UpdateFaceThumbnailMargin(margin));
+                        },
+                    },
+
                     adw::SwitchRow {
                         set_title: &fl!("prefs-processing-motion-photos"),
                         set_subtitle: &fl!("prefs-processing-motion-photos", "subtitle"),
@@ -170,12 +594,51 @@ impl SimpleAsyncComponent for PreferencesDialog {
                     },
 
                 },
+                add = &adw::PreferencesGroup {
+                    set_title: &fl!("prefs-privacy-section"),
+
+                    #[watch]
+                    set_description: Some(&if model.is_unlocked() {
+                        fl!("prefs-privacy-section", "description")
+                    } else {
+                        fl!("prefs-privacy-section", "locked")
+                    }),
+
+                    #[local_ref]
+                    privacy_mode_row -> adw::ComboRow {
+                        set_title: &fl!("prefs-privacy-mode"),
+                        set_subtitle: &fl!("prefs-privacy-mode", "subtitle"),
+
+                        #[watch]
+                        set_sensitive: model.is_unlocked(),
+
+                        connect_selected_item_notify[sender] => move |row| {
+                            let mode = PrivacyMode::from_repr(row.selected()).unwrap_or_default();
+                            let _ = sender.input_sender().send(PreferencesInput::UpdatePrivacyMode(mode));
+                        }
+                    },
+
+                    #[local_ref]
+                    privacy_pin_row -> adw::PasswordEntryRow {
+                        set_title: &fl!("prefs-privacy-pin"),
+                        set_tooltip_text: Some(&fl!("prefs-privacy-pin", "tooltip")),
+                        set_show_apply_button: true,
+
+                        #[watch]
+                        set_sensitive: model.is_unlocked(),
+
+                        connect_apply[sender] => move |row| {
+                            let _ = sender.input_sender().send(PreferencesInput::UpdatePrivacyPin(row.text().into()));
+                            row.set_text("");
+                        },
+                    },
+                },
             }
         }
     }
 
     async fn init(
-        (settings_state, parent): Self::Init,
+        (settings_state, privacy_state, parent): Self::Init,
         dialog: Self::Root,
         sender: AsyncComponentSender<Self>,
     ) -> AsyncComponentParts<Self> {
@@ -187,19 +650,134 @@ impl SimpleAsyncComponent for PreferencesDialog {
             .active(settings_state.read().face_detection_mode == FaceDetectionMode::On)
             .build();
 
+        let face_detection_model_row = adw::ComboRow::new();
+        let list = gtk::StringList::new(&[
+            &fl!("prefs-processing-face-detection-model", "fast"),
+            &fl!("prefs-processing-face-detection-model", "accurate"),
+        ]);
+        face_detection_model_row.set_model(Some(&list));
+
+        let face_confidence_threshold_row = adw::SpinRow::with_range(0.0, 1.0, 0.05);
+        face_confidence_threshold_row
+            .set_value(settings_state.read().face_confidence_threshold.into());
+
+        let face_cluster_distance_row = adw::SpinRow::with_range(0.0, 2.0, 0.05);
+        face_cluster_distance_row.set_value(settings_state.read().face_cluster_distance.into());
+
+        let face_thumbnail_margin_row = adw::SpinRow::with_range(0.0, 2.0, 0.1);
+        face_thumbnail_margin_row.set_value(settings_state.read().face_thumbnail_margin.into());
+
+        let thumbnail_cache_limit_row = adw::SpinRow::with_range(0.0, 1000.0, 0.5);
+        thumbnail_cache_limit_row.set_value(settings_state.read().thumbnail_cache_limit_gb);
+
+        let slideshow_interval_row = adw::SpinRow::with_range(1.0, 600.0, 1.0);
+        slideshow_interval_row.set_value(settings_state.read().slideshow_interval_secs.into());
+
+        let similar_photos_distance_row = adw::SpinRow::with_range(0.0, 32.0, 1.0);
+        similar_photos_distance_row.set_value(settings_state.read().similar_photos_distance.into());
+
         let album_sort_row = adw::ComboRow::new();
         let list = gtk::StringList::new(&[
-            &fl!("prefs-albums-chronological-sort", "ascending"),
-            &fl!("prefs-albums-chronological-sort", "descending"),
+            &fl!("prefs-albums-sort", "capture-date-ascending"),
+            &fl!("prefs-albums-sort", "capture-date-descending"),
+            &fl!("prefs-albums-sort", "file-name"),
+            &fl!("prefs-albums-sort", "file-size"),
         ]);
         album_sort_row.set_model(Some(&list));
 
+        let album_zoom_row = adw::ComboRow::new();
+        let list = gtk::StringList::new(&[
+            &fl!("prefs-albums-zoom", "small"),
+            &fl!("prefs-albums-zoom", "medium"),
+            &fl!("prefs-albums-zoom", "large"),
+        ]);
+        album_zoom_row.set_model(Some(&list));
+
+        let folders_view_mode_row = adw::ComboRow::new();
+        let list = gtk::StringList::new(&[
+            &fl!("prefs-albums-folders-view", "tree"),
+            &fl!("prefs-albums-folders-view", "flat"),
+        ]);
+        folders_view_mode_row.set_model(Some(&list));
+
+        let thumbnail_order_row = adw::ComboRow::new();
+        let list = gtk::StringList::new(&[
+            &fl!("prefs-library-section-thumbnail-order", "newest-first"),
+            &fl!("prefs-library-section-thumbnail-order", "oldest-first"),
+            &fl!("prefs-library-section-thumbnail-order", "largest-first"),
+        ]);
+        thumbnail_order_row.set_model(Some(&list));
+
+        let thumbnail_location_row = adw::ComboRow::new();
+        let list = gtk::StringList::new(&[
+            &fl!("prefs-library-section-thumbnail-location", "central"),
+            &fl!("prefs-library-section-thumbnail-location", "sidecar"),
+        ]);
+        thumbnail_location_row.set_model(Some(&list));
+
+        let thumbnail_power_mode_row = adw::ComboRow::new();
+        let list = gtk::StringList::new(&[
+            &fl!("prefs-library-section-thumbnail-power", "always"),
+            &fl!("prefs-library-section-thumbnail-power", "ac-power"),
+            &fl!("prefs-library-section-thumbnail-power", "ac-power-idle"),
+        ]);
+        thumbnail_power_mode_row.set_model(Some(&list));
+
+        let import_layout_row = adw::ComboRow::new();
+        let list = gtk::StringList::new(&[
+            &fl!("prefs-library-section-import-layout", "year-month"),
+            &fl!("prefs-library-section-import-layout", "year-month-day"),
+            &fl!("prefs-library-section-import-layout", "year"),
+        ]);
+        import_layout_row.set_model(Some(&list));
+
+        let sidecar_policy_row = adw::ComboRow::new();
+        let list = gtk::StringList::new(&[
+            &fl!("prefs-library-section-sidecar-policy", "database-wins"),
+            &fl!("prefs-library-section-sidecar-policy", "sidecar-wins"),
+        ]);
+        sidecar_policy_row.set_model(Some(&list));
+
+        let privacy_mode_row = adw::ComboRow::new();
+        let list = gtk::StringList::new(&[
+            &fl!("prefs-privacy-mode", "hide"),
+            &fl!("prefs-privacy-mode", "blur"),
+        ]);
+        privacy_mode_row.set_model(Some(&list));
+
+        let privacy_pin_row = adw::PasswordEntryRow::new();
+
+        let ignore_patterns_row = adw::EntryRow::new();
+        ignore_patterns_row.set_text(&settings_state.read().ignore_patterns.join(", "));
+
+        let library_dirs_group = adw::PreferencesGroup::new();
+
         let model = Self {
             settings_state: settings_state.clone(),
+            privacy: *privacy_state.read(),
+            privacy_state,
             parent,
             dialog: dialog.clone(),
             settings: settings_state.read().clone(),
             album_sort: album_sort_row.clone(),
+            album_zoom: album_zoom_row.clone(),
+            folders_view_mode: folders_view_mode_row.clone(),
+            face_detection_model: face_detection_model_row.clone(),
+            face_confidence_threshold: face_confidence_threshold_row.clone(),
+            face_cluster_distance: face_cluster_distance_row.clone(),
+            face_thumbnail_margin: face_thumbnail_margin_row.clone(),
+            thumbnail_cache_limit: thumbnail_cache_limit_row.clone(),
+            thumbnail_order: thumbnail_order_row.clone(),
+            thumbnail_location: thumbnail_location_row.clone(),
+            thumbnail_power_mode: thumbnail_power_mode_row.clone(),
+            import_layout: import_layout_row.clone(),
+            sidecar_policy: sidecar_policy_row.clone(),
+            slideshow_interval: slideshow_interval_row.clone(),
+            similar_photos_distance: similar_photos_distance_row.clone(),
+            ignore_patterns: ignore_patterns_row.clone(),
+            privacy_mode: privacy_mode_row.clone(),
+            library_dirs_group: library_dirs_group.clone(),
+            library_dir_rows: Vec::new(),
         };
 
         let widgets = view_output!();
@@ -219,12 +797,58 @@ impl SimpleAsyncComponent for PreferencesDialog {
                 info!("Received update from settings shared state");
                 self.settings = settings;
 
-                let index = match self.settings.album_sort {
-                    AlbumSort::Ascending => 0,
-                    AlbumSort::Descending => 1,
-                };
+                self.album_sort
+                    .set_selected(self.settings.album_sort as u32);
+
+                self.album_zoom
+                    .set_selected(self.settings.album_zoom as u32);
+
+                self.folders_view_mode
+                    .set_selected(self.settings.folders_view_mode as u32);
+
+                self.thumbnail_order
+                    .set_selected(self.settings.thumbnail_order as u32);
+
+                self.thumbnail_location
+                    .set_selected(self.settings.thumbnail_location as u32);
+
+                self.thumbnail_power_mode
+                    .set_selected(self.settings.thumbnail_power_mode as u32);
+
+                self.import_layout
+                    .set_selected(self.settings.import_layout as u32);
+
+                self.sidecar_policy
+                    .set_selected(self.settings.sidecar_policy as u32);
+
+                self.privacy_mode
+                    .set_selected(self.settings.privacy_mode as u32);
 
-                self.album_sort.set_selected(index);
+                self.face_detection_model
+                    .set_selected(self.settings.face_detection_model as u32);
+
+                self.face_confidence_threshold
+                    .set_value(self.settings.face_confidence_threshold.into());
+
+                self.face_cluster_distance
+                    .set_value(self.settings.face_cluster_distance.into());
+
+                self.face_thumbnail_margin
+                    .set_value(self.settings.face_thumbnail_margin.into());
+
+                self.thumbnail_cache_limit
+                    .set_value(self.settings.thumbnail_cache_limit_gb);
+
+                self.slideshow_interval
+                    .set_value(self.settings.slideshow_interval_secs.into());
+
+                self.similar_photos_distance
+                    .set_value(self.settings.similar_photos_distance.into());
+
+                self.ignore_patterns
+                    .set_text(&self.settings.ignore_patterns.join(", "));
+
+                self.update_library_dir_rows(&sender);
             }
             PreferencesInput::UpdateShowSelfies(show_selfies) => {
                 info!("Update show selfies: {}", show_selfies);
@@ -239,6 +863,51 @@ impl SimpleAsyncComponent for PreferencesDialog {
                     let _ = sender.output(PreferencesOutput::ProcessMotionPhotos);
                 }
             }
+            PreferencesInput::UpdateWatchLibrary(enable) => {
+                info!("Update watch library: {:?}", enable);
+                self.settings.watch_library = enable;
+                *self.settings_state.write() = self.settings.clone();
+            }
+            PreferencesInput::UpdateFollowSymlinks(enable) => {
+                if self.settings.follow_symlinks == enable {
+                    return;
+                }
+                info!("Update follow symlinks: {:?}", enable);
+                self.settings.follow_symlinks = enable;
+                *self.settings_state.write() = self.settings.clone();
+
+                // Files behind links that weren't followed before are only found
+                // by scanning everything.
+                if enable {
+                    let _ = sender.output(PreferencesOutput::Rebuild);
+                }
+            }
+            PreferencesInput::UpdateAnimatedPreviews(enable) => {
+                info!("Update animated previews: {:?}", enable);
+                self.settings.animated_previews = enable;
+                *self.settings_state.write() = self.settings.clone();
+                if enable {
+                    let _ = sender.output(PreferencesOutput::GenerateAnimatedPreviews);
+                } else {
+                    let _ = sender.output(PreferencesOutput::RemoveAnimatedPreviews);
+                }
+            }
+            PreferencesInput::UpdateThumbnailCacheLimit(limit) => {
+                // Spin row notifies when the value is set from the shared state too.
+                let previous = self.settings.thumbnail_cache_limit_bytes();
+                if self.settings.thumbnail_cache_limit_gb == limit {
+                    return;
+                }
+                info!("Update thumbnail cache limit: {} GB", limit);
+                self.settings.thumbnail_cache_limit_gb = limit;
+                *self.settings_state.write() = self.settings.clone();
+
+                // Evict thumbnails now if the limit is lower, rather than waiting for the next scan.
+                let current = self.settings.thumbnail_cache_limit_bytes();
+                if current.is_some_and(|current| previous.is_none_or(|previous| current < previous)) {
+                    let _ = sender.output(PreferencesOutput::LimitThumbnailCache);
+                }
+            }
             PreferencesInput::UpdateFaceDetectionMode(mode) => {
                 info!("Update face detection mode: {:?}", mode);
                 self.settings.face_detection_mode = mode;
@@ -247,50 +916,218 @@ impl SimpleAsyncComponent for PreferencesDialog {
                     let _ = sender.output(PreferencesOutput::EnableFaceDetection);
                 }
             }
+            PreferencesInput::UpdateFaceDetectionModel(detection_model) => {
+                // Combo row notifies when the value is set from the shared state too.
+                if self.settings.face_detection_model == detection_model {
+                    return;
+                }
+                info!("Update face detection model: {:?}", detection_model);
+                self.settings.face_detection_model = detection_model;
+                *self.settings_state.write() = self.settings.clone();
+                let _ = sender.output(PreferencesOutput::FaceDetectionModelChanged);
+            }
+            PreferencesInput::UpdateFaceConfidenceThreshold(threshold) => {
+                // Spin row notifies when the value is set from the shared state too.
+                if self.settings.face_confidence_threshold == threshold {
+                    return;
+                }
+                info!("Update face confidence threshold: {}", threshold);
+                self.settings.face_confidence_threshold = threshold;
+                *self.settings_state.write() = self.settings.clone();
+            }
+            PreferencesInput::UpdateFaceClusterDistance(distance) => {
+                if self.settings.face_cluster_distance == distance {
+                    return;
+                }
+                info!("Update face cluster distance: {}", distance);
+                self.settings.face_cluster_distance = distance;
+                *self.settings_state.write() = self.settings.clone();
+            }
+            PreferencesInput::UpdateSlideshowInterval(secs) => {
+                if self.settings.slideshow_interval_secs == secs {
+                    return;
+                }
+                info!("Update slideshow interval: {} seconds", secs);
+                self.settings.slideshow_interval_secs = secs;
+                *self.settings_state.write() = self.settings.clone();
+            }
+            PreferencesInput::UpdateSimilarPhotosDistance(distance) => {
+                if self.settings.similar_photos_distance == distance {
+                    return;
+                }
+                info!("Update similar photos distance: {}", distance);
+                self.settings.similar_photos_distance = distance;
+                *self.settings_state.write() = self.settings.clone();
+            }
+            PreferencesInput::UpdateFaceThumbnailMargin(margin) => {
+                if self.settings.face_thumbnail_margin == margin {
+                    return;
+                }
+                info!("Update face thumbnail margin: {}", margin);
+                self.settings.face_thumbnail_margin = margin;
+                *self.settings_state.write() = self.settings.clone();
+            }
             PreferencesInput::Sort(mode) => {
                 info!("Update album sort: {:?}", mode);
                 self.settings.album_sort = mode;
                 *self.settings_state.write() = self.settings.clone();
             }
+            PreferencesInput::UpdateAlbumZoom(zoom) => {
+                if self.settings.album_zoom == zoom {
+                    return;
+                }
+                info!("Update album zoom: {:?}", zoom);
+                self.settings.album_zoom = zoom;
+                *self.settings_state.write() = self.settings.clone();
+            }
+            PreferencesInput::UpdateFoldersViewMode(mode) => {
+                info!("Update folders view mode: {:?}", mode);
+                self.settings.folders_view_mode = mode;
+                *self.settings_state.write() = self.settings.clone();
+            }
+            PreferencesInput::UpdateShowHiddenFolders(show) => {
+                if self.settings.show_hidden_folders == show {
+                    return;
+                }
+                info!("Update show hidden folders: {:?}", show);
+                self.settings.show_hidden_folders = show;
+                *self.settings_state.write() = self.settings.clone();
+            }
+            PreferencesInput::UpdateThumbnailOrder(order) => {
+                info!("Update thumbnail order: {:?}", order);
+                self.settings.thumbnail_order = order;
+                *self.settings_state.write() = self.settings.clone();
+            }
+            PreferencesInput::UpdateThumbnailLocation(location) => {
+                // Combo row notifies when the value is set from the shared state too.
+                if self.settings.thumbnail_location == location {
+                    return;
+                }
+                info!("Update thumbnail location: {:?}", location);
+                self.settings.thumbnail_location = location;
+                *self.settings_state.write() = self.settings.clone();
+                let _ = sender.output(PreferencesOutput::ThumbnailLocationChanged);
+            }
+            PreferencesInput::UpdateThumbnailPowerMode(mode) => {
+                info!("Update thumbnail power mode: {:?}", mode);
+                self.settings.thumbnail_power_mode = mode;
+                *self.settings_state.write() = self.settings.clone();
+            }
+            PreferencesInput::UpdateImportLayout(layout) => {
+                info!("Update import layout: {:?}", layout);
+                self.settings.import_layout = layout;
+                *self.settings_state.write() = self.settings.clone();
+            }
+            PreferencesInput::UpdateSidecarPolicy(policy) => {
+                info!("Update sidecar policy: {:?}", policy);
+                self.settings.sidecar_policy = policy;
+                *self.settings_state.write() = self.settings.clone();
+            }
+            PreferencesInput::Privacy(privacy) => {
+                self.privacy = privacy;
+            }
+            PreferencesInput::UpdatePrivacyMode(mode) => {
+                if self.settings.privacy_mode == mode {
+                    return;
+                }
+                if *self.privacy_state.read() != Privacy::Unlocked {
+                    warn!("Not changing privacy mode while private pictures are locked");
+                    self.privacy_mode
+                        .set_selected(self.settings.privacy_mode as u32);
+                    return;
+                }
+                info!("Update privacy mode: {:?}", mode);
+                self.settings.privacy_mode = mode;
+                *self.settings_state.write() = self.settings.clone();
+            }
+            PreferencesInput::UpdatePrivacyPin(pin) => {
+                if *self.privacy_state.read() != Privacy::Unlocked {
+                    warn!("Not changing privacy PIN while private pictures are locked");
+                    return;
+                }
+                info!("Update privacy PIN");
+                if let Err(e) = self.settings.set_privacy_pin(pin.trim()) {
+                    error!("Failed changing privacy PIN: {:?}", e);
+                    return;
+                }
+                *self.settings_state.write() = self.settings.clone();
+            }
+            PreferencesInput::UpdateIgnorePatterns(text) => {
+                let patterns: Vec<String> = text
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|pattern| !pattern.is_empty())
+                    .map(String::from)
+                    .collect();
+
+                if self.settings.ignore_patterns == patterns {
+                    return;
+                }
+                info!("Update ignore patterns: {:?}", patterns);
+
+                // Files that are no longer ignored are only found by scanning everything.
+                let is_any_removed = self
+                    .settings
+                    .ignore_patterns
+                    .iter()
+                    .any(|pattern| !patterns.contains(pattern));
+
+                self.settings.ignore_patterns = patterns;
+                *self.settings_state.write() = self.settings.clone();
+
+                if is_any_removed {
+                    let _ = sender.output(PreferencesOutput::Rebuild);
+                } else {
+                    let _ = sender.output(PreferencesOutput::Rescan);
+                }
+            }
             PreferencesInput::ChoosePicturesDir => {
                 info!("Presenting select pictures directory file chooser");
-                if let Some(root) = gtk::Widget::root(self.parent.widget_ref()) {
-                    let identifier = WindowIdentifier::from_native(&root).await;
-                    let request = OpenFileRequest::default()
-                        .directory(true)
-                        .identifier(identifier)
-                        .modal(true) // can't be modal without identifier.
-                        .multiple(false);
-
-                    match request.send().await.and_then(|r| r.response()) {
-                        Ok(files) => {
-                            info!("Open: {:?}", files);
-                            if let Some(first) = files.uris().first() {
-                                info!("User has chosen picture library at: {:?}", first.path());
-                                if let Some(library_base_dir) =
-                                    files.uris().first().and_then(|uri| uri.to_file_path().ok())
-                                {
-                                    info!(
-                                        "User has chosen picture library at: {:?}",
-                                        library_base_dir
-                                    );
-                                    if self.settings.library_base_dir.sandbox_path != library_base_dir {
-                                        info!(
-                                            "New pictures base director is: {:?}",
-                                            library_base_dir
-                                        );
-                                        self.settings.library_base_dir = host_path::host_path(&library_base_dir)
-                                            .await.unwrap_or(FlatpakPathBuf::build(&library_base_dir, &library_base_dir));
-                                        *self.settings_state.write() = self.settings.clone();
-                                    }
-                                }
-                            }
-                        }
-                        Err(err) => {
-                            error!("Failed to open a file: {err}");
-                        }
+                let Some(library_base_dir) = self.choose_dir().await else {
+                    return;
+                };
+
+                info!("User has chosen picture library at: {:?}", library_base_dir);
+                if self.settings.library_base_dir.sandbox_path != library_base_dir {
+                    info!("New pictures base director is: {:?}", library_base_dir);
+                    let library_base_dir = host_path::host_path(&library_base_dir)
+                        .await.unwrap_or(FlatpakPathBuf::build(&library_base_dir, &library_base_dir));
+
+                    // Don't let the pictures directory overlap any other directory.
+                    let roots = LibraryRoots::build(&library_base_dir, &[]);
+                    if self.settings.library_dirs.iter().any(|dir| roots.overlapping(&dir.sandbox_path).is_some()) {
+                        self.dialog.add_toast(adw::Toast::new(&fl!("prefs-library-dirs-section", "overlapping")));
+                        return;
                     }
+
+                    self.settings.library_base_dir = library_base_dir;
+                    *self.settings_state.write() = self.settings.clone();
+                }
+            }
+            PreferencesInput::AddLibraryDir => {
+                info!("Presenting add library directory file chooser");
+                let Some(dir) = self.choose_dir().await else {
+                    return;
+                };
+
+                info!("User has chosen to add library directory: {:?}", dir);
+                let dir = host_path::host_path(&dir)
+                    .await.unwrap_or(FlatpakPathBuf::build(&dir, &dir));
+
+                // Scanning a directory twice would add every picture in it twice.
+                if let Err(e) = self.settings.library_roots().add(dir.clone()) {
+                    info!("Not adding library directory: {}", e);
+                    self.dialog.add_toast(adw::Toast::new(&fl!("prefs-library-dirs-section", "overlapping")));
+                    return;
                 }
+
+                self.settings.library_dirs.push(dir);
+                *self.settings_state.write() = self.settings.clone();
+            }
+            PreferencesInput::RemoveLibraryDir(dir) => {
+                info!("Remove library directory: {:?}", dir);
+                self.settings.library_dirs.retain(|x| x.sandbox_path != dir);
+                *self.settings_state.write() = self.settings.clone();
             }
         }
     }
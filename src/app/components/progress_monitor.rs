@@ -3,41 +3,145 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use relm4::Reducible;
+use relm4::gtk::gio;
+use relm4::gtk::prelude::SettingsExt;
+use tracing::{error, info};
+
+use crate::config::APP_ID;
+
+/// Progress is saved after this many items, so that little is lost if the app crashes.
+const SAVE_INTERVAL: usize = 100;
 
 /// Media types
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MediaType {
     Photo,
     Video,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ThumbnailType {
     Photo,
     Video,
     Face,
+    Animated,
 }
 
 /// Different kinds of background task that have a progress bar
 /// Note that some background tasks just have the banner and spinner.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TaskName {
+    Scan,
     Enrich(MediaType),
     Thumbnail(ThumbnailType),
     Transcode,
     MotionPhoto,
     DetectFaces,
     RecognizeFaces,
+    Export,
+    ExportMetadata,
+    Import,
+    WriteSidecars,
+    ImportSidecars,
+    RelocateThumbnails,
 
     /// FIXME figure out if 'Idle' will be used.
     Idle,
 }
 
+impl TaskName {
+    /// Tasks that carry on from where they got to if the app exits before they complete.
+    const RESUMABLE: [TaskName; 10] = [
+        TaskName::Enrich(MediaType::Photo),
+        TaskName::Enrich(MediaType::Video),
+        TaskName::Thumbnail(ThumbnailType::Photo),
+        TaskName::Thumbnail(ThumbnailType::Video),
+        TaskName::Thumbnail(ThumbnailType::Face),
+        TaskName::Thumbnail(ThumbnailType::Animated),
+        TaskName::Transcode,
+        TaskName::MotionPhoto,
+        TaskName::DetectFaces,
+        TaskName::RecognizeFaces,
+    ];
+
+    /// Is progress of the task saved, so that it can be resumed after a crash?
+    pub fn is_resumable(&self) -> bool {
+        Self::RESUMABLE.contains(self)
+    }
+
+    /// Name of the task in saved progress.
+    fn key(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    fn from_key(key: &str) -> Option<TaskName> {
+        Self::RESUMABLE.into_iter().find(|task| task.key() == key)
+    }
+
+    /// Can the task be paused and resumed without restarting?
+    pub fn is_pausable(&self) -> bool {
+        matches!(
+            self,
+            TaskName::Thumbnail(ThumbnailType::Photo)
+                | TaskName::Thumbnail(ThumbnailType::Video)
+                | TaskName::Thumbnail(ThumbnailType::Animated)
+        )
+    }
+}
+
+/// Progress of a task that was running when the app last exited, such as by crashing.
+/// Saved while the task runs, and cleared when the task completes.
+#[derive(Debug, Clone, Copy)]
+pub struct Interrupted {
+    pub task_name: TaskName,
+    pub current_count: usize,
+    pub end_count: usize,
+}
+
+impl Interrupted {
+    /// Task that didn't complete the last time the app ran, if any.
+    pub fn load() -> Option<Interrupted> {
+        let gio_settings = gio::Settings::new(APP_ID);
+        let task_name = TaskName::from_key(&gio_settings.string("interrupted-task"))?;
+        Some(Interrupted {
+            task_name,
+            current_count: gio_settings.uint("interrupted-count") as usize,
+            end_count: gio_settings.uint("interrupted-total") as usize,
+        })
+    }
+
+    fn save(&self) {
+        let gio_settings = gio::Settings::new(APP_ID);
+        let result = gio_settings
+            .set_string("interrupted-task", &self.task_name.key())
+            .and_then(|_| gio_settings.set_uint("interrupted-count", self.current_count as u32))
+            .and_then(|_| gio_settings.set_uint("interrupted-total", self.end_count as u32));
+
+        if let Err(e) = result {
+            error!("Failed saving progress of {:?}: {:?}", self.task_name, e);
+        }
+    }
+
+    /// Forget saved progress, because the task has completed.
+    fn clear() {
+        let gio_settings = gio::Settings::new(APP_ID);
+        if let Err(e) = gio_settings.set_string("interrupted-task", "") {
+            error!("Failed clearing saved progress: {:?}", e);
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum ProgressMonitorInput {
     Start(TaskName, usize),
+
+    /// Start a task whose final count isn't known until it completes.
+    StartIndeterminate(TaskName),
+
     Advance,
     Complete,
+    Pause,
+    Resume,
 }
 
 /// Monitors the progress of a task and informs subscribers about changes.
@@ -50,6 +154,18 @@ pub struct ProgressMonitor {
 
     // Final progress
     end_count: usize,
+
+    /// Is the final progress unknown until the task completes?
+    pub is_indeterminate: bool,
+
+    /// Has the user paused background tasks?
+    pub is_paused: bool,
+
+    /// Is the task carrying on from where it got to before the app last exited?
+    pub is_resuming: bool,
+
+    /// Task that didn't complete the last time the app ran. Taken when it starts again.
+    interrupted: Option<Interrupted>,
 }
 
 impl ProgressMonitor {
@@ -62,7 +178,23 @@ impl ProgressMonitor {
     }
 
     pub fn is_complete(&self) -> bool {
-        self.current_count == self.end_count
+        !self.is_indeterminate && self.current_count == self.end_count
+    }
+
+    /// Total count of items, including those processed before the app last exited.
+    pub fn end_count(&self) -> usize {
+        self.end_count
+    }
+
+    fn save_progress(&self) {
+        if self.task_name.is_resumable() {
+            Interrupted {
+                task_name: self.task_name,
+                current_count: self.current_count,
+                end_count: self.end_count,
+            }
+            .save();
+        }
     }
 }
 
@@ -74,6 +206,10 @@ impl Reducible for ProgressMonitor {
             task_name: TaskName::Idle,
             current_count: 0,
             end_count: 0,
+            is_indeterminate: false,
+            is_paused: false,
+            is_resuming: false,
+            interrupted: Interrupted::load(),
         }
     }
 
@@ -81,16 +217,57 @@ impl Reducible for ProgressMonitor {
         match input {
             ProgressMonitorInput::Start(task_name, end_count) => {
                 self.task_name = task_name;
-                self.end_count = end_count;
+                self.is_indeterminate = false;
+
+                // Items processed before the interruption are skipped when the task runs
+                // again, so count on from where the task got to.
+                match self.interrupted.take_if(|i| i.task_name == task_name) {
+                    Some(interrupted) => {
+                        info!("Resuming {:?}", interrupted);
+                        self.current_count = interrupted.current_count;
+                        self.end_count = interrupted.current_count + end_count;
+                        self.is_resuming = true;
+                    }
+                    None => {
+                        self.current_count = 0;
+                        self.end_count = end_count;
+                        self.is_resuming = false;
+                    }
+                }
+
+                self.save_progress();
+            }
+            ProgressMonitorInput::StartIndeterminate(task_name) => {
+                self.task_name = task_name;
+                self.end_count = 0;
                 self.current_count = 0;
+                self.is_indeterminate = true;
+                self.is_resuming = false;
             }
             ProgressMonitorInput::Advance => {
-                if self.current_count < self.end_count {
+                if self.is_indeterminate || self.current_count < self.end_count {
                     self.current_count += 1;
                 }
+
+                if self.current_count % SAVE_INTERVAL == 0 {
+                    self.save_progress();
+                }
             }
             ProgressMonitorInput::Complete => {
+                self.is_indeterminate = false;
+                self.end_count = self.current_count.max(self.end_count);
                 self.current_count = self.end_count;
+                self.is_resuming = false;
+
+                if self.task_name.is_resumable() {
+                    Interrupted::clear();
+                }
+            }
+            ProgressMonitorInput::Pause => {
+                self.is_paused = true;
+            }
+            ProgressMonitorInput::Resume => {
+                self.is_paused = false;
             }
         }
         true // subscribers only notified if 'true' is returned
@@ -2,44 +2,98 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use relm4::adw;
 use relm4::gtk;
-use relm4::gtk::prelude::WidgetExt;
+use relm4::gtk::prelude::*;
 use relm4::shared_state::Reducer;
 use relm4::*;
 
 use std::sync::Arc;
 
 use super::progress_monitor::{MediaType, ThumbnailType, ProgressMonitor, TaskName};
+use fotema_core::number;
 use crate::fl;
 
 #[derive(Debug)]
 pub enum ProgressPanelInput {
-    Update(TaskName, f64, usize, bool),
+    /// Task, fraction complete, count of items processed, is complete, is paused,
+    /// is indeterminate, total count of items if resuming after an interruption.
+    Update(TaskName, f64, usize, bool, bool, bool, Option<usize>),
+
+    /// User has pressed or released the pause button.
+    TogglePause(bool),
+}
+
+#[derive(Debug)]
+pub enum ProgressPanelOutput {
+    /// Pause or resume background tasks.
+    Pause(bool),
 }
 
 /// Shows progress of a background task
 pub struct ProgressPanel {
+    root: gtk::Box,
     progress_bar: gtk::ProgressBar,
+
+    // Shown instead of the progress bar when the final count isn't known.
+    spinner: adw::Spinner,
+    tally: gtk::Label,
+
+    pause_button: gtk::ToggleButton,
+    is_paused: bool,
 }
 
 #[relm4::component(pub)]
 impl SimpleComponent for ProgressPanel {
     type Init = Arc<Reducer<ProgressMonitor>>;
     type Input = ProgressPanelInput;
-    type Output = ();
+    type Output = ProgressPanelOutput;
 
     view! {
-        gtk::ProgressBar {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Horizontal,
             set_margin_all: 12,
+            set_spacing: 6,
             set_visible: false,
-            set_show_text: true,
-            set_pulse_step: 0.05,
+
+            #[local_ref]
+            spinner -> adw::Spinner {
+                set_valign: gtk::Align::Center,
+                set_visible: false,
+            },
+
+            #[local_ref]
+            tally -> gtk::Label {
+                set_hexpand: true,
+                set_halign: gtk::Align::Start,
+                set_visible: false,
+            },
+
+            #[local_ref]
+            progress_bar -> gtk::ProgressBar {
+                set_hexpand: true,
+                set_valign: gtk::Align::Center,
+                set_show_text: true,
+                set_pulse_step: 0.05,
+            },
+
+            #[local_ref]
+            pause_button -> gtk::ToggleButton {
+                set_valign: gtk::Align::Center,
+                set_icon_name: "media-playback-pause-symbolic",
+                set_tooltip_text: Some(&fl!("progress-pause-button", "tooltip")),
+                add_css_class: "flat",
+
+                connect_toggled[sender] => move |button| {
+                    sender.input(ProgressPanelInput::TogglePause(button.is_active()));
+                },
+            },
         }
     }
 
     fn init(
         progress_monitor: Self::Init,
-        progress_bar: Self::Root,
+        root: Self::Root,
         sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
         progress_monitor.subscribe(sender.input_sender(), |data| {
@@ -48,11 +102,24 @@ impl SimpleComponent for ProgressPanel {
                 data.fraction(),
                 data.current_count,
                 data.is_complete(),
+                data.is_paused,
+                data.is_indeterminate,
+                data.is_resuming.then(|| data.end_count()),
             )
         });
 
+        let progress_bar = gtk::ProgressBar::new();
+        let spinner = adw::Spinner::new();
+        let tally = gtk::Label::new(None);
+        let pause_button = gtk::ToggleButton::new();
+
         let model = ProgressPanel {
+            root: root.clone(),
             progress_bar: progress_bar.clone(),
+            spinner: spinner.clone(),
+            tally: tally.clone(),
+            pause_button: pause_button.clone(),
+            is_paused: false,
         };
 
         let widgets = view_output!();
@@ -60,12 +127,60 @@ impl SimpleComponent for ProgressPanel {
         ComponentParts { model, widgets }
     }
 
-    fn update(&mut self, msg: Self::Input, _sender: ComponentSender<Self>) {
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
         match msg {
-            ProgressPanelInput::Update(task_name, fraction, count, is_complete) => {
-                if count == 0 {
-                    self.progress_bar.set_visible(true);
+            ProgressPanelInput::TogglePause(is_paused) => {
+                // Button is also toggled when the paused state is updated from the monitor.
+                if is_paused != self.is_paused {
+                    let _ = sender.output(ProgressPanelOutput::Pause(is_paused));
+                }
+            }
+            ProgressPanelInput::Update(
+                task_name,
+                fraction,
+                count,
+                is_complete,
+                is_paused,
+                is_indeterminate,
+                resuming_total,
+            ) => {
+                let is_pause_changed = is_paused != self.is_paused;
+                self.is_paused = is_paused;
+                self.pause_button.set_active(is_paused);
+                self.pause_button.set_visible(task_name.is_pausable());
+                self.pause_button.set_icon_name(if is_paused {
+                    "media-playback-start-symbolic"
+                } else {
+                    "media-playback-pause-symbolic"
+                });
+
+                self.progress_bar.set_visible(!is_indeterminate);
+                self.spinner.set_visible(is_indeterminate);
+                self.tally.set_visible(is_indeterminate);
+
+                if is_indeterminate {
+                    // Scanning is the only task without a known final count.
+                    self.root.set_visible(true);
+                    self.tally.set_text(&fl!("progress-scan", count = count));
+                    return;
+                }
+
+                if is_paused {
+                    self.progress_bar.set_text(Some(&fl!("progress-paused")));
+                } else if let Some(total) = resuming_total {
+                    self.root.set_visible(true);
+                    self.progress_bar.set_text(Some(&fl!(
+                        "progress-resuming",
+                        count = number::format_count(count),
+                        total = number::format_count(total)
+                    )));
+                } else if count == 0 || is_pause_changed {
+                    self.root.set_visible(true);
                     match task_name {
+                        TaskName::Scan => {
+                            self.progress_bar
+                                .set_text(Some(&fl!("progress-scan", count = count)));
+                        }
                         TaskName::Enrich(MediaType::Photo) => {
                             self.progress_bar
                                 .set_text(Some(&fl!("progress-metadata-photos")));
@@ -86,6 +201,10 @@ impl SimpleComponent for ProgressPanel {
                             self.progress_bar
                                 .set_text(Some(&fl!("progress-thumbnails-faces")));
                         }
+                        TaskName::Thumbnail(ThumbnailType::Animated) => {
+                            self.progress_bar
+                                .set_text(Some(&fl!("progress-thumbnails-animated")));
+                        }
                         TaskName::Transcode => {
                             self.progress_bar
                                 .set_text(Some(&fl!("progress-convert-videos")));
@@ -102,6 +221,29 @@ impl SimpleComponent for ProgressPanel {
                             self.progress_bar
                                 .set_text(Some(&fl!("progress-recognize-faces-photos")));
                         }
+                        TaskName::Export => {
+                            self.progress_bar
+                                .set_text(Some(&fl!("progress-export-photos")));
+                        }
+                        TaskName::ExportMetadata => {
+                            self.progress_bar
+                                .set_text(Some(&fl!("progress-export-metadata")));
+                        }
+                        TaskName::Import => {
+                            self.progress_bar.set_text(Some(&fl!("progress-import")));
+                        }
+                        TaskName::WriteSidecars => {
+                            self.progress_bar
+                                .set_text(Some(&fl!("progress-write-sidecars")));
+                        }
+                        TaskName::ImportSidecars => {
+                            self.progress_bar
+                                .set_text(Some(&fl!("progress-import-sidecars")));
+                        }
+                        TaskName::RelocateThumbnails => {
+                            self.progress_bar
+                                .set_text(Some(&fl!("progress-relocate-thumbnails")));
+                        }
                         TaskName::Idle => {
                             self.progress_bar.set_text(Some(&fl!("progress-idle")));
                         }
@@ -109,7 +251,7 @@ impl SimpleComponent for ProgressPanel {
                 }
 
                 if is_complete {
-                    self.progress_bar.set_visible(false);
+                    self.root.set_visible(false);
                     self.progress_bar.set_text(None);
                 } else {
                     self.progress_bar.set_fraction(fraction);
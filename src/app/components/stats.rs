@@ -0,0 +1,190 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use relm4::adw::prelude::*;
+use relm4::gtk;
+use relm4::prelude::*;
+
+use chrono::Local;
+use humansize::{DECIMAL, format_size};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tracing::{error, info};
+
+use crate::app::SettingsState;
+use crate::fl;
+use fotema_core::database;
+use fotema_core::visual::{self, LibraryStats};
+
+pub struct StatsDialog {
+    parent: adw::ApplicationWindow,
+    dialog: adw::Dialog,
+
+    con: Arc<Mutex<database::Connection>>,
+    settings_state: SettingsState,
+    cache_dir: PathBuf,
+    thumbnails_dir: PathBuf,
+
+    /// None until statistics have been calculated.
+    stats: Option<LibraryStats>,
+}
+
+impl StatsDialog {
+    fn count(&self, f: impl Fn(&LibraryStats) -> u64) -> String {
+        self.stats
+            .as_ref()
+            .map(|stats| f(stats).to_string())
+            .unwrap_or_default()
+    }
+
+    fn size(&self, f: impl Fn(&LibraryStats) -> u64) -> String {
+        self.stats
+            .as_ref()
+            .map(|stats| format_size(f(stats), DECIMAL))
+            .unwrap_or_default()
+    }
+
+    fn date_span(&self) -> String {
+        self.stats
+            .as_ref()
+            .and_then(|stats| stats.date_span)
+            .map(|(oldest, newest)| {
+                fl!(
+                    "library-stats-date-span",
+                    oldest = oldest.with_timezone(&Local).format("%x").to_string(),
+                    newest = newest.with_timezone(&Local).format("%x").to_string()
+                )
+            })
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug)]
+pub enum StatsInput {
+    /// Calculate statistics and show the dialog.
+    Present,
+}
+
+#[relm4::component(pub async)]
+impl SimpleAsyncComponent for StatsDialog {
+    type Init = (
+        Arc<Mutex<database::Connection>>,
+        SettingsState,
+        PathBuf,
+        PathBuf,
+        adw::ApplicationWindow,
+    );
+    type Input = StatsInput;
+    type Output = ();
+
+    view! {
+        adw::Dialog {
+            set_title: &fl!("library-stats"),
+            set_content_width: 360,
+
+            #[wrap(Some)]
+            set_child = &adw::ToolbarView {
+                add_top_bar = &adw::HeaderBar,
+
+                #[wrap(Some)]
+                set_content = &adw::PreferencesPage {
+                    add = &adw::PreferencesGroup {
+                        adw::ActionRow {
+                            set_title: &fl!("library-stats", "photos"),
+                            add_css_class: "property",
+                            #[watch]
+                            set_subtitle: &model.count(|stats| stats.photos),
+                        },
+
+                        adw::ActionRow {
+                            set_title: &fl!("library-stats", "videos"),
+                            add_css_class: "property",
+                            #[watch]
+                            set_subtitle: &model.count(|stats| stats.videos),
+                        },
+
+                        adw::ActionRow {
+                            set_title: &fl!("library-stats", "people"),
+                            add_css_class: "property",
+                            #[watch]
+                            set_subtitle: &model.count(|stats| stats.people),
+                        },
+
+                        adw::ActionRow {
+                            set_title: &fl!("library-stats", "date-span"),
+                            add_css_class: "property",
+                            #[watch]
+                            set_subtitle: &model.date_span(),
+                        },
+                    },
+
+                    add = &adw::PreferencesGroup {
+                        set_title: &fl!("library-stats", "disk-space"),
+
+                        adw::ActionRow {
+                            set_title: &fl!("library-stats", "originals-size"),
+                            add_css_class: "property",
+                            #[watch]
+                            set_subtitle: &model.size(|stats| stats.originals_size),
+                        },
+
+                        adw::ActionRow {
+                            set_title: &fl!("library-stats", "thumbnails-size"),
+                            add_css_class: "property",
+                            #[watch]
+                            set_subtitle: &model.size(|stats| stats.thumbnails_size),
+                        },
+                    },
+                },
+            },
+        }
+    }
+
+    async fn init(
+        (con, settings_state, cache_dir, thumbnails_dir, parent): Self::Init,
+        dialog: Self::Root,
+        _sender: AsyncComponentSender<Self>,
+    ) -> AsyncComponentParts<Self> {
+        let model = Self {
+            parent,
+            dialog: dialog.clone(),
+            con,
+            settings_state,
+            cache_dir,
+            thumbnails_dir,
+            stats: None,
+        };
+
+        let widgets = view_output!();
+
+        AsyncComponentParts { model, widgets }
+    }
+
+    async fn update(&mut self, msg: Self::Input, _sender: AsyncComponentSender<Self>) {
+        match msg {
+            StatsInput::Present => {
+                info!("Calculating library statistics");
+                let library_roots = self.settings_state.read().library_roots();
+                let cache_dir = self.cache_dir.clone();
+                let thumbnails_dir = self.thumbnails_dir.clone();
+                let con = self.con.clone();
+
+                // Walks the file system, so keep it off the main thread.
+                let result = relm4::spawn_blocking(move || {
+                    visual::Repository::open(&library_roots, &cache_dir, con)
+                        .and_then(|repo| repo.stats(&thumbnails_dir))
+                })
+                .await;
+
+                match result {
+                    Ok(Ok(stats)) => self.stats = Some(stats),
+                    Ok(Err(e)) => error!("Failed calculating library statistics: {:?}", e),
+                    Err(e) => error!("Failed calculating library statistics: {:?}", e),
+                }
+
+                self.dialog.present(Some(&self.parent));
+            }
+        }
+    }
+}
@@ -10,6 +10,7 @@ use relm4::prelude::*;
 use relm4::typed_view::grid::{RelmGridItem, TypedGridView};
 use gtk::prelude::OrientableExt;
 
+use crate::app::SettingsState;
 use crate::fl;
 use fotema_core::FaceId;
 use fotema_core::PersonId;
@@ -263,6 +264,8 @@ pub enum FaceThumbnailsOutput {}
 pub struct FaceThumbnails {
     people_repo: people::Repository,
 
+    settings_state: SettingsState,
+
     picture_id: Option<PictureId>,
 
     face_grid: TypedGridView<FaceGridItem, gtk::SingleSelection>,
@@ -273,7 +276,7 @@ pub struct FaceThumbnails {
 
 #[relm4::component(pub async)]
 impl SimpleAsyncComponent for FaceThumbnails {
-    type Init = people::Repository;
+    type Init = (people::Repository, SettingsState);
     type Input = FaceThumbnailsInput;
     type Output = FaceThumbnailsOutput;
 
@@ -291,22 +294,23 @@ impl SimpleAsyncComponent for FaceThumbnails {
     }
 
     async fn init(
-        people_repo: Self::Init,
+        (people_repo, settings_state): Self::Init,
         root: Self::Root,
         sender: AsyncComponentSender<Self>,
     ) -> AsyncComponentParts<Self> {
+        // Face confidence threshold might have changed.
+        settings_state.subscribe(sender.input_sender(), |_| FaceThumbnailsInput::Refresh);
 
         let face_grid: TypedGridView<FaceGridItem, gtk::SingleSelection>  = TypedGridView::new();
         let grid_view = &face_grid.view.clone();
 
         let widgets = view_output!();
 
-        let person_select = PersonSelect::builder().launch(people_repo.clone()).forward(
-            sender.input_sender(),
-            |msg| match msg {
+        let person_select = PersonSelect::builder()
+            .launch((people_repo.clone(), settings_state.clone()))
+            .forward(sender.input_sender(), |msg| match msg {
                 PersonSelectOutput::Done => FaceThumbnailsInput::PersonSelected,
-            },
-        );
+            });
 
         let person_dialog = adw::Dialog::builder()
             .child(person_select.widget())
@@ -317,6 +321,7 @@ impl SimpleAsyncComponent for FaceThumbnails {
         let model = Self {
             picture_id: None,
             people_repo,
+            settings_state,
             person_dialog,
             person_select,
             face_grid,
@@ -343,7 +348,8 @@ impl SimpleAsyncComponent for FaceThumbnails {
 
                 info!("Showing faces for {}", picture_id);
 
-                let result = self.people_repo.find_faces(&picture_id);
+                let min_confidence = self.settings_state.read().face_confidence_threshold;
+                let result = self.people_repo.find_faces(&picture_id, min_confidence);
                 if let Err(e) = result {
                     error!("Failed getting faces: {}", e);
                     return;
@@ -385,7 +391,7 @@ impl SimpleAsyncComponent for FaceThumbnails {
             }
             FaceThumbnailsInput::NotPerson(face_id) => {
                 debug!("Set not person for face: {}", face_id);
-                if let Err(e) = self.people_repo.mark_not_person(face_id) {
+                if let Err(e) = self.people_repo.unassign_face(face_id) {
                     error!("Failed marking face as not person: {}", e);
                 }
                 sender.input(FaceThumbnailsInput::Refresh);
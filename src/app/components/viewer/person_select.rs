@@ -7,9 +7,11 @@ use relm4::gtk::{self, gdk};
 use relm4::prelude::*;
 use relm4::*;
 
+use crate::app::SettingsState;
 use crate::fl;
 use fotema_core::FaceId;
 use fotema_core::PersonId;
+use fotema_core::PictureId;
 use fotema_core::people;
 
 use tracing::{debug, error};
@@ -21,6 +23,9 @@ pub enum PersonSelectInput {
     /// Present person selector for a give face.
     Activate(FaceId, PathBuf),
 
+    /// Present person selector to assign the one unknown face in each of the pictures.
+    ActivateForPictures(Vec<PictureId>),
+
     /// Create a new person to associate with a face.
     NewPerson,
 
@@ -41,6 +46,8 @@ pub enum PersonSelectOutput {
 pub struct PersonSelect {
     people_repo: people::Repository,
 
+    settings_state: SettingsState,
+
     /// Avatar for face to associate with person.
     avatar: adw::Avatar,
 
@@ -56,11 +63,14 @@ pub struct PersonSelect {
 
     /// ID of face to associate with person,
     face_id: Option<FaceId>,
+
+    /// IDs of pictures whose unknown face to associate with person.
+    picture_ids: Vec<PictureId>,
 }
 
 #[relm4::component(pub async)]
 impl SimpleAsyncComponent for PersonSelect {
-    type Init = people::Repository;
+    type Init = (people::Repository, SettingsState);
     type Input = PersonSelectInput;
     type Output = PersonSelectOutput;
 
@@ -86,7 +96,7 @@ impl SimpleAsyncComponent for PersonSelect {
     }
 
     async fn init(
-        people_repo: Self::Init,
+        (people_repo, settings_state): Self::Init,
         root: Self::Root,
         sender: AsyncComponentSender<Self>,
     ) -> AsyncComponentParts<Self> {
@@ -127,11 +137,13 @@ impl SimpleAsyncComponent for PersonSelect {
 
         let model = Self {
             people_repo,
+            settings_state,
             avatar,
             face_name,
             people_list,
             all_people: vec![],
             face_id: None,
+            picture_ids: vec![],
         };
 
         AsyncComponentParts { model, widgets }
@@ -142,10 +154,10 @@ impl SimpleAsyncComponent for PersonSelect {
             PersonSelectInput::Activate(face_id, thumbnail) => {
                 debug!("Activate for face {}", face_id);
 
-                self.people_list.remove_all();
-                self.all_people.clear();
                 self.face_name.set_text("");
+                self.face_name.set_visible(true);
                 self.face_id = Some(face_id);
+                self.picture_ids.clear();
 
                 {
                     let sender = sender.clone();
@@ -158,59 +170,37 @@ impl SimpleAsyncComponent for PersonSelect {
                 let img = gdk::Texture::from_filename(&thumbnail).ok();
                 self.avatar.set_custom_image(img.as_ref());
 
-                let people = self.people_repo.all_people().unwrap_or_default();
-
-                for person in people {
-                    let avatar = adw::Avatar::builder().size(50).name(&person.name).build();
-
-                    if let Some(thumbnail_path) = person.small_thumbnail_path {
-                        let img = gdk::Texture::from_filename(&thumbnail_path).ok();
-                        avatar.set_custom_image(img.as_ref());
-                    }
-
-                    let row = adw::ActionRow::builder()
-                        .title(person.name)
-                        .activatable(true)
-                        .build();
+                let max_distance = self.settings_state.read().face_cluster_distance;
+                let suggestion = self
+                    .people_repo
+                    .suggest_person_for_face(face_id, max_distance)
+                    .unwrap_or_else(|e| {
+                        error!("Failed suggesting person for face: {:?}", e);
+                        None
+                    });
 
-                    row.add_prefix(&avatar);
+                self.show_people(suggestion, &sender);
+            }
+            PersonSelectInput::ActivateForPictures(picture_ids) => {
+                debug!("Activate for {} pictures", picture_ids.len());
 
-                    {
-                        let sender = sender.clone();
-                        row.connect_activate(move |_| {
-                            sender.input(PersonSelectInput::Associate(person.person_id));
-                        });
-                    }
+                // A new person needs a face for their thumbnail, so only existing
+                // people can be chosen.
+                self.face_name.set_visible(false);
+                self.face_id = None;
+                self.picture_ids = picture_ids;
+                self.avatar.set_custom_image(None::<&gdk::Paintable>);
 
-                    self.people_list.append(&row);
-                    self.all_people.push(person.person_id);
-                }
+                self.show_people(None, &sender);
             }
             PersonSelectInput::Associate(person_id) => {
-                if let Some(face_id) = self.face_id {
-                    debug!("Associating face {} with person {}", face_id, person_id);
-                    if let Err(e) = self.people_repo.mark_as_person(face_id, person_id) {
-                        error!("Failed associating face with person: {:?}", e);
-                    }
-                }
-                self.people_list.remove_all();
-                self.all_people.clear();
+                self.associate(person_id);
                 let _ = sender.output(PersonSelectOutput::Done);
             }
             PersonSelectInput::AssociateByIndex(person_id_index) => {
-                if let (Some(face_id), Some(person_id)) =
-                    (self.face_id, self.all_people.get(person_id_index))
-                {
-                    debug!(
-                        "Associating face {} with person {} by idnex",
-                        face_id, person_id
-                    );
-                    if let Err(e) = self.people_repo.mark_as_person(face_id, *person_id) {
-                        error!("Failed associating face with person: {:?}", e);
-                    }
+                if let Some(person_id) = self.all_people.get(person_id_index).copied() {
+                    self.associate(person_id);
                 }
-                self.people_list.remove_all();
-                self.all_people.clear();
                 let _ = sender.output(PersonSelectOutput::Done);
             }
             PersonSelectInput::NewPerson => {
@@ -229,3 +219,83 @@ impl SimpleAsyncComponent for PersonSelect {
         }
     }
 }
+
+impl PersonSelect {
+    /// Lists people to choose from, with the suggested person first.
+    fn show_people(
+        &mut self,
+        suggestion: Option<(PersonId, f32)>,
+        sender: &AsyncComponentSender<Self>,
+    ) {
+        self.people_list.remove_all();
+        self.all_people.clear();
+
+        let mut people = self.people_repo.all_people(false).unwrap_or_default();
+
+        // Put the suggested person first so they can be chosen with one click.
+        let suggested_index = suggestion
+            .and_then(|(person_id, _)| people.iter().position(|p| p.person_id == person_id));
+        if let Some(index) = suggested_index {
+            let person = people.remove(index);
+            people.insert(0, person);
+        }
+
+        for person in people {
+            let avatar = adw::Avatar::builder().size(50).name(&person.name).build();
+
+            if let Some(thumbnail_path) = person.small_thumbnail_path {
+                let img = gdk::Texture::from_filename(&thumbnail_path).ok();
+                avatar.set_custom_image(img.as_ref());
+            }
+
+            let row = adw::ActionRow::builder()
+                .title(person.name)
+                .activatable(true)
+                .build();
+
+            let is_suggested = |(person_id, _): &(PersonId, f32)| *person_id == person.person_id;
+            if let Some((_, confidence)) = suggestion.filter(is_suggested) {
+                let confidence = (confidence * 100.0).round() as u32;
+                row.set_subtitle(&fl!("people-person-suggested", confidence = confidence));
+            }
+
+            row.add_prefix(&avatar);
+
+            {
+                let sender = sender.clone();
+                row.connect_activate(move |_| {
+                    sender.input(PersonSelectInput::Associate(person.person_id));
+                });
+            }
+
+            self.people_list.append(&row);
+            self.all_people.push(person.person_id);
+        }
+    }
+
+    /// Associates the face, or the unknown face of each picture, with a person.
+    fn associate(&mut self, person_id: PersonId) {
+        if let Some(face_id) = self.face_id {
+            debug!("Associating face {} with person {}", face_id, person_id);
+            if let Err(e) = self.people_repo.mark_as_person(face_id, person_id) {
+                error!("Failed associating face with person: {:?}", e);
+            }
+        } else if !self.picture_ids.is_empty() {
+            debug!(
+                "Associating {} pictures with person {}",
+                self.picture_ids.len(),
+                person_id
+            );
+            match self
+                .people_repo
+                .assign_pictures_to_person(&self.picture_ids, person_id)
+            {
+                Ok(count) => debug!("Assigned {} pictures", count),
+                Err(e) => error!("Failed associating pictures with person: {:?}", e),
+            }
+        }
+        self.picture_ids.clear();
+        self.people_list.remove_all();
+        self.all_people.clear();
+    }
+}
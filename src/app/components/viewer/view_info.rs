@@ -5,13 +5,17 @@
 use super::face_thumbnails::{FaceThumbnails, FaceThumbnailsInput};
 /// Properties view for a photo.
 ///Inspired by how Loupe displays its property view.
+use fotema_core::PictureId;
 use fotema_core::VisualId;
 use fotema_core::people;
+use fotema_core::tag;
+use fotema_core::user_album::{self, UserAlbumId};
 use fotema_core::FlatpakPathBuf;
+use fotema_core::photo::model::DatePrecision;
 
 use gtk::prelude::OrientableExt;
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Local, Utc};
 use glycin::ImageDetails;
 use humansize::{DECIMAL, format_size};
 use relm4::adw::prelude::*;
@@ -21,7 +25,9 @@ use relm4::prelude::*;
 use std::fs;
 use std::sync::Arc;
 
+use crate::app::SettingsState;
 use crate::app::SharedState;
+use crate::app::components::albums::album_filter::AlbumFilter;
 use crate::fl;
 
 use tracing::{debug, warn};
@@ -39,23 +45,124 @@ pub enum ViewInfoInput {
 
     OpenFolder,
 
+    /// Show all items taken in the same place as the item being viewed.
+    ViewPlace,
+
+    /// Show all items taken in the same month, or year, as the item being viewed.
+    ViewTakenIn,
+
     /// Refresh faces
     RefreshFaces,
+
+    /// Tag the picture with the name typed in the tag entry.
+    AddTag,
+
+    /// Remove a tag from the picture.
+    RemoveTag(String),
+
+    /// Show all pictures with a tag.
+    ViewTag(String),
+
+    /// Put the picture in the user album named in the album entry.
+    AddToUserAlbum,
+
+    /// Take the picture out of a user album.
+    RemoveFromUserAlbum(UserAlbumId),
+
+    /// Choose the picture as the cover of a user album.
+    SetUserAlbumCover(UserAlbumId),
+
+    /// Show a user album.
+    ViewUserAlbum(UserAlbumId),
+
+    /// Library has been updated, which might have changed the user albums of the picture.
+    RefreshUserAlbums,
+
+    /// Show pictures that look like the picture being viewed.
+    FindSimilar,
+
+    /// Private switch has been toggled.
+    SetPrivate(bool),
+}
+
+#[derive(Debug)]
+pub enum ViewInfoOutput {
+    /// Show an album of items taken in a place.
+    ViewPlace(String),
+
+    /// Show an album of items taken in a year or month.
+    ViewTakenIn(AlbumFilter),
+
+    /// Show an album of pictures with a tag.
+    ViewTag(String),
+
+    /// Show an album of pictures that look like a picture.
+    FindSimilar(PictureId),
+
+    /// Tag a picture.
+    AddTag(PictureId, String),
+
+    /// Remove a tag from a picture.
+    RemoveTag(PictureId, String),
+
+    /// Put a picture in a user album. String is the album name as typed.
+    AddToUserAlbum(PictureId, String),
+
+    /// Take a picture out of a user album.
+    RemoveFromUserAlbum(PictureId, UserAlbumId),
+
+    /// Choose a picture as the cover of a user album.
+    SetUserAlbumCover(UserAlbumId, PictureId),
+
+    /// Show a user album.
+    ViewUserAlbum(UserAlbumId),
+
+    /// Mark a picture as private, or not.
+    SetPrivate(PictureId, bool),
 }
 
 pub struct ViewInfo {
     state: SharedState,
 
+    settings_state: SettingsState,
+
     path: Option<FlatpakPathBuf>,
 
     folder: adw::ActionRow,
     file_name: adw::ActionRow,
 
+    place: adw::ActionRow,
+    place_name: Option<String>,
+
+    // Only pictures can be tagged or made private.
+    picture_id: Option<PictureId>,
+    tags: Vec<String>,
+    private_row: adw::SwitchRow,
+
+    // Is the picture private? Distinguishes the user toggling the private switch
+    // from the switch being set for a newly shown picture.
+    is_private: bool,
+    tags_details: adw::PreferencesGroup,
+    tag_entry: adw::EntryRow,
+    tag_rows: Vec<adw::ActionRow>,
+
+    // User albums the picture is in.
+    user_albums: Vec<user_album::Entry>,
+    albums_details: adw::PreferencesGroup,
+    album_entry: adw::EntryRow,
+    album_rows: Vec<adw::ActionRow>,
+
+    input_sender: relm4::Sender<ViewInfoInput>,
+
     // FIXME what timestamps to show for live photos that have an image an a video?
     date_time_details: adw::PreferencesGroup,
+    taken_at: adw::ActionRow,
     created_at: adw::ActionRow,
     modified_at: adw::ActionRow,
 
+    // Year or month the item being viewed was taken in.
+    taken_in: Option<AlbumFilter>,
+
     image_details: adw::PreferencesGroup,
     image_size: adw::ActionRow,
     image_format: adw::ActionRow,
@@ -65,6 +172,14 @@ pub struct ViewInfo {
     exif_originally_created_at: adw::ActionRow,
     exif_originally_modified_at: adw::ActionRow,
 
+    camera_details: adw::PreferencesGroup,
+    camera_camera: adw::ActionRow,
+    camera_lens: adw::ActionRow,
+    camera_aperture: adw::ActionRow,
+    camera_exposure_time: adw::ActionRow,
+    camera_focal_length: adw::ActionRow,
+    camera_iso: adw::ActionRow,
+
     video_details: adw::PreferencesGroup,
     video_dimensions: adw::ActionRow,
     video_container_format: adw::ActionRow,
@@ -80,9 +195,9 @@ pub struct ViewInfo {
 
 #[relm4::component(pub)]
 impl SimpleComponent for ViewInfo {
-    type Init = (SharedState, people::Repository);
+    type Init = (SharedState, people::Repository, SettingsState);
     type Input = ViewInfoInput;
-    type Output = ();
+    type Output = ViewInfoOutput;
 
     view! {
         gtk::ScrolledWindow {
@@ -124,10 +239,93 @@ impl SimpleComponent for ViewInfo {
                             set_icon_name: Some("image-alt-symbolic"),
                         }
                     },
+
+                    #[local_ref]
+                    place -> adw::ActionRow {
+                        set_title: &fl!("infobar-place"),
+                        add_css_class: "property",
+                        set_subtitle_selectable: true,
+
+                        add_prefix = &gtk::Image {
+                            set_icon_name: Some("mark-location-symbolic"),
+                        },
+
+                        add_suffix = &gtk::Button {
+                            set_valign: gtk::Align::Center,
+                            set_icon_name: "right-symbolic",
+                            set_tooltip_text: Some(&fl!("infobar-place", "tooltip")),
+                            add_css_class: "flat",
+                            connect_clicked => ViewInfoInput::ViewPlace,
+                        }
+                    },
+                },
+
+                #[local_ref]
+                tags_details -> adw::PreferencesGroup {
+                    #[local_ref]
+                    private_row -> adw::SwitchRow {
+                        set_title: &fl!("infobar-private"),
+                        set_subtitle: &fl!("infobar-private", "subtitle"),
+
+                        add_prefix = &gtk::Image {
+                            set_icon_name: Some("changes-prevent-symbolic"),
+                        },
+
+                        connect_active_notify[sender] => move |switch| {
+                            sender.input(ViewInfoInput::SetPrivate(switch.is_active()));
+                        },
+                    },
+
+                    #[local_ref]
+                    tag_entry -> adw::EntryRow {
+                        set_title: &fl!("infobar-tags", "add"),
+                        set_show_apply_button: true,
+                        connect_apply => ViewInfoInput::AddTag,
+
+                        add_prefix = &gtk::Image {
+                            set_icon_name: Some("list-add-symbolic"),
+                        }
+                    },
+                },
+
+                #[local_ref]
+                albums_details -> adw::PreferencesGroup {
+                    #[local_ref]
+                    album_entry -> adw::EntryRow {
+                        set_title: &fl!("infobar-albums", "add"),
+                        set_show_apply_button: true,
+                        connect_apply => ViewInfoInput::AddToUserAlbum,
+
+                        add_prefix = &gtk::Image {
+                            set_icon_name: Some("folder-symbolic"),
+                        }
+                    },
                 },
 
                 #[local_ref]
                 date_time_details -> adw::PreferencesGroup {
+                    #[local_ref]
+                    taken_at -> adw::ActionRow {
+                        set_title: &fl!("infobar-taken-at"),
+                        add_css_class: "property",
+                        set_subtitle_selectable: true,
+
+                        add_prefix = &gtk::Image {
+                            set_icon_name: Some("today-symbolic"),
+                        },
+
+                        add_suffix = &gtk::Button {
+                            set_valign: gtk::Align::Center,
+                            set_icon_name: "right-symbolic",
+                            #[watch]
+                            set_tooltip_text: Some(&model.taken_in_tooltip()),
+                            #[watch]
+                            set_visible: model.taken_in.is_some(),
+                            add_css_class: "flat",
+                            connect_clicked => ViewInfoInput::ViewTakenIn,
+                        }
+                    },
+
                     #[local_ref]
                     created_at -> adw::ActionRow {
                         set_title: &fl!("infobar-file-created"),
@@ -194,6 +392,21 @@ impl SimpleComponent for ViewInfo {
                         },
                         add_suffix = model.face_thumbnails.widget(),
                     },
+
+                    adw::ActionRow {
+                        set_title: &fl!("infobar-similar"),
+                        set_tooltip_text: Some(&fl!("infobar-similar", "tooltip")),
+                        set_activatable: true,
+                        connect_activated => ViewInfoInput::FindSimilar,
+
+                        add_prefix = &gtk::Image {
+                            set_icon_name: Some("image-alt-symbolic"),
+                        },
+
+                        add_suffix = &gtk::Image {
+                            set_icon_name: Some("right-symbolic"),
+                        },
+                    },
                 },
 
                 #[local_ref]
@@ -219,6 +432,69 @@ impl SimpleComponent for ViewInfo {
                     },
                 },
 
+                #[local_ref]
+                camera_details -> adw::PreferencesGroup {
+                    #[local_ref]
+                    camera_camera -> adw::ActionRow {
+                        set_title: &fl!("infobar-camera"),
+                        add_css_class: "property",
+                        set_subtitle_selectable: true,
+                        add_prefix = &gtk::Image {
+                            set_icon_name: Some("camera-photo-symbolic"),
+                        }
+                    },
+
+                    #[local_ref]
+                    camera_lens -> adw::ActionRow {
+                        set_title: &fl!("infobar-lens"),
+                        add_css_class: "property",
+                        set_subtitle_selectable: true,
+                        add_prefix = &gtk::Image {
+                            set_icon_name: Some("camera-photo-symbolic"),
+                        }
+                    },
+
+                    #[local_ref]
+                    camera_aperture -> adw::ActionRow {
+                        set_title: &fl!("infobar-aperture"),
+                        add_css_class: "property",
+                        set_subtitle_selectable: true,
+                        add_prefix = &gtk::Image {
+                            set_icon_name: Some("camera-photo-symbolic"),
+                        }
+                    },
+
+                    #[local_ref]
+                    camera_exposure_time -> adw::ActionRow {
+                        set_title: &fl!("infobar-exposure-time"),
+                        add_css_class: "property",
+                        set_subtitle_selectable: true,
+                        add_prefix = &gtk::Image {
+                            set_icon_name: Some("stopwatch-symbolic"),
+                        }
+                    },
+
+                    #[local_ref]
+                    camera_focal_length -> adw::ActionRow {
+                        set_title: &fl!("infobar-focal-length"),
+                        add_css_class: "property",
+                        set_subtitle_selectable: true,
+                        add_prefix = &gtk::Image {
+                            set_icon_name: Some("ruler-corner-symbolic"),
+                        }
+                    },
+
+                    #[local_ref]
+                    camera_iso -> adw::ActionRow {
+                        set_title: &fl!("infobar-iso"),
+                        add_css_class: "property",
+                        set_subtitle_selectable: true,
+                        add_prefix = &gtk::Image {
+                            set_icon_name: Some("camera-photo-symbolic"),
+                        }
+                    },
+                },
+
 
                 #[local_ref]
                 video_details -> adw::PreferencesGroup {
@@ -297,14 +573,23 @@ impl SimpleComponent for ViewInfo {
     }
 
     fn init(
-        (state, people_repo): Self::Init,
+        (state, people_repo, settings_state): Self::Init,
         _root: Self::Root,
-        _sender: ComponentSender<Self>,
+        sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
         let folder = adw::ActionRow::new();
         let file_name = adw::ActionRow::new();
+        let place = adw::ActionRow::new();
+
+        let tags_details = adw::PreferencesGroup::new();
+        let private_row = adw::SwitchRow::new();
+        let tag_entry = adw::EntryRow::new();
+
+        let albums_details = adw::PreferencesGroup::new();
+        let album_entry = adw::EntryRow::new();
 
         let date_time_details = adw::PreferencesGroup::new();
+        let taken_at = adw::ActionRow::new();
         let created_at = adw::ActionRow::new();
         let modified_at = adw::ActionRow::new();
 
@@ -317,6 +602,14 @@ impl SimpleComponent for ViewInfo {
         let exif_originally_created_at = adw::ActionRow::new();
         let exif_originally_modified_at = adw::ActionRow::new();
 
+        let camera_details = adw::PreferencesGroup::new();
+        let camera_camera = adw::ActionRow::new();
+        let camera_lens = adw::ActionRow::new();
+        let camera_aperture = adw::ActionRow::new();
+        let camera_exposure_time = adw::ActionRow::new();
+        let camera_focal_length = adw::ActionRow::new();
+        let camera_iso = adw::ActionRow::new();
+
         let video_details = adw::PreferencesGroup::new();
         let video_duration = adw::ActionRow::new();
         let video_dimensions = adw::ActionRow::new();
@@ -327,18 +620,40 @@ impl SimpleComponent for ViewInfo {
         let video_originally_created_at = adw::ActionRow::new();
 
         let faces_row = adw::ActionRow::new();
-        let face_thumbnails = FaceThumbnails::builder().launch(people_repo).detach();
+        let face_thumbnails = FaceThumbnails::builder()
+            .launch((people_repo, settings_state.clone()))
+            .detach();
 
         let model = ViewInfo {
             state,
+            settings_state,
 
             folder: folder.clone(),
             file_name: file_name.clone(),
             path: None,
 
+            place: place.clone(),
+            place_name: None,
+
+            picture_id: None,
+            tags: Vec::new(),
+            private_row: private_row.clone(),
+            is_private: false,
+            tags_details: tags_details.clone(),
+            tag_entry: tag_entry.clone(),
+            tag_rows: Vec::new(),
+            user_albums: Vec::new(),
+            albums_details: albums_details.clone(),
+            album_entry: album_entry.clone(),
+            album_rows: Vec::new(),
+
+            input_sender: sender.input_sender().clone(),
+
             date_time_details: date_time_details.clone(),
+            taken_at: taken_at.clone(),
             created_at: created_at.clone(),
             modified_at: modified_at.clone(),
+            taken_in: None,
 
             image_details: image_details.clone(),
             image_size: image_size.clone(),
@@ -349,6 +664,14 @@ impl SimpleComponent for ViewInfo {
             exif_originally_created_at: exif_originally_created_at.clone(),
             exif_originally_modified_at: exif_originally_modified_at.clone(),
 
+            camera_details: camera_details.clone(),
+            camera_camera: camera_camera.clone(),
+            camera_lens: camera_lens.clone(),
+            camera_aperture: camera_aperture.clone(),
+            camera_exposure_time: camera_exposure_time.clone(),
+            camera_focal_length: camera_focal_length.clone(),
+            camera_iso: camera_iso.clone(),
+
             video_details: video_details.clone(),
             video_file_size: video_file_size.clone(),
             video_originally_created_at: video_originally_created_at.clone(),
@@ -364,10 +687,15 @@ impl SimpleComponent for ViewInfo {
 
         let widgets = view_output!();
 
+        // Pictures are added to new albums once the album has been made.
+        model
+            .state
+            .subscribe(sender.input_sender(), |_| ViewInfoInput::RefreshUserAlbums);
+
         ComponentParts { model, widgets }
     }
 
-    fn update(&mut self, msg: Self::Input, _sender: ComponentSender<Self>) {
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
         match msg {
             ViewInfoInput::OpenFolder => {
                 // FIXME using self.host_path works when run in GNOME Builder, but
@@ -386,6 +714,16 @@ impl SimpleComponent for ViewInfo {
                     |_| (),
                 );
             }
+            ViewInfoInput::ViewPlace => {
+                if let Some(place_name) = self.place_name.clone() {
+                    let _ = sender.output(ViewInfoOutput::ViewPlace(place_name));
+                }
+            }
+            ViewInfoInput::ViewTakenIn => {
+                if let Some(filter) = self.taken_in.clone() {
+                    let _ = sender.output(ViewInfoOutput::ViewTakenIn(filter));
+                }
+            }
             ViewInfoInput::FileOnly(ref visual_id) => {
                 let result = {
                     let data = self.state.read();
@@ -400,6 +738,7 @@ impl SimpleComponent for ViewInfo {
                 self.video_details.set_visible(false);
                 self.image_details.set_visible(false);
                 self.exif_details.set_visible(false);
+                self.camera_details.set_visible(false);
 
                 let _ = self.update_file_details(vis.clone());
             }
@@ -444,6 +783,7 @@ impl SimpleComponent for ViewInfo {
 
                 self.image_details.set_visible(false);
                 self.exif_details.set_visible(false);
+                self.camera_details.set_visible(false);
 
                 let _ = self.update_file_details(vis.clone());
 
@@ -454,6 +794,102 @@ impl SimpleComponent for ViewInfo {
             ViewInfoInput::RefreshFaces => {
                 self.face_thumbnails.emit(FaceThumbnailsInput::Refresh);
             }
+            ViewInfoInput::AddTag => {
+                let Some(picture_id) = self.picture_id else {
+                    return;
+                };
+                let Some((name, folded_name)) = tag::normalize(&self.tag_entry.text()) else {
+                    return;
+                };
+
+                self.tag_entry.set_text("");
+
+                // Show the tag straight away, rather than waiting for the library to update.
+                if !self.tags.iter().any(|tag| tag::fold(tag) == folded_name) {
+                    self.tags.push(name.clone());
+                    self.tags.sort_by_key(|tag| tag::fold(tag));
+                    self.update_tag_rows();
+                }
+
+                let _ = sender.output(ViewInfoOutput::AddTag(picture_id, name));
+            }
+            ViewInfoInput::RemoveTag(name) => {
+                let Some(picture_id) = self.picture_id else {
+                    return;
+                };
+
+                self.tags.retain(|tag| *tag != name);
+                self.update_tag_rows();
+
+                let _ = sender.output(ViewInfoOutput::RemoveTag(picture_id, name));
+            }
+            ViewInfoInput::ViewTag(name) => {
+                let _ = sender.output(ViewInfoOutput::ViewTag(name));
+            }
+            ViewInfoInput::AddToUserAlbum => {
+                let Some(picture_id) = self.picture_id else {
+                    return;
+                };
+                let Some((name, _)) = tag::normalize(&self.album_entry.text()) else {
+                    return;
+                };
+
+                // Album is shown once it has been made and the library has been updated.
+                self.album_entry.set_text("");
+                let _ = sender.output(ViewInfoOutput::AddToUserAlbum(picture_id, name));
+            }
+            ViewInfoInput::RemoveFromUserAlbum(album_id) => {
+                let Some(picture_id) = self.picture_id else {
+                    return;
+                };
+
+                self.user_albums.retain(|entry| entry.album_id != album_id);
+                self.update_album_rows();
+
+                let _ = sender.output(ViewInfoOutput::RemoveFromUserAlbum(picture_id, album_id));
+            }
+            ViewInfoInput::SetUserAlbumCover(album_id) => {
+                if let Some(picture_id) = self.picture_id {
+                    let _ = sender.output(ViewInfoOutput::SetUserAlbumCover(album_id, picture_id));
+                }
+            }
+            ViewInfoInput::ViewUserAlbum(album_id) => {
+                let _ = sender.output(ViewInfoOutput::ViewUserAlbum(album_id));
+            }
+            ViewInfoInput::RefreshUserAlbums => {
+                let Some(picture_id) = self.picture_id else {
+                    return;
+                };
+
+                let user_albums = self
+                    .state
+                    .read()
+                    .iter()
+                    .find(|visual| visual.picture_id == Some(picture_id))
+                    .map(|visual| visual.user_albums.clone())
+                    .unwrap_or_default();
+
+                if self.user_albums != user_albums {
+                    self.user_albums = user_albums;
+                    self.update_album_rows();
+                }
+            }
+            ViewInfoInput::FindSimilar => {
+                if let Some(picture_id) = self.picture_id {
+                    let _ = sender.output(ViewInfoOutput::FindSimilar(picture_id));
+                }
+            }
+            ViewInfoInput::SetPrivate(is_private) => {
+                let Some(picture_id) = self.picture_id else {
+                    return;
+                };
+                if self.is_private == is_private {
+                    return;
+                }
+
+                self.is_private = is_private;
+                let _ = sender.output(ViewInfoOutput::SetPrivate(picture_id, is_private));
+            }
         }
     }
 }
@@ -462,16 +898,155 @@ impl SimpleComponent for ViewInfo {
 const FALLBACK: &str = "–";
 
 impl ViewInfo {
+    /// A row for each tag of the picture, after the entry for adding tags.
+    fn update_tag_rows(&mut self) {
+        for row in self.tag_rows.drain(..) {
+            self.tags_details.remove(&row);
+        }
+
+        for name in &self.tags {
+            let row = adw::ActionRow::builder()
+                .title(name)
+                .activatable(true)
+                .tooltip_text(fl!("infobar-tags", "view-tooltip"))
+                .build();
+
+            let remove = gtk::Button::builder()
+                .valign(gtk::Align::Center)
+                .icon_name("window-close-symbolic")
+                .tooltip_text(fl!("infobar-tags", "remove-tooltip"))
+                .css_classes(["flat"])
+                .build();
+            row.add_suffix(&remove);
+
+            {
+                let sender = self.input_sender.clone();
+                let name = name.clone();
+                row.connect_activated(move |_| sender.emit(ViewInfoInput::ViewTag(name.clone())));
+            }
+
+            {
+                let sender = self.input_sender.clone();
+                let name = name.clone();
+                remove
+                    .connect_clicked(move |_| sender.emit(ViewInfoInput::RemoveTag(name.clone())));
+            }
+
+            self.tags_details.add(&row);
+            self.tag_rows.push(row);
+        }
+    }
+
+    /// A row for each user album the picture is in, after the entry for adding to albums.
+    fn update_album_rows(&mut self) {
+        for row in self.album_rows.drain(..) {
+            self.albums_details.remove(&row);
+        }
+
+        for entry in &self.user_albums {
+            let row = adw::ActionRow::builder()
+                .title(&entry.name)
+                .activatable(true)
+                .tooltip_text(fl!("infobar-albums", "view-tooltip"))
+                .build();
+
+            if entry.is_cover {
+                row.set_subtitle(&fl!("infobar-albums", "cover"));
+            } else {
+                let cover = gtk::Button::builder()
+                    .valign(gtk::Align::Center)
+                    .icon_name("image-alt-symbolic")
+                    .tooltip_text(fl!("infobar-albums", "cover-tooltip"))
+                    .css_classes(["flat"])
+                    .build();
+                row.add_suffix(&cover);
+
+                let sender = self.input_sender.clone();
+                let album_id = entry.album_id;
+                cover.connect_clicked(move |_| {
+                    sender.emit(ViewInfoInput::SetUserAlbumCover(album_id))
+                });
+            }
+
+            let remove = gtk::Button::builder()
+                .valign(gtk::Align::Center)
+                .icon_name("window-close-symbolic")
+                .tooltip_text(fl!("infobar-albums", "remove-tooltip"))
+                .css_classes(["flat"])
+                .build();
+            row.add_suffix(&remove);
+
+            {
+                let sender = self.input_sender.clone();
+                let album_id = entry.album_id;
+                row.connect_activated(move |_| sender.emit(ViewInfoInput::ViewUserAlbum(album_id)));
+            }
+
+            {
+                let sender = self.input_sender.clone();
+                let album_id = entry.album_id;
+                remove.connect_clicked(move |_| {
+                    sender.emit(ViewInfoInput::RemoveFromUserAlbum(album_id))
+                });
+            }
+
+            self.albums_details.add(&row);
+            self.album_rows.push(row);
+        }
+    }
+
     fn update_file_details(&mut self, vis: Arc<fotema_core::visual::Visual>) -> Result<(), String> {
 
         self.path = Some(vis.path().clone());
 
-        Self::update_row(&self.folder, vis.folder_name());
+        self.picture_id = vis.picture_id;
+        self.tags = vis.tags.clone();
+        self.tags_details.set_visible(vis.picture_id.is_some());
+        self.is_private = vis.is_private;
+        self.private_row.set_active(vis.is_private);
+        self.tag_entry.set_text("");
+        self.update_tag_rows();
+
+        self.user_albums = vis.user_albums.clone();
+        self.albums_details.set_visible(vis.picture_id.is_some());
+        self.album_entry.set_text("");
+        self.update_album_rows();
+
+        let library_roots = self.settings_state.read().library_roots();
+        let folder_name = if vis.is_in_library_root(&library_roots) {
+            Some(fl!("folder-library-root"))
+        } else {
+            vis.folder_name()
+        };
+        Self::update_row(&self.folder, folder_name);
         Self::update_row(
             &self.file_name,
             vis.host_path().file_name().map(|p| p.to_string_lossy()),
         );
 
+        self.place_name = vis.place_name.clone();
+        Self::update_row(&self.place, self.place_name.as_ref());
+
+        // Dates without a time are stored as midnight UTC, so aren't shown in local time.
+        let taken_at = match vis.taken_at_precision() {
+            DatePrecision::Exact => vis
+                .ordering_ts
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M:%S %:z")
+                .to_string(),
+            DatePrecision::Day => vis.ordering_ts.format("%Y-%m-%d").to_string(),
+            DatePrecision::Month => vis.ordering_ts.format("%Y-%m").to_string(),
+            DatePrecision::Year => vis.ordering_ts.format("%Y").to_string(),
+        };
+        let taken_at = if vis.is_taken_at_estimate() {
+            fl!("infobar-taken-at", "estimate", timestamp = taken_at)
+        } else if vis.is_taken_at_imprecise() {
+            fl!("infobar-taken-at", "imprecise", timestamp = taken_at)
+        } else {
+            taken_at
+        };
+        self.taken_in = Some(AlbumFilter::taken_in_same_period(&vis));
+
         // FIXME duplicated from Scanner
         let file = fs::File::open(vis.sandbox_path()).map_err(|e| e.to_string())?;
 
@@ -490,6 +1065,7 @@ impl ViewInfo {
             .ok();
 
         let has_date_time_details = [
+            Self::update_row(&self.taken_at, Some(taken_at)),
             Self::update_row(&self.created_at, fs_created_at),
             Self::update_row(&self.modified_at, fs_modified_at),
         ]
@@ -532,7 +1108,8 @@ impl ViewInfo {
         self.image_details.set_visible(has_image_details);
 
         if let Some(Ok(exif)) = image_details.metadata_exif().as_ref().map(|x| x.get_full()) {
-            let metadata = fotema_core::photo::metadata::from_raw(exif).ok();
+            let time_zone = self.settings_state.read().naive_time_zone;
+            let metadata = fotema_core::photo::metadata::from_raw(exif, time_zone).ok();
 
             let fs_created_at: Option<String> = metadata
                 .clone()
@@ -552,8 +1129,11 @@ impl ViewInfo {
             .any(|x| x);
 
             self.exif_details.set_visible(has_exif_details);
+
+            self.update_camera_details(metadata);
         } else {
             self.exif_details.set_visible(false);
+            self.camera_details.set_visible(false);
         }
 
         Ok(())
@@ -610,6 +1190,58 @@ impl ViewInfo {
         Ok(())
     }
 
+    /// Camera rows are shown together, with a placeholder for missing tags,
+    /// so the settings are easy to compare between pictures.
+    fn update_camera_details(&mut self, metadata: Option<fotema_core::photo::Metadata>) {
+        let lens = metadata.as_ref().and_then(|x| x.lens_model.clone());
+        let camera = metadata.map(|x| x.camera).unwrap_or_default();
+
+        let name = match (camera.make, camera.model) {
+            // Models often repeat the make, such as "Canon" and "Canon EOS 80D".
+            (Some(make), Some(model)) if model.starts_with(&make) => Some(model),
+            (Some(make), Some(model)) => Some(format!("{} {}", make, model)),
+            (make, model) => make.or(model),
+        };
+
+        let aperture = camera.f_number.map(|x| format!("ƒ/{:.1}", x));
+
+        let exposure_time = camera.exposure_time.map(|x| {
+            if x < 1.0 {
+                format!("1/{:.0} s", 1.0 / x)
+            } else {
+                format!("{:.1} s", x)
+            }
+        });
+
+        let focal_length = camera.focal_length.map(|x| format!("{:.0} mm", x));
+
+        let iso = camera.iso.map(|x| x.to_string());
+
+        let values = [
+            (&self.camera_camera, name),
+            (&self.camera_lens, lens),
+            (&self.camera_aperture, aperture),
+            (&self.camera_exposure_time, exposure_time),
+            (&self.camera_focal_length, focal_length),
+            (&self.camera_iso, iso),
+        ];
+
+        let has_camera_details = values.iter().any(|(_, value)| value.is_some());
+
+        for (row, value) in values {
+            row.set_subtitle(value.as_deref().unwrap_or(FALLBACK));
+        }
+
+        self.camera_details.set_visible(has_camera_details);
+    }
+
+    fn taken_in_tooltip(&self) -> String {
+        match self.taken_in {
+            Some(AlbumFilter::Year(_)) => fl!("infobar-taken-at", "year-tooltip"),
+            _ => fl!("infobar-taken-at", "month-tooltip"),
+        }
+    }
+
     /// Borrowed from Loupe.
     /// Updates a row to be visible if it has a value to display, and returns
     /// visibility status.
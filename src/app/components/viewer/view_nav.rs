@@ -11,19 +11,23 @@ use relm4::gtk::prelude::*;
 use relm4::prelude::*;
 use relm4::*;
 
-use super::view_info::{ViewInfo, ViewInfoInput};
+use super::view_info::{ViewInfo, ViewInfoInput, ViewInfoOutput};
 use super::view_one::{ViewOne, ViewOneInput, ViewOneOutput};
 use crate::app::components::albums::album_filter::AlbumFilter;
-use crate::app::components::albums::album_sort::AlbumSort;
+use crate::app::components::albums::album_sort::SortKey;
 
 use crate::adaptive;
+use crate::app::Privacy;
+use crate::app::SettingsState;
 use crate::app::SharedState;
 use crate::app::components::progress_monitor::ProgressMonitor;
 use crate::fl;
 
+use fotema_core::PictureId;
 use fotema_core::Visual;
 use fotema_core::VisualId;
 use fotema_core::people;
+use fotema_core::user_album::UserAlbumId;
 use std::sync::Arc;
 
 use tracing::{debug, error, info};
@@ -46,6 +50,15 @@ relm4::new_stateless_action!(
     "ignore_unknown_faces"
 );
 
+// Outline detected faces over photos, for checking face detection.
+relm4::new_stateful_action!(
+    ShowFaceBoundsAction,
+    ViewNavActionGroup,
+    "show_face_bounds",
+    (),
+    bool
+);
+
 #[derive(Debug)]
 pub enum ViewNavInput {
     /// View an item after applying an album filter.
@@ -90,13 +103,52 @@ pub enum ViewNavInput {
     /// Ignore all unknown faces for item
     IgnoreUnknownFaces,
 
+    /// Show or hide outlines of detected faces.
+    ShowFaceBounds(bool),
+
     // Sort
-    Sort(AlbumSort),
+    Sort(SortKey),
+
+    /// Private pictures have been locked or unlocked.
+    Privacy(Privacy),
 }
 
 #[derive(Debug)]
 pub enum ViewNavOutput {
     TranscodeAll,
+
+    /// Show an album of items taken in a place.
+    ViewPlace(String),
+
+    /// Show an album of items taken in a year or month.
+    ViewTakenIn(AlbumFilter),
+
+    /// Show an album of pictures with a tag.
+    ViewTag(String),
+
+    /// Show an album of pictures that look like a picture.
+    FindSimilar(PictureId),
+
+    /// Tag a picture.
+    AddTag(PictureId, String),
+
+    /// Remove a tag from a picture.
+    RemoveTag(PictureId, String),
+
+    /// Put a picture in a user album. String is the album name as typed.
+    AddToUserAlbum(PictureId, String),
+
+    /// Take a picture out of a user album.
+    RemoveFromUserAlbum(PictureId, UserAlbumId),
+
+    /// Choose a picture as the cover of a user album.
+    SetUserAlbumCover(UserAlbumId, PictureId),
+
+    /// Show a user album.
+    ViewUserAlbum(UserAlbumId),
+
+    /// Mark a picture as private, or not.
+    SetPrivate(PictureId, bool),
 }
 
 pub struct ViewNav {
@@ -126,10 +178,15 @@ pub struct ViewNav {
     /// Index into shared state for currently viewed item.
     album_index: Option<usize>,
 
-    // Album currently displayed item is a member of
-    album_filter: AlbumFilter,
+    // Album currently displayed item is a member of.
+    // None if album must be reloaded.
+    album_filter: Option<AlbumFilter>,
+
+    album_sort: SortKey,
 
-    album_sort: AlbumSort,
+    /// Private pictures can't be navigated to while locked, even if their
+    /// blurred thumbnails are shown in albums.
+    privacy: Privacy,
 
     // Visual items filtered by album filter.
     // This is to support the next and previous buttons.
@@ -160,6 +217,7 @@ impl SimpleAsyncComponent for ViewNav {
         Arc<Reducer<ProgressMonitor>>,
         Arc<adaptive::LayoutState>,
         people::Repository,
+        SettingsState,
     );
     type Input = ViewNavInput;
     type Output = ViewNavOutput;
@@ -169,6 +227,9 @@ impl SimpleAsyncComponent for ViewNav {
             section! {
                 &fl!("viewer-faces-menu", "restore-ignored") => RestoreIgnoredFacesAction,
                 &fl!("viewer-faces-menu", "ignore-unknown") => IgnoreUnknownFacesAction,
+            },
+            section! {
+                &fl!("viewer-faces-menu", "show-bounds") => ShowFaceBoundsAction,
             }
         }
     }
@@ -219,7 +280,7 @@ impl SimpleAsyncComponent for ViewNav {
     }
 
     async fn init(
-        (state, transcode_progress_monitor, layout_state, people_repo): Self::Init,
+        (state, transcode_progress_monitor, layout_state, people_repo, settings_state): Self::Init,
         root: Self::Root,
         sender: AsyncComponentSender<Self>,
     ) -> AsyncComponentParts<Self> {
@@ -332,7 +393,7 @@ impl SimpleAsyncComponent for ViewNav {
 
         carousel_pages.push(
             ViewOne::builder()
-                .launch(transcode_progress_monitor.clone())
+                .launch((transcode_progress_monitor.clone(), people_repo.clone()))
                 .forward(sender.input_sender(), |msg| match msg {
                     ViewOneOutput::TranscodeAll => ViewNavInput::TranscodeAll,
                     ViewOneOutput::PhotoShown(id, info) => ViewNavInput::ShowPhotoInfo(id, info),
@@ -344,7 +405,7 @@ impl SimpleAsyncComponent for ViewNav {
 
         carousel_pages.push(
             ViewOne::builder()
-                .launch(transcode_progress_monitor.clone())
+                .launch((transcode_progress_monitor.clone(), people_repo.clone()))
                 .forward(sender.input_sender(), |msg| match msg {
                     ViewOneOutput::TranscodeAll => ViewNavInput::TranscodeAll,
                     ViewOneOutput::PhotoShown(id, info) => ViewNavInput::ShowPhotoInfo(id, info),
@@ -356,7 +417,7 @@ impl SimpleAsyncComponent for ViewNav {
 
         carousel_pages.push(
             ViewOne::builder()
-                .launch(transcode_progress_monitor.clone())
+                .launch((transcode_progress_monitor.clone(), people_repo.clone()))
                 .forward(sender.input_sender(), |msg| match msg {
                     ViewOneOutput::TranscodeAll => ViewNavInput::TranscodeAll,
                     ViewOneOutput::PhotoShown(id, info) => ViewNavInput::ShowPhotoInfo(id, info),
@@ -367,8 +428,30 @@ impl SimpleAsyncComponent for ViewNav {
         );
 
         let view_info = ViewInfo::builder()
-            .launch((state.clone(), people_repo.clone()))
-            .detach();
+            .launch((state.clone(), people_repo.clone(), settings_state))
+            .forward(sender.output_sender(), |msg| match msg {
+                ViewInfoOutput::ViewPlace(place_name) => ViewNavOutput::ViewPlace(place_name),
+                ViewInfoOutput::ViewTakenIn(filter) => ViewNavOutput::ViewTakenIn(filter),
+                ViewInfoOutput::ViewTag(name) => ViewNavOutput::ViewTag(name),
+                ViewInfoOutput::FindSimilar(picture_id) => ViewNavOutput::FindSimilar(picture_id),
+                ViewInfoOutput::AddTag(picture_id, name) => ViewNavOutput::AddTag(picture_id, name),
+                ViewInfoOutput::RemoveTag(picture_id, name) => {
+                    ViewNavOutput::RemoveTag(picture_id, name)
+                }
+                ViewInfoOutput::AddToUserAlbum(picture_id, name) => {
+                    ViewNavOutput::AddToUserAlbum(picture_id, name)
+                }
+                ViewInfoOutput::RemoveFromUserAlbum(picture_id, album_id) => {
+                    ViewNavOutput::RemoveFromUserAlbum(picture_id, album_id)
+                }
+                ViewInfoOutput::SetUserAlbumCover(album_id, picture_id) => {
+                    ViewNavOutput::SetUserAlbumCover(album_id, picture_id)
+                }
+                ViewInfoOutput::ViewUserAlbum(album_id) => ViewNavOutput::ViewUserAlbum(album_id),
+                ViewInfoOutput::SetPrivate(picture_id, is_private) => {
+                    ViewNavOutput::SetPrivate(picture_id, is_private)
+                }
+            });
 
         layout_state.subscribe(sender.input_sender(), |layout| ViewNavInput::Adapt(*layout));
 
@@ -380,8 +463,9 @@ impl SimpleAsyncComponent for ViewNav {
             carousel_last_page_index: 0,
             view_info,
             album_index: None,
-            album_filter: AlbumFilter::None,
-            album_sort: AlbumSort::default(),
+            album_filter: None,
+            album_sort: SortKey::default(),
+            privacy: Privacy::default(),
             album: Vec::new(),
             is_narrow: false,
             show_infobar,
@@ -405,9 +489,18 @@ impl SimpleAsyncComponent for ViewNav {
             })
         };
 
+        let show_face_bounds_action = {
+            let sender = sender.clone();
+            RelmAction::<ShowFaceBoundsAction>::new_stateful(&false, move |_, is_shown| {
+                *is_shown = !*is_shown;
+                sender.input(ViewNavInput::ShowFaceBounds(*is_shown));
+            })
+        };
+
         let mut actions = RelmActionGroup::<ViewNavActionGroup>::new();
         actions.add_action(restore_action);
         actions.add_action(ignore_unknown_faces_action);
+        actions.add_action(show_face_bounds_action);
         actions.register_for_widget(&root);
 
         let keys = gtk::EventControllerKey::new();
@@ -448,11 +541,13 @@ impl SimpleAsyncComponent for ViewNav {
                 // To support next/previous navigation we must have a view of the visual
                 // items filtered with the same album filter as the album the user is currently
                 // looking at.
-                if self.album_filter != album_filter {
-                    self.album_filter = album_filter.clone();
+                if self.album_filter.as_ref() != Some(&album_filter) {
+                    self.album_filter = Some(album_filter.clone());
                     let items = self.state.read();
+                    let is_locked = self.privacy == Privacy::Locked;
                     self.album = items
                         .iter()
+                        .filter(|v| !(is_locked && v.is_private))
                         .filter(|v| album_filter.clone().filter(v))
                         .cloned()
                         .collect();
@@ -750,9 +845,19 @@ impl SimpleAsyncComponent for ViewNav {
 
                 self.view_info.emit(ViewInfoInput::RefreshFaces);
             }
+            ViewNavInput::ShowFaceBounds(is_shown) => {
+                self.carousel_pages
+                    .iter()
+                    .for_each(|page| page.emit(ViewOneInput::ShowFaceBounds(is_shown)));
+            }
             ViewNavInput::Sort(album_sort) => {
                 self.album_sort = album_sort;
-                self.album_filter = AlbumFilter::None;
+                self.album_filter = None;
+                self.album.clear();
+            }
+            ViewNavInput::Privacy(privacy) => {
+                self.privacy = privacy;
+                self.album_filter = None;
                 self.album.clear();
             }
             ViewNavInput::SheetHeight(height) => {
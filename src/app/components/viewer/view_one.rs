@@ -5,8 +5,12 @@
 use chrono::TimeDelta;
 use fotema_core::Visual;
 use fotema_core::VisualId;
+use fotema_core::PictureId;
+use fotema_core::people;
+use fotema_core::people::model::Rect;
+use fotema_core::photo::model::DisplayTransform;
 use fotema_core::visual::model::PictureOrientation;
-use fotema_core::FlatpakPathBuf;
+use fotema_core::{Availability, FlatpakPathBuf};
 
 use glycin;
 use relm4::adw::gdk;
@@ -21,9 +25,11 @@ use crate::app::components::progress_monitor::ProgressMonitor;
 use crate::app::components::progress_panel::ProgressPanel;
 use crate::fl;
 
+use std::cell::RefCell;
+use std::rc::Rc;
 use std::sync::Arc;
 
-use tracing::{Level, debug, event, info};
+use tracing::{Level, debug, error, event, info};
 
 const TEN_SECS_IN_MICROS: i64 = 10_000_000;
 const FIFTEEN_SECS_IN_MICROS: i64 = 15_000_000;
@@ -58,6 +64,9 @@ pub enum Broken {
     /// Visual item no longer on file system.
     MissingInFileSystem(FlatpakPathBuf),
 
+    /// Couldn't reach the visual item, such as on a disconnected network share.
+    Unavailable(FlatpakPathBuf),
+
     /// Glycin couldn't load the file.
     Failed,
 
@@ -95,6 +104,20 @@ pub enum ViewOneInput {
 
     // Video has been "prepared", so duration should be available
     VideoPrepared,
+
+    /// Show or hide outlines of detected faces over a photo.
+    ShowFaceBounds(bool),
+}
+
+/// Detected faces to outline over a photo, for checking face detection.
+#[derive(Debug, Default)]
+struct FaceOverlay {
+    /// Size of the photo as stored, before it is turned the right way up.
+    image_width: f64,
+    image_height: f64,
+
+    /// Face bounds as fractions of the photo as stored.
+    bounds: Vec<Rect>,
 }
 
 #[derive(Debug)]
@@ -138,11 +161,23 @@ pub struct ViewOne {
     video_timestamp: String,
 
     transcode_progress: Controller<ProgressPanel>,
+
+    people_repo: people::Repository,
+
+    /// Photo whose faces can be outlined, with its size as stored and how it is
+    /// turned to display it.
+    face_source: Option<(PictureId, u32, u32, DisplayTransform)>,
+
+    show_face_bounds: bool,
+
+    face_overlay: Rc<RefCell<FaceOverlay>>,
+
+    face_overlay_area: gtk::DrawingArea,
 }
 
 #[relm4::component(pub async)]
 impl SimpleAsyncComponent for ViewOne {
-    type Init = Arc<Reducer<ProgressMonitor>>;
+    type Init = (Arc<Reducer<ProgressMonitor>>, people::Repository);
     type Input = ViewOneInput;
     type Output = ViewOneOutput;
 
@@ -264,8 +299,20 @@ impl SimpleAsyncComponent for ViewOne {
                     #[watch]
                     set_visible: model.viewing == Viewing::Photo || model.viewing == Viewing::MotionPhoto || model.viewing == Viewing::Video,
 
-                    #[local_ref]
-                    picture -> gtk::Picture {}
+                    gtk::Overlay {
+                        #[wrap(Some)]
+                        #[local_ref]
+                        set_child = &picture -> gtk::Picture {},
+
+                        // Same size as the picture, and turned the same way.
+                        #[local_ref]
+                        add_overlay = &face_overlay_area -> gtk::DrawingArea {
+                            set_can_target: false,
+
+                            #[watch]
+                            set_visible: model.show_face_bounds && model.viewing == Viewing::Photo,
+                        },
+                    },
                 },
 
                 adw::StatusPage {
@@ -318,12 +365,46 @@ impl SimpleAsyncComponent for ViewOne {
     }
 
     async fn init(
-        transcode_progress_monitor: Self::Init,
+        (transcode_progress_monitor, people_repo): Self::Init,
         root: Self::Root,
         _sender: AsyncComponentSender<Self>,
     ) -> AsyncComponentParts<Self> {
         let picture = gtk::Picture::new();
 
+        let face_overlay = Rc::new(RefCell::new(FaceOverlay::default()));
+        let face_overlay_area = gtk::DrawingArea::new();
+        {
+            let face_overlay = face_overlay.clone();
+            face_overlay_area.set_draw_func(move |_, cr, width, height| {
+                let overlay = face_overlay.borrow();
+                if overlay.image_width <= 0.0 || overlay.image_height <= 0.0 {
+                    return;
+                }
+
+                // The picture is scaled to fit, keeping its aspect ratio, and centred.
+                let scale = f64::min(
+                    width as f64 / overlay.image_width,
+                    height as f64 / overlay.image_height,
+                );
+                let image_width = overlay.image_width * scale;
+                let image_height = overlay.image_height * scale;
+                let offset_x = (width as f64 - image_width) / 2.0;
+                let offset_y = (height as f64 - image_height) / 2.0;
+
+                cr.set_source_rgba(1.0, 0.8, 0.0, 0.9);
+                cr.set_line_width(3.0);
+                for bounds in &overlay.bounds {
+                    cr.rectangle(
+                        offset_x + bounds.x as f64 * image_width,
+                        offset_y + bounds.y as f64 * image_height,
+                        bounds.width as f64 * image_width,
+                        bounds.height as f64 * image_height,
+                    );
+                }
+                let _ = cr.stroke();
+            });
+        }
+
         let transcode_progress = ProgressPanel::builder()
             .launch(transcode_progress_monitor.clone())
             .detach();
@@ -341,6 +422,11 @@ impl SimpleAsyncComponent for ViewOne {
             is_skipping_allowed: false,
             video_timestamp: "".into(),
             transcode_progress,
+            people_repo,
+            face_source: None,
+            show_face_bounds: false,
+            face_overlay,
+            face_overlay_area: face_overlay_area.clone(),
         };
 
         let widgets = view_output!();
@@ -361,11 +447,27 @@ impl SimpleAsyncComponent for ViewOne {
                 self.broken = Broken::None;
                 self.is_skipping_allowed = false;
                 self.visual_id = None;
-
-                if !visual_sandbox_path.exists() {
-                    self.viewing = Viewing::Error;
-                    self.broken = Broken::MissingInFileSystem(visual.path().clone());
-                    return;
+                self.face_source = None;
+                self.update_face_bounds();
+
+                // Checking a file on a slow network share can block for seconds.
+                let path = visual.path().clone();
+                let availability = gio::spawn_blocking(move || path.availability())
+                    .await
+                    .unwrap_or(Availability::Unavailable);
+
+                match availability {
+                    Availability::Available => {}
+                    Availability::Missing => {
+                        self.viewing = Viewing::Error;
+                        self.broken = Broken::MissingInFileSystem(visual.path().clone());
+                        return;
+                    }
+                    Availability::Unavailable => {
+                        self.viewing = Viewing::Error;
+                        self.broken = Broken::Unavailable(visual.path().clone());
+                        return;
+                    }
                 }
 
                 self.picture.set_paintable(None::<&gdk::Paintable>);
@@ -377,6 +479,7 @@ impl SimpleAsyncComponent for ViewOne {
                 // clear orientation transformation css classes
                 for orient in PictureOrientation::iter() {
                     self.picture.remove_css_class(orient.as_ref());
+                    self.face_overlay_area.remove_css_class(orient.as_ref());
                 }
 
                 if visual.is_photo_only() {
@@ -389,6 +492,7 @@ impl SimpleAsyncComponent for ViewOne {
                         .picture_orientation
                         .unwrap_or(PictureOrientation::North);
                     self.picture.add_css_class(orientation.as_ref());
+                    self.face_overlay_area.add_css_class(orientation.as_ref());
 
                     let file = gio::File::for_path(visual_sandbox_path);
 
@@ -416,6 +520,16 @@ impl SimpleAsyncComponent for ViewOne {
 
                     let texture = frame.texture();
                     self.picture.set_paintable(Some(&texture));
+
+                    self.face_source = visual.picture_id.map(|picture_id| {
+                        (
+                            picture_id,
+                            texture.width() as u32,
+                            texture.height() as u32,
+                            orientation.display_transform(),
+                        )
+                    });
+                    self.update_face_bounds();
                 } else {
                     // video or motion photo
                     let is_transcoded = visual
@@ -632,11 +746,51 @@ impl SimpleAsyncComponent for ViewOne {
                 event!(Level::INFO, "Transcode all");
                 let _ = sender.output(ViewOneOutput::TranscodeAll);
             }
+            ViewOneInput::ShowFaceBounds(show_face_bounds) => {
+                self.show_face_bounds = show_face_bounds;
+                self.update_face_bounds();
+            }
         }
     }
 }
 
 impl ViewOne {
+    /// Loads the bounds of the faces detected in the photo being viewed, if they
+    /// are to be outlined.
+    fn update_face_bounds(&mut self) {
+        let mut overlay = FaceOverlay::default();
+
+        if let (true, Some((picture_id, width, height, transform))) =
+            (self.show_face_bounds, self.face_source)
+        {
+            // Face bounds are relative to the photo the right way up.
+            let (oriented_width, oriented_height) = if transform.rotation % 180 == 0 {
+                (width, height)
+            } else {
+                (height, width)
+            };
+
+            match self.people_repo.faces_in_picture(picture_id) {
+                Ok(faces) => {
+                    overlay.bounds = faces
+                        .iter()
+                        .map(|face| {
+                            face.normalized_bounds(oriented_width, oriented_height)
+                                .untransform(&transform)
+                        })
+                        .collect();
+                }
+                Err(e) => error!("Failed loading faces for picture {}: {:?}", picture_id, e),
+            }
+
+            overlay.image_width = width as f64;
+            overlay.image_height = height as f64;
+        }
+
+        *self.face_overlay.borrow_mut() = overlay;
+        self.face_overlay_area.queue_draw();
+    }
+
     fn play_button_icon_name(&self) -> &str {
         match self.playback {
             Playback::Playing => "pause-symbolic",
@@ -657,6 +811,7 @@ impl ViewOne {
     fn broken_status_icon_name(&self) -> Option<&str> {
         match self.broken {
             Broken::MissingInFileSystem(_) => Some("item-missing-symbolic"),
+            Broken::Unavailable(_) => Some("network-offline-symbolic"),
             Broken::Failed => Some("sad-computer-symbolic"),
             Broken::None => None,
         }
@@ -668,6 +823,10 @@ impl ViewOne {
                 "viewer-error-missing-file",
                 file_name = visual_path.host_path.to_string_lossy()
             )),
+            Broken::Unavailable(ref visual_path) => Some(fl!(
+                "viewer-error-unavailable-file",
+                file_name = visual_path.host_path.to_string_lossy()
+            )),
             Broken::Failed => Some(fl!("viewer-error-failed-to-load")),
             Broken::None => None::<String>,
         }
@@ -0,0 +1,88 @@
+// SPDX-FileCopyrightText: © 2025 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Library maintenance without a window, for `fotema --rescan` when Fotema
+//! isn't already running.
+
+use anyhow::*;
+use relm4::gtk::glib;
+use std::sync::{Arc, Mutex};
+use tracing::{error, info, warn};
+
+use fotema_core::Scanner;
+use fotema_core::database;
+use fotema_core::thumbnailify::Thumbnailer;
+use fotema_core::photo::Repository as PhotoRepository;
+use fotema_core::video::Repository as VideoRepository;
+use fotema_core::visual::Repository as VisualRepository;
+
+use super::App;
+use super::background::library_scan_task;
+use crate::config::APP_ID;
+
+/// Scans the library for files added, changed, or deleted since the last scan, then
+/// returns. Thumbnails for new pictures are generated the next time Fotema is opened.
+pub fn rescan() -> glib::ExitCode {
+    match glib::MainContext::default().block_on(rescan_library()) {
+        std::result::Result::Ok(changed) => {
+            info!("Rescan complete. {} pictures missing or trashed.", changed);
+            glib::ExitCode::SUCCESS
+        }
+        Err(e) => {
+            error!("Failed rescanning library: {:?}", e);
+            glib::ExitCode::FAILURE
+        }
+    }
+}
+
+async fn rescan_library() -> Result<usize> {
+    let settings = App::load_settings().await?;
+    if !settings.is_onboarding_complete || !settings.library_base_dir.exists() {
+        bail!("No pictures directory has been chosen yet. Open Fotema to choose one.");
+    }
+
+    let library_roots = settings.library_roots();
+
+    let data_dir = glib::user_data_dir().join(APP_ID);
+    let _ = std::fs::create_dir_all(&data_dir);
+
+    let cache_dir = glib::user_cache_dir().join(APP_ID);
+    let _ = std::fs::create_dir_all(&cache_dir);
+
+    let con = database::setup(&data_dir.join("pictures.sqlite"))?;
+    let con = Arc::new(Mutex::new(con));
+
+    let mut photo_repo = PhotoRepository::open(&library_roots, &cache_dir, &data_dir, con.clone())?;
+    let mut video_repo = VideoRepository::open(&library_roots, &cache_dir, &data_dir, con.clone())?;
+    let mut visual_repo = VisualRepository::open(&library_roots, &cache_dir, con)?;
+
+    let thumbnailer = Thumbnailer::build(&cache_dir.join("thumbnails"));
+    thumbnailer.set_location(settings.thumbnail_location);
+
+    // Same roots as a scan from the app: always the primary root, but only other
+    // roots that are present.
+    let mut scanners = vec![Scanner::build(&library_roots.primary().sandbox_path)?];
+    for root in library_roots.iter().skip(1) {
+        if root.exists() {
+            scanners.push(Scanner::build(&root.sandbox_path)?);
+        } else {
+            warn!("Not scanning missing library root {:?}", root);
+        }
+    }
+
+    for scanner in &mut scanners {
+        scanner.set_ignore_patterns(&settings.ignore_patterns);
+        scanner.set_follow_symlinks(settings.follow_symlinks);
+    }
+
+    library_scan_task::scan_library(
+        &scanners,
+        &mut photo_repo,
+        &mut video_repo,
+        &mut visual_repo,
+        &thumbnailer,
+        false,
+        || {},
+    )
+}
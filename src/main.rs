@@ -13,7 +13,7 @@ use app::App;
 
 use config::{APP_ID, GETTEXT_PACKAGE, LOCALEDIR, RESOURCES_FILE};
 use gettextrs::{LocaleCategory, gettext};
-use gtk::prelude::ApplicationExt;
+use gtk::prelude::{ApplicationExt, ApplicationExtManual};
 use gtk::{gio, glib};
 use relm4::{
     RelmApp,
@@ -21,10 +21,11 @@ use relm4::{
     gtk, main_application,
 };
 
+use std::ops::ControlFlow;
 use tracing_subscriber::filter::EnvFilter;
 use tracing_subscriber::filter::LevelFilter;
 
-relm4::new_action_group!(AppActionGroup, "app");
+relm4::new_action_group!(pub(crate) AppActionGroup, "app");
 relm4::new_stateless_action!(QuitAction, AppActionGroup, "quit");
 
 fn main() {
@@ -54,6 +55,7 @@ fn main() {
     // be easily identified. Hopefully Fotema users won't cause OSM too much trouble, but
     // I'd like to be compliant with the OSM policies just in case they need to get in touch.
     // OSM--Thank you for your service.
+    #[cfg(feature = "map")]
     shumate::functions::set_user_agent(Some("Fotema Photo Gallery for Linux (https://fotema.app)"));
 
     glib::set_application_name(&gettext("Fotema"));
@@ -80,7 +82,38 @@ fn main() {
 
     app.set_accelerators_for_action::<QuitAction>(&["<Control>q"]);
 
-    let app = RelmApp::from_app(app);
+    app.add_main_option(
+        "rescan",
+        glib::Char::from(0),
+        glib::OptionFlags::NONE,
+        glib::OptionArg::None,
+        &gettext("Scan the library for added, changed, or deleted files, then exit"),
+        None,
+    );
+
+    // `fotema --rescan` asks a running instance to rescan, and the running instance's
+    // progress banner reports on the scan. If Fotema isn't running, then scan and exit
+    // without opening a window.
+    app.connect_handle_local_options(|app, options| {
+        if !options.contains("rescan") {
+            return ControlFlow::Continue(());
+        }
+
+        if let Err(e) = app.register(gio::Cancellable::NONE) {
+            tracing::error!("Failed registering application: {}", e);
+            return ControlFlow::Break(glib::ExitCode::FAILURE);
+        }
+
+        if app.is_remote() {
+            tracing::info!("Asking running instance to rescan");
+            app.activate_action("rescan", None);
+            ControlFlow::Break(glib::ExitCode::SUCCESS)
+        } else {
+            ControlFlow::Break(app::headless::rescan())
+        }
+    });
+
+    let app = RelmApp::from_app(app).with_args(std::env::args().collect());
 
     let data = res
         .lookup_data(